@@ -60,6 +60,33 @@ fn bench_mul() -> (f64, u8) {
     ((tt[tt.len() >> 1] as f64) / 100.0, s.encode32()[0])
 }
 
+fn bench_mul_vartime_glv() -> (f64, u8) {
+    let z = core_cycles();
+    let mut seed = [0u8; 32];
+    seed[ 0.. 8].copy_from_slice(&z.to_le_bytes());
+    seed[ 8..16].copy_from_slice(&z.to_le_bytes());
+    seed[16..24].copy_from_slice(&z.to_le_bytes());
+    seed[24..32].copy_from_slice(&z.to_le_bytes());
+    let mut s = Scalar::decode_reduce(&seed);
+    let mut P = Point::mulgen(&s);
+    let mut tt = [0; 100];
+    for i in 0..tt.len() {
+        let begin = core_cycles();
+        for _ in 0..100 {
+            P = P.mul_vartime_glv(&s);
+            if P.isneutral() != 0 {
+                s += Scalar::ZERO;
+            } else {
+                s += Scalar::ONE;
+            }
+        }
+        let end = core_cycles();
+        tt[i] = end.wrapping_sub(begin);
+    }
+    tt.sort();
+    ((tt[tt.len() >> 1] as f64) / 100.0, s.encode32()[0])
+}
+
 fn bench_mul_add_mulgen() -> (f64, u8) {
     let z = core_cycles();
     let mut uu = [Scalar::ZERO; 128];
@@ -162,6 +189,9 @@ fn main() {
     let (v, x) = bench_mul();
     bx ^= x;
     println!("secp256k1 point mul:           {:13.2}", v);
+    let (v, x) = bench_mul_vartime_glv();
+    bx ^= x;
+    println!("secp256k1 point mul_vartime:   {:13.2}", v);
     let (v, x) = bench_mulgen();
     bx ^= x;
     println!("secp256k1 point mulgen:        {:13.2}", v);
@@ -309,6 +309,17 @@ pub use w32::GFsecp256k1;
 ))]
 pub use w32::GFp256;
 
+/// Finite field: integers modulo 2^384 - 2^128 - 2^96 + 2^32 - 1.
+///
+/// This is the base field used by curve P-384.
+#[cfg(all(
+    any(
+        feature = "w32_backend",
+        all(not(feature = "w64_backend"), target_pointer_width = "32")),
+    feature = "gfp384",
+))]
+pub use w32::GFp384;
+
 /// Finite field: integers modulo 2^448 - 2^224 - 1.
 ///
 /// This is a dedicated type for the base field used by curve Curve448.
@@ -320,6 +331,17 @@ pub use w32::GFp256;
 ))]
 pub use w32::GF448;
 
+/// Finite field: integers modulo 2^521 - 1.
+///
+/// This is the base field used by curve P-521.
+#[cfg(all(
+    any(
+        feature = "w32_backend",
+        all(not(feature = "w64_backend"), target_pointer_width = "32")),
+    feature = "gfp521",
+))]
+pub use w32::GFp521;
+
 /// Finite fields: GF(2^127) and GF(2^254)
 ///
 /// These are dedicated types used for the base field of curve GLS-254.
@@ -475,6 +497,17 @@ pub use w64::GFsecp256k1;
 ))]
 pub use w64::GFp256;
 
+/// Finite field: integers modulo 2^384 - 2^128 - 2^96 + 2^32 - 1.
+///
+/// This is the base field used by curve P-384.
+#[cfg(all(
+    any(
+        feature = "w64_backend",
+        all(not(feature = "w32_backend"), target_pointer_width = "64")),
+    feature = "gfp384",
+))]
+pub use w64::GFp384;
+
 /// Finite field: integers modulo 2^448 - 2^224 - 1.
 ///
 /// This is a dedicated type for the base field used by curve Curve448.
@@ -486,6 +519,17 @@ pub use w64::GFp256;
 ))]
 pub use w64::GF448;
 
+/// Finite field: integers modulo 2^521 - 1.
+///
+/// This is the base field used by curve P-521.
+#[cfg(all(
+    any(
+        feature = "w64_backend",
+        all(not(feature = "w32_backend"), target_pointer_width = "64")),
+    feature = "gfp521",
+))]
+pub use w64::GFp521;
+
 /// Finite fields: GF(2^127) and GF(2^254)
 ///
 /// These are dedicated types used for the base field of curve GLS-254.
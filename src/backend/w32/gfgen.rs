@@ -1299,9 +1299,7 @@ macro_rules! define_gfgen { ($typename:ident, $fieldparams:ident, $submod:ident,
         // function returns c0 and c1 as arrays of ceil(cl/8) bytes.
         // The constants `Self::SPLIT_BITLEN` and `Self::SPLIT_LEN` contain,
         // respectively,  the lengths in bits and in bytes of c0 and c1.
-        //
-        // WARNING: this function is not implemented for moduli larger than
-        // 2^512 (TODO).
+        // Moduli up to 576 bits are supported (this covers P-521).
         //
         // This function is NOT constant-time; it must be used only on
         // public scalar values (e.g. when verifying signatures).
@@ -1699,7 +1697,11 @@ macro_rules! define_gfgen { ($typename:ident, $fieldparams:ident, $submod:ident,
             addm_cond_inner(a, mm, 0, 0)
         }
 
-        // Get the modulus true size (in 32-bit words).
+        // Get the modulus true size (in 32-bit words). The modulus
+        // parameter uses 64-bit limbs, so its top 32-bit word may be zero
+        // (e.g. for P-521); we scan 32-bit words downwards, starting from
+        // the top, until a non-zero word is found. `modulus_size_inner(j)`
+        // examines word `j - 1`.
         const fn modulus_size() -> usize {
             const fn modulus_size_inner(j: usize) -> usize {
                 let j = j - 1;
@@ -1708,7 +1710,7 @@ macro_rules! define_gfgen { ($typename:ident, $fieldparams:ident, $submod:ident,
                 if w != 0 {
                     j + 1
                 } else {
-                    modulus_size_inner(j - 1)
+                    modulus_size_inner(j)
                 }
             }
             modulus_size_inner(2 * $fieldparams::MODULUS.len())
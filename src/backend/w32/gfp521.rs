@@ -0,0 +1,25 @@
+// TODO: make a dedicated GFp521 implementation (e.g. with 17 limbs of
+// 31 or 32 bits), leveraging the Mersenne modulus. For now, we use the
+// generic code.
+
+use super::gfgen::{define_gfgen, define_gfgen_tests};
+
+struct GFp521Params;
+impl GFp521Params {
+
+    // p = 2^521 - 1
+    const MODULUS: [u64; 9] = [
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0x00000000000001FF,
+    ];
+}
+
+define_gfgen!(GFp521, GFp521Params, gfp521mod, false);
+define_gfgen_tests!(GFp521, 3, test_gfp521mod);
@@ -50,11 +50,7 @@ macro_rules! define_bigint { ($typename:ident, $bitlen:expr) => {
 
         // Swap the contents of self with rhs.
         fn swap(&mut self, rhs: &mut Self) {
-            for i in 0..Self::N {
-                let t = self.0[i];
-                self.0[i] = rhs.0[i];
-                rhs.0[i] = t;
-            }
+            core::mem::swap(&mut self.0, &mut rhs.0);
         }
 
         // Get the length (in bits) of this value.
@@ -380,24 +376,29 @@ macro_rules! define_lagrange { ($name:ident, $n0:ident, $n1:ident, $n2:ident, $n
 define_bigint!(ZInt128, 128);
 define_bigint!(ZInt192, 192);
 define_bigint!(ZInt256, 256);
+define_bigint!(ZInt288, 288);
 define_bigint!(ZInt320, 320);
 define_bigint!(ZInt384, 384);
 define_bigint!(ZInt448, 448);
 define_bigint!(ZInt512, 512);
+define_bigint!(ZInt576, 576);
 define_bigint!(ZInt640, 640);
 define_bigint!(ZInt768, 768);
+define_bigint!(ZInt832, 832);
 define_bigint!(ZInt896, 896);
 define_bigint!(ZInt1024, 1024);
+define_bigint!(ZInt1152, 1152);
 
 define_lagrange!(lagrange256_vartime, ZInt128, ZInt256, ZInt384, ZInt512);
 define_lagrange!(lagrange320_vartime, ZInt192, ZInt320, ZInt448, ZInt640);
 define_lagrange!(lagrange384_vartime, ZInt192, ZInt384, ZInt512, ZInt768);
 define_lagrange!(lagrange448_vartime, ZInt256, ZInt448, ZInt640, ZInt896);
 define_lagrange!(lagrange512_vartime, ZInt256, ZInt512, ZInt768, ZInt1024);
+define_lagrange!(lagrange576_vartime, ZInt288, ZInt576, ZInt832, ZInt1152);
 
 //
 // Rules:
-//   k and n must have the same length, which is between 8 and 16 (inclusive)
+//   k and n must have the same length, which is between 8 and 18 (inclusive)
 //   k and n use unsigned little-endian notation
 //   k < n (numerically)
 //   c0 and c1 must have length at most ceil(n.len()/2)
@@ -410,13 +411,13 @@ define_lagrange!(lagrange512_vartime, ZInt256, ZInt512, ZInt768, ZInt1024);
 pub(crate) fn lagrange_vartime(k: &[u32], n: &[u32], max_bitlen: u32,
     c0: &mut [u32], c1: &mut [u32])
 {
-    if n.len() < 8 || n.len() > 16 {
+    if n.len() < 8 || n.len() > 18 {
         unimplemented!();
     }
     // Expand k and n into larger arrays so that we may have an even number
     // of limbs.
-    let mut nk = [0u32; 16];
-    let mut nn = [0u32; 16];
+    let mut nk = [0u32; 18];
+    let mut nn = [0u32; 18];
     nk[..k.len()].copy_from_slice(k);
     nn[..n.len()].copy_from_slice(n);
     let nlen = (n.len() + 1) & !1usize;
@@ -463,6 +464,14 @@ pub(crate) fn lagrange_vartime(k: &[u32], n: &[u32], max_bitlen: u32,
             c0.copy_from_slice(&v0[..c0.len()]);
             c1.copy_from_slice(&v1[..c1.len()]);
         }
+        18 => {
+            let (v0, v1) = lagrange576_vartime(
+                <&[u32; 18]>::try_from(k).unwrap(),
+                <&[u32; 18]>::try_from(n).unwrap(),
+                max_bitlen);
+            c0.copy_from_slice(&v0[..c0.len()]);
+            c1.copy_from_slice(&v1[..c1.len()]);
+        }
         _ => {
             unimplemented!();
         }
@@ -63,6 +63,18 @@ pub mod gf448;
 #[cfg(feature = "gf448")]
 pub use gf448::GF448;
 
+#[cfg(feature = "gfp384")]
+pub mod gfp384;
+
+#[cfg(feature = "gfp384")]
+pub use gfp384::GFp384;
+
+#[cfg(feature = "gfp521")]
+pub mod gfp521;
+
+#[cfg(feature = "gfp521")]
+pub use gfp521::GFp521;
+
 pub mod lagrange;
 
 #[cfg(feature = "gfgen")]
@@ -1262,9 +1262,7 @@ macro_rules! define_gfgen { ($typename:ident, $fieldparams:ident, $submod:ident,
         // function returns c0 and c1 as arrays of ceil(cl/8) bytes.
         // The constants `Self::SPLIT_BITLEN` and `Self::SPLIT_LEN` contain,
         // respectively,  the lengths in bits and in bytes of c0 and c1.
-        //
-        // WARNING: this function is not implemented for moduli larger than
-        // 2^512 (TODO).
+        // Moduli up to 576 bits are supported (this covers P-521).
         //
         // This function is NOT constant-time; it must be used only on
         // public scalar values (e.g. when verifying signatures).
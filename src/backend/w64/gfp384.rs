@@ -0,0 +1,21 @@
+// TODO: make a dedicated GFp384 implementation, leveraging the special
+// modulus format. For now, we use the generic code.
+
+use super::gfgen::{define_gfgen, define_gfgen_tests};
+
+struct GFp384Params;
+impl GFp384Params {
+
+    // p = 2^384 - 2^128 - 2^96 + 2^32 - 1
+    const MODULUS: [u64; 6] = [
+        0x00000000FFFFFFFF,
+        0xFFFFFFFF00000000,
+        0xFFFFFFFFFFFFFFFE,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+    ];
+}
+
+define_gfgen!(GFp384, GFp384Params, gfp384mod, false);
+define_gfgen_tests!(GFp384, 19, test_gfp384mod);
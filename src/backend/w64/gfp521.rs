@@ -0,0 +1,1294 @@
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use core::convert::TryFrom;
+
+use super::sgnw;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GFp521([u64; 9]);
+
+// 2^58 - 1
+const M58: u64 = 0x03FFFFFFFFFFFFFF;
+
+// 2^57 - 1
+const M57: u64 = 0x01FFFFFFFFFFFFFF;
+
+impl GFp521 {
+
+    // INTERNAL FORMAT
+    // ===============
+    //
+    // Value is split over nine unsigned limbs, in base 2^58. If the limbs
+    // are y0 to y8, then the value is:
+    //   y0 + y1*2^58 + y2*2^116 + ... + y8*2^464
+    // The value is implicitly considered modulo p = 2^521 - 1. In the
+    // "normal" format, limbs y0 to y7 are less than 2^59 - 1, and y8 is
+    // less than 2^58 - 1; all functions accept operands in normal format,
+    // and return values in normal format. Most functions in fact return
+    // values with y0..y7 <= 2^58 and y8 < 2^57 (see set_carry_propagate()).
+    //
+    // Since 2^522 = 2 mod p, multiplications compute the 81 products
+    // a_i*b_j (less than 2^118) and accumulate them into nine 128-bit
+    // words, with products such that i+j >= 9 being doubled and wrapping
+    // around; each accumulator is lower than 9*2^119 < 2^123. A carry
+    // propagation then yields the nine output limbs; the bits beyond
+    // 2^521 are folded back into the low limbs, since 2^521 = 1 mod p.
+
+    // Modulus p in base 2^64 (low-to-high order).
+    pub const MODULUS: [u64; 9] = [
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0x00000000000001FF,
+    ];
+
+    // Element encoding length: 66 bytes.
+    pub const ENC_LEN: usize = 66;
+
+    pub const ZERO: GFp521 = GFp521([ 0, 0, 0, 0, 0, 0, 0, 0, 0 ]);
+    pub const ONE: GFp521 = GFp521([ 1, 0, 0, 0, 0, 0, 0, 0, 0 ]);
+    pub const TWO: GFp521 = GFp521([ 2, 0, 0, 0, 0, 0, 0, 0, 0 ]);
+    pub const THREE: GFp521 = GFp521([ 3, 0, 0, 0, 0, 0, 0, 0, 0 ]);
+    pub const MINUS_ONE: GFp521 = GFp521([
+        M58 - 1, M58, M58, M58, M58, M58, M58, M58, M57 ]);
+
+    // Modulus p in base 2^58.
+    const P58: [u64; 9] = [ M58, M58, M58, M58, M58, M58, M58, M58, M57 ];
+
+    // 2*p in base 2^58 (non-normalized: each limb is twice the
+    // corresponding limb of p). This is used as a bias in subtractions.
+    const P58X2: [u64; 9] = [
+        2 * M58, 2 * M58, 2 * M58, 2 * M58,
+        2 * M58, 2 * M58, 2 * M58, 2 * M58, 2 * M57 ];
+
+    // Create an element from a 576-bit value (implicitly reduced modulo
+    // the field order) provided as nine 64-bit limbs (in low-to-high order).
+    pub const fn w64le(x: [u64; 9]) -> Self {
+        let mut d = [0u64; 9];
+        let mut i = 0;
+        while i < 9 {
+            let off = 58 * i;
+            let k = off >> 6;
+            let s = off & 63;
+            let mut v = x[k] >> s;
+            if s > 6 {
+                v |= x[k + 1] << (64 - s);
+            }
+            d[i] = v & M58;
+            i += 1;
+        }
+
+        // Limb 8 keeps only 57 bits; the bits beyond 2^521 wrap around
+        // (since 2^521 = 1 mod p). Limb 0 may then be up to 2^58 + 2^55,
+        // which is fine.
+        d[8] &= M57;
+        d[0] += x[8] >> 9;
+        Self(d)
+    }
+
+    // Create an element from a 576-bit value (implicitly reduced modulo
+    // the field order) provided as nine 64-bit limbs (in high-to-low order).
+    pub const fn w64be(x: [u64; 9]) -> Self {
+        Self::w64le([ x[8], x[7], x[6], x[5], x[4], x[3], x[2], x[1], x[0] ])
+    }
+
+    // Create an element from a 576-bit value (implicitly reduced modulo
+    // the field order) provided as nine 64-bit limbs (in low-to-high order).
+    #[inline(always)]
+    pub fn from_w64le(x: [u64; 9]) -> Self {
+        Self::w64le(x)
+    }
+
+    // Create an element from a 576-bit value (implicitly reduced modulo
+    // the field order) provided as nine 64-bit limbs (in high-to-low order).
+    #[inline(always)]
+    pub fn from_w64be(x: [u64; 9]) -> Self {
+        Self::w64be(x)
+    }
+
+    // Create an element by converting to provided integer (implicitly
+    // reduced modulo the field order).
+    #[inline(always)]
+    pub fn from_i32(x: i32) -> Self {
+        Self::from_i64(x as i64)
+    }
+
+    // Create an element by converting to provided integer (implicitly
+    // reduced modulo the field order).
+    #[inline(always)]
+    pub fn from_u32(x: u32) -> Self {
+        Self([ x as u64, 0, 0, 0, 0, 0, 0, 0, 0 ])
+    }
+
+    // Create an element by converting to provided integer (implicitly
+    // reduced modulo the field order).
+    #[inline(always)]
+    pub fn from_i64(x: i64) -> Self {
+        let s = sgnw(x as u64);
+        let mut r = Self::from_u64(((x as u64) ^ s).wrapping_sub(s));
+        r.set_cond(&-r, s as u32);
+        r
+    }
+
+    // Create an element by converting to provided integer (implicitly
+    // reduced modulo the field order).
+    #[inline(always)]
+    pub fn from_u64(x: u64) -> Self {
+        Self([ x & M58, x >> 58, 0, 0, 0, 0, 0, 0, 0 ])
+    }
+
+    // Create an element by converting to provided integer (implicitly
+    // reduced modulo the field order).
+    #[inline(always)]
+    pub fn from_i128(x: i128) -> Self {
+        let s = (x >> 127) as u128;
+        let mut r = Self::from_u128(((x as u128) ^ s).wrapping_sub(s));
+        r.set_cond(&-r, s as u32);
+        r
+    }
+
+    // Create an element by converting to provided integer (implicitly
+    // reduced modulo the field order).
+    #[inline(always)]
+    pub fn from_u128(x: u128) -> Self {
+        Self([
+            (x as u64) & M58,
+            ((x >> 58) as u64) & M58,
+            (x >> 116) as u64,
+            0, 0, 0, 0, 0, 0 ])
+    }
+
+    // Propagate carries. Input limbs may be up to 2^63 - 1; on output,
+    // limbs y0 and y2..y7 are lower than 2^58, y1 is at most 2^58, and
+    // y8 is lower than 2^57.
+    #[inline(always)]
+    fn set_carry_propagate(&mut self) {
+        for i in 0..8 {
+            self.0[i + 1] += self.0[i] >> 58;
+            self.0[i] &= M58;
+        }
+        self.0[0] += self.0[8] >> 57;
+        self.0[8] &= M57;
+        self.0[1] += self.0[0] >> 58;
+        self.0[0] &= M58;
+    }
+
+    #[inline]
+    fn set_add(&mut self, rhs: &Self) {
+        for i in 0..9 {
+            self.0[i] += rhs.0[i];
+        }
+        self.set_carry_propagate();
+    }
+
+    #[inline]
+    fn set_sub(&mut self, rhs: &Self) {
+        // We add 2*p so that all limbs remain nonnegative.
+        for i in 0..9 {
+            self.0[i] = (self.0[i] + Self::P58X2[i]) - rhs.0[i];
+        }
+        self.set_carry_propagate();
+    }
+
+    // Negate this value (in place).
+    #[inline]
+    pub fn set_neg(&mut self) {
+        for i in 0..9 {
+            self.0[i] = Self::P58X2[i] - self.0[i];
+        }
+        self.set_carry_propagate();
+    }
+
+    // Conditionally copy the provided value ('a') into self:
+    //  - If ctl == 0xFFFFFFFF, then the value of 'a' is copied into self.
+    //  - If ctl == 0, then the value of self is unchanged.
+    // ctl MUST be equal to 0 or 0xFFFFFFFF.
+    #[inline]
+    pub fn set_cond(&mut self, a: &Self, ctl: u32) {
+        let cw = ((ctl as i32) as i64) as u64;
+        for i in 0..9 {
+            self.0[i] ^= cw & (self.0[i] ^ a.0[i]);
+        }
+    }
+
+    // Return a value equal to either a0 (if ctl == 0) or a1 (if
+    // ctl == 0xFFFFFFFF). Value ctl MUST be either 0 or 0xFFFFFFFF.
+    #[inline(always)]
+    pub fn select(a0: &Self, a1: &Self, ctl: u32) -> Self {
+        let mut r = *a0;
+        r.set_cond(a1, ctl);
+        r
+    }
+
+    // Conditionally swap two elements: values a and b are exchanged if
+    // ctl == 0xFFFFFFFF, or not exchanged if ctl == 0x00000000. Value
+    // ctl MUST be either 0x00000000 or 0xFFFFFFFF.
+    #[inline]
+    pub fn cswap(a: &mut Self, b: &mut Self, ctl: u32) {
+        let cw = ((ctl as i32) as i64) as u64;
+        for i in 0..9 {
+            let t = cw & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+
+    // Divide this value by 2 (in place).
+    #[inline]
+    fn set_half(&mut self) {
+        // Only limb y0 contributes to the parity of the value. If the
+        // value is odd, we add p to make it even, then shift.
+        let m = (self.0[0] & 1).wrapping_neg();
+        for i in 0..9 {
+            self.0[i] += Self::P58[i] & m;
+        }
+        for i in 0..8 {
+            self.0[i] = (self.0[i] >> 1) + ((self.0[i + 1] & 1) << 57);
+        }
+        self.0[8] >>= 1;
+        self.set_carry_propagate();
+    }
+
+    #[inline(always)]
+    pub fn half(self) -> Self {
+        let mut r = self;
+        r.set_half();
+        r
+    }
+
+    // Multiply this value by 2^k (in place), for 1 <= k <= 3.
+    #[inline(always)]
+    fn set_mul_pow2(&mut self, k: u32) {
+        for i in 0..9 {
+            self.0[i] <<= k;
+        }
+        self.set_carry_propagate();
+    }
+
+    #[inline]
+    pub fn set_mul2(&mut self) {
+        self.set_mul_pow2(1);
+    }
+
+    #[inline(always)]
+    pub fn mul2(self) -> Self {
+        let mut r = self;
+        r.set_mul2();
+        r
+    }
+
+    #[inline]
+    pub fn set_mul3(&mut self) {
+        for i in 0..9 {
+            self.0[i] *= 3;
+        }
+        self.set_carry_propagate();
+    }
+
+    #[inline(always)]
+    pub fn mul3(self) -> Self {
+        let mut r = self;
+        r.set_mul3();
+        r
+    }
+
+    #[inline]
+    pub fn set_mul4(&mut self) {
+        self.set_mul_pow2(2);
+    }
+
+    #[inline(always)]
+    pub fn mul4(self) -> Self {
+        let mut r = self;
+        r.set_mul4();
+        r
+    }
+
+    #[inline]
+    pub fn set_mul8(&mut self) {
+        self.set_mul_pow2(3);
+    }
+
+    #[inline(always)]
+    pub fn mul8(self) -> Self {
+        let mut r = self;
+        r.set_mul8();
+        r
+    }
+
+    #[inline]
+    pub fn set_mul16(&mut self) {
+        self.set_mul_small(16);
+    }
+
+    #[inline(always)]
+    pub fn mul16(self) -> Self {
+        let mut r = self;
+        r.set_mul16();
+        r
+    }
+
+    #[inline]
+    pub fn set_mul32(&mut self) {
+        self.set_mul_small(32);
+    }
+
+    #[inline(always)]
+    pub fn mul32(self) -> Self {
+        let mut r = self;
+        r.set_mul32();
+        r
+    }
+
+    // Reduce nine 128-bit accumulators (each less than 2^126) into
+    // a value in normal format.
+    #[inline(always)]
+    fn reduce_wide(&mut self, mut c: [u128; 9]) {
+        for i in 0..8 {
+            c[i + 1] += c[i] >> 58;
+            self.0[i] = (c[i] as u64) & M58;
+        }
+        self.0[8] = (c[8] as u64) & M57;
+
+        // Bits beyond 2^521 wrap around; the excess h is less than
+        // 2^69, and we split it over limbs y0 and y1.
+        let h = c[8] >> 57;
+        self.0[0] += (h as u64) & M58;
+        self.0[1] += (h >> 58) as u64;
+        self.0[1] += self.0[0] >> 58;
+        self.0[0] &= M58;
+        self.0[2] += self.0[1] >> 58;
+        self.0[1] &= M58;
+    }
+
+    #[inline]
+    pub fn set_mul_small(&mut self, x: u32) {
+        let mut c = [0u128; 9];
+        for (ci, &a) in c.iter_mut().zip(self.0.iter()) {
+            *ci = (a as u128) * (x as u128);
+        }
+        self.reduce_wide(c);
+    }
+
+    #[inline(always)]
+    pub fn mul_small(self, x: u32) -> Self {
+        let mut r = self;
+        r.set_mul_small(x);
+        r
+    }
+
+    #[inline]
+    fn set_mul(&mut self, rhs: &Self) {
+        let a = &self.0;
+        let b = &rhs.0;
+        let mut c = [0u128; 9];
+        for i in 0..9 {
+            let ai = a[i] as u128;
+            for j in 0..9 {
+                if (i + j) < 9 {
+                    c[i + j] += ai * (b[j] as u128);
+                } else {
+                    c[i + j - 9] += ai * ((b[j] << 1) as u128);
+                }
+            }
+        }
+        self.reduce_wide(c);
+    }
+
+    // Square this value (in place).
+    #[inline]
+    pub fn set_square(&mut self) {
+        let a = &self.0;
+        let mut c = [0u128; 9];
+        for i in 0..9 {
+            let ai = a[i] as u128;
+            if (2 * i) < 9 {
+                c[2 * i] += ai * ai;
+            } else {
+                c[2 * i - 9] += ai * ((a[i] << 1) as u128);
+            }
+            for j in (i + 1)..9 {
+                let t = ai * ((a[j] << 1) as u128);
+                if (i + j) < 9 {
+                    c[i + j] += t;
+                } else {
+                    c[i + j - 9] += t << 1;
+                }
+            }
+        }
+        self.reduce_wide(c);
+    }
+
+    // Square this value.
+    #[inline(always)]
+    pub fn square(self) -> Self {
+        let mut r = self;
+        r.set_square();
+        r
+    }
+
+    // Square this value n times (in place).
+    #[inline(always)]
+    pub fn set_xsquare(&mut self, n: u32) {
+        for _ in 0..n {
+            self.set_square();
+        }
+    }
+
+    // Square this value n times.
+    #[inline(always)]
+    pub fn xsquare(self, n: u32) -> Self {
+        let mut r = self;
+        r.set_xsquare(n);
+        r
+    }
+
+    // Normalize this value to its unique representation in the 0..p-1
+    // range, with all limbs fully reduced.
+    #[inline]
+    fn set_normalized(&mut self) {
+        // After the first propagation, the value may still slightly
+        // exceed 2^521 (through limb y1). A second propagation then
+        // folds at most one unit, which cannot overflow again.
+        self.set_carry_propagate();
+        for _ in 0..2 {
+            for i in 0..8 {
+                self.0[i + 1] += self.0[i] >> 58;
+                self.0[i] &= M58;
+            }
+            self.0[0] += self.0[8] >> 57;
+            self.0[8] &= M57;
+        }
+
+        // Value is now in the 0..p range; p must be mapped to 0. The
+        // value is p if and only if all limbs are maximal.
+        let mut e = self.0[8] ^ M57;
+        for i in 0..8 {
+            e |= self.0[i] ^ M58;
+        }
+        let m = ((e | e.wrapping_neg()) >> 63).wrapping_sub(1);
+        for i in 0..9 {
+            self.0[i] &= !m;
+        }
+    }
+
+    // Compute x^(2^519 - 1), for the current value x. This is used for
+    // inversions, Legendre symbols and square roots.
+    fn pow_2_519_m1(self) -> Self {
+        let x = self;
+        let x2 = x.square() * x;
+        let x3 = x2.square() * x;
+        let x4 = x2.xsquare(2) * x2;
+        let x7 = x4.xsquare(3) * x3;
+        let x8 = x4.xsquare(4) * x4;
+        let x16 = x8.xsquare(8) * x8;
+        let x32 = x16.xsquare(16) * x16;
+        let x64 = x32.xsquare(32) * x32;
+        let x128 = x64.xsquare(64) * x64;
+        let x256 = x128.xsquare(128) * x128;
+        let x512 = x256.xsquare(256) * x256;
+        x512.xsquare(7) * x7
+    }
+
+    // Invert this value (in place); if this value is zero, then it
+    // remains equal to zero.
+    pub fn set_invert(&mut self) {
+        // 1/x = x^(p-2) = x^(2^521 - 3) = (x^(2^519 - 1))^4 * x
+        let x = *self;
+        *self = x.pow_2_519_m1().xsquare(2) * x;
+    }
+
+    // Invert this value; if this value is zero, then zero is returned.
+    #[inline(always)]
+    pub fn invert(self) -> Self {
+        let mut r = self;
+        r.set_invert();
+        r
+    }
+
+    #[inline(always)]
+    fn set_div(&mut self, y: &Self) {
+        self.set_mul(&y.invert());
+    }
+
+    // Perform a batch inversion of some elements. All elements of
+    // the slice are replaced with their respective inverse (elements
+    // of value zero are "inverted" into themselves).
+    pub fn batch_invert(xx: &mut [Self]) {
+        // We use Montgomery's trick:
+        //   1/u = v*(1/(u*v))
+        //   1/v = u*(1/(u*v))
+        // Applied recursively on n elements, this computes an inversion
+        // with a single inversion in the field, and 3*(n-1) multiplications.
+        // We use batches of 100 elements; larger batches only yield
+        // moderate improvements, while sticking to a fixed moderate batch
+        // size allows stack-based allocation.
+        let n = xx.len();
+        let mut i = 0;
+        while i < n {
+            let blen = if (n - i) > 100 { 100 } else { n - i };
+            let mut tt = [Self::ZERO; 100];
+            tt[0] = xx[i];
+            let zz0 = tt[0].iszero();
+            tt[0].set_cond(&Self::ONE, zz0);
+            for j in 1..blen {
+                tt[j] = xx[i + j];
+                tt[j].set_cond(&Self::ONE, tt[j].iszero());
+                tt[j] *= tt[j - 1];
+            }
+            let mut k = Self::ONE / tt[blen - 1];
+            for j in (1..blen).rev() {
+                let mut x = xx[i + j];
+                let zz = x.iszero();
+                x.set_cond(&Self::ONE, zz);
+                xx[i + j].set_cond(&(k * tt[j - 1]), !zz);
+                k *= x;
+            }
+            xx[i].set_cond(&k, !zz0);
+            i += blen;
+        }
+    }
+
+    // Compute the Legendre symbol on this value. Return value is:
+    //   0   if this value is zero
+    //  +1   if this value is a non-zero quadratic residue
+    //  -1   if this value is not a quadratic residue
+    pub fn legendre(self) -> i32 {
+        // (p-1)/2 = 2^520 - 1
+        let y = self.pow_2_519_m1().square() * self;
+        let z = y.iszero();
+        let o = y.equals(Self::ONE);
+        ((o & 1) as i32) - ((!(o | z) & 1) as i32)
+    }
+
+    // Set this value to its square root. Returned value is 0xFFFFFFFF
+    // if the operation succeeded (value was indeed a quadratic
+    // residue), 0 otherwise (value was not a quadratic residue). In the
+    // latter case, this value is set to the square root of -self.
+    // In all cases, the returned root is the one whose least significant
+    // bit is 0 (when normalized in 0..p-1).
+    fn set_sqrt_ext(&mut self) -> u32 {
+        // Candidate root is y = x^((p+1)/4) = x^(2^519).
+        let mut y = self.xsquare(519);
+
+        // Normalize y and negate it if necessary to set the low bit to 0.
+        y.set_normalized();
+        y.set_cond(&-y, ((y.0[0] as u32) & 1).wrapping_neg());
+
+        // Check that the candidate is indeed a square root.
+        // Note that: y^2 = x^((p+1)/2) = x*(x^((p-1)/2))
+        // If x is a non-zero square, then x^((p-1)/2) = 1, and y is
+        // a square root of x; if x is not a square, then x^((p-1)/2) = -1,
+        // and we indeed have y^2 = -x, as expected by the API.
+        let r = y.square().equals(*self);
+        *self = y;
+        r
+    }
+
+    // Set this value to its square root. Returned value is 0xFFFFFFFF
+    // if the operation succeeded (value was indeed a quadratic
+    // residue), 0 otherwise (value was not a quadratic residue). This
+    // differs from set_sqrt_ext() in that this function sets the value
+    // to zero if there is no square root.
+    fn set_sqrt(&mut self) -> u32 {
+        let r = self.set_sqrt_ext();
+        self.set_cond(&Self::ZERO, !r);
+        r
+    }
+
+    // Compute the square root of this value. Returned values are (y, r):
+    //  - If this value is indeed a quadratic residue, then y is the
+    //    square root whose least significant bit (when normalized in 0..p-1)
+    //    is 0, and r is equal to 0xFFFFFFFF.
+    //  - If this value is not a quadratic residue, then y is zero, and
+    //    r is equal to 0.
+    #[inline(always)]
+    pub fn sqrt(self) -> (Self, u32) {
+        let mut x = self;
+        let r = x.set_sqrt();
+        (x, r)
+    }
+
+    // Compute the square root of this value. Returned values are (y, r):
+    //  - If this value is indeed a quadratic residue, then y is a
+    //    square root of this value, and r is 0xFFFFFFFF.
+    //  - If this value is not a quadratic residue, then y is set to
+    //    a square root of -x, and r is 0x00000000.
+    // In all cases, the returned root is normalized: the least significant
+    // bit of its integer representation (in the 0..p-1 range) is 0.
+    #[inline(always)]
+    pub fn sqrt_ext(self) -> (Self, u32) {
+        let mut x = self;
+        let r = x.set_sqrt_ext();
+        (x, r)
+    }
+
+    // Equality check between two field elements (constant-time);
+    // returned value is 0xFFFFFFFF on equality, 0 otherwise.
+    #[inline(always)]
+    pub fn equals(self, rhs: Self) -> u32 {
+        (self - rhs).iszero()
+    }
+
+    // Compare this value with zero (constant-time); returned value
+    // is 0xFFFFFFFF if this element is zero, 0 otherwise.
+    #[inline]
+    pub fn iszero(self) -> u32 {
+        let mut r = self;
+        r.set_normalized();
+        let mut t = 0;
+        for i in 0..9 {
+            t |= r.0[i];
+        }
+        ((t | t.wrapping_neg()) >> 63).wrapping_sub(1) as u32
+    }
+
+    // Convert this value (which must be normalized) into nine 64-bit
+    // words (low-to-high order).
+    #[inline(always)]
+    fn to_w64le(self) -> [u64; 9] {
+        let mut w = [0u64; 9];
+        for i in 0..9 {
+            let off = 58 * i;
+            let k = off >> 6;
+            let s = off & 63;
+            w[k] |= self.0[i] << s;
+            if s > 6 {
+                w[k + 1] |= self.0[i] >> (64 - s);
+            }
+        }
+        w
+    }
+
+    // Decode up to 72 bytes (unsigned little-endian) into 64-bit words.
+    #[inline(always)]
+    fn bytes_to_w64le(buf: &[u8]) -> [u64; 9] {
+        let mut tmp = [0u8; 72];
+        tmp[..buf.len()].copy_from_slice(buf);
+        let mut w = [0u64; 9];
+        for i in 0..9 {
+            w[i] = u64::from_le_bytes(*<&[u8; 8]>::try_from(
+                &tmp[(i * 8)..(i * 8 + 8)]).unwrap());
+        }
+        w
+    }
+
+    // Encode this value over exactly 66 bytes. Encoding is always canonical
+    // (little-endian encoding of the value in the 0..p-1 range, top seven
+    // bits of the last byte are always 0).
+    #[inline(always)]
+    pub fn encode(self) -> [u8; 66] {
+        let mut r = self;
+        r.set_normalized();
+        let w = r.to_w64le();
+        let mut d = [0u8; 66];
+        for i in 0..8 {
+            d[(i * 8)..(i * 8 + 8)].copy_from_slice(&w[i].to_le_bytes());
+        }
+        d[64..66].copy_from_slice(&w[8].to_le_bytes()[..2]);
+        d
+    }
+
+    // Decode a field element from 66 bytes. On success, this sets this
+    // element to the decoded value, and returns 0xFFFFFFFF. If the source
+    // encoding is not canonical (i.e. the unsigned little-endian
+    // interpretation of the 66 bytes is not lower than the field modulus p,
+    // or if the source slice has not length exactly 66 bytes), then this
+    // sets this element to zero, and returns 0.
+    #[inline]
+    pub fn set_decode_ct(&mut self, buf: &[u8]) -> u32 {
+        *self = Self::ZERO;
+        if buf.len() != 66 {
+            return 0;
+        }
+
+        let w = Self::bytes_to_w64le(buf);
+
+        // The value is canonical if the top 7 bits are zero and the
+        // value is not equal to p (all 521 low bits set).
+        let hi = w[8] >> 9;
+        let mut e = w[8] ^ 0x1FF;
+        for &wi in &w[..8] {
+            e |= !wi;
+        }
+        let ok = ((hi | hi.wrapping_neg()) >> 63).wrapping_sub(1)
+            & (0u64.wrapping_sub((e | e.wrapping_neg()) >> 63));
+        *self = Self::w64le(w);
+        for i in 0..9 {
+            self.0[i] &= ok;
+        }
+        ok as u32
+    }
+
+    // Decode a field element from 66 bytes. On success, this returns the
+    // new element, and a status 0xFFFFFFFF. If the source encoding is not
+    // canonical (i.e. the unsigned little-endian interpretation of the 66
+    // bytes is not lower than the field modulus p, or if the source slice
+    // has not length exactly 66 bytes), then this sets this returns the
+    // element zero and the status 0x00000000.
+    #[inline(always)]
+    pub fn decode_ct(buf: &[u8]) -> (Self, u32) {
+        let mut r = Self::ZERO;
+        let cc = r.set_decode_ct(buf);
+        (r, cc)
+    }
+
+    // Decode a field element from 66 bytes. On success, this returns the
+    // new element. If the source encoding is not canonical (i.e. the
+    // unsigned little-endian interpretation of the 66 bytes is not lower
+    // than the field modulus p, or if the source slice has not length
+    // exactly 66 bytes), then this sets this returns `None`.
+    #[inline(always)]
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        let (r, cc) = Self::decode_ct(buf);
+        if cc != 0 {
+            Some(r)
+        } else {
+            None
+        }
+    }
+
+    // Decode a field element from some bytes. The bytes are interpreted
+    // in unsigned little-endian convention, and the resulting integer is
+    // reduced modulo p. This process never fails.
+    pub fn set_decode_reduce(&mut self, buf: &[u8]) {
+        *self = Self::ZERO;
+        let mut n = buf.len();
+        if n == 0 {
+            return;
+        }
+
+        // Get the high bytes so that the remaining number of bytes is
+        // a multiple of 65.
+        let mut n1 = n % 65;
+        if n1 == 0 {
+            n1 = 65;
+        }
+        n -= n1;
+        *self = Self::w64le(Self::bytes_to_w64le(&buf[n..]));
+
+        // Process remaining 65-byte chunks in high to low order. For
+        // each chunk, the current value is multiplied by 2^520, which is
+        // equal to 1/2 modulo p, and the new chunk is added in.
+        while n > 0 {
+            n -= 65;
+            self.set_half();
+            self.set_add(&Self::w64le(
+                Self::bytes_to_w64le(&buf[n..(n + 65)])));
+        }
+    }
+
+    // Decode a field element from some bytes. The bytes are interpreted
+    // in unsigned little-endian convention, and the resulting integer is
+    // reduced modulo p. This process never fails.
+    pub fn decode_reduce(buf: &[u8]) -> Self {
+        let mut r = Self::ZERO;
+        r.set_decode_reduce(buf);
+        r
+    }
+}
+
+// ========================================================================
+// Implementations of all the traits needed to use the simple operators
+// (+, *, /...) on field element instances, with or without references.
+
+impl Add<GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn add(self, other: GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_add(&other);
+        r
+    }
+}
+
+impl Add<&GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn add(self, other: &GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_add(other);
+        r
+    }
+}
+
+impl Add<GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn add(self, other: GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_add(&other);
+        r
+    }
+}
+
+impl Add<&GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn add(self, other: &GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_add(other);
+        r
+    }
+}
+
+impl AddAssign<GFp521> for GFp521 {
+    #[inline(always)]
+    fn add_assign(&mut self, other: GFp521) {
+        self.set_add(&other);
+    }
+}
+
+impl AddAssign<&GFp521> for GFp521 {
+    #[inline(always)]
+    fn add_assign(&mut self, other: &GFp521) {
+        self.set_add(other);
+    }
+}
+
+impl Div<GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn div(self, other: GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_div(&other);
+        r
+    }
+}
+
+impl Div<&GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn div(self, other: &GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_div(other);
+        r
+    }
+}
+
+impl Div<GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn div(self, other: GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_div(&other);
+        r
+    }
+}
+
+impl Div<&GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn div(self, other: &GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_div(other);
+        r
+    }
+}
+
+impl DivAssign<GFp521> for GFp521 {
+    #[inline(always)]
+    fn div_assign(&mut self, other: GFp521) {
+        self.set_div(&other);
+    }
+}
+
+impl DivAssign<&GFp521> for GFp521 {
+    #[inline(always)]
+    fn div_assign(&mut self, other: &GFp521) {
+        self.set_div(other);
+    }
+}
+
+impl Mul<GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn mul(self, other: GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_mul(&other);
+        r
+    }
+}
+
+impl Mul<&GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn mul(self, other: &GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_mul(other);
+        r
+    }
+}
+
+impl Mul<GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn mul(self, other: GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_mul(&other);
+        r
+    }
+}
+
+impl Mul<&GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn mul(self, other: &GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_mul(other);
+        r
+    }
+}
+
+impl MulAssign<GFp521> for GFp521 {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: GFp521) {
+        self.set_mul(&other);
+    }
+}
+
+impl MulAssign<&GFp521> for GFp521 {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: &GFp521) {
+        self.set_mul(other);
+    }
+}
+
+impl Neg for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn neg(self) -> GFp521 {
+        let mut r = self;
+        r.set_neg();
+        r
+    }
+}
+
+impl Neg for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn neg(self) -> GFp521 {
+        let mut r = *self;
+        r.set_neg();
+        r
+    }
+}
+
+impl Sub<GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn sub(self, other: GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_sub(&other);
+        r
+    }
+}
+
+impl Sub<&GFp521> for GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn sub(self, other: &GFp521) -> GFp521 {
+        let mut r = self;
+        r.set_sub(other);
+        r
+    }
+}
+
+impl Sub<GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn sub(self, other: GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_sub(&other);
+        r
+    }
+}
+
+impl Sub<&GFp521> for &GFp521 {
+    type Output = GFp521;
+
+    #[inline(always)]
+    fn sub(self, other: &GFp521) -> GFp521 {
+        let mut r = *self;
+        r.set_sub(other);
+        r
+    }
+}
+
+impl SubAssign<GFp521> for GFp521 {
+    #[inline(always)]
+    fn sub_assign(&mut self, other: GFp521) {
+        self.set_sub(&other);
+    }
+}
+
+impl SubAssign<&GFp521> for GFp521 {
+    #[inline(always)]
+    fn sub_assign(&mut self, other: &GFp521) {
+        self.set_sub(other);
+    }
+}
+
+// ========================================================================
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::{GFp521};
+    use num_bigint::{BigInt, Sign};
+    use sha2::{Sha512, Digest};
+
+    // va, vb and vx must be 66 bytes each in length
+    fn check_gf_ops(va: &[u8], vb: &[u8], vx: &[u8]) {
+        let zp = (BigInt::from(1u32) << 521) - 1;
+
+        let a = GFp521::decode_reduce(va);
+        let b = GFp521::decode_reduce(vb);
+        let za = BigInt::from_bytes_le(Sign::Plus, va);
+        let zb = BigInt::from_bytes_le(Sign::Plus, vb);
+
+        let vc = a.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = &za % &zp;
+        assert!(zc == zd);
+
+        let c = a + b;
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za + &zb) % &zp;
+        assert!(zc == zd);
+
+        let c = a - b;
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = ((&zp + &za % &zp) - &zb % &zp) % &zp;
+        assert!(zc == zd);
+
+        let c = -a;
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&zp - &za % &zp) % &zp;
+        assert!(zc == zd);
+
+        let c = a * b;
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za * &zb) % &zp;
+        assert!(zc == zd);
+
+        let c = a.half();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd: BigInt = ((&zp + (&zc << 1)) - &za % &zp) % &zp;
+        assert!(zd.sign() == Sign::NoSign);
+
+        let c = a.mul2();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za << 1) % &zp;
+        assert!(zc == zd);
+
+        let c = a.mul3();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za * 3u32) % &zp;
+        assert!(zc == zd);
+
+        let c = a.mul4();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za << 2) % &zp;
+        assert!(zc == zd);
+
+        let c = a.mul8();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za << 3) % &zp;
+        assert!(zc == zd);
+
+        let c = a.mul16();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za << 4) % &zp;
+        assert!(zc == zd);
+
+        let c = a.mul32();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za << 5) % &zp;
+        assert!(zc == zd);
+
+        let x = u32::from_le_bytes([vb[0], vb[1], vb[2], vb[3]]);
+        let c = a.mul_small(x);
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za * x) % &zp;
+        assert!(zc == zd);
+
+        let c = a.square();
+        let vc = c.encode();
+        let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+        let zd = (&za * &za) % &zp;
+        assert!(zc == zd);
+
+        let (e, cc) = GFp521::decode_ct(va);
+        if cc != 0 {
+            assert!(cc == 0xFFFFFFFF);
+            assert!(e.encode() == va);
+        } else {
+            assert!(e.encode() == [0u8; 66]);
+        }
+
+        let mut tmp = [0u8; 198];
+        tmp[0..66].copy_from_slice(va);
+        tmp[66..132].copy_from_slice(vb);
+        tmp[132..198].copy_from_slice(vx);
+        for k in 0..199 {
+            let c = GFp521::decode_reduce(&tmp[0..k]);
+            let vc = c.encode();
+            let zc = BigInt::from_bytes_le(Sign::Plus, &vc);
+            let zd = BigInt::from_bytes_le(Sign::Plus, &tmp[0..k]) % &zp;
+            assert!(zc == zd);
+        }
+
+        let c = a / b;
+        let d = c * b;
+        if b.iszero() != 0 {
+            assert!(c.iszero() != 0);
+        } else {
+            assert!(a.equals(d) != 0);
+        }
+    }
+
+    #[test]
+    fn gfp521_ops() {
+        let mut va = [0u8; 66];
+        let mut vb = [0u8; 66];
+        let mut vx = [0u8; 66];
+        check_gf_ops(&va, &vb, &vx);
+        assert!(GFp521::decode_reduce(&va).iszero() == 0xFFFFFFFF);
+        assert!(GFp521::decode_reduce(&va).equals(GFp521::decode_reduce(&vb)) == 0xFFFFFFFF);
+        assert!(GFp521::decode_reduce(&va).legendre() == 0);
+        for i in 0..66 {
+            va[i] = 0xFFu8;
+            vb[i] = 0xFFu8;
+            vx[i] = 0xFFu8;
+        }
+        check_gf_ops(&va, &vb, &vx);
+        assert!(GFp521::decode_reduce(&va).iszero() == 0);
+        assert!(GFp521::decode_reduce(&va).equals(GFp521::decode_reduce(&vb)) == 0xFFFFFFFF);
+
+        // p itself (all 521 low bits set) reduces to zero, and its
+        // encoding is not canonical; p - 1 is canonical.
+        va[65] = 0x01;
+        assert!(GFp521::decode_reduce(&va).iszero() == 0xFFFFFFFF);
+        assert!(GFp521::decode_ct(&va).1 == 0);
+        va[0] = 0xFE;
+        let (e, cc) = GFp521::decode_ct(&va);
+        assert!(cc == 0xFFFFFFFF);
+        assert!(e.equals(GFp521::MINUS_ONE) == 0xFFFFFFFF);
+        assert!(e.encode() == va);
+        va[65] = 0x02;
+        assert!(GFp521::decode_ct(&va).1 == 0);
+
+        // Operands with limbs at the top of the allowed range.
+        check_gf_ops(&GFp521::MINUS_ONE.encode(), &va, &vx);
+        let m = GFp521::MINUS_ONE;
+        assert!((m * m).equals(GFp521::ONE) == 0xFFFFFFFF);
+        assert!((m + GFp521::ONE).iszero() == 0xFFFFFFFF);
+        assert!(GFp521::from_i32(-1).equals(m) == 0xFFFFFFFF);
+        assert!(GFp521::from_i64(-3).equals(-GFp521::THREE) == 0xFFFFFFFF);
+        assert!(GFp521::from_i128(-2).equals(-GFp521::TWO) == 0xFFFFFFFF);
+        assert!(GFp521::from_u128(u128::MAX).equals(
+            GFp521::w64le([ u64::MAX, u64::MAX, 0, 0, 0, 0, 0, 0, 0 ]))
+            == 0xFFFFFFFF);
+        assert!(GFp521::w64le([ 0, 0, 0, 0, 0, 0, 0, 0, 1u64 << 9 ])
+            .equals(GFp521::ONE) == 0xFFFFFFFF);
+        assert!(GFp521::w64le([ u64::MAX; 9 ]).equals(
+            GFp521::from_u64((1u64 << 55) - 1)) == 0xFFFFFFFF);
+
+        let mut sh = Sha512::new();
+        for i in 0..300 {
+            let mut va = [0u8; 66];
+            let mut vb = [0u8; 66];
+            let mut vx = [0u8; 66];
+            sh.update(((6 * i + 0) as u64).to_le_bytes());
+            va[..64].copy_from_slice(&sh.finalize_reset());
+            sh.update(((6 * i + 1) as u64).to_le_bytes());
+            va[64..].copy_from_slice(&sh.finalize_reset()[..2]);
+            sh.update(((6 * i + 2) as u64).to_le_bytes());
+            vb[..64].copy_from_slice(&sh.finalize_reset());
+            sh.update(((6 * i + 3) as u64).to_le_bytes());
+            vb[64..].copy_from_slice(&sh.finalize_reset()[..2]);
+            sh.update(((6 * i + 4) as u64).to_le_bytes());
+            vx[..64].copy_from_slice(&sh.finalize_reset());
+            sh.update(((6 * i + 5) as u64).to_le_bytes());
+            vx[64..].copy_from_slice(&sh.finalize_reset()[..2]);
+            check_gf_ops(&va, &vb, &vx);
+            assert!(GFp521::decode_reduce(&va).iszero() == 0);
+            assert!(GFp521::decode_reduce(&va).equals(GFp521::decode_reduce(&vb)) == 0);
+            let nqr = 3u32;
+            let s = GFp521::decode_reduce(&va).square();
+            let s2 = s.mul_small(nqr);
+            assert!(s.legendre() == 1);
+            assert!(s2.legendre() == -1);
+            let (t, r) = s.sqrt();
+            assert!(r == 0xFFFFFFFF);
+            assert!(t.square().equals(s) == 0xFFFFFFFF);
+            assert!((t.encode()[0] & 1) == 0);
+            let (t, r) = s.sqrt_ext();
+            assert!(r == 0xFFFFFFFF);
+            assert!(t.square().equals(s) == 0xFFFFFFFF);
+            assert!((t.encode()[0] & 1) == 0);
+            let (t2, r) = s2.sqrt();
+            assert!(r == 0);
+            assert!(t2.iszero() == 0xFFFFFFFF);
+            let (t2, r) = s2.sqrt_ext();
+            assert!(r == 0);
+            assert!(t2.square().equals(-s2) == 0xFFFFFFFF);
+        }
+    }
+
+    #[test]
+    fn gfp521_batch_invert() {
+        let mut xx = [GFp521::ZERO; 300];
+        let mut sh = Sha512::new();
+        for i in 0..300 {
+            sh.update((i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            xx[i] = GFp521::decode_reduce(&v[..]);
+        }
+        xx[120] = GFp521::ZERO;
+        let mut yy = xx;
+        GFp521::batch_invert(&mut yy[..]);
+        for i in 0..300 {
+            if xx[i].iszero() != 0 {
+                assert!(yy[i].iszero() == 0xFFFFFFFF);
+            } else {
+                assert!((xx[i] * yy[i]).equals(GFp521::ONE) == 0xFFFFFFFF);
+            }
+        }
+    }
+}
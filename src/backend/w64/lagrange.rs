@@ -105,11 +105,7 @@ macro_rules! define_bigint { ($typename:ident, $bitlen:expr) => {
 
         // Swap the contents of self with rhs.
         fn swap(&mut self, rhs: &mut Self) {
-            for i in 0..Self::N {
-                let t = self.0[i];
-                self.0[i] = rhs.0[i];
-                rhs.0[i] = t;
-            }
+            core::mem::swap(&mut self.0, &mut rhs.0);
         }
 
         // Get the length (in bits) of this value. If `unsigned` is true,
@@ -441,20 +437,24 @@ define_bigint!(ZInt320, 320);
 define_bigint!(ZInt384, 384);
 define_bigint!(ZInt448, 448);
 define_bigint!(ZInt512, 512);
+define_bigint!(ZInt576, 576);
 define_bigint!(ZInt640, 640);
 define_bigint!(ZInt768, 768);
+define_bigint!(ZInt832, 832);
 define_bigint!(ZInt896, 896);
 define_bigint!(ZInt1024, 1024);
+define_bigint!(ZInt1152, 1152);
 
 define_lagrange!(lagrange256_vartime, ZInt128, ZInt256, ZInt384, ZInt512);
 define_lagrange!(lagrange320_vartime, ZInt192, ZInt320, ZInt448, ZInt640);
 define_lagrange!(lagrange384_vartime, ZInt192, ZInt384, ZInt512, ZInt768);
 define_lagrange!(lagrange448_vartime, ZInt256, ZInt448, ZInt640, ZInt896);
 define_lagrange!(lagrange512_vartime, ZInt256, ZInt512, ZInt768, ZInt1024);
+define_lagrange!(lagrange576_vartime, ZInt320, ZInt576, ZInt832, ZInt1152);
 
 //
 // Rules:
-//   k and n must have the same length, which is between 4 and 8 (inclusive)
+//   k and n must have the same length, which is between 4 and 9 (inclusive)
 //   k and n use unsigned little-endian notation
 //   k < n (numerically)
 //   c0 and c1 must have length at most ceil(n.len()/2)
@@ -508,6 +508,14 @@ pub(crate) fn lagrange_vartime(k: &[u64], n: &[u64], max_bitlen: u32,
             c0.copy_from_slice(&v0[..c0.len()]);
             c1.copy_from_slice(&v1[..c1.len()]);
         }
+        9 => {
+            let (v0, v1) = lagrange576_vartime(
+                <&[u64; 9]>::try_from(k).unwrap(),
+                <&[u64; 9]>::try_from(n).unwrap(),
+                max_bitlen);
+            c0.copy_from_slice(&v0[..c0.len()]);
+            c1.copy_from_slice(&v1[..c1.len()]);
+        }
         _ => {
             unimplemented!();
         }
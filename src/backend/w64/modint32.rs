@@ -913,8 +913,9 @@ impl<const M0: u64, const M1: u64, const M2: u64, const M3: u64> ModInt256ct<M0,
 
     // Perform a batch inversion of some elements. All elements of
     // the slice are replaced with their respective inverse (elements
-    // of value zero are "inverted" into themselves).
-    pub fn batch_invert(xx: &mut [Self]) {
+    // of value zero are "inverted" into themselves). Returned value is
+    // 0xFFFFFFFF if all elements were non-zero, 0x00000000 otherwise.
+    pub fn batch_invert(xx: &mut [Self]) -> u32 {
         // We use Montgomery's trick:
         //   1/u = v*(1/(u*v))
         //   1/v = u*(1/(u*v))
@@ -924,6 +925,7 @@ impl<const M0: u64, const M1: u64, const M2: u64, const M3: u64> ModInt256ct<M0,
         // moderate improvements, while sticking to a fixed moderate batch
         // size allows stack-based allocation.
         let n = xx.len();
+        let mut r = 0xFFFFFFFFu32;
         let mut i = 0;
         while i < n {
             let blen = if (n - i) > 200 { 200 } else { n - i };
@@ -931,6 +933,7 @@ impl<const M0: u64, const M1: u64, const M2: u64, const M3: u64> ModInt256ct<M0,
             tt[0] = xx[i];
             let zz0 = tt[0].iszero();
             tt[0].set_cond(&Self::ONE, zz0);
+            r &= !zz0;
             for j in 1..blen {
                 tt[j] = xx[i + j];
                 tt[j].set_cond(&Self::ONE, tt[j].iszero());
@@ -943,10 +946,12 @@ impl<const M0: u64, const M1: u64, const M2: u64, const M3: u64> ModInt256ct<M0,
                 x.set_cond(&Self::ONE, zz);
                 xx[i + j].set_cond(&(k * tt[j - 1]), !zz);
                 k *= x;
+                r &= !zz;
             }
             xx[i].set_cond(&k, !zz0);
             i += blen;
         }
+        r
     }
 
     // Compute the Legendre symbol on this value. Return value is:
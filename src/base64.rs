@@ -0,0 +1,423 @@
+//! Base64 encoding and decoding (RFC 4648).
+//!
+//! Both the standard alphabet (with `+` and `/`) and the URL-safe
+//! alphabet (with `-` and `_`) are supported, with or without padding
+//! (`=` characters), as selected by a `Variant` parameter. All functions
+//! work over caller-provided buffers; `encoded_len()` and `decoded_len()`
+//! return the exact sizes of the output.
+//!
+//! As in the `hex` module, characters are mapped to and from their
+//! values with arithmetic operations only (no lookup table, no
+//! data-dependent branch), and errors are accumulated over the whole
+//! input. Timing may depend on the input length and on the number of
+//! padding characters, but not on the encoded data, so that these
+//! functions can be used on secret values.
+//!
+//! Decoding is strict: only the canonical encoding of a given byte
+//! sequence is accepted. In particular, the unused bits of the last
+//! character must be zero, padding must be present (and correct) for
+//! the padded variants, and absent for the unpadded variants. Whitespace
+//! is not tolerated.
+
+/// Base64 variant (alphabet and padding).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// Standard alphabet (`+` and `/`), with padding.
+    Standard,
+    /// Standard alphabet (`+` and `/`), without padding.
+    StandardNoPad,
+    /// URL-safe alphabet (`-` and `_`), with padding.
+    UrlSafe,
+    /// URL-safe alphabet (`-` and `_`), without padding.
+    UrlSafeNoPad,
+}
+
+/// Error type for Base64 decoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64Error {
+    /// The input length is not valid for the variant.
+    InvalidLength,
+    /// Padding is missing, misplaced, or present in an unpadded variant.
+    InvalidPadding,
+    /// The input contains a character outside of the alphabet.
+    BadCharacter,
+    /// The last character has non-zero unused bits (non-canonical
+    /// encoding).
+    NonCanonical,
+    /// The output buffer is too small.
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Base64Error::InvalidLength => f.write_str("invalid input length"),
+            Base64Error::InvalidPadding => f.write_str("invalid padding"),
+            Base64Error::BadCharacter => f.write_str("invalid base64 character"),
+            Base64Error::NonCanonical => f.write_str("non-canonical encoding"),
+            Base64Error::BufferTooSmall => f.write_str("output buffer too small"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Base64Error {}
+
+impl Variant {
+
+    #[inline(always)]
+    fn padded(self) -> bool {
+        matches!(self, Variant::Standard | Variant::UrlSafe)
+    }
+
+    // Characters for values 62 and 63.
+    #[inline(always)]
+    fn extra_chars(self) -> (i32, i32) {
+        match self {
+            Variant::Standard | Variant::StandardNoPad => (0x2B, 0x2F),
+            Variant::UrlSafe | Variant::UrlSafeNoPad => (0x2D, 0x5F),
+        }
+    }
+}
+
+// Map a 6-bit value to its character.
+#[inline(always)]
+fn value_to_char(v: u8, c62: i32, c63: i32) -> u8 {
+    let v = v as i32;
+    // Start with 'A' + v; then adjust for each range. Each
+    // (k - v) >> 8 is -1 if v > k, 0 otherwise.
+    let mut c = v + 0x41;
+    c += ((25 - v) >> 8) & 6;
+    c -= ((51 - v) >> 8) & 75;
+    c += ((61 - v) >> 8) & (c62 - 62 + 4);
+    c += ((62 - v) >> 8) & (c63 - c62 - 1);
+    c as u8
+}
+
+// Equality mask: -1 if x == y, 0 otherwise (x and y in 0..255).
+#[inline(always)]
+fn eq_mask(x: i32, y: i32) -> i32 {
+    ((x ^ y) - 1) >> 8
+}
+
+// Range mask: -1 if lo <= x <= hi, 0 otherwise (values in -255..255).
+#[inline(always)]
+fn range_mask(x: i32, lo: i32, hi: i32) -> i32 {
+    !(((x - lo) | (hi - x)) >> 8)
+}
+
+// Map a character to its 6-bit value; the returned mask is -1 if the
+// character is valid, 0 otherwise.
+#[inline(always)]
+fn char_to_value(c: u8, c62: i32, c63: i32) -> (u32, i32) {
+    let c = c as i32;
+    let m1 = range_mask(c, 0x41, 0x5A);
+    let m2 = range_mask(c, 0x61, 0x7A);
+    let m3 = range_mask(c, 0x30, 0x39);
+    let m4 = eq_mask(c, c62);
+    let m5 = eq_mask(c, c63);
+    let v = ((c - 0x41) & m1) | ((c - 0x47) & m2) | ((c + 4) & m3)
+        | (62 & m4) | (63 & m5);
+    (v as u32, m1 | m2 | m3 | m4 | m5)
+}
+
+/// Get the length of the encoding of `len` bytes.
+pub fn encoded_len(len: usize, variant: Variant) -> usize {
+    if variant.padded() {
+        len.div_ceil(3) << 2
+    } else {
+        (len / 3) * 4 + [0, 2, 3][len % 3]
+    }
+}
+
+// Get the number of padding characters at the end of `src` (0 to 2;
+// `src` is assumed to have a valid length for the padded variants).
+fn padding_len(src: &[u8]) -> usize {
+    let n = src.len();
+    if n >= 2 && src[n - 2] == b'=' && src[n - 1] == b'=' {
+        2
+    } else if n >= 1 && src[n - 1] == b'=' {
+        1
+    } else {
+        0
+    }
+}
+
+/// Get the length of the decoded output for the provided encoded
+/// string. For the padded variants, this depends on the number of
+/// padding characters. An error is returned if the input length is not
+/// valid; other errors (e.g. invalid characters) are detected only by
+/// `decode()`.
+pub fn decoded_len(src: &[u8], variant: Variant)
+    -> Result<usize, Base64Error>
+{
+    let mut n = src.len();
+    if variant.padded() {
+        if (n & 3) != 0 {
+            return Err(Base64Error::InvalidLength);
+        }
+        n -= padding_len(src);
+    }
+    if (n & 3) == 1 {
+        return Err(Base64Error::InvalidLength);
+    }
+    Ok((n >> 2) * 3 + [0, 0, 1, 2][n & 3])
+}
+
+/// Encode some bytes in Base64. The output buffer must have length at
+/// least `encoded_len(src.len(), variant)`; the number of written
+/// characters is returned.
+pub fn encode(src: &[u8], dst: &mut [u8], variant: Variant)
+    -> Result<usize, Base64Error>
+{
+    let n = encoded_len(src.len(), variant);
+    if dst.len() < n {
+        return Err(Base64Error::BufferTooSmall);
+    }
+    let (c62, c63) = variant.extra_chars();
+    let mut j = 0;
+    for chunk in src.chunks(3) {
+        let mut w = (chunk[0] as u32) << 16;
+        if chunk.len() > 1 {
+            w |= (chunk[1] as u32) << 8;
+        }
+        if chunk.len() > 2 {
+            w |= chunk[2] as u32;
+        }
+        let k = chunk.len() + 1;
+        for i in 0..k {
+            let v = ((w >> (18 - 6 * i)) & 0x3F) as u8;
+            dst[j + i] = value_to_char(v, c62, c63);
+        }
+        j += k;
+    }
+    while j < n {
+        dst[j] = b'=';
+        j += 1;
+    }
+    Ok(n)
+}
+
+/// Decode some Base64 characters. The output buffer must have length at
+/// least `decoded_len(src, variant)`; the number of decoded bytes is
+/// returned.
+///
+/// The whole input is always processed; on error, no information is
+/// leaked on the position of the invalid character(s), and the relevant
+/// part of the output buffer is filled with zeros. If several errors
+/// apply, then `InvalidPadding` is reported in priority, then
+/// `BadCharacter`, then `NonCanonical`.
+pub fn decode(src: &[u8], dst: &mut [u8], variant: Variant)
+    -> Result<usize, Base64Error>
+{
+    let dlen = decoded_len(src, variant)?;
+    if dst.len() < dlen {
+        return Err(Base64Error::BufferTooSmall);
+    }
+    let src = if variant.padded() {
+        &src[..(src.len() - padding_len(src))]
+    } else {
+        src
+    };
+    let dst = &mut dst[..dlen];
+
+    let (c62, c63) = variant.extra_chars();
+    let mut ok = -1i32;
+    let mut no_pad = -1i32;
+    let mut j = 0;
+    let mut last = 0u32;
+    for chunk in src.chunks(4) {
+        let mut w = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let (v, m) = char_to_value(c, c62, c63);
+            ok &= m;
+            no_pad &= !eq_mask(c as i32, 0x3D);
+            w |= v << (18 - 6 * i);
+            last = v;
+        }
+        let k = chunk.len() - 1;
+        for i in 0..k {
+            dst[j + i] = (w >> (16 - 8 * i)) as u8;
+        }
+        j += k;
+    }
+
+    // Unused bits of the last character (2 or 4 bits, for a final
+    // group of 3 or 2 characters) must be zero.
+    let unused = match src.len() & 3 {
+        2 => 0x0F,
+        3 => 0x03,
+        _ => 0x00,
+    };
+    let canon = (((last & unused) as i32) - 1) >> 8;
+
+    let m = (ok & no_pad & canon) as u8;
+    for b in dst.iter_mut() {
+        *b &= m;
+    }
+    if no_pad == 0 {
+        Err(Base64Error::InvalidPadding)
+    } else if ok == 0 {
+        Err(Base64Error::BadCharacter)
+    } else if canon == 0 {
+        Err(Base64Error::NonCanonical)
+    } else {
+        Ok(dlen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Variant, Base64Error, encode, decode, encoded_len, decoded_len};
+
+    const ALL: [Variant; 4] = [
+        Variant::Standard, Variant::StandardNoPad,
+        Variant::UrlSafe, Variant::UrlSafeNoPad,
+    ];
+
+    // Simple table-based reference encoder.
+    fn ref_encode(src: &[u8], variant: Variant) -> crate::Vec<u8> {
+        let mut alph = *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        if variant == Variant::UrlSafe || variant == Variant::UrlSafeNoPad {
+            alph[62] = b'-';
+            alph[63] = b'_';
+        }
+        let mut r = crate::Vec::new();
+        for chunk in src.chunks(3) {
+            let mut t = [0u8; 3];
+            t[..chunk.len()].copy_from_slice(chunk);
+            let w = ((t[0] as usize) << 16) | ((t[1] as usize) << 8) | (t[2] as usize);
+            for i in 0..(chunk.len() + 1) {
+                r.push(alph[(w >> (18 - 6 * i)) & 0x3F]);
+            }
+            if variant == Variant::Standard || variant == Variant::UrlSafe {
+                for _ in chunk.len()..3 {
+                    r.push(b'=');
+                }
+            }
+        }
+        r
+    }
+
+    #[test]
+    fn rfc4648() {
+        const KAT: [(&str, &str); 7] = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (p, e) in KAT.iter() {
+            let mut buf = [0u8; 8];
+            let n = encode(p.as_bytes(), &mut buf, Variant::Standard).unwrap();
+            assert!(&buf[..n] == e.as_bytes());
+            let mut out = [0u8; 6];
+            let n = decode(e.as_bytes(), &mut out, Variant::Standard).unwrap();
+            assert!(&out[..n] == p.as_bytes());
+
+            let e2 = e.trim_end_matches('=');
+            let n = encode(p.as_bytes(), &mut buf, Variant::UrlSafeNoPad).unwrap();
+            assert!(&buf[..n] == e2.as_bytes());
+            let n = decode(e2.as_bytes(), &mut out, Variant::UrlSafeNoPad).unwrap();
+            assert!(&out[..n] == p.as_bytes());
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut data = [0u8; 257];
+        let mut x = 0x12345678u32;
+        for i in 0..data.len() {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            data[i] = (x >> 24) as u8;
+        }
+        // Make sure that all 6-bit values appear in the encodings.
+        data[..48].copy_from_slice(&hex::decode("00108310518720928b30d38f41149351559761969b71d79f8218a39259a7a29aabb2dbafc31cb3d35db7e39ebbf3dfbf").unwrap());
+        for variant in ALL.iter() {
+            let variant = *variant;
+            for n in 0..=data.len() {
+                let r = ref_encode(&data[..n], variant);
+                let elen = encoded_len(n, variant);
+                assert!(elen == r.len());
+                let mut enc = [0u8; 344];
+                assert!(encode(&data[..n], &mut enc, variant) == Ok(elen));
+                assert!(enc[..elen] == r[..]);
+                assert!(encode(&data[..n], &mut enc[..(elen.saturating_sub(1))], variant)
+                    == if elen == 0 { Ok(0) } else { Err(Base64Error::BufferTooSmall) });
+                assert!(decoded_len(&enc[..elen], variant) == Ok(n));
+                let mut dec = [0u8; 257];
+                assert!(decode(&enc[..elen], &mut dec, variant) == Ok(n));
+                assert!(dec[..n] == data[..n]);
+                if n > 0 {
+                    assert!(decode(&enc[..elen], &mut dec[..(n - 1)], variant)
+                        == Err(Base64Error::BufferTooSmall));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bad_input() {
+        use Base64Error::*;
+        const KAT: [(&str, Variant, Base64Error); 17] = [
+            ("Zg=", Variant::Standard, InvalidLength),
+            ("Zg", Variant::Standard, InvalidLength),
+            ("Z", Variant::StandardNoPad, InvalidLength),
+            ("Zm9vY", Variant::UrlSafeNoPad, InvalidLength),
+            ("Zg==", Variant::StandardNoPad, InvalidPadding),
+            ("Zm8=", Variant::UrlSafeNoPad, InvalidPadding),
+            ("Z===", Variant::Standard, InvalidPadding),
+            ("Zm=v", Variant::Standard, InvalidPadding),
+            ("Zg==Zm9v", Variant::Standard, InvalidPadding),
+            ("Zh==", Variant::Standard, NonCanonical),
+            ("Zm9=", Variant::Standard, NonCanonical),
+            ("Zh", Variant::UrlSafeNoPad, NonCanonical),
+            ("Zm9vYmF*", Variant::Standard, BadCharacter),
+            ("Zm9v YmF", Variant::StandardNoPad, BadCharacter),
+            ("+/+/", Variant::UrlSafe, BadCharacter),
+            ("-_-_", Variant::Standard, BadCharacter),
+            ("Zm9vYmF\n", Variant::StandardNoPad, BadCharacter),
+        ];
+        for (s, variant, err) in KAT.iter() {
+            let mut out = [0xAAu8; 8];
+            assert!(decode(s.as_bytes(), &mut out, *variant) == Err(*err));
+            if *err != InvalidLength {
+                let n = decoded_len(s.as_bytes(), *variant).unwrap();
+                assert!(out[..n].iter().all(|&b| b == 0));
+            }
+        }
+
+        // Every character outside of the alphabet is rejected, at every
+        // position.
+        for variant in ALL.iter() {
+            let variant = *variant;
+            let mut good = *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+            if variant == Variant::UrlSafe || variant == Variant::UrlSafeNoPad {
+                good[62] = b'-';
+                good[63] = b'_';
+            }
+            for c in 0..=255u8 {
+                let valid = good.contains(&c);
+                for j in 0..8 {
+                    let mut s = *b"AAAAAAAA";
+                    s[j] = c;
+                    let mut out = [0u8; 6];
+                    let r = decode(&s, &mut out, variant);
+                    if valid {
+                        assert!(r == Ok(6));
+                    } else if c == b'=' && j == 7 && variant.padded() {
+                        assert!(r == Ok(5));
+                    } else if c == b'=' {
+                        assert!(r == Err(InvalidPadding));
+                    } else {
+                        assert!(r == Err(BadCharacter));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! BLAKE2b hash function (RFC 7693).
+//!
+//! `Blake2b` is an unkeyed BLAKE2b context with a configurable output
+//! length (1 to 64 bytes); `Blake2b512` is a convenience wrapper for the
+//! full 64-byte output. This is a portable implementation (no SIMD),
+//! meant for formats that mandate BLAKE2b (e.g. libsodium sealed boxes,
+//! minisign prehashed signatures); for new designs, `blake2s` offers
+//! more options (keys, salt, personalization) and is faster on most
+//! platforms.
+//!
+//! `blake2b_derive_subkey()` implements libsodium's key derivation
+//! function (`crypto_kdf_derive_from_key()`), which uses keyed BLAKE2b
+//! with salt and personalization.
+
+use core::convert::TryFrom;
+
+/// BLAKE2b context (unkeyed).
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2b {
+    h: [u64; 8],
+    buf: [u8; BUF_LEN],
+    buf_len: usize,
+    ctr: u128,
+    out_len: usize,
+}
+
+/// Convenience wrapper for BLAKE2b (unkeyed) with a 512-bit output.
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2b512(Blake2b);
+
+/// Error type for hash function parameters that are out of the supported
+/// range (e.g. when they come from an untrusted source).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashError {
+    /// The requested output length is not supported.
+    BadOutputLength,
+}
+
+impl core::fmt::Display for HashError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HashError::BadOutputLength => f.write_str("bad output length"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HashError {}
+
+const BUF_LEN: usize = 128;
+
+const IV: [u64; 8] = [
+    0x6A09E667F3BCC908, 0xBB67AE8584CAA73B,
+    0x3C6EF372FE94F82B, 0xA54FF53A5F1D36F1,
+    0x510E527FADE682D1, 0x9B05688C2B3E6C1F,
+    0x1F83D9ABFB41BD6B, 0x5BE0CD19137E2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [  0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15 ],
+    [ 14, 10,  4,  8,  9, 15, 13,  6,  1, 12,  0,  2, 11,  7,  5,  3 ],
+    [ 11,  8, 12,  0,  5,  2, 15, 13, 10, 14,  3,  6,  7,  1,  9,  4 ],
+    [  7,  9,  3,  1, 13, 12, 11, 14,  2,  6,  5, 10,  4,  0, 15,  8 ],
+    [  9,  0,  5,  7,  2,  4, 10, 15, 14,  1, 11, 12,  6,  8,  3, 13 ],
+    [  2, 12,  6, 10,  0, 11,  8,  3,  4, 13,  7,  5, 15, 14,  1,  9 ],
+    [ 12,  5,  1, 15, 14, 13,  4, 10,  0,  7,  6,  3,  9,  2,  8, 11 ],
+    [ 13, 11,  7, 14, 12,  1,  3,  9,  5,  0, 15,  4,  8,  6,  2, 10 ],
+    [  6, 15, 14,  9, 11,  3,  0,  8, 12,  2, 13,  7,  1,  4, 10,  5 ],
+    [ 10,  2,  8,  4,  7,  6,  1,  5, 15, 11,  9, 14,  3, 12, 13,  0 ],
+];
+
+impl Blake2b {
+
+    /// Initialize the context. The output length (in bytes) MUST be
+    /// between 1 and 64 bytes (inclusive); this function panics
+    /// otherwise.
+    pub fn new(out_len: usize) -> Self {
+        assert!((1..=64).contains(&out_len));
+        let mut r = Self {
+            h: [0u64; 8],
+            buf: [0u8; BUF_LEN],
+            buf_len: 0,
+            ctr: 0,
+            out_len,
+        };
+        r.reset();
+        r
+    }
+
+    // Initialize a context with a key (up to 64 bytes), a salt and a
+    // personalization string (RFC 7693, section 2.8). The output length
+    // MUST be between 1 and 64 bytes. The key is buffered as the first
+    // block; the parameters are not kept, so the returned context MUST
+    // NOT be reset.
+    fn with_params(out_len: usize, key: &[u8], salt: &[u8; 16],
+        personal: &[u8; 16]) -> Self
+    {
+        assert!(key.len() <= 64);
+        let mut r = Self::new(out_len);
+        r.h[0] ^= (key.len() as u64) << 8;
+        for i in 0..2 {
+            r.h[4 + i] ^= u64::from_le_bytes(*<&[u8; 8]>::try_from(
+                &salt[(8 * i)..(8 * i + 8)]).unwrap());
+            r.h[6 + i] ^= u64::from_le_bytes(*<&[u8; 8]>::try_from(
+                &personal[(8 * i)..(8 * i + 8)]).unwrap());
+        }
+        if !key.is_empty() {
+            r.buf[..key.len()].copy_from_slice(key);
+            r.buf_len = BUF_LEN;
+        }
+        r
+    }
+
+    /// Reset this context.
+    pub fn reset(&mut self) {
+        self.h = IV;
+        self.h[0] ^= 0x01010000 ^ (self.out_len as u64);
+        self.buf_len = 0;
+        self.ctr = 0;
+    }
+
+    /// Inject some more bytes into the context.
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+        while !data.is_empty() {
+            // The last block must remain buffered, since it is processed
+            // with the "last block" flag upon finalization; thus, a full
+            // buffer is processed only when more data is available.
+            if self.buf_len == BUF_LEN {
+                self.ctr += BUF_LEN as u128;
+                Self::process_block(&mut self.h, &self.buf, self.ctr, false);
+                self.buf_len = 0;
+            }
+            let clen = core::cmp::min(BUF_LEN - self.buf_len, data.len());
+            self.buf[self.buf_len..(self.buf_len + clen)]
+                .copy_from_slice(&data[..clen]);
+            self.buf_len += clen;
+            data = &data[clen..];
+        }
+    }
+
+    /// Finalize this context and get the output. The output (`out_len`
+    /// bytes) is written into the provided slice. The output size is
+    /// returned. The context is not modified.
+    pub fn finalize_write(&self, out: &mut [u8]) -> usize {
+        let mut h = self.h;
+        let mut buf = self.buf;
+        buf[self.buf_len..].copy_from_slice(&[0u8; BUF_LEN][self.buf_len..]);
+        Self::process_block(&mut h, &buf,
+            self.ctr + (self.buf_len as u128), true);
+        let mut tmp = [0u8; 64];
+        for i in 0..8 {
+            tmp[(8 * i)..(8 * i + 8)].copy_from_slice(&h[i].to_le_bytes());
+        }
+        out[..self.out_len].copy_from_slice(&tmp[..self.out_len]);
+        self.out_len
+    }
+
+    /// Finalize this context and get the output. The output (`out_len`
+    /// bytes) is written into the provided slice. The output size is
+    /// returned. The context is automatically reset and can be used for
+    /// a new hashing operation.
+    pub fn finalize_reset_write(&mut self, out: &mut [u8]) -> usize {
+        let r = self.finalize_write(out);
+        self.reset();
+        r
+    }
+
+    /// One-stop function for hashing some input into an output buffer.
+    /// The output length is provided explicitly; the output buffer (`out`)
+    /// may be larger.
+    pub fn hash_into(out_len: usize, data: &[u8], out: &mut [u8]) {
+        let mut sh = Self::new(out_len);
+        sh.update(data);
+        sh.finalize_write(out);
+    }
+
+    // Process one block; `ctr` is the total number of input bytes up to
+    // the end of that block, and `last` is set for the final block.
+    fn process_block(h: &mut [u64; 8], block: &[u8], ctr: u128, last: bool) {
+        let mut m = [0u64; 16];
+        for i in 0..16 {
+            m[i] = u64::from_le_bytes(*<&[u8; 8]>::try_from(
+                &block[(8 * i)..(8 * i + 8)]).unwrap());
+        }
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(&h[..]);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= ctr as u64;
+        v[13] ^= (ctr >> 64) as u64;
+        if last {
+            v[14] = !v[14];
+        }
+
+        macro_rules! g {
+            ($a: expr, $b: expr, $c: expr, $d: expr, $x: expr, $y: expr) => {
+                v[$a] = v[$a].wrapping_add(v[$b]).wrapping_add($x);
+                v[$d] = (v[$d] ^ v[$a]).rotate_right(32);
+                v[$c] = v[$c].wrapping_add(v[$d]);
+                v[$b] = (v[$b] ^ v[$c]).rotate_right(24);
+                v[$a] = v[$a].wrapping_add(v[$b]).wrapping_add($y);
+                v[$d] = (v[$d] ^ v[$a]).rotate_right(16);
+                v[$c] = v[$c].wrapping_add(v[$d]);
+                v[$b] = (v[$b] ^ v[$c]).rotate_right(63);
+            }
+        }
+        for r in 0..12 {
+            let s = &SIGMA[r % 10];
+            g!(0, 4,  8, 12, m[s[ 0]], m[s[ 1]]);
+            g!(1, 5,  9, 13, m[s[ 2]], m[s[ 3]]);
+            g!(2, 6, 10, 14, m[s[ 4]], m[s[ 5]]);
+            g!(3, 7, 11, 15, m[s[ 6]], m[s[ 7]]);
+            g!(0, 5, 10, 15, m[s[ 8]], m[s[ 9]]);
+            g!(1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g!(2, 7,  8, 13, m[s[12]], m[s[13]]);
+            g!(3, 4,  9, 14, m[s[14]], m[s[15]]);
+        }
+        for i in 0..8 {
+            h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+impl Blake2b512 {
+
+    /// Initialize the context.
+    pub fn new() -> Self {
+        Self(Blake2b::new(64))
+    }
+
+    /// Inject some more bytes into the context.
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Finalize this context and get the output. The context is not
+    /// modified.
+    pub fn finalize(&self) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        self.0.finalize_write(&mut out);
+        out
+    }
+
+    /// Finalize this context and get the output. The context is
+    /// automatically reset and can be used for a new hashing operation.
+    pub fn finalize_reset(&mut self) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        self.0.finalize_reset_write(&mut out);
+        out
+    }
+
+    /// One-stop function for hashing some input.
+    pub fn hash(data: &[u8]) -> [u8; 64] {
+        let mut sh = Self::new();
+        sh.update(data);
+        sh.finalize()
+    }
+}
+
+impl Default for Blake2b512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Derive a subkey from a master key, as libsodium's
+/// `crypto_kdf_derive_from_key()`: the subkey is the keyed BLAKE2b hash
+/// (with `master` as key) of the empty input, with salt set to the
+/// subkey identifier (`subkey_id`, over 8 bytes, little-endian, followed
+/// by 8 zeros) and personalization set to the context string (`ctx`,
+/// followed by 8 zeros). The subkey length is the length of the `subkey`
+/// slice, and must be between 16 and 64 bytes (inclusive); otherwise, an
+/// error is returned and `subkey` is not modified.
+pub fn blake2b_derive_subkey(subkey: &mut [u8], subkey_id: u64,
+    ctx: &[u8; 8], master: &[u8; 32]) -> Result<(), HashError>
+{
+    if !(16..=64).contains(&subkey.len()) {
+        return Err(HashError::BadOutputLength);
+    }
+    let mut salt = [0u8; 16];
+    salt[..8].copy_from_slice(&subkey_id.to_le_bytes());
+    let mut personal = [0u8; 16];
+    personal[..8].copy_from_slice(ctx);
+    let sh = Blake2b::with_params(subkey.len(), master, &salt, &personal);
+    sh.finalize_write(subkey);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Blake2b, Blake2b512, HashError, blake2b_derive_subkey};
+    use crate::Vec;
+
+    // Each entry is: input length, output length, output. The input is
+    // the sequence of bytes (7*i + 3) mod 256. Outputs were computed
+    // with Python's hashlib.
+    const KAT: [(usize, usize, &str); 10] = [
+        (0, 64, "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"),
+        (3, 64, "76ce7e90c577d436a65b691e51f0a1ad536130d70a18df3bf5368551327258b6f706f4a47c45f2d0d8147cfbb5623077e798247972e4f9338603a13abc05a4db"),
+        (127, 64, "71546bbf9110ad184cc60f2eb120fcfd9b4dbbca7a7f1270045b8a23a6a4f4330f65c1f030dd2f5fabc6c57617242c37cf427bd90407fac5b9deffd3ae888c39"),
+        (128, 64, "2d9e329f42afa3601d646692b81c13e87fcaff5bf15972e9813d7373cb6d181f9599f4d513d4af4fd6ebd37497aceb29aba5ee23ed764d8510b552bd088814fb"),
+        (129, 64, "47889df9eb4d717afc5019df5c6a83df00a0b8677395e078cd5778ace0f338a618e68b7d9afb065d9e6a01ccd31d109447e7fae771c3ee3e105709194122ba2b"),
+        (256, 64, "91019c558584980249ca43eceed27e19f1c3c24161b93eed1eee2a6a774f60bf8a81b43750870bee1698feac9c5336ae4d5c842e7ead159bf3916387e8ded9ae"),
+        (1000, 64, "4bdd2c9cf31d797a81d245c989ffb7515143ca345c66f73087dd5c58bf642bf083ba16894eab79e3b08d5126404d833e7510271b50be36a7b7cbbb46f5c89fac"),
+        (0, 32, "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"),
+        (200, 24, "dcef2c55bfb4852adc50cb295b103b726c21bf2d4677bf58"),
+        (300, 1, "a1"),
+    ];
+
+    #[test]
+    fn blake2b() {
+        // Test vector from RFC 7693, appendix A.
+        let ref_abc = hex::decode("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap();
+        assert!(Blake2b512::hash(b"abc")[..] == ref_abc[..]);
+
+        for (len, out_len, rh) in KAT.iter() {
+            let data: Vec<u8> = (0..*len).map(|i| (7 * i + 3) as u8).collect();
+            let rv = hex::decode(rh).unwrap();
+            let mut out = [0u8; 64];
+            Blake2b::hash_into(*out_len, &data, &mut out);
+            assert!(out[..*out_len] == rv[..]);
+
+            // Same computation with data injected by chunks of varying
+            // lengths; the context is reused after a reset.
+            let mut sh = Blake2b::new(*out_len);
+            for step in [1, 5, 64, 127, 128, 129].iter() {
+                for chunk in data.chunks(*step) {
+                    sh.update(chunk);
+                }
+                let mut out = [0u8; 64];
+                assert!(sh.finalize_reset_write(&mut out) == *out_len);
+                assert!(out[..*out_len] == rv[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn derive_subkey() {
+        // Reference values computed with Python's hashlib (keyed BLAKE2b
+        // with salt = LE64(subkey_id) || 0^8, personal = ctx || 0^8,
+        // key = master, empty input), which is what libsodium's
+        // crypto_kdf_derive_from_key() computes. The master key is the
+        // one of libsodium's kdf test (bytes 0 to 31).
+        const KAT_KDF: [(u64, &[u8; 8], &str); 6] = [
+            (0, b"Examples",
+             "367bf6fa5adbb0033ce5950a86f1ad56e8f680d8f5786df7652eeb0824c75c66a332d2ac8f1d45749877724c67eff1290ffcb2de5ad9373df1d43e30296dca8d"),
+            (1, b"Examples",
+             "16169dc6ca3f6bbfe8beba85a97846f7"),
+            (42, b"crrl-kdf",
+             "ae3bffca3ca823fff9f0d9eeae4e6addfaf67a1c85491859"),
+            (0xFFFFFFFFFFFFFFFF, &[0u8; 8],
+             "ddb4c4efa38e7444d013401e976bd7cfe9a9ee8a013120c4bbd8b925c8d8df74"),
+            (0x0123456789ABCDEF, b"KDF-test",
+             "092743153569e45d50681160ec8b93c560fdbf228107b8af95d0dc8dcec960d97b847d7aafdfd10e"),
+            (7, b"KDF test",
+             "21fb32a79af77a85dbf5f3ff166f1fc9d975160b5a92d03cd11d50b207e6cd7036a912141a088670665324eca48b5164"),
+        ];
+        let mut master = [0u8; 32];
+        for i in 0..32 {
+            master[i] = i as u8;
+        }
+        for (id, ctx, refout) in KAT_KDF.iter() {
+            let refout = hex::decode(refout).unwrap();
+            let mut subkey = [0u8; 64];
+            let subkey = &mut subkey[..refout.len()];
+            blake2b_derive_subkey(subkey, *id, ctx, &master).unwrap();
+            assert!(subkey == &refout[..]);
+        }
+
+        let mut subkey = [0u8; 65];
+        assert!(blake2b_derive_subkey(&mut subkey[..15], 0, b"Examples",
+            &master).err() == Some(HashError::BadOutputLength));
+        assert!(blake2b_derive_subkey(&mut subkey[..], 0, b"Examples",
+            &master).err() == Some(HashError::BadOutputLength));
+        assert!(subkey == [0u8; 65]);
+    }
+}
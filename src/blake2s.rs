@@ -5,17 +5,21 @@
 use core::convert::TryFrom;
 
 /// BLAKE2s context (unkeyed).
+#[derive(Clone, Copy, Debug)]
 #[repr(align(32))]
 pub struct Blake2s {
     h: [u32; 8],
     buf: [u8; BUF_LEN],
     ctr: u64,
     out_len: usize,
+    salt_personal: [u32; 4],
 }
 
 /// BLAKE2s context (with a key). The key is saved internally, so that
 /// multiple successive hashing operations can be performed with the same
-/// context without reinjecting the key each time.
+/// context without reinjecting the key each time. The type is not `Copy`,
+/// so that duplicating the key material requires an explicit `clone()`.
+#[derive(Clone)]
 #[repr(align(32))]
 pub struct KeyedBlake2s {
     ctx: Blake2s,
@@ -23,11 +27,44 @@ pub struct KeyedBlake2s {
     saved_key_len: usize,
 }
 
+// The debug output of a keyed context shows only the output length; the
+// saved key and the internal buffer (which may contain the key) are redacted.
+impl core::fmt::Debug for KeyedBlake2s {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KeyedBlake2s")
+            .field("out_len", &self.ctx.out_len)
+            .finish_non_exhaustive()
+    }
+}
+
 const BUF_LEN: usize = 64;
 
+/// Error type for hash function parameters that are out of the supported
+/// range (e.g. when they come from an untrusted source).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashError {
+    /// The requested output length is not supported.
+    BadOutputLength,
+    /// The provided key length is not supported.
+    BadKeyLength,
+}
+
+impl core::fmt::Display for HashError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HashError::BadOutputLength => f.write_str("bad output length"),
+            HashError::BadKeyLength => f.write_str("bad key length"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HashError {}
+
 /// Convenience wrapper for BLAKE2s (unkeyed) with a 256-bit output, which
 /// is the most common combination. That wrapper offers finalization functions
 /// that return the computed output as a fixed-size 32-byte array.
+#[derive(Clone, Copy, Debug)]
 pub struct Blake2s256(Blake2s);
 
 impl Blake2s256 {
@@ -44,6 +81,13 @@ impl Blake2s256 {
         self.0.update(data);
     }
 
+    /// Inject several chunks of data into the context; this is
+    /// equivalent to calling `update()` on each chunk in order.
+    #[inline(always)]
+    pub fn update_vectored(&mut self, parts: &[&[u8]]) {
+        self.0.update_vectored(parts);
+    }
+
     /// Finalize the current computation and get a 32-byte output.
     /// The context MUST NOT be used afterwards without first resetting it.
     #[inline(always)]
@@ -83,6 +127,15 @@ impl Blake2s256 {
         sh.update(data);
         sh.finalize()
     }
+
+    /// One-stop function for hashing the concatenation of several chunks
+    /// of input into a 32-byte output.
+    #[inline(always)]
+    pub fn hash_vectored(parts: &[&[u8]]) -> [u8; 32] {
+        let mut sh = Self::new();
+        sh.update_vectored(parts);
+        sh.finalize()
+    }
 }
 
 impl KeyedBlake2s {
@@ -90,19 +143,41 @@ impl KeyedBlake2s {
     /// Initialize the context. The output length (in bytes) must be
     /// between 1 and 32. The key length must be between 0 and 32 bytes;
     /// if the key has length 0, then this is equivalent to unkeyed
-    /// hashing.
+    /// hashing. This function panics if either length is out of range;
+    /// see `try_new()` for a non-panicking version.
     pub fn new(out_len: usize, key: &[u8]) -> Self {
-        assert!(key.len() <= 32);
-        let mut ctx = Blake2s::new(out_len);
+        match Self::try_new(out_len, key) {
+            Ok(ctx) => ctx,
+            Err(e) => panic!("KeyedBlake2s::new(): {}", e),
+        }
+    }
+
+    /// Initialize the context. The output length (in bytes) must be
+    /// between 1 and 32, and the key length between 0 and 32 bytes;
+    /// otherwise, an error is returned (this function never panics).
+    #[inline]
+    pub fn try_new(out_len: usize, key: &[u8]) -> Result<Self, HashError> {
+        Self::try_new_with_params(out_len, key, &[0u8; 8], &[0u8; 8])
+    }
+
+    /// Initialize the context with an explicit salt and personalization
+    /// string (both of 8 bytes, as defined in RFC 7693, section 2.8).
+    /// The output length (in bytes) must be between 1 and 32, and the
+    /// key length between 0 and 32 bytes; otherwise, an error is returned.
+    /// Salt and personalization are kept when the context is reset.
+    pub fn try_new_with_params(out_len: usize, key: &[u8],
+        salt: &[u8; 8], personal: &[u8; 8]) -> Result<Self, HashError>
+    {
+        if key.len() > 32 {
+            return Err(HashError::BadKeyLength);
+        }
+        let ctx = Blake2s::try_new_with_params(out_len, salt, personal)?;
         let mut saved_key = [0u8; 32];
         let saved_key_len = key.len();
-        if saved_key_len > 0 {
-            ctx.h[0] ^= (saved_key_len as u32) << 8;
-            saved_key[..saved_key_len].copy_from_slice(key);
-            ctx.buf[..saved_key_len].copy_from_slice(key);
-            ctx.ctr = BUF_LEN as u64;
-        }
-        Self { ctx, saved_key, saved_key_len }
+        saved_key[..saved_key_len].copy_from_slice(key);
+        let mut r = Self { ctx, saved_key, saved_key_len };
+        r.reinject_key();
+        Ok(r)
     }
 
     /// Inject some more bytes into the context.
@@ -111,13 +186,35 @@ impl KeyedBlake2s {
         self.ctx.update(data);
     }
 
+    /// Inject several chunks of data into the context; this is
+    /// equivalent to calling `update()` on each chunk in order.
+    #[inline(always)]
+    pub fn update_vectored(&mut self, parts: &[&[u8]]) {
+        self.ctx.update_vectored(parts);
+    }
+
     /// Reset this context.
     #[inline]
     pub fn reset(&mut self) {
         self.ctx.reset();
+        self.reinject_key();
+    }
+
+    /// Reset this context, with a new output length (in bytes), which
+    /// MUST be between 1 and 32 (inclusive). The saved key is kept.
+    /// This function panics if the output length is out of range.
+    pub fn reset_with_out_len(&mut self, out_len: usize) {
+        self.ctx.reset_with_out_len(out_len);
+        self.reinject_key();
+    }
+
+    // Inject the saved key (if any) into a freshly reset context.
+    #[inline]
+    fn reinject_key(&mut self) {
         if self.saved_key_len > 0 {
             self.ctx.h[0] ^= (self.saved_key_len as u32) << 8;
-            self.ctx.buf[..self.saved_key_len].copy_from_slice(&self.saved_key);
+            self.ctx.buf[..self.saved_key_len].copy_from_slice(
+                &self.saved_key[..self.saved_key_len]);
             self.ctx.ctr = BUF_LEN as u64;
         }
     }
@@ -160,17 +257,50 @@ impl Blake2s {
     ];
 
     /// Initialize the context. The output length (in bytes) MUST be
-    /// between 1 and 32 bytes (inclusive).
+    /// between 1 and 32 bytes (inclusive); this function panics otherwise
+    /// (see `try_new()` for a non-panicking version).
     pub fn new(out_len: usize) -> Self {
-        assert!(1 <= out_len && out_len <= 32);
-        let mut h = Self::IV;
-        h[0] ^= 0x01010000 ^ (out_len as u32);
-        Self {
-            h: h,
+        match Self::try_new(out_len) {
+            Ok(ctx) => ctx,
+            Err(e) => panic!("Blake2s::new(): {}", e),
+        }
+    }
+
+    /// Initialize the context. If the output length (in bytes) is not
+    /// between 1 and 32 bytes (inclusive), then an error is returned
+    /// (this function never panics).
+    #[inline]
+    pub fn try_new(out_len: usize) -> Result<Self, HashError> {
+        Self::try_new_with_params(out_len, &[0u8; 8], &[0u8; 8])
+    }
+
+    /// Initialize the context with an explicit salt and personalization
+    /// string (both of 8 bytes, as defined in RFC 7693, section 2.8).
+    /// If the output length (in bytes) is not between 1 and 32 bytes
+    /// (inclusive), then an error is returned. Salt and personalization
+    /// are kept when the context is reset.
+    pub fn try_new_with_params(out_len: usize,
+        salt: &[u8; 8], personal: &[u8; 8]) -> Result<Self, HashError>
+    {
+        if !(1..=32).contains(&out_len) {
+            return Err(HashError::BadOutputLength);
+        }
+        let mut salt_personal = [0u32; 4];
+        for i in 0..2 {
+            salt_personal[i] = u32::from_le_bytes(*<&[u8; 4]>::try_from(
+                &salt[(4 * i)..(4 * i + 4)]).unwrap());
+            salt_personal[i + 2] = u32::from_le_bytes(*<&[u8; 4]>::try_from(
+                &personal[(4 * i)..(4 * i + 4)]).unwrap());
+        }
+        let mut r = Self {
+            h: [0u32; 8],
             buf: [0u8; BUF_LEN],
             ctr: 0,
-            out_len: out_len,
-        }
+            out_len,
+            salt_personal,
+        };
+        r.reset();
+        Ok(r)
     }
 
     /// Inject some more bytes into the context.
@@ -215,11 +345,65 @@ impl Blake2s {
         }
     }
 
+    /// Inject several chunks of data into the context; this is
+    /// equivalent to calling `update()` on each chunk in order, but
+    /// since the total length is known in advance, full blocks are
+    /// processed as soon as they are complete (directly from the input
+    /// chunks whenever possible).
+    pub fn update_vectored(&mut self, parts: &[&[u8]]) {
+        assert!(self.ctr != !0u64);
+
+        // Remaining input length. The last block of the whole input
+        // (even if full) must remain buffered, since it will be processed
+        // with the "last block" flag upon finalization.
+        let mut rem: usize = parts.iter().map(|d| d.len()).sum();
+
+        // Number of bytes currently in the buffer (0 to 64).
+        let p = (self.ctr as usize) & (BUF_LEN - 1);
+        let mut bp = if self.ctr != 0 && p == 0 { BUF_LEN } else { p };
+
+        for part in parts {
+            let mut d: &[u8] = part;
+            while !d.is_empty() {
+                if bp == BUF_LEN {
+                    Self::process_block(&mut self.h, &self.buf, self.ctr, false);
+                    bp = 0;
+                }
+                if bp == 0 && d.len() >= BUF_LEN && rem > BUF_LEN {
+                    self.ctr += BUF_LEN as u64;
+                    Self::process_block(&mut self.h, &d[..BUF_LEN],
+                        self.ctr, false);
+                    d = &d[BUF_LEN..];
+                    rem -= BUF_LEN;
+                    continue;
+                }
+                let clen = core::cmp::min(BUF_LEN - bp, d.len());
+                self.buf[bp..(bp + clen)].copy_from_slice(&d[..clen]);
+                self.ctr += clen as u64;
+                bp += clen;
+                d = &d[clen..];
+                rem -= clen;
+            }
+        }
+    }
+
+    /// Reset this context, with a new output length (in bytes), which
+    /// MUST be between 1 and 32 (inclusive). This function panics if the
+    /// output length is out of range.
+    pub fn reset_with_out_len(&mut self, out_len: usize) {
+        assert!((1..=32).contains(&out_len));
+        self.out_len = out_len;
+        self.reset();
+    }
+
     /// Reset this context.
     #[inline]
     pub fn reset(&mut self) {
         self.h[..].copy_from_slice(&Self::IV);
         self.h[0] ^= 0x01010000 ^ (self.out_len as u32);
+        for i in 0..4 {
+            self.h[i + 4] ^= self.salt_personal[i];
+        }
         self.buf[..].copy_from_slice(&[0u8; BUF_LEN]);
         self.ctr = 0;
     }
@@ -913,10 +1097,121 @@ impl Blake2s {
     }
 }
 
+/// Domain-separated hashing helper, built on BLAKE2s.
+///
+/// This is meant for building protocol hashes (e.g. challenges in
+/// signature schemes) out of a sequence of values, with an unambiguous
+/// encoding. The encoding is fixed and will not change:
+///
+///  - BLAKE2s is used with a 32-byte output, no key, and the
+///    personalization string "crrl-dom".
+///
+///  - The domain label is injected first, as its length (in bytes,
+///    over 8 bytes, little-endian) followed by the label itself.
+///
+///  - `append_bytes()` injects the length of the data (over 8 bytes,
+///    little-endian) followed by the data; `append_u64_le()` injects
+///    the value over exactly 8 bytes (little-endian); `append_point()`
+///    injects the 32 bytes as is (the length is implied by the type).
+///
+///  - On finalization, a single tag byte is injected: 0x00 for a
+///    32-byte output (`finalize()`). For a 64-byte output
+///    (`finalize_scalar_bytes()`), two 32-byte outputs are computed
+///    from the same state, with tag bytes 0x01 and 0x02, and
+///    concatenated.
+///
+/// Since fixed-size values (`append_u64_le()` and `append_point()`) are
+/// not length-prefixed, the sequence and types of appended values must
+/// be fixed by the protocol that uses this helper.
+///
+/// Existing formats that hash with BLAKE2s (in particular the jq255e and
+/// jq255s signature challenges) deliberately keep using plain,
+/// unpersonalized BLAKE2s: switching them to this helper would change
+/// their outputs and break compatibility with already produced
+/// signatures. This helper is meant for new protocols only.
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2sDomain(Blake2s);
+
+impl Blake2sDomain {
+
+    const PERSONAL: [u8; 8] = *b"crrl-dom";
+
+    /// Create a new context with the provided domain label.
+    pub fn new(label: &'static [u8]) -> Self {
+        let mut r = Self(Blake2s::try_new_with_params(
+            32, &[0u8; 8], &Self::PERSONAL).unwrap());
+        r.append_bytes(label);
+        r
+    }
+
+    /// Append a byte string of arbitrary length (length-prefixed).
+    #[inline]
+    pub fn append_bytes(&mut self, data: &[u8]) {
+        self.0.update(&(data.len() as u64).to_le_bytes());
+        self.0.update(data);
+    }
+
+    /// Append a 64-bit integer (over 8 bytes, little-endian).
+    #[inline]
+    pub fn append_u64_le(&mut self, x: u64) {
+        self.0.update(&x.to_le_bytes());
+    }
+
+    /// Append an encoded point (or any other 32-byte value).
+    #[inline]
+    pub fn append_point(&mut self, pt: &[u8; 32]) {
+        self.0.update(pt);
+    }
+
+    /// Finalize the computation and get a 32-byte output.
+    pub fn finalize(mut self) -> [u8; 32] {
+        self.0.update(&[0x00]);
+        self.0.inner_finalize()
+    }
+
+    /// Finalize the computation and get a 64-byte output, suitable for
+    /// wide reduction into a scalar (e.g. with `Scalar::decode_reduce()`)
+    /// with negligible bias.
+    pub fn finalize_scalar_bytes(self) -> [u8; 64] {
+        let mut r = [0u8; 64];
+        let mut sh = self.0;
+        sh.update(&[0x01]);
+        r[..32].copy_from_slice(&sh.inner_finalize());
+        let mut sh = self.0;
+        sh.update(&[0x02]);
+        r[32..].copy_from_slice(&sh.inner_finalize());
+        r
+    }
+}
+
+/// Derive a subkey from a master key, with the same semantics as
+/// libsodium's `crypto_kdf_derive_from_key()`, but using BLAKE2s instead
+/// of BLAKE2b: the subkey is the keyed BLAKE2s hash (with `master` as
+/// key) of the empty input, with salt set to the subkey identifier
+/// (`subkey_id`, over 8 bytes, little-endian) and personalization set
+/// to the context string (`ctx`). The subkey length is the length of
+/// the `subkey` slice, and must be between 16 and 32 bytes (inclusive);
+/// otherwise, an error is returned and `subkey` is not modified. The
+/// libsodium-compatible BLAKE2b version, with subkeys up to 64 bytes, is
+/// `blake2b::blake2b_derive_subkey()`.
+pub fn blake2s_derive_subkey(subkey: &mut [u8], subkey_id: u64,
+    ctx: &[u8; 8], master: &[u8; 32]) -> Result<(), HashError>
+{
+    if !(16..=32).contains(&subkey.len()) {
+        return Err(HashError::BadOutputLength);
+    }
+    let mut sh = KeyedBlake2s::try_new_with_params(subkey.len(), master,
+        &subkey_id.to_le_bytes(), ctx)?;
+    sh.finalize_write(subkey);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::{Blake2s256, Blake2s, KeyedBlake2s};
+    use super::{Blake2s256, Blake2s, KeyedBlake2s, HashError};
+    use super::{blake2s_derive_subkey, Blake2sDomain};
+    use crate::Vec;
 
     static KAT_BLAKE2S: [[&str; 3]; 257] = [
         // Each group of three values is:
@@ -2029,4 +2324,221 @@ mod tests {
 
         assert!(ctx.finalize() == BLAKE2S_RES);
     }
+
+    #[test]
+    fn try_new() {
+        assert!(Blake2s::try_new(0).err() == Some(HashError::BadOutputLength));
+        assert!(Blake2s::try_new(33).err() == Some(HashError::BadOutputLength));
+        let key = [0x5Au8; 33];
+        assert!(KeyedBlake2s::try_new(0, &key[..32]).err()
+            == Some(HashError::BadOutputLength));
+        assert!(KeyedBlake2s::try_new(33, &key[..32]).err()
+            == Some(HashError::BadOutputLength));
+        assert!(KeyedBlake2s::try_new(32, &key).err()
+            == Some(HashError::BadKeyLength));
+
+        let data = b"try_new";
+        for out_len in [1, 32] {
+            let mut buf1 = [0u8; 32];
+            let mut buf2 = [0u8; 32];
+            let mut sh = Blake2s::try_new(out_len).unwrap();
+            sh.update(data);
+            assert!(sh.finalize_write(&mut buf1) == out_len);
+            Blake2s::hash_into(out_len, data, &mut buf2);
+            assert!(buf1 == buf2);
+
+            for key_len in [0, 1, 32] {
+                let mut buf1 = [0u8; 32];
+                let mut buf2 = [0u8; 32];
+                let mut sh = KeyedBlake2s::try_new(
+                    out_len, &key[..key_len]).unwrap();
+                sh.update(data);
+                assert!(sh.finalize_write(&mut buf1) == out_len);
+                KeyedBlake2s::hash_into(out_len, &key[..key_len],
+                    data, &mut buf2);
+                assert!(buf1 == buf2);
+            }
+        }
+    }
+
+    #[test]
+    fn reset_with_out_len() {
+        let data = b"reset_with_out_len";
+        let key = [0xA5u8; 32];
+        let mut sh = Blake2s::new(32);
+        for out_len in [17, 1, 32, 20] {
+            let mut buf1 = [0u8; 32];
+            let mut buf2 = [0u8; 32];
+            sh.update(b"garbage");
+            sh.reset_with_out_len(out_len);
+            sh.update(data);
+            assert!(sh.finalize_reset_write(&mut buf1) == out_len);
+            let mut sh2 = Blake2s::new(out_len);
+            sh2.update(data);
+            sh2.finalize_write(&mut buf2);
+            assert!(buf1 == buf2);
+        }
+
+        for key_len in [0, 1, 16, 32] {
+            let mut sh = KeyedBlake2s::new(32, &key[..key_len]);
+            for out_len in [17, 1, 32, 20] {
+                let mut buf1 = [0u8; 32];
+                let mut buf2 = [0u8; 32];
+                sh.update(b"garbage");
+                sh.reset_with_out_len(out_len);
+                sh.update(data);
+                assert!(sh.finalize_reset_write(&mut buf1) == out_len);
+                KeyedBlake2s::hash_into(out_len, &key[..key_len],
+                    data, &mut buf2);
+                assert!(buf1 == buf2);
+
+                // Plain reset() keeps the new length.
+                sh.update(data);
+                assert!(sh.finalize_reset_write(&mut buf1) == out_len);
+                assert!(buf1 == buf2);
+            }
+        }
+    }
+
+    #[test]
+    fn update_vectored() {
+        let mut data = [0u8; 300];
+        for i in 0..data.len() {
+            data[i] = (i * 11 + 3) as u8;
+        }
+        let key = [0x33u8; 20];
+        let mut x = 0x2545F491u32;
+        for n in 0..data.len() {
+            let mut ref1 = [0u8; 32];
+            let mut ref2 = [0u8; 32];
+            for start in [0, 1, 63, 64, 65] {
+                let start = core::cmp::min(start, n);
+                Blake2s::hash_into(32, &data[..n], &mut ref1);
+                KeyedBlake2s::hash_into(24, &key, &data[..n], &mut ref2);
+                for _ in 0..4 {
+                    // Random partition of data[start..n], with
+                    // interleaved empty slices.
+                    let mut parts: Vec<&[u8]> = Vec::new();
+                    let mut j = start;
+                    while j < n {
+                        x ^= x << 13;
+                        x ^= x >> 17;
+                        x ^= x << 5;
+                        let clen = core::cmp::min(
+                            (x % 150) as usize, n - j);
+                        parts.push(&data[j..(j + clen)]);
+                        if (x & 0x10000) != 0 {
+                            parts.push(&[]);
+                        }
+                        j += clen;
+                    }
+
+                    let mut buf = [0u8; 32];
+                    let mut sh = Blake2s::new(32);
+                    sh.update(&data[..start]);
+                    sh.update_vectored(&parts);
+                    sh.finalize_write(&mut buf);
+                    assert!(buf == ref1);
+
+                    let mut sh = KeyedBlake2s::new(24, &key);
+                    sh.update(&data[..start]);
+                    sh.update_vectored(&parts);
+                    assert!(sh.finalize_write(&mut buf) == 24);
+                    assert!(buf[..24] == ref2[..24]);
+
+                    if start == 0 {
+                        assert!(Blake2s256::hash_vectored(&parts) == ref1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn derive_subkey() {
+        // Reference values computed with the BLAKE2 reference
+        // implementation (salt = LE64(subkey_id), personal = ctx,
+        // key = master, empty input), as done by libsodium's
+        // crypto_kdf_derive_from_key() with BLAKE2b.
+        const KAT_KDF: [(u64, &[u8; 8], &str); 5] = [
+            (0, b"Examples",
+             "657ff27a224795b8a21ed255f8b6a09aceb236882712d65fa39822ab13eb958f"),
+            (1, b"Examples",
+             "97822c96eded2f3d3ddc519dce273b7e"),
+            (42, b"crrl-kdf",
+             "4464cd89bc09612142608885981768ce40477333ce7e6222"),
+            (0xFFFFFFFFFFFFFFFF, &[0u8; 8],
+             "79ae43d8135923424fb08002d6bceee5492a662d9301b69b16687c9db6f0d878"),
+            (0x0123456789ABCDEF, b"KDF-test",
+             "17c8cd1709f9e4771c524459d0dd912322f70267"),
+        ];
+        let mut master = [0u8; 32];
+        for i in 0..32 {
+            master[i] = i as u8;
+        }
+        for (id, ctx, refout) in KAT_KDF.iter() {
+            let refout = hex::decode(refout).unwrap();
+            let mut subkey = [0u8; 32];
+            let subkey = &mut subkey[..refout.len()];
+            blake2s_derive_subkey(subkey, *id, ctx, &master).unwrap();
+            assert!(subkey == &refout[..]);
+        }
+
+        let mut subkey = [0u8; 33];
+        assert!(blake2s_derive_subkey(&mut subkey[..15], 0, b"Examples",
+            &master).err() == Some(HashError::BadOutputLength));
+        assert!(blake2s_derive_subkey(&mut subkey[..], 0, b"Examples",
+            &master).err() == Some(HashError::BadOutputLength));
+        assert!(subkey == [0u8; 33]);
+
+        // Salt and personalization must survive a reset.
+        let mut sh = KeyedBlake2s::try_new_with_params(32, &master,
+            &0u64.to_le_bytes(), b"Examples").unwrap();
+        let mut buf = [0u8; 32];
+        sh.update(b"garbage");
+        sh.reset();
+        sh.finalize_write(&mut buf);
+        assert!(buf[..] == hex::decode(KAT_KDF[0].2).unwrap()[..]);
+    }
+
+    #[test]
+    fn domain() {
+        // Golden values: these pin the encoding, which must not change.
+        let d = Blake2sDomain::new(b"crrl-test");
+        assert!(d.finalize()[..] == hex::decode("fa843813ce695a9abc386684b5574b3afe272d1179f9a9eb7a9d218478fcad73").unwrap()[..]);
+        assert!(d.finalize_scalar_bytes()[..] == hex::decode("117753ebbc1d661a517fc22fe7ced914540db3b1b5f8e95e857646d912b6667763486c2108c02ebc2f23ad2bd5d9c2b68e7cd46d441aa926592b5bfd3e90e59d").unwrap()[..]);
+
+        let mut pt = [0u8; 32];
+        for i in 0..32 {
+            pt[i] = i as u8;
+        }
+        let mut d = Blake2sDomain::new(b"crrl-test");
+        d.append_bytes(b"");
+        d.append_u64_le(0x0123456789ABCDEF);
+        d.append_point(&pt);
+        d.append_bytes(b"message");
+        assert!(d.finalize()[..] == hex::decode("d766cd61592342a163fc1364a6ada8f36696b10d8d3ea3f12ed2b294cd103e2a").unwrap()[..]);
+        assert!(d.finalize_scalar_bytes()[..] == hex::decode("accb75c3c17557af8b83b6b1ba98217772bb200eaea80facc9aad3e7fcd018b8ffd0ddee19d3f353d42ce9d1ff59b6da00a01c93efa08c933bab38bf182d29eb").unwrap()[..]);
+
+        let mut d = Blake2sDomain::new(b"");
+        d.append_bytes(&[b'a'; 100]);
+        assert!(d.finalize()[..] == hex::decode("d548e0b8584d87ce570e342c33cb18f7b1eb9aa9644365e2e62c76fe4f922323").unwrap()[..]);
+        assert!(d.finalize_scalar_bytes()[..] == hex::decode("36c90c140a9232deaacdf2142de5dde9ff89dc6e80f7a49c5b39681971b28a003f6e529a8c06a929fa20238f8e7cca2309a959aae10e6eaa69b0939898ffb242").unwrap()[..]);
+
+        // Length prefixes make these two sequences distinct.
+        let mut d1 = Blake2sDomain::new(b"crrl-test");
+        d1.append_bytes(b"ab");
+        d1.append_bytes(b"c");
+        let mut d2 = Blake2sDomain::new(b"crrl-test");
+        d2.append_bytes(b"a");
+        d2.append_bytes(b"bc");
+        assert!(d1.finalize() != d2.finalize());
+    }
+
+    #[test]
+    fn keyed_debug_redacted() {
+        let sh = KeyedBlake2s::new(32, b"secret MAC key");
+        let dbg = format!("{:?}", sh);
+        assert!(dbg == "KeyedBlake2s { out_len: 32, .. }");
+    }
 }
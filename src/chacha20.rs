@@ -0,0 +1,773 @@
+//! ChaCha20 and XChaCha20 stream ciphers.
+//!
+//! `ChaCha20` follows RFC 8439 (32-byte key, 12-byte nonce, 32-bit block
+//! counter). `XChaCha20` uses 24-byte nonces, as described in
+//! draft-irtf-cfrg-xchacha: a subkey is derived from the key and the
+//! first 16 bytes of the nonce with HChaCha20, and then used with
+//! ChaCha20 and the remaining 8 bytes of the nonce.
+//!
+//! The keystream position can be set explicitly (`seek()`), so that
+//! any part of the stream may be processed independently. The total
+//! keystream length for a given key and nonce is limited to 2^32 blocks
+//! of 64 bytes (256 GiB); trying to go beyond that limit triggers a
+//! panic (the block counter is never allowed to wrap around).
+//!
+//! On x86_64, four blocks are computed in parallel with SSE2 (with SSSE3
+//! byte shuffles for some rotations when the target supports AVX2). A
+//! portable implementation is used on other architectures.
+//!
+//! These are raw stream ciphers, without any authentication; they must
+//! be combined with a MAC (or used within an AEAD construction) to
+//! protect against alterations.
+//!
+//! `ChaChaRng` is a random generator which outputs the ChaCha20
+//! keystream for a 32-byte seed and a 64-bit stream identifier; it
+//! implements the `RngCore`, `CryptoRng` and `SeedableRng` traits. It
+//! does not allocate, and is meant to expand a single seed (e.g. from a
+//! hardware source) into independent streams for nonces, blinding
+//! values and key generation.
+
+use core::convert::TryFrom;
+use crate::{CryptoRng, RngCore, RngError, SeedableRng};
+
+/// ChaCha20 stream cipher context (RFC 8439).
+#[derive(Clone, Copy, Debug)]
+pub struct ChaCha20 {
+    key: [u32; 8],
+    nonce: [u32; 3],
+    // Current keystream position (in bytes, from the start of block 0).
+    pos: u64,
+    // Buffered keystream block (index `buf_block`, or none if
+    // `buf_block` is `u64::MAX`).
+    buf: [u8; 64],
+    buf_block: u64,
+}
+
+/// XChaCha20 stream cipher context (24-byte nonces).
+#[derive(Clone, Copy, Debug)]
+pub struct XChaCha20(ChaCha20);
+
+const SIGMA: [u32; 4] = [ 0x61707865, 0x3320646E, 0x79622D32, 0x6B206574 ];
+
+// Maximum keystream length, in bytes.
+const MAX_POS: u64 = 1u64 << 38;
+
+fn decode_words(src: &[u8], dst: &mut [u32]) {
+    for i in 0..dst.len() {
+        dst[i] = u32::from_le_bytes(*<&[u8; 4]>::try_from(
+            &src[(4 * i)..(4 * i + 4)]).unwrap());
+    }
+}
+
+// Apply the 20 rounds (10 double-rounds) on a state.
+fn rounds(x: &mut [u32; 16]) {
+    macro_rules! qr {
+        ($a: expr, $b: expr, $c: expr, $d: expr) => {
+            x[$a] = x[$a].wrapping_add(x[$b]);
+            x[$d] = (x[$d] ^ x[$a]).rotate_left(16);
+            x[$c] = x[$c].wrapping_add(x[$d]);
+            x[$b] = (x[$b] ^ x[$c]).rotate_left(12);
+            x[$a] = x[$a].wrapping_add(x[$b]);
+            x[$d] = (x[$d] ^ x[$a]).rotate_left(8);
+            x[$c] = x[$c].wrapping_add(x[$d]);
+            x[$b] = (x[$b] ^ x[$c]).rotate_left(7);
+        }
+    }
+    for _ in 0..10 {
+        qr!(0, 4,  8, 12);
+        qr!(1, 5,  9, 13);
+        qr!(2, 6, 10, 14);
+        qr!(3, 7, 11, 15);
+        qr!(0, 5, 10, 15);
+        qr!(1, 6, 11, 12);
+        qr!(2, 7,  8, 13);
+        qr!(3, 4,  9, 14);
+    }
+}
+
+fn init_state(key: &[u32; 8], nonce: &[u32; 3], counter: u32) -> [u32; 16] {
+    let mut st = [0u32; 16];
+    st[..4].copy_from_slice(&SIGMA);
+    st[4..12].copy_from_slice(key);
+    st[12] = counter;
+    st[13..].copy_from_slice(nonce);
+    st
+}
+
+// Compute one keystream block.
+fn block_words(key: &[u32; 8], nonce: &[u32; 3], counter: u32, out: &mut [u8]) {
+    let st = init_state(key, nonce, counter);
+    let mut x = st;
+    rounds(&mut x);
+    for i in 0..16 {
+        out[(4 * i)..(4 * i + 4)].copy_from_slice(
+            &x[i].wrapping_add(st[i]).to_le_bytes());
+    }
+}
+
+// Compute four consecutive keystream blocks (256 bytes). Block counters
+// wrap around (the caller is responsible for not using blocks beyond
+// the limit).
+#[cfg(target_arch = "x86_64")]
+fn block4_words(key: &[u32; 8], nonce: &[u32; 3], counter: u32, out: &mut [u8]) {
+    assert!(out.len() >= 256);
+    unsafe {
+        // x86_64, using SSE2 (and SSSE3 shuffles for the rotations by
+        // 8 and 16 bits, if AVX2 is enabled). Each register contains
+        // one state word, for four blocks.
+        use core::arch::x86_64::*;
+
+        let st = init_state(key, nonce, counter);
+        let mut xs = [_mm_setzero_si128(); 16];
+        for i in 0..16 {
+            xs[i] = _mm_set1_epi32(st[i] as i32);
+        }
+        xs[12] = _mm_add_epi32(xs[12], _mm_setr_epi32(0, 1, 2, 3));
+        let mut x = xs;
+
+        #[cfg(target_feature = "avx2")]
+        let xrol8 = _mm_setr_epi8(
+            3, 0, 1, 2, 7, 4, 5, 6,
+            11, 8, 9, 10, 15, 12, 13, 14);
+        #[cfg(target_feature = "avx2")]
+        let xrol16 = _mm_setr_epi8(
+            2, 3, 0, 1, 6, 7, 4, 5,
+            10, 11, 8, 9, 14, 15, 12, 13);
+
+        macro_rules! rol16 { ($x: expr) => {{
+            #[cfg(target_feature = "avx2")]
+            let y = _mm_shuffle_epi8($x, xrol16);
+            #[cfg(not(target_feature = "avx2"))]
+            let y = _mm_or_si128(_mm_slli_epi32($x, 16), _mm_srli_epi32($x, 16));
+            y
+        }} }
+
+        macro_rules! rol8 { ($x: expr) => {{
+            #[cfg(target_feature = "avx2")]
+            let y = _mm_shuffle_epi8($x, xrol8);
+            #[cfg(not(target_feature = "avx2"))]
+            let y = _mm_or_si128(_mm_slli_epi32($x, 8), _mm_srli_epi32($x, 24));
+            y
+        }} }
+
+        macro_rules! qr {
+            ($a: expr, $b: expr, $c: expr, $d: expr) => {
+                x[$a] = _mm_add_epi32(x[$a], x[$b]);
+                x[$d] = rol16!(_mm_xor_si128(x[$d], x[$a]));
+                x[$c] = _mm_add_epi32(x[$c], x[$d]);
+                let t = _mm_xor_si128(x[$b], x[$c]);
+                x[$b] = _mm_or_si128(_mm_slli_epi32(t, 12), _mm_srli_epi32(t, 20));
+                x[$a] = _mm_add_epi32(x[$a], x[$b]);
+                x[$d] = rol8!(_mm_xor_si128(x[$d], x[$a]));
+                x[$c] = _mm_add_epi32(x[$c], x[$d]);
+                let t = _mm_xor_si128(x[$b], x[$c]);
+                x[$b] = _mm_or_si128(_mm_slli_epi32(t, 7), _mm_srli_epi32(t, 25));
+            }
+        }
+
+        for _ in 0..10 {
+            qr!(0, 4,  8, 12);
+            qr!(1, 5,  9, 13);
+            qr!(2, 6, 10, 14);
+            qr!(3, 7, 11, 15);
+            qr!(0, 5, 10, 15);
+            qr!(1, 6, 11, 12);
+            qr!(2, 7,  8, 13);
+            qr!(3, 4,  9, 14);
+        }
+
+        // Add the initial state, and transpose each group of four
+        // words so that each block is contiguous.
+        let op = out.as_mut_ptr() as *mut __m128i;
+        for i in 0..4 {
+            let y0 = _mm_add_epi32(x[4 * i], xs[4 * i]);
+            let y1 = _mm_add_epi32(x[4 * i + 1], xs[4 * i + 1]);
+            let y2 = _mm_add_epi32(x[4 * i + 2], xs[4 * i + 2]);
+            let y3 = _mm_add_epi32(x[4 * i + 3], xs[4 * i + 3]);
+            let z0 = _mm_unpacklo_epi32(y0, y1);
+            let z1 = _mm_unpackhi_epi32(y0, y1);
+            let z2 = _mm_unpacklo_epi32(y2, y3);
+            let z3 = _mm_unpackhi_epi32(y2, y3);
+            _mm_storeu_si128(op.add(i), _mm_unpacklo_epi64(z0, z2));
+            _mm_storeu_si128(op.add(i + 4), _mm_unpackhi_epi64(z0, z2));
+            _mm_storeu_si128(op.add(i + 8), _mm_unpacklo_epi64(z1, z3));
+            _mm_storeu_si128(op.add(i + 12), _mm_unpackhi_epi64(z1, z3));
+        }
+    }
+}
+
+/// Compute a single ChaCha20 keystream block (64 bytes), for the
+/// provided key, nonce and block counter (RFC 8439, section 2.3).
+pub fn chacha20_block(key: &[u8; 32], nonce: &[u8; 12], counter: u32)
+    -> [u8; 64]
+{
+    let mut kw = [0u32; 8];
+    let mut nw = [0u32; 3];
+    decode_words(key, &mut kw);
+    decode_words(nonce, &mut nw);
+    let mut r = [0u8; 64];
+    block_words(&kw, &nw, counter, &mut r);
+    r
+}
+
+/// HChaCha20 function: derive a 32-byte subkey from a key and a 16-byte
+/// nonce (draft-irtf-cfrg-xchacha, section 2.2).
+pub fn hchacha20(key: &[u8; 32], nonce: &[u8; 16]) -> [u8; 32] {
+    let mut x = [0u32; 16];
+    x[..4].copy_from_slice(&SIGMA);
+    decode_words(key, &mut x[4..12]);
+    decode_words(nonce, &mut x[12..]);
+    rounds(&mut x);
+    let mut r = [0u8; 32];
+    for i in 0..4 {
+        r[(4 * i)..(4 * i + 4)].copy_from_slice(&x[i].to_le_bytes());
+        r[(4 * i + 16)..(4 * i + 20)].copy_from_slice(&x[i + 12].to_le_bytes());
+    }
+    r
+}
+
+impl ChaCha20 {
+
+    /// Create a new context with the provided key, nonce and initial
+    /// block counter. The keystream starts at the beginning of the
+    /// block with the provided counter.
+    pub fn new(key: &[u8; 32], nonce: &[u8; 12], counter: u32) -> Self {
+        let mut kw = [0u32; 8];
+        let mut nw = [0u32; 3];
+        decode_words(key, &mut kw);
+        decode_words(nonce, &mut nw);
+        Self {
+            key: kw,
+            nonce: nw,
+            pos: (counter as u64) << 6,
+            buf: [0u8; 64],
+            buf_block: u64::MAX,
+        }
+    }
+
+    /// Set the keystream position to the start of the block with the
+    /// provided counter.
+    #[inline]
+    pub fn set_counter(&mut self, counter: u32) {
+        self.pos = (counter as u64) << 6;
+    }
+
+    /// Set the keystream position to the provided byte offset, counted
+    /// from the start of the block with counter 0 (i.e. the block
+    /// counter is `pos / 64`). The position cannot exceed 2^38; this
+    /// function panics otherwise.
+    #[inline]
+    pub fn seek(&mut self, pos: u64) {
+        assert!(pos <= MAX_POS);
+        self.pos = pos;
+    }
+
+    /// Get the current keystream position (in bytes, counted from the
+    /// start of the block with counter 0).
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    // XOR keystream bytes from the current block (which is computed
+    // and buffered if needed) into the provided data; the data must
+    // not extend beyond the end of the block.
+    fn xor_buffered(&mut self, data: &mut [u8]) {
+        let blk = self.pos >> 6;
+        if self.buf_block != blk {
+            block_words(&self.key, &self.nonce, blk as u32, &mut self.buf);
+            self.buf_block = blk;
+        }
+        let off = (self.pos as usize) & 63;
+        for (d, k) in data.iter_mut().zip(&self.buf[off..]) {
+            *d ^= *k;
+        }
+        self.pos += data.len() as u64;
+    }
+
+    /// Encrypt or decrypt some data in place, by XORing it with the
+    /// next keystream bytes. The keystream position is advanced by
+    /// the data length. This function panics if the keystream limit
+    /// (2^32 blocks) would be exceeded.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        assert!((data.len() as u64) <= MAX_POS - self.pos);
+        let mut j = 0;
+
+        // Finish the current partial block, if any.
+        let off = (self.pos as usize) & 63;
+        if off != 0 {
+            let clen = core::cmp::min(64 - off, data.len());
+            self.xor_buffered(&mut data[..clen]);
+            j = clen;
+        }
+
+        // Process full blocks, four at a time if possible.
+        #[cfg(target_arch = "x86_64")]
+        while (data.len() - j) >= 256 {
+            let mut ks = [0u8; 256];
+            block4_words(&self.key, &self.nonce, (self.pos >> 6) as u32,
+                &mut ks);
+            for i in 0..256 {
+                data[j + i] ^= ks[i];
+            }
+            self.pos += 256;
+            j += 256;
+        }
+        while (data.len() - j) >= 64 {
+            let mut ks = [0u8; 64];
+            block_words(&self.key, &self.nonce, (self.pos >> 6) as u32,
+                &mut ks);
+            for i in 0..64 {
+                data[j + i] ^= ks[i];
+            }
+            self.pos += 64;
+            j += 64;
+        }
+
+        // Last partial block (buffered for subsequent calls).
+        if j < data.len() {
+            self.xor_buffered(&mut data[j..]);
+        }
+    }
+}
+
+impl XChaCha20 {
+
+    /// Create a new context with the provided key, 24-byte nonce and
+    /// initial block counter.
+    pub fn new(key: &[u8; 32], nonce: &[u8; 24], counter: u32) -> Self {
+        let subkey = hchacha20(key,
+            <&[u8; 16]>::try_from(&nonce[..16]).unwrap());
+        let mut n2 = [0u8; 12];
+        n2[4..].copy_from_slice(&nonce[16..]);
+        Self(ChaCha20::new(&subkey, &n2, counter))
+    }
+
+    /// Set the keystream position to the start of the block with the
+    /// provided counter.
+    #[inline]
+    pub fn set_counter(&mut self, counter: u32) {
+        self.0.set_counter(counter);
+    }
+
+    /// Set the keystream position to the provided byte offset, counted
+    /// from the start of the block with counter 0. The position cannot
+    /// exceed 2^38; this function panics otherwise.
+    #[inline]
+    pub fn seek(&mut self, pos: u64) {
+        self.0.seek(pos);
+    }
+
+    /// Get the current keystream position (in bytes, counted from the
+    /// start of the block with counter 0).
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.0.position()
+    }
+
+    /// Encrypt or decrypt some data in place, by XORing it with the
+    /// next keystream bytes. The keystream position is advanced by
+    /// the data length. This function panics if the keystream limit
+    /// (2^32 blocks) would be exceeded.
+    #[inline]
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        self.0.apply_keystream(data);
+    }
+}
+
+/// ChaCha20-based random generator.
+///
+/// The output is the ChaCha20 keystream for a 32-byte seed (used as
+/// key), with the original layout of the ChaCha state: a 64-bit block
+/// counter followed by a 64-bit stream identifier (in place of the
+/// 32-bit counter and 96-bit nonce of RFC 8439). Each stream has length
+/// 2^70 bytes, which cannot be exhausted in practice.
+///
+/// Distinct stream identifiers yield independent, non-overlapping
+/// streams; `fork()` makes a new generator over the same seed with
+/// another stream identifier, e.g. for a child process or a specific
+/// usage (nonces, blinding...). Forking with the identifier of an
+/// existing generator reproduces that generator's output, so each
+/// identifier should be used only once for a given seed.
+#[derive(Clone, Debug)]
+pub struct ChaChaRng {
+    key: [u32; 8],
+    stream: u64,
+    // Counter of the next block to compute.
+    ctr: u64,
+    // Buffered output (four blocks), and current read offset in it.
+    buf: [u8; 256],
+    ptr: usize,
+}
+
+impl ChaChaRng {
+
+    /// Create a generator from a seed and a stream identifier.
+    pub fn new(seed: &[u8; 32], stream_id: u64) -> Self {
+        let mut key = [0u32; 8];
+        decode_words(seed, &mut key);
+        Self {
+            key,
+            stream: stream_id,
+            ctr: 0,
+            buf: [0u8; 256],
+            ptr: 256,
+        }
+    }
+
+    /// Get a new generator using the same seed as this one, but with
+    /// the provided stream identifier. The new generator starts at the
+    /// beginning of its stream; this generator is not modified.
+    pub fn fork(&self, stream_id: u64) -> Self {
+        Self {
+            key: self.key,
+            stream: stream_id,
+            ctr: 0,
+            buf: [0u8; 256],
+            ptr: 256,
+        }
+    }
+
+    /// Get the stream identifier of this generator.
+    #[inline]
+    pub fn stream_id(&self) -> u64 {
+        self.stream
+    }
+
+    // Compute the next four blocks into the buffer.
+    fn refill(&mut self) {
+        // The high half of the block counter is the first nonce word.
+        let stream = self.stream;
+        let nonce = |c: u64| [(c >> 32) as u32,
+            stream as u32, (stream >> 32) as u32];
+
+        // The four-block function does not propagate carries into the
+        // high half of the counter; it is used only when there is no
+        // carry.
+        #[cfg(target_arch = "x86_64")]
+        if (self.ctr as u32) <= 0xFFFFFFFC {
+            block4_words(&self.key, &nonce(self.ctr), self.ctr as u32,
+                &mut self.buf);
+            self.ctr = self.ctr.wrapping_add(4);
+            self.ptr = 0;
+            return;
+        }
+
+        for i in 0..4 {
+            let c = self.ctr.wrapping_add(i as u64);
+            block_words(&self.key, &nonce(c), c as u32,
+                &mut self.buf[(64 * i)..(64 * i + 64)]);
+        }
+        self.ctr = self.ctr.wrapping_add(4);
+        self.ptr = 0;
+    }
+}
+
+impl RngCore for ChaChaRng {
+
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut j = 0;
+        while j < dest.len() {
+            if self.ptr == self.buf.len() {
+                self.refill();
+            }
+            let clen = core::cmp::min(dest.len() - j,
+                self.buf.len() - self.ptr);
+            dest[j..(j + clen)].copy_from_slice(
+                &self.buf[self.ptr..(self.ptr + clen)]);
+            self.ptr += clen;
+            j += clen;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RngError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for ChaChaRng {
+    type Seed = [u8; 32];
+
+    /// Create a generator from a seed, with stream identifier 0.
+    fn from_seed(seed: [u8; 32]) -> Self {
+        Self::new(&seed, 0)
+    }
+}
+
+impl CryptoRng for ChaChaRng { }
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ChaCha20, XChaCha20, ChaChaRng, chacha20_block, hchacha20};
+    use crate::{RngCore, SeedableRng};
+    use crate::Vec;
+
+    fn seq_key(start: u8) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        for i in 0..32 {
+            key[i] = start.wrapping_add(i as u8);
+        }
+        key
+    }
+
+    #[test]
+    fn rfc8439_block() {
+        // RFC 8439, section 2.3.2.
+        let key = seq_key(0);
+        let nonce = hex::decode("000000090000004a00000000").unwrap();
+        let blk = chacha20_block(&key,
+            <&[u8; 12]>::try_from(&nonce[..]).unwrap(), 1);
+        assert!(blk[..] == hex::decode("10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4ed2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e").unwrap()[..]);
+    }
+
+    #[test]
+    fn rfc8439_encrypt() {
+        // RFC 8439, section 2.4.2.
+        let key = seq_key(0);
+        let nonce = hex::decode("000000000000004a00000000").unwrap();
+        let nonce = <&[u8; 12]>::try_from(&nonce[..]).unwrap();
+        let pt = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let ct = hex::decode("6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0bf91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d807ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab77937365af90bbf74a35be6b40b8eedf2785e42874d").unwrap();
+        let mut buf = pt.to_vec();
+        let mut cc = ChaCha20::new(&key, nonce, 1);
+        cc.apply_keystream(&mut buf);
+        assert!(buf == ct);
+        assert!(cc.position() == 64 + (pt.len() as u64));
+        cc.set_counter(1);
+        cc.apply_keystream(&mut buf);
+        assert!(buf[..] == pt[..]);
+    }
+
+    #[test]
+    fn hchacha20_kat() {
+        // draft-irtf-cfrg-xchacha-03, section 2.2.1.
+        let key = seq_key(0);
+        let nonce = hex::decode("000000090000004a0000000031415927").unwrap();
+        let sk = hchacha20(&key, <&[u8; 16]>::try_from(&nonce[..]).unwrap());
+        assert!(sk[..] == hex::decode("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc").unwrap()[..]);
+    }
+
+    #[test]
+    fn xchacha20_kat() {
+        // draft-irtf-cfrg-xchacha-03, appendix A.3.2 (note that the
+        // nonce ends with 0x58, not 0x57).
+        let key = seq_key(0x80);
+        let nonce = hex::decode("404142434445464748494a4b4c4d4e4f5051525354555658").unwrap();
+        let nonce = <&[u8; 24]>::try_from(&nonce[..]).unwrap();
+        let pt = b"The dhole (pronounced \"dole\") is also known as the Asiatic wild dog, red dog, and whistling dog. It is about the size of a German shepherd but looks more like a long-legged fox. This highly elusive and skilled jumper is classified with wolves, coyotes, jackals, and foxes in the taxonomic family Canidae.";
+        let ct = hex::decode("7d0a2e6b7f7c65a236542630294e063b7ab9b555a5d5149aa21e4ae1e4fbce87ecc8e08a8b5e350abe622b2ffa617b202cfad72032a3037e76ffdcdc4376ee053a190d7e46ca1de04144850381b9cb29f051915386b8a710b8ac4d027b8b050f7cba5854e028d564e453b8a968824173fc16488b8970cac828f11ae53cabd20112f87107df24ee6183d2274fe4c8b1485534ef2c5fbc1ec24bfc3663efaa08bc047d29d25043532db8391a8a3d776bf4372a6955827ccb0cdd4af403a7ce4c63d595c75a43e045f0cce1f29c8b93bd65afc5974922f214a40b7c402cdb91ae73c0b63615cdad0480680f16515a7ace9d39236464328a37743ffc28f4ddb324f4d0f5bbdc270c65b1749a6efff1fbaa09536175ccd29fb9e6057b307320d316838a9c71f70b5b5907a66f7ea49aadc409").unwrap();
+        let mut buf = pt.to_vec();
+        let mut cc = XChaCha20::new(&key, nonce, 1);
+        cc.apply_keystream(&mut buf);
+        assert!(buf == ct);
+        cc.seek(64);
+        cc.apply_keystream(&mut buf);
+        assert!(buf[..] == pt[..]);
+    }
+
+    fn long_keystream() -> ([u8; 32], [u8; 12], Vec<u8>) {
+        // Reference keystream (1000 bytes, starting at block 7) for a
+        // non-trivial key and nonce; long enough to exercise the
+        // multi-block code path.
+        let mut key = [0u8; 32];
+        for i in 0..32 {
+            key[i] = (i * 7 + 3) as u8;
+        }
+        let mut nonce = [0u8; 12];
+        for i in 0..12 {
+            nonce[i] = (i * 13 + 5) as u8;
+        }
+        let ks = hex::decode(LONG_KS).unwrap();
+        (key, nonce, ks)
+    }
+
+    #[test]
+    fn multiblock() {
+        let (key, nonce, ks) = long_keystream();
+        let mut buf = [0u8; 1000];
+        let mut cc = ChaCha20::new(&key, &nonce, 7);
+        cc.apply_keystream(&mut buf);
+        assert!(buf[..] == ks[..]);
+    }
+
+    #[test]
+    fn seek() {
+        let (key, nonce, ks) = long_keystream();
+        let mut cc = ChaCha20::new(&key, &nonce, 0);
+
+        // Random access at arbitrary offsets and lengths.
+        for (off, len) in [(0, 1000), (1, 999), (63, 300), (64, 256),
+            (65, 700), (300, 1), (511, 489), (999, 1)]
+        {
+            let mut buf = [0u8; 1000];
+            cc.seek(448 + (off as u64));
+            cc.apply_keystream(&mut buf[..len]);
+            assert!(buf[..len] == ks[off..(off + len)]);
+            assert!(cc.position() == 448 + ((off + len) as u64));
+        }
+
+        // Sequential processing in pieces of irregular sizes.
+        let mut buf = [0u8; 1000];
+        cc.set_counter(7);
+        let mut j = 0;
+        let mut k = 1;
+        while j < buf.len() {
+            let clen = core::cmp::min(k, buf.len() - j);
+            cc.apply_keystream(&mut buf[j..(j + clen)]);
+            j += clen;
+            k = (k * 5 + 17) % 300;
+        }
+        assert!(buf[..] == ks[..]);
+    }
+
+    #[test]
+    fn limit() {
+        let key = seq_key(0);
+        let nonce = [0u8; 12];
+        let mut cc = ChaCha20::new(&key, &nonce, 0xFFFFFFFF);
+        let mut buf = [0u8; 64];
+        cc.apply_keystream(&mut buf);
+        assert!(buf == chacha20_block(&key, &nonce, 0xFFFFFFFF));
+        let r = std::panic::catch_unwind(move || {
+            let mut buf = [0u8; 1];
+            cc.apply_keystream(&mut buf);
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn rng_stream() {
+        // First 256 output bytes for a fixed seed and stream 0; these
+        // values must not change across versions.
+        let seed = seq_key(0);
+        let mut rng = ChaChaRng::new(&seed, 0);
+        let mut buf = [0u8; 256];
+        rng.fill_bytes(&mut buf);
+        assert!(buf[..] == hex::decode(concat!(
+            "39fd2b7dd9c5196a8dbd0377b8dc4a498a35d86fbcde6accb2cc7d4cd8ea2492",
+            "2b23cce7a26023ab3f0eef693ac87f64258235eab1f7a32dc22762a0485b410c",
+            "18b84231ade6a6d113615c61af434e27f8b1f3f5e1ad5b5cecf8fc122a35755c",
+            "7208086dd1ee3c5d9d815824640e003c9ba0f65ede5d59ce0d2a4a7f31955acd",
+            "42f22ddca74a92d56ca78aef298e723b60237f3647eabeb7f3e09c30ce80e3e2",
+            "84a8021b8a5c0b2494cd3c8d5b13507ec7e7a0784df4a3e2ea8162d261c59d23",
+            "e7ab11c0f73c3b7eb0983950b3e2c4a08f843da95fb7fcb3f13456816b51b782",
+            "4df2f9bd5613d4b4ed952fd858cd1b984acbf8ff1fd1a7c806d81ca8e4ae3b2c",
+        )).unwrap()[..]);
+
+        // Same output with from_seed(), and with requests of irregular
+        // sizes.
+        let mut rng = ChaChaRng::from_seed(seed);
+        let mut buf2 = [0u8; 256];
+        let mut j = 0;
+        let mut k = 1;
+        while j < buf2.len() {
+            let clen = core::cmp::min(k, buf2.len() - j);
+            rng.fill_bytes(&mut buf2[j..(j + clen)]);
+            j += clen;
+            k = (k * 5 + 3) % 70;
+        }
+        assert!(buf == buf2);
+        let mut rng = ChaChaRng::new(&seed, 0);
+        assert!(rng.next_u32() == u32::from_le_bytes(
+            *<&[u8; 4]>::try_from(&buf[..4]).unwrap()));
+        assert!(rng.next_u64() == u64::from_le_bytes(
+            *<&[u8; 8]>::try_from(&buf[4..12]).unwrap()));
+
+        // Carry into the high half of the 64-bit block counter. With
+        // the stream identifier, the high counter half makes up the
+        // 96-bit nonce of the RFC 8439 block function.
+        let stream = 0x0123456789ABCDEFu64;
+        let mut rng = ChaChaRng::new(&seed, stream);
+        rng.ctr = 0x00000005FFFFFFFE;
+        let mut buf = [0u8; 256];
+        rng.fill_bytes(&mut buf);
+        for i in 0..4 {
+            let c = 0x00000005FFFFFFFEu64 + (i as u64);
+            let mut nonce = [0u8; 12];
+            nonce[..4].copy_from_slice(&((c >> 32) as u32).to_le_bytes());
+            nonce[4..].copy_from_slice(&stream.to_le_bytes());
+            assert!(buf[(64 * i)..(64 * i + 64)]
+                == chacha20_block(&seed, &nonce, c as u32)[..]);
+        }
+    }
+
+    #[test]
+    fn rng_fork() {
+        let seed = seq_key(0x40);
+        let rng = ChaChaRng::new(&seed, 0);
+        let mut blocks = Vec::new();
+        for id in [0u64, 1, 2, 1u64 << 32, u64::MAX] {
+            let mut r2 = rng.fork(id);
+            assert!(r2.stream_id() == id);
+            let mut buf = [0u8; 4096];
+            r2.fill_bytes(&mut buf);
+            if id == 0 {
+                let mut r3 = ChaChaRng::new(&seed, 0);
+                let mut buf3 = [0u8; 4096];
+                r3.fill_bytes(&mut buf3);
+                assert!(buf == buf3);
+            }
+            for b in buf.chunks_exact(64) {
+                blocks.push(<[u8; 64]>::try_from(b).unwrap());
+            }
+        }
+
+        // No two output blocks are equal, within a stream or across
+        // streams.
+        blocks.sort();
+        for i in 1..blocks.len() {
+            assert!(blocks[i - 1] != blocks[i]);
+        }
+    }
+
+    use core::convert::TryFrom;
+
+    static LONG_KS: &str = concat!(
+        "3d9e9e0d8011dc4290eb665b07773fa8cd400fcd6eb33233d0d5c340f4ac164f",
+        "05496b9500f4681e043ecfae96e6c03154a330c072b224cdbd220bcd759b09e5",
+        "bd2a1a305e66273cca428f47bb0e0bebf41ccdbe093a02d7937dd484e447ef5e",
+        "0264c98ccf912ce6cc92f93e096b949a2e510ab518ab5cdaf1a6c18715d02365",
+        "2e8bca0776219818deabdcf5091fe20099c4b9e0db1dc19d84808efe12fec82e",
+        "5503dfca9706afd3bfb5504e36418df3b6e76616633d9aca2e694d4603de2073",
+        "3b95519f3a8efbfded8c8eb83ace377d0658ebeedb3d6c62a6b5141bf66fdef1",
+        "d43afd02da1233010c06416a6bcb926daf92436357692e94afcb845979018244",
+        "bfb87611869c56462cc122bb5389abc62d31da974315765f4d136b668c84f825",
+        "a8cef26470c4732703d9d106c6c4a63834bf4f3c599690144f16d8d400a6147b",
+        "284e988d7e63a3cf365cefed5daae1ca55c4d45ca84695a4c4fdd2735f1547ac",
+        "53ff3ced63be196f62e1247944aac885a13536fbc9e3d1f19a2419105355e06b",
+        "58882ad05823c1943cdc4e00aa0d044df851eb66888fa9cc4852198e4eee1f77",
+        "30db434985e321e5803636059a1e07cb4a7bcb0dda4e5994d060acc4fe72d72b",
+        "6fae003d04e8814dc83284d1f658655888b54aa1494b857fe4b8758dd5fc31ca",
+        "a894619450e0b3e3cc6986a4f3dad924da960efae60c6be13f4d501eb42ad93a",
+        "de8367bedf4efe95bc506d84f2346570e61681f323414a710a42df824ed2f3ff",
+        "a2752ade6a95e860bf6b071323029d3928ae7adb695afa5927798452d877daa6",
+        "49e7a61c34e89fe762a50e36f19feff64914cf78d95dc6da3f53538fb46f2a0e",
+        "73e6da26837bc099bdb3600aedbff26d48511cedf47e184716d4c5d903ac005c",
+        "5fb05adbac6cfc68cb3ccc2f5160eb8945b27333d8675922e51fef5f21082878",
+        "e58318e2c46f9d3531efe544523cc45d1b8fe39954bb35a788e11ba5824b8b5f",
+        "bcce8ae257eb1cf9327551ee49d12751f2652531957ce0f0f37f493f686b96b2",
+        "259f79f2719143b6453822a63f631223516f32f57daab8dad1fe43cff00cf8ef",
+        "00d2496689a42bfc6357d161a78ed4c50be4c155fd02c2fa8f325e8d85ae0ce7",
+        "1a5f2a637dee677496dc9e983f14d07a70166120aa1b28ea3ed20f53cca2feb5",
+        "eaf2854d2764d1171f0de742f7a8802f5c72e679d9f6c98afac2ccc3367f0fcd",
+        "4ebc6703495c1a60421e91ca57c5f0a6ca9121fa4fe550ca11697232a5782265",
+        "355965f2f8d1121b5cb1007f4eec97e97938c2c60c52ab9928c2c6161fc68748",
+        "eafc1278ba64a79ac7512f612b7cd7a687b6736550f865ef725398d162bccee7",
+        "9509eb54396d90306cf17cbce133be1f3de163680b08d808a30894b6f018bdec",
+        "9c4967a38c697618",
+    );
+}
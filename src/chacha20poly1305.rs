@@ -0,0 +1,135 @@
+//! ChaCha20-Poly1305 authenticated encryption (RFC 8439).
+//!
+//! `ChaCha20Poly1305` holds a 32-byte key. Encryption and decryption
+//! work in place: `encrypt()` encrypts the data and returns the 16-byte
+//! tag, and `decrypt()` verifies the tag and then decrypts the data. The
+//! nonce (12 bytes) MUST NOT be reused with the same key.
+//!
+//! The Poly1305 one-time key is the first half of the ChaCha20 block
+//! with counter 0; data is encrypted with the keystream starting at
+//! block counter 1. The MAC input is the additional authenticated data
+//! and the ciphertext, each padded to a multiple of 16 bytes, followed
+//! by their lengths (RFC 8439, section 2.8).
+
+use crate::chacha20::{ChaCha20, chacha20_block};
+use crate::poly1305::Poly1305;
+
+/// ChaCha20-Poly1305 AEAD context.
+#[derive(Clone, Copy, Debug)]
+pub struct ChaCha20Poly1305 {
+    key: [u8; 32],
+}
+
+impl ChaCha20Poly1305 {
+
+    /// Key length (in bytes).
+    pub const KEY_LEN: usize = 32;
+
+    /// Nonce length (in bytes).
+    pub const NONCE_LEN: usize = 12;
+
+    /// Tag length (in bytes).
+    pub const TAG_LEN: usize = 16;
+
+    /// Create a new context with the provided key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self { key: *key }
+    }
+
+    // Compute the tag over the AAD and the ciphertext.
+    fn tag(&self, nonce: &[u8; 12], aad: &[u8], ct: &[u8]) -> [u8; 16] {
+        let mut otk = [0u8; 32];
+        otk.copy_from_slice(&chacha20_block(&self.key, nonce, 0)[..32]);
+        let mut pc = Poly1305::new(&otk);
+        let zeros = [0u8; 16];
+        pc.update(aad);
+        pc.update(&zeros[..(aad.len().wrapping_neg() & 15)]);
+        pc.update(ct);
+        pc.update(&zeros[..(ct.len().wrapping_neg() & 15)]);
+        pc.update(&(aad.len() as u64).to_le_bytes());
+        pc.update(&(ct.len() as u64).to_le_bytes());
+        pc.finalize()
+    }
+
+    /// Encrypt some data in place, with the provided nonce and additional
+    /// authenticated data (`aad`, possibly empty). The tag is returned.
+    pub fn encrypt(&self, nonce: &[u8; 12], aad: &[u8], data: &mut [u8])
+        -> [u8; 16]
+    {
+        ChaCha20::new(&self.key, nonce, 1).apply_keystream(data);
+        self.tag(nonce, aad, data)
+    }
+
+    /// Decrypt some data in place, with the provided nonce, additional
+    /// authenticated data and tag. If the tag is correct, then the data
+    /// is decrypted and `true` is returned; otherwise, `false` is
+    /// returned and the data is left unmodified. The tag comparison is
+    /// constant-time; tags whose length is not exactly 16 bytes are
+    /// rejected.
+    pub fn decrypt(&self, nonce: &[u8; 12], aad: &[u8], data: &mut [u8],
+        tag: &[u8]) -> bool
+    {
+        if !crate::ct::equals(&self.tag(nonce, aad, data), tag) {
+            return false;
+        }
+        ChaCha20::new(&self.key, nonce, 1).apply_keystream(data);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ChaCha20Poly1305;
+
+    #[test]
+    fn rfc8439() {
+        // Test vector from RFC 8439, section 2.8.2.
+        let mut key = [0u8; 32];
+        for i in 0..32 {
+            key[i] = 0x80 + i as u8;
+        }
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&hex::decode("070000004041424344454647").unwrap());
+        let aad = hex::decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+        let pt = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let ct = hex::decode("d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b6116").unwrap();
+        let tag = hex::decode("1ae10b594f09e26a7e902ecbd0600691").unwrap();
+
+        let aead = ChaCha20Poly1305::new(&key);
+        let mut data = pt.to_vec();
+        assert!(aead.encrypt(&nonce, &aad, &mut data)[..] == tag[..]);
+        assert!(data == ct);
+        assert!(aead.decrypt(&nonce, &aad, &mut data, &tag));
+        assert!(data[..] == pt[..]);
+    }
+
+    #[test]
+    fn tamper() {
+        let aead = ChaCha20Poly1305::new(&[0x11; 32]);
+        let nonce = [0x22; 12];
+        for len in 0..70 {
+            let pt = vec![0x33u8; len];
+            let mut ct = pt.clone();
+            let tag = aead.encrypt(&nonce, b"aad", &mut ct);
+            let mut data = ct.clone();
+            assert!(aead.decrypt(&nonce, b"aad", &mut data, &tag));
+            assert!(data == pt);
+
+            // Wrong AAD, nonce, ciphertext or tag: rejected, and the
+            // data is left unmodified.
+            let mut data = ct.clone();
+            assert!(!aead.decrypt(&nonce, b"aae", &mut data, &tag));
+            assert!(!aead.decrypt(&[0x23; 12], b"aad", &mut data, &tag));
+            assert!(!aead.decrypt(&nonce, b"aad", &mut data, &tag[..15]));
+            let mut t2 = tag;
+            t2[len % 16] ^= 0x80;
+            assert!(!aead.decrypt(&nonce, b"aad", &mut data, &t2));
+            assert!(data == ct);
+            if len > 0 {
+                data[len / 2] ^= 0x01;
+                assert!(!aead.decrypt(&nonce, b"aad", &mut data, &tag));
+            }
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! CPace password-authenticated key exchange over ristretto255.
+//!
+//! This module implements the CPACE-RISTRETTO255-SHA512 ciphersuite of
+//! [draft-irtf-cfrg-cpace]. Both parties derive a common generator `g`
+//! from the password-related string (PRS), the channel identifier (CI)
+//! and the session identifier (sid); each party then sends `y*g` for a
+//! fresh random scalar `y`, and the intermediate session key (ISK) is
+//! obtained by hashing the shared point along with the transcript.
+//!
+//!  - In initiator-responder mode, the initiator calls `CPace::initiator()`
+//!    and the responder calls `CPace::responder()`; messages can be sent
+//!    in any order, but the roles define the transcript order.
+//!
+//!  - In symmetric mode, both parties call `CPace::symmetric()`; the
+//!    transcript then uses the ordered concatenation of the messages.
+//!
+//! Each party calls `finish()` with the peer message to obtain the
+//! 64-byte ISK. Messages use the draft framing `lv_cat(Y, AD)`, where `Y`
+//! is the encoded share and `AD` some associated data (possibly empty).
+//! Shares that fail to decode, or decode to the neutral element, are
+//! rejected. There is no key confirmation: a password mismatch yields
+//! distinct ISKs on both sides.
+//!
+//! [draft-irtf-cfrg-cpace]: https://datatracker.ietf.org/doc/draft-irtf-cfrg-cpace/
+
+// Points traditionally use uppercase letters.
+#![allow(non_snake_case)]
+
+use crate::ristretto255::{Point, Scalar};
+use crate::{CryptoRng, RngCore, Vec};
+use sha2::{Sha512, Digest};
+
+/// Error type for CPace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CPaceError {
+    /// The peer message is not a correctly framed `lv_cat(Y, AD)`.
+    InvalidMessage,
+    /// The peer share is invalid or the neutral element.
+    InvalidShare,
+}
+
+impl core::fmt::Display for CPaceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CPaceError::InvalidMessage => f.write_str("invalid message framing"),
+            CPaceError::InvalidShare => f.write_str("invalid public share"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CPaceError {}
+
+/// Domain separation identifier for the ristretto255 group.
+const DSI: &[u8] = b"CPaceRistretto255";
+
+/// Domain separation identifier for the ISK computation.
+const DSI_ISK: &[u8] = b"CPaceRistretto255_ISK";
+
+/// Input block size of SHA-512 (`H.s_in_bytes`).
+const S_IN_BYTES: usize = 128;
+
+/// Appends `prepend_len(data)` (LEB128-encoded length, then the data).
+fn append_lv(out: &mut Vec<u8>, data: &[u8]) {
+    let mut n = data.len();
+    loop {
+        let b = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(b);
+            break;
+        }
+        out.push(b | 0x80);
+    }
+    out.extend_from_slice(data);
+}
+
+/// Returns the length of `prepend_len(data)` for data of length `n`.
+fn lv_len(n: usize) -> usize {
+    let mut k = 1;
+    let mut m = n >> 7;
+    while m != 0 {
+        k += 1;
+        m >>= 7;
+    }
+    k + n
+}
+
+/// Splits `prepend_len(data) || rest` into `(data, rest)`.
+fn split_lv(buf: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut n = 0usize;
+    for (i, &b) in buf.iter().enumerate() {
+        if i >= 4 {
+            return None;
+        }
+        n |= ((b & 0x7F) as usize) << (7 * i);
+        if (b & 0x80) == 0 {
+            let rest = &buf[(i + 1)..];
+            if n > rest.len() {
+                return None;
+            }
+            return Some((&rest[..n], &rest[n..]));
+        }
+    }
+    None
+}
+
+/// Computes the generator string:
+/// `lv_cat(DSI, PRS, zero_bytes(len_zpad), CI, sid)`.
+fn generator_string(prs: &[u8], ci: &[u8], sid: &[u8]) -> Vec<u8> {
+    let zpad = S_IN_BYTES.saturating_sub(
+        1 + lv_len(prs.len()) + lv_len(DSI.len()));
+    let mut gs = Vec::new();
+    append_lv(&mut gs, DSI);
+    append_lv(&mut gs, prs);
+    append_lv(&mut gs, &[0u8; S_IN_BYTES][..zpad]);
+    append_lv(&mut gs, ci);
+    append_lv(&mut gs, sid);
+    gs
+}
+
+/// Computes the password-dependent generator `g`: the generator string
+/// is hashed with SHA-512, and the output is mapped to a group element
+/// with the ristretto255 one-way map.
+pub fn calculate_generator(prs: &[u8], ci: &[u8], sid: &[u8]) -> Point {
+    let mut h = [0u8; 64];
+    h[..].copy_from_slice(&Sha512::digest(generator_string(prs, ci, sid)));
+    Point::from_uniform_bytes(&h)
+}
+
+/// Protocol role.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    Initiator,
+    Responder,
+    Symmetric,
+}
+
+/// State of a CPace exchange in progress.
+///
+/// A state is obtained from `initiator()`, `responder()` or
+/// `symmetric()`, and consumed by `finish()`.
+pub struct CPace {
+    role: Role,
+    y: Scalar,
+    sid: Vec<u8>,
+    msg: Vec<u8>,
+}
+
+impl CPace {
+
+    /// Starts an exchange as initiator.
+    ///
+    /// `prs` is the password-related string, `sid` the session
+    /// identifier, `ci` the channel identifier, and `ad` the associated
+    /// data to send along with the share. Returned values are the state,
+    /// and the message `lv_cat(Ya, ADa)` to send to the responder.
+    pub fn initiator<T: CryptoRng + RngCore>(rng: &mut T,
+        prs: &[u8], sid: &[u8], ci: &[u8], ad: &[u8]) -> (Self, Vec<u8>)
+    {
+        Self::start(Role::Initiator, Self::sample_scalar(rng),
+            prs, sid, ci, ad)
+    }
+
+    /// Starts an exchange as responder.
+    ///
+    /// Parameters are the same as in `initiator()`. Returned values are
+    /// the state, and the message `lv_cat(Yb, ADb)` to send to the
+    /// initiator.
+    pub fn responder<T: CryptoRng + RngCore>(rng: &mut T,
+        prs: &[u8], sid: &[u8], ci: &[u8], ad: &[u8]) -> (Self, Vec<u8>)
+    {
+        Self::start(Role::Responder, Self::sample_scalar(rng),
+            prs, sid, ci, ad)
+    }
+
+    /// Starts an exchange in symmetric mode.
+    ///
+    /// Parameters are the same as in `initiator()`. Returned values are
+    /// the state, and the message to send to the peer.
+    pub fn symmetric<T: CryptoRng + RngCore>(rng: &mut T,
+        prs: &[u8], sid: &[u8], ci: &[u8], ad: &[u8]) -> (Self, Vec<u8>)
+    {
+        Self::start(Role::Symmetric, Self::sample_scalar(rng),
+            prs, sid, ci, ad)
+    }
+
+    fn sample_scalar<T: CryptoRng + RngCore>(rng: &mut T) -> Scalar {
+        loop {
+            let mut buf = [0u8; 64];
+            rng.fill_bytes(&mut buf);
+            let y = Scalar::decode_reduce(&buf);
+            if y.iszero() == 0 {
+                return y;
+            }
+        }
+    }
+
+    fn start(role: Role, y: Scalar,
+        prs: &[u8], sid: &[u8], ci: &[u8], ad: &[u8]) -> (Self, Vec<u8>)
+    {
+        let g = calculate_generator(prs, ci, sid);
+        let mut msg = Vec::new();
+        append_lv(&mut msg, &(g * y).encode());
+        append_lv(&mut msg, ad);
+        (Self { role, y, sid: sid.to_vec(), msg: msg.clone() }, msg)
+    }
+
+    /// Finishes the exchange with the message received from the peer.
+    ///
+    /// On success, the 64-byte intermediate session key (ISK) is
+    /// returned. An error is reported if the message is not correctly
+    /// framed, or if the peer share is not a valid encoding of a
+    /// non-neutral group element.
+    pub fn finish(self, peer_msg: &[u8]) -> Result<[u8; 64], CPaceError> {
+        let (Y, rest) = split_lv(peer_msg).ok_or(CPaceError::InvalidMessage)?;
+        let (_, rest) = split_lv(rest).ok_or(CPaceError::InvalidMessage)?;
+        if !rest.is_empty() {
+            return Err(CPaceError::InvalidMessage);
+        }
+        let K = match Point::decode(Y) {
+            Some(P) if P.isneutral() == 0 => P * self.y,
+            _ => return Err(CPaceError::InvalidShare),
+        };
+        if K.isneutral() != 0 {
+            return Err(CPaceError::InvalidShare);
+        }
+
+        let mut prefix = Vec::new();
+        append_lv(&mut prefix, DSI_ISK);
+        append_lv(&mut prefix, &self.sid);
+        append_lv(&mut prefix, &K.encode());
+        let mut sh = Sha512::new();
+        sh.update(&prefix);
+        match self.role {
+            Role::Initiator => {
+                sh.update(&self.msg);
+                sh.update(peer_msg);
+            }
+            Role::Responder => {
+                sh.update(peer_msg);
+                sh.update(&self.msg);
+            }
+            Role::Symmetric => {
+                // o_cat(): "oc", then the lexicographically larger
+                // message first.
+                sh.update(b"oc");
+                if self.msg[..] > peer_msg[..] {
+                    sh.update(&self.msg);
+                    sh.update(peer_msg);
+                } else {
+                    sh.update(peer_msg);
+                    sh.update(&self.msg);
+                }
+            }
+        }
+        let mut isk = [0u8; 64];
+        isk[..].copy_from_slice(&sh.finalize());
+        Ok(isk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{CPace, Role, calculate_generator, generator_string};
+    use crate::ristretto255::Scalar;
+
+    const PRS: &[u8] = b"Password";
+    const CI: &[u8] = b"\nAinitiator\nBresponder";
+    const SID: &str = "7e4b4791d6a8ef019b936c79fb7f2c57";
+    const YA: &str = "da3d23700a9e5699258aef94dc060dfda5ebb61f02a5ea77fad53f4ff0976d08";
+    const YB: &str = "d2316b454718c35362d83d69df6320f38578ed5984651435e2949762d900b80d";
+
+    fn scalar(s: &str) -> Scalar {
+        Scalar::decode(&hex::decode(s).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn generator() {
+        // Test vector from draft-irtf-cfrg-cpace (ristretto255).
+        let sid = hex::decode(SID).unwrap();
+        let gs = generator_string(PRS, CI, &sid);
+        assert!(gs.len() == 168);
+        assert!(gs[..28] == hex::decode("11435061636552697374726574746f3235350850617373776f726464").unwrap());
+        let g = calculate_generator(PRS, CI, &sid);
+        assert!(g.encode()[..] == hex::decode("5e25411ca1ad7c9debfd0b33ad987a95cefef2d3f15dcc8bd26415a5dfe2e15a").unwrap());
+    }
+
+    #[test]
+    fn kat() {
+        // Test vectors from draft-irtf-cfrg-cpace (ristretto255).
+        let sid = hex::decode(SID).unwrap();
+        let (sa, ma) = CPace::start(Role::Initiator, scalar(YA),
+            PRS, &sid, CI, b"ADa");
+        let (sb, mb) = CPace::start(Role::Responder, scalar(YB),
+            PRS, &sid, CI, b"ADb");
+        assert!(ma == hex::decode("20383a85dd236978f17f8c8545b50dabc52a39fcdab2cf8bc531ce040ff77ca82d03414461").unwrap());
+        assert!(mb == hex::decode("20a6206309c0e8e5f579295e35997ac4300ab3fecec3c17f7b604f3e698fa1383c03414462").unwrap());
+        let ka = sa.finish(&mb).unwrap();
+        let kb = sb.finish(&ma).unwrap();
+        assert!(ka == kb);
+        assert!(ka[..] == hex::decode("e91ccb2c0f5e0d0993a33956e3be59754f3f2b07db57631f5394452ea2e7b4354674eb1f5686c078462bf83bec72e8743df440108e638f3526d9b90e85be096f").unwrap());
+
+        let (sa, ma) = CPace::start(Role::Symmetric, scalar(YA),
+            PRS, &sid, CI, b"ADa");
+        let (sb, mb) = CPace::start(Role::Symmetric, scalar(YB),
+            PRS, &sid, CI, b"ADb");
+        let ka = sa.finish(&mb).unwrap();
+        let kb = sb.finish(&ma).unwrap();
+        assert!(ka == kb);
+        assert!(ka[..] == hex::decode("1638fb6ff564a80a12af07c036870e10c4efb539fa847fdf3e9c46217bf52cd4df4ca0fe51146492a9ba6dd6a42ac402bc2d60adb4084c81758d754d1d81482a").unwrap());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn exchange() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x33; 32]);
+        for i in 0..10 {
+            let prs = [i as u8; 12];
+            let sid = [(i + 100) as u8; 16];
+            let (sa, ma) = CPace::initiator(&mut rng, &prs, &sid, b"ci", b"");
+            let (sb, mb) = CPace::responder(&mut rng, &prs, &sid, b"ci", &[7u8; 200]);
+            assert!(sa.finish(&mb).unwrap() == sb.finish(&ma).unwrap());
+
+            let (s1, m1) = CPace::symmetric(&mut rng, &prs, &sid, b"ci", b"x");
+            let (s2, m2) = CPace::symmetric(&mut rng, &prs, &sid, b"ci", b"y");
+            assert!(s1.finish(&m2).unwrap() == s2.finish(&m1).unwrap());
+        }
+
+        // A password mismatch yields distinct keys.
+        let (sa, ma) = CPace::initiator(&mut rng, b"pw1", b"sid", b"ci", b"");
+        let (sb, mb) = CPace::responder(&mut rng, b"pw2", b"sid", b"ci", b"");
+        assert!(sa.finish(&mb).unwrap() != sb.finish(&ma).unwrap());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn invalid_shares() {
+        use super::CPaceError;
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x44; 32]);
+
+        // Invalid encodings (negative field element, out-of-range field
+        // element), and the neutral element.
+        let bad = [
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ];
+        for b in bad.iter() {
+            let mut msg = vec![0x20u8];
+            msg.extend_from_slice(&hex::decode(b).unwrap());
+            msg.push(0x00);
+            let (s, _) = CPace::initiator(&mut rng, b"pw", b"sid", b"ci", b"");
+            assert!(s.finish(&msg) == Err(CPaceError::InvalidShare));
+        }
+
+        // Bad framing.
+        let (_, mb) = CPace::responder(&mut rng, b"pw", b"sid", b"ci", b"AD");
+        for cut in [0, 1, 33, mb.len() - 1].iter() {
+            let (s, _) = CPace::initiator(&mut rng, b"pw", b"sid", b"ci", b"");
+            assert!(s.finish(&mb[..*cut]) == Err(CPaceError::InvalidMessage));
+        }
+        let mut ext = mb.clone();
+        ext.push(0x00);
+        let (s, _) = CPace::initiator(&mut rng, b"pw", b"sid", b"ci", b"");
+        assert!(s.finish(&ext) == Err(CPaceError::InvalidMessage));
+    }
+}
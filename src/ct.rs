@@ -0,0 +1,103 @@
+//! Constant-time utility functions on byte slices.
+//!
+//! The functions in this module have an execution time which does not
+//! depend on the contents of the slices they operate on, only on their
+//! lengths (which are assumed to be public). They are meant for handling
+//! secret values, e.g. comparing a received MAC value with the expected
+//! one, or comparing derived keys.
+//!
+//! Following the conventions of this crate, conditions are expressed as
+//! `u32` values: 0xFFFFFFFF for "true", 0x00000000 for "false". The only
+//! exception is `equals()`, whose result is returned as a `bool` since it
+//! is normally used to take an immediate decision (e.g. reject a
+//! message); the comparison itself does not exit early.
+
+/// Compare two byte slices for equality.
+///
+/// If the two slices have different lengths, then `false` is returned
+/// immediately (lengths are considered public). Otherwise, all bytes are
+/// processed, and the execution time does not depend on the slice
+/// contents, nor on the position of the first difference, if any.
+pub fn equals(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut r = 0u32;
+    for i in 0..a.len() {
+        r |= (a[i] ^ b[i]) as u32;
+    }
+    // r is in 0..255; r - 1 has its top bit set only if r = 0.
+    (r.wrapping_sub(1) >> 31) != 0
+}
+
+/// Return `a0` (if `ctl` = 0x00000000) or `a1` (if `ctl` = 0xFFFFFFFF).
+///
+/// Value `ctl` MUST be either 0x00000000 or 0xFFFFFFFF.
+#[inline(always)]
+pub fn select_u8(a0: u8, a1: u8, ctl: u32) -> u8 {
+    a0 ^ ((a0 ^ a1) & (ctl as u8))
+}
+
+/// Conditionally copy `src` into `dst`.
+///
+///  - If `ctl` = 0xFFFFFFFF, then the contents of `src` are copied into
+///    `dst`.
+///
+///  - If `ctl` = 0x00000000, then `dst` is unchanged.
+///
+/// `ctl` MUST be equal to 0x00000000 or 0xFFFFFFFF. Both slices MUST have
+/// the same length; this function panics otherwise.
+pub fn cond_copy(dst: &mut [u8], src: &[u8], ctl: u32) {
+    assert!(dst.len() == src.len());
+    for i in 0..dst.len() {
+        dst[i] = select_u8(dst[i], src[i], ctl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{equals, select_u8, cond_copy};
+
+    #[test]
+    fn equals_bytes() {
+        let mut a = [0u8; 65];
+        for i in 0..a.len() {
+            a[i] = (i * 37 + 5) as u8;
+        }
+        for n in 0..=a.len() {
+            let mut b = a;
+            assert!(equals(&a[..n], &b[..n]));
+            for j in 0..n {
+                for bit in 0..8 {
+                    b[j] ^= 1u8 << bit;
+                    assert!(!equals(&a[..n], &b[..n]));
+                    b[j] ^= 1u8 << bit;
+                }
+            }
+            if n > 0 {
+                assert!(!equals(&a[..n], &b[..(n - 1)]));
+                assert!(!equals(&a[..(n - 1)], &b[..n]));
+            }
+        }
+        assert!(equals(&[], &[]));
+        assert!(!equals(&[0x00], &[0xFF]));
+        assert!(!equals(&[0x80], &[0x00]));
+    }
+
+    #[test]
+    fn select_copy() {
+        for a0 in 0..=255u8 {
+            for a1 in [0x00, 0x01, 0x7F, 0x80, 0xFE, 0xFF] {
+                assert!(select_u8(a0, a1, 0) == a0);
+                assert!(select_u8(a0, a1, 0xFFFFFFFF) == a1);
+            }
+        }
+        let src = [1u8, 2, 3, 4, 5];
+        let mut dst = [9u8; 5];
+        cond_copy(&mut dst, &src, 0);
+        assert!(dst == [9u8; 5]);
+        cond_copy(&mut dst, &src, 0xFFFFFFFF);
+        assert!(dst == src);
+    }
+}
@@ -313,6 +313,36 @@ impl Point {
         self.0.X.iszero()
     }
 
+    /// Multiplies this element by the cofactor of the group.
+    ///
+    /// This group has prime order (the cofactor is 1), so the element is
+    /// returned unchanged. This function is provided so that generic
+    /// code can clear cofactors uniformly across all curve modules.
+    #[inline(always)]
+    pub fn mul_by_cofactor(self) -> Self {
+        self
+    }
+
+    /// Multiplies this element by the group order.
+    ///
+    /// Since this group has prime order, the result is always the
+    /// neutral. This function is provided for uniformity with the curve
+    /// modules whose groups have a cofactor.
+    #[inline(always)]
+    pub fn mul_by_order(self) -> Self {
+        Self::NEUTRAL
+    }
+
+    /// Tests whether this element is in the subgroup of prime order.
+    ///
+    /// Since this group has prime order, 0xFFFFFFFF is always returned.
+    /// This function is provided for uniformity with the curve modules
+    /// whose groups have a cofactor.
+    #[inline(always)]
+    pub fn is_in_subgroup(self) -> u32 {
+        0xFFFFFFFF
+    }
+
     /// Conditionally copies the provided element (`P`) into `self`.
     ///
     ///  - If `ctl` = 0xFFFFFFFF, then the value of `P` is copied into `self`.
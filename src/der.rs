@@ -0,0 +1,328 @@
+//! DER encoding and decoding of ECDSA signatures.
+//!
+//! ECDSA signatures are represented in this library as the concatenation
+//! of `r` and `s`, both with unsigned big-endian encoding over the byte
+//! length of the curve order (e.g. 64 bytes in total for P-256 and
+//! secp256k1). Many protocols and tools (X.509, TLS, OpenSSL) instead
+//! use the ASN.1 structure:
+//!
+//! ```text
+//! ECDSA-Sig-Value ::= SEQUENCE {
+//!     r  INTEGER,
+//!     s  INTEGER
+//! }
+//! ```
+//!
+//! encoded with the Distinguished Encoding Rules (DER).
+//! `encode_ecdsa_signature()` converts the raw format into DER, and
+//! `decode_ecdsa_signature()` converts back. Decoding is strict by
+//! default: lengths and integers must be minimally encoded, integers
+//! must not be negative, and the whole input must be consumed. A lenient
+//! mode additionally accepts non-minimal lengths and integers with
+//! superfluous leading zeros (as some BER encoders produce); other
+//! errors (wrong tags, negative values, trailing data, indefinite
+//! lengths) are rejected in both modes.
+//!
+//! Signatures are public values; these functions are not constant-time.
+//!
+//! `DerError` is also reported by the key import functions of the `p256`
+//! module (`SubjectPublicKeyInfo` and PKCS#8 `PrivateKeyInfo`), which
+//! rely on the same internal DER reader.
+
+/// Error type for DER decoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DerError {
+    /// The input is not a valid encoding (wrong tag, truncated value,
+    /// trailing data...).
+    InvalidEncoding,
+    /// The input is valid BER, but not DER (non-minimal length or
+    /// integer encoding); such inputs are accepted in lenient mode.
+    NonCanonical,
+    /// An integer is negative or too large for the curve.
+    OutOfRange,
+    /// The algorithm or curve identifier (OID) of a key is not the
+    /// expected one.
+    UnsupportedAlgorithm,
+    /// The encoded key value is invalid (e.g. not a curve point, scalar
+    /// out of range, or mismatched private and public keys).
+    InvalidKey,
+}
+
+impl core::fmt::Display for DerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DerError::InvalidEncoding => f.write_str("invalid DER encoding"),
+            DerError::NonCanonical => f.write_str("non-canonical DER encoding"),
+            DerError::OutOfRange => f.write_str("integer out of range"),
+            DerError::UnsupportedAlgorithm => f.write_str("unsupported key algorithm"),
+            DerError::InvalidKey => f.write_str("invalid key value"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DerError {}
+
+/// Get the maximum length of the DER encoding of a signature whose raw
+/// format has length `raw_len` (i.e. twice the length of the curve
+/// order). For P-256 and secp256k1 (`raw_len` = 64), this is 72 bytes.
+pub const fn max_ecdsa_signature_len(raw_len: usize) -> usize {
+    // Each INTEGER is at most one byte longer than the raw value (sign
+    // byte), plus two header bytes; the SEQUENCE header may use a long
+    // form length.
+    let content = raw_len + 6;
+    if content < 0x80 {
+        content + 2
+    } else {
+        content + 3
+    }
+}
+
+// Write a length (in short or long form) at the start of `out`; the
+// number of written bytes is returned. Lengths up to 65535 are supported.
+fn write_len(len: usize, out: &mut [u8]) -> usize {
+    if len < 0x80 {
+        out[0] = len as u8;
+        1
+    } else if len < 0x100 {
+        out[0] = 0x81;
+        out[1] = len as u8;
+        2
+    } else {
+        out[0] = 0x82;
+        out[1] = (len >> 8) as u8;
+        out[2] = len as u8;
+        3
+    }
+}
+
+// Get the length of the INTEGER contents for the provided unsigned
+// big-endian value (leading zeros removed, sign byte added if needed).
+fn int_len(v: &[u8]) -> usize {
+    let mut i = 0;
+    while i < v.len() && v[i] == 0 {
+        i += 1;
+    }
+    if i == v.len() {
+        1
+    } else if v[i] >= 0x80 {
+        v.len() - i + 1
+    } else {
+        v.len() - i
+    }
+}
+
+// Write an INTEGER for the provided unsigned big-endian value; the
+// number of written bytes is returned.
+fn write_int(v: &[u8], out: &mut [u8]) -> usize {
+    let n = int_len(v);
+    out[0] = 0x02;
+    let mut j = 1 + write_len(n, &mut out[1..]);
+    if n > v.len() {
+        out[j] = 0x00;
+        j += 1;
+        out[j..(j + v.len())].copy_from_slice(v);
+        j + v.len()
+    } else {
+        out[j..(j + n)].copy_from_slice(&v[(v.len() - n)..]);
+        j + n
+    }
+}
+
+/// Encodes a raw signature (`r || s`, both halves of the same length,
+/// unsigned big-endian) into DER. The output buffer must be large
+/// enough (see `max_ecdsa_signature_len()`); the number of written bytes
+/// is returned. This function panics if the raw signature has odd
+/// length, or if the output buffer is too small.
+pub fn encode_ecdsa_signature(sig: &[u8], out: &mut [u8]) -> usize {
+    assert!((sig.len() & 1) == 0);
+    let (r, s) = sig.split_at(sig.len() >> 1);
+    let rl = int_len(r);
+    let sl = int_len(s);
+    let hlen = |n: usize| if n < 0x80 { 2 } else if n < 0x100 { 3 } else { 4 };
+    let content = hlen(rl) + rl + hlen(sl) + sl;
+    out[0] = 0x30;
+    let mut j = 1 + write_len(content, &mut out[1..]);
+    j += write_int(r, &mut out[j..]);
+    j += write_int(s, &mut out[j..]);
+    j
+}
+
+// Read a tag and length at the start of `src`; returned values are the
+// contents and the remaining bytes.
+pub(crate) fn read_tlv(src: &[u8], tag: u8, lenient: bool)
+    -> Result<(&[u8], &[u8]), DerError>
+{
+    if src.len() < 2 || src[0] != tag {
+        return Err(DerError::InvalidEncoding);
+    }
+    let mut len = src[1] as usize;
+    let mut j = 2;
+    if len >= 0x80 {
+        // Long form; 0x80 alone is the (BER-only) indefinite length,
+        // which is not supported.
+        let nb = len - 0x80;
+        if nb == 0 || nb > 2 || src.len() < 2 + nb {
+            return Err(DerError::InvalidEncoding);
+        }
+        len = 0;
+        for i in 0..nb {
+            len = (len << 8) | (src[2 + i] as usize);
+        }
+        j += nb;
+        let minimal = len >= 0x80 && (nb == 1 || len >= 0x100);
+        if !minimal && !lenient {
+            return Err(DerError::NonCanonical);
+        }
+    }
+    if src.len() - j < len {
+        return Err(DerError::InvalidEncoding);
+    }
+    Ok((&src[j..(j + len)], &src[(j + len)..]))
+}
+
+// Decode an unsigned INTEGER into `out` (unsigned big-endian, with
+// exactly the length of `out`). The remaining bytes are returned.
+fn read_int<'a>(src: &'a [u8], out: &mut [u8], lenient: bool)
+    -> Result<&'a [u8], DerError>
+{
+    let (mut v, rem) = read_tlv(src, 0x02, lenient)?;
+    if v.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+    if v[0] >= 0x80 {
+        return Err(DerError::OutOfRange);
+    }
+    // A leading zero is allowed only if the next byte has its top bit
+    // set (or in lenient mode).
+    if v.len() >= 2 && v[0] == 0x00 && v[1] < 0x80 && !lenient {
+        return Err(DerError::NonCanonical);
+    }
+    while v.len() > 1 && v[0] == 0x00 {
+        v = &v[1..];
+    }
+    if v.len() > out.len() {
+        return Err(DerError::OutOfRange);
+    }
+    let k = out.len() - v.len();
+    out[..k].fill(0);
+    out[k..].copy_from_slice(v);
+    Ok(rem)
+}
+
+/// Decodes a DER-encoded signature into the raw format (`r || s`); the
+/// output buffer length is the raw signature length (twice the length
+/// of the curve order), and `r` and `s` must fit in half of it. If
+/// `lenient` is `true`, then non-minimal lengths and integers are
+/// accepted. The whole input must be consumed.
+///
+/// This function does not check that `r` and `s` are lower than the
+/// curve order; signature verification functions reject out-of-range
+/// values.
+pub fn decode_ecdsa_signature(src: &[u8], out: &mut [u8], lenient: bool)
+    -> Result<(), DerError>
+{
+    assert!((out.len() & 1) == 0);
+    let (seq, rem) = read_tlv(src, 0x30, lenient)?;
+    if !rem.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+    let (r, s) = out.split_at_mut(out.len() >> 1);
+    let seq = read_int(seq, r, lenient)?;
+    let seq = read_int(seq, s, lenient)?;
+    if !seq.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use super::DerError::*;
+
+    // Tests use a toy order length of 4 bytes.
+    fn dec(s: &str, lenient: bool) -> Result<[u8; 8], DerError> {
+        let mut out = [0u8; 8];
+        decode_ecdsa_signature(&hex::decode(s).unwrap(), &mut out, lenient)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn ecdsa_sig() {
+        // (raw, DER) pairs: high bit set in r and/or s, short values,
+        // and zeros.
+        const KAT: &[(&str, &str)] = &[
+            ("0102030405060708", "300c020401020304020405060708"),
+            ("8102030405060708", "300d02050081020304020405060708"),
+            ("0102030485060708", "300d02040102030402050085060708"),
+            ("8102030485060708", "300e0205008102030402050085060708"),
+            ("000000010000ff80", "3008020101020300ff80"),
+            ("0000007f00000080", "300702017f02020080"),
+            ("0000000000000000", "3006020100020100"),
+        ];
+        for (raw, der) in KAT.iter() {
+            let raw = hex::decode(raw).unwrap();
+            let der = hex::decode(der).unwrap();
+            let mut buf = [0u8; 20];
+            let n = encode_ecdsa_signature(&raw, &mut buf);
+            assert!(buf[..n] == der[..]);
+            assert!(n <= max_ecdsa_signature_len(8));
+            let mut out = [0u8; 8];
+            decode_ecdsa_signature(&der, &mut out, false).unwrap();
+            assert!(out[..] == raw[..]);
+        }
+
+        // Large orders need a long-form length for the sequence.
+        assert!(max_ecdsa_signature_len(64) == 72);
+        assert!(max_ecdsa_signature_len(132) == 141);
+        let raw = [0xFFu8; 132];
+        let mut buf = [0u8; 141];
+        assert!(encode_ecdsa_signature(&raw, &mut buf) == 141);
+        assert!(buf[..6] == [0x30, 0x81, 0x8A, 0x02, 0x43, 0x00]);
+        let mut out = [0u8; 132];
+        decode_ecdsa_signature(&buf, &mut out, false).unwrap();
+        assert!(out == raw);
+
+        // Malformed inputs, rejected in both modes.
+        for lenient in [false, true] {
+            // empty, truncated, trailing garbage (after and within the
+            // sequence), wrong tags, indefinite length, empty integer
+            assert!(dec("", lenient).err() == Some(InvalidEncoding));
+            assert!(dec("3007020101020101", lenient).err()
+                == Some(InvalidEncoding));
+            assert!(dec("300602010102010100", lenient).err()
+                == Some(InvalidEncoding));
+            assert!(dec("3009020101020101020101", lenient).err()
+                == Some(InvalidEncoding));
+            assert!(dec("3106020101020101", lenient).err()
+                == Some(InvalidEncoding));
+            assert!(dec("3006030101020101", lenient).err()
+                == Some(InvalidEncoding));
+            assert!(dec("30800201010201010000", lenient).err()
+                == Some(InvalidEncoding));
+            assert!(dec("30050200020101", lenient).err()
+                == Some(InvalidEncoding));
+            // negative integer, value larger than the order length
+            assert!(dec("3006020181020101", lenient).err()
+                == Some(OutOfRange));
+            assert!(dec("300a02050102030405020101", lenient).err()
+                == Some(OutOfRange));
+        }
+
+        // BER-only encodings: non-minimal integers and lengths.
+        const BER: &[&str] = &[
+            "300702020001020101",
+            "3009020400000001020101",
+            "308106020101020101",
+            "30820006020101020101",
+            "300702810101020101",
+            "30080282000101020101",
+        ];
+        for s in BER.iter() {
+            assert!(dec(s, false).err() == Some(NonCanonical));
+            assert!(dec(s, true).unwrap() == [0, 0, 0, 1, 0, 0, 0, 1]);
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! ECIES (public key encryption) over P-256 and secp256k1.
+//!
+//! This module implements a simple ECIES-like public key encryption
+//! scheme: the sender generates an ephemeral key pair, performs an ECDH
+//! key exchange with the recipient public key, derives a symmetric key
+//! and nonce from the shared secret with HKDF-SHA256, and encrypts the
+//! message with ChaCha20-Poly1305. The `p256` and `secp256k1` submodules
+//! provide `encrypt()` and `decrypt()` for each curve; recipient key
+//! pairs are normal key pairs of the corresponding curve module.
+//!
+//! The ciphertext format (version 1) is:
+//!
+//! ```text
+//!     version || epk || encrypted_message || tag
+//! ```
+//!
+//! where `version` is a single byte of value 0x01, `epk` is the
+//! ephemeral public key in compressed format (33 bytes), and `tag` is
+//! the 16-byte Poly1305 tag; thus, a ciphertext is 50 bytes longer than
+//! the plaintext. The key and nonce are derived as follows:
+//!
+//! ```text
+//!     Z = x(esk*Q)
+//!     okm = HKDF-SHA256(salt = "", ikm = Z, info = label || epk || rpk, L = 44)
+//!     key = okm[0..32]
+//!     nonce = okm[32..44]
+//! ```
+//!
+//! with `Z` the x coordinate of the shared point (32 bytes, unsigned
+//! big-endian, as in SEC 1, section 3.3.1), `rpk` the recipient public
+//! key in compressed format (33 bytes), and `label` the ASCII string
+//! `"crrl ECIES v1 P-256"` or `"crrl ECIES v1 secp256k1"`. Since the
+//! information string includes both public keys, the derived key is
+//! bound to the intended recipient and to the ephemeral key. The
+//! caller-provided additional authenticated data (`aad`, possibly
+//! empty) is used as AAD for ChaCha20-Poly1305; it is not included in
+//! the ciphertext, and the same value must be provided for decryption.
+//! A new ephemeral key is used for each message, so that a given
+//! key/nonce pair is never reused.
+
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+use crate::chacha20poly1305::ChaCha20Poly1305;
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+use crate::hkdf::HkdfSha256;
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+use crate::Vec;
+
+/// Error type for ECIES.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EciesError {
+    /// Encryption failed (invalid recipient public key).
+    Encrypt,
+    /// The ciphertext uses an unsupported format version.
+    UnsupportedVersion,
+    /// The ephemeral public key in the ciphertext is invalid.
+    InvalidEphemeralKey,
+    /// Decryption failed (truncated ciphertext, wrong AAD or private
+    /// key, or authentication failure).
+    Decrypt,
+}
+
+impl core::fmt::Display for EciesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EciesError::Encrypt => f.write_str("invalid recipient public key"),
+            EciesError::UnsupportedVersion => f.write_str("unsupported version"),
+            EciesError::InvalidEphemeralKey => f.write_str("invalid ephemeral public key"),
+            EciesError::Decrypt => f.write_str("decryption failed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EciesError {}
+
+/// Format version of the ciphertexts produced by this module.
+pub const VERSION: u8 = 0x01;
+
+/// Overhead of a ciphertext: version (1 byte), ephemeral public key
+/// (33 bytes) and tag (16 bytes).
+pub const OVERHEAD: usize = 50;
+
+// Derives the AEAD key and nonce from the shared secret and both public
+// keys. The helpers below are used only by the curve submodules, and
+// are compiled only when at least one of them is enabled.
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+fn derive_key(label: &[u8], z: &[u8; 32], epk: &[u8; 33], rpk: &[u8; 33])
+    -> (ChaCha20Poly1305, [u8; 12])
+{
+    let mut okm = [0u8; 44];
+    let mut info = [0u8; 32 + 66];
+    let ilen = label.len() + 66;
+    info[..label.len()].copy_from_slice(label);
+    info[label.len()..(label.len() + 33)].copy_from_slice(epk);
+    info[(label.len() + 33)..ilen].copy_from_slice(rpk);
+    HkdfSha256::new(&[], z).expand(&info[..ilen], &mut okm).unwrap();
+    let mut key = [0u8; 32];
+    let mut nonce = [0u8; 12];
+    key.copy_from_slice(&okm[..32]);
+    nonce.copy_from_slice(&okm[32..]);
+    (ChaCha20Poly1305::new(&key), nonce)
+}
+
+// Encrypts a message, given the shared secret and both public keys.
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+fn seal(label: &[u8], z: &[u8; 32], epk: &[u8; 33], rpk: &[u8; 33],
+    pt: &[u8], aad: &[u8]) -> Vec<u8>
+{
+    let (aead, nonce) = derive_key(label, z, epk, rpk);
+    let mut out = Vec::with_capacity(pt.len() + OVERHEAD);
+    out.push(VERSION);
+    out.extend_from_slice(epk);
+    out.extend_from_slice(pt);
+    let tag = aead.encrypt(&nonce, aad, &mut out[34..]);
+    out.extend_from_slice(&tag);
+    out
+}
+
+// Decrypts a message, given the shared secret and both public keys. The
+// ciphertext (`ct`) is the complete ciphertext, including the header;
+// its length has already been checked.
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+fn open(label: &[u8], z: &[u8; 32], epk: &[u8; 33], rpk: &[u8; 33],
+    ct: &[u8], aad: &[u8]) -> Result<Vec<u8>, EciesError>
+{
+    let (aead, nonce) = derive_key(label, z, epk, rpk);
+    let tag_off = ct.len() - 16;
+    let mut pt = ct[34..tag_off].to_vec();
+    if !aead.decrypt(&nonce, aad, &mut pt, &ct[tag_off..]) {
+        return Err(EciesError::Decrypt);
+    }
+    Ok(pt)
+}
+
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+macro_rules! define_ecies { () => {
+
+    use core::convert::TryFrom;
+    use super::{EciesError, VERSION, OVERHEAD, seal, open};
+    use crate::{CryptoRng, RngCore, Vec};
+
+    /// Encrypts a message for the recipient public key `pk`, with the
+    /// provided additional authenticated data (`aad`, possibly empty).
+    /// The ephemeral key pair is generated from the provided random
+    /// source. The returned ciphertext is 50 bytes longer than the
+    /// message. An error is returned if the recipient public key is the
+    /// point-at-infinity.
+    pub fn encrypt<T: CryptoRng + RngCore>(rng: &mut T, pk: &PublicKey,
+        pt: &[u8], aad: &[u8]) -> Result<Vec<u8>, EciesError>
+    {
+        encrypt_with_ephemeral(&PrivateKey::generate(rng), pk, pt, aad)
+    }
+
+    // Encryption with an explicit ephemeral private key.
+    fn encrypt_with_ephemeral(esk: &PrivateKey, pk: &PublicKey, pt: &[u8],
+        aad: &[u8]) -> Result<Vec<u8>, EciesError>
+    {
+        let z = shared_secret(esk, pk).ok_or(EciesError::Encrypt)?;
+        let epk = esk.to_public_key().encode_compressed();
+        let rpk = pk.encode_compressed();
+        Ok(seal(LABEL, &z, &epk, &rpk, pt, aad))
+    }
+
+    /// Decrypts a ciphertext with the recipient private key `sk` and
+    /// the additional authenticated data `aad` (which must be equal to
+    /// the one used for encryption). An error is returned if the
+    /// ciphertext is shorter than 50 bytes, does not use the version 1
+    /// format, contains an invalid ephemeral public key (not a
+    /// compressed encoding of a curve point other than the
+    /// point-at-infinity), or fails authentication.
+    pub fn decrypt(sk: &PrivateKey, ct: &[u8], aad: &[u8])
+        -> Result<Vec<u8>, EciesError>
+    {
+        if ct.len() < OVERHEAD {
+            return Err(EciesError::Decrypt);
+        }
+        if ct[0] != VERSION {
+            return Err(EciesError::UnsupportedVersion);
+        }
+        let epk = <[u8; 33]>::try_from(&ct[1..34]).unwrap();
+        let ek = PublicKey::decode(&epk)
+            .ok_or(EciesError::InvalidEphemeralKey)?;
+        let z = shared_secret(sk, &ek)
+            .ok_or(EciesError::InvalidEphemeralKey)?;
+        let rpk = sk.to_public_key().encode_compressed();
+        open(LABEL, &z, &epk, &rpk, ct, aad)
+    }
+
+} } // End of macro: define_ecies
+
+// ========================================================================
+
+#[cfg(all(test, any(feature = "p256", feature = "secp256k1")))]
+macro_rules! define_ecies_tests { () => {
+
+    use super::{encrypt, decrypt, encrypt_with_ephemeral, shared_secret};
+    use super::{PrivateKey, PublicKey, Point};
+    use super::super::{EciesError, OVERHEAD};
+    use crate::Vec;
+
+    fn decode_sk(s: &str) -> PrivateKey {
+        PrivateKey::decode(&hex::decode(s).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn golden_vectors() {
+        let rsk = decode_sk(KAT_RSK);
+        let esk = decode_sk(KAT_ESK);
+        let rpk = rsk.to_public_key();
+        assert!(hex::encode(rpk.encode_compressed()) == KAT_RPK);
+        let msg100: Vec<u8> = (0..100).collect();
+        let inputs: [(&[u8], &[u8]); 3] = [
+            (b"", b""),
+            (b"ecies", b"context"),
+            (&msg100, b""),
+        ];
+        for ((m, aad), kh) in inputs.iter().zip(KAT.iter()) {
+            let ct = hex::decode(kh).unwrap();
+            assert!(encrypt_with_ephemeral(&esk, &rpk, m, aad).unwrap() == ct);
+            assert!(decrypt(&rsk, &ct, aad).unwrap()[..] == m[..]);
+        }
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn tamper() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x42; 32]);
+        let rsk = decode_sk(KAT_RSK);
+        let rpk = rsk.to_public_key();
+        let aad = b"aad";
+        for len in 0..40 {
+            let pt: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let ct = encrypt(&mut rng, &rpk, &pt, aad).unwrap();
+            assert!(ct.len() == len + OVERHEAD);
+            assert!(decrypt(&rsk, &ct, aad).unwrap() == pt);
+
+            // Any modified byte is detected: version, ephemeral key,
+            // encrypted message and tag.
+            for i in 0..ct.len() {
+                let mut ct2 = ct.clone();
+                ct2[i] ^= 0x01;
+                let r = decrypt(&rsk, &ct2, aad);
+                if i == 0 {
+                    assert!(r == Err(EciesError::UnsupportedVersion));
+                } else {
+                    assert!(r.is_err());
+                }
+            }
+
+            // Truncated or extended ciphertexts are rejected.
+            for j in 0..ct.len() {
+                assert!(decrypt(&rsk, &ct[..j], aad).is_err());
+            }
+            let mut ct3 = ct.clone();
+            ct3.push(0x00);
+            assert!(decrypt(&rsk, &ct3, aad) == Err(EciesError::Decrypt));
+
+            // Wrong AAD or wrong private key.
+            assert!(decrypt(&rsk, &ct, b"aae") == Err(EciesError::Decrypt));
+            assert!(decrypt(&rsk, &ct, b"") == Err(EciesError::Decrypt));
+            let rsk2 = PrivateKey::generate(&mut rng);
+            assert!(decrypt(&rsk2, &ct, aad) == Err(EciesError::Decrypt));
+        }
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn invalid_ephemeral() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x17; 32]);
+        let rsk = decode_sk(KAT_RSK);
+        let rpk = rsk.to_public_key();
+        let ct = encrypt(&mut rng, &rpk, b"message", b"").unwrap();
+
+        // The point-at-infinity (which has no compressed encoding), an
+        // invalid prefix, and an x coordinate which is not on the
+        // curve are all rejected.
+        let mut ct2 = ct.clone();
+        for i in 1..34 {
+            ct2[i] = 0x00;
+        }
+        assert!(decrypt(&rsk, &ct2, b"")
+            == Err(EciesError::InvalidEphemeralKey));
+        let mut ct2 = ct.clone();
+        ct2[1] = 0x04;
+        assert!(decrypt(&rsk, &ct2, b"")
+            == Err(EciesError::InvalidEphemeralKey));
+        let mut ct2 = ct.clone();
+        let mut x = 0u8;
+        loop {
+            ct2[33] = x;
+            if PublicKey::decode(&ct2[1..34]).is_none() {
+                break;
+            }
+            x += 1;
+        }
+        assert!(decrypt(&rsk, &ct2, b"")
+            == Err(EciesError::InvalidEphemeralKey));
+
+        // Encryption to the point-at-infinity is refused.
+        let pk0 = PublicKey { point: Point::NEUTRAL };
+        assert!(shared_secret(&rsk, &pk0).is_none());
+        assert!(encrypt(&mut rng, &pk0, b"message", b"")
+            == Err(EciesError::Encrypt));
+    }
+
+} } // End of macro: define_ecies_tests
+
+// ========================================================================
+
+/// ECIES over P-256.
+#[cfg(feature = "p256")]
+pub mod p256 {
+
+    use crate::p256::{PrivateKey, PublicKey, ecdh};
+    #[cfg(test)]
+    use crate::p256::Point;
+
+    const LABEL: &[u8] = b"crrl ECIES v1 P-256";
+
+    // ECDH, with the x coordinate of the shared point as output.
+    fn shared_secret(sk: &PrivateKey, pk: &PublicKey) -> Option<[u8; 32]> {
+        ecdh(sk, pk)
+    }
+
+    define_ecies!{}
+
+    #[cfg(test)]
+    mod tests {
+
+        // Recipient and ephemeral private keys, recipient public key,
+        // and ciphertexts computed with an independent implementation
+        // for ("", ""), ("ecies", "context") and (bytes 0 to 99, "") as
+        // (message, AAD) pairs.
+        static KAT_RSK: &str = "2f4ecc7c1b0e8e0b4ab2f5d2ba3d1d6e3f1f0e9a8b7c6d5e4f30211203a4b5c6";
+        static KAT_RPK: &str = "03cbbedb1cbf9720369d8e17a692fd9e2a18fcb7a4cb52fafa98deab0b6be50a01";
+        static KAT_ESK: &str = "6a3c2d1e0f9e8d7c6b5a4938271605f4e3d2c1b0a99887766554433221100ffe";
+        static KAT: [&str; 3] = [
+            "01035eac88ff41095b88b8a061ec3cbbe430bafed990d67c6fdc4e8305509be64fda54379f67ff0bd4f24b532366f12ba18a",
+            "01035eac88ff41095b88b8a061ec3cbbe430bafed990d67c6fdc4e8305509be64fda850041b9c79d0e0e65537f13fd1892e62756e4e169",
+            "01035eac88ff41095b88b8a061ec3cbbe430bafed990d67c6fdc4e8305509be64fdae0622adfb01b188a56900e6d7f751afcc35f233c8eee9ae0c11964f8673a76f85a911fe8e4513dd539adbcc32608deede3d0c514a4402b436f80a0ca98f20a6f4c6e8a58ec3edfc00f62ca387a183d5474d0de5d2fcdac3132294f71581f66f19a442b649ae909fc7c4fc19a61d4b9fe8a9076dd",
+        ];
+
+        define_ecies_tests!{}
+    }
+}
+
+/// ECIES over secp256k1.
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1 {
+
+    use crate::secp256k1::{PrivateKey, PublicKey, ecdh_xonly};
+    #[cfg(test)]
+    use crate::secp256k1::Point;
+
+    const LABEL: &[u8] = b"crrl ECIES v1 secp256k1";
+
+    // ECDH, with the x coordinate of the shared point as output.
+    fn shared_secret(sk: &PrivateKey, pk: &PublicKey) -> Option<[u8; 32]> {
+        ecdh_xonly(sk, pk)
+    }
+
+    define_ecies!{}
+
+    #[cfg(test)]
+    mod tests {
+
+        // Recipient and ephemeral private keys, recipient public key,
+        // and ciphertexts computed with an independent implementation
+        // for ("", ""), ("ecies", "context") and (bytes 0 to 99, "") as
+        // (message, AAD) pairs.
+        static KAT_RSK: &str = "2f4ecc7c1b0e8e0b4ab2f5d2ba3d1d6e3f1f0e9a8b7c6d5e4f30211203a4b5c6";
+        static KAT_RPK: &str = "03e5a7cdc6a8f189bdde940a0a991b4db2199f400359e28eb3e749e76b3b47db04";
+        static KAT_ESK: &str = "6a3c2d1e0f9e8d7c6b5a4938271605f4e3d2c1b0a99887766554433221100ffe";
+        static KAT: [&str; 3] = [
+            "01034e4bbc52a7d380c233950e2e977d5ef9df28ece033afe7a8d9af1fde26d0bcda6c8e0ea74aa0c3f1e9ac088958fe5bbc",
+            "01034e4bbc52a7d380c233950e2e977d5ef9df28ece033afe7a8d9af1fde26d0bcda031047cd82bce58cd5565d7f89ddfa1a7ae9eef38b",
+            "01034e4bbc52a7d380c233950e2e977d5ef9df28ece033afe7a8d9af1fde26d0bcda66722cabf5a69869dfc267a521310a094d638de14a9b2e055522c8e3acbb8a17dc41f3800c2331c9fc57aea26d411463230c3d03d0cd1dc26103cbb317474505c95f143a33edd5450292e4fc8b416f45cda2a46bb8aa5411c9afd8acd0afead5e9f6de305b8de1b3c9c298619bf9e50d954ebde2",
+        ];
+
+        define_ecies_tests!{}
+    }
+}
@@ -29,7 +29,9 @@
 //! Scalars can be encoded over 32 bytes (using unsigned little-endian
 //! convention) and decoded back. Encoding is always canonical, and
 //! decoding always verifies that the value is indeed in the canonical
-//! range.
+//! range. `Scalar::from_bytes_mod_order_wide()` reduces a 64-byte value
+//! (e.g. a hash output) modulo `L`, and `Scalar::hash_from_bytes()` hashes
+//! data into a scalar with SHA-512.
 //!
 //! Points can be encoded over 32 bytes, and decoded back. As with
 //! scalars, encoding is always canonical, and verified upon decoding.
@@ -48,6 +50,23 @@
 //! available on `PublicKey`, again for Ed25519, Ed25519ctx and
 //! Ed25519ph.
 //!
+//! `PrivateKey::expand()` returns an `ExpandedPrivateKey`, which holds
+//! only the secret scalar and the signing prefix; it can also be built
+//! directly from these values, for keys which are not derived from a
+//! seed.
+//!
+//! `sign_raw_blinded()` computes the same signature as `sign_raw()`, but
+//! the multiplication of the generator by the secret nonce is randomized
+//! with an extra RNG (see `Point::mulgen_blinded()`), as a
+//! countermeasure against power and electromagnetic analysis on shared
+//! hardware. `sign_raw_checked()` verifies the new signature before
+//! returning it, and reports an error (`SignError`) instead of a faulty
+//! signature, as a countermeasure against fault attacks.
+//!
+//! With `std` support, `sign_reader()` and `PublicKey::verify_reader()`
+//! process messages read from a stream (e.g. a large file) with bounded
+//! memory; signing reads the data twice, seeking back between passes.
+//!
 //! # Ed25519 Edge Cases
 //!
 //! It is known that there is a great amount of variation about how
@@ -74,6 +93,53 @@
 //!   accepted. The full 32 bytes are used: the three top bits of the
 //!   last byte, though always of value 0, are checked.
 //!
+//! `PublicKey::verify_zip215()` implements the alternate rules of
+//! [ZIP-215], under which non-canonical encodings of points (for `R` and
+//! for the public key) are accepted; this is meant for consensus
+//! systems that need these exact rules. The default verification
+//! functions are not affected.
+//!
+//! Conversely, `PublicKey::verify_strict()` implements the most
+//! conservative rules: it additionally rejects low-order and
+//! mixed-order points (for both `R` and the public key), and uses the
+//! cofactorless equation. It rejects all twelve test vectors of the
+//! paper. The three modes compare as follows:
+//!
+//! | Check                     | default    | ZIP-215    | strict       |
+//! | :------------------------ | :--------- | :--------- | :----------- |
+//! | non-canonical `R` or `A`  | rejected   | accepted   | rejected     |
+//! | `S` not lower than `L`    | rejected   | rejected   | rejected     |
+//! | low-order `R` or `A`      | accepted   | accepted   | rejected     |
+//! | mixed-order `R` or `A`    | accepted   | accepted   | rejected     |
+//! | verification equation     | cofactored | cofactored | cofactorless |
+//!
+//! # Batch Verification
+//!
+//! `verify_batch()` verifies many Ed25519 signatures at once, with a
+//! single multi-scalar multiplication over random linear combinations of
+//! the verification equations; this is substantially faster than
+//! verifying the signatures one by one. The random coefficients are
+//! derived from a caller-provided seed. Batch verification uses the same
+//! cofactored equation as individual verification, and the two always
+//! agree (except with negligible probability). If a batch fails,
+//! `verify_batch_find_invalid()` locates the invalid signatures by
+//! bisection. `verify_same_message()` and
+//! `verify_same_message_find_invalid()` are variants for batches where
+//! all signatures are over the same message (e.g. votes from many
+//! signers). These functions require heap allocation support.
+//!
+//! `PrecomputedTable` holds precomputed windows for a fixed set of
+//! points, so that many multi-scalar multiplications over these points
+//! can be performed without recomputing the tables each time (this also
+//! requires heap allocation support).
+//!
+//! The `slip10` submodule implements SLIP-10 hierarchical derivation of
+//! Ed25519 private keys (hardened derivation only).
+//!
+//! The `blinding` submodule implements the key blinding scheme of Tor
+//! onion services (v3), which derives per-period public and private keys
+//! from a long-term key pair.
+//!
 //! # Truncated Signatures
 //!
 //! The `PublicKey::verify_trunc_*()` functions support _truncated
@@ -94,6 +160,7 @@
 //! [RFC 7748]: https://datatracker.ietf.org/doc/html/rfc7748
 //! [RFC 8032]: https://datatracker.ietf.org/doc/html/rfc8032
 //! [taming]: https://eprint.iacr.org/2020/1244
+//! [ZIP-215]: https://zips.z.cash/zip-0215
 
 // Projective/fractional coordinates traditionally use uppercase letters,
 // using lowercase only for affine coordinates.
@@ -103,7 +170,13 @@ use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use core::convert::TryFrom;
 use super::field::{GF25519, ModInt256};
 use sha2::{Sha512, Digest};
-use super::{CryptoRng, RngCore};
+use super::{CryptoRng, RngCore, DecodeError, ParseError, SignError};
+
+#[cfg(feature = "alloc")]
+use crate::Vec;
+
+#[cfg(feature = "std")]
+use std::io::{self, Read, Seek, SeekFrom};
 
 /// A point on the twisted Edwards curve edwards25519.
 #[derive(Clone, Copy, Debug)]
@@ -126,6 +199,76 @@ impl Scalar {
     pub fn encode(self) -> [u8; 32] {
         self.encode32()
     }
+
+    /// Decodes a 64-byte value into a scalar, with reduction.
+    ///
+    /// The bytes are interpreted as an integer in unsigned little-endian
+    /// convention, and reduced modulo `L`. If the input is uniformly
+    /// random, then the output bias is negligible (about 2^-259). This
+    /// is the same as `decode_reduce()` on a 64-byte input, and it is
+    /// constant-time.
+    pub fn from_bytes_mod_order_wide(b: &[u8; 64]) -> Self {
+        Self::decode_reduce(&b[..])
+    }
+
+    /// Hashes some data into a scalar.
+    ///
+    /// The data is hashed with SHA-512 (the input is the concatenation
+    /// of the domain separation string `dst` and `data`), and the
+    /// 64-byte output is reduced modulo `L` with
+    /// `from_bytes_mod_order_wide()`. Since `dst` is not length-prefixed,
+    /// each usage context should use a fixed domain separation string.
+    pub fn hash_from_bytes(dst: &[u8], data: &[u8]) -> Self {
+        let mut sh = Sha512::new();
+        sh.update(dst);
+        sh.update(data);
+        let mut b = [0u8; 64];
+        b[..].copy_from_slice(&sh.finalize());
+        Self::from_bytes_mod_order_wide(&b)
+    }
+
+    /// Inverts many scalars at once, using the provided scratch space.
+    ///
+    /// All elements of `xx` are replaced with their respective inverses;
+    /// elements of value zero are left untouched. Returned value is
+    /// 0xFFFFFFFF if all elements were non-zero, 0x00000000 otherwise.
+    /// This uses Montgomery's trick (prefix products, a single
+    /// inversion, then back-substitution), for a cost of one inversion
+    /// and about `3*n` multiplications; it is constant-time (the batch
+    /// size is considered public).
+    ///
+    /// The scratch slice `tmp` MUST have length at least `xx.len()`
+    /// (a panic is triggered otherwise). `batch_invert()` computes the
+    /// same values without a scratch slice, by processing the batch
+    /// in chunks of 200 elements (with one inversion per chunk).
+    pub fn batch_invert_scratch(xx: &mut [Self], tmp: &mut [Self]) -> u32 {
+        let n = xx.len();
+        assert!(tmp.len() >= n);
+        if n == 0 {
+            return 0xFFFFFFFF;
+        }
+
+        // tmp[i] = product of all non-zero xx[j] for j <= i.
+        let mut r = 0xFFFFFFFFu32;
+        let mut acc = Self::ONE;
+        for (x, t) in xx.iter().zip(tmp.iter_mut()) {
+            let zz = x.iszero();
+            r &= !zz;
+            acc *= Self::select(x, &Self::ONE, zz);
+            *t = acc;
+        }
+
+        // k = 1/(product of all non-zero elements); then walk back.
+        let mut k = Self::ONE / acc;
+        for j in (0..n).rev() {
+            let x = xx[j];
+            let zz = x.iszero();
+            let p = if j > 0 { tmp[j - 1] } else { Self::ONE };
+            xx[j].set_cond(&(k * p), !zz);
+            k *= Self::select(&x, &Self::ONE, zz);
+        }
+        r
+    }
 }
 
 impl Point {
@@ -205,6 +348,37 @@ impl Point {
     /// outcome (success or failure) should remain hidden from
     /// timing-based side channels.
     pub fn set_decode(&mut self, buf: &[u8]) -> u32 {
+        self.set_decode_inner(buf, false)
+    }
+
+    /// Tries to decode a point from bytes, with the lenient rules of
+    /// ZIP-215.
+    ///
+    /// This is similar to `set_decode()`, except that non-canonical
+    /// encodings are accepted: the `y` coordinate may be any 255-bit
+    /// integer (it is reduced modulo p), and the sign bit may be set
+    /// when `x` is zero (it is then ignored). This is meant for
+    /// `PublicKey::verify_zip215()`; since several encodings may map to
+    /// the same point, re-encoding the point does not necessarily yield
+    /// the source bytes.
+    pub fn set_decode_zip215(&mut self, buf: &[u8]) -> u32 {
+        self.set_decode_inner(buf, true)
+    }
+
+    /// Tries to decode a point from some bytes, with the lenient rules
+    /// of ZIP-215 (see `set_decode_zip215()`).
+    pub fn decode_zip215(buf: &[u8]) -> Option<Point> {
+        let mut P = Point::NEUTRAL;
+        if P.set_decode_zip215(buf) != 0 {
+            Some(P)
+        } else {
+            None
+        }
+    }
+
+    // Point decoding; if `lax` is true, then non-canonical encodings
+    // are accepted (ZIP-215 rules).
+    fn set_decode_inner(&mut self, buf: &[u8], lax: bool) -> u32 {
         // We follow all steps from RFC 8032, section 5.1.3.
 
         if buf.len() != 32 {
@@ -219,8 +393,12 @@ impl Point {
         bb[31] &= 0x7F;
 
         // Decode y. This may fail if the source value is not in the
-        // proper 0..p-1 range.
-        let (mut y, mut r) = GF25519::decode32(&bb[..]);
+        // proper 0..p-1 range (in lax mode, the value is reduced).
+        let (mut y, mut r) = if lax {
+            (GF25519::decode_reduce(&bb[..]), 0xFFFFFFFF)
+        } else {
+            GF25519::decode32(&bb[..])
+        };
 
         // Recompute a candidate x.
 
@@ -262,9 +440,11 @@ impl Point {
 
         // If the sign bit of x does not match the specified bit, then
         // negate x. This may induce a failure if x == 0 and the requested
-        // sign bit is 1.
+        // sign bit is 1 (except in lax mode).
         let nx = (((x.encode()[0] & 0x01) ^ sign_x) as u32).wrapping_neg();
-        r &= !(x.iszero() & nx);
+        if !lax {
+            r &= !(x.iszero() & nx);
+        }
         x.set_cond(&-x, nx);
 
         // If the process failed, then set (x,y) to (0,1).
@@ -296,6 +476,81 @@ impl Point {
         }
     }
 
+    /// Decodes many points.
+    ///
+    /// Each element of `out` receives the result of `decode()` on the
+    /// corresponding encoding; the number of successfully decoded points
+    /// is returned. `out` MUST have the same length as `encodings`. This
+    /// is meant for loading large sets of public keys (e.g. a validator
+    /// set); as with `decode()`, which entries were invalid may leak
+    /// through timing-based side channels.
+    ///
+    /// Note: decoding computes the `x` coordinate with a combined
+    /// inverse square root (a single exponentiation, and no inversion),
+    /// so there is no inversion to amortize with Montgomery's trick, and
+    /// the square roots of distinct values cannot be shared; the cost is
+    /// that of decoding the points one by one.
+    pub fn decode_batch(encodings: &[[u8; 32]], out: &mut [Option<Point>])
+        -> usize
+    {
+        assert!(encodings.len() == out.len());
+        let mut n = 0;
+        for (buf, P) in encodings.iter().zip(out.iter_mut()) {
+            *P = Self::decode(buf);
+            n += P.is_some() as usize;
+        }
+        n
+    }
+
+    /// Tries to decode a point from some bytes, with detailed error
+    /// reporting.
+    ///
+    /// Unlike `decode()`, this function also checks that the point is
+    /// in the subgroup of prime order L (a point of low or mixed order
+    /// is reported as `DecodeError::NotInSubgroup`). Other failures are
+    /// reported as:
+    ///
+    ///  - `BadLength`: the source slice does not have length 32 bytes;
+    ///
+    ///  - `NonCanonicalField`: the `y` coordinate is not lower than p;
+    ///
+    ///  - `NonCanonicalEncoding`: `x` is zero but the sign bit is set;
+    ///
+    ///  - `NotOnCurve`: there is no curve point with the provided `y`.
+    ///
+    /// This function is meant for diagnostics, e.g. to investigate
+    /// interoperability issues; the cause of a failure is computed with
+    /// variable-time code. `decode()` and `set_decode()` should be used
+    /// in production code.
+    pub fn decode_verbose(buf: &[u8]) -> Result<Point, DecodeError> {
+        if buf.len() != 32 {
+            return Err(DecodeError::BadLength);
+        }
+        match Self::decode(buf) {
+            Some(P) => {
+                if P.is_in_subgroup() != 0 {
+                    Ok(P)
+                } else {
+                    Err(DecodeError::NotInSubgroup)
+                }
+            }
+            None => {
+                let mut bb = [0u8; 32];
+                bb[..].copy_from_slice(buf);
+                bb[31] &= 0x7F;
+                if GF25519::decode32(&bb).1 == 0 {
+                    Err(DecodeError::NonCanonicalField)
+                } else if Self::decode_zip215(buf).is_some() {
+                    // y is canonical and matches a point, hence the
+                    // failure is from the sign bit with x = 0.
+                    Err(DecodeError::NonCanonicalEncoding)
+                } else {
+                    Err(DecodeError::NotOnCurve)
+                }
+            }
+        }
+    }
+
     /// Encodes this point into exactly 32 bytes.
     ///
     /// Encoding is always canonical.
@@ -307,6 +562,42 @@ impl Point {
         r
     }
 
+    /// Gets the affine coordinates `(x, y)` of this point.
+    ///
+    /// Each coordinate is encoded over 32 bytes (unsigned little-endian
+    /// convention); the values are fully reduced modulo p = 2^255 - 19.
+    /// The neutral point is (0, 1).
+    pub fn to_affine_coordinates(self) -> ([u8; 32], [u8; 32]) {
+        let iZ = GF25519::ONE / self.Z;
+        ((self.X * iZ).encode(), (self.Y * iZ).encode())
+    }
+
+    /// Creates a point from its affine coordinates `(x, y)`.
+    ///
+    /// Each coordinate is encoded over 32 bytes (unsigned little-endian
+    /// convention), and must be canonical (lower than p = 2^255 - 19).
+    /// If a coordinate is not canonical, or the coordinates do not
+    /// fulfill the curve equation, then `None` is returned. The point
+    /// is not required to be in the prime-order subgroup. As with
+    /// `decode()`, only the success or failure of this function may
+    /// leak through timing-based side channels.
+    pub fn from_affine_coordinates(x: &[u8; 32], y: &[u8; 32])
+        -> Option<Point>
+    {
+        let (x, rx) = GF25519::decode32(x);
+        let (y, ry) = GF25519::decode32(y);
+
+        // Curve equation: -x^2 + y^2 = 1 + d*x^2*y^2
+        let x2 = x.square();
+        let y2 = y.square();
+        let r = rx & ry & (y2 - x2).equals(GF25519::ONE + Self::D * x2 * y2);
+        if r != 0 {
+            Some(Self { X: x, Y: y, Z: GF25519::ONE, T: x * y })
+        } else {
+            None
+        }
+    }
+
     /// Creates a point by converting a point in Duif coordinates.
     fn from_duif(P: &PointDuif) -> Self {
         let X = (P.ypx - P.ymx).half();
@@ -517,6 +808,29 @@ impl Point {
         self.Y.equals(self.Z)
     }
 
+    /// Multiplies this point by the cofactor (8), with three doublings.
+    ///
+    /// The result is always in the subgroup of prime order `L`; points of
+    /// low order are mapped to the neutral. This function is
+    /// constant-time.
+    #[inline]
+    pub fn mul_by_cofactor(self) -> Self {
+        self.xdouble(3)
+    }
+
+    /// Multiplies this point by the prime order `L` of the subgroup.
+    ///
+    /// The result is the neutral if and only if this point is in the
+    /// subgroup of order `L`; otherwise, it is a non-neutral point of
+    /// low order (`L` is odd, so the low-order component of the point is
+    /// not cleared). This function is constant-time; if only the
+    /// subgroup membership is needed, `is_in_subgroup()` is faster.
+    pub fn mul_by_order(self) -> Self {
+        // Multiplication by a scalar computes the product with an
+        // integer in the 0 to L-1 range, hence: L*P = (L-1)*P + P.
+        self * (-Scalar::ONE) + self
+    }
+
     /// Tests whether this point is a low-order point, i.e. a point of
     /// order 1, 2, 4 or 8.
     ///
@@ -857,6 +1171,45 @@ impl Point {
         (self.Z + self.Y, self.Z - self.Y)
     }
 
+    /// Maps a point from the Montgomery curve (Curve25519, as used by
+    /// X25519) to the corresponding Edwards point.
+    ///
+    /// The source point is given by its u coordinate (32 bytes, unsigned
+    /// little-endian, canonical), and `sign` selects the Edwards point:
+    /// its x coordinate has least significant bit equal to `sign & 1`
+    /// (this is the sign bit of the usual Edwards point encoding). The
+    /// Edwards y coordinate is `(u - 1)/(u + 1)`. Failure (`None`) is
+    /// reported in the following cases:
+    ///
+    ///  - `u` is not canonical (it is not lower than p = 2^255 - 19);
+    ///
+    ///  - `u` = -1, which has no image on the Edwards curve;
+    ///
+    ///  - `u` is not the coordinate of a curve point (i.e. it is on the
+    ///    quadratic twist);
+    ///
+    ///  - the resulting x coordinate is zero but `sign` is 1.
+    ///
+    /// For all points `P` except the neutral (which maps to u = 0, like
+    /// the point of order 2), `P.to_montgomery_u().encode()` and the sign
+    /// bit of `P.encode()` yield `P` back through this function. As with
+    /// `decode()`, only the success or failure of this function may leak
+    /// through timing-based side channels.
+    pub fn from_montgomery_u(u: &[u8; 32], sign: u8) -> Option<Point> {
+        let (u, mut r) = GF25519::decode32(u);
+        let d = u + GF25519::ONE;
+        r &= !d.iszero();
+        let mut y = ((u - GF25519::ONE) / d).encode();
+        y[31] |= (sign & 1) << 7;
+        let mut P = Point::NEUTRAL;
+        r &= P.set_decode(&y);
+        if r != 0 {
+            Some(P)
+        } else {
+            None
+        }
+    }
+
     /// Recodes a scalar into 51 signed digits.
     ///
     /// Each digit is in -15..+16, top digit is in 0..+4.
@@ -976,28 +1329,108 @@ impl Point {
         }
     }
 
+    /// Computes the points i*(2^130)*B for i = 1 to 16, in Duif format;
+    /// this replaces the `PRECOMP_B130` table when small tables are
+    /// used.
+    #[cfg(feature = "small_tables_ed25519")]
+    fn make_precomp_b130() -> [PointDuif; 16] {
+        let P = Self::BASE.xdouble(130);
+        let mut tp = [P; 16];
+        for i in 1..16 {
+            tp[i] = tp[i - 1] + P;
+        }
+        let mut iZ = [GF25519::ZERO; 16];
+        for i in 0..16 {
+            iZ[i] = tp[i].Z;
+        }
+        GF25519::batch_invert(&mut iZ[..]);
+        let mut win = [PointDuif {
+            ypx: GF25519::ONE, ymx: GF25519::ONE, t2d: GF25519::ZERO,
+        }; 16];
+        for i in 0..16 {
+            let x = tp[i].X * iZ[i];
+            let y = tp[i].Y * iZ[i];
+            win[i] = PointDuif { ypx: y + x, ymx: y - x, t2d: (x * y) * Self::D2 };
+        }
+        win
+    }
+
     /// Sets this point by multiplying the conventional generator by the
     /// provided scalar.
     ///
     /// This operation is constant-time. It is faster than using the
     /// generic multiplication on `Self::BASE`.
     pub fn set_mulgen(&mut self, n: &Scalar) {
+        self.set_mulgen_inner(n, None);
+    }
+
+    // Multiplies the generator by the scalar n. If z is provided, then
+    // the coordinates of the initial point are scaled by z (which must
+    // not be zero); this does not change the result.
+    fn set_mulgen_inner(&mut self, n: &Scalar, z: Option<&GF25519>) {
         // Recode the scalar into 51 signed digits.
         let sd = Self::recode_scalar(n);
 
         // We process four chunks in parallel. Each chunk is 13 digits,
         // except the top one which is 12 digits only.
-        *self = Self::from_duif(&Self::lookup_duif(&PRECOMP_B, sd[12]));
-        self.set_add_duif(&Self::lookup_duif(&PRECOMP_B65, sd[25]));
-        self.set_add_duif(&Self::lookup_duif(&PRECOMP_B130, sd[38]));
+        #[cfg(not(any(feature = "bigtables", feature = "small_tables_ed25519")))]
+        {
+            *self = Self::from_duif(&Self::lookup_duif(&PRECOMP_B, sd[12]));
+            if let Some(z) = z {
+                self.set_rerandomize(z);
+            }
+            self.set_add_duif(&Self::lookup_duif(&PRECOMP_B65, sd[25]));
+            self.set_add_duif(&Self::lookup_duif(&PRECOMP_B130, sd[38]));
+
+            // Process the digits in high-to-low order.
+            for i in (0..12).rev() {
+                self.set_xdouble(5);
+                self.set_add_duif(&Self::lookup_duif(&PRECOMP_B, sd[i]));
+                self.set_add_duif(&Self::lookup_duif(&PRECOMP_B65, sd[i + 13]));
+                self.set_add_duif(&Self::lookup_duif(&PRECOMP_B130, sd[i + 26]));
+                self.set_add_duif(&Self::lookup_duif(&PRECOMP_B195, sd[i + 39]));
+            }
+        }
 
-        // Process the digits in high-to-low order.
-        for i in (0..12).rev() {
-            self.set_xdouble(5);
-            self.set_add_duif(&Self::lookup_duif(&PRECOMP_B, sd[i]));
-            self.set_add_duif(&Self::lookup_duif(&PRECOMP_B65, sd[i + 13]));
-            self.set_add_duif(&Self::lookup_duif(&PRECOMP_B130, sd[i + 26]));
-            self.set_add_duif(&Self::lookup_duif(&PRECOMP_B195, sd[i + 39]));
+        // With the larger tables, we process eight chunks in parallel.
+        // Each chunk is 7 digits, except the top one which is 2 digits
+        // only; this halves the number of doublings. Which lookups are
+        // skipped depends only on the digit index, not on the scalar.
+        #[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+        {
+            *self = Self::from_duif(&Self::lookup_duif(&PRECOMP_B, sd[6]));
+            if let Some(z) = z {
+                self.set_rerandomize(z);
+            }
+            for k in 1..7 {
+                self.set_add_duif(&Self::lookup_duif(
+                    PRECOMP_B_COMB[k], sd[7 * k + 6]));
+            }
+
+            // Process the digits in high-to-low order.
+            for i in (0..6).rev() {
+                self.set_xdouble(5);
+                for (k, win) in PRECOMP_B_COMB.iter().enumerate() {
+                    let j = 7 * k + i;
+                    if j < 51 {
+                        self.set_add_duif(&Self::lookup_duif(win, sd[j]));
+                    }
+                }
+            }
+        }
+
+        // With the small tables, digits are processed with Horner's
+        // rule and a single table (about 4 times as many doublings).
+        #[cfg(feature = "small_tables_ed25519")]
+        {
+            *self = Self::from_duif(&Self::lookup_duif(&PRECOMP_B, sd[50]));
+            if let Some(z) = z {
+                self.set_rerandomize(z);
+            }
+            for i in (0..50).rev() {
+                self.set_xdouble(5);
+                self.set_add_duif(&Self::lookup_duif(&PRECOMP_B, sd[i]));
+            }
         }
     }
 
@@ -1012,6 +1445,41 @@ impl Point {
         P
     }
 
+    // Scales the coordinates of this point by the non-zero field element
+    // z; the represented point is unchanged.
+    fn set_rerandomize(&mut self, z: &GF25519) {
+        self.X *= z;
+        self.Y *= z;
+        self.Z *= z;
+        self.T *= z;
+    }
+
+    /// Creates a point by multiplying the conventional generator by the
+    /// provided scalar, with blinding.
+    ///
+    /// The result is the same as with `mulgen()`, but the computation is
+    /// randomized with the provided RNG, as a countermeasure against
+    /// power and electromagnetic analysis: the scalar n is split into
+    /// (n - r) + r for a random r, and the coordinates of the initial
+    /// point of each partial product are scaled by random non-zero
+    /// field elements. This is about twice as expensive as `mulgen()`.
+    pub fn mulgen_blinded<T: CryptoRng + RngCore>(n: &Scalar, rng: &mut T)
+        -> Self
+    {
+        Self::mulgen_with_blinding(n, &Blinding::new(rng))
+    }
+
+    // Blinded multiplication of the generator, with the provided
+    // blinding values.
+    fn mulgen_with_blinding(n: &Scalar, b: &Blinding) -> Self {
+        let mut P = Self::NEUTRAL;
+        P.set_mulgen_inner(&(*n - b.r), Some(&b.z1));
+        let mut Q = Self::NEUTRAL;
+        Q.set_mulgen_inner(&b.r, Some(&b.z2));
+        P.set_add(&Q);
+        P
+    }
+
     /// 5-bit wNAF recoding of a scalar; output is a sequence of 254
     /// digits.
     ///
@@ -1050,6 +1518,40 @@ impl Point {
         sd
     }
 
+    /// wNAF recoding of a scalar with a window of `w` bits; output is a
+    /// sequence of 254 digits, whose non-zero values are odd and in the
+    /// `-(2^(w-1)-1)` to `+(2^(w-1)-1)` range.
+    fn recode_scalar_wNAF(n: &Scalar, w: usize) -> [i16; 254] {
+        // Since L < 2^253, 254 digits are enough. We process the
+        // scalar with an extra zero byte at the end so that windows
+        // never extend beyond the buffer.
+        let mut bb = [0u8; 34];
+        bb[..32].copy_from_slice(&n.encode());
+        let mut sd = [0i16; 254];
+        let mask = (1u32 << w) - 1;
+        let mut carry = 0u32;
+        let mut i = 0;
+        while i < 254 {
+            let j = i >> 3;
+            let bits = (bb[j] as u32) | ((bb[j + 1] as u32) << 8)
+                | ((bb[j + 2] as u32) << 16);
+            let v = ((bits >> (i & 7)) & mask) + carry;
+            if (v & 1) == 0 {
+                i += 1;
+                continue;
+            }
+            if v < (1u32 << (w - 1)) {
+                sd[i] = v as i16;
+                carry = 0;
+            } else {
+                sd[i] = (v as i16) - ((1u32 << w) as i16);
+                carry = 1;
+            }
+            i += w;
+        }
+        sd
+    }
+
     /// 5-bit wNAF recoding of a nonnegative integer.
     ///
     /// 129 digits are produced. Non-zero digits have an odd value,
@@ -1070,6 +1572,87 @@ impl Point {
         sd
     }
 
+    /// Computes the window of odd multiples of a point, for 5-bit wNAF:
+    /// `win[i] = (2*i+1)*P` (for `i` = 0 to 7).
+    #[cfg(feature = "alloc")]
+    fn make_window_vartime(P: &Self) -> [Self; 8] {
+        let mut win = [Self::NEUTRAL; 8];
+        let Q = P.double();
+        win[0] = *P;
+        for i in 1..8 {
+            win[i] = win[i - 1] + Q;
+        }
+        win
+    }
+
+    /// Given scalars `u` and `v`, sets this point to `u*self + v*B`
+    /// (with `B` being the conventional generator of the prime order
+    /// subgroup).
+    ///
+    /// This operation is constant-time. It is faster than computing
+    /// `u*self` and `v*B` separately, since the doublings are shared.
+    /// If both scalars and the point are public, then
+    /// `set_mul_add_mulgen_vartime()` is faster.
+    pub fn set_mul_add_mulgen(&mut self, u: &Scalar, v: &Scalar) {
+        // Make a 5-bit window: win[i] contains (i+1)*P
+        let mut win = [Self::NEUTRAL; 16];
+        win[0] = *self;
+        for i in 1..8 {
+            let j = 2 * i;
+            win[j - 1] = win[i - 1].double();
+            win[j] = win[j - 1] + win[0];
+        }
+        win[15] = win[7].double();
+
+        // Recode the scalars into 51 signed digits each.
+        let sdu = Self::recode_scalar(u);
+        let sdv = Self::recode_scalar(v);
+
+        // Digits of u are processed in high-to-low order. Digits of v
+        // are split into four chunks (as in set_mulgen()), which are
+        // injected in the last 13 iterations.
+        #[cfg(not(feature = "small_tables_ed25519"))]
+        {
+            *self = Self::lookup(&win, sdu[50]);
+            for i in (0..50).rev() {
+                self.set_xdouble(5);
+                self.set_add(&Self::lookup(&win, sdu[i]));
+                if i < 13 {
+                    self.set_add_duif(&Self::lookup_duif(&PRECOMP_B, sdv[i]));
+                    self.set_add_duif(&Self::lookup_duif(&PRECOMP_B65, sdv[i + 13]));
+                    self.set_add_duif(&Self::lookup_duif(&PRECOMP_B130, sdv[i + 26]));
+                    if i < 12 {
+                        self.set_add_duif(&Self::lookup_duif(&PRECOMP_B195, sdv[i + 39]));
+                    }
+                }
+            }
+        }
+
+        // With the small tables, digits of v are processed along with
+        // those of u.
+        #[cfg(feature = "small_tables_ed25519")]
+        {
+            *self = Self::lookup(&win, sdu[50]);
+            self.set_add_duif(&Self::lookup_duif(&PRECOMP_B, sdv[50]));
+            for i in (0..50).rev() {
+                self.set_xdouble(5);
+                self.set_add(&Self::lookup(&win, sdu[i]));
+                self.set_add_duif(&Self::lookup_duif(&PRECOMP_B, sdv[i]));
+            }
+        }
+    }
+
+    /// Given scalars `u` and `v`, returns `u*self + v*B` (with `B` being
+    /// the conventional generator of the prime order subgroup).
+    ///
+    /// This operation is constant-time.
+    #[inline(always)]
+    pub fn mul_add_mulgen(self, u: &Scalar, v: &Scalar) -> Self {
+        let mut R = self;
+        R.set_mul_add_mulgen(u, v);
+        R
+    }
+
     /// Given scalars `u` and `v`, sets this point to `u*self + v*B`
     /// (with `B` being the conventional generator of the prime order
     /// subgroup).
@@ -1220,6 +1803,12 @@ impl Point {
             win2[i] = win2[i - 1] + Q2;
         }
 
+        // Window for (2^130)*B (recomputed with the small tables).
+        #[cfg(not(feature = "small_tables_ed25519"))]
+        let win_b130 = &PRECOMP_B130;
+        #[cfg(feature = "small_tables_ed25519")]
+        let win_b130 = &Point::make_precomp_b130();
+
         // Initialize the accumulator point with the top digits from
         // s0 (low half of sd0), which is the only of our four coefficients
         // that can use more than 128 digits.
@@ -1279,9 +1868,9 @@ impl Point {
             }
             if e2 != 0 {
                 if e2 > 0 {
-                    T.set_add_duif(&PRECOMP_B130[e2 as usize - 1]);
+                    T.set_add_duif(&win_b130[e2 as usize - 1]);
                 } else {
-                    T.set_sub_duif(&PRECOMP_B130[(-e2) as usize - 1]);
+                    T.set_sub_duif(&win_b130[(-e2) as usize - 1]);
                 }
             }
             if f1 != 0 {
@@ -1607,7 +2196,7 @@ impl SubAssign<&Point> for Point {
 /// entropy, preferably 256 bits). From the seed are derived the secret
 /// scalar and the public key. The public key is a curve point, that can
 /// be encoded as such.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy)]
 pub struct PrivateKey {
     s: Scalar,                  // secret scalar
     seed: [u8; 32],             // source seed
@@ -1615,6 +2204,22 @@ pub struct PrivateKey {
     pub public_key: PublicKey,  // public key
 }
 
+/// An expanded Ed25519 private key.
+///
+/// This contains the secret scalar and the signing prefix (the second
+/// half of the SHA-512 output over the seed), but not the seed itself.
+/// It is obtained from a `PrivateKey` with `PrivateKey::expand()`, or
+/// directly from the expanded material with `from_expanded()`, for keys
+/// which exist only in expanded form (e.g. keys derived with SLIP-10, or
+/// exported by some hardware security modules). Signatures are the
+/// same as with the source `PrivateKey`.
+#[derive(Clone, Copy)]
+pub struct ExpandedPrivateKey {
+    s: Scalar,                  // secret scalar
+    h: [u8; 32],                // signing prefix
+    pub public_key: PublicKey,  // public key
+}
+
 /// An Ed25519 public key.
 ///
 /// It wraps around the curve point, but also includes a copy of the
@@ -1626,6 +2231,24 @@ pub struct PublicKey {
     pub encoded: [u8; 32],
 }
 
+// Reads a stream to its end and feeds the data, by chunks of at most
+// 8 kB, to the provided closure (for sign_reader() and verify_reader()).
+// Interrupted reads are retried; other errors are returned.
+#[cfg(feature = "std")]
+fn read_chunks<R: Read, F: FnMut(&[u8])>(r: &mut R, mut f: F)
+    -> io::Result<()>
+{
+    let mut buf = [0u8; 8192];
+    loop {
+        match r.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => f(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
 /// Constant string "SigEd25519 no Ed25519 collisions".
 const HASH_HEAD: [u8; 32] = [
     0x53, 0x69, 0x67, 0x45, 0x64, 0x32, 0x35, 0x35,
@@ -1697,21 +2320,48 @@ impl PrivateKey {
         self.seed
     }
 
+    /// Gets the expanded private key (secret scalar and signing prefix).
+    ///
+    /// The expanded key produces the same signatures as this private key,
+    /// but does not contain the seed.
+    pub fn expand(self) -> ExpandedPrivateKey {
+        ExpandedPrivateKey { s: self.s, h: self.h, public_key: self.public_key }
+    }
+
     /// Signs a message.
     ///
     /// This is the "Ed25519" mode of RFC 8032 (no pre-hashing, no
     /// context), also known as "PureEdDSA on Curve25519".
     pub fn sign_raw(self, m: &[u8]) -> [u8; 64] {
-        self.sign_inner(false, 0, &[0u8; 0], m)
+        self.expand().sign_raw(m)
+    }
+
+    /// Signs a message, with blinding.
+    ///
+    /// This computes exactly the same signature as `sign_raw()`; see
+    /// `ExpandedPrivateKey::sign_raw_blinded()` for details.
+    pub fn sign_raw_blinded<T: CryptoRng + RngCore>(self, rng: &mut T,
+        m: &[u8]) -> [u8; 64]
+    {
+        self.expand().sign_raw_blinded(rng, m)
+    }
+
+    /// Signs a message, and verifies the signature before returning it.
+    ///
+    /// See `ExpandedPrivateKey::sign_raw_checked()` for details.
+    pub fn sign_raw_checked(self, m: &[u8]) -> Result<[u8; 64], SignError> {
+        self.expand().sign_raw_checked(m)
     }
 
     /// Signs a message with a context.
     ///
-    /// This is the "Ed25519cx" mode of RFC 8032 (no pre-hashing, a
-    /// context is provided). The context string MUST have length at most
-    /// 255 bytes; it SHOULD NOT be of length zero.
+    /// This is the "Ed25519ctx" mode of RFC 8032 (no pre-hashing, a
+    /// context is provided). The context string MUST have length 1 to
+    /// 255 bytes; a panic is triggered otherwise (an empty context would
+    /// not provide any domain separation from other Ed25519ctx uses, and
+    /// RFC 8032 recommends against it).
     pub fn sign_ctx(self, ctx: &[u8], m: &[u8]) -> [u8; 64] {
-        self.sign_inner(true, 0, ctx, m)
+        self.expand().sign_ctx(ctx, m)
     }
 
     /// Signs a pre-hashed message.
@@ -1722,61 +2372,390 @@ impl PrivateKey {
     /// SHA-512; the caller does the hashing itself). A context string is
     /// also provided; it MUST have length at most 255 bytes.
     pub fn sign_ph(self, ctx: &[u8], hm: &[u8]) -> [u8; 64] {
-        self.sign_inner(true, 1, ctx, hm)
+        self.expand().sign_ph(ctx, hm)
     }
 
-    /// Inner signature generation function.
-    fn sign_inner(self, dom: bool, phflag: u8, ctx: &[u8],
-                  m: &[u8]) -> [u8; 64]
+    /// Signs a message read from a stream.
+    ///
+    /// This produces the same signature as `sign_raw()` over the data,
+    /// without holding the whole message in memory; see
+    /// `ExpandedPrivateKey::sign_reader()` for details.
+    ///
+    /// This function is available only if `std` support was enabled.
+    #[cfg(feature = "std")]
+    pub fn sign_reader<R: Read + Seek>(self, r: &mut R)
+        -> io::Result<[u8; 64]>
     {
-        // SHA-512(dom2(F, C) || prefix || PH(M)) -> scalar r
-        let mut sh = Sha512::new();
-        if dom {
-            assert!(ctx.len() <= 255);
-            let clen = ctx.len() as u8;
-            sh.update(&HASH_HEAD);
-            sh.update(&[phflag]);
-            sh.update(&[clen]);
-            sh.update(ctx);
-        }
-        sh.update(&self.h);
-        sh.update(m);
-        let hv1 = sh.finalize_reset();
-        let r = Scalar::decode_reduce(&hv1);
-
-        // R = r*B
-        let R = Point::mulgen(&r);
-        let R_enc = R.encode();
-
-        // SHA-512(dom2(F, C) || R || A || PH(M)) -> scalar k
-        if dom {
-            assert!(ctx.len() <= 255);
-            let clen = ctx.len() as u8;
-            sh.update(&HASH_HEAD);
-            sh.update(&[phflag]);
-            sh.update(&[clen]);
-            sh.update(ctx);
-        }
-        sh.update(&R_enc);
-        sh.update(&self.public_key.encoded);
-        sh.update(m);
-        let hv2 = sh.finalize();
-        let k = Scalar::decode_reduce(&hv2);
+        self.expand().sign_reader(r)
+    }
+}
 
-        // Signature is (R, S) with S = r + k*s mod L
-        let mut sig = [0u8; 64];
-        sig[0..32].copy_from_slice(&R_enc);
-        sig[32..64].copy_from_slice(&(r + k * self.s).encode());
+impl ExpandedPrivateKey {
 
-        sig
+    /// Instantiates an expanded private key from the secret scalar and
+    /// the signing prefix.
+    ///
+    /// `scalar` is the first half of the SHA-512 output over the seed,
+    /// after pruning (as per RFC 8032, section 5.1.5); it is interpreted
+    /// as an integer in unsigned little-endian convention and reduced
+    /// modulo `L`. No pruning is applied by this function. `prefix` is
+    /// the second half of the SHA-512 output. The public key is
+    /// recomputed from the secret scalar.
+    pub fn from_expanded(scalar: &[u8; 32], prefix: &[u8; 32]) -> Self {
+        let s = Scalar::decode_reduce(&scalar[..]);
+        let public_key = PublicKey::from_point(&Point::mulgen(&s));
+        Self { s, h: *prefix, public_key }
     }
-}
 
-impl PublicKey {
+    /// Signs a message.
+    ///
+    /// This is the "Ed25519" mode of RFC 8032 (no pre-hashing, no
+    /// context), also known as "PureEdDSA on Curve25519".
+    pub fn sign_raw(self, m: &[u8]) -> [u8; 64] {
+        self.sign_inner(false, 0, &[0u8; 0], m, None)
+    }
 
-    /// Creates an instance from a curve point.
-    pub fn from_point(point: &Point) -> Self {
-        Self { point: *point, encoded: point.encode() }
+    /// Signs a message, with blinding.
+    ///
+    /// This computes exactly the same signature as `sign_raw()` (the
+    /// signature process remains deterministic), but the multiplication
+    /// of the generator by the per-signature nonce is blinded with
+    /// randomness from the provided RNG (see `Point::mulgen_blinded()`).
+    /// This is meant for keys used on hardware shared with potential
+    /// attackers, where power or electromagnetic traces may be observed.
+    pub fn sign_raw_blinded<T: CryptoRng + RngCore>(self, rng: &mut T,
+        m: &[u8]) -> [u8; 64]
+    {
+        self.sign_inner(false, 0, &[0u8; 0], m, Some(&Blinding::new(rng)))
+    }
+
+    /// Signs a message, and verifies the signature before returning it.
+    ///
+    /// The signature is computed as with `sign_raw()`, then verified
+    /// against the public key. If the verification fails, which may
+    /// happen only if the computation was disrupted (e.g. by a hardware
+    /// glitch, or a deliberate fault injection), then
+    /// `SignError::VerificationFailed` is returned and the faulty
+    /// signature is discarded: since Ed25519 signatures are
+    /// deterministic, a faulty signature and a correct one for the same
+    /// message may reveal the private key.
+    ///
+    /// Verification is more expensive than signing; the cost of this
+    /// function is a bit more than three times that of `sign_raw()`.
+    pub fn sign_raw_checked(self, m: &[u8]) -> Result<[u8; 64], SignError> {
+        self.check_signature(self.sign_raw(m), m)
+    }
+
+    // Verify-after-sign: the signature sig (over the message m) is
+    // returned only if it is valid for the public key.
+    fn check_signature(self, sig: [u8; 64], m: &[u8])
+        -> Result<[u8; 64], SignError>
+    {
+        if self.public_key.verify_raw(&sig, m) {
+            Ok(sig)
+        } else {
+            Err(SignError::VerificationFailed)
+        }
+    }
+
+    /// Signs a message with a context.
+    ///
+    /// This is the "Ed25519ctx" mode of RFC 8032 (no pre-hashing, a
+    /// context is provided). The context string MUST have length 1 to
+    /// 255 bytes; a panic is triggered otherwise (an empty context would
+    /// not provide any domain separation from other Ed25519ctx uses, and
+    /// RFC 8032 recommends against it).
+    pub fn sign_ctx(self, ctx: &[u8], m: &[u8]) -> [u8; 64] {
+        assert!(!ctx.is_empty() && ctx.len() <= 255);
+        self.sign_inner(true, 0, ctx, m, None)
+    }
+
+    /// Signs a pre-hashed message.
+    ///
+    /// This is the "Ed25519ph" mode of RFC 8032 (message is pre-hashed),
+    /// also known as "HashEdDSA on Curve25519". The hashed message `hm`
+    /// is provided (presumably, that hash value was obtained with
+    /// SHA-512; the caller does the hashing itself). A context string is
+    /// also provided; it MUST have length at most 255 bytes.
+    pub fn sign_ph(self, ctx: &[u8], hm: &[u8]) -> [u8; 64] {
+        self.sign_inner(true, 1, ctx, hm, None)
+    }
+
+    /// Signs a message read from a stream.
+    ///
+    /// This is the "Ed25519" mode of RFC 8032: the signature is the same
+    /// as with `sign_raw()` over the data, which runs from the current
+    /// position of the reader to the end of the stream. Since Ed25519
+    /// hashes the message twice (once for the nonce, once for the
+    /// challenge), the data is read twice: the reader is rewound to its
+    /// starting position for the second pass, and is left at the end of
+    /// the stream afterwards. Data is read by chunks of 8 kB; memory
+    /// usage does not depend on the message length.
+    ///
+    /// If the data read in the second pass differs from that of the
+    /// first pass (e.g. the file was modified concurrently), then an
+    /// error of kind `InvalidData` is returned: a signature computed
+    /// with the nonce of one message and the challenge of another would
+    /// reveal the private key. Errors from the reader are propagated.
+    ///
+    /// This function is available only if `std` support was enabled.
+    #[cfg(feature = "std")]
+    pub fn sign_reader<R: Read + Seek>(self, r: &mut R)
+        -> io::Result<[u8; 64]>
+    {
+        // First pass: SHA-512(prefix || M) -> scalar r
+        let start = r.stream_position()?;
+        let mut sh1 = Sha512::new();
+        sh1.update(self.h);
+        read_chunks(r, |d| sh1.update(d))?;
+        let hv1 = sh1.finalize();
+        let ss = SignSession::from_nonce(&self,
+            &Scalar::decode_reduce(&hv1));
+
+        // Second pass: SHA-512(R || A || M) -> scalar k; the nonce hash
+        // is recomputed to check that the data did not change.
+        r.seek(SeekFrom::Start(start))?;
+        let mut sh1 = Sha512::new();
+        sh1.update(self.h);
+        let mut sh2 = Sha512::new();
+        sh2.update(ss.R_enc);
+        sh2.update(self.public_key.encoded);
+        read_chunks(r, |d| {
+            sh1.update(d);
+            sh2.update(d);
+        })?;
+        if !crate::ct::equals(&sh1.finalize(), &hv1) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                "message data changed between the two passes"));
+        }
+        Ok(ss.finish_with_challenge(&Scalar::decode_reduce(&sh2.finalize())))
+    }
+
+    /// Inner signature generation function (with optional blinding).
+    fn sign_inner(self, dom: bool, phflag: u8, ctx: &[u8],
+                  m: &[u8], blind: Option<&Blinding>) -> [u8; 64]
+    {
+        // SHA-512(dom2(F, C) || prefix || PH(M)) -> scalar r
+        let mut sh = Sha512::new();
+        if dom {
+            assert!(ctx.len() <= 255);
+            let clen = ctx.len() as u8;
+            sh.update(&HASH_HEAD);
+            sh.update(&[phflag]);
+            sh.update(&[clen]);
+            sh.update(ctx);
+        }
+        sh.update(&self.h);
+        sh.update(m);
+        let hv1 = sh.finalize();
+        let r = Scalar::decode_reduce(&hv1);
+
+        let R = match blind {
+            Some(b) => Point::mulgen_with_blinding(&r, b),
+            None => Point::mulgen(&r),
+        };
+        SignSession::from_nonce_point(&self, &r, &R)
+            .finish_inner(dom, phflag, ctx, m)
+    }
+}
+
+// Private keys are not displayable, and their debug output shows only
+// the public key (secret values are redacted).
+
+impl core::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl core::fmt::Debug for ExpandedPrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ExpandedPrivateKey")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+// Random values for blinded scalar multiplications: the scalar n is
+// split into (n - r) + r, and the initial points of the two partial
+// products have their coordinates scaled by z1 and z2 (both non-zero).
+struct Blinding {
+    r: Scalar,
+    z1: GF25519,
+    z2: GF25519,
+}
+
+impl Blinding {
+
+    fn new<T: CryptoRng + RngCore>(rng: &mut T) -> Self {
+        let mut tmp = [0u8; 64];
+        rng.fill_bytes(&mut tmp);
+        let r = Scalar::decode_reduce(&tmp);
+        rng.fill_bytes(&mut tmp);
+        let mut z1 = GF25519::decode_reduce(&tmp);
+        z1.set_cond(&GF25519::ONE, z1.iszero());
+        rng.fill_bytes(&mut tmp);
+        let mut z2 = GF25519::decode_reduce(&tmp);
+        z2.set_cond(&GF25519::ONE, z2.iszero());
+        Self { r, z1, z2 }
+    }
+}
+
+// ========================================================================
+
+/// Two-step Ed25519 signature generation.
+///
+/// The usual signing functions derive the per-signature nonce `r`
+/// deterministically from the private key and the message, which means
+/// that the nonce point `R = r*B` (the first half of the signature) is
+/// known only once the whole message is known. A `SignSession` instead
+/// commits to the nonce first: `start()` computes `R` from the provided
+/// nonce material and returns its encoding; the signature is then
+/// completed with `finish()` (or `finish_ctx()`, `finish_ph()`) once the
+/// message is available. The session owns the secret nonce and is
+/// consumed by the finishing function, so that a nonce cannot be used
+/// for two distinct messages (which would reveal the private key); the
+/// type intentionally implements neither `Clone` nor `Copy`:
+///
+/// ```compile_fail
+/// use crrl::ed25519::{PrivateKey, SignSession};
+/// let skey = PrivateKey::from_seed(&[0u8; 32]);
+/// let (session, _R) = SignSession::start(&skey, &[1u8; 64]);
+/// let sig1 = session.finish(b"message 1");
+/// let sig2 = session.finish(b"message 2");  // error: use of moved value
+/// ```
+///
+/// SECURITY WARNING: the nonce material MUST be either high-entropy
+/// (at least 64 bytes obtained from a cryptographically secure random
+/// source), or derived deterministically in a way which guarantees
+/// that the same nonce is never used with two different messages, e.g.
+/// as in RFC 8032, where the nonce material is SHA-512 computed over
+/// the signing prefix and the message. Reusing nonce material for two
+/// distinct messages, or using biased or low-entropy material, leaks
+/// the private key.
+pub struct SignSession {
+    s: Scalar,                  // secret scalar
+    r: Scalar,                  // secret nonce
+    R_enc: [u8; 32],            // encoded nonce point
+    public_key: PublicKey,      // public key
+}
+
+impl SignSession {
+
+    /// Starts a signature generation with the provided private key and
+    /// nonce material.
+    ///
+    /// The nonce material is interpreted as an integer (unsigned
+    /// little-endian convention) and reduced modulo `L`; it should have
+    /// length at least 64 bytes so that the nonce is uniform. The
+    /// session and the encoded nonce point `R` are returned.
+    pub fn start(key: &PrivateKey, nonce_material: &[u8])
+        -> (Self, [u8; 32])
+    {
+        let r = Scalar::decode_reduce(nonce_material);
+        let ss = Self::from_nonce(&key.expand(), &r);
+        let R_enc = ss.R_enc;
+        (ss, R_enc)
+    }
+
+    // Builds a session from the expanded private key and the nonce.
+    fn from_nonce(key: &ExpandedPrivateKey, r: &Scalar) -> Self {
+        Self::from_nonce_point(key, r, &Point::mulgen(r))
+    }
+
+    // Builds a session from the expanded private key, the nonce r, and
+    // the nonce point R = r*B.
+    fn from_nonce_point(key: &ExpandedPrivateKey, r: &Scalar, R: &Point)
+        -> Self
+    {
+        Self {
+            s: key.s,
+            r: *r,
+            R_enc: R.encode(),
+            public_key: key.public_key,
+        }
+    }
+
+    /// Gets the encoded nonce point `R` (first half of the signature).
+    pub fn nonce_point(&self) -> [u8; 32] {
+        self.R_enc
+    }
+
+    /// Completes the signature on a message.
+    ///
+    /// This is the "Ed25519" mode of RFC 8032 (no pre-hashing, no
+    /// context).
+    pub fn finish(self, m: &[u8]) -> [u8; 64] {
+        self.finish_inner(false, 0, &[0u8; 0], m)
+    }
+
+    /// Completes the signature on a message, with a context.
+    ///
+    /// This is the "Ed25519ctx" mode of RFC 8032. The context string
+    /// MUST have length 1 to 255 bytes; a panic is triggered otherwise.
+    pub fn finish_ctx(self, ctx: &[u8], m: &[u8]) -> [u8; 64] {
+        assert!(!ctx.is_empty() && ctx.len() <= 255);
+        self.finish_inner(true, 0, ctx, m)
+    }
+
+    /// Completes the signature on a pre-hashed message.
+    ///
+    /// This is the "Ed25519ph" mode of RFC 8032. The context string
+    /// MUST have length at most 255 bytes.
+    pub fn finish_ph(self, ctx: &[u8], hm: &[u8]) -> [u8; 64] {
+        self.finish_inner(true, 1, ctx, hm)
+    }
+
+    // Inner signature completion function.
+    fn finish_inner(self, dom: bool, phflag: u8, ctx: &[u8],
+                    m: &[u8]) -> [u8; 64]
+    {
+        // SHA-512(dom2(F, C) || R || A || PH(M)) -> scalar k
+        let mut sh = Sha512::new();
+        if dom {
+            assert!(ctx.len() <= 255);
+            let clen = ctx.len() as u8;
+            sh.update(HASH_HEAD);
+            sh.update([phflag]);
+            sh.update([clen]);
+            sh.update(ctx);
+        }
+        sh.update(self.R_enc);
+        sh.update(self.public_key.encoded);
+        sh.update(m);
+        let hv2 = sh.finalize();
+        let k = Scalar::decode_reduce(&hv2);
+        self.finish_with_challenge(&k)
+    }
+
+    // Completes the signature, given the challenge k.
+    fn finish_with_challenge(self, k: &Scalar) -> [u8; 64] {
+        // Signature is (R, S) with S = r + k*s mod L
+        let mut sig = [0u8; 64];
+        sig[0..32].copy_from_slice(&self.R_enc);
+        sig[32..64].copy_from_slice(&(self.r + k * self.s).encode());
+
+        sig
+    }
+}
+
+impl core::fmt::Debug for SignSession {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SignSession")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+// ========================================================================
+
+impl PublicKey {
+
+    /// Creates an instance from a curve point.
+    pub fn from_point(point: &Point) -> Self {
+        Self { point: *point, encoded: point.encode() }
     }
 
     // Decodes the provided bytes as a public key.
@@ -1796,6 +2775,20 @@ impl PublicKey {
         Some(Self { point, encoded })
     }
 
+    /// Decodes the provided bytes as a public key, with the lenient
+    /// rules of ZIP-215 (non-canonical encodings are accepted).
+    ///
+    /// The `encoded` field receives a copy of the source bytes (which
+    /// may differ from the canonical encoding of the point); this is
+    /// what ZIP-215 verification hashes. Keys decoded with this function
+    /// should be used only with `verify_zip215()`.
+    pub fn decode_zip215(buf: &[u8]) -> Option<PublicKey> {
+        let point = Point::decode_zip215(buf)?;
+        let mut encoded = [0u8; 32];
+        encoded[..].copy_from_slice(&buf[0..32]);
+        Some(Self { point, encoded })
+    }
+
     /// Encodes the key into exactly 32 bytes.
     ///
     /// This simply returns the contents of the `encoded` field.
@@ -1803,6 +2796,20 @@ impl PublicKey {
         self.encoded
     }
 
+    /// Tests whether this public key is weak.
+    ///
+    /// A public key is weak if its point has low order (including the
+    /// neutral), or is not in the prime-order subgroup (i.e. it has a
+    /// torsion component). Such keys can be the result of an adversarial
+    /// choice; they are accepted by the signature verification functions
+    /// (except `verify_strict()`), but protocols that assume that keys
+    /// are in the prime-order subgroup should reject them. This function
+    /// is constant-time (see `Point::has_low_order()` and
+    /// `Point::is_in_subgroup()`).
+    pub fn is_weak(self) -> bool {
+        (self.point.has_low_order() | !self.point.is_in_subgroup()) != 0
+    }
+
     /// Verifies a signature on a message.
     ///
     /// This is the "Ed25519" mode of RFC 8032 (no pre-hashing, no
@@ -1815,16 +2822,59 @@ impl PublicKey {
         self.verify_inner(sig, false, 0, &[0u8; 0], m)
     }
 
+    /// Verifies a signature on a message read from a stream.
+    ///
+    /// This is the "Ed25519" mode of RFC 8032, and accepts exactly the
+    /// same signatures as `verify_raw()` over the data, which runs from
+    /// the current position of the reader to the end of the stream. The
+    /// data is read once, by chunks of 8 kB. If the signature cannot be
+    /// decoded, then `Ok(false)` is returned without reading the stream.
+    /// Errors from the reader are propagated.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    ///
+    /// This function is available only if `std` support was enabled.
+    #[cfg(feature = "std")]
+    pub fn verify_reader<R: Read>(self, sig: &[u8], r: &mut R)
+        -> io::Result<bool>
+    {
+        if sig.len() != 64 {
+            return Ok(false);
+        }
+        let R_enc = &sig[0..32];
+        let R = match Point::decode(R_enc) {
+            Some(R) => R,
+            None    => { return Ok(false); }
+        };
+        let (S, ok) = Scalar::decode32(&sig[32..64]);
+        if ok == 0 {
+            return Ok(false);
+        }
+
+        // SHA-512(R || A || M) -> scalar k
+        let mut sh = Sha512::new();
+        sh.update(R_enc);
+        sh.update(self.encoded);
+        read_chunks(r, |d| sh.update(d))?;
+        let k = Scalar::decode_reduce(&sh.finalize());
+        Ok(self.point.verify_helper_vartime(&R, &S, &k))
+    }
+
     /// Verifies a signature on a message.
     ///
-    /// This is the "Ed25519cx" mode of RFC 8032 (no pre-hashing, a
-    /// context is provided). The context string MUST have length at most
-    /// 255 bytes; it SHOULD NOT be of length zero. Return value is
-    /// `true` on a valid signature, `false` otherwise.
+    /// This is the "Ed25519ctx" mode of RFC 8032 (no pre-hashing, a
+    /// context is provided). The context string must have length 1 to
+    /// 255 bytes; other context lengths are rejected (`false` is
+    /// returned), consistently with `sign_ctx()`. Return value is `true`
+    /// on a valid signature, `false` otherwise.
     ///
     /// Note: this function is not constant-time; it assumes that the
     /// public key and signature value are public data.
     pub fn verify_ctx(self, sig: &[u8], ctx: &[u8], m: &[u8]) -> bool {
+        if ctx.is_empty() || ctx.len() > 255 {
+            return false;
+        }
         self.verify_inner(sig, true, 0, ctx, m)
     }
 
@@ -1843,6 +2893,147 @@ impl PublicKey {
         self.verify_inner(sig, true, 1, ctx, hm)
     }
 
+    /// Verifies a signature on a message, in constant time.
+    ///
+    /// This is the "Ed25519" mode of RFC 8032 (no pre-hashing, no
+    /// context), with the same validation rules as `verify_raw()`
+    /// (canonical encodings, cofactored verification equation); both
+    /// functions always return the same result. Unlike `verify_raw()`,
+    /// this function is constant-time: neither the signature value nor
+    /// the outcome (including whether the encodings were valid) leaks
+    /// through timing-based side channels; only the signature length is
+    /// considered public.
+    ///
+    /// All other verification functions are variable-time, which is
+    /// safe since all inputs to signature verification are normally
+    /// public; this function is meant for the rare cases where the
+    /// signature or its validity must remain secret. It is about 40%
+    /// slower than `verify_raw()`.
+    pub fn verify_ct(self, sig: &[u8], m: &[u8]) -> bool {
+        if sig.len() != 64 {
+            return false;
+        }
+        let R_enc = &sig[0..32];
+        let mut R = Point::NEUTRAL;
+        let r_ok = R.set_decode(R_enc);
+        let (S, s_ok) = Scalar::decode32(&sig[32..64]);
+        let mut sh = Sha512::new();
+        sh.update(R_enc);
+        sh.update(self.encoded);
+        sh.update(m);
+        let k = Scalar::decode_reduce(&sh.finalize());
+
+        // T = S*B - k*A - R; the signature is valid if 8*T = 0.
+        let T = self.point.mul_add_mulgen(&-k, &S) - R;
+        (r_ok & s_ok & T.xdouble(3).isneutral()) != 0
+    }
+
+    /// Verifies a signature on a message, with the ZIP-215 rules.
+    ///
+    /// This is the "Ed25519" mode of RFC 8032 (no pre-hashing, no
+    /// context), with the validation rules of [ZIP-215], which are
+    /// meant for consensus systems, where all validators must agree
+    /// exactly on which signatures are valid:
+    ///
+    ///   - The `R` point may use a non-canonical encoding (see
+    ///     `Point::set_decode_zip215()`); the public key should have been
+    ///     obtained with `decode_zip215()`. The hash input uses the
+    ///     encodings as received.
+    ///
+    ///   - The `S` scalar must be canonically encoded (lower than `L`).
+    ///
+    ///   - The cofactored verification equation is used; low-order
+    ///     points, and points outside of the prime-order subgroup, are
+    ///     accepted for both `R` and the public key.
+    ///
+    /// The only difference with `verify_raw()` is thus the acceptance of
+    /// non-canonical point encodings.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    ///
+    /// [ZIP-215]: https://zips.z.cash/zip-0215
+    pub fn verify_zip215(self, sig: &[u8], m: &[u8]) -> bool {
+        if sig.len() != 64 {
+            return false;
+        }
+        let R_enc = &sig[0..32];
+        let R = match Point::decode_zip215(R_enc) {
+            Some(R) => R,
+            None    => { return false; }
+        };
+        let (S, ok) = Scalar::decode32(&sig[32..64]);
+        if ok == 0 {
+            return false;
+        }
+        let mut sh = Sha512::new();
+        sh.update(R_enc);
+        sh.update(self.encoded);
+        sh.update(m);
+        let k = Scalar::decode_reduce(&sh.finalize());
+        self.point.verify_helper_vartime(&R, &S, &k)
+    }
+
+    /// Verifies a signature on a message, with the strict rules.
+    ///
+    /// This is the "Ed25519" mode of RFC 8032 (no pre-hashing, no
+    /// context), with the most conservative validation rules:
+    ///
+    ///   - Both the `R` point and the public key must be canonically
+    ///     encoded, and the `S` scalar must be lower than `L`.
+    ///
+    ///   - Both the `R` point and the public key must be in the
+    ///     subgroup of order `L`, and must not be the neutral point;
+    ///     low-order and mixed-order points are rejected.
+    ///
+    ///   - The cofactorless verification equation is used (since all
+    ///     points are in the prime-order subgroup, it is equivalent to
+    ///     the cofactored equation).
+    ///
+    /// A signature accepted by this function is also accepted by
+    /// `verify_raw()`, but the converse is not true; see the module
+    /// documentation for a comparison of the verification modes.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_strict(self, sig: &[u8], m: &[u8]) -> bool {
+        if sig.len() != 64 {
+            return false;
+        }
+
+        // The public key may have been decoded with decode_zip215();
+        // we check that its encoding is canonical.
+        if self.point.encode() != self.encoded {
+            return false;
+        }
+        if self.point.has_low_order() != 0
+            || self.point.is_in_subgroup() == 0
+        {
+            return false;
+        }
+
+        let R_enc = &sig[0..32];
+        let R = match Point::decode(R_enc) {
+            Some(R) => R,
+            None    => { return false; }
+        };
+        if R.has_low_order() != 0 || R.is_in_subgroup() == 0 {
+            return false;
+        }
+        let (S, ok) = Scalar::decode32(&sig[32..64]);
+        if ok == 0 {
+            return false;
+        }
+        let mut sh = Sha512::new();
+        sh.update(R_enc);
+        sh.update(self.encoded);
+        sh.update(m);
+        let k = Scalar::decode_reduce(&sh.finalize());
+
+        // Cofactorless equation: R = S*B - k*A
+        self.point.mul_add_mulgen_vartime(&-k, &S).equals(R) != 0
+    }
+
     /// Inner signature verification function.
     fn verify_inner(self, sig: &[u8], dom: bool, phflag: u8, ctx: &[u8],
                     m: &[u8]) -> bool
@@ -2288,26 +3479,938 @@ impl PublicKey {
     }
 }
 
-// ========================================================================
+// Public keys are displayed as the hexadecimal string of their 32-byte
+// encoding. Parsing accepts exactly the encodings that `decode()`
+// accepts; the cause of a failure is reported as with `decode_verbose()`.
 
-// We hardcode known multiples of the points B, (2^65)*B, (2^130)*B
-// and (2^195)*B, with B being the conventional base point. These are
-// used to speed mulgen() operations up. The points are moreover stored
-// in a three-coordinate format (which was attributed to Niels Duif in
-// the original Ed25519 source code): we normalize the point to ensure
-// that Z = 1, and we store X+Y, X-Y and 2d*T (with T = X*Y, since Z = 1).
-// It can easily be seen that such values are in fact intermediaries used
-// in the plain addition formulas.
+impl core::fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encoded)
+    }
+}
 
-/// A point in Duif format (y + x, y - x, 2*d*x*y).
-#[derive(Clone, Copy, Debug)]
-struct PointDuif {
-    ypx: GF25519,
-    ymx: GF25519,
-    t2d: GF25519,
+impl core::fmt::LowerHex for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encoded)
+    }
 }
 
-impl PointDuif {
+impl core::str::FromStr for PublicKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; 32];
+        crate::hex::parse_fixed(s, &mut buf)?;
+        match Self::decode(&buf) {
+            Some(pk) => Ok(pk),
+            None => Err(ParseError::BadPoint(
+                Point::decode_verbose(&buf).err()
+                    .unwrap_or(DecodeError::NotOnCurve))),
+        }
+    }
+}
+
+// ========================================================================
+
+/// Verifies a batch of Ed25519 signatures.
+///
+/// Each entry consists of a public key, a message, and a signature; this
+/// is the "Ed25519" mode of RFC 8032 (no pre-hashing, no context). The
+/// returned value is `true` if all signatures are valid, `false`
+/// otherwise (an empty batch is valid).
+///
+/// The verification equations are combined with random 128-bit
+/// coefficients `z_i` into a single multi-scalar multiplication:
+///
+/// ```text
+///     (sum z_i*S_i)*B - sum z_i*R_i - sum (z_i*k_i)*A_i
+/// ```
+///
+/// and the result is checked to be a low-order point. The coefficients
+/// are derived deterministically from `rng_seed` and the batch contents,
+/// so that no random generator is needed; however, the seed MUST be
+/// unpredictable by whoever produced the signatures (e.g. it is a fresh
+/// random value, or a local secret).
+///
+/// If the batch is accepted, then every signature in the batch passes
+/// the cofactored verification equation used by `verify_raw()`, except
+/// with negligible probability (about 2^-127). Conversely, signatures
+/// which are all accepted by `verify_raw()` are always accepted as a
+/// batch. Batch and individual verification thus agree, including for
+/// signatures that involve low-order points or points outside of the
+/// prime-order subgroup. When a batch fails, `verify_batch_find_invalid()`
+/// can be used to identify the invalid signatures.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_batch(entries: &[(PublicKey, &[u8], &[u8])],
+    rng_seed: &[u8; 32]) -> bool
+{
+    verify_batch_inner(entries.len(),
+        |i| (&entries[i].0, entries[i].1, entries[i].2), rng_seed)
+}
+
+// Batch verification core; entry i (for 0 <= i < n) is obtained with
+// get(i), as a (public key, message, signature) triplet.
+#[cfg(feature = "alloc")]
+fn verify_batch_inner<'a, F>(n: usize, get: F, rng_seed: &[u8; 32]) -> bool
+    where F: Fn(usize) -> (&'a PublicKey, &'a [u8], &'a [u8])
+{
+    // Decode all signatures and compute the challenges k_i.
+    let mut Rs = Vec::with_capacity(n);
+    let mut Ss = Vec::with_capacity(n);
+    let mut ks = Vec::with_capacity(n);
+    let mut sh = Sha512::new();
+    for i in 0..n {
+        let (pkey, m, sig) = get(i);
+        if sig.len() != 64 {
+            return false;
+        }
+        let R = match Point::decode(&sig[0..32]) {
+            Some(R) => R,
+            None    => { return false; }
+        };
+        let (S, ok) = Scalar::decode32(&sig[32..64]);
+        if ok == 0 {
+            return false;
+        }
+        sh.update(&sig[0..32]);
+        sh.update(pkey.encoded);
+        sh.update(m);
+        Rs.push(R);
+        Ss.push(S);
+        ks.push(Scalar::decode_reduce(&sh.finalize_reset()));
+    }
+
+    // The coefficients are derived from the seed and a hash of the batch
+    // contents (each k_i covers the public key, R_i and the message).
+    sh.update(b"crrl-ed25519-batch");
+    sh.update(rng_seed);
+    sh.update((n as u64).to_le_bytes());
+    for (i, k) in ks.iter().enumerate() {
+        sh.update(get(i).2);
+        sh.update(k.encode());
+    }
+    let th = sh.finalize_reset();
+
+    // Terms for points R_i (128-bit coefficients) and A_i (full scalars).
+    // For each point, we compute a window (odd multiples of the point,
+    // for 5-bit wNAF) and recode the coefficient. The coefficient of B is
+    // accumulated and processed with the precomputed tables.
+    let mut ss = Scalar::ZERO;
+    let mut winR = Vec::with_capacity(n);
+    let mut winA = Vec::with_capacity(n);
+    let mut sdR = Vec::with_capacity(n);
+    let mut sdA = Vec::with_capacity(n);
+    let mut zz = [0u8; 64];
+    for i in 0..n {
+        if (i & 3) == 0 {
+            sh.update(th);
+            sh.update((i as u64).to_le_bytes());
+            zz[..].copy_from_slice(&sh.finalize_reset());
+        }
+        // Coefficients are forced to be odd (hence non-zero).
+        let j = (i & 3) << 4;
+        let z = u128::from_le_bytes(
+            *<&[u8; 16]>::try_from(&zz[j..(j + 16)]).unwrap()) | 1;
+        let zs = Scalar::from_u128(z);
+        ss += zs * Ss[i];
+        winR.push(Point::make_window_vartime(&-Rs[i]));
+        sdR.push(Point::recode_u128_NAF(z));
+        winA.push(Point::make_window_vartime(&get(i).0.point));
+        sdA.push(Point::recode_scalar_NAF(&-(zs * ks[i])));
+    }
+    let sd0 = Point::recode_scalar_NAF(&ss);
+
+    // Straus' algorithm: all doublings are shared.
+    let mut T = Point::NEUTRAL;
+    let mut ndbl = 0u32;
+    for i in (0..254).rev() {
+        ndbl += 1;
+        let e = sd0[i];
+        let mut nz = e != 0;
+        if !nz && i < 130 {
+            nz = sdR.iter().any(|sd| sd[i] != 0);
+        }
+        if !nz {
+            nz = sdA.iter().any(|sd| sd[i] != 0);
+        }
+        if !nz {
+            continue;
+        }
+        T.set_xdouble(ndbl);
+        ndbl = 0;
+
+        if e != 0 {
+            if e > 0 {
+                T.set_add_duif(&PRECOMP_B[e as usize - 1]);
+            } else {
+                T.set_sub_duif(&PRECOMP_B[(-e) as usize - 1]);
+            }
+        }
+        for j in 0..n {
+            let f = if i < 130 { sdR[j][i] } else { 0 };
+            if f > 0 {
+                T.set_add(&winR[j][f as usize >> 1]);
+            } else if f < 0 {
+                T.set_sub(&winR[j][(-f) as usize >> 1]);
+            }
+            let f = sdA[j][i];
+            if f > 0 {
+                T.set_add(&winA[j][f as usize >> 1]);
+            } else if f < 0 {
+                T.set_sub(&winA[j][(-f) as usize >> 1]);
+            }
+        }
+    }
+
+    // Remaining doublings are skipped: they do not change whether T is
+    // a low-order point.
+    T.has_low_order() == 0xFFFFFFFF
+}
+
+/// Finds the invalid signatures in a batch.
+///
+/// This function returns the indices (in ascending order) of the
+/// entries whose signature is invalid, i.e. rejected by `verify_raw()`;
+/// the returned vector is empty if all signatures are valid. The batch
+/// is split recursively into halves, and each half is verified with
+/// `verify_batch()` (with the provided seed), so that a large batch
+/// with few invalid signatures is processed efficiently.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_batch_find_invalid(entries: &[(PublicKey, &[u8], &[u8])],
+    rng_seed: &[u8; 32]) -> Vec<usize>
+{
+    fn find_inner(entries: &[(PublicKey, &[u8], &[u8])], off: usize,
+        rng_seed: &[u8; 32], bad: &mut Vec<usize>)
+    {
+        if verify_batch(entries, rng_seed) {
+            return;
+        }
+        // A batch of size 1 is equivalent to individual verification
+        // (the coefficient is non-zero and lower than L).
+        if entries.len() == 1 {
+            bad.push(off);
+            return;
+        }
+        let h = entries.len() >> 1;
+        find_inner(&entries[..h], off, rng_seed, bad);
+        find_inner(&entries[h..], off + h, rng_seed, bad);
+    }
+
+    let mut bad = Vec::new();
+    find_inner(entries, 0, rng_seed, &mut bad);
+    bad
+}
+
+/// Verifies a batch of Ed25519 signatures over the same message.
+///
+/// Each entry consists of a public key and a signature, and all
+/// signatures are verified against the single message `m`; this is the
+/// typical situation of votes or co-signatures, where many distinct
+/// signers sign the same data. The result is identical to that of
+/// `verify_batch()` on the same entries with the message `m` repeated,
+/// but the entries need not be assembled into triplets by the caller.
+/// The message still contributes to each challenge `k_i`: with
+/// SHA-512(R || A || M), the message comes after the per-signature
+/// values, and its hashing cannot be shared between entries.
+///
+/// The same guarantees as with `verify_batch()` apply (agreement with
+/// `verify_raw()`, except with negligible probability); the `rng_seed`
+/// MUST be unpredictable by whoever produced the signatures. An empty
+/// batch is valid.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_same_message(m: &[u8], entries: &[(PublicKey, &[u8])],
+    rng_seed: &[u8; 32]) -> bool
+{
+    verify_batch_inner(entries.len(),
+        |i| (&entries[i].0, m, entries[i].1), rng_seed)
+}
+
+/// Finds the invalid signatures in a batch over the same message.
+///
+/// This is the counterpart of `verify_batch_find_invalid()` for
+/// `verify_same_message()`: it returns the indices (in ascending order)
+/// of the entries whose signature is rejected by `verify_raw()` for the
+/// message `m`. The returned vector is empty if all signatures are
+/// valid.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_same_message_find_invalid(m: &[u8],
+    entries: &[(PublicKey, &[u8])], rng_seed: &[u8; 32]) -> Vec<usize>
+{
+    fn find_inner(m: &[u8], entries: &[(PublicKey, &[u8])], off: usize,
+        rng_seed: &[u8; 32], bad: &mut Vec<usize>)
+    {
+        if verify_same_message(m, entries, rng_seed) {
+            return;
+        }
+        if entries.len() == 1 {
+            bad.push(off);
+            return;
+        }
+        let h = entries.len() >> 1;
+        find_inner(m, &entries[..h], off, rng_seed, bad);
+        find_inner(m, &entries[h..], off + h, rng_seed, bad);
+    }
+
+    let mut bad = Vec::new();
+    find_inner(m, entries, 0, rng_seed, &mut bad);
+    bad
+}
+
+// ========================================================================
+
+/// Public key with precomputed tables, for faster signature verification.
+///
+/// When many signatures must be verified against the same public key
+/// `A`, the per-verification setup work (the window of multiples of `A`,
+/// and the reduction of the challenge scalar into two half-size
+/// integers) can be replaced with tables computed once.
+/// `PublicKeyPrecomp::new()` computes the odd multiples `j*A` and
+/// `j*(2^130)*A`, for `j` = 1, 3, 5... 63, in affine coordinates; the
+/// verification functions then use 7-bit wNAF digits for the challenge
+/// scalar, and the double-scalar multiplication needs about 130
+/// doublings and 75 mixed additions.
+///
+/// Memory usage: the tables contain 64 points of 96 bytes each, for a
+/// total of a bit more than 6 kB. Building the tables costs about as
+/// much as two signature verifications; each subsequent verification
+/// (on short messages) is then about 30% faster than with the
+/// `PublicKey` functions. The SHA-512 computation over the message is
+/// not affected.
+///
+/// The verification functions accept exactly the same signatures as
+/// their `PublicKey` counterparts (canonical encodings, cofactored
+/// verification equation). The instance contains its own copy of the
+/// public key, and does not borrow from the source `PublicKey`.
+#[derive(Clone, Debug)]
+pub struct PublicKeyPrecomp {
+    pub public_key: PublicKey,
+    // Odd multiples j*(-A), for j = 1, 3, 5... 63.
+    tab_lo: [PointDuif; 32],
+    // Odd multiples j*(-(2^130)*A), for j = 1, 3, 5... 63.
+    tab_hi: [PointDuif; 32],
+}
+
+impl PublicKeyPrecomp {
+
+    /// Computes the tables for the provided public key.
+    pub fn new(public_key: &PublicKey) -> Self {
+        // We store multiples of -A so that the verification equation
+        // only needs additions of table entries for positive digits.
+        let P_lo = -public_key.point;
+        let mut P_hi = P_lo;
+        P_hi.set_xdouble(130);
+
+        let mut tp = [Point::NEUTRAL; 64];
+        for (i, P) in [P_lo, P_hi].iter().enumerate() {
+            let Q = P.double();
+            tp[i * 32] = *P;
+            for j in 1..32 {
+                tp[i * 32 + j] = tp[i * 32 + j - 1] + Q;
+            }
+        }
+
+        // Normalize to affine coordinates, and convert to Duif format.
+        let mut iZ = [GF25519::ZERO; 64];
+        for i in 0..64 {
+            iZ[i] = tp[i].Z;
+        }
+        GF25519::batch_invert(&mut iZ[..]);
+        let mut tab = [PointDuif {
+            ypx: GF25519::ONE, ymx: GF25519::ONE, t2d: GF25519::ZERO,
+        }; 64];
+        for i in 0..64 {
+            let x = tp[i].X * iZ[i];
+            let y = tp[i].Y * iZ[i];
+            tab[i] = PointDuif { ypx: y + x, ymx: y - x, t2d: (x * y) * Point::D2 };
+        }
+        let mut tab_lo = [tab[0]; 32];
+        let mut tab_hi = [tab[0]; 32];
+        tab_lo.copy_from_slice(&tab[..32]);
+        tab_hi.copy_from_slice(&tab[32..]);
+        Self { public_key: *public_key, tab_lo, tab_hi }
+    }
+
+    /// Verifies a signature on a message.
+    ///
+    /// This is equivalent to `PublicKey::verify_raw()` ("Ed25519" mode
+    /// of RFC 8032, no pre-hashing, no context), but uses the
+    /// precomputed tables.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_raw(&self, sig: &[u8], m: &[u8]) -> bool {
+        self.verify_inner(sig, false, 0u8, &[0u8; 0], m)
+    }
+
+    /// Verifies a signature on a message, with a context.
+    ///
+    /// This is equivalent to `PublicKey::verify_ctx()` ("Ed25519ctx"
+    /// mode of RFC 8032), but uses the precomputed tables. The context
+    /// string MUST have length at most 255 bytes.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_ctx(&self, sig: &[u8], ctx: &[u8], m: &[u8]) -> bool {
+        self.verify_inner(sig, true, 0u8, ctx, m)
+    }
+
+    /// Verifies a signature on a hashed message.
+    ///
+    /// This is equivalent to `PublicKey::verify_ph()` ("Ed25519ph"
+    /// mode of RFC 8032), but uses the precomputed tables. The context
+    /// string MUST have length at most 255 bytes; the pre-hashed message
+    /// `hm` MUST be the SHA-512 hash of the message.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_ph(&self, sig: &[u8], ctx: &[u8], hm: &[u8]) -> bool {
+        self.verify_inner(sig, true, 1u8, ctx, hm)
+    }
+
+    /// Inner signature verification function.
+    fn verify_inner(&self, sig: &[u8], dom: bool, phflag: u8, ctx: &[u8],
+                    m: &[u8]) -> bool
+    {
+        if sig.len() != 64 {
+            return false;
+        }
+        let R_enc = &sig[0..32];
+        let R = match Point::decode(R_enc) {
+            Some(R) => R,
+            None    => { return false; }
+        };
+        let (S, ok) = Scalar::decode32(&sig[32..64]);
+        if ok == 0 {
+            return false;
+        }
+
+        // SHA-512(dom2(F, C) || R || A || PH(M)) -> scalar k
+        let mut sh = Sha512::new();
+        if dom {
+            assert!(ctx.len() <= 255);
+            let clen = ctx.len() as u8;
+            sh.update(HASH_HEAD);
+            sh.update([phflag]);
+            sh.update([clen]);
+            sh.update(ctx);
+        }
+        sh.update(R_enc);
+        sh.update(self.public_key.encoded);
+        sh.update(m);
+        let k = Scalar::decode_reduce(&sh.finalize());
+
+        // Check the verification equation 8*S*B = 8*R + 8*k*A.
+        self.verify_helper_vartime(&R, &S, &k)
+    }
+
+    /// Checks whether `8*s*B = 8*R + 8*k*A`, using the precomputed
+    /// tables for `A`.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    fn verify_helper_vartime(&self, R: &Point, s: &Scalar, k: &Scalar)
+        -> bool
+    {
+        // We compute T = s*B - k*A - R, and check that it is a low order
+        // point. Both s and k are split at bit 130, i.e. digit i and
+        // digit i+130 of each recoding are processed together, with
+        // the tables for B and (2^130)*B (for s, 5-bit wNAF), and for
+        // -A and -(2^130)*A (for k, 7-bit wNAF).
+        let sd = Point::recode_scalar_NAF(s);
+        let kd = Point::recode_scalar_wNAF(k, 7);
+
+        // Window for (2^130)*B (recomputed with the small tables).
+        #[cfg(not(feature = "small_tables_ed25519"))]
+        let win_b130 = &PRECOMP_B130;
+        #[cfg(feature = "small_tables_ed25519")]
+        let win_b130 = &Point::make_precomp_b130();
+
+        let mut T = Point::NEUTRAL;
+        let mut zz = true;
+        let mut ndbl = 0u32;
+        for i in (0..130).rev() {
+            // We have one more doubling to perform.
+            ndbl += 1;
+
+            // Get next digits. If they are all zeros, then we can loop
+            // immediately.
+            let e1 = sd[i] as i16;
+            let e2 = if i < 124 { sd[i + 130] as i16 } else { 0 };
+            let f1 = kd[i];
+            let f2 = if i < 124 { kd[i + 130] } else { 0 };
+            if (e1 | e2 | f1 | f2) == 0 {
+                continue;
+            }
+
+            // Apply accumulated doubles.
+            if zz {
+                zz = false;
+            } else {
+                T.set_xdouble(ndbl);
+            }
+            ndbl = 0u32;
+
+            // Process digits.
+            if e1 != 0 {
+                if e1 > 0 {
+                    T.set_add_duif(&PRECOMP_B[e1 as usize - 1]);
+                } else {
+                    T.set_sub_duif(&PRECOMP_B[(-e1) as usize - 1]);
+                }
+            }
+            if e2 != 0 {
+                if e2 > 0 {
+                    T.set_add_duif(&win_b130[e2 as usize - 1]);
+                } else {
+                    T.set_sub_duif(&win_b130[(-e2) as usize - 1]);
+                }
+            }
+            if f1 != 0 {
+                if f1 > 0 {
+                    T.set_add_duif(&self.tab_lo[f1 as usize >> 1]);
+                } else {
+                    T.set_sub_duif(&self.tab_lo[(-f1) as usize >> 1]);
+                }
+            }
+            if f2 != 0 {
+                if f2 > 0 {
+                    T.set_add_duif(&self.tab_hi[f2 as usize >> 1]);
+                } else {
+                    T.set_sub_duif(&self.tab_hi[(-f2) as usize >> 1]);
+                }
+            }
+        }
+
+        // Remaining doubles must be applied before subtracting R.
+        if !zz && ndbl > 0 {
+            T.set_xdouble(ndbl);
+        }
+        T -= R;
+        T.has_low_order() == 0xFFFFFFFF
+    }
+}
+
+// ========================================================================
+
+/// Precomputed tables for multi-scalar multiplications over a fixed set
+/// of points.
+///
+/// The table is built once from a list of points `P_i` with
+/// `PrecomputedTable::new()`; then, `mul_vartime()` computes
+/// `sum_i s_i*P_i` for any scalars `s_i`, with an interleaved wNAF
+/// (Straus) method, using the stored tables. The window size `w` (2 to
+/// 8 bits) is chosen by the caller: larger windows mean fewer point
+/// additions per multiplication, but larger tables.
+///
+/// Memory usage: for `n` points and a window of `w` bits, the table
+/// contains `n*2^(w-2)` entries (odd multiples of each point, in affine
+/// coordinates), each of size 96 bytes.
+///
+/// Note: this type is available only if heap allocation support was
+/// configured.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct PrecomputedTable {
+    window: usize,
+    num_points: usize,
+    table: Vec<PointDuif>,
+}
+
+#[cfg(feature = "alloc")]
+impl PrecomputedTable {
+
+    /// Builds the tables for the provided points, with a window of
+    /// `window_bits` bits (between 2 and 8, inclusive; a panic is
+    /// triggered otherwise).
+    pub fn new(points: &[Point], window_bits: u32) -> Self {
+        assert!((2..=8).contains(&window_bits));
+        let window = window_bits as usize;
+        let num_points = points.len();
+        let tlen = 1usize << (window - 2);
+
+        // Odd multiples (2*j+1)*P, for j = 0 to 2^(w-2)-1.
+        let mut tp = Vec::with_capacity(num_points * tlen);
+        for P in points.iter() {
+            let Q = P.double();
+            let mut R = *P;
+            tp.push(R);
+            for _ in 1..tlen {
+                R += Q;
+                tp.push(R);
+            }
+        }
+
+        // Normalize to affine coordinates, and convert to Duif format.
+        let mut iZ: Vec<GF25519> = tp.iter().map(|R| R.Z).collect();
+        GF25519::batch_invert(&mut iZ[..]);
+        let table = tp.iter().zip(iZ.iter()).map(|(R, iz)| {
+            let x = R.X * iz;
+            let y = R.Y * iz;
+            PointDuif { ypx: y + x, ymx: y - x, t2d: (x * y) * Point::D2 }
+        }).collect();
+        Self { window, num_points, table }
+    }
+
+    /// Gets the number of points in this table.
+    pub fn len(&self) -> usize {
+        self.num_points
+    }
+
+    /// Tests whether this table contains no point at all.
+    pub fn is_empty(&self) -> bool {
+        self.num_points == 0
+    }
+
+    /// Computes `sum_i scalars[i]*P_i`, for the points `P_i` used to build
+    /// this table. The number of scalars MUST be equal to the number of
+    /// points (a panic is triggered otherwise).
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    pub fn mul_vartime(&self, scalars: &[Scalar]) -> Point {
+        assert!(scalars.len() == self.num_points);
+        let tlen = 1usize << (self.window - 2);
+        let sd: Vec<[i16; 254]> = scalars.iter()
+            .map(|s| Point::recode_scalar_wNAF(s, self.window)).collect();
+
+        let mut P = Point::NEUTRAL;
+        let mut zz = true;
+        let mut ndbl = 0u32;
+        for i in (0..254).rev() {
+            // We have one more doubling to perform.
+            ndbl += 1;
+
+            for (j, sdj) in sd.iter().enumerate() {
+                let e = sdj[i];
+                if e == 0 {
+                    continue;
+                }
+
+                // Apply accumulated doubles.
+                if zz {
+                    zz = false;
+                } else if ndbl > 0 {
+                    P.set_xdouble(ndbl);
+                }
+                ndbl = 0;
+
+                let win = &self.table[(j * tlen)..((j + 1) * tlen)];
+                if e > 0 {
+                    P.set_add_duif(&win[e as usize >> 1]);
+                } else {
+                    P.set_sub_duif(&win[(-e) as usize >> 1]);
+                }
+            }
+        }
+        if !zz && ndbl > 0 {
+            P.set_xdouble(ndbl);
+        }
+        P
+    }
+}
+
+// ========================================================================
+
+/// SLIP-10 hierarchical key derivation for Ed25519.
+///
+/// [SLIP-10] derives a tree of Ed25519 private keys from a single seed
+/// (of arbitrary length; BIP-39 seeds have 64 bytes). Each node of the
+/// tree is a private key with an associated 32-byte chain code; the
+/// master node is obtained with `master_from_seed()`, and children are
+/// derived with `derive_hardened()`, using HMAC/SHA-512. Derived private
+/// keys are ordinary Ed25519 private keys (the derivation output is used
+/// as a 32-byte seed).
+///
+/// For Ed25519, SLIP-10 supports only hardened derivation: child indices
+/// must have their top bit set (`index >= 2^31`, noted `i'` in paths).
+/// Non-hardened indices are rejected with an error.
+///
+/// `derive_path()` parses a derivation path such as `"m/44'/501'/0'/0'"`
+/// and performs all derivation steps from the seed. The `h` and `H`
+/// suffixes are accepted as synonyms for `'`.
+///
+/// [SLIP-10]: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+#[cfg(feature = "hmac")]
+pub mod slip10 {
+
+    use super::PrivateKey;
+    use crate::hmac::HmacSha512;
+
+    /// A SLIP-10 chain code.
+    pub type ChainCode = [u8; 32];
+
+    /// Lowest hardened child index (2^31).
+    pub const HARDENED: u32 = 0x80000000;
+
+    /// Error type for SLIP-10 derivation.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Slip10Error {
+        /// A non-hardened child index was requested.
+        NonHardenedIndex,
+        /// The derivation path is malformed.
+        InvalidPath,
+    }
+
+    impl core::fmt::Display for Slip10Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Slip10Error::NonHardenedIndex => f.write_str("non-hardened index"),
+                Slip10Error::InvalidPath => f.write_str("invalid derivation path"),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for Slip10Error {}
+
+    /// Splits a 64-byte HMAC output into a private key (first half, used
+    /// as seed) and a chain code (second half).
+    fn split_node(i: &[u8; 64]) -> (PrivateKey, ChainCode) {
+        let mut cc = [0u8; 32];
+        cc[..].copy_from_slice(&i[32..]);
+        (PrivateKey::from_seed(&i[..32]), cc)
+    }
+
+    /// Computes the master node (private key and chain code) from a seed.
+    pub fn master_from_seed(seed: &[u8]) -> (PrivateKey, ChainCode) {
+        split_node(&HmacSha512::mac(b"ed25519 seed", seed))
+    }
+
+    /// Derives a child node from a parent node.
+    ///
+    /// The index MUST be hardened (`index >= HARDENED`); otherwise, the
+    /// `NonHardenedIndex` error is returned.
+    pub fn derive_hardened(sk: &PrivateKey, cc: &ChainCode, index: u32)
+        -> Result<(PrivateKey, ChainCode), Slip10Error>
+    {
+        if index < HARDENED {
+            return Err(Slip10Error::NonHardenedIndex);
+        }
+        let mut hm = HmacSha512::new(cc);
+        hm.update(&[0x00]);
+        hm.update(&sk.encode());
+        hm.update(&index.to_be_bytes());
+        Ok(split_node(&hm.finalize()))
+    }
+
+    /// Derives a node from a seed and a derivation path.
+    ///
+    /// The path starts with `m`, followed by zero or more `/i'`
+    /// elements, where `i` is a decimal integer lower than 2^31. Each
+    /// element must be hardened (suffix `'`, `h` or `H`); a non-hardened
+    /// element yields the `NonHardenedIndex` error. A malformed path
+    /// yields the `InvalidPath` error.
+    pub fn derive_path(seed: &[u8], path: &str)
+        -> Result<(PrivateKey, ChainCode), Slip10Error>
+    {
+        let mut elts = path.split('/');
+        if elts.next() != Some("m") {
+            return Err(Slip10Error::InvalidPath);
+        }
+        let (mut sk, mut cc) = master_from_seed(seed);
+        for e in elts {
+            let (num, hardened) = match e.strip_suffix(['\'', 'h', 'H']) {
+                Some(num) => (num, true),
+                None => (e, false),
+            };
+            if num.is_empty() || num.len() > 10
+                || !num.bytes().all(|b| b.is_ascii_digit())
+            {
+                return Err(Slip10Error::InvalidPath);
+            }
+            let index = match num.parse::<u32>() {
+                Ok(i) if i < HARDENED => i,
+                _ => return Err(Slip10Error::InvalidPath),
+            };
+            if !hardened {
+                return Err(Slip10Error::NonHardenedIndex);
+            }
+            (sk, cc) = derive_hardened(&sk, &cc, index + HARDENED)?;
+        }
+        Ok((sk, cc))
+    }
+}
+
+/// Ed25519 key blinding, as used by Tor onion services (v3).
+///
+/// This follows appendix A.2 of the Tor [rend-spec-v3]: from a
+/// long-term key pair `(a, A)` and a blinding parameter `N` (normally
+/// derived from the current time period, see `time_period_param()`), a
+/// blinding factor is computed:
+///
+/// ```text
+///     h = SHA3-256(BLIND_STRING || A || s || B || N)
+/// ```
+///
+/// where `BLIND_STRING` is `"Derive temporary signing key"` followed by a
+/// zero byte, `s` is an optional secret (empty in the functions below
+/// that take only `N`), and `B` is the string representation of the
+/// base point given in the specification. The factor is clamped as an
+/// X25519 scalar, except that bit 254 is set and bit 255 cleared. The
+/// blinded public key is `A' = h*A`; the blinded private key has secret
+/// scalar `a' = h*a mod L` and signing prefix:
+///
+/// ```text
+///     RH' = SHA-512("Derive temporary signing key hash input" || RH)[:32]
+/// ```
+///
+/// where `RH` is the signing prefix of the long-term key. Signatures
+/// computed with the blinded private key are plain Ed25519 signatures,
+/// which verify against the blinded public key. The blinded keys are
+/// unlinkable to the long-term key for anybody who does not know `A`.
+///
+/// [rend-spec-v3]: https://spec.torproject.org/rend-spec/
+#[cfg(feature = "sha3")]
+pub mod blinding {
+
+    use super::{ExpandedPrivateKey, Point, PublicKey, Scalar};
+    use crate::sha3::Sha3_256;
+    use sha2::{Sha512, Digest};
+
+    /// Domain separation string for the blinding factor (including the
+    /// terminating zero byte).
+    const BLIND_STRING: &[u8] = b"Derive temporary signing key\x00";
+
+    /// Domain separation string for the blinded signing prefix.
+    const RH_BLIND_STRING: &[u8] = b"Derive temporary signing key hash input";
+
+    /// String representation of the base point (coordinates in decimal).
+    const BASE_STRING: &[u8] = b"(15112221349535400772501151409588531511454012693041857206046113283949847762202, 46316835694926478169428394003475163141307993866256225615783033603165251855960)";
+
+    /// Builds the blinding parameter `N` for a time period.
+    ///
+    /// This is `"key-blind"`, followed by the period number and the
+    /// period length (in minutes), both over 8 bytes (big-endian).
+    pub fn time_period_param(period_num: u64, period_length: u64)
+        -> [u8; 25]
+    {
+        let mut n = [0u8; 25];
+        n[..9].copy_from_slice(b"key-blind");
+        n[9..17].copy_from_slice(&period_num.to_be_bytes());
+        n[17..].copy_from_slice(&period_length.to_be_bytes());
+        n
+    }
+
+    /// Computes the (unclamped) blinding factor `h` for a public key, an
+    /// optional secret `s` (possibly empty) and a blinding parameter.
+    pub fn blinding_factor(pk: &PublicKey, secret: &[u8], param: &[u8])
+        -> [u8; 32]
+    {
+        let mut sh = Sha3_256::new();
+        sh.update(BLIND_STRING);
+        sh.update(&pk.encoded);
+        sh.update(secret);
+        sh.update(BASE_STRING);
+        sh.update(param);
+        sh.finalize()
+    }
+
+    /// Clamps the blinding factor, and returns it divided by 8 (as a
+    /// scalar; the clamped value is a multiple of 8, and lower than 8*L).
+    fn clamped_factor_div8(h: &[u8; 32]) -> Scalar {
+        let mut t = *h;
+        t[0] &= 248;
+        t[31] &= 63;
+        t[31] |= 64;
+        let mut m = [0u8; 32];
+        for i in 0..31 {
+            m[i] = (t[i] >> 3) | (t[i + 1] << 5);
+        }
+        m[31] = t[31] >> 3;
+        Scalar::decode_reduce(&m)
+    }
+
+    /// Blinds a public key with the provided blinding parameter.
+    pub fn blind_public_key(pk: &PublicKey, param: &[u8]) -> PublicKey {
+        blind_public_key_with_factor(pk, &blinding_factor(pk, &[], param))
+    }
+
+    /// Blinds a private key with the provided blinding parameter.
+    ///
+    /// The public key of the returned key is equal to the output of
+    /// `blind_public_key()` on the source public key.
+    pub fn blind_private_key(sk: &ExpandedPrivateKey, param: &[u8])
+        -> ExpandedPrivateKey
+    {
+        let h = blinding_factor(&sk.public_key, &[], param);
+        blind_private_key_with_factor(sk, &h)
+    }
+
+    /// Blinds a public key with an explicit (unclamped) blinding factor.
+    ///
+    /// The result is `h*A`, with `h` being the clamped factor. Since `h`
+    /// is a multiple of 8, the result is always in the prime-order
+    /// subgroup.
+    pub fn blind_public_key_with_factor(pk: &PublicKey, h: &[u8; 32])
+        -> PublicKey
+    {
+        let hd8 = clamped_factor_div8(h);
+        PublicKey::from_point(&(pk.point.xdouble(3) * hd8))
+    }
+
+    /// Blinds a private key with an explicit (unclamped) blinding factor.
+    pub fn blind_private_key_with_factor(sk: &ExpandedPrivateKey,
+        h: &[u8; 32]) -> ExpandedPrivateKey
+    {
+        let s = sk.s * clamped_factor_div8(h) * Scalar::from_u32(8);
+        let mut sh = Sha512::new();
+        sh.update(RH_BLIND_STRING);
+        sh.update(sk.h);
+        let mut prefix = [0u8; 32];
+        prefix.copy_from_slice(&sh.finalize()[..32]);
+        let public_key = PublicKey::from_point(&Point::mulgen(&s));
+        ExpandedPrivateKey { s, h: prefix, public_key }
+    }
+}
+
+// ========================================================================
+
+// We hardcode known multiples of the points B, (2^65)*B, (2^130)*B
+// and (2^195)*B, with B being the conventional base point. These are
+// used to speed mulgen() operations up. The points are moreover stored
+// in a three-coordinate format (which was attributed to Niels Duif in
+// the original Ed25519 source code): we normalize the point to ensure
+// that Z = 1, and we store X+Y, X-Y and 2d*T (with T = X*Y, since Z = 1).
+// It can easily be seen that such values are in fact intermediaries used
+// in the plain addition formulas.
+// With the `small_tables_ed25519` feature, only the multiples of B are
+// included (the `bigtables` feature is then ignored); mulgen() uses a
+// single table, and the multiples of (2^130)*B are recomputed when
+// needed (in vartime functions).
+
+/// A point in Duif format (y + x, y - x, 2*d*x*y).
+#[derive(Clone, Copy, Debug)]
+struct PointDuif {
+    ypx: GF25519,
+    ymx: GF25519,
+    t2d: GF25519,
+}
+
+impl PointDuif {
 
     /* unused
     const NEUTRAL: Self = Self {
@@ -2435,6 +4538,7 @@ static PRECOMP_B: [PointDuif; 16] = [
 ];
 
 // Points i*(2^65)*B for i = 1 to 16, in Duif format.
+#[cfg(not(feature = "small_tables_ed25519"))]
 static PRECOMP_B65: [PointDuif; 16] = [
     // (2^65)*B * 1
     PointDuif { ypx: GF25519::w64be(0x4F675F5302399FD9, 0x77AFC6624312AEFA,
@@ -2551,6 +4655,7 @@ static PRECOMP_B65: [PointDuif; 16] = [
 ];
 
 // Points i*(2^130)*B for i = 1 to 16, in Duif format.
+#[cfg(not(feature = "small_tables_ed25519"))]
 static PRECOMP_B130: [PointDuif; 16] = [
     // (2^130)*B * 1
     PointDuif { ypx: GF25519::w64be(0x720A5BC050955E51, 0x20F5B522AC4E60D6,
@@ -2667,6 +4772,7 @@ static PRECOMP_B130: [PointDuif; 16] = [
 ];
 
 // Points i*(2^195)*B for i = 1 to 16, in Duif format.
+#[cfg(not(feature = "small_tables_ed25519"))]
 static PRECOMP_B195: [PointDuif; 16] = [
     // (2^195)*B * 1
     PointDuif { ypx: GF25519::w64be(0x1DEFC6AD32B587A6, 0x29A17FD797373292,
@@ -2782,6 +4888,832 @@ static PRECOMP_B195: [PointDuif; 16] = [
                                     0x3D386EF1CD60A722, 0xDDA69D930A3ED3E3) },
 ];
 
+// Points i*(2^35)*B for i = 1 to 16, in Duif format.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B35: [PointDuif; 16] = [
+    // (2^35)*B * 1
+    PointDuif { ypx: GF25519::w64be(0x3BC187FA47EB98D8, 0xE164BA772E9C16D4,
+                                    0x8E3A9028432E9615, 0x3F244D2AEED7521E),
+                ymx: GF25519::w64be(0x332F35914F8FBED3, 0xA9E18FC5CCAEE24B,
+                                    0x6A379AEFD7C7B533, 0x031408D36D63727F),
+                t2d: GF25519::w64be(0x450D81CE906FBA03, 0xD77832B53A660188,
+                                    0x998AB7CB6C46D125, 0x6D470115EA86C20C) },
+    // (2^35)*B * 2
+    PointDuif { ypx: GF25519::w64be(0x2A887F78F7635510, 0x030EBED6F0D24AC8,
+                                    0x7DBAED33EBCA6576, 0x23264D66B2CAE0B5),
+                ymx: GF25519::w64be(0x53B16D2324CCCA79, 0xAAB3995FC7D2C11E,
+                                    0x7018058EE8DB2D1D, 0xF8AE4D2AD8453902),
+                t2d: GF25519::w64be(0x0BA7250B864403F5, 0x2FB63273675D70CA,
+                                    0x0C974651CAE1F2EA, 0x2A23B9E75C012D4F) },
+    // (2^35)*B * 3
+    PointDuif { ypx: GF25519::w64be(0x40205263E0DDCADE, 0x0533B1057797470F,
+                                    0x90D6773BB5A380AD, 0x277C1AF35EBE8D3D),
+                ymx: GF25519::w64be(0x79147DB2C898AFBC, 0x7F6851F06CAD98BC,
+                                    0x98D8F5073CCDA5CE, 0xF268E80B55759D14),
+                t2d: GF25519::w64be(0x3815D5989DAD4453, 0x9C6140A62423CA3C,
+                                    0xE80E04D22E0B330A, 0xAC390A27BF609A06) },
+    // (2^35)*B * 4
+    PointDuif { ypx: GF25519::w64be(0x32C21B57FB60BDFB, 0x2E5111954EAA7D57,
+                                    0x61699176E13A85A4, 0xDD63589386F86D9C),
+                ymx: GF25519::w64be(0x7B9F2FE8032D71C9, 0x8347F8E68B250E96,
+                                    0xBC2D142189298F02, 0xBB0D18FD029C6421),
+                t2d: GF25519::w64be(0x12C49D417238C371, 0x4854FB129A0AB3F7,
+                                    0xEFC4CFC1897775C5, 0xD87823CD319E0780) },
+    // (2^35)*B * 5
+    PointDuif { ypx: GF25519::w64be(0x6DD459EEF8E553F4, 0xC2827AB742C3519E,
+                                    0xB8817CEBE460CA7D, 0xE4B6663CA29B4E70),
+                ymx: GF25519::w64be(0x41BD01ADFB744989, 0xFA21C36C32FB33A6,
+                                    0x8415480F834F352B, 0xE95E5CE99A7CFFE1),
+                t2d: GF25519::w64be(0x7ECDE3A76B962292, 0x2F6F35D1A0881813,
+                                    0x671478CC08DBE313, 0x628E5DE278CDB7D4) },
+    // (2^35)*B * 6
+    PointDuif { ypx: GF25519::w64be(0x1421B246A0A444C9, 0xBA00BCEEEB70149F,
+                                    0x626DD08FAAD5EE3F, 0x09B3A01783799542),
+                ymx: GF25519::w64be(0x2509200C6391CAB4, 0xF022D8381302E510,
+                                    0x21861C1D8E1D6BD1, 0x0950B533FFE83769),
+                t2d: GF25519::w64be(0x2AB5504448A49CE3, 0xADBA5E0C0B3EB9DC,
+                                    0x04C1F540D8F05EF5, 0x4AA43A8E8C24A7C7) },
+    // (2^35)*B * 7
+    PointDuif { ypx: GF25519::w64be(0x27D687FDAAB1754C, 0x016F4DE5A71C5DEE,
+                                    0xBB8978669C2FDBBA, 0xC6009327068FD2B0),
+                ymx: GF25519::w64be(0x277B32EE686532AF, 0x5945F78B3217877B,
+                                    0x878481607254E836, 0xE9CEB364A0D8CF91),
+                t2d: GF25519::w64be(0x6F8AEBD89A713461, 0x9FB7DECDE65E2FE8,
+                                    0xD7B5B7C47EC5D8C3, 0x604FE7E362D7EBD2) },
+    // (2^35)*B * 8
+    PointDuif { ypx: GF25519::w64be(0x7301F4CEB4EAE15D, 0x72A079D89D73E2B0,
+                                    0x58615171F9DF8C6C, 0xDC07AC631C5D3AFA),
+                ymx: GF25519::w64be(0x7093BAE1B521E23F, 0x807BEC7C9468D415,
+                                    0x9824EE415ED50824, 0x2ED227266F0F5DEC),
+                t2d: GF25519::w64be(0x390167D24EBACB23, 0xBC0A24EB3C21E569,
+                                    0xA674E1CF72BF729B, 0x6409E759D6722C41) },
+    // (2^35)*B * 9
+    PointDuif { ypx: GF25519::w64be(0x421E76717E077ACB, 0x215521F7F94A0D4F,
+                                    0xA169C2856A7E7DA7, 0x818C16ED0ADA87B1),
+                ymx: GF25519::w64be(0x0CC62066AB112CE3, 0x5BA365C867E2BDD1,
+                                    0xA181055BD3AEA703, 0x9A7C0D3A5E7F658B),
+                t2d: GF25519::w64be(0x5FCB33A1AF79B898, 0xFAC05C2AA16178AB,
+                                    0xC02630A3D1A9C2A3, 0x9BE2C13F59713536) },
+    // (2^35)*B * 10
+    PointDuif { ypx: GF25519::w64be(0x4CBD40767112CB69, 0x3FE8BAC8F3C0EDBE,
+                                    0xE2B9CEAEB10589B7, 0xD7BB054BA2F2120B),
+                ymx: GF25519::w64be(0x07DB2EE6AAE1A45D, 0xAFBBC4E56E562650,
+                                    0x4C47764DBF6A4361, 0x27F58E3BBA353F1C),
+                t2d: GF25519::w64be(0x7B8EEC6C74183287, 0x2BB61413DCF0AD8D,
+                                    0x5988E3825CB15D61, 0x0B603CC029C58176) },
+    // (2^35)*B * 11
+    PointDuif { ypx: GF25519::w64be(0x157226EDDA908078, 0x49A31CA435C298F4,
+                                    0x11F2FA886FCDE0F3, 0x25E710DEBC0CE945),
+                ymx: GF25519::w64be(0x4E43DE123A0D4723, 0x57F6FDE696B4693C,
+                                    0xAC2ACBBF75CBF524, 0xA97E2DD37FCCB34E),
+                t2d: GF25519::w64be(0x1121FD8F9842FA2E, 0x7157217F83B6B2C7,
+                                    0xF798CA0E546440A7, 0x78106956DE6DE5E6) },
+    // (2^35)*B * 12
+    PointDuif { ypx: GF25519::w64be(0x6EE809A1B132A855, 0x975FFD0AC8968359,
+                                    0xDA8B0141DA3A8CC7, 0x32FEE570FC386B73),
+                ymx: GF25519::w64be(0x72810497626EDE4D, 0xB29BD34A8AD41E9E,
+                                    0xDAF9C323FBE967BD, 0xE4CA40782CD27CB0),
+                t2d: GF25519::w64be(0x13BD1E38D173292E, 0xDC29C867D088FA25,
+                                    0x2FE3690A3E4E48C5, 0x9444BB31FCFD863A) },
+    // (2^35)*B * 13
+    PointDuif { ypx: GF25519::w64be(0x47CB9B289966D15B, 0x1C5DA16630042B9F,
+                                    0xBD4995BD19C51BEB, 0x63AF4895947348BA),
+                ymx: GF25519::w64be(0x6165AF4BA6EED26B, 0x0B8F8A3B94A02A03,
+                                    0x089A689A0B76FC24, 0x5C21049A9CD223BE),
+                t2d: GF25519::w64be(0x1B06AF44DD4B070D, 0x94BEC94B15E71959,
+                                    0xC68E21B8707AC86E, 0x0B86BB45C33EFFB2) },
+    // (2^35)*B * 14
+    PointDuif { ypx: GF25519::w64be(0x1ED018B64F88A4BD, 0x030B98D7659177AC,
+                                    0x325C25316F554450, 0x223FB5CF1DFAC521),
+                ymx: GF25519::w64be(0x41E86FCFB14099B0, 0x0BCB2127AE122B94,
+                                    0xE55937D781D8AAB7, 0xD32B4CD8696149B5),
+                t2d: GF25519::w64be(0x746A247A37CDC5D9, 0x0AF90D6CEEC5A4D4,
+                                    0x880F874742AD3BD5, 0x3630DFA1B802A6B0) },
+    // (2^35)*B * 15
+    PointDuif { ypx: GF25519::w64be(0x0D00ACB3050689A1, 0xD99125E2B72D3E7E,
+                                    0xEB60A84618C0DC72, 0x6011919027DD8D15),
+                ymx: GF25519::w64be(0x03EBAD4BAA135B87, 0x88B856B85A5CDA39,
+                                    0x38DEDBC0E66B9002, 0x39B06F4F9C707397),
+                t2d: GF25519::w64be(0x0E612DF34769E365, 0xE952A4559E6C4B78,
+                                    0x15D30731C969B1E2, 0xEE51A76626D3F857) },
+    // (2^35)*B * 16
+    PointDuif { ypx: GF25519::w64be(0x681E3351BFF0E4E2, 0xC522D02E7BBFCDB7,
+                                    0x2254AE83D22F7843, 0x6ECCD85278D941ED),
+                ymx: GF25519::w64be(0x20A365142BB40F49, 0x232FCF25C593546D,
+                                    0x5005093537FC5B51, 0xD531B8BD2B7B9AF6),
+                t2d: GF25519::w64be(0x539EF98E45D5472B, 0x69249495BA6550E4,
+                                    0x2F8B71F21FA20EFB, 0x8B64B59D83034F45) },
+];
+
+// Points i*(2^70)*B for i = 1 to 16, in Duif format.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B70: [PointDuif; 16] = [
+    // (2^70)*B * 1
+    PointDuif { ypx: GF25519::w64be(0x73D86B7ABB6F723A, 0x00E7D4AE8AC80592,
+                                    0x7FE2B5109EB63AD8, 0x9A6CE876760321FD),
+                ymx: GF25519::w64be(0x3BA2504F049B673C, 0x9A498330AF74181B,
+                                    0x15801004E2663135, 0xE1F6B79EBF8469AD),
+                t2d: GF25519::w64be(0x6813B8F37973E5DB, 0x30A9520D9B04A635,
+                                    0xA9134F0FBBB1EDAB, 0x0B52B5606DBA5AB6) },
+    // (2^70)*B * 2
+    PointDuif { ypx: GF25519::w64be(0x59AAB07A0D40166A, 0xF33AE4F552A2C8B4,
+                                    0x3B084CFE2F53B965, 0x14FD6DFA726CCC74),
+                ymx: GF25519::w64be(0x3AA1D11FAF60A4D8, 0x61E96A8042F15EF4,
+                                    0xF9C6F635B26F1BEE, 0xDBDAE701C5738DD3),
+                t2d: GF25519::w64be(0x1865E78EC8E6AA46, 0x5B374337FEA9F451,
+                                    0x1848718460137738, 0x77BCEC4C925EAC25) },
+    // (2^70)*B * 3
+    PointDuif { ypx: GF25519::w64be(0x58D4F3C210C31E66, 0xAC0CBD408861BD2E,
+                                    0x706B8F478D49D876, 0x833FD1DAD6A1B645),
+                ymx: GF25519::w64be(0x63D223C83E81144E, 0xD2DD866C87773EC2,
+                                    0x1906E8D7797B4F24, 0xEDC157E62914A03C),
+                t2d: GF25519::w64be(0x21DDEBB6B92D3A3D, 0xD8C0B9D6C2DAE858,
+                                    0x505C0D96B0A8E850, 0x373735DA96155E15) },
+    // (2^70)*B * 4
+    PointDuif { ypx: GF25519::w64be(0x17038418EAF66F5C, 0x2747AFF478121965,
+                                    0x30F6269264C635FB, 0x967C54E91C529CCB),
+                ymx: GF25519::w64be(0x582F446752DA63F7, 0x3EF06DFC713EAF1C,
+                                    0x44157E25F50C2F7E, 0xCCC4B7C7B66E1F7A),
+                t2d: GF25519::w64be(0x0C2A1C4BCDA28DC9, 0xB21EF18B4E5A1364,
+                                    0xA81042E8A4488BC4, 0xC6317BD320324CE4) },
+    // (2^70)*B * 5
+    PointDuif { ypx: GF25519::w64be(0x07BBA53EEFA8C5AA, 0x47F4D3BAC324CAE4,
+                                    0x951E2403B0A18A6A, 0x90002FA9B507ECFE),
+                ymx: GF25519::w64be(0x0FBBE90FEFFF9F2D, 0x79BBCE2341FF0B7D,
+                                    0x892FF2D3E216A584, 0xE3BBCB2DFE982283),
+                t2d: GF25519::w64be(0x55CB5EE4032A5259, 0x5106D3D062146110,
+                                    0x32BA1431674E5083, 0x0B6F6D4C516A4B61) },
+    // (2^70)*B * 6
+    PointDuif { ypx: GF25519::w64be(0x4ABABFDBC85D0D7A, 0xB743DC46DCE50374,
+                                    0x9C142AF84D3E4199, 0x8E62B29B7CAA19B7),
+                ymx: GF25519::w64be(0x66A130AFAA0B3A35, 0x737D9B83FA95CF1C,
+                                    0xE1BC2A2BCE935138, 0xF66B08008C5DE967),
+                t2d: GF25519::w64be(0x5D3758BABFD4E0E4, 0xA67D448C7AFF8313,
+                                    0xEE2F18BAD77A001A, 0xAD9AE4425CDCFDE0) },
+    // (2^70)*B * 7
+    PointDuif { ypx: GF25519::w64be(0x25ADA8E98FE3C33F, 0x453280D2CDF77236,
+                                    0x92105F35FFA09813, 0xC98258AECE8A047D),
+                ymx: GF25519::w64be(0x1252D1C82742EAD3, 0x8699DD9A312C5DE0,
+                                    0x8A8D34FE2A76281A, 0x2F498C0DDDBB6EF5),
+                t2d: GF25519::w64be(0x4210C779E9DF1FD3, 0xEB14FB4ADC5DFE5A,
+                                    0x602118E0BA8811E2, 0xFA9C0B833E348D2C) },
+    // (2^70)*B * 8
+    PointDuif { ypx: GF25519::w64be(0x5A6A9B30A314DC83, 0xC63BD212D55CC5AB,
+                                    0x0D6D907DBE1C8D22, 0xEDC4814869BD6945),
+                ymx: GF25519::w64be(0x7C558BD1C6F64877, 0xD15B0272FBB2D28F,
+                                    0xB2269E3EDB87C059, 0xD24DC7D06F1F0447),
+                t2d: GF25519::w64be(0x0404A5CA0AFBAFC3, 0xA50C3A791CBC5FA4,
+                                    0x12BB628AC35A24F0, 0xD0EC1524D396463D) },
+    // (2^70)*B * 9
+    PointDuif { ypx: GF25519::w64be(0x7DD29417F997D476, 0xB5BFD4CFDA132498,
+                                    0x532F4C0DAA01E9E0, 0x54A8031AB15EF51B),
+                ymx: GF25519::w64be(0x206A553D12D5F71E, 0x89448262F4B77522,
+                                    0x7DCE5409911EE681, 0xC8E696E65C72151D),
+                t2d: GF25519::w64be(0x47F8AFC86CDB7CEC, 0x96E69F8DD5376D89,
+                                    0xBFB29447B3F367AF, 0x7BCAF69C5D8AA236) },
+    // (2^70)*B * 10
+    PointDuif { ypx: GF25519::w64be(0x6F59360A7107B128, 0x29F18A151F1ECB1E,
+                                    0x156248BBD2B205B1, 0xC0450302FB35F3C4),
+                ymx: GF25519::w64be(0x0ED560EC453060FC, 0xA7CE5A322A9D488F,
+                                    0xFD29849EF104944E, 0x83CD50C1087B8389),
+                t2d: GF25519::w64be(0x37E7C36AC935394C, 0x816C832E22B8E91C,
+                                    0xF9FEBFE6EF47EFB8, 0x036BB0C06AE9CD5C) },
+    // (2^70)*B * 11
+    PointDuif { ypx: GF25519::w64be(0x591CF22C4F624A48, 0x89A27F38AF7541C1,
+                                    0xB72AA8974331187D, 0x5C058ABDA9B5FAB2),
+                ymx: GF25519::w64be(0x0A89A2C5D70D65EB, 0x3A197130C9C5D781,
+                                    0xECF688216897D2D1, 0x80B1B0965C664FAF),
+                t2d: GF25519::w64be(0x423D188FEB2549AA, 0x675D5D20C112F9D9,
+                                    0xFB440FE3AF4B55E5, 0x0780F043E498FB98) },
+    // (2^70)*B * 12
+    PointDuif { ypx: GF25519::w64be(0x39527516E7F8EE98, 0x04343FD83D5D6967,
+                                    0xB5C6F728E350598B, 0x62BC9E1B2A416FD1),
+                ymx: GF25519::w64be(0x46395BFDCADD9633, 0x574B046B668FD2DE,
+                                    0xCCBAD0CB5B265EE8, 0x8C1F40070AA743D6),
+                t2d: GF25519::w64be(0x76579A29E822D016, 0xEFD4BEF154D56FEA,
+                                    0x9C7745BCD1005C2A, 0x117FDB2D1A5D9A9C) },
+    // (2^70)*B * 13
+    PointDuif { ypx: GF25519::w64be(0x7F2CE474C70F74F6, 0x12C76F9DA8BB8AFA,
+                                    0x601581CB87F879AF, 0x112BBA8CDCFD72E3),
+                ymx: GF25519::w64be(0x14AF5C2521D5E79E, 0xC0A83CD2F0A78FF5,
+                                    0x1BD2C126D19C33BE, 0x45AFB91117F2E17B),
+                t2d: GF25519::w64be(0x389E9FBDEB4B9353, 0x82412F0A3E32BBC6,
+                                    0xCB740D73128F3B26, 0x02D08D91EBB35BE1) },
+    // (2^70)*B * 14
+    PointDuif { ypx: GF25519::w64be(0x15AC49F730EF6D6D, 0xA132DF6CAA03E1DD,
+                                    0xB2FC6F51B05ED686, 0x4D7B10E9BC867734),
+                ymx: GF25519::w64be(0x42FD12F8710BA041, 0x4CB8E88BB0801801,
+                                    0xF2CEEEA371751718, 0x683D8A6B64FA2A47),
+                t2d: GF25519::w64be(0x1F27856BCD7CCC52, 0x8800258576FB74A3,
+                                    0x89088C7E5B3E8570, 0xF1EFE16F76E808DB) },
+    // (2^70)*B * 15
+    PointDuif { ypx: GF25519::w64be(0x61364079E9483024, 0xE3A2430F9B3E1279,
+                                    0x2B6747E55AA12109, 0xF898D054E2C6A19E),
+                ymx: GF25519::w64be(0x0A6656CF8C5BE07A, 0x04ABBDCC3E2CAE46,
+                                    0x734681BB2628443B, 0x411019CD44291356),
+                t2d: GF25519::w64be(0x35A351DFF7CFBE83, 0x3F10EB4D6EEC49C6,
+                                    0x0ADC71338DDA0BAD, 0x57975AE3686595EA) },
+    // (2^70)*B * 16
+    PointDuif { ypx: GF25519::w64be(0x02C514BB2A2777C1, 0xB5512887750D35CE,
+                                    0xD832284993DE80E1, 0x333CB51352B434F2),
+                ymx: GF25519::w64be(0x43A384DC9E05BDB1, 0x3ED65F11EC224C1B,
+                                    0x23CD51A2BCA9A37F, 0x45B68E7E49C02A17),
+                t2d: GF25519::w64be(0x1160920961548059, 0x313916D7A9B0D253,
+                                    0xFB8BD37EF6B54B53, 0x684BD5DA8BF1B645) },
+];
+
+// Points i*(2^105)*B for i = 1 to 16, in Duif format.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B105: [PointDuif; 16] = [
+    // (2^105)*B * 1
+    PointDuif { ypx: GF25519::w64be(0x31E83B4161D081C1, 0xD3B0DA49A66BDE53,
+                                    0x6A1B0CE99646AC8B, 0x34EEBB6FC1CC5AD0),
+                ymx: GF25519::w64be(0x60B63BEBF508A72D, 0xFB02D32FCCBAAC5C,
+                                    0x620C35005E58C102, 0xB478BD1E249DD197),
+                t2d: GF25519::w64be(0x55CF1EB62D550317, 0x5BECE14B6F18683F,
+                                    0x49E48F4F29320AD8, 0x97E8C7129E062B4F) },
+    // (2^105)*B * 2
+    PointDuif { ypx: GF25519::w64be(0x50BDC87DC8E5B827, 0xD4B2E883B8E55365,
+                                    0xDEEBC4EC571A4842, 0xC4DCFB6A5D8BD080),
+                ymx: GF25519::w64be(0x66F80C93A637B607, 0x19F83664ECB5B9B6,
+                                    0xFC13C187C7F13F61, 0x423A5D465AB3E1B9),
+                t2d: GF25519::w64be(0x1DD56444725FD5AE, 0x64B03AC325B73B96,
+                                    0x32353E15F011ABD9, 0x606D37836EDFE111) },
+    // (2^105)*B * 3
+    PointDuif { ypx: GF25519::w64be(0x509A41C325950AF6, 0x55851DFDF35973CD,
+                                    0xFC921658342D9E3B, 0x5D669E29AB1DD398),
+                ymx: GF25519::w64be(0x224C7C679A1D5314, 0xBC98D3E3BA8598EF,
+                                    0x0C9F3C497F24DB66, 0xF2745D032AFFFE19),
+                t2d: GF25519::w64be(0x05BFF02328A11389, 0x82EC12809D833E89,
+                                    0x793EF3F4641B1F33, 0xBDC06EDCA6F925E9) },
+    // (2^105)*B * 4
+    PointDuif { ypx: GF25519::w64be(0x29D4DB8CA0A0CB69, 0xAC1F26E938781276,
+                                    0xF35D2A3B432610E1, 0xE5DB47E813B69540),
+                ymx: GF25519::w64be(0x106A03DC25A966BE, 0xFA98894C06BD035D,
+                                    0xA7602025F3E778F5, 0x398E080C1789DB9D),
+                t2d: GF25519::w64be(0x4AB38A51052CBEFA, 0x3C57658AC888F7F0,
+                                    0x38669DA5ACD309E5, 0xD9AD0AAF333353D0) },
+    // (2^105)*B * 5
+    PointDuif { ypx: GF25519::w64be(0x1E30242A0DEFE5DC, 0xF3E617E09E5C19A8,
+                                    0xBF9853DB2D1D4EF0, 0x62BD3F7B5446311F),
+                ymx: GF25519::w64be(0x109D02C2C5720545, 0x7744831A83C52159,
+                                    0xA430EDFCDD044621, 0x746CF253A9FF8D18),
+                t2d: GF25519::w64be(0x01D1902D4E329321, 0xB6E5338854B17B98,
+                                    0x92F146CFFE2F3B3D, 0x08600BC2CEEDE125) },
+    // (2^105)*B * 6
+    PointDuif { ypx: GF25519::w64be(0x253847E88DC2EE28, 0xA5ABC6A21B466EAB,
+                                    0xC695E6D34B29BAE7, 0x2FF983833EA03EC5),
+                ymx: GF25519::w64be(0x1E65138D4D3B87FC, 0xBF3714D62FC138D7,
+                                    0xE22C05AEED2C2279, 0x451BB499AEF4E67B),
+                t2d: GF25519::w64be(0x18BDA0F574F5CEB9, 0x6ECD5C569B9E6DD3,
+                                    0xD6EA580012EFD914, 0x172250DBA1D0079F) },
+    // (2^105)*B * 7
+    PointDuif { ypx: GF25519::w64be(0x195A57BCE2334E7A, 0x8F6EFB64BB319BAC,
+                                    0x2A0275DC3CA869D5, 0x89697E8C5CF77386),
+                ymx: GF25519::w64be(0x1721E09A6D2238E1, 0xF35A74D8F4EA2F26,
+                                    0x2EC2A91C443898CA, 0xE535DD0EA478C053),
+                t2d: GF25519::w64be(0x148B2EB02B070EDF, 0xA7A1A4A382EF6C4A,
+                                    0x7D0FD917F15817B3, 0xBCC9279F9D9829DD) },
+    // (2^105)*B * 8
+    PointDuif { ypx: GF25519::w64be(0x7B9B1FB5DEA1959E, 0x076353D40AADBF45,
+                                    0xE3BC096A9C82BAD1, 0xF68FE2E8809DE054),
+                ymx: GF25519::w64be(0x68AEE70642287CFF, 0xDEF7AAA8EA31609F,
+                                    0x054442883F955BB7, 0xDFDACBEE4324C0E9),
+                t2d: GF25519::w64be(0x2D13D55A28BD85FB, 0x27776093D3E46B5F,
+                                    0x95242E37579082BB, 0xF01CC8F17471CC0C) },
+    // (2^105)*B * 9
+    PointDuif { ypx: GF25519::w64be(0x3A1799B28C6AA8C4, 0x7D4BD235CAD653EF,
+                                    0x109F590E791E6767, 0x02317D7A985E2C38),
+                ymx: GF25519::w64be(0x1E1FA0D551B199A4, 0xE690F625D1C5619C,
+                                    0x38DBD6B2D3EE5545, 0x5998FA629A434CE8),
+                t2d: GF25519::w64be(0x6BF808D353A344EB, 0x40BFD0D2D3100988,
+                                    0xA077D90E2BDFDDDA, 0x7578410DEC9BF4CD) },
+    // (2^105)*B * 10
+    PointDuif { ypx: GF25519::w64be(0x0B39947AF38C5C5B, 0x4CFA0D686C53CE91,
+                                    0x9C72DE2CB860893C, 0xDB5A4B330C074BA0),
+                ymx: GF25519::w64be(0x5997379A77328481, 0x74FE4084A7311AFF,
+                                    0xB04879298F859575, 0x5616B80E1EE8DC02),
+                t2d: GF25519::w64be(0x07F299B6F372C8C4, 0x1335B775EEFECB9E,
+                                    0x834DD623EFA9CF0A, 0xF6DF534609D87B6C) },
+    // (2^105)*B * 11
+    PointDuif { ypx: GF25519::w64be(0x7901A95DEDA13BF9, 0xCC1CD1A57DC50CDA,
+                                    0x07F4924FD82D03C8, 0x64D07250D7BDE92C),
+                ymx: GF25519::w64be(0x42020BEBFA740E46, 0x2C629496370B9F92,
+                                    0xD9E1F81D6C05500E, 0xD5D56FB4FD1C5231),
+                t2d: GF25519::w64be(0x6EFCAAE5304D7EBD, 0xF28F6CBB27AC757C,
+                                    0x661772B27288DE36, 0x56FFC2AE584A9640) },
+    // (2^105)*B * 12
+    PointDuif { ypx: GF25519::w64be(0x4D86DDFD093B2409, 0xDC98718C735E333B,
+                                    0x8EDDAB656741D202, 0xD3DD670DF1A96C0D),
+                ymx: GF25519::w64be(0x624EA114B42ED60E, 0x65C3B980AE9BC41A,
+                                    0x0CBA80CC09B85956, 0xF2119DE56789BF53),
+                t2d: GF25519::w64be(0x02C610703EE726B7, 0xA1DD72E7317563F4,
+                                    0x8594546CFEFCC227, 0xAB98ED32DD4DD551) },
+    // (2^105)*B * 13
+    PointDuif { ypx: GF25519::w64be(0x14FB44816B5989B4, 0xC9E1C8BFE82641E6,
+                                    0x41CCDE54878F80C6, 0x16F2A5A9852581ED),
+                ymx: GF25519::w64be(0x1BA89F1B4087D54F, 0x20F4E0DC6300DE48,
+                                    0x1972F6F1EE56D958, 0x868FE99CDEB1DF42),
+                t2d: GF25519::w64be(0x379FF96F9A0EE584, 0xE3AE928C76408CE0,
+                                    0x64D8DB276F7F07D1, 0x2F32B2241128B0C6) },
+    // (2^105)*B * 14
+    PointDuif { ypx: GF25519::w64be(0x7EF77389C56D5AA3, 0xC2EAA8EF48D097DD,
+                                    0x63276041DC772A20, 0x1157F662B88A75A6),
+                ymx: GF25519::w64be(0x094E78A4AC38F097, 0x5C21B53F421B090D,
+                                    0x38A85ADC386E1833, 0x0FBC73EDBAB0D57A),
+                t2d: GF25519::w64be(0x2DF33FDB3F1C2E88, 0x2A14AF761FD87D76,
+                                    0x0187B949F6F465F9, 0x6B2E729417D82DA2) },
+    // (2^105)*B * 15
+    PointDuif { ypx: GF25519::w64be(0x0826C73FD92DB255, 0x284C92F5C6FE3B52,
+                                    0xCEAC814320F1BE0C, 0x78C87F2E415B1D54),
+                ymx: GF25519::w64be(0x57C9987A093F804C, 0x2455391EBDF9A7D9,
+                                    0xA66B2C759CD16B67, 0x141706B2A94D48F9),
+                t2d: GF25519::w64be(0x30F677E24A34A6FC, 0xE3F61BAE6A7CDB8A,
+                                    0x97297F1EA4AE3F01, 0x8CCBD0B8D311994D) },
+    // (2^105)*B * 16
+    PointDuif { ypx: GF25519::w64be(0x3619B5D7560916D8, 0x3C619F0B87A8BB19,
+                                    0xA8DED2B6E454EAD3, 0xBF019CCE7AEE7A52),
+                ymx: GF25519::w64be(0x6B8341EE8BF90D58, 0xCCD2CA913D21CD0F,
+                                    0xA8DA8A9A85624BB7, 0xFAC5D2065B35B8DA),
+                t2d: GF25519::w64be(0x6A927B6B7173A8D7, 0xB7CDED7B28C8C7C0,
+                                    0x64D592F24FAFEFAE, 0x3579F26B0282C4B2) },
+];
+
+// Points i*(2^140)*B for i = 1 to 16, in Duif format.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B140: [PointDuif; 16] = [
+    // (2^140)*B * 1
+    PointDuif { ypx: GF25519::w64be(0x7BC92FC9B9FA74ED, 0xE027B34F1BAD0249,
+                                    0xDF2B9E60388B4995, 0x529FDFFE638C7BF3),
+                ymx: GF25519::w64be(0x0ABA390EAB0BF2DA, 0xB0721D71D0850050,
+                                    0xA3C31A20E085C145, 0x0C9C5303F7957BE4),
+                t2d: GF25519::w64be(0x02672B37DD3FB8E0, 0xE906B3FFBD596B50,
+                                    0x83697D5479AFDA3A, 0x9F97EF2E801AD9F9) },
+    // (2^140)*B * 2
+    PointDuif { ypx: GF25519::w64be(0x232CA21EF736E2C8, 0x29EB29CE7EC544E1,
+                                    0xEB9CA6257A4849DB, 0xEE9BA729398CA7F5),
+                ymx: GF25519::w64be(0x741D1FCBAB2CA2A5, 0x937E1A2617F58F74,
+                                    0xA4286BEC82B34C1C, 0x48B2CA8B260885E4),
+                t2d: GF25519::w64be(0x0400F3A049E3414B, 0xF18602DF9851C7AF,
+                                    0x08803CEAFA39EB14, 0xBF61423D253FCB17) },
+    // (2^140)*B * 3
+    PointDuif { ypx: GF25519::w64be(0x32830AC7157EADF3, 0xDF9713EBD67A91C7,
+                                    0x146785452C8D2560, 0x2EFBA412A06E7B06),
+                ymx: GF25519::w64be(0x0C9176E984D756FB, 0x6EB259D5E8D82D09,
+                                    0x36A3D6D7C4D39716, 0xABCE0476BA61C55B),
+                t2d: GF25519::w64be(0x0D794F8383EBA820, 0x29525226EBCCEAE1,
+                                    0x04A05D7875B18E2C, 0x0E782A7AB73769E8) },
+    // (2^140)*B * 4
+    PointDuif { ypx: GF25519::w64be(0x0D6592233127DB16, 0x1734A1D70D5F7C9B,
+                                    0x411FD93EFAD1B8B7, 0x7BE44CE7A7A2E1AC),
+                ymx: GF25519::w64be(0x097B0BF22092A6C2, 0xF0D73C2BB93A9EF3,
+                                    0xEE805BCF648AAE45, 0xFF35F5CB9E1516F4),
+                t2d: GF25519::w64be(0x7FC90FEA93EB3A67, 0x625C6C1CC6CB4305,
+                                    0xA6C2EAEAD61ABB25, 0xC48BAB1521A9D733) },
+    // (2^140)*B * 5
+    PointDuif { ypx: GF25519::w64be(0x6CE97DABF7D8FA11, 0xD64392817CCF2C7A,
+                                    0x955391695328404E, 0xC527DEB59C7CB23D),
+                ymx: GF25519::w64be(0x6598EE93C98B577E, 0x40A951A2FDC71669,
+                                    0x1A8F2F5E3B258BF4, 0x0408F1FE1F5C5926),
+                t2d: GF25519::w64be(0x73119FA08C12BB03, 0xC5E75173E53DE537,
+                                    0xEB6034116F2CE532, 0x25B5A8E50EF7C48F) },
+    // (2^140)*B * 6
+    PointDuif { ypx: GF25519::w64be(0x3CD6A85295621212, 0x671857C03C56522B,
+                                    0xBF62F16C7897B727, 0x7845B94D21F4774D),
+                ymx: GF25519::w64be(0x3025798A9EA8428C, 0xFACB2B1338BEE7B9,
+                                    0xBCE621C9C8F53787, 0xED30129453F1A4CB),
+                t2d: GF25519::w64be(0x3F52C02852661036, 0x67B99DFC96988ADE,
+                                    0xBDAA5B0062E8C12F, 0x3FECDE923AECA999) },
+    // (2^140)*B * 7
+    PointDuif { ypx: GF25519::w64be(0x05D0E85C99091569, 0x629549AB16DEA4AB,
+                                    0xAC8A5017A9D2F671, 0x9258BF99EEC416C6),
+                ymx: GF25519::w64be(0x60C0104BA696AC59, 0x0B5BA9E57582DDF1,
+                                    0x28624754FA7F53D7, 0xFFEAA48E2A1351C6),
+                t2d: GF25519::w64be(0x36853C69AB96DE4D, 0x378CEC9F0F11DF65,
+                                    0xFA07FC56B50BCF74, 0x051DE020DE9CBE97) },
+    // (2^140)*B * 8
+    PointDuif { ypx: GF25519::w64be(0x4A0AFF6D62825FC8, 0xF1F24CC446978F9B,
+                                    0x724BAE854C08DCBE, 0x4433C0B0FAC5E7BE),
+                ymx: GF25519::w64be(0x6A704FEC92FBCE6B, 0x241CD1D679BD3FDE,
+                                    0x7F42ED71A847B9EC, 0x36D9B8DE78F39B2D),
+                t2d: GF25519::w64be(0x681109BEE0DCFE37, 0xBF09E2F5FA66190B,
+                                    0xC102DF9402A092F8, 0xE917FB9E61095301) },
+    // (2^140)*B * 9
+    PointDuif { ypx: GF25519::w64be(0x13F50B4A919BC2FD, 0xCA2F140EB029722B,
+                                    0xE71BC28004E4733D, 0x5762D8060BDDDA5B),
+                ymx: GF25519::w64be(0x718B2AD721D0FA92, 0xB388E4BDFE750D46,
+                                    0x16D8E77F12F42AF4, 0x67E355E70B74B679),
+                t2d: GF25519::w64be(0x0DA8BC68CC4749FB, 0x31A4CC78E106A42D,
+                                    0x8F9BC50F6DDC2C4E, 0xA75F9E9135C3D1F0) },
+    // (2^140)*B * 10
+    PointDuif { ypx: GF25519::w64be(0x73187FA7C5C7CD96, 0x0E1DAB442D8740DC,
+                                    0xDE0AB6564B241559, 0xF5ECA4FD803DD355),
+                ymx: GF25519::w64be(0x622A9D7DA8EE4740, 0x28ED2D72BB93E68C,
+                                    0x2B1A00C9630E685A, 0xD23A31DE887C6285),
+                t2d: GF25519::w64be(0x397CB12559EAAD86, 0x37CB4544D713560B,
+                                    0x7D7880CAAA7EC4C5, 0x84C0142B04A84201) },
+    // (2^140)*B * 11
+    PointDuif { ypx: GF25519::w64be(0x68B58E920D0FFACB, 0x636BAE223093499B,
+                                    0x9B21D7BA7D73BC00, 0xEFB2A76344D19B8A),
+                ymx: GF25519::w64be(0x2E062D9265CCC761, 0x147AE873DD863002,
+                                    0x78D54DEFFB39E82F, 0xE7D90C2F01527611),
+                t2d: GF25519::w64be(0x090E311660E2A41A, 0xB9A55923C1A84FA0,
+                                    0x5EE6587570C4B340, 0xE82B2B75614714A8) },
+    // (2^140)*B * 12
+    PointDuif { ypx: GF25519::w64be(0x5727B3F2462E6C8A, 0x549E9762AF34EB63,
+                                    0xB45D07A840E48EA7, 0xBD8B148C43E25292),
+                ymx: GF25519::w64be(0x1FFC4FF2E1BACDF9, 0x5BBED49D6F220420,
+                                    0xF45467A4D32023C7, 0xC520589D9F497147),
+                t2d: GF25519::w64be(0x4AEB1DB9A870354B, 0x3D9A36649447D659,
+                                    0x0722500E7DA81ADD, 0xE18AFD4BF58D3E7A) },
+    // (2^140)*B * 13
+    PointDuif { ypx: GF25519::w64be(0x2BA53AA49E880127, 0x7FC03E5F8728CA36,
+                                    0xBA51453584F56946, 0x9927DFDF45EA9B0C),
+                ymx: GF25519::w64be(0x0C9CBBC1818D1AB4, 0x011BF863DC39BF5D,
+                                    0x9338F765CA2FCC1B, 0x2A8A84C356188F25),
+                t2d: GF25519::w64be(0x294A103E01BDBE84, 0xE05AAAD200D20F92,
+                                    0xB52B9CA3FDA18551, 0xDCEE5C21133879F7) },
+    // (2^140)*B * 14
+    PointDuif { ypx: GF25519::w64be(0x5ED0B58417D79BFE, 0xFA28D6CF33F5AF0D,
+                                    0x4770C8DCE55915AA, 0x7223B8D294494827),
+                ymx: GF25519::w64be(0x0D9DA8044FC82834, 0x8F2F67C738F34312,
+                                    0x0F92187804E08FFD, 0xF64154CCA7EEF00D),
+                t2d: GF25519::w64be(0x311E7BC493728125, 0x661966BE7501F8C4,
+                                    0xAA6FC20A74B9FFB9, 0x05FE50C4DC3A414F) },
+    // (2^140)*B * 15
+    PointDuif { ypx: GF25519::w64be(0x7E8B773078C649A9, 0x6CBF3141E1DAADAC,
+                                    0x30DB1AA155BAA602, 0xD60C4A64E03842EA),
+                ymx: GF25519::w64be(0x0635FE385B3D9551, 0x43F194E175BC0487,
+                                    0x698CF0183E4058BB, 0x5580F2DEA24EDE0A),
+                t2d: GF25519::w64be(0x5B56664E2BB5334D, 0xD7C86CC801572CE4,
+                                    0x0AB92B28C804FFCF, 0x5FE8A3ACB0928174) },
+    // (2^140)*B * 16
+    PointDuif { ypx: GF25519::w64be(0x26F57EEE878A19D4, 0xDC9F350B9F92D0AA,
+                                    0x29159DB373899DDD, 0x9C18FCFA36048D13),
+                ymx: GF25519::w64be(0x504AA7767973613D, 0x7F62865B31EF238C,
+                                    0x551DCDB2EA718385, 0x559A0CC9782A0DDE),
+                t2d: GF25519::w64be(0x4041943D9DBA3069, 0x85C15A344F5A2467,
+                                    0x5180D162247AF17B, 0x0CAB2CD55687EFB1) },
+];
+
+// Points i*(2^175)*B for i = 1 to 16, in Duif format.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B175: [PointDuif; 16] = [
+    // (2^175)*B * 1
+    PointDuif { ypx: GF25519::w64be(0x74071475BC927D0B, 0x0A6DE44EC3A4A3FB,
+                                    0x817FCC7DED6C0AF2, 0x1466F5AF5307FA11),
+                ymx: GF25519::w64be(0x1F699A54D78A2619, 0xED2995FF36B70A36,
+                                    0x0611D7253FDED2A0, 0x8C99CC5A8B3F55C3),
+                t2d: GF25519::w64be(0x2F9A2C4476BD2966, 0x1BD0653ED3274FDE,
+                                    0x296537D2CB045A31, 0xE77292F373E7EA8A) },
+    // (2^175)*B * 2
+    PointDuif { ypx: GF25519::w64be(0x3F6BD725DA4EA12D, 0x981F375DF5504234,
+                                    0x7AC860292BFFFF06, 0xA2B4DAE0B5511C9A),
+                ymx: GF25519::w64be(0x36597D25EA5C013D, 0xB72712DA2DF7AFA9,
+                                    0x023A8AEE5787C690, 0xEB18B9AB7F5745C6),
+                t2d: GF25519::w64be(0x7B7ECC19DA60D6D0, 0x6466F8F99202932D,
+                                    0xD940579E6FC6905F, 0x734D8D7B106058AC) },
+    // (2^175)*B * 3
+    PointDuif { ypx: GF25519::w64be(0x7DD93FEA6683420F, 0x7EB023D05322D57C,
+                                    0xA894F697154AE922, 0xB12139514D4ABB6C),
+                ymx: GF25519::w64be(0x37DA15B8BB4435B8, 0x70305B6E46C6DBDC,
+                                    0x4FBD7C241F4A720C, 0x362AB7B66D30FA1D),
+                t2d: GF25519::w64be(0x0E8A2F5731DF98BC, 0x8D1B45A26A8A576B,
+                                    0x3C304376700ECB0C, 0xCC056224B2A96028) },
+    // (2^175)*B * 4
+    PointDuif { ypx: GF25519::w64be(0x03BEDC661BF5CABA, 0x09BBFFCD8F2D82DB,
+                                    0x82263654E7A38650, 0x6DAE4A51A77CFA9B),
+                ymx: GF25519::w64be(0x4235AD7601743956, 0x951D44444AE12BD2,
+                                    0xDD252E660642906E, 0x78C2373C695C690D),
+                t2d: GF25519::w64be(0x0E7CE2B0CDF066A1, 0xA0CAB423E2E36EE4,
+                                    0x492942549189F298, 0x6258CB0D078975F5) },
+    // (2^175)*B * 5
+    PointDuif { ypx: GF25519::w64be(0x6A2DA02AE66D5DC5, 0x1C09A6F64DD23346,
+                                    0xC0C9C3D1EAC6BA84, 0x9A1AB80116A75D07),
+                ymx: GF25519::w64be(0x311F6E4B37520C71, 0x8E72ECDE9DC471C0,
+                                    0xC8BC171931F33697, 0x87CF4DCD2A1BD2D1),
+                t2d: GF25519::w64be(0x4DE371EDF49E9F8D, 0x85BF332402453573,
+                                    0xD3297880000A6ED4, 0xC69036439B4BEDE1) },
+    // (2^175)*B * 6
+    PointDuif { ypx: GF25519::w64be(0x615256138AECEEB5, 0x4882D47E7F2FAB89,
+                                    0xF130C051C1FCBA2D, 0xFEA6FEDFD94B70F9),
+                ymx: GF25519::w64be(0x666E0A5D8FB4674A, 0x09DB17DD3AE94D48,
+                                    0xFD361DF43C6139AD, 0xC494643AC48C85A3),
+                t2d: GF25519::w64be(0x7F0BC810D514DEE4, 0x9ABE4EBA75E8985D,
+                                    0xCD65BCF0AA458B6B, 0x2ABBF64E4870CB0D) },
+    // (2^175)*B * 7
+    PointDuif { ypx: GF25519::w64be(0x38DA73541998890A, 0xDC5F06596DD99FC6,
+                                    0x2CA05498A41B12A6, 0xC3940B9745BC15EB),
+                ymx: GF25519::w64be(0x76787DB829AB2CC9, 0xF88158C0E0A5FE4B,
+                                    0x759E577956C69105, 0x13C4BC68F462C14B),
+                t2d: GF25519::w64be(0x3DECC9ACCAE6539F, 0x526EAFAA85CFC5D4,
+                                    0xDDA26C3C5B075DC4, 0x34AA2A0ED3C1E7B6) },
+    // (2^175)*B * 8
+    PointDuif { ypx: GF25519::w64be(0x1D3A907DDEC5AB75, 0x311E2EDD43EC6957,
+                                    0x9FF6F8BA2EF72E98, 0x83AC9DAD737213A0),
+                ymx: GF25519::w64be(0x0D1F8DBCF8EEDBF5, 0xCBC8DFD94F463C28,
+                                    0x8D67369E57E03035, 0xB9006BA426F4136F),
+                t2d: GF25519::w64be(0x00011B44A31BFDE3, 0x0128013C030321CB,
+                                    0x29329FAD851B3480, 0xBA1693313ED081DC) },
+    // (2^175)*B * 9
+    PointDuif { ypx: GF25519::w64be(0x0C1321DA0D12554E, 0x87E0F8CBF974D74F,
+                                    0x5383C8A0615830C9, 0x24A5E0A59A1FDFF4),
+                ymx: GF25519::w64be(0x160C07D1A43E624A, 0x02EA7FF97B8762A5,
+                                    0x4573300D905CFC00, 0x9A0F802F42D2DFB3),
+                t2d: GF25519::w64be(0x2D1EC309C3128CB9, 0xCFB9843AE2CD8AFC,
+                                    0xD7D13C8ACDF73ABF, 0x801B58B61EB60999) },
+    // (2^175)*B * 10
+    PointDuif { ypx: GF25519::w64be(0x63D988A2D2851026, 0x11A8DD7F9A7966AD,
+                                    0xC1BF725C5852BD6A, 0x16561F696A0AA75C),
+                ymx: GF25519::w64be(0x3AC48D916E8357E1, 0x7AE38B38268E4D71,
+                                    0x5D40E38E4DD60DD2, 0x3FDFA06C3FC66C0C),
+                t2d: GF25519::w64be(0x33FAD52B2368A066, 0xF81669B384E72B91,
+                                    0xE92BCEB8FDD8F683, 0x00120753AFBD232E) },
+    // (2^175)*B * 11
+    PointDuif { ypx: GF25519::w64be(0x5812D613C8F7AB33, 0x71881FA41484D9D8,
+                                    0x7DA35E9A11AA0B78, 0x2743F95B7BF78F19),
+                ymx: GF25519::w64be(0x60F53E4554AE8752, 0xC125419C3D20A9AA,
+                                    0xEE994B07E2883308, 0x0FC0C08A2281CF0E),
+                t2d: GF25519::w64be(0x76C0BC468F7526FD, 0x4CF3234D478ABA9C,
+                                    0x889909E6D4C96349, 0x3855AFF2E70B6EA0) },
+    // (2^175)*B * 12
+    PointDuif { ypx: GF25519::w64be(0x3CC355CCB90A71E2, 0xA3FEB6E6ECF6A56F,
+                                    0x072B4F7B05A13ACB, 0x8D2CC8D0C422CFE8),
+                ymx: GF25519::w64be(0x16C0F429A256DCA7, 0xB2ACFCD2F305E746,
+                                    0x0AF86430333F7735, 0x540649C6C5E41E16),
+                t2d: GF25519::w64be(0x631EAF426BAE7568, 0xC87CD1A4BABA9244,
+                                    0xB8A494CB7A5637CE, 0xE9B69443903E9131) },
+    // (2^175)*B * 13
+    PointDuif { ypx: GF25519::w64be(0x724E7FBEF47A0BE3, 0x329F2DDC5FC5674A,
+                                    0x3508FC2E3DF0E9E6, 0x381FFDDC16A5818C),
+                ymx: GF25519::w64be(0x55ADDBE5F5A519A0, 0x2AC2F8C88FE817E8,
+                                    0x0D2A9D4C1423FA07, 0xF2C10E8B33C7919B),
+                t2d: GF25519::w64be(0x588AC85EEFBFD497, 0x3A2B0BF6262FB7F8,
+                                    0xAD2D794E83AB2FD6, 0x870A5A334902941E) },
+    // (2^175)*B * 14
+    PointDuif { ypx: GF25519::w64be(0x431F2C7F665F80B5, 0x53658F2732E45DE1,
+                                    0x7280C5FBE2F80552, 0x47D975B9A3700DE8),
+                ymx: GF25519::w64be(0x5599648B1EA919B5, 0xBC3D97611EF9BF83,
+                                    0x85DD4B526C16E5A6, 0xB3E90410DA66FE9F),
+                t2d: GF25519::w64be(0x7B04715F91253B26, 0x8900441A2090A9D7,
+                                    0x14AB352FA1EA514A, 0xD6026344858F7B19) },
+    // (2^175)*B * 15
+    PointDuif { ypx: GF25519::w64be(0x0AB43BBA319E5CA5, 0xB875328BD12D19FC,
+                                    0xA98CE4D1B7C70F5E, 0xF2AF1E2122D8DD3F),
+                ymx: GF25519::w64be(0x6EE9D6953F4C454E, 0xED34A67351D1449C,
+                                    0x6A8ABE4A84316EFB, 0xAC305F6293B728D1),
+                t2d: GF25519::w64be(0x03551BEA61610B42, 0x78FCE1C5822D9142,
+                                    0xE9C95EBBFE9C87D5, 0xFE619A76E585D058) },
+    // (2^175)*B * 16
+    PointDuif { ypx: GF25519::w64be(0x48D0ACFA57CDE223, 0xB09A9558450BF944,
+                                    0x970ED3DD6D1D9B0B, 0xB376C280C4E6BAC6),
+                ymx: GF25519::w64be(0x59B37BF5C2F6583F, 0xC0404769B7EB2C44,
+                                    0x86357C8B7D5C7AB4, 0x83EDBD28ACF6AE43),
+                t2d: GF25519::w64be(0x16234191336D3BDB, 0x4208CE7EE9960394,
+                                    0xF1D1A197622F3A37, 0xB60F26E47DABE671) },
+];
+
+// Points i*(2^210)*B for i = 1 to 16, in Duif format.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B210: [PointDuif; 16] = [
+    // (2^210)*B * 1
+    PointDuif { ypx: GF25519::w64be(0x30F4452EDCBC1B65, 0xE9D2E163C7B4F632,
+                                    0xE152A5002C40483A, 0xC845DFA56DE66FDE),
+                ymx: GF25519::w64be(0x38DC083705ACD0FD, 0x3A85A94514A93CB5,
+                                    0x1D168F6960E6F45D, 0x18FB8A7559230A93),
+                t2d: GF25519::w64be(0x632D9A1A593F2469, 0x8844FC73C0EA4E71,
+                                    0xFA134569F99CBECC, 0x856D2782C5759740) },
+    // (2^210)*B * 2
+    PointDuif { ypx: GF25519::w64be(0x3058AD43D1838620, 0xBD1924778C1061A3,
+                                    0xFB35068987ACBA3F, 0x3304FB0E63066222),
+                ymx: GF25519::w64be(0x08F5114789A8DBA8, 0xB491C1E014CC3E6D,
+                                    0x4999EDDECA5D3E71, 0xB16C62F587E593FB),
+                t2d: GF25519::w64be(0x26549FA4EFE3DC99, 0xBDC78ABDAC994F9A,
+                                    0x05C3DF38A22EA610, 0x323C0FFDE57663D0) },
+    // (2^210)*B * 3
+    PointDuif { ypx: GF25519::w64be(0x16E05886620C8C83, 0x815A098BBB9F9FD4,
+                                    0xF0743E6B54CE640C, 0x69868D5FFEBB50A7),
+                ymx: GF25519::w64be(0x2AAB4CDEB6AFA0DC, 0xFCEFE07CFD18F4F5,
+                                    0x16156E8E86140488, 0x6BD4D462DE864067),
+                t2d: GF25519::w64be(0x121D162BDA6FE00B, 0xF294D389F116DEEA,
+                                    0x6EA0CDB37060B971, 0xB25FB012EA8A685A) },
+    // (2^210)*B * 4
+    PointDuif { ypx: GF25519::w64be(0x606175F6332E25D2, 0xC6409D99F53EABC6,
+                                    0x69E6A2D7D2F86746, 0x04DBBC17F75396B9),
+                ymx: GF25519::w64be(0x49128C7F727275C9, 0x30738C9CF151316D,
+                                    0xB62658E24179A88D, 0x738B38D787CE8F89),
+                t2d: GF25519::w64be(0x6A39E6356944F235, 0x4634AACD5B06B807,
+                                    0x0910D6F5A1F1D0A5, 0x4021370EF540E7DD) },
+    // (2^210)*B * 5
+    PointDuif { ypx: GF25519::w64be(0x6078628121B4D1BB, 0x509895EFD38E4AC8,
+                                    0x6BD1215A64352758, 0x86574401DE1F0B8B),
+                ymx: GF25519::w64be(0x12FFF160AB3EFE68, 0xE7476FB8A019E9C1,
+                                    0xF754110D76A447A6, 0x70E5C27B7DB5F6C1),
+                t2d: GF25519::w64be(0x78DA471A8077DC0F, 0x6ECF90ABADE37883,
+                                    0x3E40CB74DC025290, 0x37F83BAC36DD6B3C) },
+    // (2^210)*B * 6
+    PointDuif { ypx: GF25519::w64be(0x792E6EE065D751FF, 0xD34F467544BC1863,
+                                    0xD5FF666F4FEB12D2, 0x94AF90407FF40AAC),
+                ymx: GF25519::w64be(0x74B9962FF12A2D8D, 0xFE5DDDB1E60B380C,
+                                    0x9456EDA831DD5817, 0xF80D81ADF1B88C86),
+                t2d: GF25519::w64be(0x3E761555F0F14EB7, 0x284B1ACC692C2A77,
+                                    0x6D574726588406FB, 0x0640531002D9D83B) },
+    // (2^210)*B * 7
+    PointDuif { ypx: GF25519::w64be(0x70DD0D5C3332A259, 0x124ACE20F6C83066,
+                                    0x9827991A1A0864F1, 0x99CB093B01E8FB26),
+                ymx: GF25519::w64be(0x2318291C4BD40E7E, 0x45340014EB969F43,
+                                    0x90C1545B3C2030BC, 0x06E95187577C6496),
+                t2d: GF25519::w64be(0x476A1B78DE28D591, 0x2C29DD45A31391A1,
+                                    0xD563FBDD5B5F906F, 0xBBBCFF53FBFA638E) },
+    // (2^210)*B * 8
+    PointDuif { ypx: GF25519::w64be(0x1F5EC83D3F9846E2, 0xB1CBCD50CC5236A6,
+                                    0xFBCD6EA198FE352B, 0x1DA1965774049E9D),
+                ymx: GF25519::w64be(0x42889E7E530D2E58, 0x24F3EF0959E33CC4,
+                                    0x6C3A760EDBFA25EA, 0x96CD5640DF90F3E7),
+                t2d: GF25519::w64be(0x241E246B06BF9F51, 0x20FBDADC5DFAE796,
+                                    0xAF42A207DD876EE9, 0x8EFB23C3328CCB75) },
+    // (2^210)*B * 9
+    PointDuif { ypx: GF25519::w64be(0x68FA0077621A286F, 0x93A03CA2A234D58F,
+                                    0x14A0814F39DFB0B2, 0x3FDB342234C38720),
+                ymx: GF25519::w64be(0x14919D2861314181, 0xDE08C09EB3999DF9,
+                                    0x52B59C8DD4DA31EF, 0xB4F279A3BFBA8C75),
+                t2d: GF25519::w64be(0x7E5772F65BFEA242, 0x8654D1D2BC392F08,
+                                    0xA93B0D25992CBE3F, 0xBF4AF3BC8BC6FCBD) },
+    // (2^210)*B * 10
+    PointDuif { ypx: GF25519::w64be(0x57333DB8A78B20E3, 0x5D8D83A975079BDF,
+                                    0x1684B60A3F38891D, 0xF8B4CE7012053BED),
+                ymx: GF25519::w64be(0x344801B82B259A6C, 0x07ECE76067B170CE,
+                                    0x3E197CE2F9498020, 0xA4498A2F6555415B),
+                t2d: GF25519::w64be(0x67E4405B048ADD7A, 0x218A8DC30C5F18DE,
+                                    0x9E41C8A818AE391B, 0xA33D73BE3680DFAD) },
+    // (2^210)*B * 11
+    PointDuif { ypx: GF25519::w64be(0x7A97EDA3A1C7CC76, 0x98C79DD0A7FECE23,
+                                    0x3DCCE2FE471A5789, 0xCBD918BECDAB4C20),
+                ymx: GF25519::w64be(0x2DA07D7A9B4128AF, 0xB0E6A114975E4A5E,
+                                    0x2DFF8994A2759D67, 0x729B2178B9DF8ABF),
+                t2d: GF25519::w64be(0x4E225ED2835B9A7E, 0x0F470B3F6E1F7968,
+                                    0x3F01C666FBC27E77, 0xFB7DF7AA601D2989) },
+    // (2^210)*B * 12
+    PointDuif { ypx: GF25519::w64be(0x2BC65635E8BD53EE, 0x31CE40BB1BFC8D20,
+                                    0x22A70F12F403D809, 0x7EAAFC9A6280BBB8),
+                ymx: GF25519::w64be(0x5BC2C77FB38D9097, 0x3F00862EA51EBB4B,
+                                    0x4C9260C80B462065, 0x29E68E57AD6E98F6),
+                t2d: GF25519::w64be(0x1FDD6C3B034A7830, 0x681532EA65185FA3,
+                                    0xE58FB17DDE1947DC, 0xE8D5DC9FA96BAD93) },
+    // (2^210)*B * 13
+    PointDuif { ypx: GF25519::w64be(0x086BA5215B57E65B, 0x1F8492223BFA7C9E,
+                                    0xEBCA9FC0176E9407, 0x0D772295E5AF3331),
+                ymx: GF25519::w64be(0x63F1730C5EE03A5F, 0xADDDA6884499A4F0,
+                                    0xC44048C3D5B6CDD5, 0xFEFB1C9932A678C6),
+                t2d: GF25519::w64be(0x09209D93FD84498F, 0xB0DDC54638574CF5,
+                                    0x02D0C5052E9134A8, 0xD634FD5372CEA6DE) },
+    // (2^210)*B * 14
+    PointDuif { ypx: GF25519::w64be(0x354D55BEA1AD5843, 0x4FF493B0AA9C7D96,
+                                    0xDEA084BDE6CC2D39, 0x743EBCE89BB1CB45),
+                ymx: GF25519::w64be(0x474FB8E1047ADD13, 0xF896BA2F16EA7685,
+                                    0xFD235D1E6E8243C6, 0x6E68300997ECB7D5),
+                t2d: GF25519::w64be(0x5CFBBA3D0B564016, 0xC58C8CD71F19907E,
+                                    0x067FA63C9B9B6851, 0x9278A2C01800D98B) },
+    // (2^210)*B * 15
+    PointDuif { ypx: GF25519::w64be(0x094056FA8ECEE5C0, 0x786B77BBEC38721F,
+                                    0xD060AF4D93C84B28, 0xEA56A35ABFEC5336),
+                ymx: GF25519::w64be(0x4C7EA6DB6F4BB996, 0x536CEFFF6B7EE198,
+                                    0xB2CCD28C2CA7D7A3, 0xA87BE18267E9A0F7),
+                t2d: GF25519::w64be(0x757EAA99D8EF6719, 0xA1A39CC7CC372588,
+                                    0xD88CA5A82C5D34BE, 0x772969FEE6948AF3) },
+    // (2^210)*B * 16
+    PointDuif { ypx: GF25519::w64be(0x48C8A121BBE6C9E5, 0x961E32405E08F7B5,
+                                    0x2DBB1F8C3EFDCABF, 0x9C13A6A52DD8F7A9),
+                ymx: GF25519::w64be(0x35FF7FC33AE4CC0E, 0x79AC432370E2E652,
+                                    0xE3DF9E993399EBDD, 0x0A64E28C55DC18FE),
+                t2d: GF25519::w64be(0x42D7A91274429FAB, 0x6035C9C905FBB912,
+                                    0xD224B2D7C128B615, 0xFC415A7C59646445) },
+];
+
+// Points i*(2^245)*B for i = 1 to 16, in Duif format.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B245: [PointDuif; 16] = [
+    // (2^245)*B * 1
+    PointDuif { ypx: GF25519::w64be(0x5FBA433DD082ED00, 0xB9EF494DB7CBABBD,
+                                    0x243B9C526F9AC26B, 0xCC991B4138B41246),
+                ymx: GF25519::w64be(0x48B46BEEBAA1D1B9, 0x8E49E035D4CCFFCA,
+                                    0x1A8C6A2D80ABC617, 0xF37CA2AB3D343DFF),
+                t2d: GF25519::w64be(0x0ECFEDF8E8E710B3, 0x41C3FED066663E5C,
+                                    0xB9734ADE74498F84, 0x9C49E355C9941AD0) },
+    // (2^245)*B * 2
+    PointDuif { ypx: GF25519::w64be(0x5A71945B48E2D1F1, 0x1D0E8463850C3699,
+                                    0x71B177CC0B98369E, 0x3BD82DBFDA777DF6),
+                ymx: GF25519::w64be(0x2617E120CDB8F73C, 0xEC2FB0D9ADA95734,
+                                    0x6C6663D9C4AD2B53, 0x8497404D0D55E274),
+                t2d: GF25519::w64be(0x77DE29FC11FFEB6A, 0x9C347230AC2A8846,
+                                    0x327EC60410B24509, 0x6F203DD5405B4B42) },
+    // (2^245)*B * 3
+    PointDuif { ypx: GF25519::w64be(0x352721C2BCDA9C51, 0x41A85F31F6CB1F47,
+                                    0xDC9821E174D78A71, 0x6D822986698A19E0),
+                ymx: GF25519::w64be(0x4EEECF0AD5C73A95, 0xC467E7FA7FF27379,
+                                    0x149145C93B0E40B7, 0x085AE2C759FF1BE4),
+                t2d: GF25519::w64be(0x2D5B2D842ED24C21, 0x8E5261B166C15AA5,
+                                    0x1087CF0D368A1746, 0x48329952213FC985) },
+    // (2^245)*B * 4
+    PointDuif { ypx: GF25519::w64be(0x5FCA747AA82ADF18, 0x090820304B6292BE,
+                                    0xC2D4A2032B64CEE0, 0x28302E71630EF9F6),
+                ymx: GF25519::w64be(0x17377BD75BB810F9, 0x3EA732DC21858DEA,
+                                    0x3E50F49F1651BE01, 0x7EB9EFB23FE24C74),
+                t2d: GF25519::w64be(0x04A8933CAB76862B, 0x3DAD8D0D2E442166,
+                                    0x86F23A2C6BCB0CF1, 0x232A03C35C258EA5) },
+    // (2^245)*B * 5
+    PointDuif { ypx: GF25519::w64be(0x7E89496707553BD6, 0xFFD49F2A5CA402CC,
+                                    0x50AFB0F0302B0571, 0x3B8A6E649388CA53),
+                ymx: GF25519::w64be(0x0C91DFC2C787514C, 0x973A26EED9D41FB4,
+                                    0x4521C051BB0767E9, 0x936B928585643C93),
+                t2d: GF25519::w64be(0x69A469596FBC4C15, 0x80D6CABF73A4E50C,
+                                    0x2D2C00C3387C1F28, 0xC21D5FD01659642E) },
+    // (2^245)*B * 6
+    PointDuif { ypx: GF25519::w64be(0x26C65917E6F34387, 0x4A215707C5BA0FD1,
+                                    0xFFBA4CE7A3360231, 0x7EC47E6D2C946395),
+                ymx: GF25519::w64be(0x0E1EBA929B853202, 0x3432D6B7BC175F63,
+                                    0xCCB3BC756FF0BD89, 0xA32B3CEDF15913CC),
+                t2d: GF25519::w64be(0x3939824B1E5C082C, 0x1DE1CB49736195D4,
+                                    0x2CF17DD2B1DADAE8, 0xF043561BDD87BEE2) },
+    // (2^245)*B * 7
+    PointDuif { ypx: GF25519::w64be(0x37C78E1A999AAF8D, 0x8F098B71FBCC8088,
+                                    0xFFA195BF89670F41, 0xD8E656E00D50F42F),
+                ymx: GF25519::w64be(0x31CD295C263B4BD5, 0x9BD9F9B22AC90416,
+                                    0xA7C61CE18D3A5102, 0x8CC3DBC7200D96AE),
+                t2d: GF25519::w64be(0x6713B83B3FE29D02, 0xB50FF47FF3570517,
+                                    0xFE43C4EEA679E77D, 0x7ED17B734CF721DC) },
+    // (2^245)*B * 8
+    PointDuif { ypx: GF25519::w64be(0x4005419B1D2BC140, 0x6FB73E54C4DFB634,
+                                    0xF9C9A035C1DAC5B6, 0x69082B0E8C936A50),
+                ymx: GF25519::w64be(0x0FA3614F3B1CA6BF, 0x5D254FF397808678,
+                                    0xBC8CBECE44CFB3A0, 0xD2C604B622943DFF),
+                t2d: GF25519::w64be(0x1FBA218AEF40AB42, 0xF8499F911954FA8E,
+                                    0x2089C1AF3A44AC90, 0xA003FEBDB9BE82F0) },
+    // (2^245)*B * 9
+    PointDuif { ypx: GF25519::w64be(0x2D158F446696F93B, 0x5C37342093B9F7C5,
+                                    0xB8382A826C0C78E5, 0x40A697F2B211FBF1),
+                ymx: GF25519::w64be(0x1C1097155A881948, 0x33F14A2B06F6AF2B,
+                                    0xB29D65905846EF2D, 0xAB4D1BA8E485D906),
+                t2d: GF25519::w64be(0x5DD0EAFFE381DCD2, 0x25A86D8719EC55E9,
+                                    0xB9D82580D43AAE54, 0xCF1560DDBD177AE9) },
+    // (2^245)*B * 10
+    PointDuif { ypx: GF25519::w64be(0x1EE5607DE32BB35C, 0x0FF6DFFD40C649C0,
+                                    0x95047EE20B472265, 0x65248AA618B2A0CA),
+                ymx: GF25519::w64be(0x682CCCCF1A1ADB96, 0xD6A18F42BE67D5D7,
+                                    0x4241D955A8A1E641, 0x807964FA65772413),
+                t2d: GF25519::w64be(0x5C87404A739171DA, 0x6983E7D0B2F78CE8,
+                                    0xF47138ECF73C5C06, 0xE193DCB6AF86F421) },
+    // (2^245)*B * 11
+    PointDuif { ypx: GF25519::w64be(0x1E2BD5ADA9433F43, 0x86093A545649DC9F,
+                                    0x86A902B688320017, 0x117B4016162BB05B),
+                ymx: GF25519::w64be(0x0A48CA2331C7EF31, 0xC90F6D25F262D5B8,
+                                    0x4073BAABC57BF6EA, 0xC0AD56B9E00D8D36),
+                t2d: GF25519::w64be(0x2322128AE217EE99, 0x28953CED2521D734,
+                                    0x75E2FF5B42620E8A, 0xD60C2360C9AE46D1) },
+    // (2^245)*B * 12
+    PointDuif { ypx: GF25519::w64be(0x0BEEC6B7572CC1B9, 0xB4C0724876D251FD,
+                                    0xBAFC40E9058D03A7, 0x98370AAB8551A012),
+                ymx: GF25519::w64be(0x3F2931E363245B5F, 0xC83E0E698C40A1FD,
+                                    0xF160B5D1B457BB44, 0x3215471F0062E1EC),
+                t2d: GF25519::w64be(0x5B2AE65A22BFD7A8, 0x392CDD475766C362,
+                                    0x318CB03F6C5E64D6, 0x4BCC1B77438C1BDE) },
+    // (2^245)*B * 13
+    PointDuif { ypx: GF25519::w64be(0x49492EB61CF97A5B, 0x2D826B8EC48831A1,
+                                    0x1384A8A9837753B1, 0x0882A69FC64A3ABB),
+                ymx: GF25519::w64be(0x1DE5040EDC6D8AB7, 0x1F70B9C579814792,
+                                    0x3C27A260EC2875BC, 0xFD2211A758532FF1),
+                t2d: GF25519::w64be(0x2EA786739A411F29, 0x8F286EEA36C9E2BF,
+                                    0xC51EDF86FA924431, 0x398A362EAD2938B3) },
+    // (2^245)*B * 14
+    PointDuif { ypx: GF25519::w64be(0x09FFFF2E8360CE83, 0xBCB3FF45DDDC9D07,
+                                    0xFF0148AAE75C6FAA, 0x3726AAFD161E284A),
+                ymx: GF25519::w64be(0x3F7D6D95E293B4C0, 0x1C3FF21CA915F99F,
+                                    0x1FAE250D8FFB076B, 0x43AFDD6A49BD5A61),
+                t2d: GF25519::w64be(0x4E949BFCAE0E63AD, 0x1E7F71A44985E403,
+                                    0xA83BEFEFA25C5C2A, 0x2FAC6C568BD9951A) },
+    // (2^245)*B * 15
+    PointDuif { ypx: GF25519::w64be(0x5C0CA5A2C32620FD, 0x6C6D36BF2909999A,
+                                    0x252D4105CB24094F, 0x465E637A0D8BCF88),
+                ymx: GF25519::w64be(0x63445E3A43B14471, 0x51FA5069A669E4A3,
+                                    0x0EBBC77716A226A0, 0x1ECE8939367118B8),
+                t2d: GF25519::w64be(0x19A68EB9F6E1ADB7, 0x22E3D4722A44E7E8,
+                                    0xE2725F53BF0DD3A6, 0x90FBE44952151D63) },
+    // (2^245)*B * 16
+    PointDuif { ypx: GF25519::w64be(0x6C535D13FF7761D5, 0xC839C6AB99DCDEF1,
+                                    0xA81D3EEE08DAAF7F, 0x4F3E57043E7B0194),
+                ymx: GF25519::w64be(0x392E3ACAA8C86E42, 0x74FD6C7D6C2B5E01,
+                                    0x81F6E89A7BA63741, 0xAB549448FAC8F53E),
+                t2d: GF25519::w64be(0x25E17FE4D50AC13B, 0x19319C76F29AB0AB,
+                                    0x2E0781445887E816, 0x4CBD34E93E8A35AF) },
+];
+// Tables for the larger comb (`bigtables` feature): PRECOMP_B_COMB[k]
+// contains the points i*(2^(35*k))*B for i = 1 to 16.
+#[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+static PRECOMP_B_COMB: [&[PointDuif; 16]; 8] = [
+    &PRECOMP_B, &PRECOMP_B35, &PRECOMP_B70, &PRECOMP_B105,
+    &PRECOMP_B140, &PRECOMP_B175, &PRECOMP_B210, &PRECOMP_B245,
+];
+
 // ========================================================================
 
 /// Let U_i = i*(2^240)*B  (i = 0 to 16384, B = generator).
@@ -8261,8 +11193,13 @@ static UX_COMP: [u64; 16385] = [
 #[cfg(test)]
 mod tests {
 
-    use super::{Point, Scalar, PrivateKey, PublicKey};
+    use super::{Point, Scalar, PrivateKey, ExpandedPrivateKey, PublicKey};
+    use super::{verify_batch, verify_batch_find_invalid, PrecomputedTable};
+    use super::{verify_same_message, verify_same_message_find_invalid};
+    use super::{PublicKeyPrecomp, SignSession, HASH_HEAD};
+    use crate::{DecodeError, ParseError};
     use sha2::{Sha256, Sha512, Digest};
+    use crate::Vec;
 
     /* unused
     use std::fmt;
@@ -8432,6 +11369,65 @@ mod tests {
         assert!(Q.encode() == enc);
     }
 
+    #[test]
+    fn mulgen_tables() {
+        // Scalars with digits at the chunk boundaries of both the default
+        // and the `bigtables` combs.
+        let mut edge = [Scalar::ZERO, Scalar::ONE, -Scalar::ONE];
+        let mut x = Scalar::ONE;
+        for i in 0..253 {
+            if i == 5 * 13 || i == 5 * 35 || i == 5 * 49 {
+                edge[0] += x;
+                edge[1] -= x;
+            }
+            x = x + x;
+        }
+        for s in edge.iter() {
+            let P = Point::mulgen(s);
+            let Q = Point::BASE * s;
+            assert!(P.equals(Q) == 0xFFFFFFFF);
+        }
+
+        // Recompute the tables of the larger comb.
+        #[cfg(all(feature = "bigtables", not(feature = "small_tables_ed25519")))]
+        {
+            use super::{PRECOMP_B_COMB, GF25519};
+            let mut T = Point::BASE;
+            for k in 0..8 {
+                let mut P = T;
+                for i in 0..16 {
+                    let e = &PRECOMP_B_COMB[k][i];
+                    let iZ = GF25519::ONE / P.Z;
+                    let x = P.X * iZ;
+                    let y = P.Y * iZ;
+                    assert!(e.ypx.equals(y + x) == 0xFFFFFFFF);
+                    assert!(e.ymx.equals(y - x) == 0xFFFFFFFF);
+                    assert!(e.t2d.equals((x * y).mul2() * Point::D) == 0xFFFFFFFF);
+                    P += T;
+                }
+                T.set_xdouble(35);
+            }
+        }
+
+        // Check the window computed at runtime with small tables.
+        #[cfg(feature = "small_tables_ed25519")]
+        {
+            use super::GF25519;
+            let win = Point::make_precomp_b130();
+            let T = Point::BASE.xdouble(130);
+            let mut P = T;
+            for e in win.iter() {
+                let iZ = GF25519::ONE / P.Z;
+                let x = P.X * iZ;
+                let y = P.Y * iZ;
+                assert!(e.ypx.equals(y + x) == 0xFFFFFFFF);
+                assert!(e.ymx.equals(y - x) == 0xFFFFFFFF);
+                assert!(e.t2d.equals((x * y).mul2() * Point::D) == 0xFFFFFFFF);
+                P += T;
+            }
+        }
+    }
+
     #[test]
     fn mul() {
         let mut sh = Sha256::new();
@@ -8474,6 +11470,30 @@ mod tests {
             let R1 = u * A + Point::mulgen(&v);
             let R2 = A.mul_add_mulgen_vartime(&u, &v);
             assert!(R1.equals(R2) == 0xFFFFFFFF);
+            let R3 = A.mul_add_mulgen(&u, &v);
+            assert!(R1.equals(R3) == 0xFFFFFFFF);
+
+            // Edge cases: zero scalars, neutral point, and a point
+            // outside of the prime order subgroup.
+            let T8_enc: [u8; 32] = [
+                0x26, 0xE8, 0x95, 0x8F, 0xC2, 0xB2, 0x27, 0xB0,
+                0x45, 0xC3, 0xF4, 0x89, 0xF2, 0xEF, 0x98, 0xF0,
+                0xD5, 0xDF, 0xAC, 0x05, 0xD3, 0xC6, 0x33, 0x39,
+                0xB1, 0x38, 0x02, 0x88, 0x6D, 0x53, 0xFC, 0x85,
+            ];
+            let T = A + Point::decode(&T8_enc).unwrap();
+            for (P, u, v) in [
+                (A, Scalar::ZERO, v), (A, u, Scalar::ZERO),
+                (A, Scalar::ZERO, Scalar::ZERO),
+                (Point::NEUTRAL, u, v), (T, u, v), (T, -u, Scalar::ZERO),
+            ].iter()
+            {
+                let R1 = u * P + Point::mulgen(v);
+                let R2 = P.mul_add_mulgen_vartime(u, v);
+                let R3 = P.mul_add_mulgen(u, v);
+                assert!(R1.equals(R2) == 0xFFFFFFFF);
+                assert!(R1.equals(R3) == 0xFFFFFFFF);
+            }
         }
     }
 
@@ -8620,85 +11640,1039 @@ mod tests {
     ];
 
     #[test]
-    fn signatures() {
+    fn signatures() {
+        for tv in TEST_VECTORS.iter() {
+            let seed = hex::decode(tv.s).unwrap();
+            let Q_enc = hex::decode(tv.Q).unwrap();
+            let msg = hex::decode(tv.m).unwrap();
+            let ctx = hex::decode(tv.ctx).unwrap();
+            let mut sig = [0u8; 64];
+            hex::decode_to_slice(tv.sig, &mut sig[..]).unwrap();
+
+            let skey = PrivateKey::from_seed(&seed[..]);
+            assert!(&Q_enc[..] == skey.public_key.encode());
+            if tv.dom {
+                if tv.ph {
+                    let mut sh = Sha512::new();
+                    sh.update(&msg[..]);
+                    let hm = sh.finalize();
+                    assert!(skey.sign_ph(&ctx[..], &hm) == sig);
+                } else {
+                    assert!(skey.sign_ctx(&ctx[..], &msg[..]) == sig);
+                }
+            } else {
+                assert!(skey.sign_raw(&msg[..]) == sig);
+            }
+
+            let pkey = PublicKey::decode(&Q_enc[..]).unwrap();
+            if tv.dom {
+                if tv.ph {
+                    let mut sh = Sha512::new();
+                    sh.update(&msg[..]);
+                    let mut hm = sh.finalize();
+                    assert!(pkey.verify_ph(&sig, &ctx[..], &hm));
+                    assert!(!pkey.verify_ph(&sig, &[1u8], &hm));
+                    hm[42] ^= 0x08;
+                    assert!(!pkey.verify_ph(&sig, &ctx[..], &hm));
+                } else {
+                    assert!(pkey.verify_ctx(&sig, &ctx[..], &msg[..]));
+                    assert!(!pkey.verify_ctx(&sig, &[1u8], &msg[..]));
+                    assert!(!pkey.verify_ctx(&sig, &ctx[..], &[0u8]));
+                }
+            } else {
+                assert!(pkey.verify_raw(&sig, &msg[..]));
+                assert!(!pkey.verify_raw(&sig, &[0u8]));
+                assert!(pkey.verify_ct(&sig, &msg[..]));
+                assert!(!pkey.verify_ct(&sig, &[0u8]));
+                let mut sig2 = sig;
+                sig2[5] ^= 0x01;
+                assert!(!pkey.verify_ct(&sig2, &msg[..]));
+                assert!(!pkey.verify_ct(&sig[..63], &msg[..]));
+            }
+        }
+    }
+
+    #[test]
+    fn expanded() {
+        // from_expanded() with the pruned SHA-512(seed) halves must yield
+        // the same keys and signatures as the seed-based path.
+        for tv in TEST_VECTORS.iter() {
+            let seed = hex::decode(tv.s).unwrap();
+            let Q_enc = hex::decode(tv.Q).unwrap();
+            let msg = hex::decode(tv.m).unwrap();
+            let ctx = hex::decode(tv.ctx).unwrap();
+            let mut sig = [0u8; 64];
+            hex::decode_to_slice(tv.sig, &mut sig[..]).unwrap();
+
+            let hh = Sha512::digest(&seed[..]);
+            let mut scalar = [0u8; 32];
+            let mut prefix = [0u8; 32];
+            scalar[..].copy_from_slice(&hh[..32]);
+            prefix[..].copy_from_slice(&hh[32..]);
+            scalar[0] &= 0xF8;
+            scalar[31] &= 0x7F;
+            scalar[31] |= 0x40;
+            let ekey = ExpandedPrivateKey::from_expanded(&scalar, &prefix);
+            assert!(&Q_enc[..] == ekey.public_key.encode());
+            if tv.dom {
+                if tv.ph {
+                    let hm = Sha512::digest(&msg[..]);
+                    assert!(ekey.sign_ph(&ctx[..], &hm) == sig);
+                } else {
+                    assert!(ekey.sign_ctx(&ctx[..], &msg[..]) == sig);
+                }
+            } else {
+                assert!(ekey.sign_raw(&msg[..]) == sig);
+            }
+        }
+
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let seed = sh.finalize_reset();
+            let skey = PrivateKey::from_seed(&seed[..]);
+            let ekey = skey.expand();
+            assert!(ekey.public_key.encoded == skey.public_key.encoded);
+            for j in 0..10 {
+                let msg = [i as u8; 64];
+                let msg = &msg[..(j * 7)];
+                assert!(ekey.sign_raw(msg) == skey.sign_raw(msg));
+                assert!(ekey.sign_ctx(b"ctx", msg) == skey.sign_ctx(b"ctx", msg));
+                assert!(ekey.sign_ph(b"", msg) == skey.sign_ph(b"", msg));
+            }
+        }
+    }
+
+    #[test]
+    fn sign_session() {
+        // With the nonce material derived as in RFC 8032, the two-step
+        // process yields the deterministic signatures.
+        for tv in TEST_VECTORS.iter() {
+            let seed = hex::decode(tv.s).unwrap();
+            let msg = hex::decode(tv.m).unwrap();
+            let ctx = hex::decode(tv.ctx).unwrap();
+            let mut sig = [0u8; 64];
+            hex::decode_to_slice(tv.sig, &mut sig[..]).unwrap();
+
+            let skey = PrivateKey::from_seed(&seed[..]);
+            let hm = Sha512::digest(&msg[..]);
+            let pm = if tv.ph { &hm[..] } else { &msg[..] };
+            let mut sh = Sha512::new();
+            if tv.dom {
+                sh.update(&HASH_HEAD);
+                sh.update(&[tv.ph as u8, ctx.len() as u8]);
+                sh.update(&ctx[..]);
+            }
+            sh.update(&skey.expand().h);
+            sh.update(pm);
+            let nonce_material = sh.finalize();
+
+            let (ss, R_enc) = SignSession::start(&skey, &nonce_material);
+            assert!(R_enc[..] == sig[..32]);
+            assert!(ss.nonce_point() == R_enc);
+            let sig2 = if tv.dom {
+                if tv.ph {
+                    ss.finish_ph(&ctx[..], pm)
+                } else {
+                    ss.finish_ctx(&ctx[..], pm)
+                }
+            } else {
+                ss.finish(pm)
+            };
+            assert!(sig2 == sig);
+        }
+
+        // Random nonce material.
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let seed = sh.finalize_reset();
+            let skey = PrivateKey::from_seed(&seed[..]);
+            let nonce_material = Sha512::digest(&seed[..]);
+            let (ss, R_enc) = SignSession::start(&skey, &nonce_material);
+            let msg = [i as u8; 5];
+            let sig = ss.finish(&msg);
+            assert!(sig[..32] == R_enc[..]);
+            assert!(skey.public_key.verify_raw(&sig, &msg));
+            assert!(sig != skey.sign_raw(&msg));
+        }
+    }
+
+    #[test]
+    fn signatures_ctx() {
+        // Ed25519ctx test vectors from RFC 8032, section 7.2:
+        // (seed, public key, message, context, signature)
+        const KAT: [(&str, &str, &str, &str, &str); 4] = [
+            ("0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+             "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+             "f726936d19c800494e3fdaff20b276a8",
+             "666f6f",
+             "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d"),
+            ("0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+             "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+             "f726936d19c800494e3fdaff20b276a8",
+             "626172",
+             "fc60d5872fc46b3aa69f8b5b4351d5808f92bcc044606db097abab6dbcb1aee3216c48e8b3b66431b5b186d1d28f8ee15a5ca2df6668346291c2043d4eb3e90d"),
+            ("0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+             "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+             "508e9e6882b979fea900f62adceaca35",
+             "666f6f",
+             "8b70c1cc8310e1de20ac53ce28ae6e7207f33c3295e03bb5c0732a1d20dc64908922a8b052cf99b7c4fe107a5abb5b2c4085ae75890d02df26269d8945f84b0b"),
+            ("ab9c2853ce297ddab85c993b3ae14bcad39b2c682beabc27d6d4eb20711d6560",
+             "0f1d1274943b91415889152e893d80e93275a1fc0b65fd71b4b0dda10ad7d772",
+             "f726936d19c800494e3fdaff20b276a8",
+             "666f6f",
+             "21655b5f1aa965996b3f97b3c849eafba922a0a62992f73b3d1b73106a84ad85e9b86a7b6005ea868337ff2d20a7f5fbd4cd10b0be49a68da2b2e0dc0ad8960f"),
+        ];
+        for (s, Q, m, c, sig) in KAT.iter() {
+            let skey = PrivateKey::from_seed(&hex::decode(s).unwrap());
+            let pkey = skey.public_key;
+            assert!(pkey.encode()[..] == hex::decode(Q).unwrap()[..]);
+            let msg = hex::decode(m).unwrap();
+            let ctx = hex::decode(c).unwrap();
+            let sig = hex::decode(sig).unwrap();
+            assert!(skey.sign_ctx(&ctx, &msg)[..] == sig[..]);
+            assert!(pkey.verify_ctx(&sig, &ctx, &msg));
+
+            // A ctx signature never verifies as a plain Ed25519 or
+            // Ed25519ph signature, and vice versa.
+            assert!(!pkey.verify_raw(&sig, &msg));
+            assert!(!pkey.verify_ph(&sig, &ctx, &msg));
+            let sig2 = skey.sign_raw(&msg);
+            assert!(pkey.verify_raw(&sig2, &msg));
+            assert!(!pkey.verify_ctx(&sig2, &ctx, &msg));
+        }
+
+        // Empty and overlong contexts are rejected.
+        let skey = PrivateKey::from_seed(&[0x42u8; 32]);
+        let pkey = skey.public_key;
+        let sig = skey.sign_ctx(&[0u8; 255], b"msg");
+        assert!(pkey.verify_ctx(&sig, &[0u8; 255], b"msg"));
+        assert!(!pkey.verify_ctx(&sig, &[0u8; 256], b"msg"));
+        let sig = skey.sign_raw(b"msg");
+        assert!(!pkey.verify_ctx(&sig, b"", b"msg"));
+        assert!(std::panic::catch_unwind(|| skey.sign_ctx(b"", b"msg")).is_err());
+        assert!(std::panic::catch_unwind(|| skey.sign_ctx(&[0u8; 256], b"msg")).is_err());
+    }
+
+    #[test]
+    fn signatures_frost() {
+        // Test vector from draft-irtf-cfrg-frost-05, section C.1
+        let d_enc = hex::decode("7b1c33d3f5291d85de664833beb1ad469f7fb6025a0ec78b3a790c6e13a98304").unwrap();
+        let (d, _) = Scalar::decode32(&d_enc[..]);
+        let Q_enc = hex::decode("15d21ccd7ee42959562fc8aa63224c8851fb3ec85a3faf66040d380fb9738673").unwrap();
+        let Q = Point::decode(&Q_enc[..]).unwrap();
+        assert!(Point::mulgen(&d).equals(Q) == 0xFFFFFFFF);
+        let pkey = PublicKey::from_point(&Q);
+        let msg = hex::decode("74657374").unwrap();
+        let sig = hex::decode("2b8d9c6995333c5990e3a3dd6568785539d3322f7f0376452487ea35cfda587b75650edb12b1a8619c88ed1f8463d6baeefb18d3fed3c279102fdfecb255fa0e").unwrap();
+        assert!(pkey.verify_raw(&sig[..], &msg[..]));
+    }
+
+    #[test]
+    fn signatures_trunc() {
+        let skey = PrivateKey::from_seed(&[0u8; 32]);
+        let pkey = skey.public_key;
+        for i in 0..10 {
+            let mut msg = [0u8; 8];
+            msg[..].copy_from_slice(&(i as u64).to_le_bytes());
+            let mut sig = skey.sign_raw(&msg);
+            sig[63] = 0;
+            for rm in 8..33 {
+                let n = 512 - rm;
+                sig[n >> 3] &= !(0x01u8 << (n & 7));
+                let vv = pkey.verify_trunc_raw(&sig, rm, &msg);
+                assert!(vv.is_some());
+                let sig2 = vv.unwrap();
+                assert!(pkey.verify_raw(&sig2, &msg));
+                msg[0] ^= 1;
+                assert!(pkey.verify_trunc_raw(&sig, rm, &msg).is_none());
+                msg[0] ^= 1;
+            }
+        }
+    }
+
+    #[test]
+    fn batch() {
+        // 64 key pairs, messages of various lengths.
+        let mut pkeys = Vec::new();
+        let mut msgs = Vec::new();
+        let mut sigs = Vec::new();
+        for i in 0..64 {
+            let skey = PrivateKey::from_seed(&Sha256::digest(&[i as u8]));
+            let msg: Vec<u8> = (0..(i * 3)).map(|j| (i + j) as u8).collect();
+            sigs.push(skey.sign_raw(&msg));
+            pkeys.push(skey.public_key);
+            msgs.push(msg);
+        }
+        let seed = [0x5Au8; 32];
+
+        // All-valid batches of sizes 1 to 64.
+        for n in 0..65 {
+            let entries: Vec<(PublicKey, &[u8], &[u8])> = (0..n)
+                .map(|i| (pkeys[i], &msgs[i][..], &sigs[i][..])).collect();
+            assert!(verify_batch(&entries, &seed));
+        }
+
+        // Batches with exactly one invalid signature.
+        for n in [1, 2, 7, 32] {
+            for j in 0..n {
+                for k in 0..3 {
+                    let mut bad_sig = sigs[j];
+                    let mut bad_msg = msgs[j].clone();
+                    match k {
+                        0 => { bad_sig[40] ^= 0x01; }
+                        1 => { bad_msg.push(0x00); }
+                        _ => { bad_sig[0] ^= 0x04; }
+                    }
+                    let mut entries: Vec<(PublicKey, &[u8], &[u8])> = (0..n)
+                        .map(|i| (pkeys[i], &msgs[i][..], &sigs[i][..]))
+                        .collect();
+                    entries[j] = (pkeys[j], &bad_msg[..], &bad_sig[..]);
+                    assert!(!pkeys[j].verify_raw(&bad_sig, &bad_msg));
+                    assert!(!verify_batch(&entries, &seed));
+                    assert!(verify_batch_find_invalid(&entries, &seed) == [j]);
+                }
+            }
+        }
+
+        // Several invalid signatures (including a wrong length).
+        let mut entries: Vec<(PublicKey, &[u8], &[u8])> = (0..20)
+            .map(|i| (pkeys[i], &msgs[i][..], &sigs[i][..])).collect();
+        entries[3].1 = &msgs[4][..];
+        entries[11].2 = &sigs[11][..63];
+        entries[12].0 = pkeys[13];
+        assert!(!verify_batch(&entries, &seed));
+        assert!(verify_batch_find_invalid(&entries, &seed) == [3, 11, 12]);
+    }
+
+    #[test]
+    fn same_message() {
+        let msg = b"block 1234: vote for 5678";
+        let mut pkeys = Vec::new();
+        let mut sigs = Vec::new();
+        for i in 0..40 {
+            let skey = PrivateKey::from_seed(&Sha256::digest(&[i as u8]));
+            sigs.push(skey.sign_raw(msg));
+            pkeys.push(skey.public_key);
+        }
+        let seed = [0xA5u8; 32];
+
+        // Empty batch.
+        assert!(verify_same_message(msg, &[], &seed));
+        assert!(verify_same_message_find_invalid(msg, &[], &seed).is_empty());
+
+        // All-valid batches; agreement with verify_batch().
+        for n in [1, 2, 5, 40] {
+            let entries: Vec<(PublicKey, &[u8])> = (0..n)
+                .map(|i| (pkeys[i], &sigs[i][..])).collect();
+            let full: Vec<(PublicKey, &[u8], &[u8])> = (0..n)
+                .map(|i| (pkeys[i], &msg[..], &sigs[i][..])).collect();
+            assert!(verify_same_message(msg, &entries, &seed));
+            assert!(verify_batch(&full, &seed));
+            assert!(!verify_same_message(b"other", &entries, &seed));
+            assert!(verify_same_message_find_invalid(b"other", &entries,
+                &seed) == (0..n).collect::<Vec<usize>>());
+        }
+
+        // A single corrupted entry is detected and located.
+        for j in 0..40 {
+            let mut bad_sig = sigs[j];
+            bad_sig[33 + (j % 31)] ^= 0x01;
+            let mut entries: Vec<(PublicKey, &[u8])> = (0..40)
+                .map(|i| (pkeys[i], &sigs[i][..])).collect();
+            entries[j].1 = &bad_sig[..];
+            assert!(!pkeys[j].verify_raw(&bad_sig, msg));
+            assert!(!verify_same_message(msg, &entries, &seed));
+            assert!(verify_same_message_find_invalid(msg, &entries, &seed)
+                == [j]);
+        }
+
+        // Several invalid entries: wrong key, wrong length.
+        let mut entries: Vec<(PublicKey, &[u8])> = (0..20)
+            .map(|i| (pkeys[i], &sigs[i][..])).collect();
+        entries[2].0 = pkeys[3];
+        entries[17].1 = &sigs[17][..63];
+        assert!(!verify_same_message(msg, &entries, &seed));
+        assert!(verify_same_message_find_invalid(msg, &entries, &seed)
+            == [2, 17]);
+    }
+
+    #[test]
+    fn batch_edge_cases() {
+        // Signatures built with low-order components added to R and/or
+        // A; individual (cofactored) verification accepts them, as long
+        // as the prime-order part is correct. Batch verification must
+        // agree in all cases.
+        let T8_enc: [u8; 32] = [
+            0x26, 0xE8, 0x95, 0x8F, 0xC2, 0xB2, 0x27, 0xB0,
+            0x45, 0xC3, 0xF4, 0x89, 0xF2, 0xEF, 0x98, 0xF0,
+            0xD5, 0xDF, 0xAC, 0x05, 0xD3, 0xC6, 0x33, 0x39,
+            0xB1, 0x38, 0x02, 0x88, 0x6D, 0x53, 0xFC, 0x85,
+        ];
+        let T8 = Point::decode(&T8_enc).unwrap();
+        let msg = b"edge case";
+        let seed = [0xA5u8; 32];
+
+        let mut edge = Vec::new();
+        let mut sh = Sha256::new();
+        for i in 0..24 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let a = if (i & 1) == 0 { Scalar::decode_reduce(&v[..16]) } else { Scalar::ZERO };
+            let r = if (i & 2) == 0 { Scalar::decode_reduce(&v[16..]) } else { Scalar::ZERO };
+            let A = Point::mulgen(&a) + T8 * ((i >> 2) as u64);
+            let R = Point::mulgen(&r) + T8 * (((i >> 2) * 3 + 1) as u64);
+            let pkey = PublicKey::from_point(&A);
+            let R_enc = R.encode();
+            let mut sh2 = Sha512::new();
+            sh2.update(&R_enc);
+            sh2.update(&pkey.encoded);
+            sh2.update(&msg[..]);
+            let k = Scalar::decode_reduce(&sh2.finalize());
+            let mut S = r + k * a;
+            if (i % 3) == 2 {
+                S += Scalar::ONE;
+            }
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(&R_enc);
+            sig[32..].copy_from_slice(&S.encode());
+            let ok = pkey.verify_raw(&sig, &msg[..]);
+            assert!(ok == ((i % 3) != 2));
+            assert!(pkey.verify_ct(&sig, &msg[..]) == ok);
+            assert!(verify_batch(&[(pkey, &msg[..], &sig[..])], &seed) == ok);
+            edge.push((pkey, sig, ok));
+        }
+
+        // Mixed batch.
+        let entries: Vec<(PublicKey, &[u8], &[u8])> = edge.iter()
+            .map(|(pk, sig, _)| (*pk, &msg[..], &sig[..])).collect();
+        let bad: Vec<usize> = (0..edge.len()).filter(|&i| !edge[i].2).collect();
+        assert!(!verify_batch(&entries, &seed));
+        assert!(verify_batch_find_invalid(&entries, &seed) == bad);
+        let good: Vec<(PublicKey, &[u8], &[u8])> = edge.iter()
+            .filter(|e| e.2)
+            .map(|(pk, sig, _)| (*pk, &msg[..], &sig[..])).collect();
+        assert!(verify_batch(&good, &seed));
+    }
+
+    #[test]
+    fn zip215() {
+        // ZIP-215 test set: all 14 encodings (canonical or not) of
+        // low-order points, used for both A and R, with S = 0 and
+        // message "Zcash". All 196 combinations must be accepted by
+        // verify_zip215(); the strict verification rejects all cases
+        // with a non-canonical encoding.
+        let mut enc: Vec<[u8; 32]> = Vec::new();
+        let y8 = hex::decode("26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05").unwrap();
+        let y8n = hex::decode("c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a").unwrap();
+        let mut base: Vec<[u8; 32]> = Vec::new();
+        for i in 0..4 {
+            // y = 0, 1, p, p+1 (the last two are non-canonical)
+            let mut e = [0u8; 32];
+            if i < 2 {
+                e[0] = i as u8;
+            } else {
+                e = [0xFF; 32];
+                e[0] = 0xEB + i as u8;
+                e[31] = 0x7F;
+            }
+            base.push(e);
+        }
+        let mut e = [0xFFu8; 32];
+        e[0] = 0xEC;
+        e[31] = 0x7F;
+        base.push(e);
+        let mut e = [0u8; 32];
+        e[..].copy_from_slice(&y8);
+        base.push(e);
+        let mut e = [0u8; 32];
+        e[..].copy_from_slice(&y8n);
+        base.push(e);
+        for e in base.iter() {
+            enc.push(*e);
+            let mut e2 = *e;
+            e2[31] |= 0x80;
+            enc.push(e2);
+        }
+        assert!(enc.len() == 14);
+
+        let mut num_strict = 0;
+        for A_enc in enc.iter() {
+            let pkey = PublicKey::decode_zip215(A_enc).unwrap();
+            assert!(pkey.encoded == *A_enc);
+            assert!(pkey.point.has_low_order() == 0xFFFFFFFF);
+            let spkey = PublicKey::decode(A_enc);
+            for R_enc in enc.iter() {
+                let mut sig = [0u8; 64];
+                sig[..32].copy_from_slice(R_enc);
+                assert!(pkey.verify_zip215(&sig, b"Zcash"));
+                let strict = match spkey {
+                    Some(spk) => {
+                        assert!(spk.verify_ct(&sig, b"Zcash")
+                            == spk.verify_raw(&sig, b"Zcash"));
+                        spk.verify_raw(&sig, b"Zcash")
+                    }
+                    None => false,
+                };
+                if Point::decode(R_enc).is_some() && spkey.is_some() {
+                    assert!(strict);
+                    num_strict += 1;
+                } else {
+                    assert!(!strict);
+                }
+            }
+        }
+        assert!(num_strict == 64);
+
+        // Non-canonical y with the sign bit set, on a non-low-order
+        // point: y + p fits on 255 bits only if y < 19.
+        for y in 0..19u8 {
+            for sign in [0x00u8, 0x80u8] {
+                let mut e = [0u8; 32];
+                e[0] = y;
+                e[31] = sign;
+                let mut e2 = [0xFFu8; 32];
+                e2[0] = 0xED + y;
+                e2[31] = 0x7F | sign;
+                let P1 = Point::decode_zip215(&e);
+                let P2 = Point::decode_zip215(&e2);
+                assert!(P1.is_some() == P2.is_some());
+                if let (Some(P1), Some(P2)) = (P1, P2) {
+                    assert!(P1.equals(P2) == 0xFFFFFFFF);
+                    assert!(Point::decode(&e2).is_none());
+                    if y > 1 {
+                        // x != 0, hence the canonical decoder must
+                        // accept the reduced value.
+                        assert!(Point::decode(&e).is_some());
+                    }
+                }
+            }
+        }
+
+        // Public key: the neutral point, encoded with the sign bit set
+        // (non-canonical).
+        let mut A_enc = [0u8; 32];
+        A_enc[0] = 0x01;
+        A_enc[31] = 0x80;
+        let pkey = PublicKey::decode_zip215(&A_enc).unwrap();
+        let T8 = Point::decode(&enc[10]).unwrap();
+
+        // S = L-1 (highest canonical value) is accepted; S = L is not.
+        let S = -Scalar::ONE;
+        let R = Point::mulgen(&S) + T8;
+        let mut sig = [0u8; 64];
+        sig[..32].copy_from_slice(&R.encode());
+        sig[32..].copy_from_slice(&S.encode());
+        assert!(pkey.verify_zip215(&sig, b"Zcash"));
+        let mut sig2 = sig;
+        sig2[32] = sig[32].wrapping_add(1);
+        assert!(sig2[32] == 0xED);
+        assert!(!pkey.verify_zip215(&sig2, b"Zcash"));
+        sig2[32..].copy_from_slice(&Scalar::ZERO.encode());
+        assert!(!pkey.verify_zip215(&sig2, b"Zcash"));
+
+        // Truncated signatures are rejected.
+        assert!(!pkey.verify_zip215(&sig[..63], b"Zcash"));
+
+        // The regular verification functions are not modified.
+        let skey = PrivateKey::from_seed(&[0x42u8; 32]);
+        let sig = skey.sign_raw(b"Zcash");
+        assert!(skey.public_key.verify_raw(&sig, b"Zcash"));
+        assert!(skey.public_key.verify_zip215(&sig, b"Zcash"));
+        assert!(!skey.public_key.verify_zip215(&sig, b"Zcash!"));
+    }
+
+    #[test]
+    fn strict() {
+        // Edge cases from "Taming the many EdDSAs" (table 5 of the
+        // paper), rebuilt here with the same structure: vectors 0 to 5
+        // are accepted by the default verification, vectors 6 to 11 are
+        // rejected; the strict mode rejects all of them.
+        let T8_enc: [u8; 32] = [
+            0x26, 0xE8, 0x95, 0x8F, 0xC2, 0xB2, 0x27, 0xB0,
+            0x45, 0xC3, 0xF4, 0x89, 0xF2, 0xEF, 0x98, 0xF0,
+            0xD5, 0xDF, 0xAC, 0x05, 0xD3, 0xC6, 0x33, 0x39,
+            0xB1, 0x38, 0x02, 0x88, 0x6D, 0x53, 0xFC, 0x85,
+        ];
+        let T8 = Point::decode(&T8_enc).unwrap();
+        let L_enc = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010").unwrap();
+        let a = Scalar::decode_reduce(&Sha512::digest(b"strict a"));
+        let r = Scalar::decode_reduce(&Sha512::digest(b"strict r"));
+        let A_prime = Point::mulgen(&a);
+        let A_mixed = A_prime + T8;
+
+        // Compute the challenge for given encodings of R and A.
+        let challenge = |R_enc: &[u8], A_enc: &[u8], m: &[u8]| {
+            let mut sh = Sha512::new();
+            sh.update(R_enc);
+            sh.update(A_enc);
+            sh.update(m);
+            Scalar::decode_reduce(&sh.finalize())
+        };
+        let make_sig = |R_enc: &[u8], S: &Scalar| {
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(R_enc);
+            sig[32..].copy_from_slice(&S.encode());
+            sig
+        };
+
+        // Each case: (encoded public key, message, signature)
+        let mut cases: Vec<([u8; 32], Vec<u8>, [u8; 64])> = Vec::new();
+
+        // 0: S = 0, small-order A and R.
+        let R_enc = (T8 * 3).encode();
+        cases.push((T8_enc, b"case 0".to_vec(),
+            make_sig(&R_enc, &Scalar::ZERO)));
+
+        // 1: 0 < S < L, small-order A, mixed-order R.
+        let R_enc = (Point::mulgen(&r) + T8).encode();
+        cases.push((T8_enc, b"case 1".to_vec(), make_sig(&R_enc, &r)));
+
+        // 2: 0 < S < L, mixed-order A, small-order R.
+        let A_enc = A_mixed.encode();
+        let R_enc = (T8 * 5).encode();
+        let k = challenge(&R_enc, &A_enc, b"case 2");
+        cases.push((A_enc, b"case 2".to_vec(), make_sig(&R_enc, &(k * a))));
+
+        // 3 and 4: mixed-order A and R; the torsion components cancel
+        // out (case 3, cofactorless equation passes) or not (case 4,
+        // only the cofactored equation passes). R = r*B + T8 and the
+        // message is chosen so that k = -1 mod 8 (or not).
+        let R_enc = (Point::mulgen(&r) + T8).encode();
+        for (cancel, name) in [(true, "case 3"), (false, "case 4")] {
+            for j in 0u32.. {
+                let mut m = name.as_bytes().to_vec();
+                m.extend_from_slice(&j.to_le_bytes());
+                let k = challenge(&R_enc, &A_enc, &m);
+                if ((T8 * k + T8).isneutral() != 0) == cancel {
+                    cases.push((A_enc, m, make_sig(&R_enc, &(r + k * a))));
+                    break;
+                }
+            }
+        }
+
+        // 5: mixed-order A, prime-order R, cofactorless equation fails.
+        let R_enc = Point::mulgen(&r).encode();
+        for j in 0u32.. {
+            let mut m = b"case 5".to_vec();
+            m.extend_from_slice(&j.to_le_bytes());
+            let k = challenge(&R_enc, &A_enc, &m);
+            if (T8 * k).isneutral() == 0 {
+                cases.push((A_enc, m, make_sig(&R_enc, &(r + k * a))));
+                break;
+            }
+        }
+
+        // 6: S > L (valid signature, with L added to S).
+        let A_enc = A_prime.encode();
+        let k = challenge(&R_enc, &A_enc, b"case 6");
+        let mut sig = make_sig(&R_enc, &(r + k * a));
+        let mut cc = 0u32;
+        for i in 0..32 {
+            let z = (sig[32 + i] as u32) + (L_enc[i] as u32) + cc;
+            sig[32 + i] = z as u8;
+            cc = z >> 8;
+        }
+        cases.push((A_enc, b"case 6".to_vec(), sig));
+
+        // 7: S >> L (top bits of S set).
+        let k = challenge(&R_enc, &A_enc, b"case 7");
+        let mut sig = make_sig(&R_enc, &(r + k * a));
+        sig[63] |= 0xE0;
+        cases.push((A_enc, b"case 7".to_vec(), sig));
+
+        // 8 and 9: non-canonical (small-order) R.
+        let mut R_enc = [0u8; 32];
+        R_enc[0] = 0x01;
+        R_enc[31] = 0x80;
+        cases.push((T8_enc, b"case 8".to_vec(),
+            make_sig(&R_enc, &Scalar::ZERO)));
+        let mut R_enc = [0xFFu8; 32];
+        R_enc[0] = 0xEE;
+        R_enc[31] = 0x7F;
+        cases.push((T8_enc, b"case 9".to_vec(),
+            make_sig(&R_enc, &Scalar::ZERO)));
+
+        // 10 and 11: non-canonical (small-order) A.
+        let R_enc = (T8 * 3).encode();
+        let mut A_enc = [0u8; 32];
+        A_enc[0] = 0x01;
+        A_enc[31] = 0x80;
+        cases.push((A_enc, b"case 10".to_vec(),
+            make_sig(&R_enc, &Scalar::ZERO)));
+        let mut A_enc = [0xFFu8; 32];
+        A_enc[0] = 0xED;
+        A_enc[31] = 0xFF;
+        cases.push((A_enc, b"case 11".to_vec(),
+            make_sig(&R_enc, &Scalar::ZERO)));
+
+        assert!(cases.len() == 12);
+        for (i, (A_enc, m, sig)) in cases.iter().enumerate() {
+            let default = match PublicKey::decode(A_enc) {
+                Some(pkey) => {
+                    assert!(pkey.verify_ct(sig, m) == pkey.verify_raw(sig, m));
+                    pkey.verify_raw(sig, m)
+                }
+                None => false,
+            };
+            assert!(default == (i <= 5));
+            if let Some(pkey) = PublicKey::decode_zip215(A_enc) {
+                assert!(!pkey.verify_strict(sig, m));
+            }
+        }
+
+        // Regular signatures are accepted by the strict mode.
+        for i in 0..10u8 {
+            let skey = PrivateKey::from_seed(&[i; 32]);
+            let sig = skey.sign_raw(&[i]);
+            assert!(skey.public_key.verify_strict(&sig, &[i]));
+            assert!(!skey.public_key.verify_strict(&sig, &[i + 1]));
+            assert!(!skey.public_key.verify_strict(&sig[..63], &[i]));
+        }
+    }
+
+    #[test]
+    fn scalar_wide() {
+        // 2^512 - 1
+        let r = Scalar::from_bytes_mod_order_wide(&[0xFFu8; 64]);
+        assert!(r.encode()[..] == hex::decode("000f9c44e31106a447938568a71b0ed065bef517d273ecce3d9a307c1b419903").unwrap()[..]);
+
+        // L*2^k and L*2^k - 1, for all k such that the value fits.
+        let L_enc = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010").unwrap();
+        for k in 0..260 {
+            let mut b = [0u8; 64];
+            for i in 0..253 {
+                let bit = (L_enc[i >> 3] >> (i & 7)) & 1;
+                b[(i + k) >> 3] |= bit << ((i + k) & 7);
+            }
+            assert!(Scalar::from_bytes_mod_order_wide(&b).iszero() == 0xFFFFFFFF);
+            for x in b.iter_mut() {
+                let z = *x;
+                *x = z.wrapping_sub(1);
+                if z != 0 {
+                    break;
+                }
+            }
+            assert!(Scalar::from_bytes_mod_order_wide(&b).equals(-Scalar::ONE)
+                == 0xFFFFFFFF);
+        }
+
+        // Random inputs (reference values computed with big integers).
+        const KAT: [(&str, &str); 3] = [
+            ("d7b1fcf3c913c42ba1960a5b68404ecfc023331e11fdff14bd24a69611b3307e1e124ee07d8cef74354178d750e7edc5c489dac622a5f0b91050a175edf4c491",
+             "2c7797b0790dec940370971ff4c924ad173b564e70e6fb606a06344c2d527b06"),
+            ("38b252cb8abc4984b30b2edad8a4d1623f852a6151528f7ceee34d37e5381ada22079e18e5070c8f2b0b2577963fcb6938c390e0d162d4f4ae822ca37e89840a",
+             "cb95475190056b922138b88dbbfc9c96e0f42cb0cae35d121c30079668075706"),
+            ("96a1f7ed694a2673b97e64459c9c17ace60f26d8bb2df85cc34294d39c31a0eeb86833fc7db7ee36f367cd5c48662f9597a32aa35179b01623365644e5b01b6f",
+             "131ed714a966b1d583b16f47c53dbef71ad4e24b0c578a3a502eb75e6373080b"),
+        ];
+        for (bh, rh) in KAT.iter() {
+            let mut b = [0u8; 64];
+            hex::decode_to_slice(bh, &mut b[..]).unwrap();
+            let r = Scalar::from_bytes_mod_order_wide(&b);
+            assert!(r.encode()[..] == hex::decode(rh).unwrap()[..]);
+        }
+
+        let r = Scalar::hash_from_bytes(b"dst", b"data");
+        assert!(r.encode()[..] == hex::decode("da92ab5d2d1812e6d5713dda6f1b21bcadd393685c1e8e60172da522c918db04").unwrap()[..]);
+    }
+
+    #[test]
+    fn scalar_batch_invert() {
+        let mut sh = Sha256::new();
+        for n in [0usize, 1, 2, 5, 199, 200, 201, 450].iter() {
+            let n = *n;
+            let mut xx = Vec::with_capacity(n);
+            for i in 0..n {
+                sh.update(&(i as u64).to_le_bytes());
+                sh.update(&(n as u64).to_le_bytes());
+                xx.push(Scalar::decode_reduce(&sh.finalize_reset()));
+            }
+            for zmask in 0..3 {
+                // zmask = 1: first element is zero; zmask = 2: every
+                // seventh element is zero.
+                let mut yy = xx.clone();
+                if n > 0 && zmask == 1 {
+                    yy[0] = Scalar::ZERO;
+                }
+                if zmask == 2 {
+                    for i in (0..n).step_by(7) {
+                        yy[i] = Scalar::ZERO;
+                    }
+                }
+                let has_zero = yy.iter().any(|x| x.iszero() != 0);
+                let expected_r = if has_zero { 0 } else { 0xFFFFFFFF };
+
+                let mut y1 = yy.clone();
+                assert!(Scalar::batch_invert(&mut y1[..]) == expected_r);
+                let mut y2 = yy.clone();
+                let mut tmp = vec![Scalar::ZERO; n];
+                assert!(Scalar::batch_invert_scratch(&mut y2[..], &mut tmp)
+                    == expected_r);
+                for i in 0..n {
+                    if yy[i].iszero() != 0 {
+                        assert!(y1[i].iszero() == 0xFFFFFFFF);
+                        assert!(y2[i].iszero() == 0xFFFFFFFF);
+                    } else {
+                        let iy = Scalar::ONE / yy[i];
+                        assert!(y1[i].equals(iy) == 0xFFFFFFFF);
+                        assert!(y2[i].equals(iy) == 0xFFFFFFFF);
+                        assert!((y2[i] * yy[i]).equals(Scalar::ONE)
+                            == 0xFFFFFFFF);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn precomputed_table() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<PrecomputedTable>();
+
+        let mut sh = Sha256::new();
+        let mut points = Vec::new();
+        let mut scalars = Vec::new();
+        for i in 0..17 {
+            sh.update(&(2 * i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            points.push(Point::mulgen(&Scalar::decode_reduce(&v)));
+            sh.update(&(2 * i as u64 + 1).to_le_bytes());
+            let v = sh.finalize_reset();
+            scalars.push(Scalar::decode_reduce(&v));
+        }
+        // Some special cases: neutral point, zero and -1 scalars,
+        // point outside of the prime-order subgroup.
+        points[3] = Point::NEUTRAL;
+        scalars[5] = Scalar::ZERO;
+        scalars[6] = -Scalar::ONE;
+        points[7] += Point::ORDER2;
+
+        for n in [0, 1, 2, 5, 17] {
+            let mut R = Point::NEUTRAL;
+            for i in 0..n {
+                R += points[i] * scalars[i];
+            }
+            for w in 2..9 {
+                let table = PrecomputedTable::new(&points[..n], w);
+                assert!(table.len() == n);
+                assert!(table.mul_vartime(&scalars[..n]).equals(R)
+                    == 0xFFFFFFFF);
+            }
+        }
+
+        let t1 = PrecomputedTable::new(&points, 3);
+        let t2 = PrecomputedTable::new(&points, 6);
+        for i in 0..10 {
+            for s in scalars.iter_mut() {
+                *s = s.square() + Scalar::from_u32(i);
+            }
+            assert!(t1.mul_vartime(&scalars).equals(t2.mul_vartime(&scalars))
+                == 0xFFFFFFFF);
+        }
+    }
+
+    #[test]
+    fn public_key_precomp() {
         for tv in TEST_VECTORS.iter() {
-            let seed = hex::decode(tv.s).unwrap();
             let Q_enc = hex::decode(tv.Q).unwrap();
             let msg = hex::decode(tv.m).unwrap();
             let ctx = hex::decode(tv.ctx).unwrap();
             let mut sig = [0u8; 64];
             hex::decode_to_slice(tv.sig, &mut sig[..]).unwrap();
 
-            let skey = PrivateKey::from_seed(&seed[..]);
-            assert!(&Q_enc[..] == skey.public_key.encode());
-            if tv.dom {
-                if tv.ph {
-                    let mut sh = Sha512::new();
-                    sh.update(&msg[..]);
-                    let hm = sh.finalize();
-                    assert!(skey.sign_ph(&ctx[..], &hm) == sig);
+            // The source key is dropped; the precomputed key owns its
+            // data.
+            let pp = {
+                let pkey = PublicKey::decode(&Q_enc[..]).unwrap();
+                PublicKeyPrecomp::new(&pkey)
+            };
+            assert!(pp.public_key.encoded[..] == Q_enc[..]);
+            let check = |sig: &[u8]| {
+                if tv.dom {
+                    if tv.ph {
+                        let hm = Sha512::digest(&msg[..]);
+                        pp.verify_ph(sig, &ctx[..], &hm)
+                    } else {
+                        pp.verify_ctx(sig, &ctx[..], &msg[..])
+                    }
                 } else {
-                    assert!(skey.sign_ctx(&ctx[..], &msg[..]) == sig);
+                    pp.verify_raw(sig, &msg[..])
                 }
-            } else {
-                assert!(skey.sign_raw(&msg[..]) == sig);
+            };
+            assert!(check(&sig));
+            for i in 0..64 {
+                let mut sig2 = sig;
+                sig2[i] ^= 0x04;
+                assert!(!check(&sig2));
             }
+            assert!(!check(&sig[..63]));
+        }
 
-            let pkey = PublicKey::decode(&Q_enc[..]).unwrap();
-            if tv.dom {
-                if tv.ph {
-                    let mut sh = Sha512::new();
-                    sh.update(&msg[..]);
-                    let mut hm = sh.finalize();
-                    assert!(pkey.verify_ph(&sig, &ctx[..], &hm));
-                    assert!(!pkey.verify_ph(&sig, &[1u8], &hm));
-                    hm[42] ^= 0x08;
-                    assert!(!pkey.verify_ph(&sig, &ctx[..], &hm));
-                } else {
-                    assert!(pkey.verify_ctx(&sig, &ctx[..], &msg[..]));
-                    assert!(!pkey.verify_ctx(&sig, &[1u8], &msg[..]));
-                    assert!(!pkey.verify_ctx(&sig, &ctx[..], &[0u8]));
-                }
-            } else {
-                assert!(pkey.verify_raw(&sig, &msg[..]));
-                assert!(!pkey.verify_raw(&sig, &[0u8]));
+        // Random keys and messages; altered signatures and messages.
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let seed = sh.finalize_reset();
+            let skey = PrivateKey::from_seed(&seed[..]);
+            let pkey = skey.public_key;
+            let pp = PublicKeyPrecomp::new(&pkey);
+            for j in 0..10 {
+                let msg = [i as u8, j as u8, 0x5A];
+                let mut sig = skey.sign_raw(&msg);
+                assert!(pp.verify_raw(&sig, &msg));
+                assert!(pkey.verify_raw(&sig, &msg));
+                assert!(!pp.verify_raw(&sig, &msg[..2]));
+                sig[j] ^= 0x01;
+                assert!(pp.verify_raw(&sig, &msg)
+                    == pkey.verify_raw(&sig, &msg));
+                sig[j] ^= 0x01;
+                sig[32 + j] ^= 0x80;
+                assert!(pp.verify_raw(&sig, &msg)
+                    == pkey.verify_raw(&sig, &msg));
             }
         }
+
+        // Mixed-order public key and R: the cofactored equation accepts
+        // such signatures, the precomputed tables must as well.
+        let T8_enc: [u8; 32] = [
+            0x26, 0xE8, 0x95, 0x8F, 0xC2, 0xB2, 0x27, 0xB0,
+            0x45, 0xC3, 0xF4, 0x89, 0xF2, 0xEF, 0x98, 0xF0,
+            0xD5, 0xDF, 0xAC, 0x05, 0xD3, 0xC6, 0x33, 0x39,
+            0xB1, 0x38, 0x02, 0x88, 0x6D, 0x53, 0xFC, 0x85,
+        ];
+        let T8 = Point::decode(&T8_enc).unwrap();
+        let a = Scalar::decode_reduce(&Sha512::digest(b"precomp a"));
+        let pkey = PublicKey::from_point(&(Point::mulgen(&a) + T8));
+        let pp = PublicKeyPrecomp::new(&pkey);
+        for j in 0..8u64 {
+            let msg = [j as u8];
+            let r = Scalar::decode_reduce(&Sha512::digest(&msg));
+            let R_enc = (Point::mulgen(&r) + T8 * j).encode();
+            let mut sh = Sha512::new();
+            sh.update(&R_enc);
+            sh.update(&pkey.encoded);
+            sh.update(&msg);
+            let k = Scalar::decode_reduce(&sh.finalize());
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(&R_enc);
+            sig[32..].copy_from_slice(&(r + k * a).encode());
+            assert!(pkey.verify_raw(&sig, &msg));
+            assert!(pp.verify_raw(&sig, &msg));
+            sig[32] ^= 0x01;
+            assert!(!pkey.verify_raw(&sig, &msg));
+            assert!(!pp.verify_raw(&sig, &msg));
+        }
     }
 
     #[test]
-    fn signatures_frost() {
-        // Test vector from draft-irtf-cfrg-frost-05, section C.1
-        let d_enc = hex::decode("7b1c33d3f5291d85de664833beb1ad469f7fb6025a0ec78b3a790c6e13a98304").unwrap();
-        let (d, _) = Scalar::decode32(&d_enc[..]);
-        let Q_enc = hex::decode("15d21ccd7ee42959562fc8aa63224c8851fb3ec85a3faf66040d380fb9738673").unwrap();
-        let Q = Point::decode(&Q_enc[..]).unwrap();
-        assert!(Point::mulgen(&d).equals(Q) == 0xFFFFFFFF);
-        let pkey = PublicKey::from_point(&Q);
-        let msg = hex::decode("74657374").unwrap();
-        let sig = hex::decode("2b8d9c6995333c5990e3a3dd6568785539d3322f7f0376452487ea35cfda587b75650edb12b1a8619c88ed1f8463d6baeefb18d3fed3c279102fdfecb255fa0e").unwrap();
-        assert!(pkey.verify_raw(&sig[..], &msg[..]));
+    fn decode_verbose() {
+        let B = Point::BASE;
+        let P = Point::mulgen(&Scalar::decode_reduce(b"decode verbose"));
+        for Q in [Point::NEUTRAL, B, P].iter() {
+            let R = Point::decode_verbose(&Q.encode()).unwrap();
+            assert!(R.equals(*Q) == 0xFFFFFFFF);
+        }
+
+        let buf = B.encode();
+        assert!(Point::decode_verbose(&buf[..31]).err()
+            == Some(DecodeError::BadLength));
+        assert!(Point::decode_verbose(&[0u8; 33]).err()
+            == Some(DecodeError::BadLength));
+
+        // y = p, y = p + 1 (with sign bit), y = 2^255 - 1
+        for s in [
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        ].iter() {
+            let buf = hex::decode(s).unwrap();
+            assert!(Point::decode_verbose(&buf).err()
+                == Some(DecodeError::NonCanonicalField));
+        }
+
+        // y = 1 and y = -1 (x = 0) with the sign bit set.
+        for s in [
+            "0100000000000000000000000000000000000000000000000000000000000080",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        ].iter() {
+            let buf = hex::decode(s).unwrap();
+            assert!(Point::decode_verbose(&buf).err()
+                == Some(DecodeError::NonCanonicalEncoding));
+        }
+
+        // y = 2 and y = 7: (y^2 - 1)/(d*y^2 + 1) is not a square.
+        for s in [
+            "0200000000000000000000000000000000000000000000000000000000000000",
+            "0700000000000000000000000000000000000000000000000000000000000080",
+        ].iter() {
+            let buf = hex::decode(s).unwrap();
+            assert!(Point::decode_verbose(&buf).err()
+                == Some(DecodeError::NotOnCurve));
+        }
+
+        // Low-order and mixed-order points decode with decode(), but not
+        // with decode_verbose().
+        let T8_enc: [u8; 32] = [
+            0x26, 0xE8, 0x95, 0x8F, 0xC2, 0xB2, 0x27, 0xB0,
+            0x45, 0xC3, 0xF4, 0x89, 0xF2, 0xEF, 0x98, 0xF0,
+            0xD5, 0xDF, 0xAC, 0x05, 0xD3, 0xC6, 0x33, 0x39,
+            0xB1, 0x38, 0x02, 0x88, 0x6D, 0x53, 0xFC, 0x85,
+        ];
+        let T8 = Point::decode(&T8_enc).unwrap();
+        for Q in [T8, T8.double(), P + T8, P + T8 * 4].iter() {
+            let buf = Q.encode();
+            assert!(Point::decode(&buf).is_some());
+            assert!(Point::decode_verbose(&buf).err()
+                == Some(DecodeError::NotInSubgroup));
+        }
     }
 
     #[test]
-    fn signatures_trunc() {
-        let skey = PrivateKey::from_seed(&[0u8; 32]);
-        let pkey = skey.public_key;
-        for i in 0..10 {
-            let mut msg = [0u8; 8];
-            msg[..].copy_from_slice(&(i as u64).to_le_bytes());
-            let mut sig = skey.sign_raw(&msg);
-            sig[63] = 0;
-            for rm in 8..33 {
-                let n = 512 - rm;
-                sig[n >> 3] &= !(0x01u8 << (n & 7));
-                let vv = pkey.verify_trunc_raw(&sig, rm, &msg);
-                assert!(vv.is_some());
-                let sig2 = vv.unwrap();
-                assert!(pkey.verify_raw(&sig2, &msg));
-                msg[0] ^= 1;
-                assert!(pkey.verify_trunc_raw(&sig, rm, &msg).is_none());
-                msg[0] ^= 1;
+    fn weak_keys() {
+        // All encodings of low-order points, canonical or not.
+        let mut enc = Vec::new();
+        for y in ["0000000000000000000000000000000000000000000000000000000000000000",
+                  "0100000000000000000000000000000000000000000000000000000000000000",
+                  "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+                  "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+                  "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+                  "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+                  "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a"]
+        {
+            let mut e = [0u8; 32];
+            hex::decode_to_slice(y, &mut e[..]).unwrap();
+            enc.push(e);
+            e[31] |= 0x80;
+            enc.push(e);
+        }
+        let mut num_canonical = 0;
+        for e in enc.iter() {
+            let pkey = PublicKey::decode_zip215(e).unwrap();
+            assert!(pkey.is_weak());
+            assert!(pkey.point.has_low_order() == 0xFFFFFFFF);
+            if let Some(pkey) = PublicKey::decode(e) {
+                assert!(pkey.is_weak());
+                num_canonical += 1;
+            }
+        }
+        assert!(num_canonical == 8);
+
+        // Base point and honest keys are not weak; adding a torsion
+        // component makes them weak.
+        let T8 = Point::decode(&enc[10]).unwrap();
+        assert!(!PublicKey::from_point(&Point::BASE).is_weak());
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let skey = PrivateKey::from_seed(&sh.finalize_reset());
+            assert!(!skey.public_key.is_weak());
+            for j in 1..8 {
+                let A = skey.public_key.point + T8 * j;
+                let pkey = PublicKey::from_point(&A);
+                assert!(pkey.is_weak());
+                assert!(pkey.point.has_low_order() == 0);
             }
         }
     }
@@ -8727,4 +12701,706 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn affine_coordinates() {
+        // Base point coordinates (RFC 8032, section 5.1).
+        let (x, y) = Point::BASE.to_affine_coordinates();
+        assert!(x[..] == hex::decode("1ad5258f602d56c9b2a7259560c72c695cdcd6fd31e2a4c0fe536ecdd3366921").unwrap());
+        assert!(y[..] == hex::decode("5866666666666666666666666666666666666666666666666666666666666666").unwrap());
+        let B = Point::from_affine_coordinates(&x, &y).unwrap();
+        assert!(B.equals(Point::BASE) == 0xFFFFFFFF);
+
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        let (x, y) = Point::NEUTRAL.to_affine_coordinates();
+        assert!(x == [0u8; 32] && y == one);
+
+        // Round-trips through compression, for points with arbitrary
+        // projective coordinates.
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let P = Point::mulgen(&Scalar::decode_reduce(&v[..]))
+                + Point::BASE.double();
+            let (x, y) = P.to_affine_coordinates();
+            let Q = Point::from_affine_coordinates(&x, &y).unwrap();
+            assert!(Q.equals(P) == 0xFFFFFFFF);
+            let mut enc = y;
+            enc[31] |= x[0] << 7;
+            assert!(enc == P.encode());
+            assert!(Point::decode(&enc).unwrap().to_affine_coordinates()
+                == (x, y));
+
+            // Points not on the curve, and non-canonical coordinates,
+            // are rejected.
+            let mut x2 = x;
+            x2[0] ^= 0x02;
+            assert!(Point::from_affine_coordinates(&x2, &y).is_none());
+            let mut y2 = y;
+            y2[31] |= 0x80;
+            assert!(Point::from_affine_coordinates(&x, &y2).is_none());
+        }
+
+        // x = p is the non-canonical encoding of x = 0.
+        let mut p_enc = [0xFFu8; 32];
+        p_enc[0] = 0xED;
+        p_enc[31] = 0x7F;
+        assert!(Point::from_affine_coordinates(&[0u8; 32], &one).is_some());
+        assert!(Point::from_affine_coordinates(&p_enc, &one).is_none());
+    }
+
+    #[test]
+    fn montgomery() {
+        // The base point maps to u = 9 (the X25519 base point).
+        let mut nine = [0u8; 32];
+        nine[0] = 9;
+        assert!(Point::BASE.to_montgomery_u().encode() == nine);
+        let B = Point::from_montgomery_u(&nine, 0).unwrap();
+        assert!(B.equals(Point::BASE) == 0xFFFFFFFF);
+        let B = Point::from_montgomery_u(&nine, 1).unwrap();
+        assert!(B.equals(-Point::BASE) == 0xFFFFFFFF);
+
+        // Round trips, with both sign choices.
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let P = Point::mulgen(&Scalar::decode_reduce(&v[..]))
+                + Point::ORDER2 * (i as u64);
+            let u = P.to_montgomery_u().encode();
+            let sign = P.encode()[31] >> 7;
+            let Q = Point::from_montgomery_u(&u, sign).unwrap();
+            assert!(Q.equals(P) == 0xFFFFFFFF);
+            let Q = Point::from_montgomery_u(&u, sign ^ 1).unwrap();
+            assert!(Q.equals(-P) == 0xFFFFFFFF);
+            assert!(Q.to_montgomery_u().encode() == u);
+        }
+
+        // u = 0 is the point of order 2 (x = 0, hence sign 1 fails).
+        let P = Point::from_montgomery_u(&[0u8; 32], 0).unwrap();
+        assert!(P.equals(Point::ORDER2) == 0xFFFFFFFF);
+        assert!(Point::from_montgomery_u(&[0u8; 32], 1).is_none());
+
+        // u = -1 has no Edwards image.
+        let mut m1 = [0xFFu8; 32];
+        m1[0] = 0xEC;
+        m1[31] = 0x7F;
+        assert!(Point::from_montgomery_u(&m1, 0).is_none());
+        assert!(Point::from_montgomery_u(&m1, 1).is_none());
+
+        // Non-canonical u (p + 9, and 9 with the top bit set).
+        let mut pp9 = [0xFFu8; 32];
+        pp9[0] = 0xF6;
+        pp9[31] = 0x7F;
+        assert!(Point::from_montgomery_u(&pp9, 0).is_none());
+        let mut n2 = nine;
+        n2[31] |= 0x80;
+        assert!(Point::from_montgomery_u(&n2, 0).is_none());
+
+        // u = 2 is on the twist, not on the curve.
+        let mut two = [0u8; 32];
+        two[0] = 2;
+        assert!(Point::from_montgomery_u(&two, 0).is_none());
+        assert!(Point::from_montgomery_u(&two, 1).is_none());
+    }
+
+    #[test]
+    fn hex_strings() {
+        // Scalars: round trips, uppercase input, alternate format.
+        let mut sh = Sha256::new();
+        for i in 0..10 {
+            sh.update(&(i as u64).to_le_bytes());
+            let x = Scalar::decode_reduce(&sh.finalize_reset());
+            let str = format!("{}", x);
+            assert!(str == hex::encode(x.encode()));
+            assert!(format!("{:x}", x) == str);
+            assert!(format!("{:#x}", x) == format!("0x{}", str));
+            let y: Scalar = str.parse().unwrap();
+            assert!(y.equals(x) == 0xFFFFFFFF);
+            let y: Scalar = str.to_uppercase().parse().unwrap();
+            assert!(y.equals(x) == 0xFFFFFFFF);
+        }
+
+        // Scalars not lower than L are rejected.
+        let lhex = "edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010";
+        let lm1 = "ecd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010";
+        assert!(lhex.parse::<Scalar>().err() == Some(ParseError::ScalarOutOfRange));
+        assert!(lm1.parse::<Scalar>().unwrap().equals(-Scalar::ONE) == 0xFFFFFFFF);
+        let ff = "ff".repeat(32);
+        assert!(ff.parse::<Scalar>().err() == Some(ParseError::ScalarOutOfRange));
+        assert!(lhex[..62].parse::<Scalar>().err() == Some(ParseError::BadLength));
+        assert!(format!("{}00", lm1).parse::<Scalar>().err()
+            == Some(ParseError::BadLength));
+        assert!(lm1.replace('f', "g").parse::<Scalar>().err()
+            == Some(ParseError::BadHex));
+
+        // Public keys.
+        for i in 0..10 {
+            let skey = PrivateKey::from_seed(&Sha256::digest(&[i as u8]));
+            let pkey = skey.public_key;
+            let str = format!("{}", pkey);
+            assert!(str == hex::encode(pkey.encode()));
+            assert!(format!("{:x}", pkey) == str);
+            let pk2: PublicKey = str.parse().unwrap();
+            assert!(pk2.encoded == pkey.encoded);
+            assert!(pk2.point.equals(pkey.point) == 0xFFFFFFFF);
+
+            // Secret values do not appear in the debug output.
+            let dbg = format!("{:?}", skey);
+            assert!(dbg.starts_with("PrivateKey"));
+            assert!(dbg.contains(&format!("{:?}", pkey)));
+            assert!(!dbg.contains(&format!("{:?}", skey.s)));
+            assert!(!dbg.contains(&format!("{:?}", skey.seed)));
+            let dbg = format!("{:?}", skey.expand());
+            assert!(!dbg.contains(&format!("{:?}", skey.s)));
+            assert!(!dbg.contains(&format!("{:?}", skey.h)));
+        }
+        let mut two = [0u8; 32];
+        two[0] = 2;
+        assert!(hex::encode(two).parse::<PublicKey>().err()
+            == Some(ParseError::BadPoint(DecodeError::NotOnCurve)));
+        let mut p_enc = [0xFFu8; 32];
+        p_enc[0] = 0xED;
+        p_enc[31] = 0x7F;
+        assert!(hex::encode(p_enc).parse::<PublicKey>().err()
+            == Some(ParseError::BadPoint(DecodeError::NonCanonicalField)));
+    }
+
+    // A reader over in-memory data, which can be configured to fail
+    // after some position, to return interrupted reads, or to modify the
+    // data after a rewind.
+    struct TestReader {
+        data: Vec<u8>,
+        pos: usize,
+        fail_at: usize,
+        interrupt: bool,
+        mutate: bool,
+        ticks: usize,
+    }
+
+    impl TestReader {
+        fn new(data: &[u8]) -> Self {
+            Self {
+                data: data.to_vec(), pos: 0, fail_at: usize::MAX,
+                interrupt: false, mutate: false, ticks: 0,
+            }
+        }
+    }
+
+    impl std::io::Read for TestReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            use std::io::{Error, ErrorKind};
+            self.ticks += 1;
+            if self.interrupt && (self.ticks & 1) != 0 {
+                return Err(Error::new(ErrorKind::Interrupted, "interrupted"));
+            }
+            if self.pos >= self.fail_at {
+                return Err(Error::new(ErrorKind::Other, "read failure"));
+            }
+            // Short reads (at most 5000 bytes) exercise partial chunks.
+            let end = self.data.len().min(self.fail_at);
+            let n = buf.len().min(end - self.pos).min(5000);
+            buf[..n].copy_from_slice(&self.data[self.pos..(self.pos + n)]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    impl std::io::Seek for TestReader {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            match pos {
+                std::io::SeekFrom::Start(n) => { self.pos = n as usize; }
+                std::io::SeekFrom::Current(0) => { }
+                _ => unimplemented!(),
+            }
+            if self.mutate && self.pos < self.data.len() {
+                self.data[self.pos] ^= 0x01;
+            }
+            Ok(self.pos as u64)
+        }
+    }
+
+    #[test]
+    fn sign_reader() {
+        use std::io::{Cursor, ErrorKind};
+
+        let skey = PrivateKey::from_seed(&[0x42u8; 32]);
+        let pkey = skey.public_key;
+        let data: Vec<u8> = (0..20000u32).map(|i| (i * 7 + (i >> 8)) as u8).collect();
+        for len in [0, 1, 100, 8191, 8192, 8193, 16384, 20000] {
+            let m = &data[..len];
+            let sig = skey.sign_raw(m);
+            let mut cur = Cursor::new(m);
+            assert!(skey.sign_reader(&mut cur).unwrap() == sig);
+            assert!(cur.position() == len as u64);
+            let mut cur = Cursor::new(m);
+            assert!(skey.expand().sign_reader(&mut cur).unwrap() == sig);
+            let mut cur = Cursor::new(m);
+            assert!(pkey.verify_reader(&sig, &mut cur).unwrap());
+            let mut sig2 = sig;
+            sig2[50] ^= 0x10;
+            let mut cur = Cursor::new(m);
+            assert!(!pkey.verify_reader(&sig2, &mut cur).unwrap());
+            if len > 0 {
+                let mut m2 = m.to_vec();
+                m2[len / 2] ^= 0x80;
+                let mut cur = Cursor::new(&m2);
+                assert!(!pkey.verify_reader(&sig, &mut cur).unwrap());
+            }
+
+            // Same with interrupted and short reads.
+            let mut rd = TestReader::new(m);
+            rd.interrupt = true;
+            assert!(skey.sign_reader(&mut rd).unwrap() == sig);
+            let mut rd = TestReader::new(m);
+            rd.interrupt = true;
+            assert!(pkey.verify_reader(&sig, &mut rd).unwrap());
+        }
+
+        // The message starts at the current position of the reader.
+        let sig = skey.sign_raw(&data[300..]);
+        let mut cur = Cursor::new(&data);
+        cur.set_position(300);
+        assert!(skey.sign_reader(&mut cur).unwrap() == sig);
+        cur.set_position(300);
+        assert!(pkey.verify_reader(&sig, &mut cur).unwrap());
+
+        // Read errors are propagated.
+        for fail_at in [0, 5000, 12000] {
+            let mut rd = TestReader::new(&data);
+            rd.fail_at = fail_at;
+            let err = skey.sign_reader(&mut rd).err().unwrap();
+            assert!(err.kind() == ErrorKind::Other);
+            let mut rd = TestReader::new(&data);
+            rd.fail_at = fail_at;
+            let err = pkey.verify_reader(&sig, &mut rd).err().unwrap();
+            assert!(err.kind() == ErrorKind::Other);
+        }
+
+        // Data which changes between the two passes is detected.
+        let mut rd = TestReader::new(&data);
+        rd.mutate = true;
+        let err = skey.sign_reader(&mut rd).err().unwrap();
+        assert!(err.kind() == ErrorKind::InvalidData);
+
+        // Malformed signatures are rejected without reading the data.
+        let mut rd = TestReader::new(&data);
+        rd.fail_at = 0;
+        assert!(!pkey.verify_reader(&sig[..63], &mut rd).unwrap());
+    }
+
+    #[test]
+    fn decode_batch() {
+        // Valid points (including low-order ones), off-curve encodings
+        // (y = 2), non-canonical y (y = p + 1) and x = 0 with the sign
+        // bit set, placed at every position in the batch.
+        let mut sh = Sha256::new();
+        let mut good = Vec::new();
+        for i in 0..8 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let P = Point::mulgen(&Scalar::decode_reduce(&v[..]))
+                + Point::ORDER2 * (i as u64);
+            good.push(P.encode());
+        }
+        good.push(Point::NEUTRAL.encode());
+        good.push(Point::ORDER2.encode());
+        let mut two = [0u8; 32];
+        two[0] = 2;
+        let mut pp1 = [0xFFu8; 32];
+        pp1[0] = 0xEE;
+        pp1[31] = 0x7F;
+        let mut neg0 = Point::NEUTRAL.encode();
+        neg0[31] |= 0x80;
+        let bad = [two, pp1, neg0];
+        for b in bad.iter() {
+            assert!(Point::decode(b).is_none());
+        }
+
+        for b in bad.iter() {
+            for j in 0..=good.len() {
+                let mut encs = good.clone();
+                encs.insert(j, *b);
+                let mut out = vec![None; encs.len()];
+                assert!(Point::decode_batch(&encs, &mut out) == good.len());
+                for (e, P) in encs.iter().zip(out.iter()) {
+                    match (Point::decode(e), P) {
+                        (Some(Q), Some(P)) => {
+                            assert!(P.equals(Q) == 0xFFFFFFFF);
+                            assert!(P.encode() == *e);
+                        }
+                        (None, None) => { }
+                        _ => panic!(),
+                    }
+                }
+                assert!(out[j].is_none());
+            }
+        }
+
+        // All-bad and empty batches.
+        let mut out = [None; 3];
+        assert!(Point::decode_batch(&bad, &mut out) == 0);
+        assert!(Point::decode_batch(&[], &mut []) == 0);
+    }
+
+    #[test]
+    fn cofactor() {
+        let T8_enc: [u8; 32] = [
+            0x26, 0xE8, 0x95, 0x8F, 0xC2, 0xB2, 0x27, 0xB0,
+            0x45, 0xC3, 0xF4, 0x89, 0xF2, 0xEF, 0x98, 0xF0,
+            0xD5, 0xDF, 0xAC, 0x05, 0xD3, 0xC6, 0x33, 0x39,
+            0xB1, 0x38, 0x02, 0x88, 0x6D, 0x53, 0xFC, 0x85,
+        ];
+        let T8 = Point::decode(&T8_enc).unwrap();
+        let inv8 = Scalar::ONE / Scalar::from_u32(8);
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            let mut P = Point::NEUTRAL;
+            if i > 0 {
+                sh.update(&(i as u64).to_le_bytes());
+                let v = sh.finalize_reset();
+                P.set_mulgen(&Scalar::decode_reduce(&v[..]));
+            }
+            let mut T = Point::NEUTRAL;
+            for _ in 0..8 {
+                // Cofactor clearing removes the low-order component, and
+                // keeps the prime-order component (multiplied by 8).
+                let Q = P + T;
+                let C = Q.mul_by_cofactor();
+                assert!(C.equals(P * 8u64) == 0xFFFFFFFF);
+                assert!((C * inv8).equals(P) == 0xFFFFFFFF);
+                assert!(T.mul_by_cofactor().isneutral() == 0xFFFFFFFF);
+
+                // Multiplication by L removes the prime-order component;
+                // since L = 5 mod 8, the low-order component remains.
+                let O = Q.mul_by_order();
+                assert!(O.isneutral() == Q.is_in_subgroup());
+                assert!(O.equals(T * 5u64) == 0xFFFFFFFF);
+                T += T8;
+            }
+        }
+        assert!(Point::NEUTRAL.mul_by_cofactor().isneutral() == 0xFFFFFFFF);
+        assert!(Point::NEUTRAL.mul_by_order().isneutral() == 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn sign_checked() {
+        use crate::SignError;
+
+        let mut sh = Sha512::new();
+        for i in 0..10u64 {
+            sh.update(i.to_le_bytes());
+            let v = sh.finalize_reset();
+            let sk = PrivateKey::from_seed(&v[..32]);
+            let m = &v[32..];
+            let sig = sk.sign_raw(m);
+            assert!(sk.sign_raw_checked(m) == Ok(sig));
+            let esk = sk.expand();
+            assert!(esk.sign_raw_checked(m) == Ok(sig));
+
+            // A signature corrupted before the check is not returned.
+            let mut bad = sig;
+            bad[(i as usize) * 6] ^= 0x01;
+            assert!(esk.check_signature(bad, m)
+                == Err(SignError::VerificationFailed));
+
+            // Simulated fault on the nonce: R and S are computed with two
+            // distinct (injected) nonces.
+            let sig1 = SignSession::start(&sk, &v).0.finish(m);
+            let sig2 = SignSession::start(&sk, &[i as u8; 64]).0.finish(m);
+            assert!(esk.check_signature(sig1, m) == Ok(sig1));
+            let mut bad = sig1;
+            bad[32..].copy_from_slice(&sig2[32..]);
+            assert!(esk.check_signature(bad, m)
+                == Err(SignError::VerificationFailed));
+        }
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn generate() {
+        use crate::chacha20::ChaChaRng;
+        use crate::{RngCore, SeedableRng};
+
+        // Keys are deterministic for a given RNG state, and distinct
+        // RNG states yield distinct keys.
+        let mut rng1 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng2 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng3 = ChaChaRng::from_seed([0x22u8; 32]);
+        let sk1 = PrivateKey::generate(&mut rng1);
+        let sk2 = PrivateKey::generate(&mut rng2);
+        let sk3 = PrivateKey::generate(&mut rng3);
+        assert!(sk1.encode() == sk2.encode());
+        assert!(sk1.encode() != sk3.encode());
+        assert!(PrivateKey::generate(&mut rng1).encode() != sk1.encode());
+
+        // The RNG output is used as seed for the deterministic
+        // derivation.
+        let mut seed = [0u8; 32];
+        ChaChaRng::from_seed([0x11u8; 32]).fill_bytes(&mut seed);
+        assert!(PrivateKey::from_seed(&seed).encode() == sk1.encode());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn blinded_mul() {
+        use crate::chacha20::ChaChaRng;
+        use crate::{RngCore, SeedableRng};
+
+        // Blinded computations return the same values as the unblinded
+        // ones, whatever the RNG output.
+        let mut rng = ChaChaRng::from_seed([0x44u8; 32]);
+        let mut buf = [0u8; 64];
+        for i in 0..20 {
+            rng.fill_bytes(&mut buf);
+            let n = match i {
+                0 => Scalar::ZERO,
+                1 => Scalar::ONE,
+                2 => -Scalar::ONE,
+                _ => Scalar::decode_reduce(&buf[..32]),
+            };
+            let Q = Point::mulgen(&n);
+            assert!(Point::mulgen_blinded(&n, &mut rng).equals(Q) != 0);
+
+            let sk = PrivateKey::generate(&mut rng);
+            assert!(sk.sign_raw_blinded(&mut rng, &buf[..i])
+                == sk.sign_raw(&buf[..i]));
+            let esk = sk.expand();
+            assert!(esk.sign_raw_blinded(&mut rng, &buf[..i])
+                == esk.sign_raw(&buf[..i]));
+        }
+    }
+
+    #[cfg(feature = "sha3")]
+    #[test]
+    fn blinding() {
+        use super::blinding::{blind_private_key, blind_public_key};
+        use super::blinding::{blinding_factor, time_period_param};
+        use super::blinding::blind_private_key_with_factor;
+        use super::blinding::blind_public_key_with_factor;
+
+        // Test vectors from Tor (src/test/ed25519_vectors.inc, produced
+        // by src/test/ed25519_exts_ref.py): seed (ED25519_SECRET_KEYS),
+        // public key (ED25519_PUBLIC_KEYS), blinding factor
+        // (ED25519_BLINDING_PARAMS) and blinded public key
+        // (ED25519_BLINDED_PUBLIC_KEYS).
+        const KAT: [[&str; 4]; 8] = [
+            [
+                "26c76712d89d906e6672dafa614c42e5cb1caac8c6568e4d2493087db51f0d36",
+                "c2247870536a192d142d056abefca68d6193158e7c1a59c1654c954eccaff894",
+                "54a513898b471d1d448a2f3c55c1de2c0ef718c447b04497eeb999ed32027823",
+                "1fc1fa4465bd9d4956fdbdc9d3acb3c7019bb8d5606b951c2e1dfe0b42eaeb41",
+            ],
+            [
+                "fba7a5366b5cb98c2667a18783f5cf8f4f8d1a2ce939ad22a6e685edde85128d",
+                "1519a3b15816a1aafab0b213892026ebf5c0dc232c58b21088d88cb90e9b940d",
+                "831e9b5325b5d31b7ae6197e9c7a7baf2ec361e08248bce055908971047a2347",
+                "1cbbd4a88ce8f165447f159d9f628ada18674158c4f7c5ead44ce8eb0fa6eb7e",
+            ],
+            [
+                "67e3aa7a14fac8445d15e45e38a523481a69ae35513c9e4143eb1c2196729a0e",
+                "081faa81992e360ea22c06af1aba096e7a73f1c665bc8b3e4e531c46455fd1dd",
+                "ac78a1d46faf3bfbbdc5af5f053dc6dc9023ed78236bec1760dadfd0b2603760",
+                "c5419ad133ffde7e0ac882055d942f582054132b092de377d587435722deb028",
+            ],
+            [
+                "d51385942033a76dc17f089a59e6a5a7fe80d9c526ae8ddd8c3a506b99d3d0a6",
+                "73cfa1189a723aad7966137cbffa35140bb40d7e16eae4c40b79b5f0360dd65a",
+                "f9c84dc0ac31571507993df94da1b3d28684a12ad14e67d0a068aba5c53019fc",
+                "3e08d0dc291066272e313014bfac4d39ad84aa93c038478a58011f431648105f",
+            ],
+            [
+                "5c8eac469bb3f1b85bc7cd893f52dc42a9ab66f1b02b5ce6a68e9b175d3bb433",
+                "66c1a77104d86461b6f98f73acf3cd229c80624495d2d74d6fda1e940080a96b",
+                "b1fe79d1dec9bc108df69f6612c72812755751f21ecc5af99663b30be8b9081f",
+                "59381f06acb6bf1389ba305f70874eed3e0f2ab57cdb7bc69ed59a9b8899ff4d",
+            ],
+            [
+                "eda433d483059b6d1ff8b7cfbd0fe406bfb23722c8f3c8252629284573b61b86",
+                "d21c294db0e64cb2d8976625786ede1d9754186ae8197a64d72f68c792eecc19",
+                "81f1512b63ab5fb5c1711a4ec83d379c420574aedffa8c3368e1c3989a3a0084",
+                "2b946a484344eb1c17c89dd8b04196a84f3b7222c876a07a4cece85f676f87d9",
+            ],
+            [
+                "4377c40431c30883c5fbd9bc92ae48d1ed8a47b81d13806beac5351739b5533d",
+                "c4d58b4cf85a348ff3d410dd936fa460c4f18da962c01b1963792b9dcc8a6ea6",
+                "97f45142597c473a4b0e9a12d64561133ad9e1155fe5a9807fe6af8a93557818",
+                "c6b585129b135f8769df2eba987e76e089e80ba3a2a6729134d3b28008ac098e",
+            ],
+            [
+                "c6bbcce615839756aed2cc78b1de13884dd3618f48367a17597a16c1cd7a290b",
+                "95126f14d86494020665face03f2d42ee2b312a85bc729903eb17522954a1c4a",
+                "3f44f6a5a92cde816635dfc12ade70539871078d2ff097278be2a555c9859cd0",
+                "0eefdc795b59cabbc194c6174e34ba9451e8355108520554ec285acabebb34ac",
+            ],
+        ];
+
+        // First entry of ED25519_BLINDED_SECRET_KEYS in the same file:
+        // blinded secret scalar (little-endian), then signing prefix.
+        const BLINDED_SECRET_KEY0: &str = "293c3acff4e902f6f63ddc5d5caa2a57e771db4f24de65d4c28df3232f47fa01171d43f24e3f53e70ec7ac280044ac77d4942dee5d6807118a59bdf3ee647e89";
+
+        for (i, kv) in KAT.iter().enumerate() {
+            let skey = PrivateKey::from_seed(&hex::decode(kv[0]).unwrap());
+            let pkey = skey.public_key;
+            assert!(pkey.encoded[..] == hex::decode(kv[1]).unwrap());
+            let mut h = [0u8; 32];
+            h[..].copy_from_slice(&hex::decode(kv[2]).unwrap());
+            let bpk = blind_public_key_with_factor(&pkey, &h);
+            assert!(bpk.encoded[..] == hex::decode(kv[3]).unwrap());
+            let bsk = blind_private_key_with_factor(&skey.expand(), &h);
+            assert!(bsk.public_key.encoded == bpk.encoded);
+            if i == 0 {
+                let mut enc = [0u8; 64];
+                enc[..32].copy_from_slice(&bsk.s.encode());
+                enc[32..].copy_from_slice(&bsk.h);
+                assert!(enc[..] == hex::decode(BLINDED_SECRET_KEY0).unwrap());
+            }
+            let sig = bsk.sign_raw(b"hello");
+            assert!(bpk.verify_raw(&sig, b"hello"));
+        }
+
+        // Round trips over several periods: blinded keys differ from
+        // each other and from the long-term key, and signatures verify
+        // only under the matching blinded key.
+        let skey = PrivateKey::from_seed(&[0x17u8; 32]);
+        let pkey = skey.public_key;
+        let mut prev = pkey;
+        for period in 1000..1010 {
+            let param = time_period_param(period, 1440);
+            let bpk = blind_public_key(&pkey, &param);
+            let h = blinding_factor(&pkey, &[], &param);
+            assert!(blind_public_key_with_factor(&pkey, &h).encoded
+                == bpk.encoded);
+            let bsk = blind_private_key(&skey.expand(), &param);
+            assert!(bsk.public_key.encoded == bpk.encoded);
+            assert!(bpk.encoded != pkey.encoded);
+            assert!(bpk.encoded != prev.encoded);
+            assert!(bpk.point.is_in_subgroup() == 0xFFFFFFFF);
+            let msg = period.to_le_bytes();
+            let sig = bsk.sign_raw(&msg);
+            assert!(bpk.verify_raw(&sig, &msg));
+            assert!(!pkey.verify_raw(&sig, &msg));
+            assert!(!prev.verify_raw(&sig, &msg));
+            prev = bpk;
+        }
+
+        // Torsion components of the long-term public key are cleared.
+        let h = blinding_factor(&pkey, b"secret", &time_period_param(1, 1440));
+        let pk2 = PublicKey::from_point(&(pkey.point + Point::ORDER2));
+        let bpk = blind_public_key_with_factor(&pkey, &h);
+        let bpk2 = blind_public_key_with_factor(&pk2, &h);
+        assert!(bpk2.encoded == bpk.encoded);
+        let bsk = blind_private_key_with_factor(&skey.expand(), &h);
+        assert!(bsk.public_key.encoded == bpk.encoded);
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn slip10() {
+        use super::slip10::{master_from_seed, derive_hardened, derive_path};
+        use super::slip10::{Slip10Error, HARDENED};
+
+        // Test vectors from SLIP-10 (ed25519): for each chain, the path,
+        // chain code, private key, and public key.
+        const KAT: [(&str, &[(&str, &str, &str, &str)]); 2] = [
+            ("000102030405060708090a0b0c0d0e0f", &[
+                ("m",
+                 "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+                 "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                 "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed"),
+                ("m/0'",
+                 "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+                 "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                 "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c"),
+                ("m/0'/1'",
+                 "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14",
+                 "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+                 "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187"),
+                ("m/0'/1'/2'",
+                 "2e69929e00b5ab250f49c3fb1c12f252de4fed2c1db88387094a0f8c4c9ccd6c",
+                 "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9",
+                 "ae98736566d30ed0e9d2f4486a64bc95740d89c7db33f52121f8ea8f76ff0fc1"),
+                ("m/0'/1'/2'/2'",
+                 "8f6d87f93d750e0efccda017d662a1b31a266e4a6f5993b15f5c1f07f74dd5cc",
+                 "30d1dc7e5fc04c31219ab25a27ae00b50f6fd66622f6e9c913253d6511d1e662",
+                 "8abae2d66361c879b900d204ad2cc4984fa2aa344dd7ddc46007329ac76c429c"),
+                ("m/0'/1'/2'/2'/1000000000'",
+                 "68789923a0cac2cd5a29172a475fe9e0fb14cd6adb5ad98a3fa70333e7afa230",
+                 "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+                 "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a"),
+            ]),
+            ("fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542", &[
+                ("m",
+                 "ef70a74db9c3a5af931b5fe73ed8e1a53464133654fd55e7a66f8570b8e33c3b",
+                 "171cb88b1b3c1db25add599712e36245d75bc65a1a5c9e18d76f9f2b1eab4012",
+                 "8fe9693f8fa62a4305a140b9764c5ee01e455963744fe18204b4fb948249308a"),
+                ("m/0'",
+                 "0b78a3226f915c082bf118f83618a618ab6dec793752624cbeb622acb562862d",
+                 "1559eb2bbec5790b0c65d8693e4d0875b1747f4970ae8b650486ed7470845635",
+                 "86fab68dcb57aa196c77c5f264f215a112c22a912c10d123b0d03c3c28ef1037"),
+                ("m/0'/2147483647'",
+                 "138f0b2551bcafeca6ff2aa88ba8ed0ed8de070841f0c4ef0165df8181eaad7f",
+                 "ea4f5bfe8694d8bb74b7b59404632fd5968b774ed545e810de9c32a4fb4192f4",
+                 "5ba3b9ac6e90e83effcd25ac4e58a1365a9e35a3d3ae5eb07b9e4d90bcf7506d"),
+                ("m/0'/2147483647'/1'",
+                 "73bd9fff1cfbde33a1b846c27085f711c0fe2d66fd32e139d3ebc28e5a4a6b90",
+                 "3757c7577170179c7868353ada796c839135b3d30554bbb74a4b1e4a5a58505c",
+                 "2e66aa57069c86cc18249aecf5cb5a9cebbfd6fadeab056254763874a9352b45"),
+                ("m/0'/2147483647'/1'/2147483646'",
+                 "0902fe8a29f9140480a00ef244bd183e8a13288e4412d8389d140aac1794825a",
+                 "5837736c89570de861ebc173b1086da4f505d4adb387c6a1b1342d5e4ac9ec72",
+                 "e33c0f7d81d843c572275f287498e8d408654fdf0d1e065b84e2e6f157aab09b"),
+                ("m/0'/2147483647'/1'/2147483646'/2'",
+                 "5d70af781f3a37b829f0d060924d5e960bdc02e85423494afc0b1a41bbe196d4",
+                 "551d333177df541ad876a60ea71f00447931c0a9da16f227c11ea080d7391b8d",
+                 "47150c75db263559a70d5778bf36abbab30fb061ad69f69ece61a72b0cfa4fc0"),
+            ]),
+        ];
+        for (seed, chains) in KAT.iter() {
+            let seed = hex::decode(seed).unwrap();
+            let (mut sk, mut cc) = master_from_seed(&seed);
+            for (i, (path, cc_ref, sk_ref, pk_ref)) in chains.iter().enumerate() {
+                if i > 0 {
+                    // Step-by-step derivation, with the last path element.
+                    let j = path.rfind('/').unwrap();
+                    let index: u32 = path[(j + 1)..(path.len() - 1)]
+                        .parse().unwrap();
+                    (sk, cc) = derive_hardened(&sk, &cc, index + HARDENED)
+                        .unwrap();
+                }
+                assert!(cc[..] == hex::decode(cc_ref).unwrap()[..]);
+                assert!(sk.encode()[..] == hex::decode(sk_ref).unwrap()[..]);
+                assert!(sk.public_key.encode()[..]
+                    == hex::decode(pk_ref).unwrap()[..]);
+
+                let (sk2, cc2) = derive_path(&seed, path).unwrap();
+                assert!(sk2.encode() == sk.encode() && cc2 == cc);
+                let hpath = path.replace('\'', "h");
+                let (sk2, cc2) = derive_path(&seed, &hpath).unwrap();
+                assert!(sk2.encode() == sk.encode() && cc2 == cc);
+            }
+        }
+
+        // Non-hardened derivation is rejected.
+        let (sk, cc) = master_from_seed(&[0u8; 16]);
+        assert!(derive_hardened(&sk, &cc, 0).err()
+            == Some(Slip10Error::NonHardenedIndex));
+        assert!(derive_hardened(&sk, &cc, HARDENED - 1).err()
+            == Some(Slip10Error::NonHardenedIndex));
+        assert!(derive_hardened(&sk, &cc, HARDENED).is_ok());
+        assert!(derive_path(&[0u8; 16], "m/44'/0").err()
+            == Some(Slip10Error::NonHardenedIndex));
+
+        // Malformed paths.
+        for path in ["", "/0'", "n/0'", "m/", "m//0'", "m/0''", "m/a'",
+            "m/+1'", "m/-1'", "m/ 1'", "m/2147483648'", "m/99999999999'",
+            "M/0'"]
+        {
+            assert!(derive_path(&[0u8; 16], path).err()
+                == Some(Slip10Error::InvalidPath));
+        }
+        assert!(derive_path(&[0u8; 16], "m/44'/501'/0'/0'").is_ok());
+    }
 }
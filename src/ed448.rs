@@ -468,6 +468,29 @@ impl Point {
         self.Y.equals(self.Z)
     }
 
+    /// Multiplies this point by the cofactor (4), with two doublings.
+    ///
+    /// The result is always in the subgroup of prime order `L`; points of
+    /// low order are mapped to the neutral. This function is
+    /// constant-time.
+    #[inline]
+    pub fn mul_by_cofactor(self) -> Self {
+        self.xdouble(2)
+    }
+
+    /// Multiplies this point by the prime order `L` of the subgroup.
+    ///
+    /// The result is the neutral if and only if this point is in the
+    /// subgroup of order `L`; otherwise, it is a non-neutral point of
+    /// low order (`L` is odd, so the low-order component of the point is
+    /// not cleared). This function is constant-time; if only the
+    /// subgroup membership is needed, `is_in_subgroup()` is faster.
+    pub fn mul_by_order(self) -> Self {
+        // Multiplication by a scalar computes the product with an
+        // integer in the 0 to L-1 range, hence: L*P = (L-1)*P + P.
+        self * (-Scalar::ONE) + self
+    }
+
     /// Tests whether this point is a low-order point, i.e. a point of
     /// order 1, 2 or 4.
     ///
@@ -3092,4 +3115,36 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn cofactor() {
+        let T4 = Point::decode(&[0u8; 57]).unwrap();
+        let mut sh = Shake256::default();
+        for i in 0..20 {
+            let mut P = Point::NEUTRAL;
+            if i > 0 {
+                sh.update(&(i as u64).to_le_bytes());
+                let mut v = [0u8; 64];
+                sh.finalize_xof_reset().read(&mut v);
+                P.set_mulgen(&Scalar::decode_reduce(&v[..]));
+            }
+            let mut T = Point::NEUTRAL;
+            for _ in 0..4 {
+                // Cofactor clearing removes the low-order component, and
+                // keeps the prime-order component (multiplied by 4).
+                let Q = P + T;
+                assert!(Q.mul_by_cofactor().equals(P * 4u64) == 0xFFFFFFFF);
+                assert!(T.mul_by_cofactor().isneutral() == 0xFFFFFFFF);
+
+                // Multiplication by L removes the prime-order component;
+                // since L = 3 mod 4, the low-order component remains.
+                let O = Q.mul_by_order();
+                assert!(O.isneutral() == Q.is_in_subgroup());
+                assert!(O.equals(T * 3u64) == 0xFFFFFFFF);
+                T += T4;
+            }
+        }
+        assert!(Point::NEUTRAL.mul_by_cofactor().isneutral() == 0xFFFFFFFF);
+        assert!(Point::NEUTRAL.mul_by_order().isneutral() == 0xFFFFFFFF);
+    }
 }
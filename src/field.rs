@@ -25,8 +25,265 @@ pub use crate::backend::GFsecp256k1;
 #[cfg(feature = "gfp256")]
 pub use crate::backend::GFp256;
 
+#[cfg(feature = "gfp384")]
+pub use crate::backend::GFp384;
+
 #[cfg(feature = "gf448")]
 pub use crate::backend::GF448;
 
+#[cfg(feature = "gfp521")]
+pub use crate::backend::GFp521;
+
 #[cfg(feature = "gfb254")]
 pub use crate::backend::{GFb127, GFb254};
+
+#[cfg(feature = "gf25519")]
+impl GF25519 {
+
+    // 2^((p-1)/4), which is a square root of -1.
+    const SQRT_M1: Self = Self::w64be(
+        0x2B8324804FC1DF0B,
+        0x2B4D00993DFBD7A7,
+        0x2F431806AD2FE478,
+        0xC4EE1B274A0EA0B0,
+    );
+
+    /// Square root of a ratio.
+    ///
+    /// This follows the definition of `SQRT_RATIO_M1()` in the
+    /// ristretto255 specification (RFC 9496, section 4.2). Returned
+    /// value is `(x, r)` such that:
+    ///
+    ///  - If `u` and `v` are non-zero, and `u/v` is a square, then
+    ///    `r = 0xFFFFFFFF` and `x = sqrt(u/v)`.
+    ///
+    ///  - If `u` is zero, then `r = 0xFFFFFFFF` and `x = 0` (regardless
+    ///    of the value of `v`).
+    ///
+    ///  - If `u` is non-zero but `v` is zero, then `r = 0x00000000` and
+    ///    `x = 0`.
+    ///
+    ///  - If `u` and `v` are non-zero, and `u/v` is not a square, then
+    ///    `r = 0x00000000` and `x = sqrt(SQRT_M1*(u/v))`, where
+    ///    `SQRT_M1` is the square root of -1 equal to `2^((p-1)/4)`.
+    ///
+    /// The returned root is the "nonnegative" one, i.e. the least
+    /// significant bit of its integer representation (in the 0 to p-1
+    /// range) is zero; this matches the root returned by `sqrt()`. The
+    /// computation is constant-time and uses a single exponentiation
+    /// (no separate inversion).
+    pub fn sqrt_ratio(u: &Self, v: &Self) -> (Self, u32) {
+        let u = *u;
+        let v = *v;
+        let v3 = v.square() * v;
+        let v7 = v3.square() * v;
+        let x = u * v7;
+
+        // Raise x to the power (p-5)/8 = 2^252 - 3.
+        let x2 = x.square() * x;
+        let x4 = x2.xsquare(2) * x2;
+        let x5 = x4.square() * x;
+        let x10 = x5.xsquare(5) * x5;
+        let x20 = x10.xsquare(10) * x10;
+        let x25 = x20.xsquare(5) * x5;
+        let x50 = x25.xsquare(25) * x25;
+        let x100 = x50.xsquare(50) * x50;
+        let x125 = x100.xsquare(25) * x25;
+        let x250 = x125.xsquare(125) * x125;
+        let x = x250.xsquare(2) * x;
+
+        let r = (u * v3) * x;
+        let c = v * r.square();
+        let correct_sign_sqrt   = c.equals(u);
+        let flipped_sign_sqrt   = c.equals(-u);
+        let flipped_sign_sqrt_i = c.equals(-u * Self::SQRT_M1);
+
+        let r_prime = r * Self::SQRT_M1;
+        let r = Self::select(&r, &r_prime,
+            flipped_sign_sqrt | flipped_sign_sqrt_i);
+        let r = Self::select(&r, &-r, ((r.encode()[0] & 1) as u32).wrapping_neg());
+
+        (r, correct_sign_sqrt | flipped_sign_sqrt)
+    }
+}
+
+// For fields with modulus p = 3 mod 4, -1 is not a square; sqrt_ratio()
+// uses it as the non-square multiplier.
+#[cfg(any(feature = "gfp256", feature = "gfsecp256k1"))]
+macro_rules! define_sqrt_ratio_3mod4 {
+    ($typename:ident) => {
+        impl $typename {
+
+            /// Square root of a ratio.
+            ///
+            /// Returned value is `(x, r)` such that:
+            ///
+            ///  - If `u` and `v` are non-zero, and `u/v` is a square,
+            ///    then `r = 0xFFFFFFFF` and `x = sqrt(u/v)`.
+            ///
+            ///  - If `u` is zero, then `r = 0xFFFFFFFF` and `x = 0`
+            ///    (regardless of the value of `v`).
+            ///
+            ///  - If `u` is non-zero but `v` is zero, then `r = 0x00000000`
+            ///    and `x = 0`.
+            ///
+            ///  - If `u` and `v` are non-zero, and `u/v` is not a square,
+            ///    then `r = 0x00000000` and `x = sqrt(-u/v)` (since the
+            ///    field modulus is equal to 3 modulo 4, -1 is not a
+            ///    square, hence `-u/v` is a square).
+            ///
+            /// The returned root is the one whose least significant bit
+            /// (in the 0 to p-1 range) is zero, as with `sqrt()`. The
+            /// computation is constant-time; it uses one inversion and
+            /// two square root computations.
+            pub fn sqrt_ratio(u: &Self, v: &Self) -> (Self, u32) {
+                let w = *u / *v;
+                let (x1, r1) = w.sqrt();
+                let (x2, _) = (-w).sqrt();
+                let x = Self::select(&x2, &x1, r1);
+                (x, r1 & !(v.iszero() & !u.iszero()))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gfp256")]
+define_sqrt_ratio_3mod4!(GFp256);
+
+#[cfg(feature = "gfsecp256k1")]
+define_sqrt_ratio_3mod4!(GFsecp256k1);
+
+// Integers modulo a prime (in particular, the scalars of the curve
+// modules) are displayed in hexadecimal, using their 32-byte encoding
+// (unsigned little-endian convention, as with `encode32()`). Parsing
+// expects exactly 64 hexadecimal digits and rejects values which are
+// not lower than the modulus.
+
+#[cfg(feature = "modint256")]
+impl<const M0: u64, const M1: u64, const M2: u64, const M3: u64>
+    core::fmt::Display for ModInt256<M0, M1, M2, M3>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode32())
+    }
+}
+
+#[cfg(feature = "modint256")]
+impl<const M0: u64, const M1: u64, const M2: u64, const M3: u64>
+    core::fmt::LowerHex for ModInt256<M0, M1, M2, M3>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode32())
+    }
+}
+
+#[cfg(feature = "modint256")]
+impl<const M0: u64, const M1: u64, const M2: u64, const M3: u64>
+    core::str::FromStr for ModInt256<M0, M1, M2, M3>
+{
+    type Err = crate::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; 32];
+        crate::hex::parse_fixed(s, &mut buf)?;
+        let (x, ok) = Self::decode32(&buf);
+        if ok == 0 {
+            return Err(crate::ParseError::ScalarOutOfRange);
+        }
+        Ok(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use sha2::{Sha256, Digest};
+
+    // Checks sqrt_ratio() on fixed and random inputs. Parameter `nqr`
+    // is the non-square multiplier used for non-square ratios.
+    macro_rules! check_sqrt_ratio {
+        ($typename:ident, $nqr:expr) => {{
+            let nqr: $typename = $nqr;
+            let lsb = |x: $typename| x.encode()[0] & 1;
+
+            // Known squares: 4/1, 1/4, 9/16.
+            for (u, v, x) in [(4, 1, 2), (1, 4, 0), (9, 16, 0)].iter() {
+                let u = $typename::from_u32(*u);
+                let v = $typename::from_u32(*v);
+                let (y, r) = $typename::sqrt_ratio(&u, &v);
+                assert!(r == 0xFFFFFFFF);
+                assert!((y.square() * v).equals(u) == 0xFFFFFFFF);
+                assert!(lsb(y) == 0);
+                if *x != 0 {
+                    assert!(y.equals($typename::from_u32(*x)) == 0xFFFFFFFF);
+                }
+            }
+
+            // Zero numerator and/or zero denominator.
+            let z = $typename::ZERO;
+            let three = $typename::from_u32(3);
+            let (y, r) = $typename::sqrt_ratio(&z, &three);
+            assert!(r == 0xFFFFFFFF && y.iszero() == 0xFFFFFFFF);
+            let (y, r) = $typename::sqrt_ratio(&z, &z);
+            assert!(r == 0xFFFFFFFF && y.iszero() == 0xFFFFFFFF);
+            let (y, r) = $typename::sqrt_ratio(&three, &z);
+            assert!(r == 0 && y.iszero() == 0xFFFFFFFF);
+
+            // Random inputs; for v = 1, results must match sqrt().
+            let mut sh = Sha256::new();
+            let mut nsq = 0;
+            for i in 0..100u64 {
+                sh.update(&(2 * i).to_le_bytes());
+                let u = $typename::decode_reduce(&sh.finalize_reset());
+                sh.update(&(2 * i + 1).to_le_bytes());
+                let v = $typename::decode_reduce(&sh.finalize_reset());
+                let w = u / v;
+                let (y, r) = $typename::sqrt_ratio(&u, &v);
+                assert!(lsb(y) == 0);
+                if r == 0xFFFFFFFF {
+                    assert!(y.square().equals(w) == 0xFFFFFFFF);
+                } else {
+                    assert!(r == 0);
+                    assert!(y.square().equals(nqr * w) == 0xFFFFFFFF);
+                    nsq += 1;
+                }
+                assert!(r == w.sqrt().1);
+
+                let (y, r) = $typename::sqrt_ratio(&u, &$typename::ONE);
+                let (y2, r2) = u.sqrt();
+                assert!(r == r2);
+                if r != 0 {
+                    assert!(y.equals(y2) == 0xFFFFFFFF);
+                }
+            }
+            assert!(nsq > 20 && nsq < 80);
+        }}
+    }
+
+    #[cfg(feature = "gf25519")]
+    #[test]
+    fn sqrt_ratio_gf25519() {
+        use super::GF25519;
+        check_sqrt_ratio!(GF25519, GF25519::SQRT_M1);
+
+        // 2 is not a square modulo 2^255 - 19.
+        let (y, r) = GF25519::sqrt_ratio(
+            &GF25519::from_u32(2), &GF25519::ONE);
+        assert!(r == 0);
+        assert!(y.square().equals(GF25519::SQRT_M1.mul2()) == 0xFFFFFFFF);
+    }
+
+    #[cfg(feature = "gfp256")]
+    #[test]
+    fn sqrt_ratio_gfp256() {
+        use super::GFp256;
+        check_sqrt_ratio!(GFp256, -GFp256::ONE);
+    }
+
+    #[cfg(feature = "gfsecp256k1")]
+    #[test]
+    fn sqrt_ratio_gfsecp256k1() {
+        use super::GFsecp256k1;
+        check_sqrt_ratio!(GFsecp256k1, -GFsecp256k1::ONE);
+    }
+}
@@ -1615,6 +1615,34 @@ macro_rules! define_frost_tests { () => {
         assert!(group_pk.verify_esig(&esig, &msg));
     }
 
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn dealer_generate() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        // Key generation and splitting by the trusted dealer are
+        // deterministic for a given RNG state.
+        let mut rng1 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng2 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng3 = ChaChaRng::from_seed([0x22u8; 32]);
+        let gsk1 = GroupPrivateKey::generate(&mut rng1);
+        let gsk2 = GroupPrivateKey::generate(&mut rng2);
+        let gsk3 = GroupPrivateKey::generate(&mut rng3);
+        assert!(gsk1.encode() == gsk2.encode());
+        assert!(gsk1.encode() != gsk3.encode());
+
+        let (sh1, vss1) = KeySplitter::trusted_split(&mut rng1, gsk1, 2, 3);
+        let (sh2, vss2) = KeySplitter::trusted_split(&mut rng2, gsk2, 2, 3);
+        let (sh3, _) = KeySplitter::trusted_split(&mut rng3, gsk1, 2, 3);
+        assert!(VSSElement::encode_list(&vss1) == VSSElement::encode_list(&vss2));
+        for i in 0..3 {
+            assert!(sh1[i].encode() == sh2[i].encode());
+            assert!(sh1[i].encode() != sh3[i].encode());
+            assert!(sh1[i].verify_split(&vss1));
+        }
+    }
+
 } } // End of macro: define_frost_tests
 
 // ========================================================================
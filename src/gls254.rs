@@ -893,6 +893,36 @@ impl Point {
         self.X.iszero()
     }
 
+    /// Multiplies this point by the cofactor of the group.
+    ///
+    /// This group has prime order (the cofactor is 1), so the point is
+    /// returned unchanged. This function is provided so that generic
+    /// code can clear cofactors uniformly across all curve modules.
+    #[inline(always)]
+    pub fn mul_by_cofactor(self) -> Self {
+        self
+    }
+
+    /// Multiplies this point by the group order.
+    ///
+    /// Since this group has prime order, the result is always the
+    /// neutral. This function is provided for uniformity with the curve
+    /// modules whose groups have a cofactor.
+    #[inline(always)]
+    pub fn mul_by_order(self) -> Self {
+        Self::NEUTRAL
+    }
+
+    /// Tests whether this point is in the subgroup of prime order.
+    ///
+    /// Since this group has prime order, 0xFFFFFFFF is always returned.
+    /// This function is provided for uniformity with the curve modules
+    /// whose groups have a cofactor.
+    #[inline(always)]
+    pub fn is_in_subgroup(self) -> u32 {
+        0xFFFFFFFF
+    }
+
     /// Conditionally copies the provided point (`P`) into `self`.
     ///
     ///  - If `ctl` = 0xFFFFFFFF, then the value of `P` is copied into `self`.
@@ -3440,7 +3470,7 @@ impl PublicKey {
         let c1 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&cb[8..]).unwrap());
         let R = (-self.point).mul64mu_add_mulgen_vartime(c0, c1, &s);
         let cb = make_challenge(&R, &self.encoded, hash_name, data);
-        return cb[..] == sig[0..16];
+        crate::ct::equals(&cb, &sig[0..16])
     }
 }
 
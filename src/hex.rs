@@ -0,0 +1,265 @@
+//! Constant-time hexadecimal encoding and decoding.
+//!
+//! The functions in this module do not use lookup tables, and do not
+//! branch on the values of the encoded or decoded bytes: nibbles are
+//! mapped to and from characters with arithmetic operations only, and
+//! decoding errors are accumulated and reported only at the end of the
+//! processing (without early exit). Timing depends only on the data
+//! _lengths_, which are assumed to be public. This makes these functions
+//! suitable for handling secret values such as private keys.
+//!
+//! Encoding produces lowercase characters. Decoding accepts both
+//! uppercase and lowercase characters; no whitespace or other separator
+//! is allowed, and the input length must be even.
+
+#[cfg(feature = "alloc")]
+use super::{String, Vec};
+
+/// Error type for hexadecimal decoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexError {
+    /// The input length is odd.
+    OddLength,
+    /// The input contains a character which is not a hexadecimal digit.
+    BadCharacter,
+    /// The output buffer is too small.
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for HexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HexError::OddLength => f.write_str("odd input length"),
+            HexError::BadCharacter => f.write_str("invalid hexadecimal character"),
+            HexError::BufferTooSmall => f.write_str("output buffer too small"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HexError {}
+
+// Map a nibble (0 to 15) to its lowercase hexadecimal character.
+#[inline(always)]
+fn nibble_to_char(x: u8) -> u8 {
+    let x = x as i32;
+    // If x > 9, then (9 - x) is negative and the shift yields -1.
+    (x + 0x30 + (((9 - x) >> 8) & 39)) as u8
+}
+
+// Map a character to its value as a hexadecimal digit. The returned
+// mask is -1 if the character is valid, 0 otherwise (the returned value
+// is then 0).
+#[inline(always)]
+fn char_to_nibble(c: u8) -> (u8, i32) {
+    let c = c as i32;
+    let d = c - 0x30;
+    let md = !((d | (9 - d)) >> 8);
+    let a = (c | 0x20) - 0x61;
+    let ma = !((a | (5 - a)) >> 8);
+    (((d & md) | ((a + 10) & ma)) as u8, md | ma)
+}
+
+/// Encode some bytes in hexadecimal (lowercase). The output buffer must
+/// have length at least twice the input length; the number of written
+/// characters (`2*src.len()`) is returned.
+pub fn encode_into(src: &[u8], dst: &mut [u8]) -> Result<usize, HexError> {
+    let n = src.len() << 1;
+    if dst.len() < n {
+        return Err(HexError::BufferTooSmall);
+    }
+    for (i, &b) in src.iter().enumerate() {
+        dst[2 * i] = nibble_to_char(b >> 4);
+        dst[2 * i + 1] = nibble_to_char(b & 0x0F);
+    }
+    Ok(n)
+}
+
+/// Encode some bytes in hexadecimal (lowercase) into a new string.
+#[cfg(feature = "alloc")]
+pub fn encode_string(src: &[u8]) -> String {
+    let mut buf = Vec::with_capacity(src.len() << 1);
+    for &b in src.iter() {
+        buf.push(nibble_to_char(b >> 4));
+        buf.push(nibble_to_char(b & 0x0F));
+    }
+    String::from_utf8(buf).unwrap()
+}
+
+// Decode `src` into `dst` (`src.len() == 2*dst.len()`); returned value
+// is -1 on success, 0 on error (in which case `dst` is filled with
+// zeros).
+fn decode_inner(src: &[u8], dst: &mut [u8]) -> i32 {
+    let mut ok = -1i32;
+    for i in 0..dst.len() {
+        let (hi, mh) = char_to_nibble(src[2 * i]);
+        let (lo, ml) = char_to_nibble(src[2 * i + 1]);
+        ok &= mh & ml;
+        dst[i] = (hi << 4) | lo;
+    }
+    let m = ok as u8;
+    for b in dst.iter_mut() {
+        *b &= m;
+    }
+    ok
+}
+
+/// Decode some hexadecimal characters. Both uppercase and lowercase
+/// characters are accepted. The output buffer must have length at least
+/// half of the input length (which must be even); the number of decoded
+/// bytes is returned.
+///
+/// The whole input is always processed; on error, no information is
+/// leaked on the position of the invalid character(s), and the relevant
+/// part of the output buffer is filled with zeros.
+pub fn decode_into<T: AsRef<[u8]>>(src: T, dst: &mut [u8])
+    -> Result<usize, HexError>
+{
+    let src = src.as_ref();
+    if (src.len() & 1) != 0 {
+        return Err(HexError::OddLength);
+    }
+    let n = src.len() >> 1;
+    if dst.len() < n {
+        return Err(HexError::BufferTooSmall);
+    }
+    if decode_inner(src, &mut dst[..n]) == 0 {
+        return Err(HexError::BadCharacter);
+    }
+    Ok(n)
+}
+
+/// Decode some hexadecimal characters into a fixed-length output, with a
+/// constant-time validity status. The input length MUST be exactly
+/// twice the output length (lengths are considered public); otherwise,
+/// this function panics. Returned value is 0xFFFFFFFF on success, or
+/// 0x00000000 if the input contains an invalid character (in which case
+/// the output buffer is filled with zeros). This function is meant for
+/// decoding secret values such as private keys: neither the character
+/// values nor the decoding success are leaked through timing-based
+/// side channels.
+pub fn decode_ct<T: AsRef<[u8]>>(src: T, dst: &mut [u8]) -> u32 {
+    let src = src.as_ref();
+    assert!(src.len() == dst.len() << 1);
+    decode_inner(src, dst) as u32
+}
+
+// Write some bytes in hexadecimal (lowercase) into a formatter. This is
+// used by the `Display` and `LowerHex` implementations of scalars, keys
+// and signatures; with the alternate flag (`{:#x}`), a "0x" prefix is
+// added. This helper and `parse_fixed()` are only compiled along with
+// the modules that use them (`modint256` is implied by all curve
+// modules except P-384 and P-521).
+#[cfg(any(feature = "modint256", feature = "p384", feature = "p521"))]
+pub(crate) fn fmt_bytes(f: &mut core::fmt::Formatter<'_>, src: &[u8])
+    -> core::fmt::Result
+{
+    if f.alternate() {
+        f.write_str("0x")?;
+    }
+    let mut tmp = [0u8; 128];
+    for chunk in src.chunks(64) {
+        let n = encode_into(chunk, &mut tmp).unwrap();
+        f.write_str(core::str::from_utf8(&tmp[..n]).unwrap())?;
+    }
+    Ok(())
+}
+
+// Decode a hexadecimal string into exactly `dst.len()` bytes, for the
+// `FromStr` implementations of scalars, keys and signatures.
+#[cfg(any(feature = "modint256", feature = "p384", feature = "p521"))]
+pub(crate) fn parse_fixed(s: &str, dst: &mut [u8])
+    -> Result<(), crate::ParseError>
+{
+    if s.len() != dst.len() << 1 {
+        return Err(crate::ParseError::BadLength);
+    }
+    match decode_into(s, dst) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(crate::ParseError::BadHex),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{HexError, encode_into, encode_string, decode_into, decode_ct};
+
+    #[test]
+    fn roundtrip() {
+        let mut data = [0u8; 256];
+        for i in 0..data.len() {
+            data[i] = i as u8;
+        }
+        for n in 0..=data.len() {
+            let mut tmp = [0u8; 512];
+            assert!(encode_into(&data[..n], &mut tmp) == Ok(2 * n));
+            let s = encode_string(&data[..n]);
+            assert!(s.as_bytes() == &tmp[..(2 * n)]);
+            assert!(s == hex::encode(&data[..n]));
+            let mut out = [0u8; 256];
+            assert!(decode_into(&s, &mut out) == Ok(n));
+            assert!(out[..n] == data[..n]);
+            let mut out = [0u8; 256];
+            assert!(decode_into(s.to_uppercase(), &mut out) == Ok(n));
+            assert!(out[..n] == data[..n]);
+            let mut out = [0u8; 256];
+            assert!(decode_ct(&s, &mut out[..n]) == 0xFFFFFFFF);
+            assert!(out[..n] == data[..n]);
+        }
+        let mut tmp = [0u8; 5];
+        assert!(encode_into(&data[..3], &mut tmp) == Err(HexError::BufferTooSmall));
+    }
+
+    #[test]
+    fn bad_input() {
+        let mut out = [0u8; 8];
+        assert!(decode_into("abc", &mut out) == Err(HexError::OddLength));
+        assert!(decode_into("0011", &mut out[..1]) == Err(HexError::BufferTooSmall));
+
+        // Every non-hexadecimal character is rejected, at every position.
+        let good = b"0123456789abcdefABCDEF";
+        for c in 0..=255u8 {
+            let valid = good.contains(&c);
+            for j in 0..16 {
+                let mut s = *b"0f1e2d3c4b5a6978";
+                s[j] = c;
+                let mut out = [0xAAu8; 8];
+                let r = decode_into(&s[..], &mut out);
+                if valid {
+                    assert!(r == Ok(8));
+                } else {
+                    assert!(r == Err(HexError::BadCharacter));
+                    assert!(out == [0u8; 8]);
+                }
+                let mut out = [0xAAu8; 8];
+                let r = decode_ct(&s[..], &mut out);
+                if valid {
+                    assert!(r == 0xFFFFFFFF);
+                    assert!(out[..] == hex::decode(&s[..]).unwrap()[..]);
+                } else {
+                    assert!(r == 0);
+                    assert!(out == [0u8; 8]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn kat_migration() {
+        // Decoding of the strings used in known-answer tests matches the
+        // output of the `hex` crate, so tests can use either.
+        const KAT: [&str; 4] = [
+            "",
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "E5564300C360AC729086E2CC806E828A84877F1EB8E5D974D873E065224901555FB8821590A33BACC61E39701CF9B46BD25BF5F0595BBE24655141438E7A100B",
+            "000102030405060708090a0b0c0d0e0f10111213141516171819",
+        ];
+        for s in KAT.iter() {
+            let r = hex::decode(s).unwrap();
+            let mut out = [0u8; 64];
+            let n = decode_into(s, &mut out).unwrap();
+            assert!(out[..n] == r[..]);
+        }
+    }
+}
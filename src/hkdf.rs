@@ -0,0 +1,283 @@
+//! HKDF (RFC 5869) over SHA-256 and SHA-512.
+//!
+//! The two steps of HKDF are provided as separate functions:
+//! `hkdf_sha256_extract()` computes the pseudorandom key (PRK) from a salt
+//! and some input keying material, and `hkdf_sha256_expand()` derives an
+//! arbitrary amount of output keying material from the PRK and some
+//! context information (up to 255 times the hash output length; larger
+//! outputs are reported as an error). The `hkdf_sha512_*` functions are
+//! the same over SHA-512.
+//!
+//! The `HkdfSha256` and `HkdfSha512` types hold a PRK, for protocols that
+//! derive several keys from the same secret. Their `expand_labeled()`
+//! function uses, as context information, an unambiguous encoding of a
+//! label and a context string, along with the output length (similar to
+//! the `HkdfLabel` structure of TLS 1.3, without any implicit prefix):
+//!
+//! ```text
+//!     info = I2OSP(len(out), 2) || I2OSP(len(label), 1) || label
+//!            || I2OSP(len(context), 1) || context
+//! ```
+//!
+//! Since the output length is part of the input, outputs of distinct
+//! lengths are unrelated to each other (a short output is not a prefix
+//! of a longer one).
+
+use super::hmac::{HmacSha256, HmacSha512};
+
+/// Error type for HKDF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HkdfError {
+    /// The requested output is longer than 255 times the hash output
+    /// length.
+    OutputTooLong,
+    /// The label or context string is longer than 255 bytes.
+    LabelTooLong,
+}
+
+impl core::fmt::Display for HkdfError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HkdfError::OutputTooLong => f.write_str("requested output too long"),
+            HkdfError::LabelTooLong => f.write_str("label or context too long"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HkdfError {}
+
+macro_rules! define_hkdf {
+    ($name: ident, $hmac: ident, $extract: ident, $expand: ident,
+     $expand_parts: ident, $hlen: expr, $doc: expr) => {
+
+        /// HKDF-Extract: compute the PRK from a salt and some input keying
+        /// material. An empty salt is equivalent to a salt consisting of
+        /// `HashLen` zeros.
+        pub fn $extract(salt: &[u8], ikm: &[u8]) -> [u8; $hlen] {
+            $hmac::mac(salt, ikm)
+        }
+
+        // HKDF-Expand, with the info string provided as several parts
+        // (concatenated).
+        fn $expand_parts(prk: &[u8], info: &[&[u8]], okm: &mut [u8])
+            -> Result<(), HkdfError>
+        {
+            if okm.len() > 255 * $hlen {
+                return Err(HkdfError::OutputTooLong);
+            }
+            let hm = $hmac::new(prk);
+            let mut t = [0u8; $hlen];
+            for (i, chunk) in okm.chunks_mut($hlen).enumerate() {
+                let mut h = hm;
+                if i != 0 {
+                    h.update(&t);
+                }
+                for p in info.iter() {
+                    h.update(p);
+                }
+                h.update(&[(i + 1) as u8]);
+                t = h.finalize();
+                chunk.copy_from_slice(&t[..chunk.len()]);
+            }
+            Ok(())
+        }
+
+        /// HKDF-Expand: fill `okm` with output keying material derived
+        /// from the PRK and the context information `info`. The output
+        /// length must not exceed 255 times the hash output length.
+        pub fn $expand(prk: &[u8], info: &[u8], okm: &mut [u8])
+            -> Result<(), HkdfError>
+        {
+            $expand_parts(prk, &[info], okm)
+        }
+
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name {
+            prk: [u8; $hlen],
+        }
+
+        impl $name {
+
+            /// Run HKDF-Extract on the provided salt and input keying
+            /// material.
+            pub fn new(salt: &[u8], ikm: &[u8]) -> Self {
+                Self { prk: $extract(salt, ikm) }
+            }
+
+            /// Use an existing PRK (e.g. obtained from a previous
+            /// extraction).
+            pub fn from_prk(prk: &[u8; $hlen]) -> Self {
+                Self { prk: *prk }
+            }
+
+            /// Get the PRK.
+            pub fn prk(&self) -> [u8; $hlen] {
+                self.prk
+            }
+
+            /// HKDF-Expand with the provided context information.
+            pub fn expand(&self, info: &[u8], okm: &mut [u8])
+                -> Result<(), HkdfError>
+            {
+                $expand_parts(&self.prk, &[info], okm)
+            }
+
+            /// HKDF-Expand with context information built from a label,
+            /// a context string, and the output length (see the module
+            /// documentation). The label and context must have length at
+            /// most 255 bytes each.
+            pub fn expand_labeled(&self, label: &[u8], context: &[u8],
+                out: &mut [u8]) -> Result<(), HkdfError>
+            {
+                if label.len() > 255 || context.len() > 255 {
+                    return Err(HkdfError::LabelTooLong);
+                }
+                if out.len() > 255 * $hlen {
+                    return Err(HkdfError::OutputTooLong);
+                }
+                let len = (out.len() as u16).to_be_bytes();
+                $expand_parts(&self.prk,
+                    &[&len, &[label.len() as u8], label,
+                      &[context.len() as u8], context], out)
+            }
+        }
+    }
+}
+
+define_hkdf!(HkdfSha256, HmacSha256, hkdf_sha256_extract, hkdf_sha256_expand,
+    expand_parts_sha256, 32, "HKDF/SHA-256 context (holding a PRK).");
+define_hkdf!(HkdfSha512, HmacSha512, hkdf_sha512_extract, hkdf_sha512_expand,
+    expand_parts_sha512, 64, "HKDF/SHA-512 context (holding a PRK).");
+
+#[cfg(test)]
+mod tests {
+
+    use super::{HkdfError, HkdfSha256, HkdfSha512};
+    use super::{hkdf_sha256_extract, hkdf_sha256_expand};
+    use super::{hkdf_sha512_extract, hkdf_sha512_expand};
+
+    // (salt, IKM, info, PRK/SHA-256, OKM/SHA-256, PRK/SHA-512, OKM/SHA-512)
+    // The SHA-256 values are test cases 1 to 3 from RFC 5869 (the other
+    // test cases of the RFC use SHA-1). The SHA-512 values use the same
+    // inputs.
+    const KAT: [(&str, &str, &str, &str, &str, &str, &str); 3] = [
+        (
+            "000102030405060708090a0b0c",
+            "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+            "f0f1f2f3f4f5f6f7f8f9",
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+            "665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237",
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb",
+        ),
+        (
+            "606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9fa0a1a2a3a4a5a6a7a8a9aaabacadaeaf",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f404142434445464748494a4b4c4d4e4f",
+            "b0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+            "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244",
+            "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87",
+            "35672542907d4e142c00e84499e74e1de08be86535f924e022804ad775dde27ec86cd1e5b7d178c74489bdbeb30712beb82d4f97416c5a94ea81ebdf3e629e4a",
+            "ce6c97192805b346e6161e821ed165673b84f400a2b514b2fe23d84cd189ddf1b695b48cbd1c8388441137b3ce28f16aa64ba33ba466b24df6cfcb021ecff235f6a2056ce3af1de44d572097a8505d9e7a93",
+        ),
+        (
+            "",
+            "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+            "",
+            "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04",
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8",
+            "fd200c4987ac491313bd4a2a13287121247239e11c9ef82802044b66ef357e5b194498d0682611382348572a7b1611de54764094286320578a863f36562b0df6",
+            "f5fa02b18298a72a8c23898a8703472c6eb179dc204c03425c970e3b164bf90fff22d04836d0e2343bac",
+        ),
+    ];
+
+    #[test]
+    fn rfc5869() {
+        for kat in KAT.iter() {
+            let salt = hex::decode(kat.0).unwrap();
+            let ikm = hex::decode(kat.1).unwrap();
+            let info = hex::decode(kat.2).unwrap();
+
+            let prk = hkdf_sha256_extract(&salt, &ikm);
+            assert!(prk[..] == hex::decode(kat.3).unwrap()[..]);
+            let r = hex::decode(kat.4).unwrap();
+            let mut okm = [0u8; 82];
+            let okm = &mut okm[..r.len()];
+            hkdf_sha256_expand(&prk, &info, okm).unwrap();
+            assert!(okm[..] == r[..]);
+            okm.fill(0);
+            HkdfSha256::new(&salt, &ikm).expand(&info, okm).unwrap();
+            assert!(okm[..] == r[..]);
+
+            let prk = hkdf_sha512_extract(&salt, &ikm);
+            assert!(prk[..] == hex::decode(kat.5).unwrap()[..]);
+            let r = hex::decode(kat.6).unwrap();
+            let mut okm = [0u8; 82];
+            let okm = &mut okm[..r.len()];
+            hkdf_sha512_expand(&prk, &info, okm).unwrap();
+            assert!(okm[..] == r[..]);
+            okm.fill(0);
+            HkdfSha512::from_prk(&prk).expand(&info, okm).unwrap();
+            assert!(okm[..] == r[..]);
+        }
+    }
+
+    #[test]
+    fn output_limit() {
+        let prk = hkdf_sha256_extract(b"salt", b"ikm");
+        let mut okm = [0u8; 255 * 64 + 1];
+        assert!(hkdf_sha256_expand(&prk, b"", &mut okm[..(255 * 32)]).is_ok());
+        assert!(hkdf_sha256_expand(&prk, b"", &mut okm[..(255 * 32 + 1)])
+            == Err(HkdfError::OutputTooLong));
+        let prk = hkdf_sha512_extract(b"salt", b"ikm");
+        assert!(hkdf_sha512_expand(&prk, b"", &mut okm[..(255 * 64)]).is_ok());
+        assert!(hkdf_sha512_expand(&prk, b"", &mut okm[..])
+            == Err(HkdfError::OutputTooLong));
+        let hk = HkdfSha256::new(b"salt", b"ikm");
+        assert!(hk.expand_labeled(b"key", b"", &mut okm[..(255 * 32 + 1)])
+            == Err(HkdfError::OutputTooLong));
+        assert!(hk.expand_labeled(&[0u8; 256], b"", &mut okm[..32])
+            == Err(HkdfError::LabelTooLong));
+        assert!(hk.expand_labeled(b"key", &[0u8; 256], &mut okm[..32])
+            == Err(HkdfError::LabelTooLong));
+    }
+
+    #[test]
+    fn expand_labeled() {
+        let hk = HkdfSha256::new(b"salt", b"input keying material");
+
+        // Plain expand: the output does not depend on how many bytes are
+        // requested (a shorter output is a prefix of a longer one).
+        let mut o1 = [0u8; 100];
+        let mut o2 = [0u8; 50];
+        hk.expand(b"info", &mut o1).unwrap();
+        hk.expand(b"info", &mut o2).unwrap();
+        assert!(o1[..50] == o2[..]);
+
+        // Labeled expand: the output matches the explicit encoding, and
+        // depends on the output length.
+        let mut o3 = [0u8; 50];
+        hk.expand_labeled(b"key", b"ctx", &mut o3).unwrap();
+        let mut o4 = [0u8; 50];
+        hk.expand(b"\x00\x32\x03key\x03ctx", &mut o4).unwrap();
+        assert!(o3 == o4);
+        let mut o5 = [0u8; 100];
+        hk.expand_labeled(b"key", b"ctx", &mut o5).unwrap();
+        assert!(o5[..50] != o3[..]);
+
+        // Labels are not concatenative: moving bytes between the label and
+        // the context, or splitting the output over two labels, yields
+        // different outputs.
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        hk.expand_labeled(b"ab", b"c", &mut a).unwrap();
+        hk.expand_labeled(b"a", b"bc", &mut b).unwrap();
+        assert!(a != b);
+        let mut c = [0u8; 64];
+        hk.expand_labeled(b"key1key2", b"", &mut c).unwrap();
+        hk.expand_labeled(b"key1", b"", &mut a).unwrap();
+        hk.expand_labeled(b"key2", b"", &mut b).unwrap();
+        assert!(c[..32] != a[..] && c[32..] != b[..]);
+    }
+}
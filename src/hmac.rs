@@ -0,0 +1,211 @@
+//! HMAC (RFC 2104) over SHA-256 and SHA-512.
+//!
+//! `HmacSha256` and `HmacSha512` are HMAC contexts with a fixed-size
+//! output (32 and 64 bytes, respectively). The key is provided when the
+//! context is created; as per RFC 2104, keys longer than the hash
+//! function block size are first hashed, and shorter keys are padded
+//! with zeros. The API follows that of the hash functions in the `sha2`
+//! module: `update()`, `finalize()` (which does not modify the context),
+//! `finalize_reset()` and `reset()` (the context remains keyed with the
+//! same key), and a one-stop function `mac()`.
+//!
+//! The `verify()` function compares the computed MAC value with a
+//! received tag in constant time. Only full-length tags are accepted;
+//! a truncated tag is always rejected.
+
+use super::sha2::{Sha256, Sha512};
+
+macro_rules! define_hmac {
+    ($name: ident, $hash: ident, $out_len: expr, $doc: expr) => {
+
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name {
+            // Inner hash, after injection of the key block (saved for
+            // resets).
+            inner_start: $hash,
+            // Outer hash, after injection of the key block.
+            outer_start: $hash,
+            // Current inner hash.
+            inner: $hash,
+        }
+
+        impl $name {
+
+            /// Output length (in bytes).
+            pub const OUTPUT_LEN: usize = $out_len;
+
+            /// Initialize a new context with the provided key (of
+            /// arbitrary length).
+            pub fn new(key: &[u8]) -> Self {
+                let mut kb = [0u8; $hash::BLOCK_LEN];
+                if key.len() > $hash::BLOCK_LEN {
+                    kb[..$out_len].copy_from_slice(&$hash::hash(key));
+                } else {
+                    kb[..key.len()].copy_from_slice(key);
+                }
+                for b in kb.iter_mut() {
+                    *b ^= 0x36;
+                }
+                let mut inner_start = $hash::new();
+                inner_start.update(&kb);
+                for b in kb.iter_mut() {
+                    *b ^= 0x36 ^ 0x5C;
+                }
+                let mut outer_start = $hash::new();
+                outer_start.update(&kb);
+                Self { inner_start, outer_start, inner: inner_start }
+            }
+
+            /// Reset this context, for a new MAC computation with the
+            /// same key.
+            pub fn reset(&mut self) {
+                self.inner = self.inner_start;
+            }
+
+            /// Inject some more bytes into the context.
+            pub fn update(&mut self, data: &[u8]) {
+                self.inner.update(data);
+            }
+
+            /// Finalize the current computation and get the output. The
+            /// context is not modified, so more data may be injected
+            /// afterwards (the output then covers all data injected since
+            /// the last reset).
+            pub fn finalize(&self) -> [u8; $out_len] {
+                let mut sh = self.outer_start;
+                sh.update(&self.inner.finalize());
+                sh.finalize()
+            }
+
+            /// Finalize the current computation and get the output. The
+            /// context is automatically reset (with the same key), so
+            /// that it can be used again for a new computation.
+            pub fn finalize_reset(&mut self) -> [u8; $out_len] {
+                let r = self.finalize();
+                self.reset();
+                r
+            }
+
+            /// Finalize the current computation and compare the output
+            /// with the provided tag. The comparison is constant-time
+            /// (the tag length is considered public); tags whose length
+            /// is not exactly `OUTPUT_LEN` bytes are rejected. The
+            /// context is not modified.
+            pub fn verify(&self, tag: &[u8]) -> bool {
+                crate::ct::equals(&self.finalize(), tag)
+            }
+
+            /// One-stop function for computing the MAC over some data.
+            pub fn mac(key: &[u8], data: &[u8]) -> [u8; $out_len] {
+                let mut hm = Self::new(key);
+                hm.update(data);
+                hm.finalize()
+            }
+        }
+    }
+}
+
+define_hmac!(HmacSha256, Sha256, 32, "HMAC/SHA-256 context.");
+define_hmac!(HmacSha512, Sha512, 64, "HMAC/SHA-512 context.");
+
+#[cfg(test)]
+mod tests {
+
+    use super::{HmacSha256, HmacSha512};
+
+    // Test vectors from RFC 4231 (test case 5 uses truncated outputs).
+    const KAT: [(&str, &str, &str, &str); 7] = [
+        ("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+         "4869205468657265",
+         "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+         "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"),
+        ("4a656665",
+         "7768617420646f2079612077616e7420666f72206e6f7468696e673f",
+         "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+         "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"),
+        ("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+         "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd",
+         "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+         "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb"),
+        ("0102030405060708090a0b0c0d0e0f10111213141516171819",
+         "cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd",
+         "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+         "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd"),
+        ("0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c",
+         "546573742057697468205472756e636174696f6e",
+         "a3b6167473100ee06e0c796c2955552b",
+         "415fad6271580a531d4179bc891d87a6"),
+        ("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+         "54657374205573696e67204c6172676572205468616e20426c6f636b2d53697a65204b6579202d2048617368204b6579204669727374",
+         "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+         "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"),
+        ("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+         "5468697320697320612074657374207573696e672061206c6172676572207468616e20626c6f636b2d73697a65206b657920616e642061206c6172676572207468616e20626c6f636b2d73697a6520646174612e20546865206b6579206e6565647320746f20626520686173686564206265666f7265206265696e6720757365642062792074686520484d414320616c676f726974686d2e",
+         "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+         "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58"),
+    ];
+
+    #[test]
+    fn rfc4231() {
+        for (kh, dh, r256, r512) in KAT.iter() {
+            let key = hex::decode(kh).unwrap();
+            let data = hex::decode(dh).unwrap();
+            let r256 = hex::decode(r256).unwrap();
+            let r512 = hex::decode(r512).unwrap();
+
+            let out = HmacSha256::mac(&key, &data);
+            assert!(out[..r256.len()] == r256[..]);
+            let mut hm = HmacSha256::new(&key);
+            for b in data.iter() {
+                hm.update(&[*b]);
+            }
+            assert!(hm.finalize() == out);
+            assert!(hm.finalize_reset() == out);
+            hm.update(&data);
+            assert!(hm.finalize() == out);
+            assert!(hm.verify(&out));
+
+            let out = HmacSha512::mac(&key, &data);
+            assert!(out[..r512.len()] == r512[..]);
+            let mut hm = HmacSha512::new(&key);
+            for b in data.iter() {
+                hm.update(&[*b]);
+            }
+            assert!(hm.finalize() == out);
+            assert!(hm.finalize_reset() == out);
+            hm.update(&data);
+            assert!(hm.finalize() == out);
+            assert!(hm.verify(&out));
+        }
+    }
+
+    #[test]
+    fn verify_rejects() {
+        let mut hm = HmacSha256::new(b"key");
+        hm.update(b"The quick brown fox jumps over the lazy dog");
+        let tag = hm.finalize();
+        assert!(hm.verify(&tag));
+
+        // Truncated (or extended) tags are rejected, even if they match
+        // the MAC output prefix.
+        for n in 0..tag.len() {
+            assert!(!hm.verify(&tag[..n]));
+        }
+        let mut ext = [0u8; 33];
+        ext[..32].copy_from_slice(&tag);
+        assert!(!hm.verify(&ext));
+
+        // Any modified bit leads to rejection.
+        for i in 0..tag.len() {
+            let mut t2 = tag;
+            t2[i] ^= 0x01;
+            assert!(!hm.verify(&t2));
+        }
+
+        let hm = HmacSha512::new(b"key");
+        let tag = hm.finalize();
+        assert!(hm.verify(&tag));
+        assert!(!hm.verify(&tag[..32]));
+    }
+}
@@ -0,0 +1,228 @@
+//! HMAC_DRBG with SHA-256 (NIST SP 800-90A).
+//!
+//! `HmacDrbgSha256` is a deterministic random bit generator: its output
+//! is entirely determined by the entropy input, nonce and personalization
+//! string provided at instantiation, and the subsequent reseed and
+//! additional inputs. It is meant for seed expansion and for reproducible
+//! test vectors (e.g. to drive key generation functions of this crate
+//! deterministically); the caller is responsible for providing inputs
+//! with enough entropy when it is used for actual secrets.
+//!
+//! The SP 800-90A limits are enforced: a single `generate()` call may
+//! not produce more than 65536 bytes, and at most 2^48 requests may be
+//! served between two reseeds. Violations are reported as errors.
+//!
+//! The type also implements the `RngCore` and `CryptoRng` traits;
+//! `fill_bytes()` splits large requests into chunks of at most 65536
+//! bytes (without additional input), and panics if a reseed is required.
+//! Prediction resistance is not supported (it would need an entropy
+//! source).
+
+use super::hmac::HmacSha256;
+use crate::{CryptoRng, RngCore, RngError};
+
+/// Error type for HMAC_DRBG.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrbgError {
+    /// The requested output is longer than 65536 bytes.
+    RequestTooLong,
+    /// The reseed interval has been reached; `reseed()` must be called.
+    ReseedRequired,
+}
+
+impl core::fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DrbgError::RequestTooLong => f.write_str("requested output too long"),
+            DrbgError::ReseedRequired => f.write_str("reseed required"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DrbgError {}
+
+/// HMAC_DRBG context (with SHA-256).
+#[derive(Clone, Debug)]
+pub struct HmacDrbgSha256 {
+    k: [u8; 32],
+    v: [u8; 32],
+    reseed_counter: u64,
+}
+
+impl HmacDrbgSha256 {
+
+    /// Maximum number of bytes produced by a single `generate()` call.
+    pub const MAX_REQUEST_LEN: usize = 1 << 16;
+
+    /// Maximum number of `generate()` calls between two reseeds.
+    pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+    /// Instantiate the DRBG with the provided entropy input, nonce and
+    /// personalization string (the latter may be empty).
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8])
+        -> Self
+    {
+        let mut d = Self {
+            k: [0x00u8; 32],
+            v: [0x01u8; 32],
+            reseed_counter: 1,
+        };
+        d.update(&[entropy, nonce, personalization]);
+        d
+    }
+
+    /// Reseed the DRBG with some new entropy input and (optional,
+    /// possibly empty) additional input.
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) {
+        self.update(&[entropy, additional]);
+        self.reseed_counter = 1;
+    }
+
+    /// Fill `out` with pseudorandom bytes, with some optional additional
+    /// input. At most `MAX_REQUEST_LEN` bytes may be requested at once.
+    pub fn generate(&mut self, out: &mut [u8], additional: Option<&[u8]>)
+        -> Result<(), DrbgError>
+    {
+        if out.len() > Self::MAX_REQUEST_LEN {
+            return Err(DrbgError::RequestTooLong);
+        }
+        if self.reseed_counter > Self::RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        let additional = additional.unwrap_or(&[]);
+        if !additional.is_empty() {
+            self.update(&[additional]);
+        }
+        for chunk in out.chunks_mut(32) {
+            self.v = HmacSha256::mac(&self.k, &self.v);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        self.update(&[additional]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    // HMAC_DRBG_Update, with the provided data given as several parts
+    // (concatenated).
+    fn update(&mut self, data: &[&[u8]]) {
+        let empty = data.iter().all(|d| d.is_empty());
+        for b in [0x00u8, 0x01u8] {
+            if b == 0x01 && empty {
+                break;
+            }
+            let mut hm = HmacSha256::new(&self.k);
+            hm.update(&self.v);
+            hm.update(&[b]);
+            for d in data.iter() {
+                hm.update(d);
+            }
+            self.k = hm.finalize();
+            self.v = HmacSha256::mac(&self.k, &self.v);
+        }
+    }
+}
+
+impl RngCore for HmacDrbgSha256 {
+
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(Self::MAX_REQUEST_LEN) {
+            self.generate(chunk, None).unwrap();
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RngError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for HmacDrbgSha256 { }
+
+#[cfg(test)]
+mod tests {
+
+    use super::{DrbgError, HmacDrbgSha256};
+    use crate::RngCore;
+
+    #[test]
+    fn cavp_no_reseed() {
+        // NIST CAVP HMAC_DRBG test vectors (no reseed, SHA-256, no
+        // prediction resistance), COUNT = 0: the DRBG is instantiated,
+        // then generate() is called twice; the second output is the
+        // expected value.
+        let entropy = hex::decode("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488").unwrap();
+        let nonce = hex::decode("659ba96c601dc69fc902940805ec0ca8").unwrap();
+        let r = hex::decode("e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8").unwrap();
+        let mut d = HmacDrbgSha256::new(&entropy, &nonce, b"");
+        let mut out = [0u8; 128];
+        d.generate(&mut out, None).unwrap();
+        d.generate(&mut out, None).unwrap();
+        assert!(out[..] == r[..]);
+
+        // Empty additional input is the same as no additional input.
+        let mut d = HmacDrbgSha256::new(&entropy, &nonce, b"");
+        d.generate(&mut out, Some(b"")).unwrap();
+        d.generate(&mut out, Some(b"")).unwrap();
+        assert!(out[..] == r[..]);
+    }
+
+    #[test]
+    fn reseed_additional() {
+        // Personalization, additional inputs and reseed (values computed
+        // with an independent implementation).
+        let mut d = HmacDrbgSha256::new(&[0x11u8; 32], &[0x22u8; 16],
+            b"personalization string");
+        let mut out = [0u8; 64];
+        d.generate(&mut out, Some(b"additional 1")).unwrap();
+        assert!(out[..] == hex::decode("d83ff614129845923bea95aff5918766f411e5f2efb509bcc14b54b0ce8e01e9c2ba2ffc8461c12d2fd44a6eed157f6c81383930a0e546de1c19ee264df5dc72").unwrap()[..]);
+        d.reseed(&[0x33u8; 32], b"reseed input");
+        d.generate(&mut out, None).unwrap();
+        assert!(out[..] == hex::decode("9f22b73f60ca85c894c048a64b30716ac38b438a829b14dc6ff05960316296b33daa1560da4cfa0a85b5f7d2ac1a114f6a2dcd0e16c8218cf3b2c42a594c84b8").unwrap()[..]);
+        d.generate(&mut out, Some(b"additional 2")).unwrap();
+        assert!(out[..] == hex::decode("015a96d26ff4eb08b610fda3191af4960b2de18ac57c75fba5d018b2dd2ae19d583aac243f596875d343ed51d7b4f2aad4ab71be61dfdd327de403eed83ac08f").unwrap()[..]);
+
+        // Output length which is not a multiple of 32.
+        let mut d = HmacDrbgSha256::new(&[0x11u8; 32], &[0x22u8; 16], b"");
+        let mut out = [0u8; 100];
+        d.generate(&mut out, None).unwrap();
+        assert!(out[..] == hex::decode("61eaeab613b08832480310fdae811872aabc6ea8f8b517af312078c84981d53d3d461789b6ebf0fec9d70e58c2c1b4f3b3ddf54bb5dd7366a747d02b35aa442e4b1d4b16c351cb1250f6cf8f6434b0c92c94f117ff5ef22222b2a4529ad403b6f4d00dfa").unwrap()[..]);
+    }
+
+    #[test]
+    fn limits() {
+        let mut d = HmacDrbgSha256::new(&[0x11u8; 32], &[0x22u8; 16], b"");
+        let mut out = [0u8; (1 << 16) + 1];
+        assert!(d.generate(&mut out[..(1 << 16)], None).is_ok());
+        assert!(d.generate(&mut out, None)
+            == Err(DrbgError::RequestTooLong));
+
+        d.reseed_counter = HmacDrbgSha256::RESEED_INTERVAL;
+        assert!(d.generate(&mut out[..32], None).is_ok());
+        assert!(d.generate(&mut out[..32], None)
+            == Err(DrbgError::ReseedRequired));
+        d.reseed(&[0x33u8; 32], b"");
+        assert!(d.generate(&mut out[..32], None).is_ok());
+
+        // Through RngCore, large requests are split.
+        let mut d = HmacDrbgSha256::new(&[0x11u8; 32], &[0x22u8; 16], b"");
+        let mut out2 = [0u8; (1 << 16) + 1];
+        d.fill_bytes(&mut out2);
+        let mut d = HmacDrbgSha256::new(&[0x11u8; 32], &[0x22u8; 16], b"");
+        d.generate(&mut out[..(1 << 16)], None).unwrap();
+        d.generate(&mut out[(1 << 16)..], None).unwrap();
+        assert!(out[..] == out2[..]);
+    }
+}
@@ -0,0 +1,465 @@
+//! HPKE (RFC 9180) with DHKEM(X25519, HKDF-SHA256), HKDF-SHA256 and
+//! ChaCha20-Poly1305.
+//!
+//! This module implements the base mode (`mode_base`) of [RFC 9180]
+//! hybrid public key encryption, for the ciphersuite with KEM ID 0x0020,
+//! KDF ID 0x0001 and AEAD ID 0x0003:
+//!
+//!  - The sender calls `setup_base_s()` with the recipient public key
+//!    and some application information string; this returns the
+//!    encapsulated key `enc` (32 bytes, to send to the recipient) and a
+//!    `SenderContext`.
+//!
+//!  - The recipient calls `setup_base_r()` with `enc`, its private key
+//!    and the same information string, and obtains a `RecipientContext`.
+//!
+//! Contexts encrypt (`seal()`) and decrypt (`open()`) successive
+//! messages; each message uses a nonce derived from the base nonce and
+//! a sequence number, so messages must be opened in the order in which
+//! they were sealed. Both contexts can also derive secrets with
+//! `export()`. The single-shot `seal()` and `open()` functions handle
+//! the common case of a single message.
+//!
+//! Private and public keys are X25519 keys (32 bytes each); key pairs
+//! can be random (`generate_key_pair()`) or derived from some input
+//! keying material (`derive_key_pair()`). An all-zero Diffie-Hellman
+//! output (low-order public key) is reported as an error.
+//!
+//! [RFC 9180]: https://www.rfc-editor.org/rfc/rfc9180.html
+
+use crate::chacha20poly1305::ChaCha20Poly1305;
+use crate::hkdf::hkdf_sha256_expand;
+use crate::hmac::HmacSha256;
+use crate::x25519::{x25519_base, x25519_checked, x25519_keygen};
+use crate::{CryptoRng, RngCore, Vec};
+
+/// Error type for HPKE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HpkeError {
+    /// Encapsulation failed (invalid recipient public key).
+    Encap,
+    /// Decapsulation failed (invalid encapsulated key).
+    Decap,
+    /// Decryption failed (invalid ciphertext, AAD or sequence number).
+    Open,
+    /// The sequence number reached its maximum value.
+    MessageLimitReached,
+    /// The requested exported secret is too long.
+    ExportTooLong,
+}
+
+impl core::fmt::Display for HpkeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HpkeError::Encap => f.write_str("encapsulation failed"),
+            HpkeError::Decap => f.write_str("decapsulation failed"),
+            HpkeError::Open => f.write_str("decryption failed"),
+            HpkeError::MessageLimitReached => f.write_str("message limit reached"),
+            HpkeError::ExportTooLong => f.write_str("requested export too long"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HpkeError {}
+
+/// Suite identifier for the KEM: "KEM" || I2OSP(0x0020, 2).
+const SUITE_ID_KEM: &[u8] = b"KEM\x00\x20";
+
+/// Suite identifier for the key schedule:
+/// "HPKE" || I2OSP(0x0020, 2) || I2OSP(0x0001, 2) || I2OSP(0x0003, 2).
+const SUITE_ID: &[u8] = b"HPKE\x00\x20\x00\x01\x00\x03";
+
+/// Mode identifier for the base mode.
+const MODE_BASE: u8 = 0x00;
+
+/// LabeledExtract(salt, label, ikm). An empty salt is equivalent to
+/// HMAC with an all-zero key, as required by HKDF.
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8])
+    -> [u8; 32]
+{
+    let mut hm = HmacSha256::new(salt);
+    hm.update(b"HPKE-v1");
+    hm.update(suite_id);
+    hm.update(label);
+    hm.update(ikm);
+    hm.finalize()
+}
+
+/// LabeledExpand(prk, label, info, L), with L = `out.len()`.
+fn labeled_expand(suite_id: &[u8], prk: &[u8], label: &[u8], info: &[u8],
+    out: &mut [u8]) -> Result<(), HpkeError>
+{
+    if out.len() > 255 * 32 {
+        return Err(HpkeError::ExportTooLong);
+    }
+    let mut li = Vec::with_capacity(9 + suite_id.len() + label.len() + info.len());
+    li.extend_from_slice(&(out.len() as u16).to_be_bytes());
+    li.extend_from_slice(b"HPKE-v1");
+    li.extend_from_slice(suite_id);
+    li.extend_from_slice(label);
+    li.extend_from_slice(info);
+    hkdf_sha256_expand(prk, &li, out).map_err(|_| HpkeError::ExportTooLong)
+}
+
+/// Derives a key pair from some input keying material (`DeriveKeyPair()`
+/// from RFC 9180, section 7.1.3). Returned value is
+/// `(private_key, public_key)`.
+pub fn derive_key_pair(ikm: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let dkp_prk = labeled_extract(SUITE_ID_KEM, &[], b"dkp_prk", ikm);
+    let mut sk = [0u8; 32];
+    labeled_expand(SUITE_ID_KEM, &dkp_prk, b"sk", &[], &mut sk).unwrap();
+    (sk, x25519_base(&sk))
+}
+
+/// Generates a random key pair. Returned value is
+/// `(private_key, public_key)`.
+pub fn generate_key_pair<T: CryptoRng + RngCore>(rng: &mut T)
+    -> ([u8; 32], [u8; 32])
+{
+    x25519_keygen(rng)
+}
+
+/// ExtractAndExpand(dh, kem_context).
+fn extract_and_expand(dh: &[u8; 32], enc: &[u8; 32], pk_r: &[u8; 32])
+    -> [u8; 32]
+{
+    let eae_prk = labeled_extract(SUITE_ID_KEM, &[], b"eae_prk", dh);
+    let mut kem_context = [0u8; 64];
+    kem_context[..32].copy_from_slice(enc);
+    kem_context[32..].copy_from_slice(pk_r);
+    let mut ss = [0u8; 32];
+    labeled_expand(SUITE_ID_KEM, &eae_prk, b"shared_secret",
+        &kem_context, &mut ss).unwrap();
+    ss
+}
+
+/// KeySchedule() for the base mode (empty PSK and PSK ID).
+fn key_schedule(shared_secret: &[u8; 32], info: &[u8]) -> Context {
+    let mut ksc = [0u8; 65];
+    ksc[0] = MODE_BASE;
+    ksc[1..33].copy_from_slice(
+        &labeled_extract(SUITE_ID, &[], b"psk_id_hash", &[]));
+    ksc[33..].copy_from_slice(
+        &labeled_extract(SUITE_ID, &[], b"info_hash", info));
+    let secret = labeled_extract(SUITE_ID, shared_secret, b"secret", &[]);
+    let mut key = [0u8; 32];
+    let mut base_nonce = [0u8; 12];
+    let mut exporter_secret = [0u8; 32];
+    labeled_expand(SUITE_ID, &secret, b"key", &ksc, &mut key).unwrap();
+    labeled_expand(SUITE_ID, &secret, b"base_nonce", &ksc,
+        &mut base_nonce).unwrap();
+    labeled_expand(SUITE_ID, &secret, b"exp", &ksc,
+        &mut exporter_secret).unwrap();
+    Context {
+        aead: ChaCha20Poly1305::new(&key),
+        base_nonce,
+        seq: 0,
+        exporter_secret,
+    }
+}
+
+/// Sets up a sender context with the provided recipient public key
+/// `pk_r` and information string `info`. Returned values are the
+/// encapsulated key (to send to the recipient) and the context.
+pub fn setup_base_s<T: CryptoRng + RngCore>(rng: &mut T,
+    pk_r: &[u8; 32], info: &[u8])
+    -> Result<([u8; 32], SenderContext), HpkeError>
+{
+    let (sk_e, _) = generate_key_pair(rng);
+    setup_base_s_with_ephemeral(&sk_e, pk_r, info)
+}
+
+// Sender setup with an explicit ephemeral private key.
+fn setup_base_s_with_ephemeral(sk_e: &[u8; 32], pk_r: &[u8; 32],
+    info: &[u8]) -> Result<([u8; 32], SenderContext), HpkeError>
+{
+    let dh = x25519_checked(pk_r, sk_e).ok_or(HpkeError::Encap)?;
+    let enc = x25519_base(sk_e);
+    let ss = extract_and_expand(&dh, &enc, pk_r);
+    Ok((enc, SenderContext(key_schedule(&ss, info))))
+}
+
+/// Sets up a recipient context with the received encapsulated key
+/// `enc`, the recipient private key `sk_r` and the information string
+/// `info` (which must be the same as the one used by the sender).
+pub fn setup_base_r(enc: &[u8; 32], sk_r: &[u8; 32], info: &[u8])
+    -> Result<RecipientContext, HpkeError>
+{
+    let dh = x25519_checked(enc, sk_r).ok_or(HpkeError::Decap)?;
+    let pk_r = x25519_base(sk_r);
+    let ss = extract_and_expand(&dh, enc, &pk_r);
+    Ok(RecipientContext(key_schedule(&ss, info)))
+}
+
+/// Single-shot encryption: sets up a sender context, and encrypts one
+/// message. Returned values are the encapsulated key and the ciphertext
+/// (which includes the 16-byte tag).
+pub fn seal<T: CryptoRng + RngCore>(rng: &mut T, pk_r: &[u8; 32],
+    info: &[u8], aad: &[u8], pt: &[u8])
+    -> Result<([u8; 32], Vec<u8>), HpkeError>
+{
+    let (enc, mut ctx) = setup_base_s(rng, pk_r, info)?;
+    let ct = ctx.seal(aad, pt)?;
+    Ok((enc, ct))
+}
+
+/// Single-shot decryption: sets up a recipient context, and decrypts
+/// one message.
+pub fn open(enc: &[u8; 32], sk_r: &[u8; 32], info: &[u8], aad: &[u8],
+    ct: &[u8]) -> Result<Vec<u8>, HpkeError>
+{
+    setup_base_r(enc, sk_r, info)?.open(aad, ct)
+}
+
+// Encryption context, shared by senders and recipients.
+struct Context {
+    aead: ChaCha20Poly1305,
+    base_nonce: [u8; 12],
+    seq: u64,
+    exporter_secret: [u8; 32],
+}
+
+impl Context {
+
+    // Nonce for the current sequence number; the sequence number is not
+    // modified.
+    fn compute_nonce(&self) -> Result<[u8; 12], HpkeError> {
+        // The RFC limit is 2^96 - 1 messages; with a 64-bit counter,
+        // we stop one message earlier than 2^64.
+        if self.seq == u64::MAX {
+            return Err(HpkeError::MessageLimitReached);
+        }
+        let mut nonce = self.base_nonce;
+        for (n, s) in nonce[4..].iter_mut().zip(self.seq.to_be_bytes().iter()) {
+            *n ^= *s;
+        }
+        Ok(nonce)
+    }
+
+    fn seal(&mut self, aad: &[u8], pt: &[u8]) -> Result<Vec<u8>, HpkeError> {
+        let nonce = self.compute_nonce()?;
+        let mut ct = Vec::with_capacity(pt.len() + 16);
+        ct.extend_from_slice(pt);
+        let tag = self.aead.encrypt(&nonce, aad, &mut ct);
+        ct.extend_from_slice(&tag);
+        self.seq += 1;
+        Ok(ct)
+    }
+
+    fn open(&mut self, aad: &[u8], ct: &[u8]) -> Result<Vec<u8>, HpkeError> {
+        let nonce = self.compute_nonce()?;
+        if ct.len() < 16 {
+            return Err(HpkeError::Open);
+        }
+        let (ct, tag) = ct.split_at(ct.len() - 16);
+        let mut pt = ct.to_vec();
+        if !self.aead.decrypt(&nonce, aad, &mut pt, tag) {
+            return Err(HpkeError::Open);
+        }
+        self.seq += 1;
+        Ok(pt)
+    }
+
+    fn export(&self, exporter_context: &[u8], len: usize)
+        -> Result<Vec<u8>, HpkeError>
+    {
+        let mut out = vec![0u8; len];
+        labeled_expand(SUITE_ID, &self.exporter_secret, b"sec",
+            exporter_context, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// HPKE sender context.
+pub struct SenderContext(Context);
+
+/// HPKE recipient context.
+pub struct RecipientContext(Context);
+
+impl SenderContext {
+
+    /// Encrypts a message with the provided additional authenticated
+    /// data. The returned ciphertext includes the 16-byte tag. The
+    /// sequence number is incremented.
+    pub fn seal(&mut self, aad: &[u8], pt: &[u8])
+        -> Result<Vec<u8>, HpkeError>
+    {
+        self.0.seal(aad, pt)
+    }
+
+    /// Derives a secret of `len` bytes from the exporter secret and the
+    /// provided exporter context. The length may not exceed 8160 bytes.
+    pub fn export(&self, exporter_context: &[u8], len: usize)
+        -> Result<Vec<u8>, HpkeError>
+    {
+        self.0.export(exporter_context, len)
+    }
+}
+
+impl RecipientContext {
+
+    /// Decrypts a message with the provided additional authenticated
+    /// data. Messages must be opened in the order they were sealed; on
+    /// success, the sequence number is incremented, while on failure it
+    /// is left unchanged.
+    pub fn open(&mut self, aad: &[u8], ct: &[u8])
+        -> Result<Vec<u8>, HpkeError>
+    {
+        self.0.open(aad, ct)
+    }
+
+    /// Derives a secret of `len` bytes from the exporter secret and the
+    /// provided exporter context. The length may not exceed 8160 bytes.
+    pub fn export(&self, exporter_context: &[u8], len: usize)
+        -> Result<Vec<u8>, HpkeError>
+    {
+        self.0.export(exporter_context, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{HpkeError, derive_key_pair, setup_base_r,
+                setup_base_s_with_ephemeral, seal, open};
+    use crate::x25519::x25519_base;
+
+    fn h32(s: &str) -> [u8; 32] {
+        let mut r = [0u8; 32];
+        r.copy_from_slice(&hex::decode(s).unwrap());
+        r
+    }
+
+    // Test vectors from RFC 9180, appendix A.2.1 (base mode,
+    // DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, ChaCha20Poly1305).
+    const INFO: &str = "4f6465206f6e2061204772656369616e2055726e";
+    const IKM_E: &str = "909a9b35d3dc4713a5e72a4da274b55d3d3821a37e5d099e74a647db583a904b";
+    const SK_E: &str = "f4ec9b33b792c372c1d2c2063507b684ef925b8c75a42dbcbf57d63ccd381600";
+    const PK_E: &str = "1afa08d3dec047a643885163f1180476fa7ddb54c6a8029ea33f95796bf2ac4a";
+    const SK_R: &str = "8057991eef8f1f1af18f4a9491d16a1ce333f695d4db8e38da75975c4478e0fb";
+    const PK_R: &str = "4310ee97d88cc1f088a5576c77ab0cf5c3ac797f3d95139c6c84b5429c59662a";
+    const PT: &str = "4265617574792069732074727574682c20747275746820626561757479";
+
+    const ENCRYPTIONS: [(u64, &str); 6] = [
+        (0, "1c5250d8034ec2b784ba2cfd69dbdb8af406cfe3ff938e131f0def8c8b60b4db21993c62ce81883d2dd1b51a28"),
+        (1, "6b53c051e4199c518de79594e1c4ab18b96f081549d45ce015be002090bb119e85285337cc95ba5f59992dc98c"),
+        (2, "71146bd6795ccc9c49ce25dda112a48f202ad220559502cef1f34271e0cb4b02b4f10ecac6f48c32f878fae86b"),
+        (4, "63357a2aa291f5a4e5f27db6baa2af8cf77427c7c1a909e0b37214dd47db122bb153495ff0b02e9e54a50dbe16"),
+        (255, "18ab939d63ddec9f6ac2b60d61d36a7375d2070c9b683861110757062c52b8880a5f6b3936da9cd6c23ef2a95c"),
+        (256, "7a4a13e9ef23978e2c520fd4d2e757514ae160cd0cd05e556ef692370ca53076214c0c40d4c728d6ed9e727a5b"),
+    ];
+
+    const EXPORTS: [(&str, &str); 3] = [
+        ("", "4bbd6243b8bb54cec311fac9df81841b6fd61f56538a775e7c80a9f40160606e"),
+        ("00", "8c1df14732580e5501b00f82b10a1647b40713191b7c1240ac80e2b68808ba69"),
+        ("54657374436f6e74657874", "5acb09211139c43b3090489a9da433e8a30ee7188ba8b0a9a1ccf0c229283e53"),
+    ];
+
+    #[test]
+    fn rfc9180() {
+        let info = hex::decode(INFO).unwrap();
+        let pt = hex::decode(PT).unwrap();
+        let (sk_e, pk_e) = derive_key_pair(&hex::decode(IKM_E).unwrap());
+        assert!(sk_e == h32(SK_E));
+        assert!(pk_e == h32(PK_E));
+        assert!(x25519_base(&h32(SK_R)) == h32(PK_R));
+
+        let (enc, mut sctx) = setup_base_s_with_ephemeral(
+            &sk_e, &h32(PK_R), &info).unwrap();
+        assert!(enc == pk_e);
+        let mut rctx = setup_base_r(&enc, &h32(SK_R), &info).unwrap();
+
+        let mut j = 0;
+        for seq in 0..257u64 {
+            let aad = format!("Count-{}", seq);
+            let ct = sctx.seal(aad.as_bytes(), &pt).unwrap();
+            if ENCRYPTIONS[j].0 == seq {
+                assert!(ct == hex::decode(ENCRYPTIONS[j].1).unwrap());
+                j += 1;
+            }
+            assert!(rctx.open(aad.as_bytes(), &ct).unwrap() == pt);
+        }
+        assert!(j == ENCRYPTIONS.len());
+
+        for (ec, ev) in EXPORTS.iter() {
+            let ec = hex::decode(ec).unwrap();
+            let ev = hex::decode(ev).unwrap();
+            assert!(sctx.export(&ec, 32).unwrap() == ev);
+            assert!(rctx.export(&ec, 32).unwrap() == ev);
+        }
+    }
+
+    #[test]
+    fn export_only() {
+        // Exported secrets do not depend on the sequence number, and
+        // shorter outputs are not prefixes of longer ones.
+        let info = hex::decode(INFO).unwrap();
+        let rctx = setup_base_r(&h32(PK_E), &h32(SK_R), &info).unwrap();
+        let e32 = rctx.export(&[], 32).unwrap();
+        assert!(e32 == hex::decode(EXPORTS[0].1).unwrap());
+        let e64 = rctx.export(&[], 64).unwrap();
+        assert!(e64.len() == 64 && e64[..32] != e32[..]);
+        assert!(rctx.export(&[], 0).unwrap().is_empty());
+        assert!(rctx.export(&[], 255 * 32).unwrap().len() == 255 * 32);
+        assert!(rctx.export(&[], 255 * 32 + 1) == Err(HpkeError::ExportTooLong));
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn open_failures() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x55; 32]);
+        let info = hex::decode(INFO).unwrap();
+        let (enc, mut sctx) = super::setup_base_s(&mut rng,
+            &h32(PK_R), &info).unwrap();
+        let ct0 = sctx.seal(b"aad", b"message 0").unwrap();
+        let ct1 = sctx.seal(b"aad", b"message 1").unwrap();
+        let mut rctx = setup_base_r(&enc, &h32(SK_R), &info).unwrap();
+
+        // Out-of-order, tampered, truncated or mislabeled ciphertexts
+        // are rejected, without advancing the sequence number.
+        assert!(rctx.open(b"aad", &ct1) == Err(HpkeError::Open));
+        let mut bad = ct0.clone();
+        bad[3] ^= 0x01;
+        assert!(rctx.open(b"aad", &bad) == Err(HpkeError::Open));
+        assert!(rctx.open(b"aad", &ct0[..15]) == Err(HpkeError::Open));
+        assert!(rctx.open(b"aae", &ct0) == Err(HpkeError::Open));
+        assert!(rctx.open(b"aad", &ct0).unwrap() == b"message 0");
+        assert!(rctx.open(b"aad", &ct0) == Err(HpkeError::Open));
+        assert!(rctx.open(b"aad", &ct1).unwrap() == b"message 1");
+
+        // Wrong info string or wrong key.
+        let mut rctx = setup_base_r(&enc, &h32(SK_R), b"other").unwrap();
+        assert!(rctx.open(b"aad", &ct0) == Err(HpkeError::Open));
+        let mut rctx = setup_base_r(&enc, &h32(SK_E), &info).unwrap();
+        assert!(rctx.open(b"aad", &ct0) == Err(HpkeError::Open));
+
+        // Low-order public keys are rejected.
+        assert!(super::setup_base_s(&mut rng, &[0u8; 32], &info).err()
+            == Some(HpkeError::Encap));
+        assert!(setup_base_r(&[0u8; 32], &h32(SK_R), &info).err()
+            == Some(HpkeError::Decap));
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn single_shot() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x66; 32]);
+        for len in 0..40 {
+            let (sk_r, pk_r) = super::generate_key_pair(&mut rng);
+            let pt = vec![len as u8; len];
+            let (enc, ct) = seal(&mut rng, &pk_r, b"info", b"aad", &pt)
+                .unwrap();
+            assert!(ct.len() == len + 16);
+            assert!(open(&enc, &sk_r, b"info", b"aad", &ct).unwrap() == pt);
+            assert!(open(&enc, &sk_r, b"info", b"", &ct)
+                == Err(HpkeError::Open));
+        }
+    }
+}
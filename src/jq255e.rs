@@ -47,7 +47,7 @@ use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use core::convert::TryFrom;
 use super::field::{GF255e, ModInt256};
 use super::blake2s::Blake2s256;
-use super::{CryptoRng, RngCore};
+use super::{CryptoRng, RngCore, SignError};
 use super::{Zu128, Zu256, Zu384};
 
 /// An element in the jq255e group.
@@ -449,6 +449,36 @@ impl Point {
         self.U.iszero()
     }
 
+    /// Multiplies this point by the cofactor of the group.
+    ///
+    /// This group has prime order (the cofactor is 1), so the point is
+    /// returned unchanged. This function is provided so that generic
+    /// code can clear cofactors uniformly across all curve modules.
+    #[inline(always)]
+    pub fn mul_by_cofactor(self) -> Self {
+        self
+    }
+
+    /// Multiplies this point by the group order.
+    ///
+    /// Since this group has prime order, the result is always the
+    /// neutral. This function is provided for uniformity with the curve
+    /// modules whose groups have a cofactor.
+    #[inline(always)]
+    pub fn mul_by_order(self) -> Self {
+        Self::NEUTRAL
+    }
+
+    /// Tests whether this point is in the subgroup of prime order.
+    ///
+    /// Since this group has prime order, 0xFFFFFFFF is always returned.
+    /// This function is provided for uniformity with the curve modules
+    /// whose groups have a cofactor.
+    #[inline(always)]
+    pub fn is_in_subgroup(self) -> u32 {
+        0xFFFFFFFF
+    }
+
     /// Conditionally copies the provided point (`P`) into `self`.
     ///
     ///  - If `ctl` = 0xFFFFFFFF, then the value of `P` is copied into `self`.
@@ -723,6 +753,33 @@ impl Point {
         P
     }
 
+    /// Computes the points i*(2^130)*B for odd i = 1 to 15, in affine
+    /// extended coordinates; this replaces the `PRECOMP_B130_ODD` table
+    /// when small tables are used.
+    #[cfg(feature = "small_tables_jq255e")]
+    fn make_precomp_b130_odd() -> [PointAffineExtended; 8] {
+        let P = Self::BASE.xdouble(130);
+        let Q = P.double();
+        let mut tp = [P; 8];
+        for i in 1..8 {
+            tp[i] = tp[i - 1] + Q;
+        }
+        let mut iZ = [GF255e::ZERO; 8];
+        for i in 0..8 {
+            iZ[i] = tp[i].Z;
+        }
+        GF255e::batch_invert(&mut iZ[..]);
+        let mut win = [PointAffineExtended::NEUTRAL; 8];
+        for i in 0..8 {
+            win[i] = PointAffineExtended {
+                e: tp[i].E * iZ[i],
+                u: tp[i].U * iZ[i],
+                t: tp[i].T * iZ[i],
+            };
+        }
+        win
+    }
+
     /// Supports scalar splitting.
     ///
     /// Given a 256-bit integer k (unsigned, provided as 8 32-bit limbs in
@@ -904,34 +961,54 @@ impl Point {
         // for n0, and work over the precomputed tables for B, B30, B65
         // and B95; the four other chunks work over the same tables but
         // with the zeta endomorphism applied.
-        *self = Self::from_affine_extended(
-            &Self::lookup_affine_extended(&PRECOMP_B30, sd0[12]));
-        self.set_add_affine_extended(
-            &Self::lookup_affine_extended(&PRECOMP_B95, sd0[25]));
-        self.set_add_affine_extended(
-            &Self::lookup_affine_extended(&PRECOMP_B30, sd1[12]).zeta());
-        self.set_add_affine_extended(
-            &Self::lookup_affine_extended(&PRECOMP_B95, sd1[25]).zeta());
-
-        // Process the digits in high-to-low order.
-        for i in (0..6).rev() {
-            self.set_xdouble(5);
-            self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B, sd0[i]));
-            self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B30, sd0[i + 6]));
+        #[cfg(not(feature = "small_tables_jq255e"))]
+        {
+            *self = Self::from_affine_extended(
+                &Self::lookup_affine_extended(&PRECOMP_B30, sd0[12]));
             self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B65, sd0[i + 13]));
+                &Self::lookup_affine_extended(&PRECOMP_B95, sd0[25]));
             self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B95, sd0[i + 19]));
+                &Self::lookup_affine_extended(&PRECOMP_B30, sd1[12]).zeta());
             self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B, sd1[i]).zeta());
-            self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B30, sd1[i + 6]).zeta());
-            self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B65, sd1[i + 13]).zeta());
+                &Self::lookup_affine_extended(&PRECOMP_B95, sd1[25]).zeta());
+
+            // Process the digits in high-to-low order.
+            for i in (0..6).rev() {
+                self.set_xdouble(5);
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B, sd0[i]));
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B30, sd0[i + 6]));
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B65, sd0[i + 13]));
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B95, sd0[i + 19]));
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B, sd1[i]).zeta());
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B30, sd1[i + 6]).zeta());
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B65, sd1[i + 13]).zeta());
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B95, sd1[i + 19]).zeta());
+            }
+        }
+
+        // With the small tables, digits are processed with Horner's
+        // rule and a single table (about 4 times as many doublings).
+        #[cfg(feature = "small_tables_jq255e")]
+        {
+            *self = Self::from_affine_extended(
+                &Self::lookup_affine_extended(&PRECOMP_B, sd0[25]));
             self.set_add_affine_extended(
-                &Self::lookup_affine_extended(&PRECOMP_B95, sd1[i + 19]).zeta());
+                &Self::lookup_affine_extended(&PRECOMP_B, sd1[25]).zeta());
+            for i in (0..25).rev() {
+                self.set_xdouble(5);
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B, sd0[i]));
+                self.set_add_affine_extended(
+                    &Self::lookup_affine_extended(&PRECOMP_B, sd1[i]).zeta());
+            }
         }
 
         // If n0 was negative then we need to negate the result.
@@ -1053,6 +1130,12 @@ impl Point {
         let sd1 = Self::recode_u128_NAF(u1);
         let sd2 = Self::recode_scalar_NAF(v);
 
+        // Window for (2^130)*B (recomputed with the small tables).
+        #[cfg(not(feature = "small_tables_jq255e"))]
+        let win_b130 = &PRECOMP_B130_ODD;
+        #[cfg(feature = "small_tables_jq255e")]
+        let win_b130 = &Self::make_precomp_b130_odd();
+
         let mut zz = true;
         let mut ndbl = 0u32;
         for i in (0..130).rev() {
@@ -1115,10 +1198,10 @@ impl Point {
             if e3 != 0 {
                 if e3 > 0 {
                     self.set_add_affine_extended(
-                        &PRECOMP_B130_ODD[(e3 as usize) >> 1]);
+                        &win_b130[(e3 as usize) >> 1]);
                 } else {
                     self.set_sub_affine_extended(
-                        &PRECOMP_B130_ODD[((-e3) as usize) >> 1]);
+                        &win_b130[((-e3) as usize) >> 1]);
                 }
             }
         }
@@ -1168,6 +1251,12 @@ impl Point {
             win[i] = win[i - 1] + Q;
         }
 
+        // Window for (2^130)*B (recomputed with the small tables).
+        #[cfg(not(feature = "small_tables_jq255e"))]
+        let win_b130 = &PRECOMP_B130_ODD;
+        #[cfg(feature = "small_tables_jq255e")]
+        let win_b130 = &Self::make_precomp_b130_odd();
+
         let mut zz = true;
         let mut ndbl = 0u32;
         for i in (0..130).rev() {
@@ -1222,10 +1311,10 @@ impl Point {
             if e3 != 0 {
                 if e3 > 0 {
                     self.set_add_affine_extended(
-                        &PRECOMP_B130_ODD[(e3 as usize) >> 1]);
+                        &win_b130[(e3 as usize) >> 1]);
                 } else {
                     self.set_sub_affine_extended(
-                        &PRECOMP_B130_ODD[((-e3) as usize) >> 1]);
+                        &win_b130[((-e3) as usize) >> 1]);
                 }
             }
         }
@@ -1628,6 +1717,38 @@ impl PrivateKey {
         self.sign_seeded(&[0u8; 0], hash_name, data)
     }
 
+    /// Signs a message with this private key, and verifies the
+    /// signature before returning it.
+    ///
+    /// The signature is computed as with `sign()`, then verified
+    /// against the public key. If the verification fails, which may
+    /// happen only if the computation was disrupted (e.g. by a hardware
+    /// glitch, or a deliberate fault injection), then
+    /// `SignError::VerificationFailed` is returned and the faulty
+    /// signature is discarded: since the signature process is
+    /// deterministic, a faulty signature and a correct one for the same
+    /// message may reveal the private key.
+    ///
+    /// The cost of this function is about two and a half times that of
+    /// `sign()`.
+    pub fn sign_checked(self, hash_name: &str, data: &[u8])
+        -> Result<[u8; 48], SignError>
+    {
+        self.check_signature(self.sign(hash_name, data), hash_name, data)
+    }
+
+    // Verify-after-sign: the signature sig is returned only if it is
+    // valid for the public key.
+    fn check_signature(self, sig: [u8; 48], hash_name: &str, data: &[u8])
+        -> Result<[u8; 48], SignError>
+    {
+        if self.public_key.verify(&sig, hash_name, data) {
+            Ok(sig)
+        } else {
+            Err(SignError::VerificationFailed)
+        }
+    }
+
     /// Signs a message with this private key.
     ///
     /// The data to sign is provided as `data`. When using raw data,
@@ -1844,7 +1965,7 @@ impl PublicKey {
         }
         let R = (-self.point).mul128_add_mulgen_vartime(c, &s);
         let cb = make_challenge(&R, &self.encoded, hash_name, data);
-        return cb[..] == sig[0..16];
+        crate::ct::equals(&cb, &sig[0..16])
     }
 }
 
@@ -1854,6 +1975,9 @@ impl PublicKey {
 /// are provided. Use an empty string for `hash_name` if the `data`
 /// is raw (unhashed). This function is used for both signature generation
 /// and signature verification.
+///
+/// This uses plain BLAKE2s, not `Blake2sDomain`, so that the signature
+/// format remains unchanged.
 fn make_challenge(R: &Point, enc_pk: &[u8; 32], hash_name: &str, data: &[u8])
     -> [u8; 16]
 {
@@ -1879,6 +2003,9 @@ fn make_challenge(R: &Point, enc_pk: &[u8; 32], hash_name: &str, data: &[u8])
 // and (2^195)*B, with B being the conventional base point. These are
 // used to speed mulgen() operations up. The points are moreover stored
 // in a three-coordinate format (e, u, u^2).
+// With the `small_tables_jq255e` feature, only the multiples of B are
+// included; mulgen() then uses a single table, and the odd multiples of
+// (2^130)*B are recomputed when needed (in vartime functions).
 
 /// A point in affine extended coordinates (e, u, u^2)
 #[derive(Clone, Copy, Debug)]
@@ -2028,6 +2155,7 @@ static PRECOMP_B: [GF255e; 48] = [
 ];
 
 // Points i*(2^30)*B for i = 1 to 16, affine extended format (e, u, t)
+#[cfg(not(feature = "small_tables_jq255e"))]
 static PRECOMP_B30: [GF255e; 48] = [
     // (2^30)*B * 1
     GF255e::w64be(0x13CA1147905FCBF5, 0x52326246403CA5BF,
@@ -2144,6 +2272,7 @@ static PRECOMP_B30: [GF255e; 48] = [
 ];
 
 // Points i*(2^65)*B for i = 1 to 16, affine extended format (e, u, t)
+#[cfg(not(feature = "small_tables_jq255e"))]
 static PRECOMP_B65: [GF255e; 48] = [
     // (2^65)*B * 1
     GF255e::w64be(0x131F48849CB5AC4A, 0x54C453E54BE22F73,
@@ -2260,6 +2389,7 @@ static PRECOMP_B65: [GF255e; 48] = [
 ];
 
 // Points i*(2^95)*B for i = 1 to 16, affine extended format (e, u, t)
+#[cfg(not(feature = "small_tables_jq255e"))]
 static PRECOMP_B95: [GF255e; 48] = [
     // (2^95)*B * 1
     GF255e::w64be(0x064AA342C59C0DA0, 0xFAA44349B6734FEB,
@@ -2376,6 +2506,7 @@ static PRECOMP_B95: [GF255e; 48] = [
 ];
 
 // Points i*(2^130)*B for i = 1 to 15 (odd only), affine extended format
+#[cfg(not(feature = "small_tables_jq255e"))]
 static PRECOMP_B130_ODD: [PointAffineExtended; 8] = [
     // (2^130)*B * 1
     PointAffineExtended {
@@ -3297,6 +3428,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sign_checked() {
+        use crate::SignError;
+
+        for i in 0..KAT_SIGN.len() {
+            let sk = PrivateKey::decode(&hex::decode(KAT_SIGN[i][0]).unwrap()).unwrap();
+            let hv = hex::decode(KAT_SIGN[i][3]).unwrap();
+            let sig = sk.sign(Point::HASHNAME_BLAKE2S, &hv);
+            assert!(sk.sign_checked(Point::HASHNAME_BLAKE2S, &hv) == Ok(sig));
+
+            // A signature corrupted before the check is not returned.
+            let mut bad = sig;
+            bad[i % 48] ^= 0x01;
+            assert!(sk.check_signature(bad, Point::HASHNAME_BLAKE2S, &hv)
+                == Err(SignError::VerificationFailed));
+        }
+    }
+
     static KAT_ECDH: [[&str; 5]; 20] = [
         // Each group of five values is:
         //   private key
@@ -3443,4 +3592,29 @@ mod tests {
             assert!(key2[..] == refkey2);
         }
     }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn generate() {
+        use crate::chacha20::ChaChaRng;
+        use crate::{RngCore, SeedableRng};
+
+        // Keys are deterministic for a given RNG state, and distinct
+        // RNG states yield distinct keys.
+        let mut rng1 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng2 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng3 = ChaChaRng::from_seed([0x22u8; 32]);
+        let sk1 = PrivateKey::generate(&mut rng1);
+        let sk2 = PrivateKey::generate(&mut rng2);
+        let sk3 = PrivateKey::generate(&mut rng3);
+        assert!(sk1.encode() == sk2.encode());
+        assert!(sk1.encode() != sk3.encode());
+        assert!(PrivateKey::generate(&mut rng1).encode() != sk1.encode());
+
+        // The RNG output is used as seed for the deterministic
+        // derivation.
+        let mut seed = [0u8; 32];
+        ChaChaRng::from_seed([0x11u8; 32]).fill_bytes(&mut seed);
+        assert!(PrivateKey::from_scalar(&Scalar::decode_reduce(&seed)).encode() == sk1.encode());
+    }
 }
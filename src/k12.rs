@@ -0,0 +1,323 @@
+//! KangarooTwelve hash function.
+//!
+//! KangarooTwelve (K12, specified in RFC 9861 under the name KT128) is an
+//! extendable-output function built on the Keccak-p\[1600, 12\]
+//! permutation, i.e. Keccak with half the rounds of SHA-3. Input is
+//! split into chunks of 8 KiB; the first chunk is injected directly into
+//! the final node, while each subsequent chunk is hashed independently
+//! into a 32-byte chaining value (CV), which is then injected into the
+//! final node. Chunk CVs can thus be computed in parallel; this
+//! implementation computes them sequentially, but complete chunks are
+//! processed by a separate function (`chunk_cv()`) that can be farmed out
+//! to several threads.
+//!
+//! A customization string is provided when the context is created; it is
+//! appended to the message (along with its encoded length) at
+//! finalization time. The context keeps a reference to the string, hence
+//! the lifetime parameter on `KangarooTwelve`.
+//!
+//! Output is obtained from a reader (`K12Reader`), as for SHAKE in the
+//! `sha3` module; both types implement the traits of the `xof` module.
+
+use super::sha3::KeccakSponge;
+
+// Chunk length (in bytes).
+const CHUNK_LEN: usize = 8192;
+
+// Rate of the underlying sponge (in bytes), and number of rounds.
+const RATE: usize = 168;
+const ROUNDS: usize = 12;
+
+// Encode an integer with the length_encode() function of K12: the
+// minimal big-endian encoding of x (empty if x = 0), followed by its
+// length in bytes. The encoding is written at the start of out, and
+// its length (between 1 and 9 bytes) is returned.
+fn length_encode(x: u64, out: &mut [u8; 9]) -> usize {
+    let n = 8 - (x.leading_zeros() as usize >> 3);
+    out[..n].copy_from_slice(&x.to_be_bytes()[(8 - n)..]);
+    out[n] = n as u8;
+    n + 1
+}
+
+// Finish a leaf computation and get the chunk CV.
+fn leaf_finish(leaf: &KeccakSponge) -> [u8; 32] {
+    let mut sp = *leaf;
+    sp.pad(0x0B);
+    let mut cv = [0u8; 32];
+    sp.squeeze(&mut cv);
+    cv
+}
+
+// Compute the CV for a leaf chunk. This function is independent of any
+// context, so that several chunks may be processed in parallel.
+fn chunk_cv(chunk: &[u8]) -> [u8; 32] {
+    let mut sp = KeccakSponge::new(RATE, ROUNDS);
+    sp.absorb(chunk);
+    leaf_finish(&sp)
+}
+
+/// KangarooTwelve context.
+#[derive(Clone, Copy, Debug)]
+pub struct KangarooTwelve<'a> {
+    customization: &'a [u8],
+    // Final node.
+    node: KeccakSponge,
+    // Current (incomplete) leaf chunk.
+    leaf: KeccakSponge,
+    // Total number of bytes injected so far.
+    len: u64,
+    // Number of leaf CVs injected into the final node.
+    num_leaves: u64,
+}
+
+impl<'a> KangarooTwelve<'a> {
+
+    /// Initialize a new context, with the provided customization string
+    /// (which may be empty).
+    pub fn new(customization: &'a [u8]) -> Self {
+        Self {
+            customization,
+            node: KeccakSponge::new(RATE, ROUNDS),
+            leaf: KeccakSponge::new(RATE, ROUNDS),
+            len: 0,
+            num_leaves: 0,
+        }
+    }
+
+    /// Reset this context, for a new hashing operation (with the same
+    /// customization string).
+    pub fn reset(&mut self) {
+        self.node.reset();
+        self.leaf.reset();
+        self.len = 0;
+        self.num_leaves = 0;
+    }
+
+    /// Inject some more bytes into the context.
+    pub fn update(&mut self, data: &[u8]) {
+        let mut j = 0;
+        while j < data.len() {
+            let rem = data.len() - j;
+
+            // First chunk goes directly into the final node.
+            if self.len < (CHUNK_LEN as u64) {
+                let n = core::cmp::min(CHUNK_LEN - (self.len as usize), rem);
+                self.node.absorb(&data[j..(j + n)]);
+                self.len += n as u64;
+                j += n;
+                continue;
+            }
+
+            let off = (self.len % (CHUNK_LEN as u64)) as usize;
+            if off == 0 {
+                // We are starting a new leaf chunk; if it is the first
+                // one, then the final node gets the tree mode marker.
+                if self.len == (CHUNK_LEN as u64) {
+                    self.node.absorb(&[0x03, 0, 0, 0, 0, 0, 0, 0]);
+                }
+
+                // Complete chunks in the input are processed directly.
+                let k = rem / CHUNK_LEN;
+                if k > 0 {
+                    let n = k * CHUNK_LEN;
+                    for chunk in data[j..(j + n)].chunks_exact(CHUNK_LEN) {
+                        self.node.absorb(&chunk_cv(chunk));
+                    }
+                    self.num_leaves += k as u64;
+                    self.len += n as u64;
+                    j += n;
+                    continue;
+                }
+            }
+
+            let n = core::cmp::min(CHUNK_LEN - off, rem);
+            self.leaf.absorb(&data[j..(j + n)]);
+            self.len += n as u64;
+            j += n;
+            if off + n == CHUNK_LEN {
+                self.node.absorb(&leaf_finish(&self.leaf));
+                self.leaf.reset();
+                self.num_leaves += 1;
+            }
+        }
+    }
+
+    /// Finalize the current computation and get a reader for the output.
+    /// The context is not modified, so more data may be injected
+    /// afterwards (a new reader then covers all data injected since the
+    /// last reset).
+    pub fn finalize_xof(&self) -> K12Reader {
+        let mut st = *self;
+        let mut tmp = [0u8; 9];
+        let n = length_encode(self.customization.len() as u64, &mut tmp);
+        st.update(self.customization);
+        st.update(&tmp[..n]);
+        if st.len <= (CHUNK_LEN as u64) {
+            st.node.pad(0x07);
+        } else {
+            if (st.len & (CHUNK_LEN as u64 - 1)) != 0 {
+                st.node.absorb(&leaf_finish(&st.leaf));
+                st.num_leaves += 1;
+            }
+            let n = length_encode(st.num_leaves, &mut tmp);
+            st.node.absorb(&tmp[..n]);
+            st.node.absorb(&[0xFF, 0xFF]);
+            st.node.pad(0x06);
+        }
+        K12Reader(st.node)
+    }
+
+    /// One-stop function for hashing some input into an output of the
+    /// provided length (`out` is filled).
+    pub fn hash_into(customization: &[u8], data: &[u8], out: &mut [u8]) {
+        let mut sh = KangarooTwelve::new(customization);
+        sh.update(data);
+        sh.finalize_xof().squeeze(out);
+    }
+}
+
+impl<'a> crate::xof::Xof for KangarooTwelve<'a> {
+    type Reader = K12Reader;
+
+    fn absorb(&mut self, data: &[u8]) {
+        self.update(data);
+    }
+
+    fn finalize_xof(self) -> K12Reader {
+        KangarooTwelve::finalize_xof(&self)
+    }
+}
+
+/// Output reader for KangarooTwelve.
+#[derive(Clone, Copy, Debug)]
+pub struct K12Reader(KeccakSponge);
+
+impl K12Reader {
+
+    /// Get the next bytes of output; the provided slice is filled.
+    /// Successive calls return consecutive chunks of the output stream,
+    /// regardless of how the output is split over calls.
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        self.0.squeeze(out);
+    }
+}
+
+impl crate::xof::XofReader for K12Reader {
+    fn squeeze(&mut self, out: &mut [u8]) {
+        self.0.squeeze(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{KangarooTwelve, length_encode, CHUNK_LEN};
+    use crate::Vec;
+
+    fn ptn(len: usize) -> Vec<u8> {
+        (0..len).map(|j| (j % 251) as u8).collect()
+    }
+
+    #[test]
+    fn encodings() {
+        const KAT: [(u64, &str); 6] = [
+            (0, "00"),
+            (12, "0c01"),
+            (255, "ff01"),
+            (256, "010002"),
+            (65538, "01000203"),
+            (u64::MAX, "ffffffffffffffff08"),
+        ];
+        for (x, r) in KAT.iter() {
+            let mut buf = [0u8; 9];
+            let n = length_encode(*x, &mut buf);
+            assert!(buf[..n] == hex::decode(r).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn kat() {
+        // Test vectors from RFC 9861 (section 5). Without customization
+        // string, the message is ptn(m); with a customization string
+        // ptn(c), the message consists of m bytes 0xFF.
+        let kat: [(usize, usize, &str); 11] = [
+            (0, 0, "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5"),
+            (1, 0, "2bda92450e8b147f8a7cb629e784a058efca7cf7d8218e02d345dfaa65244a1f"),
+            (17, 0, "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888"),
+            (17 * 17, 0, "0c315ebcdedbf61426de7dcf8fb725d1e74675d7f5327a5067f367b108ecb67c"),
+            (17 * 17 * 17, 0, "cb552e2ec77d9910701d578b457ddf772c12e322e4ee7fe417f92c758f0d59d0"),
+            (17 * 17 * 17 * 17, 0, "8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe"),
+            (17 * 17 * 17 * 17 * 17, 0, "844d610933b1b9963cbdeb5ae3b6b05cc7cbd67ceedf883eb678a0a8e0371682"),
+            (0, 1, "fab658db63e94a246188bf7af69a133045f46ee984c56e3c3328caaf1aa1a583"),
+            (1, 41, "d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4"),
+            (3, 41 * 41, "c389e5009ae57120854c2e8c64670ac01358cf4c1baf89447a724234dc7ced74"),
+            (7, 41 * 41 * 41, "75d2f86a2e644566726b4fbcfc5657b9dbcf070c7b0dca06450ab291d7443bcf"),
+        ];
+        for (m, c, r) in kat.iter() {
+            let cust = ptn(*c);
+            let msg = if *c == 0 { ptn(*m) } else { [0xFFu8; 7][..*m].to_vec() };
+            let mut out = [0u8; 32];
+            KangarooTwelve::hash_into(&cust, &msg, &mut out);
+            assert!(out[..] == hex::decode(r).unwrap()[..]);
+        }
+
+        // Empty input, 64-byte and 10032-byte outputs.
+        let mut out = [0u8; 10032];
+        KangarooTwelve::hash_into(b"", b"", &mut out);
+        assert!(out[..64] == hex::decode("1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e54269c056b8c82e48276038b6d292966cc07a3d4645272e31ff38508139eb0a71").unwrap()[..]);
+        assert!(out[10000..] == hex::decode("e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d").unwrap()[..]);
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        // Inputs around the chunk boundaries (reference values computed
+        // with the tiny-keccak crate).
+        let kat: [(usize, &str); 5] = [
+            (8191, "1b577636f723643e990cc7d6a659837436fd6a103626600eb8301cd1dbe553d6"),
+            (8192, "48f256f6772f9edfb6a8b661ec92dc93b95ebd05a08a17b39ae3490870c926c3"),
+            (8193, "bb66fe72eaea5179418d5295ee1344854d8ad7f3fa17efcb467ec152341284cf"),
+            (16384, "82778f7f7234c83352e76837b721fbdbb5270b88010d84fa5ab0b61ec8ce0956"),
+            (16385, "5f8d2b943922b451842b4e82740d02369e2d5f9f33c5123509a53b955fe177b2"),
+        ];
+        for (m, r) in kat.iter() {
+            let mut out = [0u8; 32];
+            KangarooTwelve::hash_into(b"", &ptn(*m), &mut out);
+            assert!(out[..] == hex::decode(r).unwrap()[..]);
+        }
+
+        // Message of exactly one chunk, with a customization string that
+        // pushes the total input into the tree mode.
+        let cust = ptn(41 * 41);
+        let mut out = [0u8; 32];
+        KangarooTwelve::hash_into(&cust, &ptn(8192), &mut out);
+        assert!(out[..] == hex::decode("60cc42ead3c58fd42ea2df7f8d4e3b79f4832b103bfcc6ffe1cb9348a34d77f0").unwrap()[..]);
+    }
+
+    #[test]
+    fn streaming() {
+        // Output does not depend on how the input is split.
+        let data = ptn(4 * CHUNK_LEN + 1000);
+        let cust = ptn(100);
+        for n in [0, 1, 8191, 8192, 8193, 16384, data.len()] {
+            let d = &data[..n];
+            let mut ref_out = [0u8; 64];
+            KangarooTwelve::hash_into(&cust, d, &mut ref_out);
+            for k in [1, 13, 168, 1000, 8192, 8193] {
+                if k == 1 && n > 20000 {
+                    continue;
+                }
+                let mut sh = KangarooTwelve::new(&cust);
+                for c in d.chunks(k) {
+                    sh.update(c);
+                }
+                let mut out = [0u8; 64];
+                sh.finalize_xof().squeeze(&mut out);
+                assert!(out == ref_out);
+                sh.reset();
+                sh.update(d);
+                sh.finalize_xof().squeeze(&mut out);
+                assert!(out == ref_out);
+            }
+        }
+    }
+}
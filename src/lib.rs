@@ -18,12 +18,23 @@
 //! ristretto255 (internally based on edwards25519) is defined in the
 //! `ristretto255` module. NIST curve P-256 (aka "secp256r1" and
 //! "prime256v1") is implemented in the `p256` module (with the ECDSA
-//! signature algorithm). Double-odd curves jq255e and jq255s are
+//! signature algorithm), and NIST curves P-384 and P-521 in the `p384`
+//! and `p521` modules.
+//! Double-odd curves jq255e and jq255s are
 //! implemented by `jq255e` and `jq255s`, respectively (including
 //! signature and key exchange schemes). Secp256k1 is implemented in
 //! `secp256k1`. Edwards448 is in `ed448`, while the specialized X448
 //! function is in `x448`. The prime-order decaf448 group is implemented
-//! in `decaf448`.
+//! in `decaf448`. The `oprf` module implements the RFC 9497 oblivious
+//! pseudorandom function over ristretto255, and `spake2` the SPAKE2
+//! password-authenticated key exchange over edwards25519. The CPace
+//! password-authenticated key exchange (over ristretto255) is in `cpace`.
+//! The `hpke` module implements RFC 9180 hybrid public key encryption
+//! (base mode, with X25519, HKDF-SHA256 and ChaCha20-Poly1305), and
+//! `sealedbox` the libsodium-compatible anonymous sealed boxes. ECIES
+//! public key encryption over P-256 and secp256k1 is in `ecies`. Minisign
+//! keys and signatures (Ed25519) are handled by `minisign`, and PASETO
+//! `v4.public` tokens by `paseto`.
 //!
 //! # Usage
 //!
@@ -116,9 +127,136 @@ pub(crate) use alloc::vec::Vec;
 #[allow(unused_imports)]
 pub(crate) use std::vec::Vec;
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+#[allow(unused_imports)]
+pub(crate) use alloc::string::String;
+
+#[cfg(feature = "std")]
+#[allow(unused_imports)]
+pub(crate) use std::string::String;
+
 /// The `rand_core` types are re-exported so that users of crrl do not
 /// have to worry about using the exact correct version of `rand_core`.
-pub use rand_core::{CryptoRng, RngCore, Error as RngError};
+pub use rand_core::{CryptoRng, RngCore, SeedableRng, Error as RngError};
+
+/// Error type for the detailed point decoding functions
+/// (`decode_verbose()`) of the curve modules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input length does not match any supported encoding.
+    BadLength,
+    /// The first byte (encoding type) has an unsupported value.
+    BadHeader,
+    /// A field element is not in canonical (fully reduced) form.
+    NonCanonicalField,
+    /// The encoding is otherwise non-canonical (e.g. a sign bit which
+    /// is set for a zero coordinate).
+    NonCanonicalEncoding,
+    /// The decoded values do not correspond to a curve point (or group
+    /// element).
+    NotOnCurve,
+    /// The point is not in the subgroup of prime order.
+    NotInSubgroup,
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::BadLength => f.write_str("invalid encoding length"),
+            DecodeError::BadHeader => f.write_str("invalid encoding type"),
+            DecodeError::NonCanonicalField => f.write_str("non-canonical field element"),
+            DecodeError::NonCanonicalEncoding => f.write_str("non-canonical encoding"),
+            DecodeError::NotOnCurve => f.write_str("not a curve point"),
+            DecodeError::NotInSubgroup => f.write_str("point not in prime order subgroup"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Error type for the parsing of hexadecimal strings into scalars, keys
+/// and signatures (`FromStr` implementations of the curve modules).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The string is not a valid hexadecimal encoding.
+    BadHex,
+    /// The decoded value does not have the expected length.
+    BadLength,
+    /// A scalar is not lower than the group order.
+    ScalarOutOfRange,
+    /// A point (or group element) encoding is invalid.
+    BadPoint(DecodeError),
+    /// The decoded point is the neutral, which is not allowed here
+    /// (e.g. for a public key).
+    NeutralPoint,
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseError::BadHex => f.write_str("invalid hexadecimal string"),
+            ParseError::BadLength => f.write_str("invalid value length"),
+            ParseError::ScalarOutOfRange => f.write_str("scalar not lower than the group order"),
+            ParseError::BadPoint(e) => write!(f, "invalid point: {}", e),
+            ParseError::NeutralPoint => f.write_str("unexpected neutral point"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Error type for the signing functions which verify the signature
+/// before returning it (`sign_checked()` and similar functions of the
+/// signature modules).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignError {
+    /// The freshly computed signature is not valid for the public key;
+    /// this indicates a computation fault (e.g. a hardware glitch, or a
+    /// deliberate fault injection), and the signature was discarded.
+    VerificationFailed,
+}
+
+impl core::fmt::Display for SignError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SignError::VerificationFailed => f.write_str("signature verification failed after signing"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignError {}
+
+/// Identifier for the hash function used by the hash-agnostic ECDSA
+/// functions (`sign_with_hash()` and `verify_with_hash()` in the `p256`
+/// and `secp256k1` modules). The same function is used for hashing the
+/// message and for the HMAC of the RFC 6979 nonce derivation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashId {
+    /// SHA-224 (28-byte output).
+    Sha224,
+    /// SHA-256 (32-byte output).
+    Sha256,
+    /// SHA-384 (48-byte output).
+    Sha384,
+    /// SHA-512 (64-byte output).
+    Sha512,
+}
+
+impl HashId {
+
+    /// Returns the output length (in bytes) of the hash function.
+    pub const fn output_len(self) -> usize {
+        match self {
+            HashId::Sha224 => 28,
+            HashId::Sha256 => 32,
+            HashId::Sha384 => 48,
+            HashId::Sha512 => 64,
+        }
+    }
+}
 
 #[allow(unused_macros)]
 macro_rules! static_assert {
@@ -129,6 +267,11 @@ macro_rules! static_assert {
 
 pub mod backend;
 pub mod field;
+pub mod xof;
+pub mod ct;
+pub mod hex;
+pub mod base64;
+pub mod der;
 
 pub use backend::{Zu128, Zu256, Zu384};
 
@@ -150,6 +293,12 @@ pub mod jq255s;
 #[cfg(feature = "p256")]
 pub mod p256;
 
+#[cfg(feature = "p384")]
+pub mod p384;
+
+#[cfg(feature = "p521")]
+pub mod p521;
+
 #[cfg(feature = "secp256k1")]
 pub mod secp256k1;
 
@@ -168,8 +317,74 @@ pub mod decaf448;
 #[cfg(all(feature = "alloc", feature = "frost"))]
 pub mod frost;
 
+#[cfg(feature = "oprf")]
+pub mod oprf;
+
+#[cfg(feature = "spake2")]
+pub mod spake2;
+
+#[cfg(all(feature = "alloc", feature = "cpace"))]
+pub mod cpace;
+
+#[cfg(all(feature = "alloc", feature = "hpke"))]
+pub mod hpke;
+
+#[cfg(all(feature = "alloc", feature = "sealedbox"))]
+pub mod sealedbox;
+
+#[cfg(all(feature = "alloc", feature = "ecies"))]
+pub mod ecies;
+
+#[cfg(all(feature = "alloc", feature = "minisign"))]
+pub mod minisign;
+
+#[cfg(all(feature = "alloc", feature = "paseto"))]
+pub mod paseto;
+
 #[cfg(feature = "lms")]
 pub mod lms;
 
+#[cfg(feature = "blake2b")]
+pub mod blake2b;
+
 #[cfg(feature = "blake2s")]
 pub mod blake2s;
+
+#[cfg(feature = "blake3")]
+pub mod blake3;
+
+#[cfg(feature = "sha2")]
+pub mod sha2;
+
+#[cfg(feature = "sha3")]
+pub mod sha3;
+
+#[cfg(feature = "k12")]
+pub mod k12;
+
+#[cfg(feature = "hmac")]
+pub mod hmac;
+
+#[cfg(feature = "hkdf")]
+pub mod hkdf;
+
+#[cfg(feature = "pbkdf2")]
+pub mod pbkdf2;
+
+#[cfg(feature = "hmac_drbg")]
+pub mod hmac_drbg;
+
+#[cfg(feature = "siphash")]
+pub mod siphash;
+
+#[cfg(feature = "chacha20")]
+pub mod chacha20;
+
+#[cfg(feature = "poly1305")]
+pub mod poly1305;
+
+#[cfg(feature = "chacha20poly1305")]
+pub mod chacha20poly1305;
+
+#[cfg(all(feature = "alloc", feature = "merkle"))]
+pub mod merkle;
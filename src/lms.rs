@@ -139,15 +139,23 @@ macro_rules! define_lms_core { () => {
         }
 
         fn make_ots_pub_y(self, q: u32, x: &[[u8; n]; p]) -> [[u8; n]; p] {
-            let mut y = [[0u8; n]; p];
-            let eq = q.to_be_bytes();
+            // The p chains are independent; they are advanced in lockstep,
+            // so that each step is a batch of p hashes over inputs of the
+            // same length.
+            let mut buf = [[0u8; 23 + n]; p];
             for i in 0..p {
-                let mut tmp = x[i];
-                for j in 0..((1 << w) - 1) {
-                    tmp = Hn(&self.I, &eq, &(i as u16).to_be_bytes(),
-                        &[j as u8], &tmp);
+                buf[i][..16].copy_from_slice(&self.I);
+                buf[i][16..20].copy_from_slice(&q.to_be_bytes());
+                buf[i][20..22].copy_from_slice(&(i as u16).to_be_bytes());
+            }
+            let mut y = *x;
+            for j in 0..((1 << w) - 1) {
+                for i in 0..p {
+                    buf[i][22] = j as u8;
+                    buf[i][23..].copy_from_slice(&y[i]);
                 }
-                y[i] = tmp;
+                let inputs: [&[u8]; p] = core::array::from_fn(|i| &buf[i][..]);
+                Hn_many(&inputs, &mut y);
             }
             y
         }
@@ -270,7 +278,7 @@ macro_rules! define_lms_core { () => {
                         &tmp, &path[(i * m)..((i + 1) * m)]);
                 }
             }
-            tmp == self.T1
+            crate::ct::equals(&tmp, &self.T1)
         }
     }
 
@@ -359,7 +367,7 @@ macro_rules! define_lms_tests { () => {
 /// LMS_SHA256_M32_H5 with LMOTS_SHA256_N32_W8
 pub mod LMS_SHA256_M32_H5_SHA256_N32_W8 {
 
-    use sha2::{Sha256, Digest};
+    use crate::sha2::Sha256;
 
     define_lms_core!{}
 
@@ -395,6 +403,11 @@ pub mod LMS_SHA256_M32_H5_SHA256_N32_W8 {
         r
     }
 
+    // Hn over several inputs, each provided as a single slice.
+    fn Hn_many(inputs: &[&[u8]], outputs: &mut [[u8; n]]) {
+        Sha256::hash_many(inputs, outputs);
+    }
+
     fn Hm(m1: &[u8], m2: &[u8], m3: &[u8], m4: &[u8], m5: &[u8]) -> [u8; m] {
         let mut sh = Sha256::new();
         sh.update(m1);
@@ -510,7 +523,7 @@ pub mod LMS_SHA256_M32_H5_SHA256_N32_W8 {
 /// LMS_SHA256_M24_H5 with LMOTS_SHA256_N24_W8
 pub mod LMS_SHA256_M24_H5_SHA256_N24_W8 {
 
-    use sha2::{Sha256, Digest};
+    use crate::sha2::Sha256;
 
     define_lms_core!{}
 
@@ -546,6 +559,17 @@ pub mod LMS_SHA256_M24_H5_SHA256_N24_W8 {
         r
     }
 
+    // Hn over several inputs, each provided as a single slice.
+    fn Hn_many(inputs: &[&[u8]], outputs: &mut [[u8; n]]) {
+        let mut tmp = [[0u8; 32]; 8];
+        for (ins, outs) in inputs.chunks(8).zip(outputs.chunks_mut(8)) {
+            Sha256::hash_many(ins, &mut tmp[..ins.len()]);
+            for (o, t) in outs.iter_mut().zip(tmp.iter()) {
+                o.copy_from_slice(&t[..n]);
+            }
+        }
+    }
+
     fn Hm(m1: &[u8], m2: &[u8], m3: &[u8], m4: &[u8], m5: &[u8]) -> [u8; m] {
         let mut sh = Sha256::new();
         sh.update(m1);
@@ -681,6 +705,13 @@ pub mod LMS_SHAKE_M24_H5_SHAKE_N24_W8 {
         r
     }
 
+    // Hn over several inputs, each provided as a single slice.
+    fn Hn_many(inputs: &[&[u8]], outputs: &mut [[u8; n]]) {
+        for (d, out) in inputs.iter().zip(outputs.iter_mut()) {
+            *out = Hn(d, &Z, &Z, &Z, &Z);
+        }
+    }
+
     fn Hm(m1: &[u8], m2: &[u8], m3: &[u8], m4: &[u8], m5: &[u8]) -> [u8; m] {
         let mut sh = Shake256::default();
         sh.update(m1);
@@ -816,6 +847,13 @@ pub mod LMS_SHAKE_M32_H5_SHAKE_N32_W8 {
         r
     }
 
+    // Hn over several inputs, each provided as a single slice.
+    fn Hn_many(inputs: &[&[u8]], outputs: &mut [[u8; n]]) {
+        for (d, out) in inputs.iter().zip(outputs.iter_mut()) {
+            *out = Hn(d, &Z, &Z, &Z, &Z);
+        }
+    }
+
     fn Hm(m1: &[u8], m2: &[u8], m3: &[u8], m4: &[u8], m5: &[u8]) -> [u8; m] {
         let mut sh = Shake256::default();
         sh.update(m1);
@@ -0,0 +1,297 @@
+//! Merkle trees over BLAKE2s.
+//!
+//! This module implements a simple binary hash tree, for commitments
+//! over a list of 32-byte leaves, and the corresponding inclusion
+//! proofs. All hashing uses BLAKE2s with a 32-byte output, and the
+//! following rules:
+//!
+//!  - A leaf value `x` is turned into a leaf node `H(0x00 || x)`.
+//!
+//!  - An inner node with children `a` and `b` (left and right) is
+//!    `H(0x01 || a || b)`.
+//!
+//!  - At each level, nodes are paired from left to right. If a level
+//!    contains an odd number of nodes, then the last node is _promoted_:
+//!    it is copied unchanged into the next level (it is NOT paired with
+//!    a copy of itself; duplication would allow building distinct leaf
+//!    lists with the same root).
+//!
+//!  - If `t` is the unique node of the last level, and `n` is the
+//!    number of leaves, then the tree root is `H(0x02 || LE64(n) || t)`
+//!    (where `LE64(n)` is the encoding of `n` over 8 bytes, with
+//!    little-endian convention). A tree must contain at least one leaf.
+//!
+//! The distinct prefix bytes for leaves and inner nodes prevent
+//! second-preimage attacks in which an inner node is presented as a
+//! leaf. The root commits to the number of leaves, which is needed
+//! because promotion makes the tree shape depend on that number: without
+//! it, a leaf could be proven at a different position in a tree of a
+//! different size. An inclusion proof (`MerklePath`) contains the number
+//! of leaves so that the verifier may rebuild the tree shape.
+
+use super::blake2s::Blake2s256;
+use super::Vec;
+
+/// A Merkle tree, with all its nodes.
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    // levels[0] contains the leaf nodes; the last level contains
+    // only the top node.
+    levels: Vec<Vec<[u8; 32]>>,
+    root: [u8; 32],
+}
+
+/// An inclusion proof for a leaf in a Merkle tree.
+#[derive(Clone, Debug)]
+pub struct MerklePath {
+    leaf_count: usize,
+    siblings: Vec<[u8; 32]>,
+}
+
+fn hash_leaf(leaf: &[u8; 32]) -> [u8; 32] {
+    let mut sh = Blake2s256::new();
+    sh.update(&[0x00]);
+    sh.update(leaf);
+    sh.finalize()
+}
+
+fn hash_root(leaf_count: usize, top: &[u8; 32]) -> [u8; 32] {
+    let mut sh = Blake2s256::new();
+    sh.update(&[0x02]);
+    sh.update(&(leaf_count as u64).to_le_bytes());
+    sh.update(top);
+    sh.finalize()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut sh = Blake2s256::new();
+    sh.update(&[0x01]);
+    sh.update(left);
+    sh.update(right);
+    sh.finalize()
+}
+
+impl MerkleTree {
+
+    /// Build a tree over the provided leaves. There must be at least
+    /// one leaf; this function panics otherwise.
+    pub fn from_leaves(leaves: &[[u8; 32]]) -> Self {
+        assert!(!leaves.is_empty());
+        let mut levels = Vec::new();
+        let mut cur: Vec<[u8; 32]> = leaves.iter().map(hash_leaf).collect();
+        while cur.len() > 1 {
+            let mut next = Vec::with_capacity((cur.len() + 1) >> 1);
+            for pair in cur.chunks(2) {
+                if pair.len() == 2 {
+                    next.push(hash_node(&pair[0], &pair[1]));
+                } else {
+                    next.push(pair[0]);
+                }
+            }
+            levels.push(cur);
+            cur = next;
+        }
+        let root = hash_root(leaves.len(), &cur[0]);
+        levels.push(cur);
+        Self { levels, root }
+    }
+
+    /// Get the number of leaves in this tree.
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Get the tree root.
+    #[inline]
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// Get the inclusion proof for the leaf at the provided index. The
+    /// index MUST be lower than the number of leaves; this function
+    /// panics otherwise.
+    pub fn prove(&self, index: usize) -> MerklePath {
+        assert!(index < self.leaf_count());
+        let mut siblings = Vec::new();
+        let mut j = index;
+        for level in &self.levels[..(self.levels.len() - 1)] {
+            let k = j ^ 1;
+            if k < level.len() {
+                siblings.push(level[k]);
+            }
+            j >>= 1;
+        }
+        MerklePath { leaf_count: self.leaf_count(), siblings }
+    }
+}
+
+impl MerklePath {
+
+    /// Create a path from its components (number of leaves in the
+    /// tree, and sibling nodes from the leaf level upwards). The
+    /// consistency of the values is checked only upon verification.
+    pub fn new(leaf_count: usize, siblings: &[[u8; 32]]) -> Self {
+        Self { leaf_count, siblings: siblings.to_vec() }
+    }
+
+    /// Get the number of leaves in the tree for which this path was
+    /// computed.
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Get the sibling nodes in this path, from the leaf level upwards.
+    /// Levels where the path node was promoted have no sibling and thus
+    /// no entry.
+    #[inline]
+    pub fn siblings(&self) -> &[[u8; 32]] {
+        &self.siblings
+    }
+
+    /// Verify that this path proves the inclusion of `leaf` at position
+    /// `index` in the tree with root `root`. This function is not
+    /// constant-time (all values are assumed to be public).
+    pub fn verify(&self, root: &[u8; 32], leaf: &[u8; 32], index: usize)
+        -> bool
+    {
+        if index >= self.leaf_count {
+            return false;
+        }
+        let mut node = hash_leaf(leaf);
+        let mut j = index;
+        let mut n = self.leaf_count;
+        let mut k = 0;
+        while n > 1 {
+            if (j ^ 1) < n {
+                if k >= self.siblings.len() {
+                    return false;
+                }
+                let sib = &self.siblings[k];
+                k += 1;
+                node = if (j & 1) == 0 {
+                    hash_node(&node, sib)
+                } else {
+                    hash_node(sib, &node)
+                };
+            }
+            j >>= 1;
+            n = (n + 1) >> 1;
+        }
+        k == self.siblings.len()
+            && crate::ct::equals(&hash_root(self.leaf_count, &node), root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{MerkleTree, MerklePath};
+    use crate::blake2s::Blake2s256;
+    use crate::Vec;
+
+    fn make_leaves(n: usize) -> Vec<[u8; 32]> {
+        let mut leaves = Vec::new();
+        for i in 0..n {
+            leaves.push(Blake2s256::hash(&(i as u64).to_le_bytes()));
+        }
+        leaves
+    }
+
+    #[test]
+    fn golden() {
+        // Reference roots (computed independently) that pin the
+        // hashing rules, including promotion of the odd node.
+        const ROOTS: [(usize, &str); 6] = [
+            ( 1, "3300aee702558534afbce78c7bb061a21fe328cd3d055544e16752ceb5933ebc"),
+            ( 2, "1b238500cd9b30e886fbb9249c71aaa1370f74f447df3195e7403d2c27978c47"),
+            ( 3, "2dbf2546d7f6d1972bdcb377a5990ebb7cc699fcb5a6598c237f3bb70ba1257a"),
+            ( 5, "2ca697a7442b6d230538bc44cbd112572dcf119b1a4e5d07d893376aec0d1aca"),
+            ( 7, "11746a0938c10d572f9bfe61f34444361c2babeb61e9726459c172a18d7c3f1c"),
+            (33, "1181b01f79c3e856689fddbbd4c7c1eed1c48f8d8ec0447d462d1448db996e70"),
+        ];
+        let leaves = make_leaves(33);
+        for (n, r) in ROOTS.iter() {
+            let tree = MerkleTree::from_leaves(&leaves[..*n]);
+            assert!(tree.root()[..] == hex::decode(r).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn all_sizes() {
+        for n in 1..=33 {
+            let leaves = make_leaves(n);
+            let tree = MerkleTree::from_leaves(&leaves);
+            assert!(tree.leaf_count() == n);
+            let root = tree.root();
+            for i in 0..n {
+                let path = tree.prove(i);
+                assert!(path.verify(&root, &leaves[i], i));
+                let path2 = MerklePath::new(path.leaf_count(), path.siblings());
+                assert!(path2.verify(&root, &leaves[i], i));
+                for j in 0..n {
+                    if j != i {
+                        assert!(!path.verify(&root, &leaves[j], i));
+                        assert!(!path.verify(&root, &leaves[i], j));
+                    }
+                }
+                assert!(!path.verify(&root, &leaves[i], n));
+            }
+        }
+    }
+
+    #[test]
+    fn tampering() {
+        for n in 1..=33 {
+            let leaves = make_leaves(n);
+            let tree = MerkleTree::from_leaves(&leaves);
+            let root = tree.root();
+            for i in 0..n {
+                let path = tree.prove(i);
+                let sibs = path.siblings();
+
+                // Any bit flip in any sibling is detected.
+                for k in 0..sibs.len() {
+                    for bit in [0, 77, 255] {
+                        let mut s2 = sibs.to_vec();
+                        s2[k][bit >> 3] ^= 1u8 << (bit & 7);
+                        let p2 = MerklePath::new(n, &s2);
+                        assert!(!p2.verify(&root, &leaves[i], i));
+                    }
+                }
+
+                // Truncated or extended paths are rejected.
+                if !sibs.is_empty() {
+                    let p2 = MerklePath::new(n, &sibs[..(sibs.len() - 1)]);
+                    assert!(!p2.verify(&root, &leaves[i], i));
+                }
+                let mut s2 = sibs.to_vec();
+                s2.push(root);
+                let p2 = MerklePath::new(n, &s2);
+                assert!(!p2.verify(&root, &leaves[i], i));
+
+                // Inner nodes cannot be presented as leaves.
+                if n > 1 {
+                    let p2 = MerklePath::new(1, &[]);
+                    assert!(!p2.verify(&root, &root, 0));
+                }
+
+                // Lying about the leaf count does not help with a
+                // different index. Promotion happens on the right edge
+                // of the tree, so we test that with the last leaf.
+                if i != n - 1 {
+                    continue;
+                }
+                for n2 in 1..=40 {
+                    let p2 = MerklePath::new(n2, sibs);
+                    for i2 in 0..n2 {
+                        if p2.verify(&root, &leaves[i], i2) {
+                            assert!(i2 == i);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,561 @@
+//! Minisign-compatible signatures.
+//!
+//! This module reads and writes the key and signature files of
+//! [minisign] (and compatible tools such as rsign2), and computes and
+//! verifies the corresponding Ed25519 signatures:
+//!
+//!  - A public key is a key identifier (8 bytes) and an Ed25519 public
+//!    key. Its file is an untrusted comment line followed by the Base64
+//!    encoding of `"Ed" || key_id || public_key`.
+//!
+//!  - A signature file has four lines: an untrusted comment, the Base64
+//!    encoding of `algorithm || key_id || signature`, a trusted comment,
+//!    and the Base64 encoding of the global signature. The algorithm is
+//!    `"Ed"` (legacy: the signature is computed over the data itself) or
+//!    `"ED"` (prehashed: the signature is computed over the BLAKE2b-512
+//!    hash of the data). The global signature is computed over the
+//!    concatenation of the signature and the trusted comment, so that
+//!    the trusted comment is authenticated as well.
+//!
+//!  - A secret key file is an untrusted comment line followed by the
+//!    Base64 encoding of the algorithm identifiers, the key derivation
+//!    parameters, the key identifier, the Ed25519 key pair (seed and
+//!    public key) and a BLAKE2b-256 checksum. Only unencrypted secret
+//!    keys are supported; password-protected (scrypt) keys are rejected
+//!    with `MinisignError::EncryptedKey`.
+//!
+//! Decoding functions take the whole file contents; lines may end with
+//! LF or CR+LF. Encoding functions produce LF-terminated lines.
+//!
+//! [minisign]: https://jedisct1.github.io/minisign/
+
+use crate::base64::{self, Variant};
+use crate::blake2b::{Blake2b, Blake2b512};
+use crate::ed25519;
+use crate::{CryptoRng, RngCore, String, Vec};
+
+/// Error type for minisign keys and signatures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinisignError {
+    /// The file structure or the Base64 encoding is invalid.
+    BadEncoding,
+    /// The signature or key algorithm is not supported.
+    UnsupportedAlgorithm,
+    /// The secret key is encrypted.
+    EncryptedKey,
+    /// The secret key checksum does not match.
+    BadChecksum,
+    /// The public key is invalid.
+    BadPublicKey,
+    /// A comment contains a line break.
+    BadComment,
+    /// The signature was made with a different key.
+    KeyIdMismatch,
+    /// The signature or the global signature is invalid.
+    InvalidSignature,
+}
+
+impl core::fmt::Display for MinisignError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MinisignError::BadEncoding => f.write_str("invalid encoding"),
+            MinisignError::UnsupportedAlgorithm => f.write_str("unsupported algorithm"),
+            MinisignError::EncryptedKey => f.write_str("encrypted secret keys are not supported"),
+            MinisignError::BadChecksum => f.write_str("secret key checksum mismatch"),
+            MinisignError::BadPublicKey => f.write_str("invalid public key"),
+            MinisignError::BadComment => f.write_str("comment contains a line break"),
+            MinisignError::KeyIdMismatch => f.write_str("key identifier mismatch"),
+            MinisignError::InvalidSignature => f.write_str("invalid signature"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MinisignError {}
+
+/// Signature algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Signature over the data itself (`"Ed"`).
+    Legacy,
+    /// Signature over the BLAKE2b-512 hash of the data (`"ED"`); this is
+    /// what recent versions of minisign produce by default.
+    Prehashed,
+}
+
+impl Algorithm {
+
+    fn id(self) -> &'static [u8; 2] {
+        match self {
+            Algorithm::Legacy => b"Ed",
+            Algorithm::Prehashed => b"ED",
+        }
+    }
+
+    fn from_id(id: &[u8]) -> Result<Self, MinisignError> {
+        match id {
+            b"Ed" => Ok(Algorithm::Legacy),
+            b"ED" => Ok(Algorithm::Prehashed),
+            _ => Err(MinisignError::UnsupportedAlgorithm),
+        }
+    }
+}
+
+/// A minisign public key.
+#[derive(Clone, Copy, Debug)]
+pub struct PublicKey {
+    /// Key identifier.
+    pub key_id: [u8; 8],
+    /// Ed25519 public key.
+    pub key: ed25519::PublicKey,
+}
+
+/// A minisign secret key (unencrypted).
+#[derive(Clone, Copy)]
+pub struct SecretKey {
+    /// Key identifier.
+    pub key_id: [u8; 8],
+    /// Ed25519 private key.
+    pub key: ed25519::PrivateKey,
+}
+
+/// A minisign signature, with its comments.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    /// Untrusted comment (not covered by any signature).
+    pub untrusted_comment: String,
+    /// Signature algorithm.
+    pub algorithm: Algorithm,
+    /// Identifier of the signing key.
+    pub key_id: [u8; 8],
+    /// Ed25519 signature over the data (or its hash).
+    pub signature: [u8; 64],
+    /// Trusted comment (covered by the global signature).
+    pub trusted_comment: String,
+    /// Ed25519 signature over the signature and the trusted comment.
+    pub global_signature: [u8; 64],
+}
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+// Secret key identifiers: checksum algorithm (BLAKE2b-256), and key
+// derivation (none).
+const CHK_ALG: &[u8; 2] = b"B2";
+const KDF_NONE: &[u8; 2] = &[0, 0];
+const KDF_SCRYPT: &[u8; 2] = b"Sc";
+
+// Length of a decoded secret key: algorithms (6), salt (32), opslimit
+// (8), memlimit (8), key identifier (8), key pair (64), checksum (32).
+const SK_LEN: usize = 158;
+
+// Split some file contents into lines; a CR before the LF is removed. A
+// final LF is optional; empty lines are not tolerated.
+fn split_lines(text: &str) -> Vec<&str> {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    text.split('\n').map(|s| s.strip_suffix('\r').unwrap_or(s)).collect()
+}
+
+// Decode a Base64 line into a value of exactly `N` bytes.
+fn decode_fixed<const N: usize>(s: &str) -> Result<[u8; N], MinisignError> {
+    let mut buf = [0u8; N];
+    match base64::decoded_len(s.as_bytes(), Variant::Standard) {
+        Ok(n) if n == N => {}
+        _ => return Err(MinisignError::BadEncoding),
+    }
+    base64::decode(s.as_bytes(), &mut buf, Variant::Standard)
+        .map_err(|_| MinisignError::BadEncoding)?;
+    Ok(buf)
+}
+
+fn encode_b64(src: &[u8]) -> String {
+    let mut buf = vec![0u8; base64::encoded_len(src.len(), Variant::Standard)];
+    base64::encode(src, &mut buf, Variant::Standard).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+// Get the comment text from a comment line.
+fn comment<'a>(line: &'a str, prefix: &str) -> Result<&'a str, MinisignError> {
+    line.strip_prefix(prefix).ok_or(MinisignError::BadEncoding)
+}
+
+// Check that a comment can be written on a single line.
+fn check_comment(c: &str) -> Result<(), MinisignError> {
+    if c.contains(['\n', '\r']) {
+        Err(MinisignError::BadComment)
+    } else {
+        Ok(())
+    }
+}
+
+// Key identifier, as printed in the default comments (hexadecimal, of
+// the identifier interpreted as a little-endian integer).
+fn key_id_string(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+impl PublicKey {
+
+    /// Decodes a public key from its Base64 encoding (the second line of
+    /// a public key file, or the value given to `minisign -P`).
+    pub fn from_base64(s: &str) -> Result<Self, MinisignError> {
+        let buf: [u8; 42] = decode_fixed(s)?;
+        if &buf[..2] != b"Ed" {
+            return Err(MinisignError::UnsupportedAlgorithm);
+        }
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&buf[2..10]);
+        let key = ed25519::PublicKey::decode(&buf[10..])
+            .ok_or(MinisignError::BadPublicKey)?;
+        Ok(Self { key_id, key })
+    }
+
+    /// Encodes this public key in Base64.
+    pub fn to_base64(&self) -> String {
+        let mut buf = [0u8; 42];
+        buf[..2].copy_from_slice(b"Ed");
+        buf[2..10].copy_from_slice(&self.key_id);
+        buf[10..].copy_from_slice(&self.key.encoded);
+        encode_b64(&buf)
+    }
+
+    /// Decodes a public key file (untrusted comment and Base64 line).
+    pub fn decode(text: &str) -> Result<Self, MinisignError> {
+        let lines = split_lines(text);
+        if lines.len() != 2 {
+            return Err(MinisignError::BadEncoding);
+        }
+        comment(lines[0], UNTRUSTED_PREFIX)?;
+        Self::from_base64(lines[1])
+    }
+
+    /// Encodes this public key as a file, with the default untrusted
+    /// comment of minisign.
+    pub fn encode(&self) -> String {
+        format!("{}minisign public key {}\n{}\n", UNTRUSTED_PREFIX,
+            key_id_string(&self.key_id), self.to_base64())
+    }
+
+    /// Verifies a signature over some data. Both the signature over the
+    /// data and the global signature (which covers the trusted comment)
+    /// are verified; the trusted comment can be used by the caller only
+    /// if this function returns `Ok(())`.
+    pub fn verify(&self, sig: &Signature, data: &[u8])
+        -> Result<(), MinisignError>
+    {
+        match sig.algorithm {
+            Algorithm::Legacy => self.verify_inner(sig, data),
+            Algorithm::Prehashed => {
+                self.verify_inner(sig, &Blake2b512::hash(data))
+            }
+        }
+    }
+
+    /// Verifies a prehashed signature, given the BLAKE2b-512 hash of the
+    /// data (e.g. computed incrementally with `Blake2b512`). Legacy
+    /// signatures are rejected with `MinisignError::UnsupportedAlgorithm`,
+    /// since they are not computed over a hash.
+    pub fn verify_prehashed(&self, sig: &Signature, hash: &[u8; 64])
+        -> Result<(), MinisignError>
+    {
+        if sig.algorithm != Algorithm::Prehashed {
+            return Err(MinisignError::UnsupportedAlgorithm);
+        }
+        self.verify_inner(sig, hash)
+    }
+
+    fn verify_inner(&self, sig: &Signature, m: &[u8])
+        -> Result<(), MinisignError>
+    {
+        if sig.key_id != self.key_id {
+            return Err(MinisignError::KeyIdMismatch);
+        }
+        if !self.key.verify_raw(&sig.signature, m) {
+            return Err(MinisignError::InvalidSignature);
+        }
+        if !self.key.verify_raw(&sig.global_signature,
+            &global_message(&sig.signature, &sig.trusted_comment))
+        {
+            return Err(MinisignError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+// Message for the global signature.
+fn global_message(signature: &[u8; 64], trusted_comment: &str) -> Vec<u8> {
+    let mut m = Vec::with_capacity(64 + trusted_comment.len());
+    m.extend_from_slice(signature);
+    m.extend_from_slice(trusted_comment.as_bytes());
+    m
+}
+
+impl SecretKey {
+
+    /// Generates a new secret key (with a random key identifier).
+    pub fn generate<T: CryptoRng + RngCore>(rng: &mut T) -> Self {
+        let mut key_id = [0u8; 8];
+        rng.fill_bytes(&mut key_id);
+        Self { key_id, key: ed25519::PrivateKey::generate(rng) }
+    }
+
+    /// Gets the public key corresponding to this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey { key_id: self.key_id, key: self.key.public_key }
+    }
+
+    // Checksum: BLAKE2b-256(sig_alg || key_id || seed || public_key).
+    fn checksum(key_id: &[u8; 8], seed: &[u8; 32], pk: &[u8; 32]) -> [u8; 32] {
+        let mut sh = Blake2b::new(32);
+        sh.update(b"Ed");
+        sh.update(key_id);
+        sh.update(seed);
+        sh.update(pk);
+        let mut chk = [0u8; 32];
+        sh.finalize_write(&mut chk);
+        chk
+    }
+
+    /// Decodes a secret key file (untrusted comment and Base64 line).
+    /// Encrypted keys are not supported. The checksum is verified, and
+    /// the public key must match the seed.
+    pub fn decode(text: &str) -> Result<Self, MinisignError> {
+        let lines = split_lines(text);
+        if lines.len() != 2 {
+            return Err(MinisignError::BadEncoding);
+        }
+        comment(lines[0], UNTRUSTED_PREFIX)?;
+        let buf: [u8; SK_LEN] = decode_fixed(lines[1])?;
+        if &buf[0..2] != b"Ed" || &buf[4..6] != CHK_ALG {
+            return Err(MinisignError::UnsupportedAlgorithm);
+        }
+        if &buf[2..4] == KDF_SCRYPT {
+            return Err(MinisignError::EncryptedKey);
+        }
+        if &buf[2..4] != KDF_NONE {
+            return Err(MinisignError::UnsupportedAlgorithm);
+        }
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&buf[54..62]);
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&buf[62..94]);
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(&buf[94..126]);
+        if !crate::ct::equals(&Self::checksum(&key_id, &seed, &pk),
+            &buf[126..])
+        {
+            return Err(MinisignError::BadChecksum);
+        }
+        let key = ed25519::PrivateKey::from_seed(&seed);
+        if key.public_key.encoded != pk {
+            return Err(MinisignError::BadChecksum);
+        }
+        Ok(Self { key_id, key })
+    }
+
+    /// Encodes this secret key as an unencrypted secret key file (key
+    /// derivation parameters are all zeros).
+    pub fn encode(&self) -> String {
+        let seed = self.key.encode();
+        let pk = self.key.public_key.encoded;
+        let mut buf = [0u8; SK_LEN];
+        buf[0..2].copy_from_slice(b"Ed");
+        buf[2..4].copy_from_slice(KDF_NONE);
+        buf[4..6].copy_from_slice(CHK_ALG);
+        buf[54..62].copy_from_slice(&self.key_id);
+        buf[62..94].copy_from_slice(&seed);
+        buf[94..126].copy_from_slice(&pk);
+        buf[126..].copy_from_slice(&Self::checksum(&self.key_id, &seed, &pk));
+        format!("{}minisign secret key\n{}\n", UNTRUSTED_PREFIX,
+            encode_b64(&buf))
+    }
+
+    /// Signs some data. The trusted comment is covered by the global
+    /// signature; the untrusted comment is not. Comments must not
+    /// contain line breaks.
+    pub fn sign(&self, data: &[u8], algorithm: Algorithm,
+        trusted_comment: &str, untrusted_comment: &str)
+        -> Result<Signature, MinisignError>
+    {
+        match algorithm {
+            Algorithm::Legacy => self.sign_inner(data, algorithm,
+                trusted_comment, untrusted_comment),
+            Algorithm::Prehashed => self.sign_inner(&Blake2b512::hash(data),
+                algorithm, trusted_comment, untrusted_comment),
+        }
+    }
+
+    /// Computes a prehashed signature, given the BLAKE2b-512 hash of the
+    /// data (e.g. computed incrementally with `Blake2b512`).
+    pub fn sign_prehashed(&self, hash: &[u8; 64], trusted_comment: &str,
+        untrusted_comment: &str) -> Result<Signature, MinisignError>
+    {
+        self.sign_inner(hash, Algorithm::Prehashed,
+            trusted_comment, untrusted_comment)
+    }
+
+    fn sign_inner(&self, m: &[u8], algorithm: Algorithm,
+        trusted_comment: &str, untrusted_comment: &str)
+        -> Result<Signature, MinisignError>
+    {
+        check_comment(trusted_comment)?;
+        check_comment(untrusted_comment)?;
+        let signature = self.key.sign_raw(m);
+        let global_signature = self.key.sign_raw(
+            &global_message(&signature, trusted_comment));
+        Ok(Signature {
+            untrusted_comment: String::from(untrusted_comment),
+            algorithm,
+            key_id: self.key_id,
+            signature,
+            trusted_comment: String::from(trusted_comment),
+            global_signature,
+        })
+    }
+}
+
+impl core::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signature {
+
+    /// Decodes a signature file. The signatures are not verified.
+    pub fn decode(text: &str) -> Result<Self, MinisignError> {
+        let lines = split_lines(text);
+        if lines.len() != 4 {
+            return Err(MinisignError::BadEncoding);
+        }
+        let untrusted_comment = comment(lines[0], UNTRUSTED_PREFIX)?;
+        let buf: [u8; 74] = decode_fixed(lines[1])?;
+        let trusted_comment = comment(lines[2], TRUSTED_PREFIX)?;
+        let global_signature: [u8; 64] = decode_fixed(lines[3])?;
+        let algorithm = Algorithm::from_id(&buf[..2])?;
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&buf[2..10]);
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&buf[10..]);
+        Ok(Self {
+            untrusted_comment: String::from(untrusted_comment),
+            algorithm,
+            key_id,
+            signature,
+            trusted_comment: String::from(trusted_comment),
+            global_signature,
+        })
+    }
+
+    /// Encodes this signature as a file.
+    pub fn encode(&self) -> String {
+        let mut buf = [0u8; 74];
+        buf[..2].copy_from_slice(self.algorithm.id());
+        buf[2..10].copy_from_slice(&self.key_id);
+        buf[10..].copy_from_slice(&self.signature);
+        format!("{}{}\n{}\n{}{}\n{}\n",
+            UNTRUSTED_PREFIX, self.untrusted_comment, encode_b64(&buf),
+            TRUSTED_PREFIX, self.trusted_comment,
+            encode_b64(&self.global_signature))
+    }
+}
+
+// ========================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Algorithm, MinisignError, PublicKey, SecretKey, Signature};
+    use crate::blake2b::Blake2b512;
+    use crate::ed25519::PrivateKey;
+
+    // Public key and signatures of the data "test", generated with the
+    // minisign tool (from the test suite of the minisign-verify crate).
+    const PK_FILE: &str = "untrusted comment: minisign public key E7620F1842B4E81F\nRWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3\n";
+    const SIG_LEGACY: &str = "untrusted comment: signature from minisign secret key\nRWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\ntrusted comment: timestamp:1555779966\tfile:test\nQtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==\n";
+    const SIG_PREHASHED: &str = "untrusted comment: signature from minisign secret key\nRUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\ntrusted comment: timestamp:1556193335\tfile:test\ny/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==\n";
+
+    #[test]
+    fn reference_signatures() {
+        let pk = PublicKey::decode(PK_FILE).unwrap();
+        assert!(pk.encode() == PK_FILE);
+        for (text, alg) in [(SIG_LEGACY, Algorithm::Legacy),
+            (SIG_PREHASHED, Algorithm::Prehashed)].iter()
+        {
+            let sig = Signature::decode(text).unwrap();
+            assert!(sig.algorithm == *alg);
+            assert!(sig.encode() == *text);
+            assert!(pk.verify(&sig, b"test").is_ok());
+            assert!(pk.verify(&sig, b"tesT").err() == Some(MinisignError::InvalidSignature));
+
+            // CR+LF line endings are accepted.
+            let crlf = text.replace('\n', "\r\n");
+            assert!(pk.verify(&Signature::decode(&crlf).unwrap(), b"test").is_ok());
+
+            // Altered trusted comment: global signature fails.
+            let mut sig2 = sig.clone();
+            sig2.trusted_comment.push('x');
+            assert!(pk.verify(&sig2, b"test").err() == Some(MinisignError::InvalidSignature));
+
+            // Altered untrusted comment: still valid.
+            let mut sig2 = sig.clone();
+            sig2.untrusted_comment.push('x');
+            assert!(pk.verify(&sig2, b"test").is_ok());
+
+            // Wrong key identifier.
+            let mut sig2 = sig.clone();
+            sig2.key_id[0] ^= 1;
+            assert!(pk.verify(&sig2, b"test").err() == Some(MinisignError::KeyIdMismatch));
+        }
+
+        let sig = Signature::decode(SIG_PREHASHED).unwrap();
+        assert!(pk.verify_prehashed(&sig, &Blake2b512::hash(b"test")).is_ok());
+        let sig = Signature::decode(SIG_LEGACY).unwrap();
+        assert!(pk.verify_prehashed(&sig, &Blake2b512::hash(b"test")).err() == Some(MinisignError::UnsupportedAlgorithm));
+
+        // Malformed files.
+        assert!(Signature::decode(&SIG_LEGACY[..100]).err() == Some(MinisignError::BadEncoding));
+        assert!(Signature::decode(&SIG_LEGACY.replace("RWQf", "RXQf")).err() == Some(MinisignError::UnsupportedAlgorithm));
+        assert!(PublicKey::decode(&PK_FILE.replace("untrusted", "trusted")).err() == Some(MinisignError::BadEncoding));
+    }
+
+    #[test]
+    fn secret_key() {
+        // Secret key for seed 00 01 02 .. 1F and key identifier
+        // 01 23 45 67 89 AB CD EF (unencrypted), with a signature; these
+        // values were computed with an independent Python script.
+        const SK_FILE: &str = "untrusted comment: minisign secret key\nRWQAAEIyAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAASNFZ4mrze8AAQIDBAUGBwgJCgsMDQ4PEBESExQVFhcYGRobHB0eHwOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG43hF1h+DgQfSqnxZtisH2eRBtuqDf2SrRD8x0kQkLhBw=\n";
+        const PK_B64: &str = "RWQBI0VniavN7wOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+        const SIG: &str = "untrusted comment: signature from crrl\nRUQBI0VniavN7x5YqhSZcQMyy8Cs86WTnW/kQMibIH5fusA9J/DB+PK85QDDtf25eGiWb1WKQRnzgnpOCpTTZYUjfj378ad5/gE=\ntrusted comment: timestamp:1700000000\tfile:data\n1MmnSx/R495Mn8/vpMkoRnGA/pGJcPu9bEwJnQtidxxRlgxbuFlk4ox6Q8M2MBAFWt0kUo0ZQTJMoufU5h6YBw==\n";
+
+        let mut seed = [0u8; 32];
+        for i in 0..32 {
+            seed[i] = i as u8;
+        }
+        let sk = SecretKey {
+            key_id: [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF],
+            key: PrivateKey::from_seed(&seed),
+        };
+        assert!(sk.encode() == SK_FILE);
+        let sk2 = SecretKey::decode(SK_FILE).unwrap();
+        assert!(sk2.key_id == sk.key_id && sk2.key.encode() == seed);
+        let pk = sk.public_key();
+        assert!(pk.to_base64() == PK_B64);
+
+        let sig = sk.sign(b"data", Algorithm::Prehashed,
+            "timestamp:1700000000\tfile:data", "signature from crrl").unwrap();
+        assert!(sig.encode() == SIG);
+        assert!(pk.verify(&Signature::decode(SIG).unwrap(), b"data").is_ok());
+        let sig = sk.sign(b"data", Algorithm::Legacy, "", "").unwrap();
+        assert!(pk.verify(&sig, b"data").is_ok());
+        assert!(sk.sign(b"data", Algorithm::Legacy, "a\nb", "").err() == Some(MinisignError::BadComment));
+
+        // Modified checksum, encrypted key.
+        let bad = SK_FILE.replace("hBw=", "hBg=");
+        assert!(SecretKey::decode(&bad).err() == Some(MinisignError::BadChecksum));
+        let enc = SK_FILE.replace("RWQAAEIy", "RWRTY0Iy");
+        assert!(SecretKey::decode(&enc).err() == Some(MinisignError::EncryptedKey));
+    }
+}
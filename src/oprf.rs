@@ -0,0 +1,321 @@
+//! OPRF (RFC 9497) over ristretto255 with SHA-512.
+//!
+//! This module implements the base mode (OPRF) of the
+//! `ristretto255-SHA512` ciphersuite of [RFC 9497]. An oblivious
+//! pseudorandom function lets a client obtain `F(k, input)` from a server
+//! holding the key `k`, without the server learning the input or the
+//! output:
+//!
+//!  - The client calls `blind()` on its input, and sends the blinded
+//!    element to the server.
+//!
+//!  - The server calls `blind_evaluate()` with its private key, and sends
+//!    back the evaluated element.
+//!
+//!  - The client calls `finalize()` to unblind the evaluated element and
+//!    obtain the 64-byte PRF output.
+//!
+//! The server can also compute the PRF output directly with `evaluate()`.
+//! Server key pairs are either random (`generate_key_pair()`) or
+//! derived deterministically from a seed (`derive_key_pair()`).
+//!
+//! Elements are exchanged in their encoded form (32 bytes). Blinding
+//! factors and private keys are scalars (type `ristretto255::Scalar`).
+//! All DSTs and the context string follow the RFC exactly; the mode is
+//! a parameter of the internal functions, so that the verifiable (VOPRF)
+//! and partially-oblivious (POPRF) modes may be added later on.
+//!
+//! [RFC 9497]: https://www.rfc-editor.org/rfc/rfc9497.html
+
+// Points traditionally use uppercase letters.
+#![allow(non_snake_case)]
+
+use crate::ristretto255::{Point, Scalar, expand_message_xmd_sha512};
+use crate::{CryptoRng, RngCore};
+use sha2::{Sha512, Digest};
+
+/// Error type for the OPRF functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OprfError {
+    /// The input is too long, or hashes to the neutral element.
+    InvalidInput,
+    /// Key pair derivation failed.
+    DeriveKeyPair,
+    /// An element could not be decoded (or is the neutral element).
+    Deserialize,
+}
+
+impl core::fmt::Display for OprfError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OprfError::InvalidInput => f.write_str("invalid input"),
+            OprfError::DeriveKeyPair => f.write_str("key pair derivation failed"),
+            OprfError::Deserialize => f.write_str("invalid element encoding"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OprfError {}
+
+/// Mode identifier for the base OPRF mode.
+const MODE_OPRF: u8 = 0x00;
+
+/// Builds the context string for the provided mode:
+/// "OPRFV1-" || I2OSP(mode, 1) || "-" || "ristretto255-SHA512"
+fn context_string(mode: u8) -> [u8; 28] {
+    let mut ctx = *b"OPRFV1-?-ristretto255-SHA512";
+    ctx[7] = mode;
+    ctx
+}
+
+/// HashToGroup(): hash_to_ristretto255 with DST
+/// "HashToGroup-" || contextString.
+fn hash_to_group(mode: u8, input: &[u8]) -> Point {
+    let mut dst = [0u8; 40];
+    dst[..12].copy_from_slice(b"HashToGroup-");
+    dst[12..].copy_from_slice(&context_string(mode));
+    Point::hash_to_point(input, &dst)
+}
+
+/// HashToScalar(): expand_message_xmd (SHA-512) to 64 bytes, then
+/// reduction modulo the group order. The DST is provided as two parts
+/// (prefix and context string).
+fn hash_to_scalar(data: &[&[u8]], dst_prefix: &[u8], ctx: &[u8]) -> Scalar {
+    let mut dst = [0u8; 64];
+    let n = dst_prefix.len() + ctx.len();
+    dst[..dst_prefix.len()].copy_from_slice(dst_prefix);
+    dst[dst_prefix.len()..n].copy_from_slice(ctx);
+    Scalar::decode_reduce(&expand_message_xmd_sha512(data, &dst[..n]))
+}
+
+/// Decodes an element; the neutral element is rejected.
+fn decode_element(buf: &[u8]) -> Result<Point, OprfError> {
+    match Point::decode(buf) {
+        Some(P) if P.isneutral() == 0 => Ok(P),
+        _ => Err(OprfError::Deserialize),
+    }
+}
+
+/// Hashes the input and the unblinded element into the PRF output.
+fn finalize_hash(input: &[u8], element: &[u8; 32]) -> [u8; 64] {
+    let mut sh = Sha512::new();
+    sh.update((input.len() as u16).to_be_bytes());
+    sh.update(input);
+    sh.update(32u16.to_be_bytes());
+    sh.update(element);
+    sh.update(b"Finalize");
+    let mut out = [0u8; 64];
+    out[..].copy_from_slice(&sh.finalize());
+    out
+}
+
+/// DeriveKeyPair() for the provided mode.
+fn derive_key_pair_mode(mode: u8, seed: &[u8; 32], info: &[u8])
+    -> Result<(Scalar, Point), OprfError>
+{
+    if info.len() > 0xFFFF {
+        return Err(OprfError::DeriveKeyPair);
+    }
+    let ctx = context_string(mode);
+    let info_len = (info.len() as u16).to_be_bytes();
+    for counter in 0..=255u8 {
+        let sk = hash_to_scalar(&[seed, &info_len, info, &[counter]],
+            b"DeriveKeyPair", &ctx);
+        if sk.iszero() == 0 {
+            return Ok((sk, Point::mulgen(&sk)));
+        }
+    }
+    Err(OprfError::DeriveKeyPair)
+}
+
+/// Derives a server key pair (private scalar, public element) from a
+/// 32-byte seed and some (possibly empty) key information.
+///
+/// This is the `DeriveKeyPair()` function of RFC 9497. The key
+/// information must have length at most 65535 bytes.
+pub fn derive_key_pair(seed: &[u8; 32], info: &[u8])
+    -> Result<(Scalar, Point), OprfError>
+{
+    derive_key_pair_mode(MODE_OPRF, seed, info)
+}
+
+/// Generates a random server key pair (private scalar, public element).
+pub fn generate_key_pair<T: CryptoRng + RngCore>(rng: &mut T)
+    -> (Scalar, Point)
+{
+    loop {
+        let mut tmp = [0u8; 64];
+        rng.fill_bytes(&mut tmp);
+        let sk = Scalar::decode_reduce(&tmp);
+        if sk.iszero() == 0 {
+            return (sk, Point::mulgen(&sk));
+        }
+    }
+}
+
+/// Blinds an input (client side).
+///
+/// Returned values are the blinding factor (to keep for `finalize()`)
+/// and the encoded blinded element (to send to the server). The input
+/// must have length at most 65535 bytes. The blinding factor is
+/// obtained from `rng`, which must be cryptographically secure.
+pub fn blind<T: CryptoRng + RngCore>(rng: &mut T, input: &[u8])
+    -> Result<(Scalar, [u8; 32]), OprfError>
+{
+    loop {
+        let mut tmp = [0u8; 64];
+        rng.fill_bytes(&mut tmp);
+        let b = Scalar::decode_reduce(&tmp);
+        if b.iszero() == 0 {
+            return Ok((b, blind_with(&b, input)?));
+        }
+    }
+}
+
+/// Blinds an input with the provided (non-zero) blinding factor.
+///
+/// This is the deterministic core of `blind()`; the blinding factor
+/// MUST be a uniformly random non-zero scalar, used only once. This
+/// function is meant for testing purposes (e.g. to reproduce test
+/// vectors) and for callers with their own scalar generation process.
+/// The encoded blinded element is returned.
+pub fn blind_with(blind: &Scalar, input: &[u8])
+    -> Result<[u8; 32], OprfError>
+{
+    if input.len() > 0xFFFF {
+        return Err(OprfError::InvalidInput);
+    }
+    let P = hash_to_group(MODE_OPRF, input);
+    if P.isneutral() != 0 {
+        return Err(OprfError::InvalidInput);
+    }
+    Ok((P * blind).encode())
+}
+
+/// Evaluates a blinded element with the private key (server side).
+///
+/// The encoded evaluated element is returned; an error is reported if
+/// the blinded element cannot be decoded.
+pub fn blind_evaluate(sk: &Scalar, blinded: &[u8])
+    -> Result<[u8; 32], OprfError>
+{
+    let P = decode_element(blinded)?;
+    Ok((P * sk).encode())
+}
+
+/// Unblinds an evaluated element (client side), and returns the PRF
+/// output.
+///
+/// `input` and `blind` are the values used with `blind()`; `evaluated`
+/// is the encoded element returned by the server.
+pub fn finalize(input: &[u8], blind: &Scalar, evaluated: &[u8])
+    -> Result<[u8; 64], OprfError>
+{
+    if input.len() > 0xFFFF {
+        return Err(OprfError::InvalidInput);
+    }
+    let Z = decode_element(evaluated)?;
+    let N = Z * (Scalar::ONE / blind);
+    Ok(finalize_hash(input, &N.encode()))
+}
+
+/// Computes the PRF output directly from the private key and the input
+/// (server side, no blinding).
+///
+/// This is the `Evaluate()` function of RFC 9497; the output is the
+/// same as what the client obtains with `finalize()`.
+pub fn evaluate(sk: &Scalar, input: &[u8]) -> Result<[u8; 64], OprfError> {
+    if input.len() > 0xFFFF {
+        return Err(OprfError::InvalidInput);
+    }
+    let P = hash_to_group(MODE_OPRF, input);
+    if P.isneutral() != 0 {
+        return Err(OprfError::InvalidInput);
+    }
+    Ok(finalize_hash(input, &(P * sk).encode()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{derive_key_pair, blind_with, blind_evaluate, finalize, evaluate};
+    use crate::ristretto255::{Point, Scalar};
+
+    #[test]
+    fn rfc9497() {
+        // RFC 9497, appendix A.1.1 (OPRF(ristretto255, SHA-512), OPRF
+        // mode): seed, key info, private key, inputs and outputs. The
+        // blinding factor and the intermediate elements were computed
+        // with an independent implementation.
+        let seed = [0xA3u8; 32];
+        let (sk, pk) = derive_key_pair(&seed, b"test key").unwrap();
+        assert!(sk.encode()[..] == hex::decode("5ebcea5ee37023ccb9fc2d2019f9d7737be85591ae8652ffa9ef0f4d37063b0e").unwrap()[..]);
+        assert!(pk.equals(Point::mulgen(&sk)) != 0);
+
+        const KAT: [(&str, &str, &str, &str); 2] = [
+            ("00",
+             "fc8167efcfa2bcc4b6727f34d7ae7777d7d272caaa6133c02613b01288b2e851",
+             "1652be32d2104b7fa0ac60c2d0b5c93405a8e68da243e294886372f28e2b7c25",
+             "527759c3d9366f277d8c6020418d96bb393ba2afb20ff90df23fb7708264e2f3ab9135e3bd69955851de4b1f9fe8a0973396719b7912ba9ee8aa7d0b5e24bcf6"),
+            ("5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a",
+             "34fc2b318d92cf97c93396cca98d50aa59e35c792a68966b6a7d708caf10ae01",
+             "a4af4331fb7c4f34bf20051b51b4acb1e30da9dd003637622fcdaf55d4655618",
+             "f4a74c9c592497375e796aa837e907b1a045d34306a749db9f34221f7e750cb4f2a6413a6bf6fa5e19ba6348eb673934a722a7ede2e7621306d18951e7cf2c73"),
+        ];
+        let (b, ok) = Scalar::decode32(&hex::decode("64d37aed22a27f5191de1c1cf1085a77f2f2d8d57d8d8d5a3c4ae42a4fd0b10d").unwrap());
+        assert!(ok != 0);
+        for (input, be, ee, out) in KAT.iter() {
+            let input = hex::decode(input).unwrap();
+            let blinded = blind_with(&b, &input).unwrap();
+            assert!(blinded[..] == hex::decode(be).unwrap()[..]);
+            let evaluated = blind_evaluate(&sk, &blinded).unwrap();
+            assert!(evaluated[..] == hex::decode(ee).unwrap()[..]);
+            let output = finalize(&input, &b, &evaluated).unwrap();
+            assert!(output[..] == hex::decode(out).unwrap()[..]);
+            assert!(evaluate(&sk, &input).unwrap() == output);
+        }
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn round_trip() {
+        use super::{OprfError, generate_key_pair, blind};
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0u8; 32]);
+        let (sk, _) = generate_key_pair(&mut rng);
+        let (sk2, _) = generate_key_pair(&mut rng);
+        for i in 0..10 {
+            let input = [i as u8; 20];
+            let (b, blinded) = blind(&mut rng, &input[..i]).unwrap();
+            let (b2, blinded2) = blind(&mut rng, &input[..i]).unwrap();
+            assert!(blinded != blinded2);
+            let out = finalize(&input[..i], &b,
+                &blind_evaluate(&sk, &blinded).unwrap()).unwrap();
+            let out2 = finalize(&input[..i], &b2,
+                &blind_evaluate(&sk, &blinded2).unwrap()).unwrap();
+            assert!(out == out2);
+            assert!(out == evaluate(&sk, &input[..i]).unwrap());
+            assert!(out != evaluate(&sk2, &input[..i]).unwrap());
+            assert!(out != evaluate(&sk, &input[..(i + 1)]).unwrap());
+        }
+
+        // Invalid or neutral elements are rejected.
+        let (b, _) = blind(&mut rng, b"x").unwrap();
+        assert!(blind_evaluate(&sk, &[0u8; 32])
+            == Err(OprfError::Deserialize));
+        assert!(blind_evaluate(&sk, &[0xFFu8; 32])
+            == Err(OprfError::Deserialize));
+        assert!(finalize(b"x", &b, &[0u8; 32])
+            == Err(OprfError::Deserialize));
+
+        // Overlong inputs are rejected.
+        let long = [0u8; 0x10000];
+        assert!(blind(&mut rng, &long).err() == Some(OprfError::InvalidInput));
+        assert!(evaluate(&sk, &long) == Err(OprfError::InvalidInput));
+        assert!(derive_key_pair(&[0u8; 32], &long).err()
+            == Some(OprfError::DeriveKeyPair));
+    }
+}
@@ -42,7 +42,8 @@
 //! accept a sequence of 33 or 65 zeros as a valid input. Thus, point
 //! decoding is stricly standards-conforming. All decoding operations
 //! enforce canonicality of encoding, and verify that the point is indeed
-//! on the curve.
+//! on the curve. `Point::decode_sec1()` additionally accepts the rarely
+//! used "hybrid" format, and reports decoding failures in detail.
 //!
 //! The `PrivateKey` structure represents a private key for the ECDSA
 //! signature algorithm; it is basically a wrapper around a private
@@ -61,6 +62,13 @@
 //! `decode()` explicitly rejects the point-at-infinity: an ECDSA public
 //! key is never the identity point.
 //!
+//! Keys can be tweaked with a scalar `t`: `PrivateKey::tweak_add()` and
+//! `PublicKey::tweak_add()` compute `x + t` and `P + t*G`, while
+//! `PrivateKey::tweak_mul()` and `PublicKey::tweak_mul()` compute `x*t`
+//! and `t*P`, so that tweaking a private key and its public key with the
+//! same `t` yields a matching key pair. These functions report a zero
+//! (or point-at-infinity) result as a failure.
+//!
 //! ECDSA signatures are generated with `PrivateKey::sign_hash()`, and
 //! verified with `PublicKey::verify_hash()`. The signature process is
 //! deterministic, using the SHA-256 function, following the description
@@ -76,6 +84,71 @@
 //! and `s` starts), and that the two `r` and `s` values are still in the
 //! proper range (i.e. lower than the curve order).
 //!
+//! `PrivateKey::sign_with_hash()` and `PublicKey::verify_with_hash()`
+//! hash the message themselves, with a hash function selected by a
+//! `HashId` (SHA-224, SHA-256, SHA-384 or SHA-512); the same function is
+//! used for the RFC 6979 nonce derivation, so that, e.g., P-256 with
+//! SHA-384 yields the signatures of RFC 6979, section A.2.5. A digest
+//! longer than 256 bits is truncated to its leftmost 256 bits.
+//!
+//! When the (off by default) `insecure-test-apis` feature is enabled,
+//! `PrivateKey::sign_with_nonce()` signs with a caller-provided
+//! nonce. This is meant only for reproducing test vectors; misuse
+//! reveals the private key.
+//!
+//! `PrivateKey::sign_hedged()` is a variant which mixes 32 bytes of
+//! additional entropy into the nonce derivation (RFC 6979, section 3.6),
+//! for protection against fault attacks on deterministic signing.
+//! `PrivateKey::sign_hash_checked()` is another countermeasure: it
+//! verifies the new signature against the caller's trusted public key
+//! before returning it, and reports an error (`SignError`) instead of a
+//! faulty signature.
+//!
+//! The `ecdh()` function performs an ECDH key exchange, returning the x
+//! coordinate of the shared point (as in SEC 1 and NIST SP 800-56A);
+//! `generate_ephemeral()` makes a new key pair for that usage.
+//!
+//! For keys used on hardware shared with potential attackers,
+//! `PrivateKey::sign_hash_blinded()` and `ecdh_blinded()` return the
+//! same values as `sign_hash()` and `ecdh()`, but with the multiplication
+//! by the secret scalar randomized with an extra RNG: the scalar is
+//! split into two random shares, and the projective coordinates of the
+//! initial points are scaled by random field elements (see
+//! `Point::mul_blinded()` and `Point::mulgen_blinded()`). This is a
+//! countermeasure against power and electromagnetic analysis; it about
+//! doubles the cost of the multiplication.
+//!
+//! Public keys can be recovered from signatures (SEC 1, section 4.1.6)
+//! with `PublicKey::recover_from_signature()`, given a 2-bit recovery
+//! identifier that the signer computes with
+//! `PublicKey::compute_recovery_id()`.
+//!
+//! For any valid signature (r, s), the pair (r, n - s) is also valid.
+//! `normalize_s()` converts a signature to its "low-s" form (s <= n/2),
+//! and `has_low_s()` tests whether a signature is in that form;
+//! `PrivateKey::sign_hash_low_s()` always produces low-s signatures, and
+//! `PublicKey::verify_hash_with_policy()` can be used to reject high-s
+//! signatures.
+//!
+//! `verify_batch()` verifies many ECDSA signatures at once, with a
+//! single multi-scalar multiplication over random linear combinations of
+//! the verification equations; each signature must come with its
+//! recovery identifier, so that the point R can be rebuilt. If a batch
+//! fails, `verify_batch_find_invalid()` locates the invalid entries.
+//! These functions require heap allocation support.
+//!
+//! Signatures can be converted to and from the ASN.1 DER format (as used
+//! in X.509 certificates, or by OpenSSL) with `signature_to_der()` and
+//! `signature_from_der()`; see the `der` module for details.
+//!
+//! Keys can be exchanged with OpenSSL, WebCrypto and other tools in the
+//! standard DER formats: `PublicKey::to_spki_der()` and
+//! `PublicKey::from_spki_der()` handle the X.509 `SubjectPublicKeyInfo`
+//! structure, while `PrivateKey::to_pkcs8_der()` and
+//! `PrivateKey::from_pkcs8_der()` handle the PKCS#8 `PrivateKeyInfo`
+//! structure (with an RFC 5915 `ECPrivateKey` inside). Only the named
+//! curve prime256v1 is supported.
+//!
 //! # Truncated Signatures
 //!
 //! The `PublicKey::verify_trunc_hash()` function supports _truncated
@@ -117,8 +190,10 @@
 
 use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use super::field::{GFp256, ModInt256};
-use sha2::{Sha256, Sha512, Digest};
-use super::{CryptoRng, RngCore};
+use sha2::{Sha224, Sha256, Sha384, Sha512, Digest};
+use sha2::digest::{Output, core_api::BlockSizeUser};
+use super::{CryptoRng, RngCore, DecodeError, ParseError, HashId, SignError};
+use super::der::{self, DerError};
 
 #[cfg(feature = "alloc")]
 use crate::Vec;
@@ -265,7 +340,7 @@ impl Point {
     /// The (very rarely encountered) "hybrid" encoding (like
     /// uncompressed, but the least significant bit of y is also copied
     /// into the first byte, which has value 0x06 or 0x07) is not
-    /// supported.
+    /// supported; see `decode_sec1()`.
     ///
     /// On success, this structure is set to the decoded point, and
     /// 0xFFFFFFFF is returned. On failure, this structure is set to the
@@ -359,7 +434,7 @@ impl Point {
     /// The (very rarely encountered) "hybrid" encoding (like
     /// uncompressed, but the least significant bit of y is also copied
     /// into the first byte, which has value 0x06 or 0x07) is not
-    /// supported.
+    /// supported; see `decode_sec1()`.
     ///
     /// On success, the decoded point is returned; on failure, `None` is
     /// returned. A failure is reported if the coordinates can be decoded
@@ -378,6 +453,81 @@ impl Point {
         }
     }
 
+    /// Tries to decode a point, with detailed error reporting.
+    ///
+    /// This accepts the same encodings as `decode()`. Failures are
+    /// reported as:
+    ///
+    ///  - `BadLength`: the input length is not 1, 33 or 65 bytes;
+    ///
+    ///  - `BadHeader`: the first byte is not 0x00 (1-byte input), 0x02
+    ///    or 0x03 (33-byte input), or 0x04 (65-byte input);
+    ///
+    ///  - `NonCanonicalField`: a coordinate is not lower than the field
+    ///    modulus;
+    ///
+    ///  - `NotOnCurve`: there is no curve point with the provided `x`
+    ///    coordinate (compressed encoding), or the coordinates do not
+    ///    match the curve equation (uncompressed encoding).
+    ///
+    /// This function is meant for diagnostics, e.g. to investigate
+    /// interoperability issues; the cause of a failure is computed with
+    /// variable-time code. `decode()` and `set_decode()` should be used
+    /// in production code.
+    pub fn decode_verbose(buf: &[u8]) -> Result<Point, DecodeError> {
+        if let Some(P) = Self::decode(buf) {
+            return Ok(P);
+        }
+        let hdr_ok = match buf.len() {
+            1 => false,
+            33 => (buf[0] & 0xFE) == 0x02,
+            65 => buf[0] == 0x04,
+            _ => { return Err(DecodeError::BadLength); }
+        };
+        if !hdr_ok {
+            return Err(DecodeError::BadHeader);
+        }
+        for j in (1..buf.len()).step_by(32) {
+            if GFp256::decode32(&bswap32(&buf[j..(j + 32)])).1 == 0 {
+                return Err(DecodeError::NonCanonicalField);
+            }
+        }
+        Err(DecodeError::NotOnCurve)
+    }
+
+    /// Decodes a point from any of the SEC 1 encodings.
+    ///
+    /// This function accepts the same encodings as `decode()`
+    /// (point-at-infinity, compressed and uncompressed), and also the
+    /// hybrid encoding: a byte of value 0x06 or 0x07, followed by
+    /// exactly 64 bytes (unsigned big-endian encodings of x and y),
+    /// where the low bit of the first byte must match the least
+    /// significant bit of y. Coordinates must be canonical and match the
+    /// curve equation, for all encodings. The single-byte encoding of
+    /// the point-at-infinity (0x00) is accepted and yields the neutral
+    /// point; since it is the only encoding of that point, callers which
+    /// must reject it (e.g. for public keys) can use `isneutral()` on the
+    /// result.
+    ///
+    /// Failures are reported as with `decode_verbose()`; a hybrid
+    /// encoding whose first byte does not match the parity of y is
+    /// reported as `NonCanonicalEncoding`. This function uses
+    /// variable-time code for classifying the encoding and reporting
+    /// errors; it does not leak the value of the decoded point.
+    pub fn decode_sec1(buf: &[u8]) -> Result<Point, DecodeError> {
+        if buf.len() == 65 && (buf[0] & 0xFE) == 0x06 {
+            let mut tmp = [0u8; 65];
+            tmp.copy_from_slice(buf);
+            tmp[0] = 0x04;
+            let P = Self::decode_verbose(&tmp)?;
+            if ((buf[0] ^ buf[64]) & 1) != 0 {
+                return Err(DecodeError::NonCanonicalEncoding);
+            }
+            return Ok(P);
+        }
+        Self::decode_verbose(buf)
+    }
+
     /// Encodes this point in compressed format (33 bytes).
     ///
     /// If the point is the neutral then `[0u8; 33]` is returned, which
@@ -796,6 +946,36 @@ impl Point {
         self.Z.iszero()
     }
 
+    /// Multiplies this point by the cofactor of the group.
+    ///
+    /// This group has prime order (the cofactor is 1), so the point is
+    /// returned unchanged. This function is provided so that generic
+    /// code can clear cofactors uniformly across all curve modules.
+    #[inline(always)]
+    pub fn mul_by_cofactor(self) -> Self {
+        self
+    }
+
+    /// Multiplies this point by the group order.
+    ///
+    /// Since this group has prime order, the result is always the
+    /// neutral. This function is provided for uniformity with the curve
+    /// modules whose groups have a cofactor.
+    #[inline(always)]
+    pub fn mul_by_order(self) -> Self {
+        Self::NEUTRAL
+    }
+
+    /// Tests whether this point is in the subgroup of prime order.
+    ///
+    /// Since this group has prime order, 0xFFFFFFFF is always returned.
+    /// This function is provided for uniformity with the curve modules
+    /// whose groups have a cofactor.
+    #[inline(always)]
+    pub fn is_in_subgroup(self) -> u32 {
+        0xFFFFFFFF
+    }
+
     // Conditionally copies the provided point (`P`) into `self`.
     //
     //  - If `ctl` is 0xFFFFFFFF, then the value of `P` is copied into `self`.
@@ -966,28 +1146,77 @@ impl Point {
         self.set_add_affine(&P, rz);
     }
 
+    /// Computes the points i*(2^130)*G for i = 1 to 16, in affine
+    /// coordinates; this replaces the `PRECOMP_G130` table when small
+    /// tables are used.
+    #[cfg(feature = "small_tables_p256")]
+    fn make_precomp_g130() -> [PointAffine; 16] {
+        let P = Self::BASE.xdouble(130);
+        let mut tp = [P; 16];
+        for i in 1..16 {
+            tp[i] = tp[i - 1] + P;
+        }
+        let mut iZ = [GFp256::ZERO; 16];
+        for i in 0..16 {
+            iZ[i] = tp[i].Z;
+        }
+        GFp256::batch_invert(&mut iZ[..]);
+        let mut win = [PointAffine { x: GFp256::ZERO, y: GFp256::ZERO }; 16];
+        for i in 0..16 {
+            win[i] = PointAffine { x: tp[i].X * iZ[i], y: tp[i].Y * iZ[i] };
+        }
+        win
+    }
+
     /// Sets this point by multiplying the conventional generator by the
     /// provided scalar.
     ///
     /// This operation is constant-time. It is faster than using the
     /// generic multiplication on `Self::BASE`.
     pub fn set_mulgen(&mut self, n: &Scalar) {
+        self.set_mulgen_inner(n, None);
+    }
+
+    // Multiplies the generator by the scalar n. If z is provided, then
+    // the coordinates of the initial point are scaled by z (which must
+    // not be zero); this does not change the result.
+    fn set_mulgen_inner(&mut self, n: &Scalar, z: Option<&GFp256>) {
         // Recode the scalar into 52 signed digits.
         let sd = Self::recode_scalar(n);
 
         // We process four chunks in parallel. Each chunk is 13 digits.
-        *self = Self::lookup_affine_proj(&PRECOMP_G, sd[12]);
-        self.set_lookup_affine_add(&PRECOMP_G65, sd[25]);
-        self.set_lookup_affine_add(&PRECOMP_G130, sd[38]);
-        self.set_lookup_affine_add(&PRECOMP_G195, sd[51]);
+        #[cfg(not(feature = "small_tables_p256"))]
+        {
+            *self = Self::lookup_affine_proj(&PRECOMP_G, sd[12]);
+            if let Some(z) = z {
+                self.set_rerandomize(z);
+            }
+            self.set_lookup_affine_add(&PRECOMP_G65, sd[25]);
+            self.set_lookup_affine_add(&PRECOMP_G130, sd[38]);
+            self.set_lookup_affine_add(&PRECOMP_G195, sd[51]);
+
+            // Process the digits in high-to-low order.
+            for i in (0..12).rev() {
+                self.set_xdouble(5);
+                self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
+                self.set_lookup_affine_add(&PRECOMP_G65, sd[i + 13]);
+                self.set_lookup_affine_add(&PRECOMP_G130, sd[i + 26]);
+                self.set_lookup_affine_add(&PRECOMP_G195, sd[i + 39]);
+            }
+        }
 
-        // Process the digits in high-to-low order.
-        for i in (0..12).rev() {
-            self.set_xdouble(5);
-            self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
-            self.set_lookup_affine_add(&PRECOMP_G65, sd[i + 13]);
-            self.set_lookup_affine_add(&PRECOMP_G130, sd[i + 26]);
-            self.set_lookup_affine_add(&PRECOMP_G195, sd[i + 39]);
+        // With the small tables, digits are processed with Horner's
+        // rule and a single table (about 4 times as many doublings).
+        #[cfg(feature = "small_tables_p256")]
+        {
+            *self = Self::lookup_affine_proj(&PRECOMP_G, sd[51]);
+            if let Some(z) = z {
+                self.set_rerandomize(z);
+            }
+            for i in (0..51).rev() {
+                self.set_xdouble(5);
+                self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
+            }
         }
     }
 
@@ -1003,6 +1232,77 @@ impl Point {
         P
     }
 
+    // Scales the coordinates of this point by the non-zero field element
+    // z; the represented point is unchanged.
+    fn set_rerandomize(&mut self, z: &GFp256) {
+        self.X *= z;
+        self.Y *= z;
+        self.Z *= z;
+    }
+
+    /// Multiplies this point by a scalar (in place), with blinding.
+    ///
+    /// The result is the same as with `set_mul()`, but the computation
+    /// is randomized with the provided RNG, as a countermeasure against
+    /// power and electromagnetic analysis: the scalar n is split into
+    /// (n - r) + r for a random r, and the two partial products start
+    /// from copies of this point whose projective coordinates are
+    /// scaled by random non-zero field elements. This is about twice as
+    /// expensive as `set_mul()`.
+    pub fn set_mul_blinded<T: CryptoRng + RngCore>(&mut self, n: &Scalar,
+        rng: &mut T)
+    {
+        self.set_mul_with_blinding(n, &Blinding::new(rng));
+    }
+
+    /// Multiplies this point by a scalar, with blinding.
+    ///
+    /// See `set_mul_blinded()` for details.
+    #[inline]
+    pub fn mul_blinded<T: CryptoRng + RngCore>(self, n: &Scalar,
+        rng: &mut T) -> Self
+    {
+        let mut P = self;
+        P.set_mul_blinded(n, rng);
+        P
+    }
+
+    // Blinded multiplication, with the provided blinding values.
+    fn set_mul_with_blinding(&mut self, n: &Scalar, b: &Blinding) {
+        let mut P = *self;
+        P.set_rerandomize(&b.z1);
+        P.set_mul(&(*n - b.r));
+        self.set_rerandomize(&b.z2);
+        self.set_mul(&b.r);
+        self.set_add(&P);
+    }
+
+    /// Creates a point by multiplying the conventional generator by the
+    /// provided scalar, with blinding.
+    ///
+    /// The result is the same as with `mulgen()`, but the computation is
+    /// randomized with the provided RNG, as a countermeasure against
+    /// power and electromagnetic analysis: the scalar n is split into
+    /// (n - r) + r for a random r, and the coordinates of the initial
+    /// point of each partial product are scaled by random non-zero
+    /// field elements. This is about twice as expensive as `mulgen()`.
+    pub fn mulgen_blinded<T: CryptoRng + RngCore>(n: &Scalar, rng: &mut T)
+        -> Self
+    {
+        Self::mulgen_with_blinding(n, &Blinding::new(rng))
+    }
+
+    // Blinded multiplication of the generator, with the provided
+    // blinding values.
+    fn mulgen_with_blinding(n: &Scalar, b: &Blinding) -> Self {
+        let mut P = Self::NEUTRAL;
+        P.set_mulgen_inner(&(*n - b.r), Some(&b.z1));
+        let mut Q = Self::NEUTRAL;
+        Q.set_mulgen_inner(&b.r, Some(&b.z2));
+        P.set_add(&Q);
+        P
+    }
+
     /// 5-bit wNAF recoding of a scalar; output is a sequence of 257
     /// digits.
     ///
@@ -1074,6 +1374,19 @@ impl Point {
         sd
     }
 
+    /// Computes the window of odd multiples of a point, for 5-bit wNAF:
+    /// `win[i] = (2*i+1)*P` (for `i` = 0 to 7).
+    #[cfg(feature = "alloc")]
+    fn make_window_vartime(P: &Self) -> [Self; 8] {
+        let mut win = [Self::NEUTRAL; 8];
+        let Q = P.double();
+        win[0] = *P;
+        for i in 1..8 {
+            win[i] = win[i - 1] + Q;
+        }
+        win
+    }
+
     /// Given scalars `u` and `v`, sets this point to `u*self + v*G`
     /// (with `G` being the conventional generator point, aka
     /// `Self::BASE`).
@@ -1268,6 +1581,12 @@ impl Point {
             win2[i] = win2[i - 1] + Q2;
         }
 
+        // Window for (2^130)*G (recomputed with the small tables).
+        #[cfg(not(feature = "small_tables_p256"))]
+        let win_g130 = &PRECOMP_G130;
+        #[cfg(feature = "small_tables_p256")]
+        let win_g130 = &Self::make_precomp_g130();
+
         // zz = true as long as the accumulator is the neutral; this
         // allows skipping the first sequence of doublings.
         let mut T = Self::NEUTRAL;
@@ -1319,9 +1638,9 @@ impl Point {
             }
             if e4 != 0 {
                 if e4 > 0 {
-                    T.set_add_affine(&PRECOMP_G130[e4 as usize - 1], 0);
+                    T.set_add_affine(&win_g130[e4 as usize - 1], 0);
                 } else {
-                    T.set_sub_affine(&PRECOMP_G130[(-e4) as usize - 1], 0);
+                    T.set_sub_affine(&win_g130[(-e4) as usize - 1], 0);
                 }
             }
         }
@@ -1914,7 +2233,7 @@ impl SubAssign<&Point> for Point {
 // ========================================================================
 
 /// A P-256 private key simply wraps around a scalar.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy)]
 pub struct PrivateKey {
     x: Scalar,   // secret scalar
 }
@@ -2005,6 +2324,38 @@ impl PrivateKey {
         PublicKey { point: Point::mulgen(&self.x) }
     }
 
+    /// Adds a tweak to this private key.
+    ///
+    /// The returned private key has secret scalar `x + t`, where `x` is
+    /// the secret scalar of this key; its public key is the output of
+    /// `PublicKey::tweak_add()` with the same tweak. If the sum is zero,
+    /// then `None` is returned. This function is constant-time;
+    /// side-channels may leak whether the result was zero, but not the
+    /// key or the tweak.
+    pub fn tweak_add(&self, t: &Scalar) -> Option<PrivateKey> {
+        let x = self.x + t;
+        if x.iszero() != 0 {
+            return None;
+        }
+        Some(PrivateKey { x })
+    }
+
+    /// Multiplies this private key by a tweak.
+    ///
+    /// The returned private key has secret scalar `x*t`, where `x` is
+    /// the secret scalar of this key; its public key is the output of
+    /// `PublicKey::tweak_mul()` with the same tweak. If the tweak is
+    /// zero, then `None` is returned. This function is constant-time;
+    /// side-channels may leak whether the tweak was zero, but not the
+    /// key or the tweak.
+    pub fn tweak_mul(&self, t: &Scalar) -> Option<PrivateKey> {
+        let x = self.x * t;
+        if x.iszero() != 0 {
+            return None;
+        }
+        Some(PrivateKey { x })
+    }
+
     /// Signs a hash value with ECDSA.
     ///
     /// The hash value may have an arbitrary length, but in general
@@ -2025,126 +2376,193 @@ impl PrivateKey {
     /// of the actual message, then the signature generation process
     /// follows RFC 6979.
     pub fn sign_hash(self, hv: &[u8], extra_rand: &[u8]) -> [u8; 64] {
+        self.sign_rfc6979::<Sha256>(hv, extra_rand, None)
+    }
 
-        // Feed a SHA-256 context with the starter block for HMAC/SHA-256,
-        // using a 32-byte key.
-        fn hmac_start(sh: &mut Sha256, key: &[u8; 32]) {
-            let mut tmp = [0x36u8; 64];
-            for i in 0..32 {
-                tmp[i] ^= key[i];
-            }
-            sh.update(&tmp);
-        }
+    /// Signs a hash value with ECDSA, with blinding.
+    ///
+    /// This computes exactly the same signature as `sign_hash()`, but
+    /// the multiplication of the generator by the per-signature nonce
+    /// is blinded with randomness from the provided RNG (see
+    /// `Point::mulgen_blinded()`). This is meant for keys used on
+    /// hardware shared with potential attackers, where power or
+    /// electromagnetic traces may be observed. The RNG output does not
+    /// influence the signature value.
+    pub fn sign_hash_blinded<T: CryptoRng + RngCore>(self, rng: &mut T,
+        hv: &[u8], extra_rand: &[u8]) -> [u8; 64]
+    {
+        let b = Blinding::new(rng);
+        self.sign_rfc6979::<Sha256>(hv, extra_rand, Some(&b))
+    }
 
-        // Finalize a HMAC/SHA-256 computation; the 32-byte key is provided
-        // again. The SHA-256 context is automatically reinitialized.
-        fn hmac_end(sh: &mut Sha256, key: &[u8; 32]) -> [u8; 32] {
-            let v = sh.finalize_reset();
-            let mut tmp = [0x5Cu8; 64];
-            for i in 0..32 {
-                tmp[i] ^= key[i];
-            }
-            sh.update(&tmp);
-            sh.update(&v);
-            sh.finalize_reset().into()
-        }
+    /// Signs a hash value with ECDSA, and verifies the signature before
+    /// returning it.
+    ///
+    /// The signature is computed as with `sign_hash()`, then verified
+    /// against the provided public key `pk`, which MUST be the trusted
+    /// public key of this private key (e.g. the one stored or published
+    /// along with it); it is not recomputed from the private key, so that
+    /// a fault on the private scalar is also detected. If the
+    /// verification fails, which may happen only if the computation was
+    /// disrupted (e.g. by a hardware glitch, or a deliberate fault
+    /// injection) or if `pk` does not match the private key, then
+    /// `SignError::VerificationFailed` is returned and the faulty
+    /// signature is discarded: with deterministic signatures, a faulty
+    /// signature and a correct one for the same message may reveal the
+    /// private key.
+    ///
+    /// The cost is about three and a half times that of `sign_hash()`, since the
+    /// verification dominates.
+    pub fn sign_hash_checked(self, pk: &PublicKey, hv: &[u8],
+        extra_rand: &[u8]) -> Result<[u8; 64], SignError>
+    {
+        check_signature(pk, self.sign_hash(hv, extra_rand), hv)
+    }
 
-        // Convert the input hash value into an integer modulo n:
-        //  - If hv.len() > 32, keep only the leftmost 32 bytes.
-        //  - Interpret the value as big-endian.
-        //  - Reduce the integer modulo n.
-        // The result is h. We also re-encode h over 32 bytes (exactly),
-        // in unsigned big-endian notation, to get hb (in RFC 6979
-        // notations, h = bits2int(hv), and hb = bits2octets(hv)).
-        let mut tmp = [0u8; 32];
-        if hv.len() >= 32 {
-            tmp[..].copy_from_slice(&hv[..32]);
-        } else {
-            tmp[(32 - hv.len())..32].copy_from_slice(hv);
-        }
-        let h = Scalar::decode_reduce(&bswap32(&tmp));
-        let hb = bswap32(&h.encode());
+    /// Signs a hash value with hedged ECDSA.
+    ///
+    /// This is the RFC 6979 process with the additional input of
+    /// section 3.6: the 32-byte `extra` value is fed into the HMAC_DRBG
+    /// along with the private key and the hash value. The resulting
+    /// signatures are standard and verifiable with
+    /// `PublicKey::verify_hash()`. If `extra` is freshly random, then
+    /// the per-signature secret nonce is unpredictable even for an
+    /// attacker who can replay the signature computation (e.g. through
+    /// fault attacks or virtual machine snapshots); if `extra` is fixed
+    /// (even all-zero), then the process is still deterministic and
+    /// safe. Note that an all-zero `extra` is not equivalent to no
+    /// additional input at all: the signature then differs from the one
+    /// returned by `sign_hash()` with an empty `extra_rand`.
+    pub fn sign_hedged(self, hv: &[u8], extra: &[u8; 32]) -> [u8; 64] {
+        self.sign_hash(hv, extra)
+    }
 
-        // Get the byte representation of the private key itself.
-        let xb = bswap32(&self.x.encode());
+    /// Signs a hash value with ECDSA, with a normalized `s` value.
+    ///
+    /// This function computes the signature as `sign_hash()`, then
+    /// replaces `s` with n - s if s > n/2; the returned signature thus
+    /// always has a low `s` (see `has_low_s()`), as required by
+    /// verifiers that reject high-s signatures (e.g.
+    /// `PublicKey::verify_hash_with_policy()` with `allow_high_s` set to
+    /// `false`). The signature remains valid for `verify_hash()`.
+    pub fn sign_hash_low_s(self, hv: &[u8], extra_rand: &[u8]) -> [u8; 64] {
+        normalize_s(&self.sign_hash(hv, extra_rand)).0
+    }
 
-        // Generate a pseudorandom k as per RFC 6979, section 3.2.
-        let mut sh = Sha256::new();
-        let V = [0x01u8; 32];
-        let K = [0x00u8; 32];
+    /// Signs a prehashed message with ECDSA.
+    ///
+    /// `digest` is the output of the hash function `D` over the message
+    /// (e.g. a SHA-384 or SHA-512 value obtained from an external
+    /// device). It is converted into an integer with the bits2int()
+    /// rules of RFC 6979 (section 2.3.2): if it is longer than 256 bits,
+    /// only its leftmost 256 bits are used; if it is shorter, it is
+    /// interpreted as is (unsigned big-endian). The per-signature nonce
+    /// is derived as per RFC 6979, with HMAC over `D`; thus, the
+    /// signature is the RFC 6979 signature for the hash function `D`.
+    /// The type `D` is used only for nonce derivation; the digest itself
+    /// is not recomputed, and its length is not checked against the
+    /// output length of `D`.
+    ///
+    /// The signature format is the same as with `sign_hash()`; for a
+    /// SHA-256 digest, `sign_prehashed::<Sha256>(hv)` is equivalent to
+    /// `sign_hash(hv, &[])`.
+    pub fn sign_prehashed<D: Digest + BlockSizeUser>(self, digest: &[u8])
+        -> [u8; 64]
+    {
+        self.sign_rfc6979::<D>(digest, &[], None)
+    }
 
-        // 3.2.d
-        hmac_start(&mut sh, &K);
-        sh.update(&V);
-        sh.update(&[0x00u8]);
-        sh.update(&xb);
-        sh.update(&hb);
-        if extra_rand.len() > 0 {
-            sh.update(&extra_rand);
+    /// Signs a message with ECDSA, using the specified hash function.
+    ///
+    /// The message `msg` is hashed with the function identified by
+    /// `hash_id`, and the digest is signed as with `sign_prehashed()`,
+    /// with the same hash function for the RFC 6979 nonce derivation.
+    /// Thus, this is deterministic ECDSA as specified in RFC 6979 for
+    /// that hash function; with `HashId::Sha256`, the signature is the
+    /// same as `sign_hash(&sha256(msg), &[])`.
+    pub fn sign_with_hash(self, msg: &[u8], hash_id: HashId) -> [u8; 64] {
+        match hash_id {
+            HashId::Sha224 =>
+                self.sign_prehashed::<Sha224>(&Sha224::digest(msg)),
+            HashId::Sha256 =>
+                self.sign_prehashed::<Sha256>(&Sha256::digest(msg)),
+            HashId::Sha384 =>
+                self.sign_prehashed::<Sha384>(&Sha384::digest(msg)),
+            HashId::Sha512 =>
+                self.sign_prehashed::<Sha512>(&Sha512::digest(msg)),
         }
-        let K = hmac_end(&mut sh, &K);
+    }
 
-        // 3.2.e
-        hmac_start(&mut sh, &K);
-        sh.update(&V);
-        let V = hmac_end(&mut sh, &K);
+    /// Signs a hash value with ECDSA, using the provided nonce.
+    ///
+    /// **This function is catastrophically insecure if misused.** It
+    /// exists only for reproducing externally produced test vectors and
+    /// for audits, and is available only when the (off by default)
+    /// `insecure-test-apis` feature is enabled. The per-signature nonce
+    /// `k` MUST be secret, uniformly random (or derived as per RFC 6979)
+    /// and never reused: two signatures with the same `k`, or a single
+    /// signature with a known or biased `k`, reveal the private key.
+    ///
+    /// The hash value `hv` is processed as in `sign_hash()`. `None` is
+    /// returned if `k` is zero, or if the signature would have `r = 0`
+    /// or `s = 0`.
+    #[cfg(feature = "insecure-test-apis")]
+    pub fn sign_with_nonce(self, hv: &[u8], k: &Scalar) -> Option<[u8; 64]> {
+        self.sign_with_k(&hash_to_scalar(hv), k, None)
+    }
 
-        // 3.2.f
-        hmac_start(&mut sh, &K);
-        sh.update(&V);
-        sh.update(&[0x01u8]);
-        sh.update(&xb);
-        sh.update(&hb);
-        if extra_rand.len() > 0 {
-            sh.update(&extra_rand);
+    // ECDSA signature generation with the nonce k, for the hash value h
+    // (already converted to a scalar). If blinding values are provided,
+    // then they are used for computing k*G. None is returned if k is
+    // zero, or if r or s is zero.
+    fn sign_with_k(self, h: &Scalar, k: &Scalar, blind: Option<&Blinding>)
+        -> Option<[u8; 64]>
+    {
+        if k.iszero() != 0 {
+            return None;
         }
-        let mut K = hmac_end(&mut sh, &K);
 
-        // 3.2.g
-        hmac_start(&mut sh, &K);
-        sh.update(&V);
-        let mut V = hmac_end(&mut sh, &K);
+        // R = k*G; then encode x(R), and decode-reduce as a scalar
+        let R = match blind {
+            Some(b) => Point::mulgen_with_blinding(k, b),
+            None => Point::mulgen(k),
+        };
+        let xR_le = bswap32(&R.encode_compressed()[1..33]);
+        let r = Scalar::decode_reduce(&xR_le);
+
+        // Compute s.
+        let s = (*h + self.x * r) / *k;
+
+        // If s and r are both non-zero, then we have our signature.
+        if (r.iszero() | s.iszero()) != 0 {
+            return None;
+        }
+        let mut sig = [0u8; 64];
+        sig[..32].copy_from_slice(&bswap32(&r.encode()));
+        sig[32..].copy_from_slice(&bswap32(&s.encode()));
+        Some(sig)
+    }
+
+    // RFC 6979 signature generation, with HMAC over the hash function D,
+    // optional additional input (section 3.6), and optional blinding.
+    fn sign_rfc6979<D: Digest + BlockSizeUser>(self, hv: &[u8], extra: &[u8],
+        blind: Option<&Blinding>) -> [u8; 64]
+    {
+        // Convert the input hash value into an integer modulo n:
+        //  - If hv.len() > 32, keep only the leftmost 32 bytes.
+        //  - Interpret the value as big-endian.
+        //  - Reduce the integer modulo n.
+        // The result is h. Since n has size exactly 256 bits, truncation
+        // to the leftmost 32 bytes is bits2int().
+        let h = hash_to_scalar(hv);
 
-        // 3.2.h
-        // We loop in case we get a zero for k or for s (either case is
+        // We loop in case we get a zero for r or for s (either case is
         // so improbable that it won't happen in practice).
+        let mut ng = Rfc6979::<D>::new(&self.x, &h, extra);
         loop {
-            // Get k. Since SHA-256 outputs 256 bits, and the curve order
-            // has size 256 bits as well, we only need one HMAC call, with
-            // no truncation.
-            hmac_start(&mut sh, &K);
-            sh.update(&V);
-            V[..].copy_from_slice(&hmac_end(&mut sh, &K));
-            let (k, cc) = Scalar::decode32(&bswap32(&V));
-            if cc != 0 && k.iszero() == 0 {
-                // We got k, compute the signature.
-
-                // R = k*G; then encode x(R), and decode-reduce as a scalar
-                let R = Point::mulgen(&k);
-                let xR_le = bswap32(&R.encode_compressed()[1..33]);
-                let r = Scalar::decode_reduce(&xR_le);
-
-                // Compute s.
-                let s = (h + self.x * r) / k;
-
-                // If s and r are both non-zero, then we have our signature.
-                if (r.iszero() | s.iszero()) == 0 {
-                    let mut sig = [0u8; 64];
-                    sig[..32].copy_from_slice(&bswap32(&r.encode()));
-                    sig[32..].copy_from_slice(&bswap32(&s.encode()));
-                    return sig;
-                }
+            if let Some(sig) = self.sign_with_k(&h, &ng.next_k(), blind) {
+                return sig;
             }
-
-            // Bad k, try again (very improbable).
-            hmac_start(&mut sh, &K);
-            sh.update(&V);
-            sh.update(&[0x00u8]);
-            let nK = hmac_end(&mut sh, &K);
-            K[..].copy_from_slice(&nK);
-            hmac_start(&mut sh, &K);
-            sh.update(&V);
-            V[..].copy_from_slice(&hmac_end(&mut sh, &K));
         }
     }
 
@@ -2227,112 +2645,957 @@ impl PrivateKey {
     }
 }
 
-impl PublicKey {
-
-    /// Decodes a public key from bytes.
-    ///
-    /// This function accepts both compressed (33 bytes) and uncompressed
-    /// (65 bytes) formats. The point is always verified to be a valid
-    /// curve point. Note that the neutral point (the
-    /// "point-at-infinity") is explicitly rejected.
-    pub fn decode(buf: &[u8]) -> Option<Self> {
-        let point = Point::decode(buf)?;
-        if point.isneutral() != 0 {
-            return None;
-        }
-        Some(Self { point })
+// Verify-after-sign: the signature sig (over the hash value hv) is
+// returned only if it is valid for the public key pk.
+fn check_signature(pk: &PublicKey, sig: [u8; 64], hv: &[u8])
+    -> Result<[u8; 64], SignError>
+{
+    if pk.verify_hash(&sig, hv) {
+        Ok(sig)
+    } else {
+        Err(SignError::VerificationFailed)
     }
+}
 
-    /// Encodes this public key into the compressed format (33 bytes).
-    ///
-    /// The first byte of the encoding always has value 0x02 or 0x03.
-    pub fn encode_compressed(self) -> [u8; 33] {
-        self.point.encode_compressed()
-    }
+// Random values for blinded scalar multiplications: the scalar n is
+// split into (n - r) + r, and the initial points of the two partial
+// products have their coordinates scaled by z1 and z2 (both non-zero).
+struct Blinding {
+    r: Scalar,
+    z1: GFp256,
+    z2: GFp256,
+}
 
-    /// Encodes this public key into the compressed format (65 bytes).
-    ///
-    /// The first byte of the encoding always has value 0x04.
-    pub fn encode_uncompressed(self) -> [u8; 65] {
-        self.point.encode_uncompressed()
+impl Blinding {
+
+    fn new<T: CryptoRng + RngCore>(rng: &mut T) -> Self {
+        let mut tmp = [0u8; 64];
+        rng.fill_bytes(&mut tmp);
+        let r = Scalar::decode_reduce(&tmp);
+        rng.fill_bytes(&mut tmp);
+        let mut z1 = GFp256::decode_reduce(&tmp);
+        z1.set_cond(&GFp256::ONE, z1.iszero());
+        rng.fill_bytes(&mut tmp);
+        let mut z2 = GFp256::decode_reduce(&tmp);
+        z2.set_cond(&GFp256::ONE, z2.iszero());
+        Self { r, z1, z2 }
     }
+}
 
-    /// Verifies a signature on a given hashed message.
-    ///
-    /// The signature (`sig`) MUST have an even length; the first half of
-    /// the signature is interpreted as the "r" integer, while the second
-    /// half is "s" (both use unsigned big-endian convention).
-    /// Out-of-range values are rejected. The hashed message is provided
-    /// as `hv`; it is nominally the output of a suitable hash function
-    /// (often SHA-256) computed over the actual message. This function
-    /// can tolerate arbitrary hash output lengths; however, for proper
-    /// security, the hash output must not be too short, and it must be
-    /// an actual hash function output, not raw structured data.
-    ///
-    /// Note: this function is not constant-time; it assumes that the
-    /// public key and signature value are public data.
-    pub fn verify_hash(self, sig: &[u8], hv: &[u8]) -> bool {
-        // Recover r and s as scalars. We truncate/pad them to 32 bytes
-        // (verifying that the removed bytes are all zeros), then decode
-        // them as scalars. Zeros and out-of-range values are rejected.
-        let sig_len = sig.len();
-        if (sig_len & 1) != 0 {
-            return false;
-        }
-        let rlen = sig_len >> 1;
-        let mut rb = [0u8; 32];
-        let mut sb = [0u8; 32];
-        if rlen > 32 {
-            for i in 0..(rlen - 32) {
-                if sig[i] != 0 || sig[rlen + i] != 0 {
-                    return false;
-                }
-            }
-            rb[..].copy_from_slice(&sig[(rlen - 32)..rlen]);
-            sb[..].copy_from_slice(&sig[(sig_len - 32)..sig_len]);
-        } else {
-            rb[(32 - rlen)..].copy_from_slice(&sig[..rlen]);
-            sb[(32 - rlen)..].copy_from_slice(&sig[rlen..]);
+// RFC 6979 nonce generator (section 3.2), with HMAC over the hash
+// function D, and optional additional input (section 3.6).
+struct Rfc6979<D: Digest + BlockSizeUser> {
+    K: Output<D>,
+    V: Output<D>,
+    started: bool,
+}
+
+impl<D: Digest + BlockSizeUser> Rfc6979<D> {
+
+    // Compute HMAC/D over the concatenation of the provided chunks.
+    // The key has the output length of D, which is never larger than
+    // the block length of D (for all SHA-2 and SHA-3 functions), hence
+    // it is used as is.
+    fn hmac(key: &[u8], data: &[&[u8]]) -> Output<D> {
+        let bs = D::block_size();
+        let mut tmp = [0u8; 256];
+        tmp[..key.len()].copy_from_slice(key);
+        for b in tmp[..bs].iter_mut() {
+            *b ^= 0x36;
         }
-        let (r, cr) = Scalar::decode32(&bswap32(&rb));
-        if cr == 0 || r.iszero() != 0 {
-            return false;
+        let mut sh = D::new();
+        sh.update(&tmp[..bs]);
+        for d in data {
+            sh.update(d);
         }
-        let (s, cs) = Scalar::decode32(&bswap32(&sb));
-        if cs == 0 || s.iszero() != 0 {
-            return false;
+        let v = sh.finalize();
+        for b in tmp[..bs].iter_mut() {
+            *b ^= 0x36 ^ 0x5C;
         }
+        let mut sh = D::new();
+        sh.update(&tmp[..bs]);
+        sh.update(&v);
+        sh.finalize()
+    }
 
-        // Convert the input hash value into an integer modulo n.
-        let mut tmp = [0u8; 32];
-        if hv.len() >= 32 {
-            tmp[..].copy_from_slice(&hv[..32]);
-        } else {
-            tmp[32 - hv.len() .. 32].copy_from_slice(hv);
+    // Initializes the generator for the private key x and the hash
+    // value h (steps 3.2.a to 3.2.g). h is re-encoded over 32 bytes, in
+    // unsigned big-endian notation, to get hb (in RFC 6979 notations,
+    // h = bits2int(hv), and hb = bits2octets(hv)).
+    fn new(x: &Scalar, h: &Scalar, extra: &[u8]) -> Self {
+        let hb = bswap32(&h.encode());
+
+        // Get the byte representation of the private key itself.
+        let xb = bswap32(&x.encode());
+
+        // V and K have the output length of D.
+        let mut V = Output::<D>::default();
+        for b in V.iter_mut() {
+            *b = 0x01;
         }
-        let h = Scalar::decode_reduce(&bswap32(&tmp));
+        let K = Output::<D>::default();
 
-        // Verification algorithm.
-        let w = Scalar::ONE / s;
-        let R = self.point.mul_add_mulgen_vartime(&(r * w), &(h * w));
-        let xR_le = bswap32(&R.encode_compressed()[1..33]);
-        let rr = Scalar::decode_reduce(&xR_le);
+        // 3.2.d
+        let K = Self::hmac(&K, &[&V, &[0x00u8], &xb, &hb, extra]);
 
-        // Signature is valid if the rebuilt r value (in rr) matches
-        // the one that was received.
-        return r.equals(rr) != 0;
+        // 3.2.e
+        V = Self::hmac(&K, &[&V]);
+
+        // 3.2.f
+        let K = Self::hmac(&K, &[&V, &[0x01u8], &xb, &hb, extra]);
+
+        // 3.2.g
+        V = Self::hmac(&K, &[&V]);
+
+        Self { K, V, started: false }
     }
 
-    /// Verifies a truncated signature on a given hashed message.
-    ///
-    /// The signature (`sig`) MUST have length 64 bytes and MUST have
-    /// been prepared with `PrivateKey::prepare_truncate()`. The last
-    /// `rm` bits are ignored (i.e. the last `floor(rm/8)` bytes are
-    /// ignored, as well as the top `rm%8` bits of the last non-ignored
-    /// byte.
-    ///
-    /// The hashed message is provided as `hv`; it is nominally the
-    /// output of a suitable hash function (often SHA-256) computed over
+    // Gets the next candidate nonce (3.2.h). Each call after the first
+    // one first updates K and V as for a rejected candidate; values
+    // which are zero or not lower than n are skipped.
+    fn next_k(&mut self) -> Scalar {
+        loop {
+            if self.started {
+                self.K = Self::hmac(&self.K, &[&self.V, &[0x00u8]]);
+                self.V = Self::hmac(&self.K, &[&self.V]);
+            }
+            self.started = true;
+
+            // Get k: HMAC outputs are concatenated until 256 bits are
+            // obtained, and only the leftmost 256 bits are kept.
+            let mut T = [0u8; 32];
+            let mut tlen = 0;
+            while tlen < 32 {
+                self.V = Self::hmac(&self.K, &[&self.V]);
+                let clen = core::cmp::min(32 - tlen, self.V.len());
+                T[tlen..(tlen + clen)].copy_from_slice(&self.V[..clen]);
+                tlen += clen;
+            }
+            let (k, cc) = Scalar::decode32(&bswap32(&T));
+            if cc != 0 && k.iszero() == 0 {
+                return k;
+            }
+        }
+    }
+}
+
+// Returns 0xFFFFFFFF if the provided scalar is "high" (greater than
+// n/2), 0x00000000 otherwise. For 0 < s < n, 2*s mod n is odd if and only
+// if 2*s >= n, i.e. s > n/2.
+fn scalar_is_high(s: Scalar) -> u32 {
+    (((s + s).encode()[0] & 1) as u32).wrapping_neg()
+}
+
+// Decodes the (r, s) pair of a signature (raw format) as scalars.
+// The two halves are truncated/padded to 32 bytes (verifying that the
+// removed bytes are all zeros), then decoded as scalars. Zeros and
+// out-of-range values are rejected.
+fn decode_rs(sig: &[u8]) -> Option<(Scalar, Scalar)> {
+    let sig_len = sig.len();
+    if (sig_len & 1) != 0 {
+        return None;
+    }
+    let rlen = sig_len >> 1;
+    let mut rb = [0u8; 32];
+    let mut sb = [0u8; 32];
+    if rlen > 32 {
+        for i in 0..(rlen - 32) {
+            if sig[i] != 0 || sig[rlen + i] != 0 {
+                return None;
+            }
+        }
+        rb[..].copy_from_slice(&sig[(rlen - 32)..rlen]);
+        sb[..].copy_from_slice(&sig[(sig_len - 32)..sig_len]);
+    } else {
+        rb[(32 - rlen)..].copy_from_slice(&sig[..rlen]);
+        sb[(32 - rlen)..].copy_from_slice(&sig[rlen..]);
+    }
+    let (r, cr) = Scalar::decode32(&bswap32(&rb));
+    if cr == 0 || r.iszero() != 0 {
+        return None;
+    }
+    let (s, cs) = Scalar::decode32(&bswap32(&sb));
+    if cs == 0 || s.iszero() != 0 {
+        return None;
+    }
+    Some((r, s))
+}
+
+// Rebuilds the point R of a signature from r and the recovery identifier
+// (bit 0 is the parity of y, bit 1 is set if x is r + n instead of r).
+// Point decoding rejects values which are not lower than p, or not the x
+// coordinate of a curve point.
+fn lift_r(r: &Scalar, recid: u8) -> Option<Point> {
+    if recid > 3 {
+        return None;
+    }
+    let mut eR = [0u8; 33];
+    eR[0] = 0x02 | (recid & 1);
+    let rb = bswap32(&r.encode());
+    if (recid & 2) == 0 {
+        eR[1..].copy_from_slice(&rb);
+    } else {
+        // Curve order n, in big-endian.
+        const N: [u8; 32] = [
+            0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00,
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xBC, 0xE6, 0xFA, 0xAD, 0xA7, 0x17, 0x9E, 0x84,
+            0xF3, 0xB9, 0xCA, 0xC2, 0xFC, 0x63, 0x25, 0x51,
+        ];
+        let mut cc = 0u32;
+        for i in (0..32).rev() {
+            let z = (rb[i] as u32) + (N[i] as u32) + cc;
+            eR[1 + i] = z as u8;
+            cc = z >> 8;
+        }
+        if cc != 0 {
+            return None;
+        }
+    }
+    Point::decode(&eR)
+}
+
+// Converts a hash value into an integer modulo n: if hv.len() > 32, only
+// the leftmost 32 bytes are kept; the value is interpreted with
+// big-endian convention, then reduced modulo n.
+fn hash_to_scalar(hv: &[u8]) -> Scalar {
+    let mut tmp = [0u8; 32];
+    if hv.len() >= 32 {
+        tmp[..].copy_from_slice(&hv[..32]);
+    } else {
+        tmp[32 - hv.len() .. 32].copy_from_slice(hv);
+    }
+    Scalar::decode_reduce(&bswap32(&tmp))
+}
+
+/// Encodes a signature (in the raw 64-byte format produced by
+/// `PrivateKey::sign_hash()`) into ASN.1 DER. The output buffer must
+/// have length at least 72 bytes; the number of written bytes is
+/// returned.
+pub fn signature_to_der(sig: &[u8; 64], out: &mut [u8]) -> usize {
+    der::encode_ecdsa_signature(sig, out)
+}
+
+/// Decodes an ASN.1 DER signature into the raw 64-byte format (as
+/// expected by `PublicKey::verify_hash()`). Decoding is strict, unless
+/// `lenient` is `true`, in which case non-minimal length and integer
+/// encodings are also accepted. The range of `r` and `s` is not
+/// checked here, only at verification time.
+pub fn signature_from_der(src: &[u8], lenient: bool)
+    -> Result<[u8; 64], DerError>
+{
+    let mut sig = [0u8; 64];
+    der::decode_ecdsa_signature(src, &mut sig, lenient)?;
+    Ok(sig)
+}
+
+// DER encoding of the AlgorithmIdentifier for P-256 keys (RFC 5480):
+// id-ecPublicKey (1.2.840.10045.2.1), with the namedCurve parameter
+// prime256v1 (1.2.840.10045.3.1.7).
+const OID_EC_PUBLIC_KEY: [u8; 7] = [
+    0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01 ];
+const OID_PRIME256V1: [u8; 8] = [
+    0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07 ];
+const ALGORITHM_ID: [u8; 21] = [
+    0x30, 0x13,
+    0x06, 0x07, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01,
+    0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07 ];
+
+// Decode an AlgorithmIdentifier at the start of `src`, and check that it
+// designates a P-256 key. The remaining bytes are returned.
+fn read_algorithm_id(src: &[u8]) -> Result<&[u8], DerError> {
+    let (ai, rem) = der::read_tlv(src, 0x30, false)?;
+    let (oid, ai) = der::read_tlv(ai, 0x06, false)?;
+    if oid != OID_EC_PUBLIC_KEY {
+        return Err(DerError::UnsupportedAlgorithm);
+    }
+    read_curve_id(ai)?;
+    Ok(rem)
+}
+
+// Decode ECParameters (which must be exactly the namedCurve OID for
+// P-256). Explicit parameters (SEQUENCE) and implicitlyCA (NULL) are
+// not supported.
+fn read_curve_id(src: &[u8]) -> Result<(), DerError> {
+    if !src.is_empty() && src[0] != 0x06 {
+        return Err(DerError::UnsupportedAlgorithm);
+    }
+    let (oid, rem) = der::read_tlv(src, 0x06, false)?;
+    if oid != OID_PRIME256V1 {
+        return Err(DerError::UnsupportedAlgorithm);
+    }
+    if !rem.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+    Ok(())
+}
+
+// Decode a BIT STRING that contains an encoded public key. The
+// remaining bytes are returned.
+fn read_public_key(src: &[u8]) -> Result<(PublicKey, &[u8]), DerError> {
+    let (bits, rem) = der::read_tlv(src, 0x03, false)?;
+    if bits.is_empty() || bits[0] != 0x00 {
+        return Err(DerError::InvalidEncoding);
+    }
+    match PublicKey::decode(&bits[1..]) {
+        Some(pk) => Ok((pk, rem)),
+        None => Err(DerError::InvalidKey),
+    }
+}
+
+impl PrivateKey {
+
+    /// Encodes this private key into a PKCS#8 `PrivateKeyInfo`
+    /// structure (RFC 5208), in DER.
+    ///
+    /// The algorithm is id-ecPublicKey with the named curve prime256v1
+    /// (RFC 5480), and the private key is a version-1 `ECPrivateKey`
+    /// (RFC 5915) that includes the public key (in uncompressed format)
+    /// but not the curve parameters. This is the format produced by
+    /// `openssl pkcs8 -topk8 -nocrypt` (and by WebCrypto for the "pkcs8"
+    /// export format); it always has length 138 bytes.
+    pub fn to_pkcs8_der(self) -> [u8; 138] {
+        let mut buf = [0u8; 138];
+        buf[..6].copy_from_slice(&[0x30, 0x81, 0x87, 0x02, 0x01, 0x00]);
+        buf[6..27].copy_from_slice(&ALGORITHM_ID);
+        buf[27..36].copy_from_slice(&[
+            0x04, 0x6D, 0x30, 0x6B, 0x02, 0x01, 0x01, 0x04, 0x20 ]);
+        buf[36..68].copy_from_slice(&self.encode());
+        buf[68..73].copy_from_slice(&[0xA1, 0x44, 0x03, 0x42, 0x00]);
+        buf[73..].copy_from_slice(&self.to_public_key().encode_uncompressed());
+        buf
+    }
+
+    /// Decodes a private key from a PKCS#8 `PrivateKeyInfo` structure
+    /// (RFC 5208), in DER.
+    ///
+    /// The algorithm must be id-ecPublicKey with the named curve
+    /// prime256v1 (RFC 5480); other algorithms and curves, and explicit
+    /// curve parameters, are reported as
+    /// `DerError::UnsupportedAlgorithm`. The inner version-1
+    /// `ECPrivateKey` (RFC 5915) may include the curve parameters (which
+    /// must then also designate prime256v1) and the public key (which
+    /// must then match the private key, otherwise `DerError::InvalidKey`
+    /// is returned). A private key octet string shorter than 32 bytes
+    /// (as produced by some old encoders) is accepted. Attributes and
+    /// the public key of a version-2 structure (RFC 5958) are ignored.
+    /// Decoding is strict DER, and the whole input must be consumed.
+    ///
+    /// This function is not constant-time with regard to the structure
+    /// of the input, but the private key value itself does not leak.
+    pub fn from_pkcs8_der(src: &[u8]) -> Result<Self, DerError> {
+        let (pki, rem) = der::read_tlv(src, 0x30, false)?;
+        if !rem.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+        let (version, pki) = der::read_tlv(pki, 0x02, false)?;
+        if version != [0x00] && version != [0x01] {
+            return Err(DerError::InvalidEncoding);
+        }
+        let pki = read_algorithm_id(pki)?;
+        let (ecpk, mut pki) = der::read_tlv(pki, 0x04, false)?;
+        if !pki.is_empty() && pki[0] == 0xA0 {
+            pki = der::read_tlv(pki, 0xA0, false)?.1;
+        }
+        if !pki.is_empty() && pki[0] == 0x81 && version == [0x01] {
+            pki = der::read_tlv(pki, 0x81, false)?.1;
+        }
+        if !pki.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+        Self::from_ec_private_key(ecpk)
+    }
+
+    // Decode an ECPrivateKey structure (RFC 5915).
+    fn from_ec_private_key(src: &[u8]) -> Result<Self, DerError> {
+        let (ecpk, rem) = der::read_tlv(src, 0x30, false)?;
+        if !rem.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+        let (version, ecpk) = der::read_tlv(ecpk, 0x02, false)?;
+        if version != [0x01] {
+            return Err(DerError::InvalidEncoding);
+        }
+        let (x, mut ecpk) = der::read_tlv(ecpk, 0x04, false)?;
+        if x.is_empty() || x.len() > 32 {
+            return Err(DerError::InvalidKey);
+        }
+        let mut xb = [0u8; 32];
+        xb[(32 - x.len())..].copy_from_slice(x);
+        let sk = match PrivateKey::decode(&xb) {
+            Some(sk) => sk,
+            None => return Err(DerError::InvalidKey),
+        };
+
+        // Optional curve parameters.
+        if !ecpk.is_empty() && ecpk[0] == 0xA0 {
+            let (params, rem) = der::read_tlv(ecpk, 0xA0, false)?;
+            read_curve_id(params)?;
+            ecpk = rem;
+        }
+
+        // Optional public key.
+        if !ecpk.is_empty() && ecpk[0] == 0xA1 {
+            let (pkv, rem) = der::read_tlv(ecpk, 0xA1, false)?;
+            let (pk, pkv) = read_public_key(pkv)?;
+            if !pkv.is_empty() {
+                return Err(DerError::InvalidEncoding);
+            }
+            if pk.point.equals(sk.to_public_key().point) == 0 {
+                return Err(DerError::InvalidKey);
+            }
+            ecpk = rem;
+        }
+        if !ecpk.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+        Ok(sk)
+    }
+}
+
+impl PublicKey {
+
+    /// Encodes this public key into a `SubjectPublicKeyInfo` structure
+    /// (RFC 5280), in DER.
+    ///
+    /// The algorithm is id-ecPublicKey with the named curve prime256v1
+    /// (RFC 5480), and the point uses the uncompressed format; the
+    /// output always has length 91 bytes.
+    pub fn to_spki_der(self) -> [u8; 91] {
+        let mut buf = [0u8; 91];
+        buf[..2].copy_from_slice(&[0x30, 0x59]);
+        buf[2..23].copy_from_slice(&ALGORITHM_ID);
+        buf[23..26].copy_from_slice(&[0x03, 0x42, 0x00]);
+        buf[26..].copy_from_slice(&self.encode_uncompressed());
+        buf
+    }
+
+    /// Decodes a public key from a `SubjectPublicKeyInfo` structure
+    /// (RFC 5280), in DER.
+    ///
+    /// The algorithm must be id-ecPublicKey with the named curve
+    /// prime256v1 (RFC 5480); other algorithms and curves, and explicit
+    /// curve parameters, are reported as
+    /// `DerError::UnsupportedAlgorithm`. The point may use the
+    /// compressed or uncompressed format; an invalid point (or the
+    /// point-at-infinity) is reported as `DerError::InvalidKey`.
+    /// Decoding is strict DER, and the whole input must be consumed.
+    pub fn from_spki_der(src: &[u8]) -> Result<Self, DerError> {
+        let (spki, rem) = der::read_tlv(src, 0x30, false)?;
+        if !rem.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+        let spki = read_algorithm_id(spki)?;
+        let (pk, spki) = read_public_key(spki)?;
+        if !spki.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+        Ok(pk)
+    }
+}
+
+/// Normalizes the `s` value of a signature (raw 64-byte format).
+///
+/// For any valid signature (r, s), the pair (r, n - s) is also valid
+/// for the same key and message. This function returns the signature
+/// with its low form, i.e. with `s` replaced with n - s if s > n/2,
+/// along with a flag which is `true` if `s` was modified. A signature
+/// whose `s` value is out of range (not lower than n) is returned
+/// unmodified (it will not pass verification anyway).
+pub fn normalize_s(sig: &[u8; 64]) -> ([u8; 64], bool) {
+    let (s, cs) = Scalar::decode32(&bswap32(&sig[32..]));
+    let mut nsig = *sig;
+    if (cs & scalar_is_high(s)) == 0 {
+        return (nsig, false);
+    }
+    nsig[32..].copy_from_slice(&bswap32(&(-s).encode()));
+    (nsig, true)
+}
+
+/// Tests whether the `s` value of a signature (raw 64-byte format) is
+/// in the low form (s <= n/2). Out-of-range values of `s` (not lower
+/// than n) are reported as not low.
+pub fn has_low_s(sig: &[u8; 64]) -> bool {
+    let (s, cs) = Scalar::decode32(&bswap32(&sig[32..]));
+    (cs & !scalar_is_high(s)) != 0
+}
+
+/// Verifies a batch of ECDSA signatures.
+///
+/// Each entry consists of a public key, a hashed message (32 bytes), a
+/// signature (raw 64-byte format) and the recovery identifier of the
+/// signature (as returned by `PublicKey::compute_recovery_id()`). The
+/// returned value is `true` if all signatures are valid, `false`
+/// otherwise (an empty batch is valid).
+///
+/// ECDSA verification only checks the x coordinate of the point
+/// `R_i = u1_i*G + u2_i*Q_i` (with `u1_i = h_i/s_i` and `u2_i = r_i/s_i`);
+/// the recovery identifier allows rebuilding the full point `R_i` from
+/// `r_i` (this is sometimes known as "ECDSA*" verification). The
+/// equations are then combined with random 128-bit coefficients `z_i`
+/// into a single multi-scalar multiplication:
+///
+/// ```text
+///     (sum z_i*u1_i)*G + sum (z_i*u2_i)*Q_i - sum z_i*R_i
+/// ```
+///
+/// and the result is checked to be the point-at-infinity. The
+/// coefficients are derived deterministically from `rng_seed` and the
+/// batch contents, so that no random generator is needed; however, the
+/// seed MUST be unpredictable by whoever produced the signatures (e.g.
+/// it is a fresh random value, or a local secret).
+///
+/// If the batch is accepted, then, for every entry,
+/// `PublicKey::recover_from_signature()` (with the entry's hashed
+/// message, signature and recovery identifier) returns the entry's
+/// public key, and thus `PublicKey::verify_hash()` accepts the
+/// signature, except with negligible probability (about 2^-127).
+/// Conversely, entries which all fulfill that property are always
+/// accepted as a batch. Note that a valid signature with an incorrect
+/// recovery identifier makes the batch fail. When a batch fails,
+/// `verify_batch_find_invalid()` can be used to identify the invalid
+/// entries.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_batch(entries: &[(PublicKey, &[u8; 32], [u8; 64], u8)],
+    rng_seed: &[u8; 32]) -> bool
+{
+    // Decode all signatures, rebuild the points R_i, and compute
+    // u1_i and u2_i. The batch contents are hashed along the way.
+    let n = entries.len();
+    let mut Rs = Vec::with_capacity(n);
+    let mut u1s = Vec::with_capacity(n);
+    let mut u2s = Vec::with_capacity(n);
+    let mut sh = Sha256::new();
+    sh.update(b"crrl-p256-ecdsa-batch");
+    sh.update(rng_seed);
+    sh.update((n as u64).to_le_bytes());
+    for (pkey, hv, sig, recid) in entries.iter() {
+        let (r, s) = match decode_rs(sig) {
+            Some(rs) => rs,
+            None => return false,
+        };
+        let R = match lift_r(&r, *recid) {
+            Some(R) => R,
+            None => return false,
+        };
+        let w = Scalar::ONE / s;
+        Rs.push(R);
+        u1s.push(hash_to_scalar(&hv[..]) * w);
+        u2s.push(r * w);
+        sh.update(pkey.point.encode_compressed());
+        sh.update(hv);
+        sh.update(sig);
+        sh.update([*recid]);
+    }
+    let th = sh.finalize_reset();
+
+    // Terms for points R_i (128-bit coefficients) and Q_i (full
+    // scalars). For each point, we compute a window (odd multiples of
+    // the point, for 5-bit wNAF) and recode the coefficient. The
+    // coefficient of G is accumulated and processed with the
+    // precomputed table.
+    let mut ss = Scalar::ZERO;
+    let mut winR = Vec::with_capacity(n);
+    let mut winQ = Vec::with_capacity(n);
+    let mut sdR = Vec::with_capacity(n);
+    let mut sdQ = Vec::with_capacity(n);
+    let mut zz = [0u8; 32];
+    for i in 0..n {
+        if (i & 1) == 0 {
+            sh.update(th);
+            sh.update((i as u64).to_le_bytes());
+            zz[..].copy_from_slice(&sh.finalize_reset());
+        }
+        // Coefficients are forced to be odd (hence non-zero).
+        let j = (i & 1) << 4;
+        let mut zb = [0u8; 16];
+        zb[..].copy_from_slice(&zz[j..(j + 16)]);
+        zb[0] |= 1;
+        let z = u128::from_le_bytes(zb);
+        let zs = Scalar::decode_reduce(&zb);
+        ss += zs * u1s[i];
+        winR.push(Point::make_window_vartime(&-Rs[i]));
+        sdR.push(Point::recode_u129_NAF(0, z));
+        winQ.push(Point::make_window_vartime(&entries[i].0.point));
+        sdQ.push(Point::recode_scalar_NAF(&(zs * u2s[i])));
+    }
+    let sd0 = Point::recode_scalar_NAF(&ss);
+
+    // Straus' algorithm: all doublings are shared.
+    let mut T = Point::NEUTRAL;
+    let mut ndbl = 0u32;
+    for i in (0..257).rev() {
+        ndbl += 1;
+        let e = sd0[i];
+        let mut nz = e != 0;
+        if !nz && i < 130 {
+            nz = sdR.iter().any(|sd| sd[i] != 0);
+        }
+        if !nz {
+            nz = sdQ.iter().any(|sd| sd[i] != 0);
+        }
+        if !nz {
+            continue;
+        }
+        T.set_xdouble(ndbl);
+        ndbl = 0;
+
+        if e > 0 {
+            T.set_add_affine(&PRECOMP_G[e as usize - 1], 0);
+        } else if e < 0 {
+            T.set_sub_affine(&PRECOMP_G[(-e) as usize - 1], 0);
+        }
+        for j in 0..n {
+            let f = if i < 130 { sdR[j][i] } else { 0 };
+            if f > 0 {
+                T.set_add(&winR[j][f as usize >> 1]);
+            } else if f < 0 {
+                T.set_sub(&winR[j][(-f) as usize >> 1]);
+            }
+            let f = sdQ[j][i];
+            if f > 0 {
+                T.set_add(&winQ[j][f as usize >> 1]);
+            } else if f < 0 {
+                T.set_sub(&winQ[j][(-f) as usize >> 1]);
+            }
+        }
+    }
+
+    // Remaining doublings are skipped: since the group has prime order,
+    // they do not change whether T is the point-at-infinity.
+    T.isneutral() != 0
+}
+
+/// Finds the invalid entries in a batch of ECDSA signatures.
+///
+/// This function returns the indices (in ascending order) of the
+/// entries that are rejected by `verify_batch()`; the returned vector
+/// is empty if all entries are valid. The batch is split recursively
+/// into halves, and each half is verified with `verify_batch()` (with
+/// the provided seed), so that a large batch with few invalid entries
+/// is processed efficiently. An entry is reported as invalid if its
+/// signature is not valid for its public key and hashed message, or if
+/// its recovery identifier is incorrect.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_batch_find_invalid(
+    entries: &[(PublicKey, &[u8; 32], [u8; 64], u8)],
+    rng_seed: &[u8; 32]) -> Vec<usize>
+{
+    fn find_inner(entries: &[(PublicKey, &[u8; 32], [u8; 64], u8)],
+        off: usize, rng_seed: &[u8; 32], bad: &mut Vec<usize>)
+    {
+        if verify_batch(entries, rng_seed) {
+            return;
+        }
+        // A batch of size 1 is equivalent to individual verification
+        // (the coefficient is non-zero and lower than n).
+        if entries.len() == 1 {
+            bad.push(off);
+            return;
+        }
+        let h = entries.len() >> 1;
+        find_inner(&entries[..h], off, rng_seed, bad);
+        find_inner(&entries[h..], off + h, rng_seed, bad);
+    }
+
+    let mut bad = Vec::new();
+    find_inner(entries, 0, rng_seed, &mut bad);
+    bad
+}
+
+/// Performs an ECDH key exchange.
+///
+/// The shared point is the product of the peer's public key (`peer`)
+/// by the private scalar; the returned shared secret is its x
+/// coordinate, over 32 bytes (unsigned big-endian), as specified by
+/// SEC 1 (section 3.3.1) and NIST SP 800-56A (and used by TLS, COSE...).
+/// The peer's public key is validated before use: a `PublicKey`
+/// instance is always a point on the curve (this is verified when it
+/// is decoded), and the point-at-infinity is rejected here. `None` is
+/// returned if the peer key or the shared point is the
+/// point-at-infinity; since the curve has prime order, the latter
+/// cannot happen with a valid peer key.
+///
+/// The computation is constant-time with regard to the private key.
+/// The output is the raw shared secret; it should normally be used as
+/// input to a key derivation function, not directly as a key.
+pub fn ecdh(sk: &PrivateKey, peer: &PublicKey) -> Option<[u8; 32]> {
+    if peer.point.isneutral() != 0 {
+        return None;
+    }
+    let S = peer.point * sk.x;
+    if S.isneutral() != 0 {
+        return None;
+    }
+    let mut d = [0u8; 32];
+    d.copy_from_slice(&S.encode_compressed()[1..]);
+    Some(d)
+}
+
+/// Performs an ECDH key exchange, with blinding.
+///
+/// This returns exactly the same output as `ecdh()`, but the
+/// multiplication of the peer's public key by the private scalar is
+/// blinded with randomness from the provided RNG (see
+/// `Point::mul_blinded()`), as a countermeasure against power and
+/// electromagnetic analysis.
+pub fn ecdh_blinded<T: CryptoRng + RngCore>(rng: &mut T,
+    sk: &PrivateKey, peer: &PublicKey) -> Option<[u8; 32]>
+{
+    if peer.point.isneutral() != 0 {
+        return None;
+    }
+    let S = peer.point.mul_blinded(&sk.x, rng);
+    if S.isneutral() != 0 {
+        return None;
+    }
+    let mut d = [0u8; 32];
+    d.copy_from_slice(&S.encode_compressed()[1..]);
+    Some(d)
+}
+
+/// Generates a new ephemeral key pair for ECDH, using the provided
+/// cryptographically secure RNG.
+pub fn generate_ephemeral<T: CryptoRng + RngCore>(rng: &mut T)
+    -> (PrivateKey, PublicKey)
+{
+    let sk = PrivateKey::generate(rng);
+    let pk = sk.to_public_key();
+    (sk, pk)
+}
+
+impl PublicKey {
+
+    /// Decodes a public key from bytes.
+    ///
+    /// This function accepts both compressed (33 bytes) and uncompressed
+    /// (65 bytes) formats. The point is always verified to be a valid
+    /// curve point. Note that the neutral point (the
+    /// "point-at-infinity") is explicitly rejected.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        let point = Point::decode(buf)?;
+        if point.isneutral() != 0 {
+            return None;
+        }
+        Some(Self { point })
+    }
+
+    /// Encodes this public key into the compressed format (33 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x02 or 0x03.
+    pub fn encode_compressed(self) -> [u8; 33] {
+        self.point.encode_compressed()
+    }
+
+    /// Encodes this public key into the compressed format (65 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x04.
+    pub fn encode_uncompressed(self) -> [u8; 65] {
+        self.point.encode_uncompressed()
+    }
+
+    /// Adds a tweak to this public key.
+    ///
+    /// The returned public key is `P + t*G`, where `P` is this public key
+    /// and `G` the conventional generator. If the result is the
+    /// point-at-infinity, then `None` is returned. This function is
+    /// constant-time (the tweak may be secret).
+    pub fn tweak_add(&self, t: &Scalar) -> Option<PublicKey> {
+        let point = self.point + Point::mulgen(t);
+        if point.isneutral() != 0 {
+            return None;
+        }
+        Some(PublicKey { point })
+    }
+
+    /// Multiplies this public key by a tweak.
+    ///
+    /// The returned public key is `t*P`, where `P` is this public key. If
+    /// the tweak is zero, then `None` is returned. This function is
+    /// constant-time (the tweak may be secret).
+    pub fn tweak_mul(&self, t: &Scalar) -> Option<PublicKey> {
+        let point = self.point * t;
+        if point.isneutral() != 0 {
+            return None;
+        }
+        Some(PublicKey { point })
+    }
+
+    /// Verifies a signature on a given hashed message.
+    ///
+    /// The signature (`sig`) MUST have an even length; the first half of
+    /// the signature is interpreted as the "r" integer, while the second
+    /// half is "s" (both use unsigned big-endian convention).
+    /// Out-of-range values are rejected. The hashed message is provided
+    /// as `hv`; it is nominally the output of a suitable hash function
+    /// (often SHA-256) computed over the actual message. This function
+    /// can tolerate arbitrary hash output lengths; however, for proper
+    /// security, the hash output must not be too short, and it must be
+    /// an actual hash function output, not raw structured data.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_hash(self, sig: &[u8], hv: &[u8]) -> bool {
+        self.verify_hash_with_policy(sig, hv, true)
+    }
+
+    /// Verifies a signature on a prehashed message.
+    ///
+    /// `digest` is the hash of the message, computed with an arbitrary
+    /// hash function. It is converted into an integer with the bits2int()
+    /// rules of RFC 6979 (section 2.3.2), as in
+    /// `PrivateKey::sign_prehashed()`: if it is longer than 256 bits,
+    /// only its leftmost 256 bits are used; if it is shorter, it is
+    /// interpreted as is (unsigned big-endian). The signature format is
+    /// the same as with `verify_hash()`, and high-s signatures are
+    /// accepted.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_prehashed(self, digest: &[u8], sig: &[u8]) -> bool {
+        self.verify_hash(sig, digest)
+    }
+
+    /// Verifies a signature on a message, using the specified hash
+    /// function.
+    ///
+    /// The message `msg` is hashed with the function identified by
+    /// `hash_id`, and the signature is verified against the digest as
+    /// with `verify_prehashed()` (high-s signatures are accepted). This
+    /// is the counterpart of `PrivateKey::sign_with_hash()`.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_with_hash(self, sig: &[u8], msg: &[u8], hash_id: HashId)
+        -> bool
+    {
+        match hash_id {
+            HashId::Sha224 => self.verify_hash(sig, &Sha224::digest(msg)),
+            HashId::Sha256 => self.verify_hash(sig, &Sha256::digest(msg)),
+            HashId::Sha384 => self.verify_hash(sig, &Sha384::digest(msg)),
+            HashId::Sha512 => self.verify_hash(sig, &Sha512::digest(msg)),
+        }
+    }
+
+    /// Verifies a signature on a given hashed message, with an explicit
+    /// policy on the `s` value.
+    ///
+    /// This function is similar to `verify_hash()`, except that if
+    /// `allow_high_s` is `false`, then signatures whose `s` value is not
+    /// in the low form (s <= n/2) are rejected. For any valid signature
+    /// (r, s), the pair (r, n - s) is also valid; requiring the low form
+    /// removes that malleability (as is done for Bitcoin transactions,
+    /// see BIP-62 and BIP-146). With `allow_high_s` set to `true`, this
+    /// function behaves exactly as `verify_hash()`.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_hash_with_policy(self, sig: &[u8], hv: &[u8],
+        allow_high_s: bool) -> bool
+    {
+        // Recover r and s as scalars.
+        let (r, s) = match decode_rs(sig) {
+            Some(rs) => rs,
+            None => return false,
+        };
+        if !allow_high_s && scalar_is_high(s) != 0 {
+            return false;
+        }
+
+        // Convert the input hash value into an integer modulo n.
+        let h = hash_to_scalar(hv);
+
+        // Verification algorithm.
+        let w = Scalar::ONE / s;
+        let R = self.point.mul_add_mulgen_vartime(&(r * w), &(h * w));
+        let xR_le = bswap32(&R.encode_compressed()[1..33]);
+        let rr = Scalar::decode_reduce(&xR_le);
+
+        // Signature is valid if the rebuilt r value (in rr) matches
+        // the one that was received.
+        return r.equals(rr) != 0;
+    }
+
+    /// Recovers the public key from a signature and the hashed message.
+    ///
+    /// This implements the public key recovery algorithm of SEC 1
+    /// (section 4.1.6). The signature (`sig`) uses the same format as
+    /// in `verify_hash()`. The recovery identifier (`recid`, in the 0 to
+    /// 3 range) designates which of the (at most four) candidate keys
+    /// is returned: bit 0 is the parity of the y coordinate of the
+    /// point R, and bit 1 is set if the x coordinate of R is r + n
+    /// instead of r (this happens only with negligible probability for
+    /// honestly generated signatures, since p - n < 2^127). The signer
+    /// can obtain the identifier with `compute_recovery_id()`. `None`
+    /// is returned if the signature or the identifier is invalid. A
+    /// returned key is such that the signature is valid for it; the
+    /// caller must still check that the key is the expected one (e.g.
+    /// against a known key fingerprint).
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// signature value is public data.
+    pub fn recover_from_signature(hv: &[u8], sig: &[u8], recid: u8)
+        -> Option<PublicKey>
+    {
+        if recid > 3 {
+            return None;
+        }
+        let (r, s) = decode_rs(sig)?;
+        let h = hash_to_scalar(hv);
+
+        let R = lift_r(&r, recid)?;
+
+        // Q = (s*R - h*G)/r
+        let w = Scalar::ONE / r;
+        let Q = R.mul_add_mulgen_vartime(&(s * w), &-(h * w));
+        if Q.isneutral() != 0 {
+            return None;
+        }
+        Some(PublicKey { point: Q })
+    }
+
+    /// Computes the recovery identifier for a signature.
+    ///
+    /// The returned value is the identifier (in the 0 to 3 range) such
+    /// that `recover_from_signature()`, with the same signature and
+    /// hashed message, returns this public key. `None` is returned if
+    /// the signature is not valid for this public key.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn compute_recovery_id(self, sig: &[u8], hv: &[u8]) -> Option<u8> {
+        for recid in 0..4 {
+            if let Some(pk) = Self::recover_from_signature(hv, sig, recid) {
+                if pk.point.equals(self.point) != 0 {
+                    return Some(recid);
+                }
+            }
+        }
+        None
+    }
+
+    /// Verifies a truncated signature on a given hashed message.
+    ///
+    /// The signature (`sig`) MUST have length 64 bytes and MUST have
+    /// been prepared with `PrivateKey::prepare_truncate()`. The last
+    /// `rm` bits are ignored (i.e. the last `floor(rm/8)` bytes are
+    /// ignored, as well as the top `rm%8` bits of the last non-ignored
+    /// byte.
+    ///
+    /// The hashed message is provided as `hv`; it is nominally the
+    /// output of a suitable hash function (often SHA-256) computed over
     /// the actual message. This function can tolerate arbitrary hash
     /// output lengths; however, for proper security, the hash output
     /// must not be too short, and it must be an actual hash function
@@ -2596,12 +3859,196 @@ impl PublicKey {
     }
 }
 
+// The private key is not displayable, and its debug output does not
+// include the secret scalar.
+impl core::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PrivateKey").finish_non_exhaustive()
+    }
+}
+
+// Public keys are displayed as the hexadecimal string of their
+// compressed encoding (33 bytes). Parsing accepts both the compressed
+// (66 hexadecimal digits) and uncompressed (130 digits) formats, with
+// the same checks as `decode()`.
+
+impl core::fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::fmt::LowerHex for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::str::FromStr for PublicKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; 65];
+        let n = if s.len() == 130 { 65 } else { 33 };
+        crate::hex::parse_fixed(s, &mut buf[..n])?;
+        let point = Point::decode_verbose(&buf[..n])
+            .map_err(ParseError::BadPoint)?;
+        if point.isneutral() != 0 {
+            return Err(ParseError::NeutralPoint);
+        }
+        Ok(Self { point })
+    }
+}
+
+// ========================================================================
+
+/// EC-SDSA (Schnorr signatures, ISO/IEC 14888-3) over P-256.
+///
+/// The signature scheme is the one specified in ISO/IEC 14888-3 and BSI
+/// TR-03111 (section 4.2.3), with SHA-256 as hash function. For a
+/// private key `d` (public key `Q = d*G`) and a message `msg`, a nonce
+/// `k` is generated, and:
+///
+/// ```text
+///     W = k*G
+///     r = SHA-256(Wx || Wy || msg)
+///     s = k + r*d mod n
+/// ```
+///
+/// where the coordinates `Wx` and `Wy` of `W` are encoded over 32 bytes
+/// each (unsigned big-endian), and `r` is interpreted as an integer with
+/// unsigned big-endian convention (and reduced modulo n) for the
+/// computation of `s`. The signature is the concatenation of `r` (the
+/// 32-byte hash output) and `s` (over 32 bytes, unsigned big-endian).
+/// The verifier recomputes `W = s*G - r*Q`, then `r` from `W` and the
+/// message. The `sign_opt()` and `verify_opt()` functions implement the
+/// EC-SDSA-opt variant, in which only `Wx` is hashed.
+///
+/// The nonce is derived deterministically from the private key and the
+/// message:
+///
+/// ```text
+///     k = SHA-512(label || d || msg) mod n
+/// ```
+///
+/// with `label` being the ASCII string `"crrl EC-SDSA P-256"` (or
+/// `"crrl EC-SDSA-opt P-256"` for the EC-SDSA-opt variant), `d` being
+/// encoded over 32 bytes (unsigned big-endian), and the SHA-512 output
+/// being interpreted with unsigned little-endian convention. Since the
+/// verification does not depend on how the nonce was obtained, the
+/// signatures are interoperable with any other EC-SDSA implementation.
+pub mod ecsdsa {
+
+    use super::{Point, Scalar, PrivateKey, PublicKey, bswap32};
+    use sha2::{Sha256, Sha512, Digest};
+
+    /// Signs a message with EC-SDSA.
+    pub fn sign(sk: &PrivateKey, msg: &[u8]) -> [u8; 64] {
+        sign_inner(sk, msg, false)
+    }
+
+    /// Verifies an EC-SDSA signature on a message.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify(pk: &PublicKey, msg: &[u8], sig: &[u8]) -> bool {
+        verify_inner(pk, msg, sig, false)
+    }
+
+    /// Signs a message with EC-SDSA-opt.
+    pub fn sign_opt(sk: &PrivateKey, msg: &[u8]) -> [u8; 64] {
+        sign_inner(sk, msg, true)
+    }
+
+    /// Verifies an EC-SDSA-opt signature on a message.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_opt(pk: &PublicKey, msg: &[u8], sig: &[u8]) -> bool {
+        verify_inner(pk, msg, sig, true)
+    }
+
+    // Computes the challenge r from the commitment W and the message. In
+    // the EC-SDSA-opt variant, only the x coordinate of W is hashed.
+    fn challenge(W: Point, msg: &[u8], opt: bool) -> [u8; 32] {
+        let eW = W.encode_uncompressed();
+        let mut sh = Sha256::new();
+        if opt {
+            sh.update(&eW[1..33]);
+        } else {
+            sh.update(&eW[1..65]);
+        }
+        sh.update(msg);
+        sh.finalize().into()
+    }
+
+    fn sign_inner(sk: &PrivateKey, msg: &[u8], opt: bool) -> [u8; 64] {
+        // Derive the nonce k. If 0 is obtained (this has negligible
+        // probability), then 1 is used instead.
+        let label: &[u8] = if opt {
+            b"crrl EC-SDSA-opt P-256"
+        } else {
+            b"crrl EC-SDSA P-256"
+        };
+        let mut sh = Sha512::new();
+        sh.update(label);
+        sh.update(bswap32(&sk.x.encode()));
+        sh.update(msg);
+        let mut k = Scalar::decode_reduce(&sh.finalize());
+        k.set_cond(&Scalar::ONE, k.iszero());
+
+        loop {
+            let r = challenge(Point::mulgen(&k), msg, opt);
+            let rs = Scalar::decode_reduce(&bswap32(&r));
+            let s = k + rs * sk.x;
+
+            // The signature is invalid if r = 0 mod n or s = 0; both are
+            // extremely improbable, but if it happens, we increment k
+            // and try again.
+            if (rs.iszero() | s.iszero()) == 0 {
+                let mut sig = [0u8; 64];
+                sig[..32].copy_from_slice(&r);
+                sig[32..].copy_from_slice(&bswap32(&s.encode()));
+                return sig;
+            }
+            k += Scalar::ONE;
+            k.set_cond(&Scalar::ONE, k.iszero());
+        }
+    }
+
+    fn verify_inner(pk: &PublicKey, msg: &[u8], sig: &[u8], opt: bool)
+        -> bool
+    {
+        if sig.len() != 64 {
+            return false;
+        }
+
+        // r must not be zero modulo n, and s must be in the 1..n-1 range.
+        let rs = Scalar::decode_reduce(&bswap32(&sig[..32]));
+        let (s, cc) = Scalar::decode32(&bswap32(&sig[32..]));
+        if cc == 0 || (rs.iszero() | s.iszero()) != 0 {
+            return false;
+        }
+
+        // W = s*G - r*Q; the signature is valid if W is not the neutral
+        // and hashes (with the message) to the received r.
+        let W = pk.point.mul_add_mulgen_vartime(&-rs, &s);
+        if W.isneutral() != 0 {
+            return false;
+        }
+        challenge(W, msg, opt)[..] == sig[..32]
+    }
+}
+
 // ========================================================================
 
 // We hardcode known multiples of the points B, (2^65)*B, (2^130)*B
 // and (2^195)*B, with B being the conventional base point. These are
 // used to speed mulgen() operations up. The points are stored in affine
 // coordinates, i.e. their Z coordinate is implicitly equal to 1.
+// With the `small_tables_p256` feature, only the multiples of B are
+// included; mulgen() then uses a single table, and the multiples of
+// (2^130)*B are recomputed when needed (in vartime functions).
 
 /// A curve point (non-infinity) in affine coordinates.
 #[derive(Clone, Copy, Debug)]
@@ -2695,6 +4142,7 @@ static PRECOMP_G: [PointAffine; 16] = [
 ];
 
 // Points i*(2^65)*G for i = 1 to 16, in affine coordinates.
+#[cfg(not(feature = "small_tables_p256"))]
 static PRECOMP_G65: [PointAffine; 16] = [
     // (2^65)*G * 1
     PointAffine { x: GFp256::w64be(0x031A8747DF8DC746, 0xE4C13D0306960801,
@@ -2779,6 +4227,7 @@ static PRECOMP_G65: [PointAffine; 16] = [
 ];
 
 // Points i*(2^130)*G for i = 1 to 16, in affine coordinates.
+#[cfg(not(feature = "small_tables_p256"))]
 static PRECOMP_G130: [PointAffine; 16] = [
     // (2^130)*G * 1
     PointAffine { x: GFp256::w64be(0x2890D721E57E1961, 0x18E63ADD579547F0,
@@ -2863,6 +4312,7 @@ static PRECOMP_G130: [PointAffine; 16] = [
 ];
 
 // Points i*(2^195)*G for i = 1 to 16, in affine coordinates.
+#[cfg(not(feature = "small_tables_p256"))]
 static PRECOMP_G195: [PointAffine; 16] = [
     // (2^195)*G * 1
     PointAffine { x: GFp256::w64be(0x9A79BFBFE71E347F, 0x4D6C6698316797E2,
@@ -2951,8 +4401,14 @@ static PRECOMP_G195: [PointAffine; 16] = [
 #[cfg(test)]
 mod tests {
 
-    use super::{Point, Scalar, PrivateKey};
-    use sha2::{Sha256, Digest};
+    use super::{Point, Scalar, PrivateKey, PublicKey};
+    use super::{signature_to_der, signature_from_der};
+    use super::{normalize_s, has_low_s, bswap32};
+    use super::{verify_batch, verify_batch_find_invalid};
+    use super::{Rfc6979, hash_to_scalar, check_signature};
+    use crate::der::DerError;
+    use crate::{DecodeError, ParseError, HashId};
+    use sha2::{Sha224, Sha256, Sha384, Sha512, Digest};
 
     #[cfg(feature = "alloc")]
     use crate::Vec;
@@ -3287,6 +4743,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_verbose() {
+        let G = Point::BASE;
+        let Gc = G.encode_compressed();
+        let Gu = G.encode_uncompressed();
+        assert!(Point::decode_verbose(&[0x00]).unwrap().isneutral() == 0xFFFFFFFF);
+        assert!(Point::decode_verbose(&Gc).unwrap().equals(G) == 0xFFFFFFFF);
+        assert!(Point::decode_verbose(&Gu).unwrap().equals(G) == 0xFFFFFFFF);
+
+        let zz = [0x04u8; 66];
+        for len in [0usize, 2, 32, 34, 64, 66].iter() {
+            assert!(Point::decode_verbose(&zz[..*len]).err()
+                == Some(DecodeError::BadLength));
+        }
+
+        assert!(Point::decode_verbose(&[0x01]).err() == Some(DecodeError::BadHeader));
+        let mut buf = Gc;
+        for hb in [0x00u8, 0x01, 0x04, 0x06].iter() {
+            buf[0] = *hb;
+            assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::BadHeader));
+        }
+        let mut buf = Gu;
+        for hb in [0x00u8, 0x02, 0x03, 0x06, 0x07].iter() {
+            buf[0] = *hb;
+            assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::BadHeader));
+        }
+
+        // Field modulus (big-endian), as x or y coordinate.
+        let p_enc = hex::decode("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff").unwrap();
+        let mut buf = Gc;
+        buf[1..].copy_from_slice(&p_enc);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+        let mut buf = Gu;
+        buf[33..].copy_from_slice(&p_enc);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+        let mut buf = Gu;
+        buf[1..33].copy_from_slice(&p_enc);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+
+        // x = 1 is not the x coordinate of any curve point.
+        let mut buf = [0u8; 33];
+        buf[0] = 0x03;
+        buf[32] = 1;
+        assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::NotOnCurve));
+        let mut buf = Gu;
+        buf[64] ^= 0x01;
+        assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::NotOnCurve));
+    }
+
     #[test]
     fn verify_helper() {
         let mut sh = Sha256::new();
@@ -3379,6 +4887,60 @@ mod tests {
         tt(Q - 5 * U, Q - 4 * U, 10);
     }
 
+    #[test]
+    fn hex_strings() {
+        // Scalars: round trips, uppercase input, alternate format.
+        let mut sh = Sha256::new();
+        for i in 0..10 {
+            sh.update(&(i as u64).to_le_bytes());
+            let x = Scalar::decode_reduce(&sh.finalize_reset());
+            let str = format!("{}", x);
+            assert!(str == hex::encode(x.encode()));
+            assert!(format!("{:x}", x) == str);
+            assert!(format!("{:#x}", x) == format!("0x{}", str));
+            let y: Scalar = str.parse().unwrap();
+            assert!(y.equals(x) == 0xFFFFFFFF);
+            let y: Scalar = str.to_uppercase().parse().unwrap();
+            assert!(y.equals(x) == 0xFFFFFFFF);
+        }
+
+        // Scalars not lower than n are rejected.
+        let nhex = "512563fcc2cab9f3849e17a7adfae6bcffffffffffffffff00000000ffffffff";
+        let nm1 = "502563fcc2cab9f3849e17a7adfae6bcffffffffffffffff00000000ffffffff";
+        assert!(nhex.parse::<Scalar>().err()
+            == Some(ParseError::ScalarOutOfRange));
+        assert!(nm1.parse::<Scalar>().unwrap().equals(-Scalar::ONE) == 0xFFFFFFFF);
+        assert!("ff".repeat(32).parse::<Scalar>().err()
+            == Some(ParseError::ScalarOutOfRange));
+        assert!(nm1[2..].parse::<Scalar>().err() == Some(ParseError::BadLength));
+
+        // Public keys (compressed or uncompressed input).
+        for i in 0..10 {
+            let skey = PrivateKey::from_seed(&[i as u8; 32]);
+            let pkey = skey.to_public_key();
+            let str = format!("{}", pkey);
+            assert!(str == hex::encode(pkey.encode_compressed()));
+            assert!(format!("{:x}", pkey) == str);
+            let pk2: PublicKey = str.parse().unwrap();
+            assert!(pk2.point.equals(pkey.point) == 0xFFFFFFFF);
+            let pk3: PublicKey = hex::encode(pkey.encode_uncompressed())
+                .parse().unwrap();
+            assert!(pk3.point.equals(pkey.point) == 0xFFFFFFFF);
+
+            // The secret scalar is not printed.
+            assert!(format!("{:?}", skey) == "PrivateKey { .. }");
+        }
+        assert!("00".parse::<PublicKey>().err() == Some(ParseError::BadLength));
+        let mut buf = [0u8; 33];
+        buf[0] = 0x05;
+        assert!(hex::encode(buf).parse::<PublicKey>().err()
+            == Some(ParseError::BadPoint(DecodeError::BadHeader)));
+        buf[0] = 0x02;
+        buf[1..].copy_from_slice(&[0xFF; 32]);
+        assert!(hex::encode(buf).parse::<PublicKey>().err()
+            == Some(ParseError::BadPoint(DecodeError::NonCanonicalField)));
+    }
+
     #[test]
     fn signatures() {
         // Test vector from RFC 6979, section A.2.5
@@ -3442,6 +5004,813 @@ mod tests {
     }
 
     #[cfg(feature = "alloc")]
+    #[test]
+    fn signatures_hedged() {
+        // Vectors computed with an independent RFC 6979 implementation
+        // (with section 3.6 additional input).
+        let sk = PrivateKey::decode(&hex::decode(
+            "048d159e242af37bc00000000000000000000000000000000000000000003039")
+            .unwrap()).unwrap();
+        let pk = sk.to_public_key();
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        let mut extra = [0u8; 32];
+        for i in 0..32 {
+            extra[i] = i as u8;
+        }
+        let sig0 = sk.sign_hedged(&hv, &[0u8; 32]);
+        let sig1 = sk.sign_hedged(&hv, &extra);
+        assert!(hex::encode(sig0) == "b6060791c50b2b7ce57070bf906f95c901955f324014ab10e710a77936b1f5eb93f31de6e8e9da5c2bf2fe732884f743e4f7454710826822d30068c7b5b98f77");
+        assert!(hex::encode(sig1) == "6ce7c2b7174b4dcbe95035dd2fc1db65b08d092c3175c26ebf390be0a8c818996ee2db3413eab89654514792ee939bc2ff1cac70d764a16d822bb35c28e42778");
+        // With no extra input at all, sign_hash() is plain RFC 6979.
+        assert!(hex::encode(sk.sign_hash(&hv, &[])) == "c622d5ad82595c95443ced4c568fa9afa81d35e6fe063638c19e40b5440c59992ff856cc9a38a785844d29bc1b9c6a1996b257c2cfca659bf874c872ff468a94");
+        assert!(sig0 != sk.sign_hash(&hv, &[]));
+        assert!(pk.verify_hash(&sig0, &hv));
+        assert!(pk.verify_hash(&sig1, &hv));
+
+        // Over many messages, with both all-zero and varying extra
+        // input, k never repeats (r values are all distinct).
+        const N: usize = 200;
+        let mut rr = [[0u8; 32]; 2 * N];
+        let mut sh = Sha256::new();
+        for i in 0..N {
+            sh.update((i as u64).to_le_bytes());
+            let hv: [u8; 32] = sh.finalize_reset().into();
+            sh.update(hv);
+            let extra: [u8; 32] = sh.finalize_reset().into();
+            let sig0 = sk.sign_hedged(&hv, &[0u8; 32]);
+            let sig1 = sk.sign_hedged(&hv, &extra);
+            assert!(pk.verify_hash(&sig0, &hv));
+            assert!(pk.verify_hash(&sig1, &hv));
+            rr[2 * i].copy_from_slice(&sig0[..32]);
+            rr[2 * i + 1].copy_from_slice(&sig1[..32]);
+        }
+        rr.sort_unstable();
+        for i in 1..(2 * N) {
+            assert!(rr[i - 1] != rr[i]);
+        }
+    }
+
+    #[test]
+    fn prehashed() {
+        // RFC 6979 signatures with SHA-224, SHA-384 and SHA-512,
+        // computed with an independent implementation (the "sample" and
+        // "test" signatures also appear in RFC 6979, section A.2.5).
+        let sk = PrivateKey::decode(&hex::decode(
+            "C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721")
+            .unwrap()).unwrap();
+        let pk = sk.to_public_key();
+        fn hash(id: usize, msg: &[u8]) -> ([u8; 64], usize) {
+            let mut hv = [0u8; 64];
+            let len = id / 8;
+            match id {
+                224 => hv[..len].copy_from_slice(&Sha224::digest(msg)),
+                384 => hv[..len].copy_from_slice(&Sha384::digest(msg)),
+                _ => hv[..len].copy_from_slice(&Sha512::digest(msg)),
+            }
+            (hv, len)
+        }
+        let kat: [(usize, &[u8], &str); 6] = [
+            (224, b"sample",
+             "53b2fff5d1752b2c689df257c04c40a587fababb3f6fc2702f1343af7ca9aa3fb9afb64fdc03dc1a131c7d2386d11e349f070aa432a4acc918bea988bf75c74c"),
+            (224, b"test",
+             "c37edb6f0ae79d47c3c27e962fa269bb4f441770357e114ee511f662ec34a692c820053a05791e521fcaad6042d40aea1d6b1a540138558f47d0719800e18f2d"),
+            (384, b"sample",
+             "0eafea039b20e9b42309fb1d89e213057cbf973dc0cfc8f129edddc800ef77194861f0491e6998b9455193e34e7b0d284ddd7149a74b95b9261f13abde940954"),
+            (384, b"test",
+             "83910e8b48bb0c74244ebdf7f07a1c5413d61472bd941ef3920e623fbccebeb68ddbec54cf8cd5874883841d712142a56a8d0f218f5003cb0296b6b509619f2c"),
+            (512, b"sample",
+             "8496a60b5e9b47c825488827e0495b0e3fa109ec4568fd3f8d1097678eb97f002362ab1adbe2b8adf9cb9edab740ea6049c028114f2460f96554f61fae3302fe"),
+            (512, b"test",
+             "461d93f31b6540894788fd206c07cfa0cc35f46fa3c91816fff1040ad1581a0439af9f15de0db8d97e72719c74820d304ce5226e32dedae67519e840d1194e55"),
+        ];
+        for &(id, msg, sig_hex) in kat.iter() {
+            let (hb, len) = hash(id, msg);
+            let hv = &hb[..len];
+            let sig = match id {
+                224 => sk.sign_prehashed::<Sha224>(hv),
+                384 => sk.sign_prehashed::<Sha384>(hv),
+                _ => sk.sign_prehashed::<Sha512>(hv),
+            };
+            assert!(hex::encode(sig) == sig_hex);
+            assert!(pk.verify_prehashed(hv, &sig));
+            let (hb2, _) = hash(id, b"other");
+            assert!(!pk.verify_prehashed(&hb2[..len], &sig));
+            if hv.len() > 32 {
+                // Only the leftmost 256 bits of the digest matter.
+                assert!(pk.verify_prehashed(&hv[..32], &sig));
+                let mut hv2 = hb;
+                hv2[len - 1] ^= 0x01;
+                assert!(pk.verify_prehashed(&hv2[..len], &sig));
+            } else {
+                // A shorter digest is interpreted as is (not shifted).
+                let mut hv2 = [0u8; 32];
+                hv2[(32 - len)..].copy_from_slice(hv);
+                assert!(pk.verify_prehashed(&hv2, &sig));
+            }
+        }
+
+        // With SHA-256, this is the same as sign_hash() without extra
+        // randomness.
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        assert!(sk.sign_prehashed::<Sha256>(&hv) == sk.sign_hash(&hv, &[]));
+
+        // Signatures produced by OpenSSL (randomized ECDSA) over the
+        // SHA-384 and SHA-512 hashes of a message.
+        let msg = b"crrl prehashed test message";
+        let ossl: [(usize, &str); 2] = [
+            (384, "3044022019199a8f6067dd9de809f51cde421580b37281c12de194920d2e1f561e26255902200dcfa0291be4dd7400cacb3f1abaa7da5ac743c3658ff51b89b22f979d4e7b16"),
+            (512, "30440220249685df8c35627616dd859ef06727bcd4c6f427d167268508f9ae8fe3ee2a1a02200afed41acab78b2b62ece56c51c3c713fdc98a4dd19bcd596f0c6a674158f0a3"),
+        ];
+        for &(id, der_hex) in ossl.iter() {
+            let (hb, len) = hash(id, msg);
+            let hv = &hb[..len];
+            let sig = signature_from_der(
+                &hex::decode(der_hex).unwrap(), false).unwrap();
+            assert!(pk.verify_prehashed(hv, &sig));
+            let (hb2, _) = hash(id, b"other");
+            assert!(!pk.verify_prehashed(&hb2[..len], &sig));
+        }
+    }
+
+    #[test]
+    fn with_hash() {
+        // RFC 6979, section A.2.5 (P-256 with SHA-224 to SHA-512).
+        let sk = PrivateKey::decode(&hex::decode(
+            "C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721")
+            .unwrap()).unwrap();
+        let pk = sk.to_public_key();
+        let kat: [(HashId, &[u8], &str); 8] = [
+            (HashId::Sha224, b"sample",
+             "53b2fff5d1752b2c689df257c04c40a587fababb3f6fc2702f1343af7ca9aa3fb9afb64fdc03dc1a131c7d2386d11e349f070aa432a4acc918bea988bf75c74c"),
+            (HashId::Sha224, b"test",
+             "c37edb6f0ae79d47c3c27e962fa269bb4f441770357e114ee511f662ec34a692c820053a05791e521fcaad6042d40aea1d6b1a540138558f47d0719800e18f2d"),
+            (HashId::Sha256, b"sample",
+             "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8"),
+            (HashId::Sha256, b"test",
+             "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083"),
+            (HashId::Sha384, b"sample",
+             "0eafea039b20e9b42309fb1d89e213057cbf973dc0cfc8f129edddc800ef77194861f0491e6998b9455193e34e7b0d284ddd7149a74b95b9261f13abde940954"),
+            (HashId::Sha384, b"test",
+             "83910e8b48bb0c74244ebdf7f07a1c5413d61472bd941ef3920e623fbccebeb68ddbec54cf8cd5874883841d712142a56a8d0f218f5003cb0296b6b509619f2c"),
+            (HashId::Sha512, b"sample",
+             "8496a60b5e9b47c825488827e0495b0e3fa109ec4568fd3f8d1097678eb97f002362ab1adbe2b8adf9cb9edab740ea6049c028114f2460f96554f61fae3302fe"),
+            (HashId::Sha512, b"test",
+             "461d93f31b6540894788fd206c07cfa0cc35f46fa3c91816fff1040ad1581a0439af9f15de0db8d97e72719c74820d304ce5226e32dedae67519e840d1194e55"),
+        ];
+        let ids = [HashId::Sha224, HashId::Sha256, HashId::Sha384,
+            HashId::Sha512];
+        for &(id, msg, sig_hex) in kat.iter() {
+            let sig = sk.sign_with_hash(msg, id);
+            assert!(hex::encode(sig) == sig_hex);
+            for &id2 in ids.iter() {
+                assert!(pk.verify_with_hash(&sig, msg, id2) == (id == id2));
+            }
+            assert!(!pk.verify_with_hash(&sig, b"other", id));
+        }
+
+        // SHA-256 is the default hash function of sign_hash().
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        let sig = sk.sign_with_hash(b"sample", HashId::Sha256);
+        assert!(sig == sk.sign_hash(&hv, &[]));
+        assert!(pk.verify_hash(&sig, &hv));
+        assert!(HashId::Sha384.output_len() == 48);
+    }
+
+    #[test]
+    fn sign_checked() {
+        use crate::SignError;
+
+        let mut sh = Sha256::new();
+        for i in 0..10u64 {
+            sh.update(i.to_le_bytes());
+            let v = sh.finalize_reset();
+            let sk = PrivateKey::from_seed(&v);
+            let pk = sk.to_public_key();
+            let hv = Sha256::digest(&v);
+            let sig = sk.sign_hash(&hv, &[]);
+            assert!(sk.sign_hash_checked(&pk, &hv, &[]) == Ok(sig));
+            assert!(sk.sign_hash_checked(&pk, &hv, b"extra")
+                == Ok(sk.sign_hash(&hv, b"extra")));
+
+            // A signature corrupted before the check is not returned.
+            let mut bad = sig;
+            bad[(i as usize) * 6] ^= 0x01;
+            assert!(check_signature(&pk, bad, &hv)
+                == Err(SignError::VerificationFailed));
+
+            // Simulated fault on the private scalar: the signature is
+            // valid for another key, and the check against the trusted
+            // public key fails.
+            let faulty = PrivateKey { x: sk.x + Scalar::ONE };
+            assert!(faulty.sign_hash_checked(&pk, &hv, &[])
+                == Err(SignError::VerificationFailed));
+        }
+
+        // Simulated fault on the nonce: r and s are computed with two
+        // distinct (injected) nonces.
+        #[cfg(feature = "insecure-test-apis")]
+        {
+            let sk = PrivateKey::from_seed(b"sign_checked");
+            let hv = Sha256::digest(b"sample");
+            let sig1 = sk.sign_with_nonce(
+                &hv, &Scalar::w64be(0, 0, 0, 12345)).unwrap();
+            let sig2 = sk.sign_with_nonce(
+                &hv, &Scalar::w64be(0, 0, 0, 12346)).unwrap();
+            let pk = sk.to_public_key();
+            assert!(check_signature(&pk, sig1, &hv) == Ok(sig1));
+            let mut bad = sig1;
+            bad[32..].copy_from_slice(&sig2[32..]);
+            assert!(check_signature(&pk, bad, &hv)
+                == Err(SignError::VerificationFailed));
+        }
+    }
+
+    #[test]
+    fn rfc6979_nonce() {
+        // RFC 6979, section A.2.5: nonces and signatures.
+        let sk = PrivateKey::decode(&hex::decode(
+            "C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721")
+            .unwrap()).unwrap();
+        let kat: [(HashId, &[u8], &str, &str); 8] = [
+            (HashId::Sha224, b"sample",
+             "103F90EE9DC52E5E7FB5132B7033C63066D194321491862059967C715985D473",
+             "53b2fff5d1752b2c689df257c04c40a587fababb3f6fc2702f1343af7ca9aa3fb9afb64fdc03dc1a131c7d2386d11e349f070aa432a4acc918bea988bf75c74c"),
+            (HashId::Sha224, b"test",
+             "669F4426F2688B8BE0DB3A6BD1989BDAEFFF84B649EEB84F3DD26080F667FAA7",
+             "c37edb6f0ae79d47c3c27e962fa269bb4f441770357e114ee511f662ec34a692c820053a05791e521fcaad6042d40aea1d6b1a540138558f47d0719800e18f2d"),
+            (HashId::Sha256, b"sample",
+             "A6E3C57DD01ABE90086538398355DD4C3B17AA873382B0F24D6129493D8AAD60",
+             "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8"),
+            (HashId::Sha256, b"test",
+             "D16B6AE827F17175E040871A1C7EC3500192C4C92677336EC2537ACAEE0008E0",
+             "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083"),
+            (HashId::Sha384, b"sample",
+             "09F634B188CEFD98E7EC88B1AA9852D734D0BC272F7D2A47DECC6EBEB375AAD4",
+             "0eafea039b20e9b42309fb1d89e213057cbf973dc0cfc8f129edddc800ef77194861f0491e6998b9455193e34e7b0d284ddd7149a74b95b9261f13abde940954"),
+            (HashId::Sha384, b"test",
+             "16AEFFA357260B04B1DD199693960740066C1A8F3E8EDD79070AA914D361B3B8",
+             "83910e8b48bb0c74244ebdf7f07a1c5413d61472bd941ef3920e623fbccebeb68ddbec54cf8cd5874883841d712142a56a8d0f218f5003cb0296b6b509619f2c"),
+            (HashId::Sha512, b"sample",
+             "5FA81C63109BADB88C1F367B47DA606DA28CAD69AA22C4FE6AD7DF73A7173AA5",
+             "8496a60b5e9b47c825488827e0495b0e3fa109ec4568fd3f8d1097678eb97f002362ab1adbe2b8adf9cb9edab740ea6049c028114f2460f96554f61fae3302fe"),
+            (HashId::Sha512, b"test",
+             "6915D11632ACA3C40D5D51C08DAF9C555933819548784480E93499000D9F0B7F",
+             "461d93f31b6540894788fd206c07cfa0cc35f46fa3c91816fff1040ad1581a0439af9f15de0db8d97e72719c74820d304ce5226e32dedae67519e840d1194e55"),
+        ];
+        for &(id, msg, k_hex, sig_hex) in kat.iter() {
+            let mut hb = [0u8; 64];
+            let hv = match id {
+                HashId::Sha224 => { hb[..28].copy_from_slice(&Sha224::digest(msg)); &hb[..28] }
+                HashId::Sha256 => { hb[..32].copy_from_slice(&Sha256::digest(msg)); &hb[..32] }
+                HashId::Sha384 => { hb[..48].copy_from_slice(&Sha384::digest(msg)); &hb[..48] }
+                HashId::Sha512 => { hb[..64].copy_from_slice(&Sha512::digest(msg)); &hb[..64] }
+            };
+            let h = hash_to_scalar(hv);
+            let k = match id {
+                HashId::Sha224 => Rfc6979::<Sha224>::new(&sk.x, &h, &[]).next_k(),
+                HashId::Sha256 => Rfc6979::<Sha256>::new(&sk.x, &h, &[]).next_k(),
+                HashId::Sha384 => Rfc6979::<Sha384>::new(&sk.x, &h, &[]).next_k(),
+                HashId::Sha512 => Rfc6979::<Sha512>::new(&sk.x, &h, &[]).next_k(),
+            };
+            let mut kb = hex::decode(k_hex).unwrap();
+            kb.reverse();
+            assert!(k.encode()[..] == kb[..]);
+            let sig = sk.sign_with_k(&h, &k, None).unwrap();
+            assert!(hex::encode(sig) == sig_hex);
+            #[cfg(feature = "insecure-test-apis")]
+            assert!(sk.sign_with_nonce(hv, &k) == Some(sig));
+        }
+
+        // Successive candidates are distinct.
+        let h = hash_to_scalar(&Sha256::digest(b"sample"));
+        let mut ng = Rfc6979::<Sha256>::new(&sk.x, &h, &[]);
+        let k1 = ng.next_k();
+        let k2 = ng.next_k();
+        assert!(k1.equals(k2) == 0);
+
+        // A zero nonce is rejected.
+        assert!(sk.sign_with_k(&h, &Scalar::ZERO, None).is_none());
+        #[cfg(feature = "insecure-test-apis")]
+        assert!(sk.sign_with_nonce(&[0u8; 32], &Scalar::ZERO).is_none());
+    }
+
+    #[test]
+    fn pkcs8_spki() {
+        // Key generated with: openssl ecparam -genkey -name prime256v1
+        // then converted with "openssl pkcs8 -topk8 -nocrypt" (with and
+        // without the public key) and "openssl ec -pubout" (uncompressed
+        // and compressed).
+        let p8 = hex::decode("308187020100301306072a8648ce3d020106082a8648ce3d030107046d306b0201010420cd6b87902390110671260246c8dfc02a96fb5682db311c6ac38a77daaccc43e0a144034200048a622273a53721149b3a20488870ec6b73e8f715da9189321affe4cf0cfa69bb8e5f421df0d29061cc45bc533822d0df7d5be5e754d7ce0e7e6e74808d3db55d").unwrap();
+        let p8_nopub = hex::decode("3041020100301306072a8648ce3d020106082a8648ce3d030107042730250201010420cd6b87902390110671260246c8dfc02a96fb5682db311c6ac38a77daaccc43e0").unwrap();
+        let spki = hex::decode("3059301306072a8648ce3d020106082a8648ce3d030107034200048a622273a53721149b3a20488870ec6b73e8f715da9189321affe4cf0cfa69bb8e5f421df0d29061cc45bc533822d0df7d5be5e754d7ce0e7e6e74808d3db55d").unwrap();
+        let spki_c = hex::decode("3039301306072a8648ce3d020106082a8648ce3d030107032200038a622273a53721149b3a20488870ec6b73e8f715da9189321affe4cf0cfa69bb").unwrap();
+        let x = hex::decode("cd6b87902390110671260246c8dfc02a96fb5682db311c6ac38a77daaccc43e0").unwrap();
+
+        let sk = PrivateKey::from_pkcs8_der(&p8).unwrap();
+        assert!(sk.encode()[..] == x[..]);
+        assert!(sk.to_pkcs8_der()[..] == p8[..]);
+        let sk2 = PrivateKey::from_pkcs8_der(&p8_nopub).unwrap();
+        assert!(sk2.encode()[..] == x[..]);
+        let pk = PublicKey::from_spki_der(&spki).unwrap();
+        assert!(pk.point.equals(sk.to_public_key().point) == 0xFFFFFFFF);
+        assert!(pk.to_spki_der()[..] == spki[..]);
+        let pk2 = PublicKey::from_spki_der(&spki_c).unwrap();
+        assert!(pk2.point.equals(pk.point) == 0xFFFFFFFF);
+
+        // ECPrivateKey with the (optional) curve parameters.
+        let p8_params = hex::decode("304d020100301306072a8648ce3d020106082a8648ce3d030107043330310201010420cd6b87902390110671260246c8dfc02a96fb5682db311c6ac38a77daaccc43e0a00a06082a8648ce3d030107").unwrap();
+        assert!(PrivateKey::from_pkcs8_der(&p8_params).unwrap().encode()[..] == x[..]);
+
+        // Round-trip of random keys.
+        let mut sh = Sha256::new();
+        for i in 0..10 {
+            sh.update((i as u64).to_le_bytes());
+            let seed = sh.finalize_reset();
+            let sk = PrivateKey::from_seed(&seed);
+            let pk = sk.to_public_key();
+            let sk2 = PrivateKey::from_pkcs8_der(&sk.to_pkcs8_der()).unwrap();
+            assert!(sk2.encode() == sk.encode());
+            let pk2 = PublicKey::from_spki_der(&pk.to_spki_der()).unwrap();
+            assert!(pk2.point.equals(pk.point) == 0xFFFFFFFF);
+        }
+
+        // secp256k1 keys (openssl ecparam -genkey -name secp256k1), and
+        // curve parameters that do not match the algorithm identifier.
+        let k1_p8 = hex::decode("308184020100301006072a8648ce3d020106052b8104000a046d306b020101042065c8efea44ec77a771af3b7110a2d2f03a824b188a3f22eeb295c89a23f8e3aca14403420004ec2adeab71db567ec756736122b6d5507e26c5952b4bb24fdb7aa506ac12886d7bbcfb6db89cbf379beb2664f4f18a1e35e1c7f1fd40500c537577d90341a943").unwrap();
+        let k1_spki = hex::decode("3056301006072a8648ce3d020106052b8104000a03420004ec2adeab71db567ec756736122b6d5507e26c5952b4bb24fdb7aa506ac12886d7bbcfb6db89cbf379beb2664f4f18a1e35e1c7f1fd40500c537577d90341a943").unwrap();
+        let p8_k1_params = hex::decode("304a020100301306072a8648ce3d020106082a8648ce3d0301070430302e0201010420cd6b87902390110671260246c8dfc02a96fb5682db311c6ac38a77daaccc43e0a00706052b8104000a").unwrap();
+        assert!(PrivateKey::from_pkcs8_der(&k1_p8).err()
+            == Some(DerError::UnsupportedAlgorithm));
+        assert!(PublicKey::from_spki_der(&k1_spki).err()
+            == Some(DerError::UnsupportedAlgorithm));
+        assert!(PrivateKey::from_pkcs8_der(&p8_k1_params).err()
+            == Some(DerError::UnsupportedAlgorithm));
+
+        // Other algorithm OID, and explicit curve parameters.
+        let mut bad = spki.clone();
+        bad[10] = 0x0C;
+        assert!(PublicKey::from_spki_der(&bad).err()
+            == Some(DerError::UnsupportedAlgorithm));
+        let mut bad = spki.clone();
+        bad[13] = 0x30;
+        assert!(PublicKey::from_spki_der(&bad).err()
+            == Some(DerError::UnsupportedAlgorithm));
+
+        // Public key that does not match the private key, invalid
+        // point, zero private key.
+        let mut bad = p8.clone();
+        bad[73..].copy_from_slice(&pk2.point.double().encode_uncompressed());
+        assert!(PrivateKey::from_pkcs8_der(&bad).err()
+            == Some(DerError::InvalidKey));
+        let mut bad = spki.clone();
+        bad[90] ^= 0x01;
+        assert!(PublicKey::from_spki_der(&bad).err()
+            == Some(DerError::InvalidKey));
+        let mut bad = p8_nopub.clone();
+        bad[35..].fill(0);
+        assert!(PrivateKey::from_pkcs8_der(&bad).err()
+            == Some(DerError::InvalidKey));
+
+        // Truncated input, trailing data, wrong version.
+        assert!(PrivateKey::from_pkcs8_der(&p8[..137]).err()
+            == Some(DerError::InvalidEncoding));
+        assert!(PublicKey::from_spki_der(&spki[..90]).err()
+            == Some(DerError::InvalidEncoding));
+        let mut bad = spki.clone();
+        bad.push(0x00);
+        assert!(PublicKey::from_spki_der(&bad).err()
+            == Some(DerError::InvalidEncoding));
+        let mut bad = p8.clone();
+        bad[33] = 0x02;
+        assert!(PrivateKey::from_pkcs8_der(&bad).err()
+            == Some(DerError::InvalidEncoding));
+    }
+
+    #[test]
+    fn recovery() {
+        let mut sh = Sha256::new();
+        let mut seen = [false; 4];
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let seed: [u8; 32] = sh.finalize_reset().into();
+            sh.update(seed);
+            let hv: [u8; 32] = sh.finalize_reset().into();
+            let sk = PrivateKey::from_seed(&seed);
+            let pk = sk.to_public_key();
+            let sig = sk.sign_hash(&hv, &[]);
+            let recid = pk.compute_recovery_id(&sig, &hv).unwrap();
+            seen[recid as usize] = true;
+            for j in 0..4 {
+                let rpk = PublicKey::recover_from_signature(&hv, &sig, j);
+                if j == recid {
+                    assert!(rpk.unwrap().point.equals(pk.point) != 0);
+                } else if let Some(rpk) = rpk {
+                    assert!(rpk.point.equals(pk.point) == 0);
+                    assert!(rpk.verify_hash(&sig, &hv));
+                }
+            }
+
+            // Invalid identifiers and signatures are rejected.
+            let rec = |sig: &[u8], recid| {
+                PublicKey::recover_from_signature(&hv, sig, recid)
+            };
+            assert!(rec(&sig, 4).is_none());
+            assert!(rec(&sig, 255).is_none());
+            let mut sig2 = sig;
+            sig2[32..].copy_from_slice(&[0u8; 32]);
+            assert!(rec(&sig2, recid).is_none());
+            assert!(pk.compute_recovery_id(&sig2, &hv).is_none());
+            let hv2: [u8; 32] = Sha256::digest(hv).into();
+            assert!(pk.compute_recovery_id(&sig, &hv2).is_none());
+        }
+        assert!(seen[0] && seen[1]);
+
+        // Synthetic signature where x(R) = r + n: we find a point R with
+        // an x coordinate greater than n, then choose s and the hashed
+        // message, and compute the public key Q = (s*R - h*G)/r for
+        // which the signature is valid.
+        let mut eR = [0u8; 33];
+        eR[0] = 0x02;
+        eR[1..].copy_from_slice(&hex::decode(
+            "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551")
+            .unwrap());
+        let mut j = 0u8;
+        let R = loop {
+            j += 1;
+            eR[32] = 0x51 + j;
+            if let Some(R) = Point::decode(&eR) {
+                break R;
+            }
+        };
+        let r = Scalar::from_u32(j as u32);
+        let s = Scalar::from_u32(12345);
+        let hv: [u8; 32] = Sha256::digest(b"overflow").into();
+        let h = super::hash_to_scalar(&hv);
+        let Q = (R * s - Point::mulgen(&h)) * (Scalar::ONE / r);
+        let pk = PublicKey { point: Q };
+        let mut sig = [0u8; 64];
+        sig[31] = j;
+        sig[62..].copy_from_slice(&12345u16.to_be_bytes());
+        assert!(pk.verify_hash(&sig, &hv));
+        assert!(pk.compute_recovery_id(&sig, &hv) == Some(2));
+        let rpk = PublicKey::recover_from_signature(&hv, &sig, 2).unwrap();
+        assert!(rpk.point.equals(pk.point) != 0);
+    }
+
+    #[test]
+    fn ecdh() {
+        // Test vectors from NIST CAVP (ECC CDH primitive, P-256):
+        // (peer x, peer y, private key, public x, public y, shared secret)
+        const KAT: &[[&str; 6]] = &[
+            ["700c48f77f56584c5cc632ca65640db91b6bacce3a4df6b42ce7cc838833d287",
+             "db71e509e3fd9b060ddb20ba5c51dcc5948d46fbf640dfe0441782cab85fa4ac",
+             "7d7dc5f71eb29ddaf80d6214632eeae03d9058af1fb6d22ed80badb62bc1a534",
+             "ead218590119e8876b29146ff89ca61770c4edbbf97d38ce385ed281d8a6b230",
+             "28af61281fd35e2fa7002523acc85a429cb06ee6648325389f59edfce1405141",
+             "46fc62106420ff012e54a434fbdd2d25ccc5852060561e68040dd7778997bd7b"],
+            ["809f04289c64348c01515eb03d5ce7ac1a8cb9498f5caa50197e58d43a86a7ae",
+             "b29d84e811197f25eba8f5194092cb6ff440e26d4421011372461f579271cda3",
+             "38f65d6dce47676044d58ce5139582d568f64bb16098d179dbab07741dd5caf5",
+             "119f2f047902782ab0c9e27a54aff5eb9b964829ca99c06b02ddba95b0a3f6d0",
+             "8f52b726664cac366fc98ac7a012b2682cbd962e5acb544671d41b9445704d1d",
+             "057d636096cb80b67a8c038c890e887d1adfa4195e9b3ce241c8a778c59cda67"],
+            ["a2339c12d4a03c33546de533268b4ad667debf458b464d77443636440ee7fec3",
+             "ef48a3ab26e20220bcda2c1851076839dae88eae962869a497bf73cb66faf536",
+             "1accfaf1b97712b85a6f54b148985a1bdc4c9bec0bd258cad4b3d603f49f32c8",
+             "d9f2b79c172845bfdb560bbb01447ca5ecc0470a09513b6126902c6b4f8d1051",
+             "f815ef5ec32128d3487834764678702e64e164ff7315185e23aff5facd96d7bc",
+             "2d457b78b4614132477618a5b077965ec90730a8c81a1c75d6d4ec68005d67ec"],
+        ];
+        let dec_pk = |x: &str, y: &str| {
+            let mut buf = [0x04u8; 65];
+            buf[1..33].copy_from_slice(&hex::decode(x).unwrap());
+            buf[33..].copy_from_slice(&hex::decode(y).unwrap());
+            PublicKey::decode(&buf).unwrap()
+        };
+        for kv in KAT.iter() {
+            let peer = dec_pk(kv[0], kv[1]);
+            let sk = PrivateKey::decode(&hex::decode(kv[2]).unwrap()).unwrap();
+            let pk = dec_pk(kv[3], kv[4]);
+            assert!(sk.to_public_key().point.equals(pk.point) != 0);
+            let z = super::ecdh(&sk, &peer).unwrap();
+            assert!(hex::encode(z) == kv[5]);
+        }
+
+        // The point-at-infinity is rejected, both when decoding and when
+        // forced into a PublicKey instance.
+        assert!(PublicKey::decode(&[0u8]).is_none());
+        let sk = PrivateKey::decode(&hex::decode(KAT[0][2]).unwrap()).unwrap();
+        let bad = PublicKey { point: Point::NEUTRAL };
+        assert!(super::ecdh(&sk, &bad).is_none());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn ecdh_ephemeral() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x33u8; 32]);
+        let (sk1, pk1) = super::generate_ephemeral(&mut rng);
+        let (sk2, pk2) = super::generate_ephemeral(&mut rng);
+        assert!(sk1.to_public_key().point.equals(pk1.point) != 0);
+        assert!(pk1.point.equals(pk2.point) == 0);
+        let z1 = super::ecdh(&sk1, &pk2).unwrap();
+        let z2 = super::ecdh(&sk2, &pk1).unwrap();
+        assert!(z1 == z2);
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn blinded_mul() {
+        use crate::chacha20::ChaChaRng;
+        use crate::{RngCore, SeedableRng};
+
+        // Blinded computations return the same values as the unblinded
+        // ones, whatever the RNG output.
+        let mut rng = ChaChaRng::from_seed([0x44u8; 32]);
+        let mut buf = [0u8; 64];
+        for i in 0..20 {
+            rng.fill_bytes(&mut buf);
+            let n = match i {
+                0 => Scalar::ZERO,
+                1 => Scalar::ONE,
+                2 => -Scalar::ONE,
+                _ => Scalar::decode_reduce(&buf[..32]),
+            };
+            let Q = Point::mulgen(&n);
+            assert!(Point::mulgen_blinded(&n, &mut rng).equals(Q) != 0);
+            let P = Point::mulgen(&Scalar::decode_reduce(&buf[32..]));
+            assert!(P.mul_blinded(&n, &mut rng).equals(P * n) != 0);
+            assert!(Point::NEUTRAL.mul_blinded(&n, &mut rng).isneutral() != 0);
+
+            let sk = PrivateKey::generate(&mut rng);
+            let hv = Sha256::digest(&buf[..]);
+            assert!(sk.sign_hash_blinded(&mut rng, &hv, &[])
+                == sk.sign_hash(&hv, &[]));
+            assert!(sk.sign_hash_blinded(&mut rng, &hv, b"extra")
+                == sk.sign_hash(&hv, b"extra"));
+            let peer = PrivateKey::generate(&mut rng).to_public_key();
+            assert!(super::ecdh_blinded(&mut rng, &sk, &peer)
+                == super::ecdh(&sk, &peer));
+        }
+
+        let sk = PrivateKey::generate(&mut rng);
+        let bad = PublicKey { point: Point::NEUTRAL };
+        assert!(super::ecdh_blinded(&mut rng, &sk, &bad).is_none());
+    }
+
+    #[test]
+    fn decode_sec1() {
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let v: [u8; 32] = sh.finalize_reset().into();
+            let P = Point::mulgen(&Scalar::decode_reduce(&v));
+            let ec = P.encode_compressed();
+            let eu = P.encode_uncompressed();
+            let mut eh = eu;
+            eh[0] = 0x06 | (eu[64] & 1);
+
+            // All three formats decode to the same point.
+            for e in [&ec[..], &eu[..], &eh[..]] {
+                let Q = Point::decode_sec1(e).unwrap();
+                assert!(Q.equals(P) != 0);
+            }
+
+            // Hybrid encoding with the wrong parity.
+            let mut eh2 = eh;
+            eh2[0] ^= 1;
+            assert!(Point::decode_sec1(&eh2).err()
+                == Some(DecodeError::NonCanonicalEncoding));
+
+            // Hybrid prefix with the compressed length, and other
+            // invalid prefixes.
+            let mut ec2 = ec;
+            ec2[0] = 0x06 | (ec[0] & 1);
+            assert!(Point::decode_sec1(&ec2).err()
+                == Some(DecodeError::BadHeader));
+            for hdr in [0x00u8, 0x01, 0x05, 0x08, 0xFF] {
+                let mut eu2 = eu;
+                eu2[0] = hdr;
+                assert!(Point::decode_sec1(&eu2).err()
+                    == Some(DecodeError::BadHeader));
+            }
+
+            // Coordinates which do not match the curve equation.
+            let mut eu2 = eu;
+            eu2[64] ^= 2;
+            let mut eh3 = eh;
+            eh3[64] ^= 2;
+            assert!(Point::decode_sec1(&eu2).err()
+                == Some(DecodeError::NotOnCurve));
+            assert!(Point::decode_sec1(&eh3).err()
+                == Some(DecodeError::NotOnCurve));
+
+            // Wrong lengths.
+            for len in [0, 2, 32, 34, 64, 66] {
+                let mut buf = [0u8; 66];
+                buf[..65].copy_from_slice(&eu);
+                assert!(Point::decode_sec1(&buf[..len]).err()
+                    == Some(DecodeError::BadLength));
+            }
+        }
+
+        // Point-at-infinity: accepted, and flagged as neutral.
+        let P = Point::decode_sec1(&[0x00]).unwrap();
+        assert!(P.isneutral() != 0);
+        assert!(Point::decode_sec1(&[0x01]).err()
+            == Some(DecodeError::BadHeader));
+
+        // x coordinate not lower than the field modulus.
+        let mut eh = [0xFFu8; 65];
+        eh[0] = 0x07;
+        assert!(Point::decode_sec1(&eh).err()
+            == Some(DecodeError::NonCanonicalField));
+    }
+
+    #[test]
+    fn low_s() {
+        // Signatures with s = (n-1)/2 (low) and s = (n+1)/2 (high).
+        let half = -Scalar::ONE / (Scalar::ONE + Scalar::ONE);
+        let mut sig_lo = [0u8; 64];
+        sig_lo[31] = 1;
+        sig_lo[32..].copy_from_slice(&bswap32(&half.encode()));
+        let mut sig_hi = sig_lo;
+        sig_hi[32..].copy_from_slice(&bswap32(&(half + Scalar::ONE).encode()));
+        assert!(has_low_s(&sig_lo));
+        assert!(!has_low_s(&sig_hi));
+        assert!(normalize_s(&sig_lo) == (sig_lo, false));
+        assert!(normalize_s(&sig_hi) == (sig_lo, true));
+
+        // Out-of-range s is left unmodified.
+        let mut sig_bad = sig_lo;
+        sig_bad[32..].fill(0xFF);
+        assert!(!has_low_s(&sig_bad));
+        assert!(normalize_s(&sig_bad) == (sig_bad, false));
+
+        let mut sh = Sha256::new();
+        let mut nhi = 0;
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let seed = sh.finalize_reset();
+            let skey = PrivateKey::from_seed(&seed);
+            let pkey = skey.to_public_key();
+            sh.update(seed);
+            let hv = sh.finalize_reset();
+
+            // Normalization flips s only for high-s signatures, and is
+            // idempotent.
+            let sig = skey.sign_hash(&hv, &[]);
+            let (nsig, flipped) = normalize_s(&sig);
+            assert!(flipped == !has_low_s(&sig));
+            assert!(has_low_s(&nsig));
+            assert!(normalize_s(&nsig) == (nsig, false));
+            assert!(nsig[..32] == sig[..32]);
+            let sig2 = skey.sign_hash_low_s(&hv, &[]);
+            assert!(sig2 == nsig);
+            if flipped {
+                nhi += 1;
+            }
+
+            // Both forms are valid, but the high form is rejected if the
+            // policy requires low-s signatures.
+            let (s, _) = Scalar::decode32(&bswap32(&nsig[32..]));
+            let mut hsig = nsig;
+            hsig[32..].copy_from_slice(&bswap32(&(-s).encode()));
+            assert!(!has_low_s(&hsig));
+            assert!(pkey.verify_hash(&nsig, &hv));
+            assert!(pkey.verify_hash(&hsig, &hv));
+            assert!(pkey.verify_hash_with_policy(&nsig, &hv, true));
+            assert!(pkey.verify_hash_with_policy(&hsig, &hv, true));
+            assert!(pkey.verify_hash_with_policy(&nsig, &hv, false));
+            assert!(!pkey.verify_hash_with_policy(&hsig, &hv, false));
+        }
+        assert!(nhi > 0 && nhi < 20);
+    }
+
+    #[test]
+    fn batch() {
+        // 24 key pairs, with signatures and recovery identifiers.
+        let mut pkeys = Vec::new();
+        let mut hvs = Vec::new();
+        let mut sigs = Vec::new();
+        let mut recids = Vec::new();
+        for i in 0..24 {
+            let skey = PrivateKey::from_seed(&Sha256::digest(&[i as u8]));
+            let pkey = skey.to_public_key();
+            let hv: [u8; 32] = Sha256::digest(&[i as u8, 0x42]).into();
+            let sig = skey.sign_hash(&hv, &[]);
+            recids.push(pkey.compute_recovery_id(&sig, &hv).unwrap());
+            pkeys.push(pkey);
+            hvs.push(hv);
+            sigs.push(sig);
+        }
+        let seed = [0x5Au8; 32];
+
+        // All-valid batches (including the empty batch).
+        for n in [0, 1, 2, 5, 24] {
+            let entries: Vec<(PublicKey, &[u8; 32], [u8; 64], u8)> = (0..n)
+                .map(|i| (pkeys[i], &hvs[i], sigs[i], recids[i])).collect();
+            assert!(verify_batch(&entries, &seed));
+            assert!(verify_batch_find_invalid(&entries, &seed).is_empty());
+        }
+
+        // Batches with exactly one invalid entry: modified s, modified
+        // hash value, wrong key, and wrong recovery identifier (the
+        // latter is still a valid signature for verify_hash()).
+        let mut bad_hv = hvs[0];
+        bad_hv[5] ^= 0x01;
+        for n in [1, 2, 7] {
+            for j in 0..n {
+                for k in 0..4 {
+                    let mut entries: Vec<(PublicKey, &[u8; 32], [u8; 64], u8)>
+                        = (0..n).map(|i| (pkeys[i], &hvs[i], sigs[i], recids[i]))
+                        .collect();
+                    match k {
+                        0 => { entries[j].2[40] ^= 0x01; }
+                        1 => { entries[j].1 = &bad_hv; }
+                        2 => { entries[j].0 = pkeys[j + 1]; }
+                        _ => { entries[j].3 ^= 1; }
+                    }
+                    let (pk, hv, sig, _) = entries[j];
+                    assert!(pk.verify_hash(&sig, hv) == (k == 3));
+                    assert!(!verify_batch(&entries, &seed));
+                    assert!(verify_batch_find_invalid(&entries, &seed) == [j]);
+                }
+            }
+        }
+
+        // Agreement with sequential verification (recovery of the
+        // public key), with several invalid entries, including
+        // out-of-range recovery identifiers and signature values.
+        let mut entries: Vec<(PublicKey, &[u8; 32], [u8; 64], u8)> = (0..24)
+            .map(|i| (pkeys[i], &hvs[i], sigs[i], recids[i])).collect();
+        entries[3].1 = &hvs[4];
+        entries[8].3 = 4;
+        entries[11].2[32..].fill(0xFF);
+        entries[12].0 = pkeys[13];
+        entries[20].2[..32].fill(0x00);
+        let mut bad = Vec::new();
+        for (i, (pk, hv, sig, recid)) in entries.iter().enumerate() {
+            match PublicKey::recover_from_signature(&hv[..], sig, *recid) {
+                Some(Q) if Q.point.equals(pk.point) != 0 => { }
+                _ => { bad.push(i); }
+            }
+        }
+        assert!(bad == [3, 8, 11, 12, 20]);
+        assert!(!verify_batch(&entries, &seed));
+        assert!(verify_batch_find_invalid(&entries, &seed) == bad);
+    }
+
+    #[test]
+    fn der_signatures() {
+        // Signatures generated with OpenSSL (deterministic, RFC 6979):
+        // high bit set in r only, in s only, and a short r.
+        let sk = PrivateKey::decode(&hex::decode(
+            "048d159e242af37bc00000000000000000000000000000000000000000003039")
+            .unwrap()).unwrap();
+        let pk = PublicKey::decode(&hex::decode(
+            "03bf4419652caca568e20a4af794ec29b0bc36c48af0b1f522922632e2d8f13a05")
+            .unwrap()).unwrap();
+        assert!(sk.to_public_key().encode_compressed() == pk.encode_compressed());
+        const KAT: &[(&[u8], &str)] = &[
+            (b"msg5", "3045022100d37677189b9a3774a0e927504f264e7e23fdf7ca46983d71e8d9dba937d03e66022068e6324223c022fea1c89a2e88e1c13459fed735dd4e7720f8ccead9f0ea96f7"),
+            (b"msg6", "3045022061743aebdabe9b133bb14cfc9cc7d8475e203d8143c7321294d29aa4aef7edc8022100c95c5b38bd83e1db3cda5f863626b57436a9b9610bfcbae02d7e3528c3b3abe5"),
+            (b"msg639", "3043021f629a7a1f812e279ba2cbdd55f6beee102e1b6d0669808e8bb50a9078d4f5c80220682d5a0e15c49208f53a69d9081835475b0f5e4b2f606acc37606a0f62d12595"),
+        ];
+        for (msg, der) in KAT.iter() {
+            let der = hex::decode(der).unwrap();
+            let hv: [u8; 32] = Sha256::digest(msg).into();
+            let sig = sk.sign_hash(&hv, &[]);
+            let mut buf = [0u8; 72];
+            let n = signature_to_der(&sig, &mut buf);
+            assert!(buf[..n] == der[..]);
+            let sig2 = signature_from_der(&der, false).unwrap();
+            assert!(sig2 == sig);
+            assert!(pk.verify_hash(&sig2, &hv));
+
+            // Trailing garbage is rejected.
+            let mut der2 = der.clone();
+            der2.push(0x00);
+            assert!(signature_from_der(&der2, false).err()
+                == Some(DerError::InvalidEncoding));
+            assert!(signature_from_der(&der2, true).err()
+                == Some(DerError::InvalidEncoding));
+
+            // Non-minimal integer encoding for s is accepted only in
+            // lenient mode.
+            let mut der3 = der.clone();
+            let k = 4 + (der[3] as usize);
+            der3[k + 1] += 1;
+            der3.insert(k + 2, 0x00);
+            der3[1] += 1;
+            assert!(signature_from_der(&der3, false).err()
+                == Some(DerError::NonCanonical));
+            assert!(signature_from_der(&der3, true).unwrap() == sig);
+        }
+    }
+
     #[test]
     fn signatures_trunc() {
         let mut seed = [0u8; 48];
@@ -3473,4 +5842,163 @@ mod tests {
             }
         }
     }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn generate() {
+        use crate::chacha20::ChaChaRng;
+        use crate::{RngCore, SeedableRng};
+
+        // Keys are deterministic for a given RNG state, and distinct
+        // RNG states yield distinct keys.
+        let mut rng1 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng2 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng3 = ChaChaRng::from_seed([0x22u8; 32]);
+        let sk1 = PrivateKey::generate(&mut rng1);
+        let sk2 = PrivateKey::generate(&mut rng2);
+        let sk3 = PrivateKey::generate(&mut rng3);
+        assert!(sk1.encode() == sk2.encode());
+        assert!(sk1.encode() != sk3.encode());
+        assert!(PrivateKey::generate(&mut rng1).encode() != sk1.encode());
+
+        // The RNG output is used as seed for the deterministic
+        // derivation.
+        let mut seed = [0u8; 32];
+        ChaChaRng::from_seed([0x11u8; 32]).fill_bytes(&mut seed);
+        assert!(PrivateKey::from_seed(&seed).encode() == sk1.encode());
+    }
+
+    #[test]
+    fn ecsdsa() {
+        use super::ecsdsa;
+
+        // Test vectors computed with an independent implementation of
+        // EC-SDSA and EC-SDSA-opt (with the same nonce derivation).
+        let sk = PrivateKey::decode(&hex::decode(
+            "C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721")
+            .unwrap()).unwrap();
+        let pk = sk.to_public_key();
+        let mut msg100 = [0u8; 100];
+        for i in 0..100 {
+            msg100[i] = i as u8;
+        }
+        let kat: [(&[u8], &str); 4] = [
+            (b"",
+             "90d4e08b362592acb09decfe77330a5cf58c932c9ac8a77a6bc91ea1ec95c3e3696901fe089b1a492a24f1c1fbd213d2cce35267117848715c51affc873ea9ca"),
+            (b"sample",
+             "8d9442c8a41f97205154ab67efb24de0d695185a35241bc8ba7ccf7fff797e07f21abebba14c5a9f2327439e5b30f3f348a9014a1176718879f1ebf08d7ba5d7"),
+            (b"test",
+             "efa5ae62e053204918edda347d064d2df8c0faa8a3fff44aa5a89850f5bb6896041c0d5e01855e343ad15e7be76fb964f6ce2208cdd10e4f4c9750897b67b688"),
+            (&msg100,
+             "ac5737041a7221bb869449496b8ce475feea7449a64dd871bc24849d74d8bbbfab1930c9b5a13691b8a77b6a510641202f291e1ad1b2d7fb9e3cdb54ec2e7c20"),
+        ];
+        let kat_opt: [(&[u8], &str); 4] = [
+            (b"",
+             "73c4bc52e95586e13cb315ab6e82703ab0a6ce9c7529d1a532807ee27d9fb8132b6a072a41bf970cbfca4c56b5b394d5fe121af50e58b7f86f1c159e752163e3"),
+            (b"sample",
+             "ff19a86a2e47b90fe4c89da2bfe4a3ba99d6c7f25e7ae3a500a998f22e2ebe998d32166c698da507312db7ba9e7c0cfd849c99d743915efbe1bc297f8bf76e30"),
+            (b"test",
+             "9c98bd62f47ca0a857fffe95c8970de9a567b446ddf8d509066b75abfc4e07bb3670472e349d1b4488c37f89ab0e934d03a29926a74c064fa15dacda31be9357"),
+            (&msg100,
+             "fa258c276acec2b5ebfd52206f90f1d294fcae429ac769d6be77c4e0ccd16e42c06772647ae48c480130753ec09e56c557e6a4a692be9de3bf3932ac6e5aae39"),
+        ];
+        for (opt, v) in [(false, &kat), (true, &kat_opt)] {
+            let sign = if opt { ecsdsa::sign_opt } else { ecsdsa::sign };
+            let verify = if opt { ecsdsa::verify_opt } else { ecsdsa::verify };
+            let verify_other =
+                if opt { ecsdsa::verify } else { ecsdsa::verify_opt };
+            for &(msg, sig_hex) in v.iter() {
+                let sig = sign(&sk, msg);
+                assert!(hex::encode(sig) == sig_hex);
+                assert!(verify(&pk, msg, &sig));
+
+                // The other variant does not accept the signature.
+                assert!(!verify_other(&pk, msg, &sig));
+
+                // Any modification of the message, of r or of s
+                // invalidates the signature.
+                let mut msg2 = [0u8; 101];
+                msg2[..msg.len()].copy_from_slice(msg);
+                assert!(!verify(&pk, &msg2[..(msg.len() + 1)], &sig));
+                if msg.len() > 0 {
+                    msg2[msg.len() - 1] ^= 0x01;
+                    assert!(!verify(&pk, &msg2[..msg.len()], &sig));
+                }
+                for i in 0..64 {
+                    let mut sig2 = sig;
+                    sig2[i] ^= 0x80;
+                    assert!(!verify(&pk, msg, &sig2));
+                }
+
+                // Wrong public key.
+                let pk2 = PrivateKey::from_seed(b"other").to_public_key();
+                assert!(!verify(&pk2, msg, &sig));
+
+                // Truncated or extended signature.
+                assert!(!verify(&pk, msg, &sig[..63]));
+                let mut sig3 = [0u8; 65];
+                sig3[..64].copy_from_slice(&sig);
+                assert!(!verify(&pk, msg, &sig3));
+
+                // s = 0 and s = n are rejected.
+                let mut sig4 = sig;
+                sig4[32..].copy_from_slice(&[0u8; 32]);
+                assert!(!verify(&pk, msg, &sig4));
+                sig4[32..].copy_from_slice(&hex::decode(
+                    "FFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551")
+                    .unwrap());
+                assert!(!verify(&pk, msg, &sig4));
+
+                // r = 0 is rejected.
+                let mut sig5 = sig;
+                sig5[..32].copy_from_slice(&[0u8; 32]);
+                assert!(!verify(&pk, msg, &sig5));
+            }
+        }
+    }
+
+    #[test]
+    fn tweak() {
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let sk = PrivateKey::from_seed(&v);
+            let pk = sk.to_public_key();
+            sh.update(&v);
+            let t = Scalar::decode_reduce(&sh.finalize_reset());
+
+            // The tweaked private key matches the tweaked public key.
+            let sk2 = sk.tweak_add(&t).unwrap();
+            let pk2 = pk.tweak_add(&t).unwrap();
+            assert!(sk2.to_public_key().point.equals(pk2.point) == 0xFFFFFFFF);
+            assert!(pk2.point.equals(pk.point + Point::mulgen(&t))
+                == 0xFFFFFFFF);
+            let sk3 = sk.tweak_mul(&t).unwrap();
+            let pk3 = pk.tweak_mul(&t).unwrap();
+            assert!(sk3.to_public_key().point.equals(pk3.point) == 0xFFFFFFFF);
+            assert!(pk3.point.equals(pk.point * t) == 0xFFFFFFFF);
+
+            // t = 0: addition is the identity, multiplication fails.
+            assert!(sk.tweak_add(&Scalar::ZERO).unwrap().encode()
+                == sk.encode());
+            assert!(pk.tweak_add(&Scalar::ZERO).unwrap().point
+                .equals(pk.point) == 0xFFFFFFFF);
+            assert!(sk.tweak_mul(&Scalar::ZERO).is_none());
+            assert!(pk.tweak_mul(&Scalar::ZERO).is_none());
+
+            // t = n - sk: the sum is zero.
+            let x = Scalar::decode32(&bswap32(&sk.encode())).0;
+            assert!(sk.tweak_add(&-x).is_none());
+            assert!(pk.tweak_add(&-x).is_none());
+
+            // t = 1/sk: the product is one.
+            let u = Scalar::ONE / x;
+            assert!(sk.tweak_mul(&u).unwrap().encode()[31] == 1);
+            assert!(pk.tweak_mul(&u).unwrap().point.equals(Point::BASE)
+                == 0xFFFFFFFF);
+            assert!(sk.tweak_mul(&Scalar::ONE).unwrap().encode()
+                == sk.encode());
+        }
+    }
 }
@@ -0,0 +1,2936 @@
+//! NIST P-384 curve implementation.
+//!
+//! This module implements generic group operations on the NIST P-384
+//! elliptic curve, a short Weierstraß curve with equation `y^2 = x^3 -
+//! 3*x + b` for a given constant `b`. This curve is standardized in
+//! [FIPS 186-4] as well as in other standards such as SEC 2 or ANSI
+//! X9:62. It is also known under the name "secp384r1".
+//!
+//! The API follows that of the `p256` module. The curve has prime order.
+//! "Scalars" are integers modulo that prime order, and are implemented
+//! by the `Scalar` type, which supports the usual arithmetic operators
+//! (`+`, `-`, `*`, `/`, and the compound assignments `+=`, `-=`, `*=`
+//! and `/=`). The base field is implemented by `GFp384` (in the `field`
+//! module); both types use the generic finite field code of the backend.
+//!
+//! A point on the curve is represented by the `Point` structure, which
+//! supports the same operators and functions as its P-256 counterpart.
+//! All point operations are implemented with fully constant-time code
+//! (except functions whose name includes "vartime") and are complete.
+//!
+//! Scalars can be encoded over 48 bytes, using unsigned **little-endian**
+//! convention, and decoded back. Points can be encoded in compressed (49
+//! bytes) or uncompressed (97 bytes) formats, which use big-endian, as
+//! specified by SEC 1. The point-at-infinity is encoded as a single byte
+//! of value 0x00; `Point::decode_sec1()` additionally accepts the
+//! "hybrid" format.
+//!
+//! The `PrivateKey` structure represents a private key for the ECDSA
+//! signature algorithm and ECDH; it is encoded over exactly 48 bytes
+//! (unsigned big-endian, as per SEC 1). The `PublicKey` structure wraps
+//! around a non-neutral `Point`.
+//!
+//! ECDSA signatures are generated with `PrivateKey::sign_hash()`, and
+//! verified with `PublicKey::verify_hash()`. The signature process is
+//! deterministic, using the SHA-384 function, following the description
+//! in [RFC 6979]. The caller provides the pre-hashed message (normally,
+//! this hashing uses SHA-384, but the functions accept hash values of any
+//! length). Signatures use the raw format: the `r` and `s` integers are
+//! encoded in unsigned big-endian over 48 bytes each, and concatenated
+//! (96 bytes in total). `signature_to_der()` and `signature_from_der()`
+//! convert signatures to and from the ASN.1 DER format.
+//!
+//! The `ecdh()` function performs an ECDH key exchange, returning the x
+//! coordinate of the shared point (as in SEC 1 and NIST SP 800-56A);
+//! `generate_ephemeral()` makes a new key pair for that usage.
+//!
+//! [FIPS 186-4]: https://csrc.nist.gov/publications/detail/fips/186/4/final
+//! [RFC 6979]: https://datatracker.ietf.org/doc/html/rfc6979
+
+// Projective/fractional coordinates traditionally use uppercase letters,
+// using lowercase only for affine coordinates.
+#![allow(non_snake_case)]
+
+use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use super::field::GFp384;
+use sha2::{Sha384, Sha512, Digest};
+use super::{CryptoRng, RngCore, DecodeError, ParseError};
+use super::der::{self, DerError};
+use crate::backend::define_gfgen;
+use crate::backend::define_gfgen_tests;
+
+/// A point on the short Weierstraß curve P-384.
+#[derive(Clone, Copy, Debug)]
+pub struct Point {
+    X: GFp384,
+    Y: GFp384,
+    Z: GFp384,
+}
+
+// Scalars are integers modulo the curve order n (a 384-bit prime).
+struct ScalarParams;
+impl ScalarParams {
+
+    const MODULUS: [u64; 6] = [
+        0xECEC196ACCC52973,
+        0x581A0DB248B0A77A,
+        0xC7634D81F4372DDF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+    ];
+}
+define_gfgen!(Scalar, ScalarParams, scalarmod, true);
+define_gfgen_tests!(Scalar, 2, tests_scalarmod);
+
+/// Reverses a 48-byte sequence (i.e. switches between big-endian and
+/// little-endian conventions).
+///
+/// Source slice MUST have length at least 48 (only the first 48 bytes
+/// are accessed).
+fn bswap48(x: &[u8]) -> [u8; 48] {
+    let mut y = [0u8; 48];
+    for i in 0..48 {
+        y[i] = x[47 - i];
+    }
+    y
+}
+
+impl Point {
+
+    // Curve equation is: y^2 = x^3 - 3*x + b  (for a given constant b)
+    // We use projective coordinates, with the same conventions and
+    // formulas as in the p256 module:
+    //   (x, y) -> (X:Y:Z) such that x = X/Z and y = Y/Z
+    //   Y is never 0 (not even for the neutral)
+    //   X = 0 and Z = 0 for the neutral
+    //   Z != 0 for all non-neutral points
+    //
+    // Point addition uses the complete formulas from Renes, Costello and
+    // Batina (https://eprint.iacr.org/2015/1060). Doublings use formulas
+    // from Bernstein and Lange (with a corrective step for the neutral);
+    // sequences of doublings temporarily switch to Jacobian coordinates.
+
+    /// The neutral element (point-at-infinity) in the curve.
+    pub const NEUTRAL: Self = Self {
+        X: GFp384::ZERO,
+        Y: GFp384::ONE,
+        Z: GFp384::ZERO,
+    };
+
+    /// The conventional base point in the curve.
+    ///
+    /// Like all non-neutral points in P-384, it generates the whole curve.
+    pub const BASE: Self = Self {
+        X: GFp384::w64be([
+            0xAA87CA22BE8B0537, 0x8EB1C71EF320AD74,
+            0x6E1D3B628BA79B98, 0x59F741E082542A38,
+            0x5502F25DBF55296C, 0x3A545E3872760AB7,
+        ]),
+        Y: GFp384::w64be([
+            0x3617DE4A96262C6F, 0x5D9E98BF9292DC29,
+            0xF8F41DBD289A147C, 0xE9DA3113B5F0B8C0,
+            0x0A60B1CE1D7E819D, 0x7A431D7C90EA0E5F,
+        ]),
+        Z: GFp384::ONE,
+    };
+
+    /// Curve equation parameter b.
+    const B: GFp384 = GFp384::w64be([
+        0xB3312FA7E23EE7E4, 0x988E056BE3F82D19,
+        0x181D9C6EFE814112, 0x0314088F5013875A,
+        0xC656398D8A2ED19D, 0x2A85C8EDD3EC2AEF,
+    ]);
+
+    /// Tries to decode a point.
+    ///
+    /// This function accepts the following encodings and lengths:
+    ///
+    ///  - A single byte of value 0x00: the point-at-infinity.
+    ///
+    ///  - A byte of value 0x02 or 0x03, followed by exactly 48 bytes
+    ///    (unsigned big-endian encoding of the x coordinate): compressed
+    ///    encoding of a non-neutral point.
+    ///
+    ///  - A byte of value 0x04, followed by exactly 96 bytes (unsigned
+    ///    big-endian encodings of x and y): uncompressed encoding of a
+    ///    non-neutral point.
+    ///
+    /// The "hybrid" encoding is not supported; see `decode_sec1()`.
+    ///
+    /// On success, this structure is set to the decoded point, and
+    /// 0xFFFFFFFF is returned. On failure, this structure is set to the
+    /// neutral point, and 0x00000000 is returned. A failure is reported
+    /// if the coordinates can be decoded but do not correspond to a
+    /// point on the curve.
+    ///
+    /// Constant-time behaviour: timing-based side channels may leak
+    /// which encoding type was used (neutral, compressed, uncompressed)
+    /// but not the value of the obtained point, nor whether the encoding
+    /// was for a valid point.
+    pub fn set_decode(&mut self, buf: &[u8]) -> u32 {
+        *self = Self::NEUTRAL;
+
+        if buf.len() == 1 {
+
+            // Single-byte encoding is for the point-at-infinity.
+            // Return 0xFFFFFFFF if and only if the byte has value 0x00.
+            (((buf[0] as i32) - 1) >> 8) as u32
+
+        } else if buf.len() == 49 {
+
+            // Compressed encoding.
+            // Check that the first byte is 0x02 or 0x03.
+            let mut r = (((((buf[0] & 0xFE) ^ 0x02) as i32) - 1) >> 8) as u32;
+
+            // Decode x.
+            let (x, rx) = GFp384::decode_ct(&bswap48(&buf[1..49]));
+            r &= rx;
+
+            // Compute: y = sqrt(x^3 - 3*x + b)
+            let (mut y, ry) = (x * (x.square() - GFp384::THREE) + Self::B).sqrt();
+            r &= ry;
+
+            // Negate y if the sign does not match the bit provided in the
+            // first encoding byte. There is no valid point with y = 0.
+            let yb = y.encode()[0];
+            let ws = (((yb ^ buf[0]) & 0x01) as u32).wrapping_neg();
+            y.set_cond(&-y, ws);
+
+            // Set the coordinates, adjusting them if the process failed.
+            self.X = GFp384::select(&GFp384::ZERO, &x, r);
+            self.Y = GFp384::select(&GFp384::ONE, &y, r);
+            self.Z = GFp384::select(&GFp384::ZERO, &GFp384::ONE, r);
+            r
+
+        } else if buf.len() == 97 {
+
+            // Uncompressed encoding.
+            // First byte must have value 0x04.
+            let mut r = ((((buf[0] ^ 0x04) as i32) - 1) >> 8) as u32;
+
+            // Decode x and y.
+            let (x, rx) = GFp384::decode_ct(&bswap48(&buf[1..49]));
+            let (y, ry) = GFp384::decode_ct(&bswap48(&buf[49..97]));
+            r &= rx & ry;
+
+            // Verify that the coordinates match the curve equation.
+            r &= y.square().equals(x * (x.square() - GFp384::THREE) + Self::B);
+
+            // Set the coordinates, adjusting them if the process failed.
+            self.X = GFp384::select(&GFp384::ZERO, &x, r);
+            self.Y = GFp384::select(&GFp384::ONE, &y, r);
+            self.Z = GFp384::select(&GFp384::ZERO, &GFp384::ONE, r);
+            r
+
+        } else {
+
+            // Invalid encoding length, return 0.
+            0
+
+        }
+    }
+
+    /// Tries to decode a point.
+    ///
+    /// This function accepts the same encodings as `set_decode()`. On
+    /// success, the decoded point is returned; on failure, `None` is
+    /// returned.
+    pub fn decode(buf: &[u8]) -> Option<Point> {
+        let mut P = Point::NEUTRAL;
+        if P.set_decode(buf) != 0 {
+            Some(P)
+        } else {
+            None
+        }
+    }
+
+    /// Tries to decode a point, with detailed error reporting.
+    ///
+    /// This accepts the same encodings as `decode()`. Failures are
+    /// reported as with `p256::Point::decode_verbose()` (with lengths
+    /// 1, 49 and 97 bytes). This function is meant for diagnostics; the
+    /// cause of a failure is computed with variable-time code.
+    pub fn decode_verbose(buf: &[u8]) -> Result<Point, DecodeError> {
+        if let Some(P) = Self::decode(buf) {
+            return Ok(P);
+        }
+        let hdr_ok = match buf.len() {
+            1 => false,
+            49 => (buf[0] & 0xFE) == 0x02,
+            97 => buf[0] == 0x04,
+            _ => { return Err(DecodeError::BadLength); }
+        };
+        if !hdr_ok {
+            return Err(DecodeError::BadHeader);
+        }
+        for j in (1..buf.len()).step_by(48) {
+            if GFp384::decode_ct(&bswap48(&buf[j..(j + 48)])).1 == 0 {
+                return Err(DecodeError::NonCanonicalField);
+            }
+        }
+        Err(DecodeError::NotOnCurve)
+    }
+
+    /// Decodes a point from any of the SEC 1 encodings.
+    ///
+    /// This function accepts the same encodings as `decode()`, and also
+    /// the hybrid encoding: a byte of value 0x06 or 0x07, followed by
+    /// exactly 96 bytes (unsigned big-endian encodings of x and y),
+    /// where the low bit of the first byte must match the least
+    /// significant bit of y. Failures are reported as with
+    /// `decode_verbose()`; a hybrid encoding whose first byte does not
+    /// match the parity of y is reported as `NonCanonicalEncoding`.
+    pub fn decode_sec1(buf: &[u8]) -> Result<Point, DecodeError> {
+        if buf.len() == 97 && (buf[0] & 0xFE) == 0x06 {
+            let mut tmp = [0u8; 97];
+            tmp.copy_from_slice(buf);
+            tmp[0] = 0x04;
+            let P = Self::decode_verbose(&tmp)?;
+            if ((buf[0] ^ buf[96]) & 1) != 0 {
+                return Err(DecodeError::NonCanonicalEncoding);
+            }
+            return Ok(P);
+        }
+        Self::decode_verbose(buf)
+    }
+
+    /// Encodes this point in compressed format (49 bytes).
+    ///
+    /// If the point is the neutral then `[0u8; 49]` is returned, which
+    /// is NOT the standard encoding of the neutral (standard is a single
+    /// byte of of value 0x00); for a non-neutral point, the first byte
+    /// is always equal to 0x02 or 0x03, never to 0x00.
+    pub fn encode_compressed(self) -> [u8; 49] {
+        let r = !self.isneutral();
+        let iZ = GFp384::ONE / self.Z;  // this is 0 if Z = 0
+        let x = self.X * iZ;  // 0 for the neutral
+        let y = self.Y * iZ;  // 0 for the neutral
+        let mut b = [0u8; 49];
+        b[0] = ((y.encode()[0] & 0x01) | 0x02) & (r as u8);
+        b[1..49].copy_from_slice(&bswap48(&x.encode()));
+        b
+    }
+
+    /// Encodes this point in uncompressed format (97 bytes).
+    ///
+    /// If the point is the neutral then `[0u8; 97]` is returned, which
+    /// is NOT the standard encoding of the neutral (standard is a single
+    /// byte of of value 0x00); for a non-neutral point, the first byte
+    /// is always equal to 0x04, never to 0x00.
+    pub fn encode_uncompressed(self) -> [u8; 97] {
+        let r = !self.isneutral();
+        let iZ = GFp384::ONE / self.Z;  // this is 0 if Z = 0
+        let x = self.X * iZ;  // 0 for the neutral
+        let y = self.Y * iZ;  // 0 for the neutral
+        let mut b = [0u8; 97];
+        b[0] = 0x04 & (r as u8);
+        b[ 1..49].copy_from_slice(&bswap48(&x.encode()));
+        b[49..97].copy_from_slice(&bswap48(&y.encode()));
+        b
+    }
+
+    /// Gets the affine (x, y) coordinates for this point.
+    ///
+    /// Values (x, y, r) are returned, with x and y being field elements,
+    /// and r a `u32` value that qualifies the outcome:
+    ///
+    ///  - if the point is the neutral, then x = 0, y = 0 and r = 0x00000000;
+    ///
+    ///  - otherwise, x and y are the affine coordinates, and r = 0xFFFFFFFF.
+    pub fn to_affine(self) -> (GFp384, GFp384, u32) {
+        let iZ = GFp384::ONE / self.Z;  // this is 0 if Z = 0
+        (self.X * iZ, self.Y * iZ, !self.isneutral())
+    }
+
+    /// Sets this instance from the provided affine coordinates.
+    ///
+    /// If the coordinates designate a valid curve point, then the
+    /// function returns 0xFFFFFFFF; otherwise, this instance is set to
+    /// the neutral, and the function returns 0x00000000.
+    pub fn set_affine(&mut self, x: GFp384, y: GFp384) -> u32 {
+        *self = Self::NEUTRAL;
+        let y2 = x * (x.square() - GFp384::THREE) + Self::B;
+        let r = y.square().equals(y2);
+        self.X.set_cond(&x, r);
+        self.Y.set_cond(&y, r);
+        self.Z.set_cond(&GFp384::ONE, r);
+        r
+    }
+
+    /// Creates an instance from the provided affine coordinates.
+    ///
+    /// The coordinates are verified to comply with the curve equation;
+    /// if they do not, then `None` is returned.
+    pub fn from_affine(x: GFp384, y: GFp384) -> Option<Self> {
+        let mut P = Self::NEUTRAL;
+        if P.set_affine(x, y) != 0 {
+            Some(P)
+        } else {
+            None
+        }
+    }
+
+    /// Adds point `rhs` to `self`.
+    fn set_add(&mut self, rhs: &Self) {
+        let (X1, Y1, Z1) = (&self.X, &self.Y, &self.Z);
+        let (X2, Y2, Z2) = (&rhs.X, &rhs.Y, &rhs.Z);
+
+        // Formulas from Renes-Costello-Batina 2016:
+        // https://eprint.iacr.org/2015/1060
+        // (algorithm 4, with some renaming and expression compaction)
+        let x1x2 = X1 * X2;
+        let y1y2 = Y1 * Y2;
+        let z1z2 = Z1 * Z2;
+        let C = (X1 + Y1) * (X2 + Y2) - x1x2 - y1y2;  // X1*Y2 + X2*Y1
+        let D = (Y1 + Z1) * (Y2 + Z2) - y1y2 - z1z2;  // Y1*Z2 + Y2*Z1
+        let E = (X1 + Z1) * (X2 + Z2) - x1x2 - z1z2;  // X1*Z2 + X2*Z1
+        let F = (E - Self::B * z1z2).mul3();
+        let G = y1y2 - F;
+        let H = y1y2 + F;
+        let I = z1z2.mul3();
+        let J = (Self::B * E - x1x2 - I).mul3();
+        let K = x1x2.mul3() - I;
+        let L = D * J;
+        let M = K * J;
+        let N = K * C;
+        let Y3 = H * G + M;
+        let X3 = H * C - L;
+        let Z3 = G * D + N;
+
+        self.X = X3;
+        self.Y = Y3;
+        self.Z = Z3;
+    }
+
+    /// Adds the affine point `rhs` to `self`.
+    ///
+    /// If the point to add is the neutral, then `rhs.x` and `rhs.y` can
+    /// be arbitrary, and `rz` is 0xFFFFFFFF; otherwise, `rhs.x` and `rhs.y`
+    /// are the affine coordinates of the point to add, and `rz` is
+    /// 0x00000000.
+    fn set_add_affine(&mut self, rhs: &PointAffine, rz: u32) {
+        let (X1, Y1, Z1) = (&self.X, &self.Y, &self.Z);
+        let (X2, Y2) = (&rhs.x, &rhs.y);
+
+        // Same formulas as in set_add(), but modified to account for
+        // Z2 = 1 (implicitly).
+        let x1x2 = X1 * X2;
+        let y1y2 = Y1 * Y2;
+        let C = (X1 + Y1) * (X2 + Y2) - x1x2 - y1y2;  // X1*Y2 + X2*Y1
+        let D = Y2 * Z1 + Y1;                         // Y1*Z2 + Y2*Z1
+        let E = X2 * Z1 + X1;                         // X1*Z2 + X2*Z1
+        let F = (E - Self::B * Z1).mul3();
+        let G = y1y2 - F;
+        let H = y1y2 + F;
+        let I = Z1.mul3();
+        let J = (Self::B * E - x1x2 - I).mul3();
+        let K = x1x2.mul3() - I;
+        let L = D * J;
+        let M = K * J;
+        let N = K * C;
+        let Y3 = H * G + M;
+        let X3 = H * C - L;
+        let Z3 = G * D + N;
+
+        // If rhs is the neutral, then we computed the wrong output and
+        // we must fix it, namely by discarding the computed values in
+        // that case.
+        self.X.set_cond(&X3, !rz);
+        self.Y.set_cond(&Y3, !rz);
+        self.Z.set_cond(&Z3, !rz);
+    }
+
+    /// Subtract the affine point `rhs` from `self`.
+    ///
+    /// If the point to add is the neutral, then `rhs.x` and `rhs.y` can
+    /// be arbitrary, and `rz` is 0xFFFFFFFF; otherwise, `rhs.x` and `rhs.y`
+    /// are the affine coordinates of the point to add, and `rz` is
+    /// 0x00000000.
+    fn set_sub_affine(&mut self, rhs: &PointAffine, rz: u32) {
+        self.set_add_affine(&PointAffine { x: rhs.x, y: -rhs.y }, rz);
+    }
+
+    /// Doubles this point (in place).
+    ///
+    /// This function is somewhat faster than using plain point addition.
+    pub fn set_double(&mut self) {
+        let (X, Y, Z) = (&self.X, &self.Y, &self.Z);
+
+        // We need to remember whether the source was the neutral.
+        let zn = Z.iszero();
+
+        // Formulas from Bernstein-Lange 2007:
+        // https://www.hyperelliptic.org/EFD/g1p/auto-shortw-projective-3.html#doubling-dbl-2007-bl-2
+        let s = (Y * Z).mul2();
+        let w = ((X - Z) * (X + Z)).mul3();
+        let R = Y * s;
+        let ss = s.square();
+        let RR = R.square();
+        let B = (X * R).mul2();
+        let h = w.square() - B - B;
+        let Z3 = s * ss;
+        let X3 = s * h;
+        let Y3 = w * (B - h) - RR.mul2();
+
+        // When the input is the neutral, this yields (0:0:0), which is
+        // not a valid representation; we set Y to 1 in that case.
+        self.X = X3;
+        self.Y = GFp384::select(&Y3, &GFp384::ONE, zn);
+        self.Z = Z3;
+    }
+
+    /// Doubles this point.
+    ///
+    /// This function is somewhat faster than using plain point addition.
+    #[inline(always)]
+    pub fn double(self) -> Self {
+        let mut r = self;
+        r.set_double();
+        r
+    }
+
+    /// Doubles this point n times (in place).
+    ///
+    /// When n > 1, this function is faster than calling `set_double()`
+    /// n times.
+    pub fn set_xdouble(&mut self, n: u32) {
+        if n == 0 {
+            return;
+        }
+        if n == 1 {
+            self.set_double();
+            return;
+        }
+
+        // First doubling: dbl-2007-bl-2 formulas, with output in
+        // Jacobian coordinates (cost 5M+2S).
+        let (X, Y, Z) = (&self.X, &self.Y, &self.Z);
+        let s = (Y * Z).mul2();
+        let w = ((X - Z) * (X + Z)).mul3();
+        let R = Y * s;
+        let RR = R.square();
+        let B = (X * R).mul2();
+        let mut X = w.square() - B - B;
+        let mut Y = w * (B - X) - RR.mul2();
+        let mut Z = s;
+
+        // We now are in Jacobian coordinates. We perform the remaining
+        // doublings.
+        for _ in 1..n {
+            // Using Hankerson-Menezes-Vanstone 2004 formulas (4M+4S)
+            let Z2 = Z.square();
+            let A = ((X - Z2) * (X + Z2)).mul3();
+            let B = Y.mul2();
+            Z *= B;
+            let C = B.square();
+            let D = C.square().half();
+            let E = C * X;
+            X = A.square() - E.mul2();
+            Y = (E - X) * A - D;
+        }
+
+        // Conversion back to projective; if the source was the neutral,
+        // then we have (0:0:0) and must set Y back to a non-zero value.
+        self.X = X * Z;
+        self.Y = GFp384::select(&Y, &GFp384::ONE, Z.iszero());
+        self.Z = Z * Z.square();
+    }
+
+    /// Doubles this point n times.
+    ///
+    /// When n > 1, this function is faster than calling `double()`
+    /// n times.
+    #[inline(always)]
+    pub fn xdouble(self, n: u32) -> Self {
+        let mut r = self;
+        r.set_xdouble(n);
+        r
+    }
+
+    /// Negates this point (in place).
+    #[inline(always)]
+    pub fn set_neg(&mut self) {
+        self.Y.set_neg();
+    }
+
+    /// Subtracts point `rhs` from `self`.
+    fn set_sub(&mut self, rhs: &Self) {
+        self.set_add(&-rhs);
+    }
+
+    /// Multiplies this point by a small integer.
+    ///
+    /// This operation is constant-time with regard to the source point,
+    /// but NOT with regard to the multiplier; the multiplier `n` MUST
+    /// NOT be secret.
+    pub fn set_mul_small(&mut self, n: u64) {
+        if n == 0 {
+            *self = Self::NEUTRAL;
+            return;
+        }
+        if n == 1 {
+            return;
+        }
+
+        let nlen = 64 - n.leading_zeros();
+        let T = *self;
+        let mut ndbl = 0u32;
+        for i in (0..(nlen - 1)).rev() {
+            ndbl += 1;
+            if ((n >> i) & 1) == 0 {
+                continue;
+            }
+            self.set_xdouble(ndbl);
+            ndbl = 0;
+            self.set_add(&T);
+        }
+        self.set_xdouble(ndbl);
+    }
+
+    /// Compares two points for equality.
+    ///
+    /// Returned value is 0xFFFFFFFF if the two points are equal,
+    /// 0x00000000 otherwise.
+    #[inline]
+    pub fn equals(self, rhs: Self) -> u32 {
+        // Since Y != 0 for all points, the test on Y cannot match between
+        // a neutral and a non-neutral point.
+        (self.X * rhs.Z).equals(rhs.X * self.Z)
+        & (self.Y * rhs.Z).equals(rhs.Y * self.Z)
+    }
+
+    /// Tests whether this point is the neutral (point-at-infinity).
+    ///
+    /// Returned value is 0xFFFFFFFF for the neutral, 0x00000000 otherwise.
+    #[inline(always)]
+    pub fn isneutral(self) -> u32 {
+        self.Z.iszero()
+    }
+
+    // Conditionally copies the provided point (`P`) into `self`.
+    //
+    //  - If `ctl` is 0xFFFFFFFF, then the value of `P` is copied into `self`.
+    //
+    //  - if `ctl` is 0x00000000, then the value of `self` is unchanged.
+    //
+    // Value `ctl` MUST be either 0x00000000 or 0xFFFFFFFF.
+    #[inline]
+    pub fn set_cond(&mut self, P: &Self, ctl: u32) {
+        self.X.set_cond(&P.X, ctl);
+        self.Y.set_cond(&P.Y, ctl);
+        self.Z.set_cond(&P.Z, ctl);
+    }
+
+    /// Returns a point equal to `P0` (if `ctl` = 0x00000000) or `P1` (if
+    /// `ctl` = 0xFFFFFFFF).
+    ///
+    /// Value `ctl` MUST be either 0x00000000 or 0xFFFFFFFF.
+    #[inline(always)]
+    pub fn select(P0: &Self, P1: &Self, ctl: u32) -> Self {
+        let mut P = *P0;
+        P.set_cond(P1, ctl);
+        P
+    }
+
+    /// Conditionally negates this point.
+    ///
+    /// This point is negated if `ctl` = 0xFFFFFFFF, but kept unchanged
+    /// if `ctl` = 0x00000000.
+    ///
+    /// Value `ctl` MUST be either 0x00000000 or 0xFFFFFFFF.
+    #[inline]
+    pub fn set_condneg(&mut self, ctl: u32) {
+        self.Y.set_cond(&-self.Y, ctl);
+    }
+
+    /// Recodes a scalar into 77 signed digits.
+    ///
+    /// Each digit is in -15..+16, top digit is in 0..+16.
+    fn recode_scalar(n: &Scalar) -> [i8; 77] {
+        let mut sd = [0i8; 77];
+        let bb = n.encode();
+        let mut cc: u32 = 0;       // carry from lower digits
+        let mut i: usize = 0;      // index of next source byte
+        let mut acc: u32 = 0;      // buffered bits
+        let mut acc_len: i32 = 0;  // number of buffered bits
+        for d_out in sd.iter_mut() {
+            if acc_len < 5 && i < 48 {
+                acc |= (bb[i] as u32) << acc_len;
+                acc_len += 8;
+                i += 1;
+            }
+            let d = (acc & 0x1F) + cc;
+            acc >>= 5;
+            acc_len -= 5;
+            let m = 16u32.wrapping_sub(d) >> 8;
+            *d_out = (d.wrapping_sub(m & 32)) as i8;
+            cc = m & 1;
+        }
+        sd
+    }
+
+    /// Lookups a point from a window, with sign handling (constant-time).
+    fn lookup(win: &[Self; 16], k: i8) -> Self {
+        // Split k into its sign s (0xFFFFFFFF for negative) and
+        // absolute value (f).
+        let s = ((k as i32) >> 8) as u32;
+        let f = ((k as u32) ^ s).wrapping_sub(s);
+        let mut P = Self::NEUTRAL;
+        for (i, Q) in win.iter().enumerate() {
+            // win[i] contains (i+1)*P; we want to keep it if (and only if)
+            // i+1 == f.
+            let j = (i as u32) + 1;
+            let w = !(f.wrapping_sub(j) | j.wrapping_sub(f));
+            let w = ((w as i32) >> 31) as u32;
+
+            P.X.set_cond(&Q.X, w);
+            P.Y.set_cond(&Q.Y, w);
+            P.Z.set_cond(&Q.Z, w);
+        }
+
+        // Negate the returned value if needed.
+        P.Y.set_cond(&-P.Y, s);
+
+        P
+    }
+
+    /// Multiplies this point by a scalar (in place).
+    ///
+    /// This operation is constant-time with regard to both the points
+    /// and the scalar value.
+    pub fn set_mul(&mut self, n: &Scalar) {
+        // Make a 5-bit window: win[i] contains (i+1)*P
+        let mut win = [Self::NEUTRAL; 16];
+        win[0] = *self;
+        for i in 1..8 {
+            let j = 2 * i;
+            win[j - 1] = win[i - 1].double();
+            win[j] = win[j - 1] + win[0];
+        }
+        win[15] = win[7].double();
+
+        // Recode the scalar into 77 signed digits.
+        let sd = Self::recode_scalar(n);
+
+        // Process the digits in high-to-low order.
+        *self = Self::lookup(&win, sd[76]);
+        for i in (0..76).rev() {
+            self.set_xdouble(5);
+            self.set_add(&Self::lookup(&win, sd[i]));
+        }
+    }
+
+    /// Lookups a point from a window in affine coordinates, with sign
+    /// handling (constant-time).
+    ///
+    /// The returned point is in affine coordinates, and an extra "output
+    /// is neutral" flag is also returned (since the neutral point does
+    /// not have defined affine coordinates).
+    fn lookup_affine(win: &[PointAffine; 16], k: i8) -> (PointAffine, u32) {
+        // Split k into its sign s (0xFFFFFFFF for negative) and
+        // absolute value (f).
+        let s = ((k as i32) >> 8) as u32;
+        let f = ((k as u32) ^ s).wrapping_sub(s);
+        let mut P = PointAffine { x: GFp384::ZERO, y: GFp384::ONE };
+        for (i, Q) in win.iter().enumerate() {
+            // win[i] contains (i+1)*P; we want to keep it if (and only if)
+            // i+1 == f.
+            let j = (i as u32) + 1;
+            let w = !(f.wrapping_sub(j) | j.wrapping_sub(f));
+            let w = ((w as i32) >> 31) as u32;
+
+            P.x.set_cond(&Q.x, w);
+            P.y.set_cond(&Q.y, w);
+        }
+
+        // Negate the returned value if needed.
+        P.y.set_cond(&-P.y, s);
+        let fz = (((f as i32) - 1) >> 8) as u32;
+
+        (P, fz)
+    }
+
+    /// Lookups a point from a window in affine coordinates, with sign
+    /// handling (constant-time).
+    ///
+    /// The returned point is projective coordinates (which can represent
+    /// the neutral).
+    #[inline]
+    fn lookup_affine_proj(win: &[PointAffine; 16], k: i8) -> Self {
+        let (P, rz) = Self::lookup_affine(win, k);
+        Self {
+            X: P.x,
+            Y: P.y,
+            Z: GFp384::select(&GFp384::ONE, &GFp384::ZERO, rz),
+        }
+    }
+
+    /// Lookups a point from a window in affine coordinates, with sign
+    /// handling (constant-time), and adds it to the current point.
+    #[inline]
+    fn set_lookup_affine_add(&mut self, win: &[PointAffine; 16], k: i8) {
+        let (P, rz) = Self::lookup_affine(win, k);
+        self.set_add_affine(&P, rz);
+    }
+
+    /// Sets this point by multiplying the conventional generator by the
+    /// provided scalar.
+    ///
+    /// This operation is constant-time. It is faster than using the
+    /// generic multiplication on `Self::BASE`.
+    pub fn set_mulgen(&mut self, n: &Scalar) {
+        // Recode the scalar into 77 signed digits.
+        let sd = Self::recode_scalar(n);
+
+        // We process four chunks in parallel. Each chunk is 20 digits,
+        // except the top one which is 17 digits only.
+        *self = Self::lookup_affine_proj(&PRECOMP_G, sd[19]);
+        self.set_lookup_affine_add(&PRECOMP_G100, sd[39]);
+        self.set_lookup_affine_add(&PRECOMP_G200, sd[59]);
+
+        // Process the digits in high-to-low order.
+        for i in (0..19).rev() {
+            self.set_xdouble(5);
+            self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
+            self.set_lookup_affine_add(&PRECOMP_G100, sd[i + 20]);
+            self.set_lookup_affine_add(&PRECOMP_G200, sd[i + 40]);
+            if i < 17 {
+                self.set_lookup_affine_add(&PRECOMP_G300, sd[i + 60]);
+            }
+        }
+    }
+
+    /// Creates a point by multiplying the conventional generator by the
+    /// provided scalar.
+    ///
+    /// This operation is constant-time. It is faster than using the
+    /// generic multiplication on `Self::BASE`.
+    #[inline]
+    pub fn mulgen(n: &Scalar) -> Self {
+        let mut P = Self::NEUTRAL;
+        P.set_mulgen(n);
+        P
+    }
+
+    /// 5-bit wNAF recoding of a scalar; output is a sequence of 385
+    /// digits.
+    ///
+    /// Non-zero digits have an odd value, between -15 and +15
+    /// (inclusive). (The recoding is constant-time, but use of wNAF is
+    /// inherently non-constant-time.)
+    fn recode_scalar_NAF(n: &Scalar) -> [i8; 385] {
+        // See p256::Point::recode_scalar_NAF() for details. Since a
+        // scalar fits on 384 bits, at most 385 digits are needed.
+        let mut sd = [0i8; 385];
+        let bb = n.encode();
+        let mut x = bb[0] as u32;
+        for i in 0..385 {
+            if (i & 7) == 4 && i < 380 {
+                x += (bb[(i + 4) >> 3] as u32) << 4;
+            }
+            let m = (x & 1).wrapping_neg();  // -1 if x is odd, 0 otherwise
+            let v = x & m & 31;              // low 5 bits if x odd, or 0
+            let c = (v & 16) << 1;           // carry (0 or 32)
+            let d = v.wrapping_sub(c);       // next digit
+            sd[i] = d as i8;
+            x = x.wrapping_sub(d) >> 1;
+        }
+        sd
+    }
+
+    /// Given scalars `u` and `v`, sets this point to `u*self + v*G`
+    /// (with `G` being the conventional generator point, aka
+    /// `Self::BASE`).
+    ///
+    /// This function can be used to support ECDSA signature
+    /// verification.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    pub fn set_mul_add_mulgen_vartime(&mut self, u: &Scalar, v: &Scalar) {
+        // Recode the scalars in 5-bit wNAF.
+        let sdu = Self::recode_scalar_NAF(u);
+        let sdv = Self::recode_scalar_NAF(v);
+
+        // Compute the window for the current point:
+        //   win[i] = (2*i+1)*self    (i = 0 to 7)
+        let mut win = [Self::NEUTRAL; 8];
+        let Q = self.double();
+        win[0] = *self;
+        for i in 1..8 {
+            win[i] = win[i - 1] + Q;
+        }
+
+        let mut zz = true;
+        let mut ndbl = 0u32;
+        for i in (0..385).rev() {
+            // We have one more doubling to perform.
+            ndbl += 1;
+
+            // Get next digits. If they are all zeros, then we can loop
+            // immediately.
+            let e1 = sdu[i];
+            let e2 = sdv[i];
+            if ((e1 as u32) | (e2 as u32)) == 0 {
+                continue;
+            }
+
+            // Apply accumulated doubles.
+            if zz {
+                *self = Self::NEUTRAL;
+                zz = false;
+            } else {
+                self.set_xdouble(ndbl);
+            }
+            ndbl = 0u32;
+
+            // Process digits.
+            if e1 != 0 {
+                if e1 > 0 {
+                    self.set_add(&win[e1 as usize >> 1]);
+                } else {
+                    self.set_sub(&win[(-e1) as usize >> 1]);
+                }
+            }
+            if e2 != 0 {
+                if e2 > 0 {
+                    self.set_add_affine(&PRECOMP_G[e2 as usize - 1], 0);
+                } else {
+                    self.set_sub_affine(&PRECOMP_G[(-e2) as usize - 1], 0);
+                }
+            }
+        }
+
+        if zz {
+            *self = Self::NEUTRAL;
+        } else {
+            if ndbl > 0 {
+                self.set_xdouble(ndbl);
+            }
+        }
+    }
+
+    /// Given scalars `u` and `v`, returns point `u*self + v*G`
+    /// (with `G` being the conventional generator point, aka
+    /// `Self::BASE`).
+    ///
+    /// This function can be used to support ECDSA signature
+    /// verification.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    #[inline(always)]
+    pub fn mul_add_mulgen_vartime(self, u: &Scalar, v: &Scalar) -> Self {
+        let mut R = self;
+        R.set_mul_add_mulgen_vartime(u, v);
+        R
+    }
+
+    /// Check whether `s*G = R + k*Q`, for the provided scalars `s`
+    /// and `k`, provided points `Q` (`self`) and `R`, and conventional
+    /// generator `G`.
+    ///
+    /// Returned value is true on match, false otherwise. This function
+    /// is meant to support Schnorr signature verification.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    pub fn verify_helper_vartime(self,
+        R: &Point, s: &Scalar, k: &Scalar) -> bool
+    {
+        let T = self.mul_add_mulgen_vartime(&(-k), s);
+        T.equals(*R) != 0
+    }
+}
+
+impl Add<Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: Point) -> Point {
+        let mut r = self;
+        r.set_add(&other);
+        r
+    }
+}
+
+impl Add<&Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: &Point) -> Point {
+        let mut r = self;
+        r.set_add(other);
+        r
+    }
+}
+
+impl Add<Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: Point) -> Point {
+        let mut r = *self;
+        r.set_add(&other);
+        r
+    }
+}
+
+impl Add<&Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: &Point) -> Point {
+        let mut r = *self;
+        r.set_add(other);
+        r
+    }
+}
+
+impl AddAssign<Point> for Point {
+    #[inline(always)]
+    fn add_assign(&mut self, other: Point) {
+        self.set_add(&other);
+    }
+}
+
+impl AddAssign<&Point> for Point {
+    #[inline(always)]
+    fn add_assign(&mut self, other: &Point) {
+        self.set_add(other);
+    }
+}
+
+impl Mul<Scalar> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Scalar) -> Point {
+        let mut r = self;
+        r.set_mul(&other);
+        r
+    }
+}
+
+impl Mul<&Scalar> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Scalar) -> Point {
+        let mut r = self;
+        r.set_mul(other);
+        r
+    }
+}
+
+impl Mul<Scalar> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Scalar) -> Point {
+        let mut r = *self;
+        r.set_mul(&other);
+        r
+    }
+}
+
+impl Mul<&Scalar> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Scalar) -> Point {
+        let mut r = *self;
+        r.set_mul(other);
+        r
+    }
+}
+
+impl MulAssign<Scalar> for Point {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: Scalar) {
+        self.set_mul(&other);
+    }
+}
+
+impl MulAssign<&Scalar> for Point {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: &Scalar) {
+        self.set_mul(other);
+    }
+}
+
+impl Mul<Point> for Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Point) -> Point {
+        let mut r = other;
+        r.set_mul(&self);
+        r
+    }
+}
+
+impl Mul<&Point> for Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Point) -> Point {
+        let mut r = *other;
+        r.set_mul(&self);
+        r
+    }
+}
+
+impl Mul<Point> for &Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Point) -> Point {
+        let mut r = other;
+        r.set_mul(self);
+        r
+    }
+}
+
+impl Mul<&Point> for &Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Point) -> Point {
+        let mut r = *other;
+        r.set_mul(self);
+        r
+    }
+}
+
+impl Mul<u64> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: u64) -> Point {
+        let mut r = self;
+        r.set_mul_small(other);
+        r
+    }
+}
+
+impl Mul<u64> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: u64) -> Point {
+        let mut r = *self;
+        r.set_mul_small(other);
+        r
+    }
+}
+
+impl MulAssign<u64> for Point {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: u64) {
+        self.set_mul_small(other);
+    }
+}
+
+impl Mul<Point> for u64 {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Point) -> Point {
+        let mut r = other;
+        r.set_mul_small(self);
+        r
+    }
+}
+
+impl Mul<&Point> for u64 {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Point) -> Point {
+        let mut r = *other;
+        r.set_mul_small(self);
+        r
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn neg(self) -> Point {
+        let mut r = self;
+        r.set_neg();
+        r
+    }
+}
+
+impl Neg for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn neg(self) -> Point {
+        let mut r = *self;
+        r.set_neg();
+        r
+    }
+}
+
+impl Sub<Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: Point) -> Point {
+        let mut r = self;
+        r.set_sub(&other);
+        r
+    }
+}
+
+impl Sub<&Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: &Point) -> Point {
+        let mut r = self;
+        r.set_sub(other);
+        r
+    }
+}
+
+impl Sub<Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: Point) -> Point {
+        let mut r = *self;
+        r.set_sub(&other);
+        r
+    }
+}
+
+impl Sub<&Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: &Point) -> Point {
+        let mut r = *self;
+        r.set_sub(other);
+        r
+    }
+}
+
+impl SubAssign<Point> for Point {
+    #[inline(always)]
+    fn sub_assign(&mut self, other: Point) {
+        self.set_sub(&other);
+    }
+}
+
+impl SubAssign<&Point> for Point {
+    #[inline(always)]
+    fn sub_assign(&mut self, other: &Point) {
+        self.set_sub(other);
+    }
+}
+
+// ========================================================================
+
+/// A P-384 private key simply wraps around a scalar.
+#[derive(Clone, Copy)]
+pub struct PrivateKey {
+    x: Scalar,   // secret scalar
+}
+
+/// A P-384 public key simply wraps around a curve point.
+#[derive(Clone, Copy, Debug)]
+pub struct PublicKey {
+    pub point: Point,
+}
+
+impl PrivateKey {
+
+    /// Generates a new private key from a cryptographically secure RNG.
+    pub fn generate<T: CryptoRng + RngCore>(rng: &mut T) -> Self {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        Self::from_seed(&seed)
+    }
+
+    /// Instantiates a private key by decoding the provided 48-byte
+    /// array.
+    ///
+    /// The 48 bytes contain the unsigned **big-endian** encoding of the
+    /// secret scalar (as per SEC1 and RFC 5915). The decoding fails if
+    /// the source slice does not have length exactly 48 bytes, or if the
+    /// scalar value is zero or not lower than the curve order.
+    ///
+    /// Decoding is constant-time; side-channels may leak whether the
+    /// value was valid or not, but not the value itself.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        if buf.len() != 48 {
+            return None;
+        }
+        let (x, r) = Scalar::decode_ct(&bswap48(buf));
+        if (r & !x.iszero()) != 0  {
+            Some(Self { x })
+        } else {
+            None
+        }
+    }
+
+    /// Encodes this private key into exactly 48 bytes.
+    ///
+    /// Encoding uses the unsigned big-endian convention, as per SEC1 and
+    /// RFC 5915.
+    pub fn encode(self) -> [u8; 48] {
+        bswap48(&self.x.encode())
+    }
+
+    /// Instantiates a private key from a random seed.
+    ///
+    /// The seed MUST have been generated from a cryptographically secure
+    /// random source that ensured an entropy of at least 128 bits. The
+    /// transform from the seed to the private key is not described by
+    /// any standard; therefore, for key storage, the private key itself
+    /// should be stored, not the seed.
+    ///
+    /// This process guarantees that the output key is valid (i.e. it is
+    /// in the proper range, and it is non-zero).
+    pub fn from_seed(seed: &[u8]) -> Self {
+        // We use SHA-512 over the input seed to get a pseudo-random
+        // 512-bit value, which is then reduced modulo the curve order.
+        // A custom prefix ("crrl P-384" in ASCII) is used to avoid
+        // collisions.
+        let mut sh = Sha512::new();
+        sh.update([ 0x63, 0x72, 0x72, 0x6c, 0x20,
+                     0x50, 0x2d, 0x33, 0x38, 0x34 ]);
+        sh.update(seed);
+        let mut x = Scalar::decode_reduce(&sh.finalize()[..]);
+
+        // We make sure we do not get zero by replacing the value with 1
+        // in that case. The probability that such a thing happens is
+        // negligible.
+        x.set_cond(&Scalar::ONE, x.iszero());
+        Self { x }
+    }
+
+    /// Gets the public key corresponding to that private key.
+    pub fn to_public_key(self) -> PublicKey {
+        PublicKey { point: Point::mulgen(&self.x) }
+    }
+
+    /// Signs a hash value with ECDSA.
+    ///
+    /// The hash value may have an arbitrary length, but in general
+    /// should be a SHA-384 output. The provided hash value (`hv`) MUST
+    /// be a real hash value, not a raw unhashed message (in particular,
+    /// if `hv` is longer than 384 bits, it is internally truncated).
+    ///
+    /// The signature (r, s) is returned as the concatenation of r and s,
+    /// each encoded over 48 bytes (unsigned big-endian notation).
+    ///
+    /// Additional randomness can be provided as the `extra_rand` slice.
+    /// It is not necessary for security that the extra randomness is
+    /// cryptographically secure. If `extra_rand` has length 0, then the
+    /// signature generation process is deterministic (but still safe!),
+    /// and follows RFC 6979 with HMAC/SHA-384.
+    pub fn sign_hash(self, hv: &[u8], extra_rand: &[u8]) -> [u8; 96] {
+
+        // Feed a SHA-384 context with the starter block for HMAC/SHA-384,
+        // using a 48-byte key.
+        fn hmac_start(sh: &mut Sha384, key: &[u8; 48]) {
+            let mut tmp = [0x36u8; 128];
+            for i in 0..48 {
+                tmp[i] ^= key[i];
+            }
+            sh.update(tmp);
+        }
+
+        // Finalize a HMAC/SHA-384 computation; the 48-byte key is provided
+        // again. The SHA-384 context is automatically reinitialized.
+        fn hmac_end(sh: &mut Sha384, key: &[u8; 48]) -> [u8; 48] {
+            let v = sh.finalize_reset();
+            let mut tmp = [0x5Cu8; 128];
+            for i in 0..48 {
+                tmp[i] ^= key[i];
+            }
+            sh.update(tmp);
+            sh.update(v);
+            sh.finalize_reset().into()
+        }
+
+        // h = bits2int(hv) mod n, and hb = bits2octets(hv).
+        let h = hash_to_scalar(hv);
+        let hb = bswap48(&h.encode());
+
+        // Get the byte representation of the private key itself.
+        let xb = bswap48(&self.x.encode());
+
+        // Generate a pseudorandom k as per RFC 6979, section 3.2.
+        let mut sh = Sha384::new();
+        let V = [0x01u8; 48];
+        let K = [0x00u8; 48];
+
+        // 3.2.d
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        sh.update([0x00u8]);
+        sh.update(xb);
+        sh.update(hb);
+        if !extra_rand.is_empty() {
+            sh.update(extra_rand);
+        }
+        let K = hmac_end(&mut sh, &K);
+
+        // 3.2.e
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        let V = hmac_end(&mut sh, &K);
+
+        // 3.2.f
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        sh.update([0x01u8]);
+        sh.update(xb);
+        sh.update(hb);
+        if !extra_rand.is_empty() {
+            sh.update(extra_rand);
+        }
+        let mut K = hmac_end(&mut sh, &K);
+
+        // 3.2.g
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        let mut V = hmac_end(&mut sh, &K);
+
+        // 3.2.h
+        // We loop in case we get a zero for k or for s (either case is
+        // so improbable that it won't happen in practice).
+        loop {
+            // Get k. Since SHA-384 outputs 384 bits, and the curve order
+            // has size 384 bits as well, we only need one HMAC call, with
+            // no truncation.
+            hmac_start(&mut sh, &K);
+            sh.update(V);
+            V[..].copy_from_slice(&hmac_end(&mut sh, &K));
+            let (k, cc) = Scalar::decode_ct(&bswap48(&V));
+            if cc != 0 && k.iszero() == 0 {
+                // We got k, compute the signature.
+
+                // R = k*G; then encode x(R), and decode-reduce as a scalar
+                let R = Point::mulgen(&k);
+                let xR_le = bswap48(&R.encode_compressed()[1..49]);
+                let r = Scalar::decode_reduce(&xR_le);
+
+                // Compute s.
+                let s = (h + self.x * r) / k;
+
+                // If s and r are both non-zero, then we have our signature.
+                if (r.iszero() | s.iszero()) == 0 {
+                    let mut sig = [0u8; 96];
+                    sig[..48].copy_from_slice(&bswap48(&r.encode()));
+                    sig[48..].copy_from_slice(&bswap48(&s.encode()));
+                    return sig;
+                }
+            }
+
+            // Bad k, try again (very improbable).
+            hmac_start(&mut sh, &K);
+            sh.update(V);
+            sh.update([0x00u8]);
+            let nK = hmac_end(&mut sh, &K);
+            K[..].copy_from_slice(&nK);
+            hmac_start(&mut sh, &K);
+            sh.update(V);
+            V[..].copy_from_slice(&hmac_end(&mut sh, &K));
+        }
+    }
+}
+
+// Decodes the (r, s) pair of a signature (raw format) as scalars.
+// The two halves are truncated/padded to 48 bytes (verifying that the
+// removed bytes are all zeros), then decoded as scalars. Zeros and
+// out-of-range values are rejected.
+fn decode_rs(sig: &[u8]) -> Option<(Scalar, Scalar)> {
+    let sig_len = sig.len();
+    if (sig_len & 1) != 0 {
+        return None;
+    }
+    let rlen = sig_len >> 1;
+    let mut rb = [0u8; 48];
+    let mut sb = [0u8; 48];
+    if rlen > 48 {
+        for i in 0..(rlen - 48) {
+            if sig[i] != 0 || sig[rlen + i] != 0 {
+                return None;
+            }
+        }
+        rb[..].copy_from_slice(&sig[(rlen - 48)..rlen]);
+        sb[..].copy_from_slice(&sig[(sig_len - 48)..sig_len]);
+    } else {
+        rb[(48 - rlen)..].copy_from_slice(&sig[..rlen]);
+        sb[(48 - rlen)..].copy_from_slice(&sig[rlen..]);
+    }
+    let (r, cr) = Scalar::decode_ct(&bswap48(&rb));
+    if cr == 0 || r.iszero() != 0 {
+        return None;
+    }
+    let (s, cs) = Scalar::decode_ct(&bswap48(&sb));
+    if cs == 0 || s.iszero() != 0 {
+        return None;
+    }
+    Some((r, s))
+}
+
+// Converts a hash value into an integer modulo n: if hv.len() > 48, only
+// the leftmost 48 bytes are kept; the value is interpreted with
+// big-endian convention, then reduced modulo n.
+fn hash_to_scalar(hv: &[u8]) -> Scalar {
+    let mut tmp = [0u8; 48];
+    if hv.len() >= 48 {
+        tmp[..].copy_from_slice(&hv[..48]);
+    } else {
+        tmp[48 - hv.len() .. 48].copy_from_slice(hv);
+    }
+    Scalar::decode_reduce(&bswap48(&tmp))
+}
+
+/// Encodes a signature (in the raw 96-byte format produced by
+/// `PrivateKey::sign_hash()`) into ASN.1 DER. The output buffer must
+/// have length at least 104 bytes; the number of written bytes is
+/// returned.
+pub fn signature_to_der(sig: &[u8; 96], out: &mut [u8]) -> usize {
+    der::encode_ecdsa_signature(sig, out)
+}
+
+/// Decodes an ASN.1 DER signature into the raw 96-byte format (as
+/// expected by `PublicKey::verify_hash()`). Decoding is strict, unless
+/// `lenient` is `true`, in which case non-minimal length and integer
+/// encodings are also accepted. The range of `r` and `s` is not
+/// checked here, only at verification time.
+pub fn signature_from_der(src: &[u8], lenient: bool)
+    -> Result<[u8; 96], DerError>
+{
+    let mut sig = [0u8; 96];
+    der::decode_ecdsa_signature(src, &mut sig, lenient)?;
+    Ok(sig)
+}
+
+/// Performs an ECDH key exchange with the provided private key and
+/// peer public key.
+///
+/// The output is the x coordinate of the shared point, encoded over
+/// exactly 48 bytes (unsigned big-endian), as in SEC 1 and NIST SP
+/// 800-56A. `None` is returned if the peer key is the point-at-infinity
+/// or if the shared point is the point-at-infinity; the latter case
+/// cannot happen with a valid peer key.
+///
+/// The computation is constant-time with regard to the private key.
+/// The output is the raw shared secret; it should normally be used as
+/// input to a key derivation function, not directly as a key.
+pub fn ecdh(sk: &PrivateKey, peer: &PublicKey) -> Option<[u8; 48]> {
+    if peer.point.isneutral() != 0 {
+        return None;
+    }
+    let S = peer.point * sk.x;
+    if S.isneutral() != 0 {
+        return None;
+    }
+    let mut d = [0u8; 48];
+    d.copy_from_slice(&S.encode_compressed()[1..]);
+    Some(d)
+}
+
+/// Generates a new ephemeral key pair for ECDH, using the provided
+/// cryptographically secure RNG.
+pub fn generate_ephemeral<T: CryptoRng + RngCore>(rng: &mut T)
+    -> (PrivateKey, PublicKey)
+{
+    let sk = PrivateKey::generate(rng);
+    let pk = sk.to_public_key();
+    (sk, pk)
+}
+
+impl PublicKey {
+
+    /// Decodes a public key from bytes.
+    ///
+    /// This function accepts both compressed (49 bytes) and uncompressed
+    /// (97 bytes) formats. The point is always verified to be a valid
+    /// curve point. Note that the neutral point (the
+    /// "point-at-infinity") is explicitly rejected.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        let point = Point::decode(buf)?;
+        if point.isneutral() != 0 {
+            return None;
+        }
+        Some(Self { point })
+    }
+
+    /// Encodes this public key into the compressed format (49 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x02 or 0x03.
+    pub fn encode_compressed(self) -> [u8; 49] {
+        self.point.encode_compressed()
+    }
+
+    /// Encodes this public key into the uncompressed format (97 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x04.
+    pub fn encode_uncompressed(self) -> [u8; 97] {
+        self.point.encode_uncompressed()
+    }
+
+    /// Verifies a signature on a given hashed message.
+    ///
+    /// The signature (`sig`) MUST have an even length; the first half of
+    /// the signature is interpreted as the "r" integer, while the second
+    /// half is "s" (both use unsigned big-endian convention).
+    /// Out-of-range values are rejected. The hashed message is provided
+    /// as `hv`; it is nominally the output of a suitable hash function
+    /// (often SHA-384) computed over the actual message.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_hash(self, sig: &[u8], hv: &[u8]) -> bool {
+        // Recover r and s as scalars.
+        let (r, s) = match decode_rs(sig) {
+            Some(rs) => rs,
+            None => return false,
+        };
+
+        // Convert the input hash value into an integer modulo n.
+        let h = hash_to_scalar(hv);
+
+        // Verification algorithm.
+        let w = Scalar::ONE / s;
+        let R = self.point.mul_add_mulgen_vartime(&(r * w), &(h * w));
+        let xR_le = bswap48(&R.encode_compressed()[1..49]);
+        let rr = Scalar::decode_reduce(&xR_le);
+
+        // Signature is valid if the rebuilt r value (in rr) matches
+        // the one that was received.
+        r.equals(rr) != 0
+    }
+}
+
+// The private key is not displayable, and its debug output does not
+// include the secret scalar.
+impl core::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PrivateKey").finish_non_exhaustive()
+    }
+}
+
+// Public keys are displayed as the hexadecimal string of their
+// compressed encoding (49 bytes). Parsing accepts both the compressed
+// (98 hexadecimal digits) and uncompressed (194 digits) formats, with
+// the same checks as `decode()`.
+
+impl core::fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::fmt::LowerHex for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::str::FromStr for PublicKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; 97];
+        let n = if s.len() == 194 { 97 } else { 49 };
+        crate::hex::parse_fixed(s, &mut buf[..n])?;
+        let point = Point::decode_verbose(&buf[..n])
+            .map_err(ParseError::BadPoint)?;
+        if point.isneutral() != 0 {
+            return Err(ParseError::NeutralPoint);
+        }
+        Ok(Self { point })
+    }
+}
+
+// ========================================================================
+
+// We hardcode known multiples of the points G, (2^100)*G, (2^200)*G
+// and (2^300)*G, with G being the conventional base point. These are
+// used to speed mulgen() operations up. The points are stored in affine
+// coordinates, i.e. their Z coordinate is implicitly equal to 1.
+
+/// A curve point (non-infinity) in affine coordinates.
+#[derive(Clone, Copy, Debug)]
+struct PointAffine {
+    x: GFp384,
+    y: GFp384,
+}
+
+// Points i*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G: [PointAffine; 16] = [
+    // G * 1
+    PointAffine {
+        x: GFp384::w64be([
+            0xAA87CA22BE8B0537, 0x8EB1C71EF320AD74,
+            0x6E1D3B628BA79B98, 0x59F741E082542A38,
+            0x5502F25DBF55296C, 0x3A545E3872760AB7,
+        ]),
+        y: GFp384::w64be([
+            0x3617DE4A96262C6F, 0x5D9E98BF9292DC29,
+            0xF8F41DBD289A147C, 0xE9DA3113B5F0B8C0,
+            0x0A60B1CE1D7E819D, 0x7A431D7C90EA0E5F,
+        ]),
+    },
+    // G * 2
+    PointAffine {
+        x: GFp384::w64be([
+            0x08D999057BA3D2D9, 0x69260045C55B97F0,
+            0x89025959A6F434D6, 0x51D207D19FB96E9E,
+            0x4FE0E86EBE0E64F8, 0x5B96A9C75295DF61,
+        ]),
+        y: GFp384::w64be([
+            0x8E80F1FA5B1B3CED, 0xB7BFE8DFFD6DBA74,
+            0xB275D875BC6CC43E, 0x904E505F256AB425,
+            0x5FFD43E94D39E22D, 0x61501E700A940E80,
+        ]),
+    },
+    // G * 3
+    PointAffine {
+        x: GFp384::w64be([
+            0x077A41D4606FFA14, 0x64793C7E5FDC7D98,
+            0xCB9D3910202DCD06, 0xBEA4F240D3566DA6,
+            0xB408BBAE5026580D, 0x02D7E5C70500C831,
+        ]),
+        y: GFp384::w64be([
+            0xC995F7CA0B0C4283, 0x7D0BBE9602A9FC99,
+            0x8520B41C85115AA5, 0xF7684C0EDC111EAC,
+            0xC24ABD6BE4B5D298, 0xB65F28600A2F1DF1,
+        ]),
+    },
+    // G * 4
+    PointAffine {
+        x: GFp384::w64be([
+            0x138251CD52AC9298, 0xC1C8AAD977321DEB,
+            0x97E709BD0B4CA0AC, 0xA55DC8AD51DCFC9D,
+            0x1589A1597E3A5120, 0xE1EFD631C63E1835,
+        ]),
+        y: GFp384::w64be([
+            0xCACAE29869A62E16, 0x31E8A28181AB5661,
+            0x6DC45D918ABC09F3, 0xAB0E63CF792AA4DC,
+            0xED7387BE37BBA569, 0x549F1C02B270ED67,
+        ]),
+    },
+    // G * 5
+    PointAffine {
+        x: GFp384::w64be([
+            0x11DE24A2C251C777, 0x573CAC5EA025E467,
+            0xF208E51DBFF98FC5, 0x4F6661CBE56583B0,
+            0x37882F4A1CA297E6, 0x0ABCDBC3836D84BC,
+        ]),
+        y: GFp384::w64be([
+            0x8FA696C77440F92D, 0x0F5837E90A00E7C5,
+            0x284B447754D5DEE8, 0x8C986533B6901AEB,
+            0x3177686D0AE8FB33, 0x184414ABE6C1713A,
+        ]),
+    },
+    // G * 6
+    PointAffine {
+        x: GFp384::w64be([
+            0x627BE1ACD064D2B2, 0x226FE0D26F2D15D3,
+            0xC33EBCBB7F0F5DA5, 0x1CBD41F262573830,
+            0x21317D7202FF30E5, 0x0937F0854E35C5DF,
+        ]),
+        y: GFp384::w64be([
+            0x09766A4CB3F8B1C2, 0x1BE6DDA6C14F1575,
+            0xB2C95352644F774C, 0x99864F6137154416,
+            0x04C45B8D84E16531, 0x1733A408D3F0F934,
+        ]),
+    },
+    // G * 7
+    PointAffine {
+        x: GFp384::w64be([
+            0x283C1D7365CE4788, 0xF29F8EBF234EDFFE,
+            0xAD6FE997FBEA5FFA, 0x2D58CC9DFA7B1C50,
+            0x8B05526F55B9EBB2, 0x040F05B48FB6D0E1,
+        ]),
+        y: GFp384::w64be([
+            0x9475C99061E41B88, 0xBA52EFDB8C169047,
+            0x1A61D867ED799729, 0xD9C92CD01DBD2256,
+            0x30D84EDE32A78F9E, 0x64664CDAC512EF8C,
+        ]),
+    },
+    // G * 8
+    PointAffine {
+        x: GFp384::w64be([
+            0x1692778EA596E0BE, 0x75114297A6FA3834,
+            0x45BF227FBE58190A, 0x900C3C73256F11FB,
+            0x5A3258D6F403D5EC, 0xE6E9B269D822C87D,
+        ]),
+        y: GFp384::w64be([
+            0xDCD2365700D4106A, 0x835388BA3DB8FD0E,
+            0x22554ADC6D521CD4, 0xBD1C30C2EC0EEC19,
+            0x6BADE1E9CDD1708D, 0x6F6ABFA4022B0AD2,
+        ]),
+    },
+    // G * 9
+    PointAffine {
+        x: GFp384::w64be([
+            0x8F0A39A4049BCB3E, 0xF1BF29B8B025B78F,
+            0x2216F7291E6FD3BA, 0xC6CB1EE285FB6E21,
+            0xC388528BFEE2B953, 0x5C55E4461079118B,
+        ]),
+        y: GFp384::w64be([
+            0x62C77E1438B601D6, 0x452C4A5322C3A979,
+            0x9A9B3D7CA3C400C6, 0xB7678854AED9B302,
+            0x9E743EFEDFD51B68, 0x262DA4F9AC664AF8,
+        ]),
+    },
+    // G * 10
+    PointAffine {
+        x: GFp384::w64be([
+            0xA669C5563BD67EEC, 0x678D29D6EF4FDE86,
+            0x4F372D90B79B9E88, 0x931D5C29291238CC,
+            0xED8E85AB507BF91A, 0xA9CB2D13186658FB,
+        ]),
+        y: GFp384::w64be([
+            0xA988B72AE7C1279F, 0x22D9083DB5F0ECDD,
+            0xF70119550C183C31, 0xC502DF78C3B705A8,
+            0x296D8195248288D9, 0x97784F6AB73A21DD,
+        ]),
+    },
+    // G * 11
+    PointAffine {
+        x: GFp384::w64be([
+            0x099056E27DA7B998, 0xDA1EEEC2904816C5,
+            0x7FE935ED5837C374, 0x56C9FD14892D3F8C,
+            0x4749B66E3AFB81D6, 0x26356F3B55B4DDD8,
+        ]),
+        y: GFp384::w64be([
+            0x2E4C0C234E30AB96, 0x688505544AC5E039,
+            0x6FC4EED8DFC363FD, 0x43FF93F41B52A325,
+            0x5466D51263AAFF35, 0x7D5DBA8138C5E0BB,
+        ]),
+    },
+    // G * 12
+    PointAffine {
+        x: GFp384::w64be([
+            0x952A7A349BD49289, 0xAB3AC421DCF683D0,
+            0x8C2ED5E41F6D0E21, 0x648AF2691A481406,
+            0xDA4A5E22DA817CB4, 0x66DA2EA77D2A7022,
+        ]),
+        y: GFp384::w64be([
+            0xA0320FAF84B5BC05, 0x63052DEAE6F66F2E,
+            0x09FB8036CE18A0EB, 0xB9028B096196B50D,
+            0x031AA64589743E22, 0x9EF6BACCE21BD16E,
+        ]),
+    },
+    // G * 13
+    PointAffine {
+        x: GFp384::w64be([
+            0xA567BA97B67AEA5B, 0xAFDAF5002FFCC6AB,
+            0x9632BFF9F01F873F, 0x6267BCD1F0F11C13,
+            0x9EE5F441ABD99F1B, 0xAAF1CA1E3B5CBCE7,
+        ]),
+        y: GFp384::w64be([
+            0xDE1B38B3989F3318, 0x644E4147AF164ECC,
+            0x5185595046932EC0, 0x86329BE057857D66,
+            0x776BCB8272218A7D, 0x6423A12736F429CC,
+        ]),
+    },
+    // G * 14
+    PointAffine {
+        x: GFp384::w64be([
+            0xE8C8F94D44FBC239, 0x6BBEAC481B89D2B0,
+            0x877B1DFFD23E7DC9, 0x5DE541EB651CCA2C,
+            0x41ABA24DBC02DE66, 0x37209ACCF0F59EA0,
+        ]),
+        y: GFp384::w64be([
+            0x891AE44356FC8AE0, 0x932BCBF6DE52C8A9,
+            0x33B86191E7728D79, 0xC8319413A09D0F48,
+            0xFC468BA05509DE22, 0xD7EE5C9E1B67B888,
+        ]),
+    },
+    // G * 15
+    PointAffine {
+        x: GFp384::w64be([
+            0xB3D13FC8B32B0105, 0x8CC15C11D8135255,
+            0x22A94156FFF01C20, 0x5B21F9F7DA7C4E9C,
+            0xA849557A10B6383B, 0x4B88701A9606860B,
+        ]),
+        y: GFp384::w64be([
+            0x152919E7DF9162A6, 0x1B049B2536164B1B,
+            0xEEBAC4A11D749AF4, 0x84D1114373DFBFD9,
+            0x838D24F8B284AF50, 0x985D588D33F7BD62,
+        ]),
+    },
+    // G * 16
+    PointAffine {
+        x: GFp384::w64be([
+            0xD5D89C3B5282369C, 0x5FBD88E2B231511A,
+            0x6B80DFF0E5152CF6, 0xA464FA9428A8583B,
+            0xAC8EBC773D157811, 0xA462B892401DAFCF,
+        ]),
+        y: GFp384::w64be([
+            0xD815229DE12906D2, 0x41816D5E9A9448F1,
+            0xD41D4FC40E2A3BDB, 0x9CABA57E440A7ABA,
+            0xD1210CB8F49BF223, 0x6822B755EBAB3673,
+        ]),
+    },
+];
+
+// Points i*(2^100)*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G100: [PointAffine; 16] = [
+    // (2^100)*G * 1
+    PointAffine {
+        x: GFp384::w64be([
+            0xA3F973765D6BD8AF, 0x7DBE997EC1BD4C63,
+            0x422454F0DA220C9B, 0x0A8CE94054A63592,
+            0xFFE3075972DCBD76, 0x9703B2F6364F3D9B,
+        ]),
+        y: GFp384::w64be([
+            0x228C222833435953, 0x061B2BCE4E84C4A3,
+            0xF72BF2A53251DFEF, 0x19425828076EEC6C,
+            0x9FE70E8CCABA0102, 0xACBDC0141FB62ABF,
+        ]),
+    },
+    // (2^100)*G * 2
+    PointAffine {
+        x: GFp384::w64be([
+            0x5007DF563B0FED0B, 0xAAD8A9A5CA3824C1,
+            0xBE42B59A865C9668, 0x8E5E8B2688257D50,
+            0xFFEBECFA7889C3F0, 0x219429A83DBA7976,
+        ]),
+        y: GFp384::w64be([
+            0x68FB60A657871405, 0xDC28A26E812DFC3F,
+            0x732EFA8973EC8BC1, 0x04E20DF75D4DC8B9,
+            0x71A89236A122379C, 0x26A7928964B4C044,
+        ]),
+    },
+    // (2^100)*G * 3
+    PointAffine {
+        x: GFp384::w64be([
+            0x39525BFEE4C5105E, 0xB1A009C8D5993067,
+            0xD015E540EE7FCE77, 0x6B418BD52FF96BDE,
+            0x359F9C05409EBD77, 0x0E0E4636A2F45920,
+        ]),
+        y: GFp384::w64be([
+            0x4C788DE4CFDD43EA, 0xA38E24DB16F420A2,
+            0x5285A9F174212BBE, 0x4789DECDB0F7A5DB,
+            0xB729E9DA3D0D23D7, 0x1F9FDFD35F85AABB,
+        ]),
+    },
+    // (2^100)*G * 4
+    PointAffine {
+        x: GFp384::w64be([
+            0xAECEB2D5DDEB6DC9, 0xF4EF04EBA22D9204,
+            0xF4B0550A2B98B6DB, 0xD460AE53682D570A,
+            0x083EC61742CEBA6E, 0xD5A43C8CE361626A,
+        ]),
+        y: GFp384::w64be([
+            0x858175324B6C9E09, 0x959971165654E5DF,
+            0x24ABC0775AC83157, 0xDA61AB45E4186EF7,
+            0x20F7F2311D104DA9, 0x4A4566631FA863F4,
+        ]),
+    },
+    // (2^100)*G * 5
+    PointAffine {
+        x: GFp384::w64be([
+            0xBE7AA0B1478AFBA6, 0xEB38B669A70FD3C4,
+            0x053DEB2C895A4937, 0x8B889001A9CCB6BB,
+            0x663042308765D06C, 0x53538A11BCEA77E9,
+        ]),
+        y: GFp384::w64be([
+            0x4AB43385AF675CC3, 0x6298BA9E17CC5C27,
+            0xE0D4854D147CC0F5, 0x360FDFE417F8D2B6,
+            0x6F7EB483EB7C6327, 0xC322CE90CE6DD6C7,
+        ]),
+    },
+    // (2^100)*G * 6
+    PointAffine {
+        x: GFp384::w64be([
+            0x0C2CD7C2B28F8D64, 0x8AFBF0B8DD134D24,
+            0xDB6BA33EC6945405, 0x417345BA44F315C9,
+            0x50BE77FD588CF2C8, 0x169BFBF1C3413ABD,
+        ]),
+        y: GFp384::w64be([
+            0xADDDBE6E791EE6CB, 0x3E6C1B0865A08A6C,
+            0xD8172259C8571525, 0xC4046B3335BC4F97,
+            0x281D12389582DA40, 0x301D606961041F5F,
+        ]),
+    },
+    // (2^100)*G * 7
+    PointAffine {
+        x: GFp384::w64be([
+            0xA925D0A924CEF089, 0x0CD92ABC5499D51C,
+            0xBACC4CABD4FF0DF3, 0x3316E0B8C8C08F79,
+            0x72070F76E7044BFE, 0xFC91C9F4CB882C2A,
+        ]),
+        y: GFp384::w64be([
+            0x6A0D7C7003BA597C, 0x1ECA46CB43DF0D14,
+            0x34B687C1DDD88C06, 0x60B86CDBFE1DDFC2,
+            0x5ECBDDB0F638D2C0, 0x283CED4E78AE5070,
+        ]),
+    },
+    // (2^100)*G * 8
+    PointAffine {
+        x: GFp384::w64be([
+            0x11F3916064945560, 0x704C5C0EF51DA970,
+            0xBD4A5771EF91C315, 0x96ABDEC43684FE3D,
+            0x462C8F0DFB69B4F2, 0x9B11FC57C3A5A01A,
+        ]),
+        y: GFp384::w64be([
+            0x05BE970DC0E40A55, 0xC8039A1C4EAA5C7F,
+            0x57251617B667823D, 0x500A4CA71B44DB54,
+            0x413AB179B4B2E5D3, 0xED363315D8434A8A,
+        ]),
+    },
+    // (2^100)*G * 9
+    PointAffine {
+        x: GFp384::w64be([
+            0x15938AEB17F26CCC, 0x7B2C6F2BB3AB240C,
+            0x153CBA5DCEA1D73C, 0x6B094FFA5EC56061,
+            0x52466A2BA9CCF36F, 0xFA3613FC200BD009,
+        ]),
+        y: GFp384::w64be([
+            0x98BDE7E5C9907CAB, 0x506529D5556A23F7,
+            0xC89CA60C9DDDD487, 0xE9D5DF41D9BFC925,
+            0x8EB067CF966A4500, 0x6D91A5FB626C8F40,
+        ]),
+    },
+    // (2^100)*G * 10
+    PointAffine {
+        x: GFp384::w64be([
+            0x34F82F6A7BCE2054, 0x16A4F4D2047119D1,
+            0x3ECA8EC2A64DCAE8, 0xFE39BCC2DF1C10EC,
+            0x7C4CAAF7E07C14FE, 0x1D1956CA46FAB11F,
+        ]),
+        y: GFp384::w64be([
+            0x343F6EF9343A3D93, 0x6BB4781345C589AB,
+            0x4BB43BE79FF93941, 0x0094CE9A48F9AC9E,
+            0x7113041B1EAC67A3, 0xEB25FC329F63CD80,
+        ]),
+    },
+    // (2^100)*G * 11
+    PointAffine {
+        x: GFp384::w64be([
+            0xE09E59CE98AB6B97, 0x6EB0D91770696F8B,
+            0x26AA86ED1C77BF24, 0xCEDBD9EC59B00C98,
+            0x22E4DC4CEABBDD2A, 0x0C67D5629332DE10,
+        ]),
+        y: GFp384::w64be([
+            0xE8B8FA6A860025EA, 0x8BD33DF7810B1DBE,
+            0xC7AC1D50D9EE11E2, 0x0453D81E87888706,
+            0x9F7BD6E18DF925A5, 0x769B39215CC8CF01,
+        ]),
+    },
+    // (2^100)*G * 12
+    PointAffine {
+        x: GFp384::w64be([
+            0x2F0E7AA3652B7C4D, 0x53291228A2DC39BB,
+            0x5B6F2450F66842A4, 0xDDF6AE35E56EC0D6,
+            0xED4A5A91F9EC8B5B, 0x75021D1A35411EAD,
+        ]),
+        y: GFp384::w64be([
+            0x0A4C9C17CF5D62F0, 0xE2C9B330E3CB878F,
+            0xEEEA51F78AAF8545, 0x89BF3DD18830FDBF,
+            0xC7AF41F69A385FB8, 0xE856807D443530EA,
+        ]),
+    },
+    // (2^100)*G * 13
+    PointAffine {
+        x: GFp384::w64be([
+            0x4EF64FCF1C996089, 0x42A1034AA228F2A5,
+            0xC7C78139B2A16DCF, 0x572A4BE8D098E756,
+            0x04F214A79BF9F6BC, 0xEEB75282241C6172,
+        ]),
+        y: GFp384::w64be([
+            0xD97759FF042E5A52, 0x3F9BF3A0754BFE5A,
+            0x866927A61EF0D5AE, 0x37D64BC7C1F11842,
+            0x07EBF1732379B1F3, 0x9A0D8620EB0005A7,
+        ]),
+    },
+    // (2^100)*G * 14
+    PointAffine {
+        x: GFp384::w64be([
+            0x41EA17047D875170, 0x859815EDAA327437,
+            0x8388E07CBB3AE4E2, 0x5B98DCBCEF351376,
+            0x1A1B6494B86D4FC4, 0x3C6D538369FF9D3D,
+        ]),
+        y: GFp384::w64be([
+            0x96C6244E94748DFC, 0x52FD810E0F7C1BC9,
+            0x8E3FB2D0197C5CC0, 0x79E4C73B7FB39787,
+            0x945BDB9092900272, 0x5CEA70FCD1743C42,
+        ]),
+    },
+    // (2^100)*G * 15
+    PointAffine {
+        x: GFp384::w64be([
+            0x2EB0D1E664C676F9, 0xD8AEA60E5CF4730B,
+            0x4812D0AAB65A408A, 0x7D5577CB64128DDC,
+            0xF33BEB91FD74725F, 0xAF6CC166D8937FD2,
+        ]),
+        y: GFp384::w64be([
+            0x1EEFDD96B4364741, 0x57F044CB02559B2D,
+            0xE9E8BBF7B2F4D7F5, 0x941F5E81FF99FDD7,
+            0x6C876D5D4B724DA5, 0x9087490C2A6C7650,
+        ]),
+    },
+    // (2^100)*G * 16
+    PointAffine {
+        x: GFp384::w64be([
+            0x1B8607872971B4D6, 0x3CAEC84571FDB423,
+            0x8EA6E63C6D9FA8D0, 0xC867147EA86CA648,
+            0xB68661B30ADD81D5, 0x22FED8DDDE151060,
+        ]),
+        y: GFp384::w64be([
+            0xBD60A0400F597307, 0xCBEFA5933F6059B7,
+            0x3750825E38B8D116, 0x1DBEF0A1A4E95E69,
+            0x630C727A5B5D3E24, 0x2C6E8711DE1AA18F,
+        ]),
+    },
+];
+
+// Points i*(2^200)*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G200: [PointAffine; 16] = [
+    // (2^200)*G * 1
+    PointAffine {
+        x: GFp384::w64be([
+            0x3DC56A32127F8C4F, 0xF9BC786C01F2318D,
+            0x49BAF2960DF0FAFE, 0x9EF815283933F3E9,
+            0x6B258CA2371DA833, 0x794A459A6C91AD26,
+        ]),
+        y: GFp384::w64be([
+            0xEA9EC23DCFC82F75, 0x9A7EAF3243B2CBDC,
+            0x2103C6A0C8ACAF24, 0x28C3A32CD1B32A37,
+            0xA42F9DDE468E6A05, 0x329B33E950C23814,
+        ]),
+    },
+    // (2^200)*G * 2
+    PointAffine {
+        x: GFp384::w64be([
+            0x797FB73B75118435, 0x1A403C5DE5EC5018,
+            0x06F82E2E2163FF3D, 0xD5A30F74CCA16EB8,
+            0x1333227FA7FBD3B5, 0x3A9EBFAA9C3FA9A3,
+        ]),
+        y: GFp384::w64be([
+            0x114AB08F4F496EF7, 0x597D30AE28DF0396,
+            0xC50DF58C5AF00C25, 0x0551FADD143620E7,
+            0x3F997323E139916F, 0xB72C3035C71ECF44,
+        ]),
+    },
+    // (2^200)*G * 3
+    PointAffine {
+        x: GFp384::w64be([
+            0x5431FA786B1AC5DE, 0x04DEDCB67D8FF2C2,
+            0xFE5F0B3FA776D0DB, 0x3FCBAAEDA552CA6D,
+            0x122A70D319C5A0C4, 0xB04416CB26AEF425,
+        ]),
+        y: GFp384::w64be([
+            0xF0313E1CCB029582, 0x4D5D931C43B49B07,
+            0xB70495B7D18032F1, 0x705D530467923CCF,
+            0x495BF99E18E9E4F9, 0x332161BC084A5248,
+        ]),
+    },
+    // (2^200)*G * 4
+    PointAffine {
+        x: GFp384::w64be([
+            0xBD525D30F236D129, 0x817641E518433FF4,
+            0x176BDDC204E8BABD, 0xA60B1E5137964836,
+            0x8F3B5F4488B021C3, 0xBA9D76B04005DF08,
+        ]),
+        y: GFp384::w64be([
+            0xC94FCC281ACA88E9, 0x362E9DAEAB830E25,
+            0xA32CF13125107B53, 0x52B986174E3F0F62,
+            0xED7998CE867F7761, 0x881B5526B4A549DD,
+        ]),
+    },
+    // (2^200)*G * 5
+    PointAffine {
+        x: GFp384::w64be([
+            0xB403F894C9CACA01, 0xCAA45A8669C3C423,
+            0x1B744AE54C7BC0B9, 0x7F9F7F1DF823A157,
+            0x5A67FE7F316B307A, 0x2AE2F31049F7893F,
+        ]),
+        y: GFp384::w64be([
+            0xE42B16F1F880249F, 0xDDF948858702A2EE,
+            0x09367C96AB817F67, 0x6C7CD2E7A265CD63,
+            0x864DF0306490AC02, 0x7CA101955D3A32F8,
+        ]),
+    },
+    // (2^200)*G * 6
+    PointAffine {
+        x: GFp384::w64be([
+            0xD3D49EA8607C614B, 0xA76D6A4FD5168FEB,
+            0xDE8633ED5D40439B, 0x656503D7D90E259C,
+            0x7CEBBA35D611B2CE, 0xCBA4CA5F954BE6A8,
+        ]),
+        y: GFp384::w64be([
+            0xBD88A0C1FEEC5D52, 0xD3902D5FF334BA16,
+            0x0DBB4B8A352C2748, 0x45C0ACB3B0AE7507,
+            0xE8267DDC8B37C9EA, 0x4042EEC828269279,
+        ]),
+    },
+    // (2^200)*G * 7
+    PointAffine {
+        x: GFp384::w64be([
+            0x24334C46F618AB7D, 0x7B46CD2C6101CBCE,
+            0xDB98ADB35911192C, 0x5C95FC7EB92F9FEB,
+            0x8FEFF018CA9DA950, 0xEB6C5F9BAB8B5B1E,
+        ]),
+        y: GFp384::w64be([
+            0x76D458A5955EF722, 0xE18A295038940778,
+            0x44A955DDC4F23846, 0xA0A64E372AF979D8,
+            0x7B8DEE5DB355C4DB, 0x7BEF7D4DF4CD6FEF,
+        ]),
+    },
+    // (2^200)*G * 8
+    PointAffine {
+        x: GFp384::w64be([
+            0x4843250B787B5AB5, 0xDBAAD9771EBB3909,
+            0x35700204D81A406D, 0xB6CCC621071E6D41,
+            0x1C79D286CC463B49, 0xC52D567C285278AF,
+        ]),
+        y: GFp384::w64be([
+            0x05347F74762D3C6B, 0xEC6D6C24F57AAA33,
+            0x295BE1420C49EB7C, 0x2D6066AE373A76B4,
+            0x5680D51B5E996346, 0x154DDACDFB0F1B8C,
+        ]),
+    },
+    // (2^200)*G * 9
+    PointAffine {
+        x: GFp384::w64be([
+            0xF478E65377F33A3F, 0x95B7D4CCC1CD75E6,
+            0x750B279CBA37B239, 0x2433D5DE3428F01E,
+            0x8B2CD6DB86681C3A, 0xD46A64C04B0A79F3,
+        ]),
+        y: GFp384::w64be([
+            0xAE1569C95EC43704, 0xCEE7DBD4ED57AFE4,
+            0xAF2CFDEA24A4DEC3, 0x278D648DB614A076,
+            0x11D41E9DD9182E64, 0x2DE3DB02F7374AB1,
+        ]),
+    },
+    // (2^200)*G * 10
+    PointAffine {
+        x: GFp384::w64be([
+            0xD8237A49706E60EA, 0x8E90B0CB57FE4EDC,
+            0x2AE1892E9CEA6AC8, 0xE22ACD074624BF34,
+            0xF15FDADBA92ED920, 0x784CB67784983052,
+        ]),
+        y: GFp384::w64be([
+            0x01134BA43DFD2016, 0x78AF86D4939C21B1,
+            0x160D9F4CE30B9C28, 0x18F56B911D1289DD,
+            0x74A46B50552E2BDF, 0xE3C5236FA3858830,
+        ]),
+    },
+    // (2^200)*G * 11
+    PointAffine {
+        x: GFp384::w64be([
+            0x23E884CF9BAF9ED1, 0xA79B2407AB6E144F,
+            0xC72FF6296481E45E, 0x0AED5B5673A7CB0A,
+            0xCA1FF56CF9EA3715, 0x99B41EEADB07A28A,
+        ]),
+        y: GFp384::w64be([
+            0x42A01723B3795CFC, 0x6931A9BBB1F2C80B,
+            0x305814FA61FB94F2, 0xEFCAF97F54D6720E,
+            0xF6FC89EBB45A7527, 0xA4399B7A8A2DE0AF,
+        ]),
+    },
+    // (2^200)*G * 12
+    PointAffine {
+        x: GFp384::w64be([
+            0xE3C24012BBCCF247, 0xC51C61C83722A5A9,
+            0x9801DCF908E3DE18, 0x5AA1C7AE5974D513,
+            0x6F69456E7C4EC08D, 0x403F39579825529E,
+        ]),
+        y: GFp384::w64be([
+            0xF62AFB2905C97F6B, 0x10E8FABBDA630AA9,
+            0xDC1CE03A2E33F9C5, 0x68340B1D91A97F21,
+            0x2771D98C8084F225, 0x4D8378A7BD2C240D,
+        ]),
+    },
+    // (2^200)*G * 13
+    PointAffine {
+        x: GFp384::w64be([
+            0xAF4D4F4A385851FD, 0x0818DB0F00D6F359,
+            0x04227DD87FB6624E, 0xA384D14EAC66E784,
+            0x42725078B7F12B72, 0xCDB0F241911AA294,
+        ]),
+        y: GFp384::w64be([
+            0x691682244B0874E8, 0x100F5A86B32BFB92,
+            0x49845025A31B0ABE, 0xCB96B2EB41447FBB,
+            0x400CDE948FF16A67, 0xA893B16835A166D8,
+        ]),
+    },
+    // (2^200)*G * 14
+    PointAffine {
+        x: GFp384::w64be([
+            0xA30F3010D0CD7A1D, 0xE6AF1971B7C6485A,
+            0xBD4297D7EC68A835, 0x5FECE2FA08F89D1E,
+            0x55869CE34FFBCF4A, 0xA088B6DDE659EDEF,
+        ]),
+        y: GFp384::w64be([
+            0x3E22EE543531EC9C, 0xCEC3A16FC01A1B7F,
+            0x210D0F0EBC3F33EC, 0xBF557B46CA25C2C9,
+            0x4B558EF92CC7FB41, 0x2AE73BB476B35742,
+        ]),
+    },
+    // (2^200)*G * 15
+    PointAffine {
+        x: GFp384::w64be([
+            0x10C78D33C4F1CBE9, 0xDF322C97F7B83F76,
+            0xE362E5460E152E0B, 0x01DAE32ECC6B4D31,
+            0xAAD8C2D696662B67, 0xF9D82A4B6AA42DB2,
+        ]),
+        y: GFp384::w64be([
+            0x2D37F1EA8F953A81, 0x5E593589655FAF8F,
+            0xCEB27F7A3BAFABCF, 0x4414C79007E5EB8F,
+            0xD9597EA7F2EF2546, 0x3B90A6BD2232293C,
+        ]),
+    },
+    // (2^200)*G * 16
+    PointAffine {
+        x: GFp384::w64be([
+            0xC57F7195462109A0, 0x1940113C400435FA,
+            0x14B81ED8D51ABD7C, 0x18B1C2E02D8D8378,
+            0x66E39E7B1C6E73DA, 0xF68A33E61B635020,
+        ]),
+        y: GFp384::w64be([
+            0xD1137EA3C0B5ABD9, 0x08402D5A9AF2E50D,
+            0x7379EAC9D21EDA2A, 0x25F6A9B4EEEEBA47,
+            0x1ABEA7851B8EC400, 0x7EC34DA2279CAC7C,
+        ]),
+    },
+];
+
+// Points i*(2^300)*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G300: [PointAffine; 16] = [
+    // (2^300)*G * 1
+    PointAffine {
+        x: GFp384::w64be([
+            0x1BFD76A030CC3EDA, 0x11FAC541D9210950,
+            0xAAAC218B29F24C09, 0x8D31786B29199BE6,
+            0x2A4B411943C7549C, 0x3CEDB737F3CDDF3F,
+        ]),
+        y: GFp384::w64be([
+            0x4BF2143E6FEF27C9, 0x8C49BB4ACDFB8100,
+            0x796343D64DEF5A2F, 0x0A18D7A8D34FDE05,
+            0xE364A04FD4E9C563, 0x07ABD76200F30197,
+        ]),
+    },
+    // (2^300)*G * 2
+    PointAffine {
+        x: GFp384::w64be([
+            0x6FE7983CBD4EACE9, 0x7E2FF959AC82AA1C,
+            0xD6C2FE4D81AE25F4, 0xAE7E1CB4F534AEDD,
+            0x0AF8313FBCB37F05, 0x6309727869A2BB56,
+        ]),
+        y: GFp384::w64be([
+            0xFA23E0B54A605A27, 0xBBAEB974FAF717FE,
+            0xE5C551C7F9BCEF59, 0x0568A87B4FDE7B5C,
+            0x850DA2A6812FDA5F, 0xD7B295809A84E277,
+        ]),
+    },
+    // (2^300)*G * 3
+    PointAffine {
+        x: GFp384::w64be([
+            0x3EE68336EEF50900, 0x517DF947E7B992FF,
+            0x45ABE907DD401C85, 0xE08F53216D3573B7,
+            0xD0907AE072527FDE, 0x501D3093BB2229F9,
+        ]),
+        y: GFp384::w64be([
+            0x93D72DACD370E6BC, 0x10D777D7FB059256,
+            0x19268B954F0FCBF4, 0xF1234463F58A09D0,
+            0xFA4C80BD4CE6D5BA, 0x148A91629419091C,
+        ]),
+    },
+    // (2^300)*G * 4
+    PointAffine {
+        x: GFp384::w64be([
+            0xA26C61D7975DA73F, 0xE156A68C4A31C37E,
+            0xED64D356D123BBD6, 0xC0329F34B529D246,
+            0xE6F4477CD836A67F, 0x3D37829A84DD3300,
+        ]),
+        y: GFp384::w64be([
+            0x35B90F84845285B7, 0x37C47E293F414924,
+            0xCA6B4C84157C9E25, 0xD4CCC9FE32A9370E,
+            0x68A97C17BBD38D76, 0xE6BDD42B46256D01,
+        ]),
+    },
+    // (2^300)*G * 5
+    PointAffine {
+        x: GFp384::w64be([
+            0x852A261EDB54A98C, 0xDD2A758403600BF7,
+            0x44C39F4A41EFFEFB, 0x37F0753869C00B0C,
+            0xBE6D2F5D46CDB439, 0x8C0CB374C0ED9F39,
+        ]),
+        y: GFp384::w64be([
+            0x93D933E409767AA0, 0xFF49FE22D57F9D14,
+            0x433EEAEB5C85DA77, 0x5A9973D69768BDB7,
+            0x2EAC8EC1DA40BB85, 0x0EA751BEA1BB628E,
+        ]),
+    },
+    // (2^300)*G * 6
+    PointAffine {
+        x: GFp384::w64be([
+            0x521B88721E52E407, 0xF869AA900B4D3459,
+            0x5255F2072CA93D44, 0xE9703B6EE792F224,
+            0xAA40E8A61585F15E, 0xBA958FAF51E8EFF9,
+        ]),
+        y: GFp384::w64be([
+            0x9645E44B80C0E3FF, 0x594BEB8C9593A52B,
+            0xA65562489DE59A4C, 0xB141871FCF3BE78A,
+            0x10E800E633B8DC14, 0xD85F1F6FA2CECF68,
+        ]),
+    },
+    // (2^300)*G * 7
+    PointAffine {
+        x: GFp384::w64be([
+            0xC50099D0EF18623F, 0xAC8280228EF1AD2D,
+            0x897F6A26951C9FA4, 0x21E7AD052DF20C3C,
+            0xC606CB7F5D078771, 0xD0FF407A8331FABE,
+        ]),
+        y: GFp384::w64be([
+            0xC9610B4841EDBF53, 0x09958E7BEFC877BB,
+            0xC105357EED8A85A5, 0x9539E1417807EAB2,
+            0x112D204933AA8A64, 0x2871FCCE0C1DF5B9,
+        ]),
+    },
+    // (2^300)*G * 8
+    PointAffine {
+        x: GFp384::w64be([
+            0x3FCFD59DDDF5AFC0, 0x8590D6144A0C71C7,
+            0xD9DDF253E411A105, 0x60515B381A04CCF4,
+            0x7FC73730A1FBCBBD, 0x274E4D05081AC1A6,
+        ]),
+        y: GFp384::w64be([
+            0x3B75E0E9E8B1C569, 0x8DD4E1C16FE41165,
+            0xDD99E65EF05A99E1, 0xA62E6466C4B75A17,
+            0xCFB9C03AEA68DB3D, 0xEDBE4469A87AC7C8,
+        ]),
+    },
+    // (2^300)*G * 9
+    PointAffine {
+        x: GFp384::w64be([
+            0x4A72A0BD51E0F326, 0xE5B41B63832EA377,
+            0xEBC830A92AC7223A, 0x8197B57F0D1455BD,
+            0x1583A7648A0611B5, 0xA6F256616F1B6ECF,
+        ]),
+        y: GFp384::w64be([
+            0xA37E39B809E1E341, 0x5287F696BE2E429F,
+            0x7C86A13A5FAF078F, 0xD8A1D8E006926E7A,
+            0x4FB3520C1E32BB8D, 0xC571EE43FABDF519,
+        ]),
+    },
+    // (2^300)*G * 10
+    PointAffine {
+        x: GFp384::w64be([
+            0x08A195767A1ABAED, 0x426EEF8F0899B4E7,
+            0x2F93C6925080F0D5, 0x8632D3EDC96308C5,
+            0x4D8C65A175C54FB9, 0x023FD897A32D6D70,
+        ]),
+        y: GFp384::w64be([
+            0xC56107DA87C76686, 0x6E5C96E630DBF2D2,
+            0xD1B79B16C2862A93, 0xE16CD23BEB4EE460,
+            0x4EB189B5318BBB36, 0x9FFC4AA86B660B8A,
+        ]),
+    },
+    // (2^300)*G * 11
+    PointAffine {
+        x: GFp384::w64be([
+            0x5B113D30D2E0B5D9, 0x513BE6C9856DB1E1,
+            0x6A4497C9F14871EE, 0x1A8B98485EE9882E,
+            0xA428DCA8FF39C452, 0x654CB28589B45CFA,
+        ]),
+        y: GFp384::w64be([
+            0x4835EBBE3B8E1A5F, 0x7E5518FCFCF26975,
+            0x4EE0B758988B5CF2, 0x41C8CEAE0116A5FF,
+            0x1EAAE80B61BF8B16, 0x7202C3CC9DFDC36B,
+        ]),
+    },
+    // (2^300)*G * 12
+    PointAffine {
+        x: GFp384::w64be([
+            0xC23CD1E16B6167D7, 0xC1B8175A8E753E18,
+            0x8B1F68B2282A24C4, 0xA657CDF70D91CFC6,
+            0xAF36956B43C10066, 0xA11CA67475A8EE8B,
+        ]),
+        y: GFp384::w64be([
+            0x32541B9E45AC16DC, 0xCE970A68B0CC91AD,
+            0xACA0237883EA503B, 0x1686FE6946E239C7,
+            0x2187161CFAEED936, 0xD958D5E3641E8280,
+        ]),
+    },
+    // (2^300)*G * 13
+    PointAffine {
+        x: GFp384::w64be([
+            0xC39D5942EBB60CB5, 0x7BDCDA5BF28B07BC,
+            0xC893829C1DEAD97F, 0xCD7B6C1167673587,
+            0x24731A4CCCE9BB79, 0x5ABF12500663CB59,
+        ]),
+        y: GFp384::w64be([
+            0x24B080E494EFDB4F, 0x58B94D9D420498C7,
+            0x04E72127AA20661A, 0xC866DE13220B2EE5,
+            0x31E42DBD1DDBB463, 0xA4F6FF0B2904589A,
+        ]),
+    },
+    // (2^300)*G * 14
+    PointAffine {
+        x: GFp384::w64be([
+            0xDBCBB763FB045976, 0x441D071B0C044D3D,
+            0x3DFD13A97E6D2FA1, 0xB3524B32F41729A9,
+            0x9E6CCCA1BF7D42BE, 0x429266F16E4FD664,
+        ]),
+        y: GFp384::w64be([
+            0xE87AFFFF18D15950, 0x2570017C1E5A7337,
+            0x1FE08514AFA8D3C0, 0x8D388C7ACE80B0FD,
+            0x7B60A78D23064B4B, 0xCA6AC38FDFD057A7,
+        ]),
+    },
+    // (2^300)*G * 15
+    PointAffine {
+        x: GFp384::w64be([
+            0xC35006325EC9B51B, 0x8C198D9CB9DA248A,
+            0xFA27901D00ABA599, 0x726DD47C7E9432DC,
+            0x6527C28023A87820, 0x9027BA67C5EBB37E,
+        ]),
+        y: GFp384::w64be([
+            0x52B23BAE223AFB1E, 0xC1A83F9C8BEE9B79,
+            0x0868691B62131C84, 0x2D5BBD2C7266A738,
+            0x6C5B7EE2110E2DBB, 0x3727FE88C6F770A0,
+        ]),
+    },
+    // (2^300)*G * 16
+    PointAffine {
+        x: GFp384::w64be([
+            0xA79E73CDB31BCB79, 0xB81682FA0E4D57AC,
+            0x875A82B402C00B31, 0xD23337A1D8BE8B31,
+            0xB52DBE4D48AC42B2, 0xBC988641044ACC65,
+        ]),
+        y: GFp384::w64be([
+            0xF968AA661801EE82, 0xD53FF1B67C98448B,
+            0x213F21ACC1C64B56, 0x70B3C1A1E5B5A54D,
+            0x363211F591556534, 0x1EE8AF1914F02364,
+        ]),
+    },
+];
+
+// ========================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Point, Scalar, PrivateKey, PublicKey};
+    use super::{signature_to_der, signature_from_der};
+    use crate::{DecodeError, ParseError};
+    use sha2::{Sha256, Sha384, Digest};
+
+
+    #[test]
+    fn base_arith() {
+        // For a point P (randomly generated on the curve), points i*P
+        // for i = 1 to 6, encoded (compressed).
+        const EPC: [&str; 6] = [
+            "0200fb3a66e9e0976b4b3ca3f8742e1cd97cf64d489caaa47b73abd03eba39874c65af43968ced324e4e64c85bf33b5c8d",
+            "024108ff6c99f33b367dcfcb12e135370b080e430e26f70abc5712c00af0710df6eac30c6b32ffce8d97816260ad0c2891",
+            "02087528359cbca9b74d62dc26aaccc4bd1c456c543a66b7edcd8bf648c87a8ce4bb79f0a8539388fa83c04b9ad1566862",
+            "0285452a6268b7886c3094edac5067a014fd14a1a38498a4f2583417e2b7fea2363b97893b6b3ba1c12acaad5d08c7b1cb",
+            "0215c7b0dc43af15add1fa9443a16d67a68369f8fa4e5cfb2a164918ef1db4631a9b91660a41028578a3298b47ddeb6914",
+            "0325fe0b84fd3b691522353f128025fa8de509bc8453ed8f60cf601f575459694f2d88e79bd16d2e417ab45e77920a936c",
+        ];
+
+        // Neutral encoding and decoding.
+        let P0 = Point::decode(&[0u8]).unwrap();
+        assert!(P0.isneutral() == 0xFFFFFFFF);
+        assert!(P0.encode_compressed() == [0u8; 49]);
+        assert!(P0.encode_uncompressed() == [0u8; 97]);
+
+        let mut PP = [Point::NEUTRAL; 6];
+        for i in 0..6 {
+            let buf = hex::decode(EPC[i]).unwrap();
+            let P = Point::decode(&buf).unwrap();
+            assert!(P.isneutral() == 0);
+            assert!(P.encode_compressed()[..] == buf[..]);
+            let Q = Point::decode(&P.encode_uncompressed()).unwrap();
+            assert!(P.equals(Q) == 0xFFFFFFFF);
+            assert!(P.equals(P0) == 0);
+            PP[i] = P;
+        }
+        let P1 = PP[0];
+
+        // Additions, doublings and small multiples.
+        assert!((P1 + P0).equals(P1) == 0xFFFFFFFF);
+        assert!((P0 + P1).equals(P1) == 0xFFFFFFFF);
+        assert!((P1 - P1).isneutral() == 0xFFFFFFFF);
+        assert!(P0.double().isneutral() == 0xFFFFFFFF);
+        assert!((P1 + P1).equals(PP[1]) == 0xFFFFFFFF);
+        assert!(P1.double().equals(PP[1]) == 0xFFFFFFFF);
+        assert!((PP[1] + P1).equals(PP[2]) == 0xFFFFFFFF);
+        assert!((PP[2] + PP[1]).equals(PP[4]) == 0xFFFFFFFF);
+        assert!((PP[4] - PP[3]).equals(P1) == 0xFFFFFFFF);
+        assert!(PP[2].double().equals(PP[5]) == 0xFFFFFFFF);
+        assert!(P1.xdouble(2).equals(PP[3]) == 0xFFFFFFFF);
+        assert!(P0.xdouble(3).isneutral() == 0xFFFFFFFF);
+        for i in 0..6 {
+            assert!((P1 * ((i + 1) as u64)).equals(PP[i]) == 0xFFFFFFFF);
+        }
+        assert!((P1 * 0u64).isneutral() == 0xFFFFFFFF);
+        let mut Q = P1;
+        for _ in 0..10 {
+            Q = Q.double();
+        }
+        assert!(Q.equals(P1.xdouble(10)) == 0xFFFFFFFF);
+        assert!(Q.equals(P1 * 1024u64) == 0xFFFFFFFF);
+
+        // Negation and conditional operations.
+        let mut Q = P1;
+        Q.set_condneg(0);
+        assert!(Q.equals(P1) == 0xFFFFFFFF);
+        Q.set_condneg(0xFFFFFFFF);
+        assert!(Q.equals(-P1) == 0xFFFFFFFF);
+        assert!(Q.encode_compressed()[0] == 0x03);
+        assert!(Point::select(&P1, &PP[1], 0).equals(P1) == 0xFFFFFFFF);
+        assert!(Point::select(&P1, &PP[1], 0xFFFFFFFF).equals(PP[1])
+            == 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn mulgen() {
+        // Test vector computed with an independent implementation
+        // (OpenSSL).
+        let s = Scalar::decode(&hex::decode("28dafc9b2f26a6f8dbd408440a83928c54530825bd44565bd9af13f238fa5643276ca498919904b1b877ac2b6ba9540d").unwrap()).unwrap();
+        let enc = hex::decode("025fb3cea26b1e8dd6b75fd71e5eda9b89aa4bea3df47adde1f0e0a5348c13bf6088f27e7c39cd9d226cdcf75f98838976").unwrap();
+
+        let R = Point::decode(&enc).unwrap();
+        let P = Point::BASE * s;
+        assert!(P.equals(R) == 0xFFFFFFFF);
+        assert!(P.encode_compressed()[..] == enc[..]);
+        let Q = Point::mulgen(&s);
+        assert!(Q.equals(R) == 0xFFFFFFFF);
+        assert!(Q.encode_compressed()[..] == enc[..]);
+    }
+
+    #[test]
+    fn mul() {
+        let mut sh = Sha384::new();
+        for i in 0..20 {
+            // Build pseudorandom s1 and s2
+            sh.update(((2 * i + 0) as u64).to_le_bytes());
+            let v1 = sh.finalize_reset();
+            sh.update(((2 * i + 1) as u64).to_le_bytes());
+            let v2 = sh.finalize_reset();
+
+            let s1 = Scalar::decode_reduce(&v1);
+            let s2 = Scalar::decode_reduce(&v2);
+            let s3 = s1 * s2;
+            let P1 = Point::mulgen(&s1);
+            let Q1 = s1 * Point::BASE;
+            assert!(P1.equals(Q1) == 0xFFFFFFFF);
+            let P2 = Point::mulgen(&s3);
+            let Q2 = s2 * Q1;
+            assert!(P2.equals(Q2) == 0xFFFFFFFF);
+        }
+
+        // Edge cases: zero, and -1 (the scalar with the largest value).
+        assert!(Point::mulgen(&Scalar::ZERO).isneutral() == 0xFFFFFFFF);
+        assert!((Point::BASE * Scalar::ZERO).isneutral() == 0xFFFFFFFF);
+        assert!(Point::mulgen(&Scalar::MINUS_ONE).equals(-Point::BASE)
+            == 0xFFFFFFFF);
+        assert!((Point::BASE * Scalar::MINUS_ONE).equals(-Point::BASE)
+            == 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn mul_add_mulgen() {
+        let mut sh = Sha384::new();
+        for i in 0..20 {
+            // Build pseudorandom A, u and v
+            sh.update(((3 * i + 0) as u64).to_le_bytes());
+            let v1 = sh.finalize_reset();
+            sh.update(((3 * i + 1) as u64).to_le_bytes());
+            let v2 = sh.finalize_reset();
+            sh.update(((3 * i + 2) as u64).to_le_bytes());
+            let v3 = sh.finalize_reset();
+            let A = Point::mulgen(&Scalar::decode_reduce(&v1));
+            let u = Scalar::decode_reduce(&v2);
+            let v = Scalar::decode_reduce(&v3);
+
+            // Compute u*A + v*B in two different ways; check that they
+            // match.
+            let R1 = u * A + Point::mulgen(&v);
+            let R2 = A.mul_add_mulgen_vartime(&u, &v);
+            assert!(R1.equals(R2) == 0xFFFFFFFF);
+        }
+    }
+
+    #[test]
+    fn verify_helper() {
+        let mut sh = Sha384::new();
+        for i in 0..20 {
+            // Build pseudorandom Q, s and k.
+            // Compute R = s*G - k*Q
+            sh.update(((3 * i + 0) as u64).to_le_bytes());
+            let v1 = sh.finalize_reset();
+            sh.update(((3 * i + 1) as u64).to_le_bytes());
+            let v2 = sh.finalize_reset();
+            sh.update(((3 * i + 2) as u64).to_le_bytes());
+            let v3 = sh.finalize_reset();
+            let Q = Point::mulgen(&Scalar::decode_reduce(&v1));
+            let s = Scalar::decode_reduce(&v2);
+            let k = Scalar::decode_reduce(&v3);
+            let R = Point::mulgen(&s) - k * Q;
+
+            // verify_helper_vartime() must return true, but this
+            // must change to false if we change a scalar or a point.
+            assert!(Q.verify_helper_vartime(&R, &s, &k));
+            assert!(!Q.verify_helper_vartime(&R, &(s + Scalar::ONE), &k));
+            assert!(!Q.verify_helper_vartime(&R, &s, &(k + Scalar::ONE)));
+            assert!(!Q.verify_helper_vartime(&(R + Point::BASE), &s, &k));
+            assert!(!(Q + Point::BASE).verify_helper_vartime(&R, &s, &k));
+        }
+    }
+
+    #[test]
+    fn decode_verbose() {
+        assert!(Point::decode_verbose(&[]).err()
+            == Some(DecodeError::BadLength));
+        assert!(Point::decode_verbose(&[0x00; 33]).err()
+            == Some(DecodeError::BadLength));
+        assert!(Point::decode_verbose(&[0x01]).err()
+            == Some(DecodeError::BadHeader));
+        let mut buf = Point::BASE.encode_uncompressed();
+        assert!(Point::decode_verbose(&buf).is_ok());
+        buf[0] = 0x06;
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::BadHeader));
+        assert!(Point::decode_sec1(&buf).err()
+            == Some(DecodeError::NonCanonicalEncoding));
+        buf[0] = 0x06 + (buf[96] & 1);
+        assert!(Point::decode_sec1(&buf).unwrap().equals(Point::BASE)
+            == 0xFFFFFFFF);
+        buf[0] = 0x04;
+        buf[96] ^= 1;
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NotOnCurve));
+        buf[1..49].fill(0xFF);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+        let mut buf = Point::BASE.encode_compressed();
+        buf[1..49].fill(0xFF);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+    }
+
+    #[test]
+    fn signatures() {
+        // Test vectors from RFC 6979 (A.2.6), with SHA-384.
+        let sk = PrivateKey::decode(&hex::decode("6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d896d5724e4c70a825f872c9ea60d2edf5").unwrap()).unwrap();
+        let mut ep = [0x04u8; 97];
+        ep[1..49].copy_from_slice(&hex::decode("ec3a4e415b4e19a4568618029f427fa5da9a8bc4ae92e02e06aae5286b300c64def8f0ea9055866064a254515480bc13").unwrap());
+        ep[49..97].copy_from_slice(&hex::decode("8015d9b72d7d57244ea8ef9ac0c621896708a59367f9dfb9f54ca84b3f1c9db1288b231c3ae0d4fe7344fd2533264720").unwrap());
+        let pk = PublicKey::decode(&ep).unwrap();
+        assert!(sk.to_public_key().encode_uncompressed() == ep);
+        assert!(sk.encode()[..] == hex::decode("6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d896d5724e4c70a825f872c9ea60d2edf5").unwrap()[..]);
+        const KAT: &[(&[u8], &str)] = &[
+            (b"sample", "94edbb92a5ecb8aad4736e56c691916b3f88140666ce9fa73d64c4ea95ad133c81a648152e44acf96e36dd1e80fabe4699ef4aeb15f178cea1fe40db2603138f130e740a19624526203b6351d0a3a94fa329c145786e679e7b82c71a38628ac8"),
+            (b"test", "8203b63d3c853e8d77227fb377bcf7b7b772e97892a80f36ab775d509d7a5feb0542a7f0812998da8f1dd3ca3cf023dbddd0760448d42d8a43af45af836fce4de8be06b485e9b61b827c2f13173923e06a739f040649a667bf3b828246baa5a5"),
+        ];
+        for (msg, esig) in KAT.iter() {
+            let hv: [u8; 48] = Sha384::digest(msg).into();
+            let sig = sk.sign_hash(&hv, &[]);
+            assert!(sig[..] == hex::decode(esig).unwrap()[..]);
+            assert!(pk.verify_hash(&sig, &hv));
+
+            // Altered hash or signature must be rejected.
+            let mut hv2 = hv;
+            hv2[5] ^= 0x01;
+            assert!(!pk.verify_hash(&sig, &hv2));
+            let mut sig2 = sig;
+            sig2[20] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+            let mut sig2 = sig;
+            sig2[70] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+
+            // Extra randomness changes the signature, but it is still
+            // valid.
+            let sig3 = sk.sign_hash(&hv, b"extra");
+            assert!(sig3 != sig);
+            assert!(pk.verify_hash(&sig3, &hv));
+        }
+
+        // Shorter hash values (e.g. SHA-256) are supported.
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        let sig = sk.sign_hash(&hv, &[]);
+        assert!(pk.verify_hash(&sig, &hv));
+        assert!(!pk.verify_hash(&sig, &Sha384::digest(b"sample")));
+    }
+
+    #[test]
+    fn sigver() {
+        // Test vectors computed with an independent implementation
+        // (OpenSSL, randomized ECDSA with SHA-384):
+        // (public x, public y, hash value, signature)
+        const KAT: &[[&str; 4]] = &[
+            ["284ec9576b347cdbef0d24dc8cffb16fbbb2bb3c4a4448bce3a4ea89dada16edd1f9707e45cd5b33d30917fe05afcd0a",
+             "9bf99b82e672c5a588df0bb79930c85d386d2e993f18dc6fbfc0399b584e16cf92dbf777a1627d28d14b233774f43d4c",
+             "f213a59fe3e41163911478319d2ade5e3204399a6546bdeb205f23ff3c6dea69de855d58e9ba4f050875b63adbf21e77",
+             "95e51305d9ac390d33d7f625802b0599e1157f1bdadd2acbb38a80088f46d9913ce23d70dcc3341006ee7b5d73e234ffa71870d65c026b68457f7fadbdda8c6643372daac5944c4600d726165a4a58105c3b56c2528779c6f2e2446ceedaf938"],
+            ["a9f1886cca55cda2895d46b9f0dc7d4983c7ba7ae7d8e13fc934a06a8284945f4bcdac4408ead18bd4ac67e5316698bb",
+             "035692e5c64e52cdf2853d3d20ab050a1bcdc6b2aee9fb8dcbe30eaa95b101aa495925966bace7a3490ffad8a57de994",
+             "f55cb0c77c151c0bd212df02f69fb2a921f4468a5cbddf6e8e924fd2e37e2cbffe4de978c4dba06d89a8e50d072af95f",
+             "159bb4efa78e7690ab041add5c4e4b240bb8eef24fd5a253b28e23974e579a927ab0230d47fbb4c25ef9feb8fb7610238e714f17875843b33a4980b975ac31d438b100a92b6904230a271523fcd47f4f5b20c3e4b7deb5bfe770dd824c598db8"],
+            ["f857d218378a5bd030acff76c830acfebf062f22da72d39e4160b0c4b456d7c3a32ad230de451a3af896378cd122cd9a",
+             "3cfca97a233e2090af087205fd111f5d96a9f5a32d01293fa15ace031c359177abd5621ed370ec17947f67ab006020c8",
+             "d89163e93db96bf1a03b714b636c2e8f34e11530e0608bfbdc65aa64df295e3b57466ae302f99b217fadef6143ff5ee4",
+             "70edf7e40dfc947923e6e1f980e840a47288869d3ab0cec2fd72bfbb4c374a546d981c826c18839167d796a5f1633487c0900fb2914b9b157afe7da1f8b8e83f7f08a34b8b7ddbc75d317ed4d2a40c215c0f3de299c025517a18ae15d4d53bc2"],
+            ["9b00d1d75e1591e3f81d1b061b311ed5057b7c884cb760f7b6db8078a9f989aa3c73a9f532923ca5c519ad513adfff21",
+             "3ff91a8c98912532e6f179e5e10909523d406d13361bf589cf01497a985d0fe16332a920cfb04b947f3d39b6c7aa1016",
+             "ce4a580d6d8b022254936d33c63768c1263681f54e96ffec3b1ee14a2609162e5e8249f4e31951745f3cc78d00e4ad0a",
+             "dd8d4dd5ec7250840ea70e78650d4323e4116f5fe519875ade1f36ab39dace539893d9eb492bb2a5fe219f5c99d9b1f3e563cd5bec50b92c211fc431baf27e22916f7c50017544e89baf467b009c5e29cc653b73f3a81172cf7390139df167cc"],
+        ];
+        for kv in KAT.iter() {
+            let mut ep = [0x04u8; 97];
+            ep[1..49].copy_from_slice(&hex::decode(kv[0]).unwrap());
+            ep[49..97].copy_from_slice(&hex::decode(kv[1]).unwrap());
+            let pk = PublicKey::decode(&ep).unwrap();
+            let pk2 = PublicKey::decode(&pk.encode_compressed()).unwrap();
+            assert!(pk.point.equals(pk2.point) == 0xFFFFFFFF);
+            let hv = hex::decode(kv[2]).unwrap();
+            let sig = hex::decode(kv[3]).unwrap();
+            assert!(pk.verify_hash(&sig, &hv));
+            let mut hv2 = hv.clone();
+            hv2[47] ^= 0x80;
+            assert!(!pk.verify_hash(&sig, &hv2));
+            let mut sig2 = sig.clone();
+            sig2[0] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+            let mut sig2 = sig.clone();
+            sig2[95] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+
+            // r and s are swapped.
+            let mut sig2 = [0u8; 96];
+            sig2[..48].copy_from_slice(&sig[48..]);
+            sig2[48..].copy_from_slice(&sig[..48]);
+            assert!(!pk.verify_hash(&sig2, &hv));
+        }
+
+        // Out-of-range and zero values are rejected.
+        let pk = PrivateKey::from_seed(b"sigver").to_public_key();
+        assert!(!pk.verify_hash(&[0u8; 96], &[0u8; 48]));
+        assert!(!pk.verify_hash(&[0xFFu8; 96], &[0u8; 48]));
+        assert!(!pk.verify_hash(&[0x01u8; 95], &[0u8; 48]));
+    }
+
+    #[test]
+    fn ecdh() {
+        // Test vectors computed with an independent implementation
+        // (OpenSSL):
+        // (peer x, peer y, private key, public x, public y, shared secret)
+        const KAT: &[[&str; 6]] = &[
+            ["e9d94a9eca519bac85bc2d20ad6dc6d0b275002f578f44ec7040286f4537c7bc7ba4b392686014d9dadc34ab80525286",
+             "03261b5074b9296d661c45dcda7de7d0adc5c1418bfa832a8edb2a6e2e73c49a918bb74d77b68d9a0c6f78bff936fd5b",
+             "0709451448ab7279f209d61c612a948b3b9acd1c8eeed390b2edd3491fb3ce3a3f28b6f47068ce063e421b4c5fc36775",
+             "9e715bbe32da92c2601c35d3f4f947be11d5e5827756250e5e1b5c768d33cfd734e71548eb57b43baf2764e082a74c51",
+             "d6919eed47747eaa21264b689b23d7f3065251c339187465ff8fd138780c33f09e0e90f0642ac6846c4097ec145afde2",
+             "73ab2afde8c8e8e94d452bf9a37c13d1bfda3a8978596bfde5efba61df923f57e4027e7934f2ff80b1a0aa38e1932678"],
+            ["d904c1bf2abfab49389999ae8e72f6f305f3c0d0b7fed3d37e4ef2c1f0452020d5a04ecf3169d358ff8d7a76d5c02003",
+             "8ffa4ab26610c350afcdc6c98843d175f40039127316e99d46751999a8e5eef83c25f170739019a6685619985eebfd40",
+             "489eff9c6f0ee379e7dccbeb67235e51e011b7627c43365b7e6c6b03dee43ed7651afd26eadeada66e7cdc0ca2446908",
+             "e3ef93498fb0726a7589f57ff3698ee74b668972c86f5ce6e2233097f78f7f47cf9b145126bd69cb290df79f81fe1b4a",
+             "b4f4b052eae708265b80894953d01e654ddfed106fb9e6f9dbcbae03fc0cf8d754004f85bd6285c0b72b616dcdbc34a2",
+             "b7524c28215f836d5688141815b77ad2384317899ddab860a3eac7cce2ea22ae93a212195dbab2d02c1cb2b5b42c75b4"],
+            ["71b5356d396a8727ca762ca209f60b3932daee6d9f9718cc7a5868db341fdcace64f5d84a328745cff049ec62bdb1b87",
+             "3e362ce4c08c5ffca53039eb0706bb4eeaba350ff8d89ecedd1c67a6e6ab18217dd9c717d4ae712dcc86d909e31bddc0",
+             "3ae01b649d32fae05516fd2b3b016b21c2905f8fb0493662cca49f64e352aa3a71fb0c7372a559092d5e298ae1105f68",
+             "fd5658c6e91a17fe3be08013ff219e7ddb0fc3fc0f9b2641a496f39730e4f26f00d0df7341f9b04a6c1af37a0cb72bd5",
+             "f2f209d38f5a23334d7b1f31a2dd06519cbcf78c93ca8d26e74c1450bb4f4c5b604a2317b51553503a7afc8c822202d3",
+             "99a4f714bf4e9863d7f451bc7e6e97d67526d3024347dfe9684a7fe9253b249d5e96a42f218acfedfc5c4e1fa855c7bd"],
+        ];
+        let dec_pk = |x: &str, y: &str| {
+            let mut buf = [0x04u8; 97];
+            buf[1..49].copy_from_slice(&hex::decode(x).unwrap());
+            buf[49..].copy_from_slice(&hex::decode(y).unwrap());
+            PublicKey::decode(&buf).unwrap()
+        };
+        for kv in KAT.iter() {
+            let peer = dec_pk(kv[0], kv[1]);
+            let sk = PrivateKey::decode(&hex::decode(kv[2]).unwrap()).unwrap();
+            let pk = dec_pk(kv[3], kv[4]);
+            assert!(sk.to_public_key().point.equals(pk.point) != 0);
+            let z = super::ecdh(&sk, &peer).unwrap();
+            assert!(hex::encode(z) == kv[5]);
+        }
+
+        // The point-at-infinity is rejected, both when decoding and when
+        // forced into a PublicKey instance.
+        assert!(PublicKey::decode(&[0u8]).is_none());
+        let sk = PrivateKey::decode(&hex::decode(KAT[0][2]).unwrap()).unwrap();
+        let bad = PublicKey { point: Point::NEUTRAL };
+        assert!(super::ecdh(&sk, &bad).is_none());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn ecdh_ephemeral() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x33u8; 32]);
+        let (sk1, pk1) = super::generate_ephemeral(&mut rng);
+        let (sk2, pk2) = super::generate_ephemeral(&mut rng);
+        assert!(sk1.to_public_key().point.equals(pk1.point) != 0);
+        assert!(pk1.point.equals(pk2.point) == 0);
+        let z1 = super::ecdh(&sk1, &pk2).unwrap();
+        let z2 = super::ecdh(&sk2, &pk1).unwrap();
+        assert!(z1 == z2);
+    }
+
+    #[test]
+    fn der_signatures() {
+        // Signatures generated with OpenSSL (deterministic, RFC 6979):
+        // high bit set in r only, in s only, and a short r.
+        let sk = PrivateKey::decode(&hex::decode(
+            "0000000000000000000000000000001234567890abcdef00000000000000000000000000000000000000000000003039")
+            .unwrap()).unwrap();
+        let pk = PublicKey::decode(&hex::decode(
+            "02447aaee6b349fcacd60a4ed6749ef13dfe612fa62f12791c7d3eab39e65bd7058b486c947f7189a0751d697c2afe2082")
+            .unwrap()).unwrap();
+        assert!(sk.to_public_key().encode_compressed() == pk.encode_compressed());
+        const KAT: &[(&[u8], &str)] = &[
+            (b"msg2", "3065023100a79321dda57617ca4dfb7791e169bae67812f63040afd22ab20a2ee328598f42850fdba882d1e4d9896bb987a874be0602300ae00aa237d92d97812f669d450f6a2abb957f5a593fa5f2af34cbe0e5ce67b89fce15f9dda981cdefd7c9b1b0b6baa1"),
+            (b"msg6", "306502304023af805fd7d45c215fc592d4090db2de40cca992459d6b2167498285ddb4d1f462666e450792708528fc9c648e333a023100cf9b3e5ea66a2aa5f37044ac691cf8a555fdfd986f0718e70f08dea4dd62ed3a27c7b1e05bf78e69ff5364e6ec151026"),
+            (b"msg265", "3064023000dc77a421b3fe57a865e6596382adb6b1339015547c3f00d2d67e8d9765a8460bf23267be49271a1dedc402cc84137602300d82bcad3b7a7a052dd488ff08f5b29eb0b5644a45a7952b9c3eb31b10af9e00d10627b96555cf890a26e509e7e1c19b"),
+        ];
+        for (msg, der) in KAT.iter() {
+            let der = hex::decode(der).unwrap();
+            let hv: [u8; 48] = Sha384::digest(msg).into();
+            let sig = sk.sign_hash(&hv, &[]);
+            let mut buf = [0u8; 104];
+            let n = signature_to_der(&sig, &mut buf);
+            assert!(buf[..n] == der[..]);
+            let sig2 = signature_from_der(&der, false).unwrap();
+            assert!(sig2 == sig);
+            assert!(pk.verify_hash(&sig2, &hv));
+        }
+    }
+
+    #[test]
+    fn public_key_fmt() {
+        let pk = PrivateKey::from_seed(b"fmt").to_public_key();
+        let s = format!("{}", pk);
+        assert!(s.len() == 98);
+        assert!(format!("{:x}", pk) == s);
+        let pk2: PublicKey = s.parse().unwrap();
+        assert!(pk2.point.equals(pk.point) == 0xFFFFFFFF);
+        let s2 = hex::encode(pk.encode_uncompressed());
+        let pk3: PublicKey = s2.parse().unwrap();
+        assert!(pk3.point.equals(pk.point) == 0xFFFFFFFF);
+        assert!("00".parse::<PublicKey>().err() == Some(ParseError::BadLength));
+    }
+}
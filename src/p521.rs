@@ -0,0 +1,3286 @@
+//! NIST P-521 curve implementation.
+//!
+//! This module implements generic group operations on the NIST P-521
+//! elliptic curve, a short Weierstraß curve with equation `y^2 = x^3 -
+//! 3*x + b` for a given constant `b`. This curve is standardized in
+//! [FIPS 186-4] as well as in other standards such as SEC 2 or ANSI
+//! X9:62. It is also known under the name "secp521r1".
+//!
+//! The API follows that of the `p256` module. The curve has prime order.
+//! "Scalars" are integers modulo that prime order, and are implemented
+//! by the `Scalar` type, which supports the usual arithmetic operators
+//! (`+`, `-`, `*`, `/`, and the compound assignments `+=`, `-=`, `*=`
+//! and `/=`). The base field is implemented by `GFp521` (in the `field`
+//! module). The field modulus is the Mersenne prime 2^521 - 1; on 64-bit
+//! systems, `GFp521` uses a dedicated implementation with 58-bit limbs
+//! and a simple folding reduction, while scalars use the generic finite
+//! field code of the backend.
+//!
+//! A point on the curve is represented by the `Point` structure, which
+//! supports the same operators and functions as its P-256 counterpart.
+//! All point operations are implemented with fully constant-time code
+//! (except functions whose name includes "vartime") and are complete.
+//!
+//! Scalars can be encoded over 66 bytes, using unsigned **little-endian**
+//! convention, and decoded back. Points can be encoded in compressed (67
+//! bytes) or uncompressed (133 bytes) formats, which use big-endian, as
+//! specified by SEC 1. The point-at-infinity is encoded as a single byte
+//! of value 0x00; `Point::decode_sec1()` additionally accepts the
+//! "hybrid" format.
+//!
+//! The `PrivateKey` structure represents a private key for the ECDSA
+//! signature algorithm and ECDH; it is encoded over exactly 66 bytes
+//! (unsigned big-endian, as per SEC 1). The `PublicKey` structure wraps
+//! around a non-neutral `Point`.
+//!
+//! ECDSA signatures are generated with `PrivateKey::sign_hash()`, and
+//! verified with `PublicKey::verify_hash()`. The signature process is
+//! deterministic, using the SHA-512 function, following the description
+//! in [RFC 6979]. The caller provides the pre-hashed message (normally,
+//! this hashing uses SHA-512, but the functions accept hash values of any
+//! length: as per FIPS 186-4, a hash value longer than 521 bits is
+//! truncated to its leftmost 521 bits, while shorter values, including
+//! SHA-512 outputs, are used as is). Signatures use the raw format: the
+//! `r` and `s` integers are encoded in unsigned big-endian over 66 bytes
+//! each, and concatenated (132 bytes in total). `signature_to_der()` and
+//! `signature_from_der()` convert signatures to and from the ASN.1 DER
+//! format.
+//!
+//! The `ecdh()` function performs an ECDH key exchange, returning the x
+//! coordinate of the shared point (as in SEC 1 and NIST SP 800-56A);
+//! `generate_ephemeral()` makes a new key pair for that usage.
+//!
+//! [FIPS 186-4]: https://csrc.nist.gov/publications/detail/fips/186/4/final
+//! [RFC 6979]: https://datatracker.ietf.org/doc/html/rfc6979
+
+// Projective/fractional coordinates traditionally use uppercase letters,
+// using lowercase only for affine coordinates.
+#![allow(non_snake_case)]
+
+use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use super::field::GFp521;
+use sha2::{Sha512, Digest};
+use super::{CryptoRng, RngCore, DecodeError, ParseError};
+use super::der::{self, DerError};
+use crate::backend::define_gfgen;
+use crate::backend::define_gfgen_tests;
+
+/// A point on the short Weierstraß curve P-521.
+#[derive(Clone, Copy, Debug)]
+pub struct Point {
+    X: GFp521,
+    Y: GFp521,
+    Z: GFp521,
+}
+
+// Scalars are integers modulo the curve order n (a 521-bit prime).
+struct ScalarParams;
+impl ScalarParams {
+
+    const MODULUS: [u64; 9] = [
+        0xBB6FB71E91386409,
+        0x3BB5C9B8899C47AE,
+        0x7FCC0148F709A5D0,
+        0x51868783BF2F966B,
+        0xFFFFFFFFFFFFFFFA,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFFFFF,
+        0x00000000000001FF,
+    ];
+}
+define_gfgen!(Scalar, ScalarParams, scalarmod, true);
+define_gfgen_tests!(Scalar, 3, tests_scalarmod);
+
+/// Reverses a 66-byte sequence (i.e. switches between big-endian and
+/// little-endian conventions).
+///
+/// Source slice MUST have length at least 66 (only the first 66 bytes
+/// are accessed).
+fn bswap66(x: &[u8]) -> [u8; 66] {
+    let mut y = [0u8; 66];
+    for i in 0..66 {
+        y[i] = x[65 - i];
+    }
+    y
+}
+
+impl Point {
+
+    // Curve equation is: y^2 = x^3 - 3*x + b  (for a given constant b)
+    // We use projective coordinates, with the same conventions and
+    // formulas as in the p256 module:
+    //   (x, y) -> (X:Y:Z) such that x = X/Z and y = Y/Z
+    //   Y is never 0 (not even for the neutral)
+    //   X = 0 and Z = 0 for the neutral
+    //   Z != 0 for all non-neutral points
+    //
+    // Point addition uses the complete formulas from Renes, Costello and
+    // Batina (https://eprint.iacr.org/2015/1060). Doublings use formulas
+    // from Bernstein and Lange (with a corrective step for the neutral);
+    // sequences of doublings temporarily switch to Jacobian coordinates.
+
+    /// The neutral element (point-at-infinity) in the curve.
+    pub const NEUTRAL: Self = Self {
+        X: GFp521::ZERO,
+        Y: GFp521::ONE,
+        Z: GFp521::ZERO,
+    };
+
+    /// The conventional base point in the curve.
+    ///
+    /// Like all non-neutral points in P-521, it generates the whole curve.
+    pub const BASE: Self = Self {
+        X: GFp521::w64be([
+            0x00000000000000C6,
+            0x858E06B70404E9CD, 0x9E3ECB662395B442,
+            0x9C648139053FB521, 0xF828AF606B4D3DBA,
+            0xA14B5E77EFE75928, 0xFE1DC127A2FFA8DE,
+            0x3348B3C1856A429B, 0xF97E7E31C2E5BD66,
+        ]),
+        Y: GFp521::w64be([
+            0x0000000000000118,
+            0x39296A789A3BC004, 0x5C8A5FB42C7D1BD9,
+            0x98F54449579B4468, 0x17AFBD17273E662C,
+            0x97EE72995EF42640, 0xC550B9013FAD0761,
+            0x353C7086A272C240, 0x88BE94769FD16650,
+        ]),
+        Z: GFp521::ONE,
+    };
+
+    /// Curve equation parameter b.
+    const B: GFp521 = GFp521::w64be([
+        0x0000000000000051,
+        0x953EB9618E1C9A1F, 0x929A21A0B68540EE,
+        0xA2DA725B99B315F3, 0xB8B489918EF109E1,
+        0x56193951EC7E937B, 0x1652C0BD3BB1BF07,
+        0x3573DF883D2C34F1, 0xEF451FD46B503F00,
+    ]);
+
+    /// Tries to decode a point.
+    ///
+    /// This function accepts the following encodings and lengths:
+    ///
+    ///  - A single byte of value 0x00: the point-at-infinity.
+    ///
+    ///  - A byte of value 0x02 or 0x03, followed by exactly 66 bytes
+    ///    (unsigned big-endian encoding of the x coordinate): compressed
+    ///    encoding of a non-neutral point.
+    ///
+    ///  - A byte of value 0x04, followed by exactly 132 bytes (unsigned
+    ///    big-endian encodings of x and y): uncompressed encoding of a
+    ///    non-neutral point.
+    ///
+    /// The "hybrid" encoding is not supported; see `decode_sec1()`.
+    ///
+    /// On success, this structure is set to the decoded point, and
+    /// 0xFFFFFFFF is returned. On failure, this structure is set to the
+    /// neutral point, and 0x00000000 is returned. A failure is reported
+    /// if the coordinates can be decoded but do not correspond to a
+    /// point on the curve.
+    ///
+    /// Constant-time behaviour: timing-based side channels may leak
+    /// which encoding type was used (neutral, compressed, uncompressed)
+    /// but not the value of the obtained point, nor whether the encoding
+    /// was for a valid point.
+    pub fn set_decode(&mut self, buf: &[u8]) -> u32 {
+        *self = Self::NEUTRAL;
+
+        if buf.len() == 1 {
+
+            // Single-byte encoding is for the point-at-infinity.
+            // Return 0xFFFFFFFF if and only if the byte has value 0x00.
+            (((buf[0] as i32) - 1) >> 8) as u32
+
+        } else if buf.len() == 67 {
+
+            // Compressed encoding.
+            // Check that the first byte is 0x02 or 0x03.
+            let mut r = (((((buf[0] & 0xFE) ^ 0x02) as i32) - 1) >> 8) as u32;
+
+            // Decode x.
+            let (x, rx) = GFp521::decode_ct(&bswap66(&buf[1..67]));
+            r &= rx;
+
+            // Compute: y = sqrt(x^3 - 3*x + b)
+            let (mut y, ry) = (x * (x.square() - GFp521::THREE) + Self::B).sqrt();
+            r &= ry;
+
+            // Negate y if the sign does not match the bit provided in the
+            // first encoding byte. There is no valid point with y = 0.
+            let yb = y.encode()[0];
+            let ws = (((yb ^ buf[0]) & 0x01) as u32).wrapping_neg();
+            y.set_cond(&-y, ws);
+
+            // Set the coordinates, adjusting them if the process failed.
+            self.X = GFp521::select(&GFp521::ZERO, &x, r);
+            self.Y = GFp521::select(&GFp521::ONE, &y, r);
+            self.Z = GFp521::select(&GFp521::ZERO, &GFp521::ONE, r);
+            r
+
+        } else if buf.len() == 133 {
+
+            // Uncompressed encoding.
+            // First byte must have value 0x04.
+            let mut r = ((((buf[0] ^ 0x04) as i32) - 1) >> 8) as u32;
+
+            // Decode x and y.
+            let (x, rx) = GFp521::decode_ct(&bswap66(&buf[1..67]));
+            let (y, ry) = GFp521::decode_ct(&bswap66(&buf[67..133]));
+            r &= rx & ry;
+
+            // Verify that the coordinates match the curve equation.
+            r &= y.square().equals(x * (x.square() - GFp521::THREE) + Self::B);
+
+            // Set the coordinates, adjusting them if the process failed.
+            self.X = GFp521::select(&GFp521::ZERO, &x, r);
+            self.Y = GFp521::select(&GFp521::ONE, &y, r);
+            self.Z = GFp521::select(&GFp521::ZERO, &GFp521::ONE, r);
+            r
+
+        } else {
+
+            // Invalid encoding length, return 0.
+            0
+
+        }
+    }
+
+    /// Tries to decode a point.
+    ///
+    /// This function accepts the same encodings as `set_decode()`. On
+    /// success, the decoded point is returned; on failure, `None` is
+    /// returned.
+    pub fn decode(buf: &[u8]) -> Option<Point> {
+        let mut P = Point::NEUTRAL;
+        if P.set_decode(buf) != 0 {
+            Some(P)
+        } else {
+            None
+        }
+    }
+
+    /// Tries to decode a point, with detailed error reporting.
+    ///
+    /// This accepts the same encodings as `decode()`. Failures are
+    /// reported as with `p256::Point::decode_verbose()` (with lengths
+    /// 1, 67 and 133 bytes). This function is meant for diagnostics; the
+    /// cause of a failure is computed with variable-time code.
+    pub fn decode_verbose(buf: &[u8]) -> Result<Point, DecodeError> {
+        if let Some(P) = Self::decode(buf) {
+            return Ok(P);
+        }
+        let hdr_ok = match buf.len() {
+            1 => false,
+            67 => (buf[0] & 0xFE) == 0x02,
+            133 => buf[0] == 0x04,
+            _ => { return Err(DecodeError::BadLength); }
+        };
+        if !hdr_ok {
+            return Err(DecodeError::BadHeader);
+        }
+        for j in (1..buf.len()).step_by(66) {
+            if GFp521::decode_ct(&bswap66(&buf[j..(j + 66)])).1 == 0 {
+                return Err(DecodeError::NonCanonicalField);
+            }
+        }
+        Err(DecodeError::NotOnCurve)
+    }
+
+    /// Decodes a point from any of the SEC 1 encodings.
+    ///
+    /// This function accepts the same encodings as `decode()`, and also
+    /// the hybrid encoding: a byte of value 0x06 or 0x07, followed by
+    /// exactly 132 bytes (unsigned big-endian encodings of x and y),
+    /// where the low bit of the first byte must match the least
+    /// significant bit of y. Failures are reported as with
+    /// `decode_verbose()`; a hybrid encoding whose first byte does not
+    /// match the parity of y is reported as `NonCanonicalEncoding`.
+    pub fn decode_sec1(buf: &[u8]) -> Result<Point, DecodeError> {
+        if buf.len() == 133 && (buf[0] & 0xFE) == 0x06 {
+            let mut tmp = [0u8; 133];
+            tmp.copy_from_slice(buf);
+            tmp[0] = 0x04;
+            let P = Self::decode_verbose(&tmp)?;
+            if ((buf[0] ^ buf[132]) & 1) != 0 {
+                return Err(DecodeError::NonCanonicalEncoding);
+            }
+            return Ok(P);
+        }
+        Self::decode_verbose(buf)
+    }
+
+    /// Encodes this point in compressed format (67 bytes).
+    ///
+    /// If the point is the neutral then `[0u8; 67]` is returned, which
+    /// is NOT the standard encoding of the neutral (standard is a single
+    /// byte of of value 0x00); for a non-neutral point, the first byte
+    /// is always equal to 0x02 or 0x03, never to 0x00.
+    pub fn encode_compressed(self) -> [u8; 67] {
+        let r = !self.isneutral();
+        let iZ = GFp521::ONE / self.Z;  // this is 0 if Z = 0
+        let x = self.X * iZ;  // 0 for the neutral
+        let y = self.Y * iZ;  // 0 for the neutral
+        let mut b = [0u8; 67];
+        b[0] = ((y.encode()[0] & 0x01) | 0x02) & (r as u8);
+        b[1..67].copy_from_slice(&bswap66(&x.encode()));
+        b
+    }
+
+    /// Encodes this point in uncompressed format (133 bytes).
+    ///
+    /// If the point is the neutral then `[0u8; 133]` is returned, which
+    /// is NOT the standard encoding of the neutral (standard is a single
+    /// byte of of value 0x00); for a non-neutral point, the first byte
+    /// is always equal to 0x04, never to 0x00.
+    pub fn encode_uncompressed(self) -> [u8; 133] {
+        let r = !self.isneutral();
+        let iZ = GFp521::ONE / self.Z;  // this is 0 if Z = 0
+        let x = self.X * iZ;  // 0 for the neutral
+        let y = self.Y * iZ;  // 0 for the neutral
+        let mut b = [0u8; 133];
+        b[0] = 0x04 & (r as u8);
+        b[ 1..67].copy_from_slice(&bswap66(&x.encode()));
+        b[67..133].copy_from_slice(&bswap66(&y.encode()));
+        b
+    }
+
+    /// Gets the affine (x, y) coordinates for this point.
+    ///
+    /// Values (x, y, r) are returned, with x and y being field elements,
+    /// and r a `u32` value that qualifies the outcome:
+    ///
+    ///  - if the point is the neutral, then x = 0, y = 0 and r = 0x00000000;
+    ///
+    ///  - otherwise, x and y are the affine coordinates, and r = 0xFFFFFFFF.
+    pub fn to_affine(self) -> (GFp521, GFp521, u32) {
+        let iZ = GFp521::ONE / self.Z;  // this is 0 if Z = 0
+        (self.X * iZ, self.Y * iZ, !self.isneutral())
+    }
+
+    /// Sets this instance from the provided affine coordinates.
+    ///
+    /// If the coordinates designate a valid curve point, then the
+    /// function returns 0xFFFFFFFF; otherwise, this instance is set to
+    /// the neutral, and the function returns 0x00000000.
+    pub fn set_affine(&mut self, x: GFp521, y: GFp521) -> u32 {
+        *self = Self::NEUTRAL;
+        let y2 = x * (x.square() - GFp521::THREE) + Self::B;
+        let r = y.square().equals(y2);
+        self.X.set_cond(&x, r);
+        self.Y.set_cond(&y, r);
+        self.Z.set_cond(&GFp521::ONE, r);
+        r
+    }
+
+    /// Creates an instance from the provided affine coordinates.
+    ///
+    /// The coordinates are verified to comply with the curve equation;
+    /// if they do not, then `None` is returned.
+    pub fn from_affine(x: GFp521, y: GFp521) -> Option<Self> {
+        let mut P = Self::NEUTRAL;
+        if P.set_affine(x, y) != 0 {
+            Some(P)
+        } else {
+            None
+        }
+    }
+
+    /// Adds point `rhs` to `self`.
+    fn set_add(&mut self, rhs: &Self) {
+        let (X1, Y1, Z1) = (&self.X, &self.Y, &self.Z);
+        let (X2, Y2, Z2) = (&rhs.X, &rhs.Y, &rhs.Z);
+
+        // Formulas from Renes-Costello-Batina 2016:
+        // https://eprint.iacr.org/2015/1060
+        // (algorithm 4, with some renaming and expression compaction)
+        let x1x2 = X1 * X2;
+        let y1y2 = Y1 * Y2;
+        let z1z2 = Z1 * Z2;
+        let C = (X1 + Y1) * (X2 + Y2) - x1x2 - y1y2;  // X1*Y2 + X2*Y1
+        let D = (Y1 + Z1) * (Y2 + Z2) - y1y2 - z1z2;  // Y1*Z2 + Y2*Z1
+        let E = (X1 + Z1) * (X2 + Z2) - x1x2 - z1z2;  // X1*Z2 + X2*Z1
+        let F = (E - Self::B * z1z2).mul3();
+        let G = y1y2 - F;
+        let H = y1y2 + F;
+        let I = z1z2.mul3();
+        let J = (Self::B * E - x1x2 - I).mul3();
+        let K = x1x2.mul3() - I;
+        let L = D * J;
+        let M = K * J;
+        let N = K * C;
+        let Y3 = H * G + M;
+        let X3 = H * C - L;
+        let Z3 = G * D + N;
+
+        self.X = X3;
+        self.Y = Y3;
+        self.Z = Z3;
+    }
+
+    /// Adds the affine point `rhs` to `self`.
+    ///
+    /// If the point to add is the neutral, then `rhs.x` and `rhs.y` can
+    /// be arbitrary, and `rz` is 0xFFFFFFFF; otherwise, `rhs.x` and `rhs.y`
+    /// are the affine coordinates of the point to add, and `rz` is
+    /// 0x00000000.
+    fn set_add_affine(&mut self, rhs: &PointAffine, rz: u32) {
+        let (X1, Y1, Z1) = (&self.X, &self.Y, &self.Z);
+        let (X2, Y2) = (&rhs.x, &rhs.y);
+
+        // Same formulas as in set_add(), but modified to account for
+        // Z2 = 1 (implicitly).
+        let x1x2 = X1 * X2;
+        let y1y2 = Y1 * Y2;
+        let C = (X1 + Y1) * (X2 + Y2) - x1x2 - y1y2;  // X1*Y2 + X2*Y1
+        let D = Y2 * Z1 + Y1;                         // Y1*Z2 + Y2*Z1
+        let E = X2 * Z1 + X1;                         // X1*Z2 + X2*Z1
+        let F = (E - Self::B * Z1).mul3();
+        let G = y1y2 - F;
+        let H = y1y2 + F;
+        let I = Z1.mul3();
+        let J = (Self::B * E - x1x2 - I).mul3();
+        let K = x1x2.mul3() - I;
+        let L = D * J;
+        let M = K * J;
+        let N = K * C;
+        let Y3 = H * G + M;
+        let X3 = H * C - L;
+        let Z3 = G * D + N;
+
+        // If rhs is the neutral, then we computed the wrong output and
+        // we must fix it, namely by discarding the computed values in
+        // that case.
+        self.X.set_cond(&X3, !rz);
+        self.Y.set_cond(&Y3, !rz);
+        self.Z.set_cond(&Z3, !rz);
+    }
+
+    /// Subtract the affine point `rhs` from `self`.
+    ///
+    /// If the point to add is the neutral, then `rhs.x` and `rhs.y` can
+    /// be arbitrary, and `rz` is 0xFFFFFFFF; otherwise, `rhs.x` and `rhs.y`
+    /// are the affine coordinates of the point to add, and `rz` is
+    /// 0x00000000.
+    fn set_sub_affine(&mut self, rhs: &PointAffine, rz: u32) {
+        self.set_add_affine(&PointAffine { x: rhs.x, y: -rhs.y }, rz);
+    }
+
+    /// Doubles this point (in place).
+    ///
+    /// This function is somewhat faster than using plain point addition.
+    pub fn set_double(&mut self) {
+        let (X, Y, Z) = (&self.X, &self.Y, &self.Z);
+
+        // We need to remember whether the source was the neutral.
+        let zn = Z.iszero();
+
+        // Formulas from Bernstein-Lange 2007:
+        // https://www.hyperelliptic.org/EFD/g1p/auto-shortw-projective-3.html#doubling-dbl-2007-bl-2
+        let s = (Y * Z).mul2();
+        let w = ((X - Z) * (X + Z)).mul3();
+        let R = Y * s;
+        let ss = s.square();
+        let RR = R.square();
+        let B = (X * R).mul2();
+        let h = w.square() - B - B;
+        let Z3 = s * ss;
+        let X3 = s * h;
+        let Y3 = w * (B - h) - RR.mul2();
+
+        // When the input is the neutral, this yields (0:0:0), which is
+        // not a valid representation; we set Y to 1 in that case.
+        self.X = X3;
+        self.Y = GFp521::select(&Y3, &GFp521::ONE, zn);
+        self.Z = Z3;
+    }
+
+    /// Doubles this point.
+    ///
+    /// This function is somewhat faster than using plain point addition.
+    #[inline(always)]
+    pub fn double(self) -> Self {
+        let mut r = self;
+        r.set_double();
+        r
+    }
+
+    /// Doubles this point n times (in place).
+    ///
+    /// When n > 1, this function is faster than calling `set_double()`
+    /// n times.
+    pub fn set_xdouble(&mut self, n: u32) {
+        if n == 0 {
+            return;
+        }
+        if n == 1 {
+            self.set_double();
+            return;
+        }
+
+        // First doubling: dbl-2007-bl-2 formulas, with output in
+        // Jacobian coordinates (cost 5M+2S).
+        let (X, Y, Z) = (&self.X, &self.Y, &self.Z);
+        let s = (Y * Z).mul2();
+        let w = ((X - Z) * (X + Z)).mul3();
+        let R = Y * s;
+        let RR = R.square();
+        let B = (X * R).mul2();
+        let mut X = w.square() - B - B;
+        let mut Y = w * (B - X) - RR.mul2();
+        let mut Z = s;
+
+        // We now are in Jacobian coordinates. We perform the remaining
+        // doublings.
+        for _ in 1..n {
+            // Using Hankerson-Menezes-Vanstone 2004 formulas (4M+4S)
+            let Z2 = Z.square();
+            let A = ((X - Z2) * (X + Z2)).mul3();
+            let B = Y.mul2();
+            Z *= B;
+            let C = B.square();
+            let D = C.square().half();
+            let E = C * X;
+            X = A.square() - E.mul2();
+            Y = (E - X) * A - D;
+        }
+
+        // Conversion back to projective; if the source was the neutral,
+        // then we have (0:0:0) and must set Y back to a non-zero value.
+        self.X = X * Z;
+        self.Y = GFp521::select(&Y, &GFp521::ONE, Z.iszero());
+        self.Z = Z * Z.square();
+    }
+
+    /// Doubles this point n times.
+    ///
+    /// When n > 1, this function is faster than calling `double()`
+    /// n times.
+    #[inline(always)]
+    pub fn xdouble(self, n: u32) -> Self {
+        let mut r = self;
+        r.set_xdouble(n);
+        r
+    }
+
+    /// Negates this point (in place).
+    #[inline(always)]
+    pub fn set_neg(&mut self) {
+        self.Y.set_neg();
+    }
+
+    /// Subtracts point `rhs` from `self`.
+    fn set_sub(&mut self, rhs: &Self) {
+        self.set_add(&-rhs);
+    }
+
+    /// Multiplies this point by a small integer.
+    ///
+    /// This operation is constant-time with regard to the source point,
+    /// but NOT with regard to the multiplier; the multiplier `n` MUST
+    /// NOT be secret.
+    pub fn set_mul_small(&mut self, n: u64) {
+        if n == 0 {
+            *self = Self::NEUTRAL;
+            return;
+        }
+        if n == 1 {
+            return;
+        }
+
+        let nlen = 64 - n.leading_zeros();
+        let T = *self;
+        let mut ndbl = 0u32;
+        for i in (0..(nlen - 1)).rev() {
+            ndbl += 1;
+            if ((n >> i) & 1) == 0 {
+                continue;
+            }
+            self.set_xdouble(ndbl);
+            ndbl = 0;
+            self.set_add(&T);
+        }
+        self.set_xdouble(ndbl);
+    }
+
+    /// Compares two points for equality.
+    ///
+    /// Returned value is 0xFFFFFFFF if the two points are equal,
+    /// 0x00000000 otherwise.
+    #[inline]
+    pub fn equals(self, rhs: Self) -> u32 {
+        // Since Y != 0 for all points, the test on Y cannot match between
+        // a neutral and a non-neutral point.
+        (self.X * rhs.Z).equals(rhs.X * self.Z)
+        & (self.Y * rhs.Z).equals(rhs.Y * self.Z)
+    }
+
+    /// Tests whether this point is the neutral (point-at-infinity).
+    ///
+    /// Returned value is 0xFFFFFFFF for the neutral, 0x00000000 otherwise.
+    #[inline(always)]
+    pub fn isneutral(self) -> u32 {
+        self.Z.iszero()
+    }
+
+    // Conditionally copies the provided point (`P`) into `self`.
+    //
+    //  - If `ctl` is 0xFFFFFFFF, then the value of `P` is copied into `self`.
+    //
+    //  - if `ctl` is 0x00000000, then the value of `self` is unchanged.
+    //
+    // Value `ctl` MUST be either 0x00000000 or 0xFFFFFFFF.
+    #[inline]
+    pub fn set_cond(&mut self, P: &Self, ctl: u32) {
+        self.X.set_cond(&P.X, ctl);
+        self.Y.set_cond(&P.Y, ctl);
+        self.Z.set_cond(&P.Z, ctl);
+    }
+
+    /// Returns a point equal to `P0` (if `ctl` = 0x00000000) or `P1` (if
+    /// `ctl` = 0xFFFFFFFF).
+    ///
+    /// Value `ctl` MUST be either 0x00000000 or 0xFFFFFFFF.
+    #[inline(always)]
+    pub fn select(P0: &Self, P1: &Self, ctl: u32) -> Self {
+        let mut P = *P0;
+        P.set_cond(P1, ctl);
+        P
+    }
+
+    /// Conditionally negates this point.
+    ///
+    /// This point is negated if `ctl` = 0xFFFFFFFF, but kept unchanged
+    /// if `ctl` = 0x00000000.
+    ///
+    /// Value `ctl` MUST be either 0x00000000 or 0xFFFFFFFF.
+    #[inline]
+    pub fn set_condneg(&mut self, ctl: u32) {
+        self.Y.set_cond(&-self.Y, ctl);
+    }
+
+    /// Recodes a scalar into 105 signed digits.
+    ///
+    /// Each digit is in -15..+16, top digit is in 0..+2.
+    fn recode_scalar(n: &Scalar) -> [i8; 105] {
+        let mut sd = [0i8; 105];
+        let bb = n.encode();
+        let mut cc: u32 = 0;       // carry from lower digits
+        let mut i: usize = 0;      // index of next source byte
+        let mut acc: u32 = 0;      // buffered bits
+        let mut acc_len: i32 = 0;  // number of buffered bits
+        for d_out in sd.iter_mut() {
+            if acc_len < 5 && i < 66 {
+                acc |= (bb[i] as u32) << acc_len;
+                acc_len += 8;
+                i += 1;
+            }
+            let d = (acc & 0x1F) + cc;
+            acc >>= 5;
+            acc_len -= 5;
+            let m = 16u32.wrapping_sub(d) >> 8;
+            *d_out = (d.wrapping_sub(m & 32)) as i8;
+            cc = m & 1;
+        }
+        sd
+    }
+
+    /// Lookups a point from a window, with sign handling (constant-time).
+    fn lookup(win: &[Self; 16], k: i8) -> Self {
+        // Split k into its sign s (0xFFFFFFFF for negative) and
+        // absolute value (f).
+        let s = ((k as i32) >> 8) as u32;
+        let f = ((k as u32) ^ s).wrapping_sub(s);
+        let mut P = Self::NEUTRAL;
+        for (i, Q) in win.iter().enumerate() {
+            // win[i] contains (i+1)*P; we want to keep it if (and only if)
+            // i+1 == f.
+            let j = (i as u32) + 1;
+            let w = !(f.wrapping_sub(j) | j.wrapping_sub(f));
+            let w = ((w as i32) >> 31) as u32;
+
+            P.X.set_cond(&Q.X, w);
+            P.Y.set_cond(&Q.Y, w);
+            P.Z.set_cond(&Q.Z, w);
+        }
+
+        // Negate the returned value if needed.
+        P.Y.set_cond(&-P.Y, s);
+
+        P
+    }
+
+    /// Multiplies this point by a scalar (in place).
+    ///
+    /// This operation is constant-time with regard to both the points
+    /// and the scalar value.
+    pub fn set_mul(&mut self, n: &Scalar) {
+        // Make a 5-bit window: win[i] contains (i+1)*P
+        let mut win = [Self::NEUTRAL; 16];
+        win[0] = *self;
+        for i in 1..8 {
+            let j = 2 * i;
+            win[j - 1] = win[i - 1].double();
+            win[j] = win[j - 1] + win[0];
+        }
+        win[15] = win[7].double();
+
+        // Recode the scalar into 105 signed digits.
+        let sd = Self::recode_scalar(n);
+
+        // Process the digits in high-to-low order.
+        *self = Self::lookup(&win, sd[104]);
+        for i in (0..104).rev() {
+            self.set_xdouble(5);
+            self.set_add(&Self::lookup(&win, sd[i]));
+        }
+    }
+
+    /// Lookups a point from a window in affine coordinates, with sign
+    /// handling (constant-time).
+    ///
+    /// The returned point is in affine coordinates, and an extra "output
+    /// is neutral" flag is also returned (since the neutral point does
+    /// not have defined affine coordinates).
+    fn lookup_affine(win: &[PointAffine; 16], k: i8) -> (PointAffine, u32) {
+        // Split k into its sign s (0xFFFFFFFF for negative) and
+        // absolute value (f).
+        let s = ((k as i32) >> 8) as u32;
+        let f = ((k as u32) ^ s).wrapping_sub(s);
+        let mut P = PointAffine { x: GFp521::ZERO, y: GFp521::ONE };
+        for (i, Q) in win.iter().enumerate() {
+            // win[i] contains (i+1)*P; we want to keep it if (and only if)
+            // i+1 == f.
+            let j = (i as u32) + 1;
+            let w = !(f.wrapping_sub(j) | j.wrapping_sub(f));
+            let w = ((w as i32) >> 31) as u32;
+
+            P.x.set_cond(&Q.x, w);
+            P.y.set_cond(&Q.y, w);
+        }
+
+        // Negate the returned value if needed.
+        P.y.set_cond(&-P.y, s);
+        let fz = (((f as i32) - 1) >> 8) as u32;
+
+        (P, fz)
+    }
+
+    /// Lookups a point from a window in affine coordinates, with sign
+    /// handling (constant-time).
+    ///
+    /// The returned point is projective coordinates (which can represent
+    /// the neutral).
+    #[inline]
+    fn lookup_affine_proj(win: &[PointAffine; 16], k: i8) -> Self {
+        let (P, rz) = Self::lookup_affine(win, k);
+        Self {
+            X: P.x,
+            Y: P.y,
+            Z: GFp521::select(&GFp521::ONE, &GFp521::ZERO, rz),
+        }
+    }
+
+    /// Lookups a point from a window in affine coordinates, with sign
+    /// handling (constant-time), and adds it to the current point.
+    #[inline]
+    fn set_lookup_affine_add(&mut self, win: &[PointAffine; 16], k: i8) {
+        let (P, rz) = Self::lookup_affine(win, k);
+        self.set_add_affine(&P, rz);
+    }
+
+    /// Sets this point by multiplying the conventional generator by the
+    /// provided scalar.
+    ///
+    /// This operation is constant-time. It is faster than using the
+    /// generic multiplication on `Self::BASE`.
+    pub fn set_mulgen(&mut self, n: &Scalar) {
+        // Recode the scalar into 105 signed digits.
+        let sd = Self::recode_scalar(n);
+
+        // We process four chunks in parallel. Each chunk is 27 digits,
+        // except the top one which is 24 digits only.
+        *self = Self::lookup_affine_proj(&PRECOMP_G, sd[26]);
+        self.set_lookup_affine_add(&PRECOMP_G135, sd[53]);
+        self.set_lookup_affine_add(&PRECOMP_G270, sd[80]);
+
+        // Process the digits in high-to-low order.
+        for i in (0..26).rev() {
+            self.set_xdouble(5);
+            self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
+            self.set_lookup_affine_add(&PRECOMP_G135, sd[i + 27]);
+            self.set_lookup_affine_add(&PRECOMP_G270, sd[i + 54]);
+            if i < 24 {
+                self.set_lookup_affine_add(&PRECOMP_G405, sd[i + 81]);
+            }
+        }
+    }
+
+    /// Creates a point by multiplying the conventional generator by the
+    /// provided scalar.
+    ///
+    /// This operation is constant-time. It is faster than using the
+    /// generic multiplication on `Self::BASE`.
+    #[inline]
+    pub fn mulgen(n: &Scalar) -> Self {
+        let mut P = Self::NEUTRAL;
+        P.set_mulgen(n);
+        P
+    }
+
+    /// 5-bit wNAF recoding of a scalar; output is a sequence of 522
+    /// digits.
+    ///
+    /// Non-zero digits have an odd value, between -15 and +15
+    /// (inclusive). (The recoding is constant-time, but use of wNAF is
+    /// inherently non-constant-time.)
+    fn recode_scalar_NAF(n: &Scalar) -> [i8; 522] {
+        // See p256::Point::recode_scalar_NAF() for details. Since a
+        // scalar fits on 521 bits, at most 522 digits are needed.
+        let mut sd = [0i8; 522];
+        let bb = n.encode();
+        let mut x = bb[0] as u32;
+        for i in 0..522 {
+            if (i & 7) == 4 {
+                x += (bb[(i + 4) >> 3] as u32) << 4;
+            }
+            let m = (x & 1).wrapping_neg();  // -1 if x is odd, 0 otherwise
+            let v = x & m & 31;              // low 5 bits if x odd, or 0
+            let c = (v & 16) << 1;           // carry (0 or 32)
+            let d = v.wrapping_sub(c);       // next digit
+            sd[i] = d as i8;
+            x = x.wrapping_sub(d) >> 1;
+        }
+        sd
+    }
+
+    /// Given scalars `u` and `v`, sets this point to `u*self + v*G`
+    /// (with `G` being the conventional generator point, aka
+    /// `Self::BASE`).
+    ///
+    /// This function can be used to support ECDSA signature
+    /// verification.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    pub fn set_mul_add_mulgen_vartime(&mut self, u: &Scalar, v: &Scalar) {
+        // Recode the scalars in 5-bit wNAF.
+        let sdu = Self::recode_scalar_NAF(u);
+        let sdv = Self::recode_scalar_NAF(v);
+
+        // Compute the window for the current point:
+        //   win[i] = (2*i+1)*self    (i = 0 to 7)
+        let mut win = [Self::NEUTRAL; 8];
+        let Q = self.double();
+        win[0] = *self;
+        for i in 1..8 {
+            win[i] = win[i - 1] + Q;
+        }
+
+        let mut zz = true;
+        let mut ndbl = 0u32;
+        for i in (0..522).rev() {
+            // We have one more doubling to perform.
+            ndbl += 1;
+
+            // Get next digits. If they are all zeros, then we can loop
+            // immediately.
+            let e1 = sdu[i];
+            let e2 = sdv[i];
+            if ((e1 as u32) | (e2 as u32)) == 0 {
+                continue;
+            }
+
+            // Apply accumulated doubles.
+            if zz {
+                *self = Self::NEUTRAL;
+                zz = false;
+            } else {
+                self.set_xdouble(ndbl);
+            }
+            ndbl = 0u32;
+
+            // Process digits.
+            if e1 != 0 {
+                if e1 > 0 {
+                    self.set_add(&win[e1 as usize >> 1]);
+                } else {
+                    self.set_sub(&win[(-e1) as usize >> 1]);
+                }
+            }
+            if e2 != 0 {
+                if e2 > 0 {
+                    self.set_add_affine(&PRECOMP_G[e2 as usize - 1], 0);
+                } else {
+                    self.set_sub_affine(&PRECOMP_G[(-e2) as usize - 1], 0);
+                }
+            }
+        }
+
+        if zz {
+            *self = Self::NEUTRAL;
+        } else {
+            if ndbl > 0 {
+                self.set_xdouble(ndbl);
+            }
+        }
+    }
+
+    /// Given scalars `u` and `v`, returns point `u*self + v*G`
+    /// (with `G` being the conventional generator point, aka
+    /// `Self::BASE`).
+    ///
+    /// This function can be used to support ECDSA signature
+    /// verification.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    #[inline(always)]
+    pub fn mul_add_mulgen_vartime(self, u: &Scalar, v: &Scalar) -> Self {
+        let mut R = self;
+        R.set_mul_add_mulgen_vartime(u, v);
+        R
+    }
+
+    /// Check whether `s*G = R + k*Q`, for the provided scalars `s`
+    /// and `k`, provided points `Q` (`self`) and `R`, and conventional
+    /// generator `G`.
+    ///
+    /// Returned value is true on match, false otherwise. This function
+    /// is meant to support Schnorr signature verification.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    pub fn verify_helper_vartime(self,
+        R: &Point, s: &Scalar, k: &Scalar) -> bool
+    {
+        let T = self.mul_add_mulgen_vartime(&(-k), s);
+        T.equals(*R) != 0
+    }
+}
+
+impl Add<Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: Point) -> Point {
+        let mut r = self;
+        r.set_add(&other);
+        r
+    }
+}
+
+impl Add<&Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: &Point) -> Point {
+        let mut r = self;
+        r.set_add(other);
+        r
+    }
+}
+
+impl Add<Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: Point) -> Point {
+        let mut r = *self;
+        r.set_add(&other);
+        r
+    }
+}
+
+impl Add<&Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn add(self, other: &Point) -> Point {
+        let mut r = *self;
+        r.set_add(other);
+        r
+    }
+}
+
+impl AddAssign<Point> for Point {
+    #[inline(always)]
+    fn add_assign(&mut self, other: Point) {
+        self.set_add(&other);
+    }
+}
+
+impl AddAssign<&Point> for Point {
+    #[inline(always)]
+    fn add_assign(&mut self, other: &Point) {
+        self.set_add(other);
+    }
+}
+
+impl Mul<Scalar> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Scalar) -> Point {
+        let mut r = self;
+        r.set_mul(&other);
+        r
+    }
+}
+
+impl Mul<&Scalar> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Scalar) -> Point {
+        let mut r = self;
+        r.set_mul(other);
+        r
+    }
+}
+
+impl Mul<Scalar> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Scalar) -> Point {
+        let mut r = *self;
+        r.set_mul(&other);
+        r
+    }
+}
+
+impl Mul<&Scalar> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Scalar) -> Point {
+        let mut r = *self;
+        r.set_mul(other);
+        r
+    }
+}
+
+impl MulAssign<Scalar> for Point {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: Scalar) {
+        self.set_mul(&other);
+    }
+}
+
+impl MulAssign<&Scalar> for Point {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: &Scalar) {
+        self.set_mul(other);
+    }
+}
+
+impl Mul<Point> for Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Point) -> Point {
+        let mut r = other;
+        r.set_mul(&self);
+        r
+    }
+}
+
+impl Mul<&Point> for Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Point) -> Point {
+        let mut r = *other;
+        r.set_mul(&self);
+        r
+    }
+}
+
+impl Mul<Point> for &Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Point) -> Point {
+        let mut r = other;
+        r.set_mul(self);
+        r
+    }
+}
+
+impl Mul<&Point> for &Scalar {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Point) -> Point {
+        let mut r = *other;
+        r.set_mul(self);
+        r
+    }
+}
+
+impl Mul<u64> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: u64) -> Point {
+        let mut r = self;
+        r.set_mul_small(other);
+        r
+    }
+}
+
+impl Mul<u64> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: u64) -> Point {
+        let mut r = *self;
+        r.set_mul_small(other);
+        r
+    }
+}
+
+impl MulAssign<u64> for Point {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: u64) {
+        self.set_mul_small(other);
+    }
+}
+
+impl Mul<Point> for u64 {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: Point) -> Point {
+        let mut r = other;
+        r.set_mul_small(self);
+        r
+    }
+}
+
+impl Mul<&Point> for u64 {
+    type Output = Point;
+
+    #[inline(always)]
+    fn mul(self, other: &Point) -> Point {
+        let mut r = *other;
+        r.set_mul_small(self);
+        r
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn neg(self) -> Point {
+        let mut r = self;
+        r.set_neg();
+        r
+    }
+}
+
+impl Neg for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn neg(self) -> Point {
+        let mut r = *self;
+        r.set_neg();
+        r
+    }
+}
+
+impl Sub<Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: Point) -> Point {
+        let mut r = self;
+        r.set_sub(&other);
+        r
+    }
+}
+
+impl Sub<&Point> for Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: &Point) -> Point {
+        let mut r = self;
+        r.set_sub(other);
+        r
+    }
+}
+
+impl Sub<Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: Point) -> Point {
+        let mut r = *self;
+        r.set_sub(&other);
+        r
+    }
+}
+
+impl Sub<&Point> for &Point {
+    type Output = Point;
+
+    #[inline(always)]
+    fn sub(self, other: &Point) -> Point {
+        let mut r = *self;
+        r.set_sub(other);
+        r
+    }
+}
+
+impl SubAssign<Point> for Point {
+    #[inline(always)]
+    fn sub_assign(&mut self, other: Point) {
+        self.set_sub(&other);
+    }
+}
+
+impl SubAssign<&Point> for Point {
+    #[inline(always)]
+    fn sub_assign(&mut self, other: &Point) {
+        self.set_sub(other);
+    }
+}
+
+// ========================================================================
+
+/// A P-521 private key simply wraps around a scalar.
+#[derive(Clone, Copy)]
+pub struct PrivateKey {
+    x: Scalar,   // secret scalar
+}
+
+/// A P-521 public key simply wraps around a curve point.
+#[derive(Clone, Copy, Debug)]
+pub struct PublicKey {
+    pub point: Point,
+}
+
+impl PrivateKey {
+
+    /// Generates a new private key from a cryptographically secure RNG.
+    pub fn generate<T: CryptoRng + RngCore>(rng: &mut T) -> Self {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        Self::from_seed(&seed)
+    }
+
+    /// Instantiates a private key by decoding the provided 66-byte
+    /// array.
+    ///
+    /// The 66 bytes contain the unsigned **big-endian** encoding of the
+    /// secret scalar (as per SEC1 and RFC 5915). The decoding fails if
+    /// the source slice does not have length exactly 66 bytes, or if the
+    /// scalar value is zero or not lower than the curve order.
+    ///
+    /// Decoding is constant-time; side-channels may leak whether the
+    /// value was valid or not, but not the value itself.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        if buf.len() != 66 {
+            return None;
+        }
+        let (x, r) = Scalar::decode_ct(&bswap66(buf));
+        if (r & !x.iszero()) != 0  {
+            Some(Self { x })
+        } else {
+            None
+        }
+    }
+
+    /// Encodes this private key into exactly 66 bytes.
+    ///
+    /// Encoding uses the unsigned big-endian convention, as per SEC1 and
+    /// RFC 5915.
+    pub fn encode(self) -> [u8; 66] {
+        bswap66(&self.x.encode())
+    }
+
+    /// Instantiates a private key from a random seed.
+    ///
+    /// The seed MUST have been generated from a cryptographically secure
+    /// random source that ensured an entropy of at least 128 bits. The
+    /// transform from the seed to the private key is not described by
+    /// any standard; therefore, for key storage, the private key itself
+    /// should be stored, not the seed.
+    ///
+    /// This process guarantees that the output key is valid (i.e. it is
+    /// in the proper range, and it is non-zero).
+    pub fn from_seed(seed: &[u8]) -> Self {
+        // We use SHA-512 over the input seed to get two pseudo-random
+        // 512-bit values, which are concatenated into a 1024-bit value,
+        // then reduced modulo the curve order (a single SHA-512 output
+        // would be shorter than the order). A custom prefix ("crrl P-521"
+        // in ASCII) and a block counter are used to avoid collisions.
+        let mut tmp = [0u8; 128];
+        let mut sh = Sha512::new();
+        for (i, chunk) in tmp.chunks_exact_mut(64).enumerate() {
+            sh.update([ 0x63, 0x72, 0x72, 0x6c, 0x20,
+                         0x50, 0x2d, 0x35, 0x32, 0x31, i as u8 ]);
+            sh.update(seed);
+            chunk.copy_from_slice(&sh.finalize_reset()[..]);
+        }
+        let mut x = Scalar::decode_reduce(&tmp);
+
+        // We make sure we do not get zero by replacing the value with 1
+        // in that case. The probability that such a thing happens is
+        // negligible.
+        x.set_cond(&Scalar::ONE, x.iszero());
+        Self { x }
+    }
+
+    /// Gets the public key corresponding to that private key.
+    pub fn to_public_key(self) -> PublicKey {
+        PublicKey { point: Point::mulgen(&self.x) }
+    }
+
+    /// Signs a hash value with ECDSA.
+    ///
+    /// The hash value may have an arbitrary length, but in general
+    /// should be a SHA-512 output. The provided hash value (`hv`) MUST
+    /// be a real hash value, not a raw unhashed message (in particular,
+    /// if `hv` is longer than 521 bits, it is internally truncated to
+    /// its leftmost 521 bits).
+    ///
+    /// The signature (r, s) is returned as the concatenation of r and s,
+    /// each encoded over 66 bytes (unsigned big-endian notation).
+    ///
+    /// Additional randomness can be provided as the `extra_rand` slice.
+    /// It is not necessary for security that the extra randomness is
+    /// cryptographically secure. If `extra_rand` has length 0, then the
+    /// signature generation process is deterministic (but still safe!),
+    /// and follows RFC 6979 with HMAC/SHA-512.
+    pub fn sign_hash(self, hv: &[u8], extra_rand: &[u8]) -> [u8; 132] {
+
+        // Feed a SHA-512 context with the starter block for HMAC/SHA-512,
+        // using a 64-byte key.
+        fn hmac_start(sh: &mut Sha512, key: &[u8; 64]) {
+            let mut tmp = [0x36u8; 128];
+            for i in 0..64 {
+                tmp[i] ^= key[i];
+            }
+            sh.update(tmp);
+        }
+
+        // Finalize a HMAC/SHA-512 computation; the 64-byte key is provided
+        // again. The SHA-512 context is automatically reinitialized.
+        fn hmac_end(sh: &mut Sha512, key: &[u8; 64]) -> [u8; 64] {
+            let v = sh.finalize_reset();
+            let mut tmp = [0x5Cu8; 128];
+            for i in 0..64 {
+                tmp[i] ^= key[i];
+            }
+            sh.update(tmp);
+            sh.update(v);
+            sh.finalize_reset().into()
+        }
+
+        // h = bits2int(hv) mod n, and hb = bits2octets(hv).
+        let h = hash_to_scalar(hv);
+        let hb = bswap66(&h.encode());
+
+        // Get the byte representation of the private key itself.
+        let xb = bswap66(&self.x.encode());
+
+        // Generate a pseudorandom k as per RFC 6979, section 3.2.
+        let mut sh = Sha512::new();
+        let V = [0x01u8; 64];
+        let K = [0x00u8; 64];
+
+        // 3.2.d
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        sh.update([0x00u8]);
+        sh.update(xb);
+        sh.update(hb);
+        if !extra_rand.is_empty() {
+            sh.update(extra_rand);
+        }
+        let K = hmac_end(&mut sh, &K);
+
+        // 3.2.e
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        let V = hmac_end(&mut sh, &K);
+
+        // 3.2.f
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        sh.update([0x01u8]);
+        sh.update(xb);
+        sh.update(hb);
+        if !extra_rand.is_empty() {
+            sh.update(extra_rand);
+        }
+        let mut K = hmac_end(&mut sh, &K);
+
+        // 3.2.g
+        hmac_start(&mut sh, &K);
+        sh.update(V);
+        let mut V = hmac_end(&mut sh, &K);
+
+        // 3.2.h
+        // We loop in case we get a zero for k or for s (either case is
+        // so improbable that it won't happen in practice).
+        let mut T = [0u8; 128];
+        loop {
+            // Get k. SHA-512 outputs 512 bits, and the curve order has
+            // size 521 bits, so we need two HMAC calls; k is then the
+            // leftmost 521 bits of the 1024-bit concatenated output. If
+            // that value is not lower than n, then we must try again.
+            for chunk in T.chunks_exact_mut(64) {
+                hmac_start(&mut sh, &K);
+                sh.update(V);
+                V[..].copy_from_slice(&hmac_end(&mut sh, &K));
+                chunk.copy_from_slice(&V);
+            }
+            let (k, cc) = Scalar::decode_ct(&bswap66(&leftmost521(&T)));
+            if cc != 0 && k.iszero() == 0 {
+                // We got k, compute the signature.
+
+                // R = k*G; then encode x(R), and decode-reduce as a scalar
+                let R = Point::mulgen(&k);
+                let xR_le = bswap66(&R.encode_compressed()[1..67]);
+                let r = Scalar::decode_reduce(&xR_le);
+
+                // Compute s.
+                let s = (h + self.x * r) / k;
+
+                // If s and r are both non-zero, then we have our signature.
+                if (r.iszero() | s.iszero()) == 0 {
+                    let mut sig = [0u8; 132];
+                    sig[..66].copy_from_slice(&bswap66(&r.encode()));
+                    sig[66..].copy_from_slice(&bswap66(&s.encode()));
+                    return sig;
+                }
+            }
+
+            // Bad k, try again (very improbable).
+            hmac_start(&mut sh, &K);
+            sh.update(V);
+            sh.update([0x00u8]);
+            let nK = hmac_end(&mut sh, &K);
+            K[..].copy_from_slice(&nK);
+            hmac_start(&mut sh, &K);
+            sh.update(V);
+            V[..].copy_from_slice(&hmac_end(&mut sh, &K));
+        }
+    }
+}
+
+// Decodes the (r, s) pair of a signature (raw format) as scalars.
+// The two halves are truncated/padded to 66 bytes (verifying that the
+// removed bytes are all zeros), then decoded as scalars. Zeros and
+// out-of-range values are rejected.
+fn decode_rs(sig: &[u8]) -> Option<(Scalar, Scalar)> {
+    let sig_len = sig.len();
+    if (sig_len & 1) != 0 {
+        return None;
+    }
+    let rlen = sig_len >> 1;
+    let mut rb = [0u8; 66];
+    let mut sb = [0u8; 66];
+    if rlen > 66 {
+        for i in 0..(rlen - 66) {
+            if sig[i] != 0 || sig[rlen + i] != 0 {
+                return None;
+            }
+        }
+        rb[..].copy_from_slice(&sig[(rlen - 66)..rlen]);
+        sb[..].copy_from_slice(&sig[(sig_len - 66)..sig_len]);
+    } else {
+        rb[(66 - rlen)..].copy_from_slice(&sig[..rlen]);
+        sb[(66 - rlen)..].copy_from_slice(&sig[rlen..]);
+    }
+    let (r, cr) = Scalar::decode_ct(&bswap66(&rb));
+    if cr == 0 || r.iszero() != 0 {
+        return None;
+    }
+    let (s, cs) = Scalar::decode_ct(&bswap66(&sb));
+    if cs == 0 || s.iszero() != 0 {
+        return None;
+    }
+    Some((r, s))
+}
+
+// Gets the leftmost 521 bits of a big-endian value (of length at least
+// 66 bytes), returned as an integer in unsigned big-endian convention
+// (over 66 bytes). Only the first 66 bytes of the source are accessed.
+fn leftmost521(x: &[u8]) -> [u8; 66] {
+    let mut y = [0u8; 66];
+    y[0] = x[0] >> 7;
+    for i in 1..66 {
+        y[i] = (x[i - 1] << 1) | (x[i] >> 7);
+    }
+    y
+}
+
+// Converts a hash value into an integer modulo n (bits2int() from RFC
+// 6979, followed by a modular reduction): if hv is longer than 521 bits
+// (i.e. hv.len() >= 66), only its leftmost 521 bits are kept; otherwise,
+// hv is used as is, which is in particular the case of SHA-512 outputs.
+// The value is interpreted with big-endian convention, then reduced
+// modulo n.
+fn hash_to_scalar(hv: &[u8]) -> Scalar {
+    let mut tmp = [0u8; 66];
+    if hv.len() >= 66 {
+        tmp = leftmost521(hv);
+    } else {
+        tmp[66 - hv.len() .. 66].copy_from_slice(hv);
+    }
+    Scalar::decode_reduce(&bswap66(&tmp))
+}
+
+/// Encodes a signature (in the raw 132-byte format produced by
+/// `PrivateKey::sign_hash()`) into ASN.1 DER. The output buffer must
+/// have length at least 141 bytes; the number of written bytes is
+/// returned.
+pub fn signature_to_der(sig: &[u8; 132], out: &mut [u8]) -> usize {
+    der::encode_ecdsa_signature(sig, out)
+}
+
+/// Decodes an ASN.1 DER signature into the raw 132-byte format (as
+/// expected by `PublicKey::verify_hash()`). Decoding is strict, unless
+/// `lenient` is `true`, in which case non-minimal length and integer
+/// encodings are also accepted. The range of `r` and `s` is not
+/// checked here, only at verification time.
+pub fn signature_from_der(src: &[u8], lenient: bool)
+    -> Result<[u8; 132], DerError>
+{
+    let mut sig = [0u8; 132];
+    der::decode_ecdsa_signature(src, &mut sig, lenient)?;
+    Ok(sig)
+}
+
+/// Performs an ECDH key exchange with the provided private key and
+/// peer public key.
+///
+/// The output is the x coordinate of the shared point, encoded over
+/// exactly 66 bytes (unsigned big-endian), as in SEC 1 and NIST SP
+/// 800-56A. `None` is returned if the peer key is the point-at-infinity
+/// or if the shared point is the point-at-infinity; the latter case
+/// cannot happen with a valid peer key.
+///
+/// The computation is constant-time with regard to the private key.
+/// The output is the raw shared secret; it should normally be used as
+/// input to a key derivation function, not directly as a key.
+pub fn ecdh(sk: &PrivateKey, peer: &PublicKey) -> Option<[u8; 66]> {
+    if peer.point.isneutral() != 0 {
+        return None;
+    }
+    let S = peer.point * sk.x;
+    if S.isneutral() != 0 {
+        return None;
+    }
+    let mut d = [0u8; 66];
+    d.copy_from_slice(&S.encode_compressed()[1..]);
+    Some(d)
+}
+
+/// Generates a new ephemeral key pair for ECDH, using the provided
+/// cryptographically secure RNG.
+pub fn generate_ephemeral<T: CryptoRng + RngCore>(rng: &mut T)
+    -> (PrivateKey, PublicKey)
+{
+    let sk = PrivateKey::generate(rng);
+    let pk = sk.to_public_key();
+    (sk, pk)
+}
+
+impl PublicKey {
+
+    /// Decodes a public key from bytes.
+    ///
+    /// This function accepts both compressed (67 bytes) and uncompressed
+    /// (133 bytes) formats. The point is always verified to be a valid
+    /// curve point. Note that the neutral point (the
+    /// "point-at-infinity") is explicitly rejected.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        let point = Point::decode(buf)?;
+        if point.isneutral() != 0 {
+            return None;
+        }
+        Some(Self { point })
+    }
+
+    /// Encodes this public key into the compressed format (67 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x02 or 0x03.
+    pub fn encode_compressed(self) -> [u8; 67] {
+        self.point.encode_compressed()
+    }
+
+    /// Encodes this public key into the uncompressed format (133 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x04.
+    pub fn encode_uncompressed(self) -> [u8; 133] {
+        self.point.encode_uncompressed()
+    }
+
+    /// Verifies a signature on a given hashed message.
+    ///
+    /// The signature (`sig`) MUST have an even length; the first half of
+    /// the signature is interpreted as the "r" integer, while the second
+    /// half is "s" (both use unsigned big-endian convention).
+    /// Out-of-range values are rejected. The hashed message is provided
+    /// as `hv`; it is nominally the output of a suitable hash function
+    /// (often SHA-512) computed over the actual message.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_hash(self, sig: &[u8], hv: &[u8]) -> bool {
+        // Recover r and s as scalars.
+        let (r, s) = match decode_rs(sig) {
+            Some(rs) => rs,
+            None => return false,
+        };
+
+        // Convert the input hash value into an integer modulo n.
+        let h = hash_to_scalar(hv);
+
+        // Verification algorithm.
+        let w = Scalar::ONE / s;
+        let R = self.point.mul_add_mulgen_vartime(&(r * w), &(h * w));
+        let xR_le = bswap66(&R.encode_compressed()[1..67]);
+        let rr = Scalar::decode_reduce(&xR_le);
+
+        // Signature is valid if the rebuilt r value (in rr) matches
+        // the one that was received.
+        r.equals(rr) != 0
+    }
+}
+
+// The private key is not displayable, and its debug output does not
+// include the secret scalar.
+impl core::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PrivateKey").finish_non_exhaustive()
+    }
+}
+
+// Public keys are displayed as the hexadecimal string of their
+// compressed encoding (67 bytes). Parsing accepts both the compressed
+// (134 hexadecimal digits) and uncompressed (266 digits) formats, with
+// the same checks as `decode()`.
+
+impl core::fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::fmt::LowerHex for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::str::FromStr for PublicKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; 133];
+        let n = if s.len() == 266 { 133 } else { 67 };
+        crate::hex::parse_fixed(s, &mut buf[..n])?;
+        let point = Point::decode_verbose(&buf[..n])
+            .map_err(ParseError::BadPoint)?;
+        if point.isneutral() != 0 {
+            return Err(ParseError::NeutralPoint);
+        }
+        Ok(Self { point })
+    }
+}
+
+// ========================================================================
+
+// We hardcode known multiples of the points G, (2^135)*G, (2^270)*G
+// and (2^405)*G, with G being the conventional base point. These are
+// used to speed mulgen() operations up. The points are stored in affine
+// coordinates, i.e. their Z coordinate is implicitly equal to 1.
+
+/// A curve point (non-infinity) in affine coordinates.
+#[derive(Clone, Copy, Debug)]
+struct PointAffine {
+    x: GFp521,
+    y: GFp521,
+}
+
+// Points i*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G: [PointAffine; 16] = [
+    // G * 1
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000C6,
+            0x858E06B70404E9CD, 0x9E3ECB662395B442,
+            0x9C648139053FB521, 0xF828AF606B4D3DBA,
+            0xA14B5E77EFE75928, 0xFE1DC127A2FFA8DE,
+            0x3348B3C1856A429B, 0xF97E7E31C2E5BD66,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000118,
+            0x39296A789A3BC004, 0x5C8A5FB42C7D1BD9,
+            0x98F54449579B4468, 0x17AFBD17273E662C,
+            0x97EE72995EF42640, 0xC550B9013FAD0761,
+            0x353C7086A272C240, 0x88BE94769FD16650,
+        ]),
+    },
+    // G * 2
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000043,
+            0x3C219024277E7E68, 0x2FCB288148C28274,
+            0x7403279B1CCC0635, 0x2C6E5505D769BE97,
+            0xB3B204DA6EF55507, 0xAA104A3A35C5AF41,
+            0xCF2FA364D60FD967, 0xF43E3933BA6D783D,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000F4,
+            0xBB8CC7F86DB26700, 0xA7F3ECEEEED3F0B5,
+            0xC6B5107C4DA97740, 0xAB21A29906C42DBB,
+            0xB3E377DE9F251F6B, 0x93937FA99A3248F4,
+            0xEAFCBE95EDC0F4F7, 0x1BE356D661F41B02,
+        ]),
+    },
+    // G * 3
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001A7,
+            0x3D352443DE29195D, 0xD91D6A64B5959479,
+            0xB52A6E5B123D9AB9, 0xE5AD7A112D7A8DD1,
+            0xAD3F164A3A483205, 0x1DA6BD16B59FE21B,
+            0xAEB490862C32EA05, 0xA5919D2EDE37AD7D,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000013E,
+            0x9B03B97DFA62DDD9, 0x979F86C6CAB814F2,
+            0xF1557FA82A9D0317, 0xD2F8AB1FA355CEEC,
+            0x2E2DD4CF8DC575B0, 0x2D5ACED1DEC3C70C,
+            0xF105C9BC93A59042, 0x5F588CA1EE86C0E5,
+        ]),
+    },
+    // G * 4
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000035,
+            0xB5DF64AE2AC204C3, 0x54B483487C9070CD,
+            0xC61C891C5FF39AFC, 0x06C5D55541D3CEAC,
+            0x8659E24AFE3D0750, 0xE8B88E9F078AF066,
+            0xA1D5025B08E5A5E2, 0xFBC87412871902F3,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000082,
+            0x096F84261279D2B6, 0x73E0178EB0B4ABB6,
+            0x5521AEF6E6E32E1B, 0x5AE63FE2F19907F2,
+            0x79F283E54BA38540, 0x5224F750A95B85EE,
+            0xBB7FAEF04699D1D9, 0xE21F47FC346E4D0D,
+        ]),
+    },
+    // G * 5
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000065,
+            0x2BF3C52927A432C7, 0x3DBC3391C04EB0BF,
+            0x7A596EFDB53F0D24, 0xCF03DAB8F177ACE4,
+            0x383C0C6D5E301423, 0x7112FEAF137E79A3,
+            0x29D7E1E6D8931738, 0xD5AB5096EC8F3078,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000015B,
+            0xE6EF1BDD6601D6EC, 0x8A2B73114A811291,
+            0x1CD8FE8E872E0051, 0xEDD817C9A0347087,
+            0xBB6897C9072CF374, 0x311540211CF5FF79,
+            0xD1F007257354F7F8, 0x173CC3E8DEB090CB,
+        ]),
+    },
+    // G * 6
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001EE,
+            0x4569D6CDB5921953, 0x2EFF34F94480D195,
+            0x623D30977FD71CF3, 0x981506ADE4AB0152,
+            0x5FBCCA16153F7394, 0xE0727A239531BE8C,
+            0x2F66E95657F380AE, 0x23731BEDF79206B9,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001DE,
+            0x0255AD0CC64F586A, 0xE2DD270546E3B111,
+            0x2AABBB73DA5A808E, 0x7240A926201A8A96,
+            0xCAB72D0E56648C9D, 0xF96C984DE274F220,
+            0x3DC7B8B55CA0DADE, 0x1EACCD7858D44F17,
+        ]),
+    },
+    // G * 7
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000056,
+            0xD5D1D99D5B7F6346, 0xEEB65FDA0B073A0C,
+            0x5F22E0E8F5483228, 0xF018D2C2F7114C5D,
+            0x8C308D0ABFC698D8, 0xC9A6DF30DCE3BBC4,
+            0x6F953F50FDC2619A, 0x01CEAD882816ECD4,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000003D,
+            0x2D1B7D9BAAA2A110, 0xD1D8317A39D68478,
+            0xB5C582D02824F0DD, 0x71DBD98A26CBDE55,
+            0x6BD0F293CDEC9E2B, 0x9523A34591CE1A5F,
+            0x9E76712A5DDEFC7B, 0x5C6B8BC90525251B,
+        ]),
+    },
+    // G * 8
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000008,
+            0x22C40FB6301F7262, 0xA8348396B010E25B,
+            0xD4E29D8A9B003E0A, 0x8B8A3B05F826298F,
+            0x5BFEA5B8579F49F0, 0x8B598C1BC8D79E1A,
+            0xB56289B5A6F40405, 0x86F9EA54AA78CE68,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000163,
+            0x31911D5542FC4820, 0x48FDAB6E78853B9A,
+            0x44F8EDE9E2C0715B, 0x5083DE610677A8F1,
+            0x89E9C0AA5911B4BF, 0xF0BA0DF065C57869,
+            0x9F3BA94009471353, 0x8AD642F11F17801C,
+        ]),
+    },
+    // G * 9
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000158,
+            0x5389E359E1E21826, 0xA2F5BF157156D488,
+            0xED34541B98874699, 0x2C4AB145B8C6B665,
+            0x7429E1396134DA35, 0xF3C556DF725A318F,
+            0x4F50BABD85CD2866, 0x1F45627967CBE207,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000002A,
+            0x2E618C9A8AEDF39F, 0x0B55557A27AE938E,
+            0x3088A654EE1CEBB6, 0xC825BA263DDB446E,
+            0x0D69E5756057AC84, 0x0FF56ECF4ABFD87D,
+            0x736C2AE928880F34, 0x3AA0EA86B9AD2A4E,
+        ]),
+    },
+    // G * 10
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000190,
+            0xEB8F22BDA61F281D, 0xFCFE7BB6721EC4CD,
+            0x901D879AC09AC7C3, 0x4A9246B11ADA8910,
+            0xA2C7C178FCC26329, 0x9DAA4DA9842093F3,
+            0x7C2E411F1A8E819A, 0x87FF09A04F2F3320,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001EB,
+            0x5D96B8491614BA9D, 0xBAEAB3B0CA2BA760,
+            0xC2EEB2144251B20B, 0xA97FD78A62EF62D2,
+            0xBF5349D44D9864BB, 0x536F6163DC57EBEF,
+            0xF3689639739FAA17, 0x2954BC98135EC759,
+        ]),
+    },
+    // G * 11
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000008A,
+            0x75841259FDEDFF54, 0x6F1A39573B4315CF,
+            0xED5DC7ED7C178495, 0x43EF2C54F2991652,
+            0xF3DBC5332663DA1B, 0xD19B1AEBE3191085,
+            0x015C024FA4C9A902, 0xECC0E02DDA0CDB9A,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000096,
+            0xFB303FCBBA212984, 0x9D0CA877054FB229,
+            0x3ADD566210BD0493, 0xED2E95D4E0B9B82B,
+            0x1BC8A90E8B42A4AB, 0x3892331914A95336,
+            0xDCAC80E3F4819B5D, 0x58874F92CE48C808,
+        ]),
+    },
+    // G * 12
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001C0,
+            0xD9DCEC93F8221C5D, 0xE4FAE9749C7FDE1E,
+            0x81874157958457B6, 0x107CF7A5967713A6,
+            0x44E90B7C3FB81B31, 0x477FEE9A60E93801,
+            0x3774C75C530928B1, 0x7BE69571BF842D8C,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000140,
+            0x48B5946A4927C0FE, 0x3CE1D103A682CA47,
+            0x63FE65AB71494DA4, 0x5E404ABF6A17C097,
+            0xD6D18843D86FCDB6, 0xCC10A6F951B9B630,
+            0x884BA72224F5AE6C, 0x79E7B1A3281B17F0,
+        ]),
+    },
+    // G * 13
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000007E,
+            0x3E98F984C396AD9C, 0xD7865D2B4924861A,
+            0x93F736CDE1B4C238, 0x4EEDD2BEAF5B8661,
+            0x32C45908E03C996A, 0x3550A5E79AB88EE9,
+            0x4BEC3B00AB38EFF8, 0x1887848D32FBCDA7,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000108,
+            0xEE58EB6D781FEDA9, 0x1A1926DAA3ED5A08,
+            0xCED50A386D5421C6, 0x9C7A67AE5C1E212A,
+            0xC1BD5D5838BC763F, 0x26DFDD351CBFBBC3,
+            0x6199EAAF9117E9F7, 0x291A01FB022A71C9,
+        ]),
+    },
+    // G * 14
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000187,
+            0x5BC7DC551B1B65A9, 0xE1B8CCFAAF84DED1,
+            0x958B401494116A2F, 0xD4FB0BABE0B31999,
+            0x74FC06C8B897222D, 0x79DF3E4B7BC744AA,
+            0x6767F6B812EFBF5D, 0x2C9E682DD3432D74,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000005C,
+            0xA4923575DACB5BD2, 0xD66290BBABB4BDFB,
+            0x8470122B8E51826A, 0x0847CE9B86D7ED62,
+            0xD07781B1B4F3584C, 0x11E89BF1D133DC0D,
+            0x5B690F53A87C84BE, 0x41669F852700D54A,
+        ]),
+    },
+    // G * 15
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000006B,
+            0x6AD89ABCB92465F0, 0x41558FC546D4300F,
+            0xB8FBCC30B40A0852, 0xD697B532DF128E11,
+            0xB91CCE27DBD00FFE, 0x7875BD1C8FC0331D,
+            0x9B8D96981E3F92BD, 0xE9AFE337BCB8DB55,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001B4,
+            0x68DA271571391D6A, 0x7CE64D2333EDBF63,
+            0xDF0496A9BAD20CBA, 0x4B62106997485ED5,
+            0x7E9062C899470A80, 0x2148E2232C96C992,
+            0x46FD90CC446ABDD9, 0x56343480A1475465,
+        ]),
+    },
+    // G * 16
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001D1,
+            0x7D10D8A89C8AD05D, 0xDA97DA26AC743B0B,
+            0x2A87F66192FD3F3D, 0xD632F8D20B188A52,
+            0x943FF18861CA00A0, 0xE5965DA7985630DF,
+            0x0DBF5C8007DCDC53, 0x3A6C508F81A8402F,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000007A,
+            0x37343C582D77001F, 0xC714B18D3D3E6972,
+            0x1335E4C3B800D50E, 0xC7CA30C94B6B82C1,
+            0xC182E1398DB547AA, 0x0B3075AC9D998852,
+            0x9E3004D28D186333, 0x52E272F89BC73ABE,
+        ]),
+    },
+];
+
+// Points i*(2^135)*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G135: [PointAffine; 16] = [
+    // (2^135)*G * 1
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000010E,
+            0x12D843E6580B2551, 0x0DE9A8F2085495F9,
+            0x0BE6C83196622944, 0x0FBA696CD6A81ED8,
+            0x93F8A34BBA0F3B82, 0x8DF7699641D84048,
+            0xEF502F8A4C9CAEE3, 0x0F73C85A8201C48B,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000018A,
+            0xD6567590AFBBEC44, 0xD402693F4E25340A,
+            0x51AC6E1FB4A19BDA, 0xDCCD32F0BAB8B58B,
+            0xEFA01A64CDDA697F, 0x4818C9D1721196C2,
+            0xE404CD9317831A13, 0x507820FBE51DE678,
+        ]),
+    },
+    // (2^135)*G * 2
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000012A,
+            0x0ED4082DAD73DF88, 0xDBA377BB41885900,
+            0x28C343062434D058, 0x5D0F7B3A94DCFC6C,
+            0x4E392A4C0706C352, 0xF3F33305EFC60F48,
+            0x9E8621EB98BBDD05, 0x714C9D4D6E4A010A,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001FE,
+            0x754F22B73A1CC818, 0xC6E2C8A0949389D1,
+            0x38C843A264DE0A06, 0x75C6667B2860701A,
+            0x41093FFE3A0E36A7, 0x1831028EC8AE469C,
+            0x1638C31454319374, 0xC0899758FCDADFEB,
+        ]),
+    },
+    // (2^135)*G * 3
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000016D,
+            0x47A0587C292E069A, 0x1A7C2CDD80FE8F9D,
+            0xA8D525106197A2B5, 0x4919757BA6066BFE,
+            0xA7BAB576F6331227, 0xDAAD94C7FD4BA95D,
+            0xD01FC88E37C09EED, 0x53F7CC1DCD9C3B89,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000160,
+            0xEAA2FD7C6C7E426D, 0x4A362D78B750367D,
+            0xAE1D930AA3099C32, 0x97FB5A235D0DA356,
+            0xC25D052115E254D7, 0x8DE8D710A373C6A3,
+            0x80E54BEC68F03073, 0x0374FC0618A5170B,
+        ]),
+    },
+    // (2^135)*G * 4
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000009F,
+            0x1995F831453CC03F, 0x632405B8A3C31198,
+            0x7E92884D50102AC6, 0xE83E4FC380AFFD1A,
+            0xC23F5AC39C5EC494, 0x44F5F5BCEA20C664,
+            0xB7707006307795F2, 0x58821C974A0C0913,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001D7,
+            0x12E8D7B35BA9DB09, 0xFEE5FE79EFB9A49B,
+            0x296FD944F9C2FA5C, 0x40A105154C5CB3BC,
+            0xBD298EB0D04A14C3, 0xE9D69357A95C778C,
+            0x794B63D991F3EFD5, 0x8019071721E50847,
+        ]),
+    },
+    // (2^135)*G * 5
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000094,
+            0x293617B76FE582A4, 0x099B286B548CE05E,
+            0x4690915C906F1B9F, 0x4089296506FA952E,
+            0x20ED57C7C492A4A3, 0x40ECA49D99C7ECCC,
+            0x796CE4619A94396F, 0xC2EB058989126FAC,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000067,
+            0x01271A1304D06B9D, 0x93ECE81C0D68EB87,
+            0x5F2FBF4346CE83B7, 0x8F6D2D2E8AC8F7A3,
+            0x59BD6CABB14C49BC, 0xCB7754A1E82A9C3D,
+            0x51EEDDCCB647A424, 0xA34B04313831CD9D,
+        ]),
+    },
+    // (2^135)*G * 6
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000016B,
+            0x88904FFA7529C9FD, 0x3964BF4A6589216A,
+            0xA51D1804962EB333, 0x36888AD0A1E9A984,
+            0xC82EF455B781BC4C, 0x27E0AA5FC9A20E92,
+            0x7D88866ED6D1E388, 0x1711039279D6593D,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000090,
+            0x0779F6AF77F5A9BE, 0x77AF93DE72024396,
+            0xE1DB4AEBE59C5EBB, 0x7534CBA2ED8B25E4,
+            0xA86CD85C3C757835, 0x51D18E8B44DAFB2E,
+            0x8B9066C009C4315C, 0x9E0ACA53E59081D4,
+        ]),
+    },
+    // (2^135)*G * 7
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001ED,
+            0x316A4A837D788DCF, 0xFFAE28447EC2A78F,
+            0xA27F52A38B084E1A, 0x623869A703DB5DD0,
+            0xB1113F8DF49AE2AD, 0x2AF00D61185031EC,
+            0x91A14033DDE59521, 0xD815B0C258BC45E5,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000AA,
+            0x6EBCFBE68959CD18, 0xC295CF2C83A6506B,
+            0xE8AA4F453C4BD41A, 0xB5715FADFEB5FD68,
+            0x4C784FCDACC9DF51, 0x5E1D6AA1CED8D6E9,
+            0xD2C6156661148B48, 0xDA151FA30AE71753,
+        ]),
+    },
+    // (2^135)*G * 8
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000014B,
+            0xD018CE08A2F751CF, 0x40C0F48E189CE8CD,
+            0x4D3737455C718337, 0x748D70127165A234,
+            0x2BD1DD818537BB62, 0x47465DB046E3E5CF,
+            0xFA7C2A74E0C3A66C, 0x0D200089E0B8B7C6,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000189,
+            0x438D3544F09A0C6C, 0xA0D4DC89DEE75E9D,
+            0x226693F7789BB143, 0x3FDEE4CF5A15B193,
+            0xBFD8A1CC532B1D53, 0x6E1EBA1EBD4031D0,
+            0xF559E050A1CD3533, 0x5513201A7AC22C09,
+        ]),
+    },
+    // (2^135)*G * 9
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000118,
+            0x500D09678BBC87B4, 0x0D28EDF14F3751BA,
+            0xC838751C0A8B3C1E, 0x79DD1FFDEC520A49,
+            0x429756900B0315FA, 0x6D0069AE28566E91,
+            0x6005C395E584A476, 0x6339D37CD0D9229F,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000005A,
+            0x3181E5A8A0B80C93, 0x8A2CF2A7F534F014,
+            0x04D69886A3169BE8, 0x3F892C301613AC11,
+            0xB73C00ADA919F8D1, 0x477AAA052EAB5B31,
+            0xE63E49DFD3416C7C, 0x7C7E207E14E4C072,
+        ]),
+    },
+    // (2^135)*G * 10
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000014C,
+            0xFCAE83AC52898292, 0x66F75278B8344ED2,
+            0x071B6EB12FBDC5AB, 0xBF26D5286355457C,
+            0x3F4F25905C5E679A, 0x9DE063DCDA7C7652,
+            0xAE4874B31138E3E6, 0xF9573F8D640E6A08,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001FC,
+            0x1DF25657E8A56798, 0x253F58CE07278B89,
+            0x6E4E5AF7B91B73BF, 0x6D4D0FBC9562B680,
+            0xC7EAFD4F089A1E10, 0xE8F88C7564985F28,
+            0x072F96E6120253A9, 0x13B69D24C652CBB5,
+        ]),
+    },
+    // (2^135)*G * 11
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000188,
+            0x19632B44D89006B4, 0x0D6B88A1BF16F3A2,
+            0xCC5EC6B62243B040, 0x98256804FE6873B0,
+            0x456CD8D972B63E74, 0x1AF9F9D700EFB6B4,
+            0x704C37ED72F83290, 0x16CE6BA9219403A6,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000117,
+            0x7C98831102AAF81C, 0xE33EB6B38A467A40,
+            0xE7FC99B4DB22B516, 0xFDB8A89202ACD7E2,
+            0xDE6E8AF2E435CC54, 0x134F8F42C7789B73,
+            0xAA2A766F9517E3BE, 0x8B9574FA6B48EFBA,
+        ]),
+    },
+    // (2^135)*G * 12
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000161,
+            0x4D4A61BE1AC0290E, 0x3B86ED771781977B,
+            0x3D1FC3F227851D69, 0x86D65EEEE2EE05AB,
+            0x59710087FC283D82, 0x7649E04403D5D78B,
+            0x0D232F2DDACE9809, 0x6DF6776BC353A269,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000C6,
+            0x08E4A467489D7DE6, 0x6E5B0CBE82136CFD,
+            0x06C039941115F60A, 0xB1C2F84C223AB61A,
+            0x547F0E86928B72C5, 0xD373BA709CD429A7,
+            0x026949097381541B, 0x2DEAC320C5400967,
+        ]),
+    },
+    // (2^135)*G * 13
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000002E,
+            0x4F1C3DB00152B926, 0xFFBDD5570CBDED62,
+            0x6009E6F01152B37C, 0xF8727BDDE8FF337E,
+            0x7E36EC91DFDA6187, 0x6DB9A7CE551CABE6,
+            0x21152A8C85CB157B, 0x0B0A8610AC5E165D,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000188,
+            0xA1C08A99132DBDBF, 0xE36787CA6985A6F8,
+            0x3029FFE7FECF53A4, 0x43B696B7CBAC9513,
+            0xFF99FB910BFA6092, 0xF680EC0030394592,
+            0x734EB64A79B60428, 0x6B32D8B606C8A9BC,
+        ]),
+    },
+    // (2^135)*G * 14
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000116,
+            0x80D5AC5EB1FF9D7D, 0xBDB10AA62F3D57A7,
+            0xB4B5767EA8867AF3, 0x7ADB74E4C33C337A,
+            0xC7D01CD0F8B7C551, 0xB60894FD6CF0C0C2,
+            0xF077AFEE002863A6, 0x06FEC2DF700CD193,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000045,
+            0x5B13488D8123FB58, 0x5C661C2E5E096C3A,
+            0xA4EDDFACCFC14923, 0x567E2BCF767E554D,
+            0xF4768566352746C5, 0x006DCEE9C15465BD,
+            0xE3CBB1AE1912BED5, 0x0E0DF41C91867318,
+        ]),
+    },
+    // (2^135)*G * 15
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001BB,
+            0x4CC16417876BB699, 0x72AFB2946A4CFC70,
+            0xB63E2B31821C239B, 0xDA3601ECCDF17866,
+            0x89F09FE4110E0044, 0x02FA0EB4A4B11B13,
+            0xF563C05B48755CEE, 0x941F1A68F391B195,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001D3,
+            0xE16AA048085F0047, 0x57957F18F1AF9EBC,
+            0x6E275E97618C2327, 0x7555793A2E11801B,
+            0x0D93FC627A75E48C, 0x4F5219550DEBAE64,
+            0x1A69D7A0A8FB98A1, 0xF02270E155C4416F,
+        ]),
+    },
+    // (2^135)*G * 16
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001D8,
+            0x348C70DA08068BB3, 0x75B7E2F1E5D9BA90,
+            0x36569B8D3352C5E8, 0x58D144A9681D54BB,
+            0x807ACA835AEE3272, 0xB7A358FAA30EE909,
+            0x4A1095A88A3AE45F, 0xAE917A54A084D91A,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000043,
+            0x2500D5A13F81C2BE, 0xB23C781375A16C9F,
+            0xA9E9C6825A002253, 0x8A053DF3C8BD817E,
+            0x0646DEA871E898BD, 0x7DCCF53DACF94FC7,
+            0x87CA2CB0457D23D0, 0xC2BCA2F5309376D1,
+        ]),
+    },
+];
+
+// Points i*(2^270)*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G270: [PointAffine; 16] = [
+    // (2^270)*G * 1
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000E6,
+            0xB4E5C2E7863E739E, 0x5CDE0727AC019CF3,
+            0x45BF6F6414134DE4, 0xDE7FD5799080B37D,
+            0xC2F0DA4B931E307B, 0xE14DD980E57AC297,
+            0x8AD717EB3AABCCA4, 0x69017ED5F1C86157,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001CA,
+            0x8E7CD41F2E0320D8, 0x194D6FB1B9EEC681,
+            0x0EA41A2B6075E71D, 0x802C6F95B6C417EB,
+            0xCEDCD4F2DE53957D, 0x9219998521CB21CC,
+            0xF92C44FB46276090, 0x094E085628E15F36,
+        ]),
+    },
+    // (2^270)*G * 2
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000DF,
+            0x95EB0D18C351721C, 0x4EF1FB91FA78398B,
+            0x3516243081719F70, 0x8FAEB0188D2E4D91,
+            0xEFE16518102C4123, 0x7A52DA3956AB4275,
+            0xA33AEF70955100C9, 0x3DFF858061E5AA9E,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001B3,
+            0x07D0BE62403CEF81, 0x23AE97B660B511C8,
+            0x00A7180D1E9A804F, 0x35050FD319BCB6AA,
+            0xADB1FC8E3B26BA83, 0x8EFD42750EF8E8FB,
+            0xE108471ABF176E9F, 0x90C4D877E5F04C88,
+        ]),
+    },
+    // (2^270)*G * 3
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001B2,
+            0x02739C50FF59E392, 0x475FC9D5318B9D6C,
+            0x425986F2F64309D0, 0x014D53FF563BCAB0,
+            0x1E0B5D0E12C6B29E, 0x31D1D9712C990B59,
+            0x0E53555266AAB703, 0x79BA87BAF1C9C2EC,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000D4,
+            0xF08A7E5A3118CE62, 0x8B2B69F3F3B99628,
+            0x31BD8B37B9945412, 0x5DEF2733713D0FEF,
+            0xC9F67BD47DE66325, 0x79B5E0399FE18430,
+            0x1C93BA4E6B08CE06, 0x8BF58DFC16F1CACC,
+        ]),
+    },
+    // (2^270)*G * 4
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000064,
+            0x29BC4B3E190FAE23, 0xEE085713E80C35CE,
+            0x527F7F90130F4497, 0x9ADC56363854B528,
+            0xEE67F380CE44AE8B, 0xBE3C0FC80AF8D3A8,
+            0xACD530B6EEB9AA6C, 0x0E13C1DDCECDF0DC,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000F0,
+            0x6C0D079E472EE36C, 0x319CBA952363AC43,
+            0x57591774FF60EC49, 0xBACC18B89BEE144F,
+            0x8D75B3F5DAA4B4CC, 0x5FD3BFCF85A94659,
+            0x192ECA92B425AF89, 0x17EA6FB5DBB3838A,
+        ]),
+    },
+    // (2^270)*G * 5
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001D7,
+            0xC9A8F84C990D041C, 0x9691C01419F19FD9,
+            0x8C00E9131FEC2333, 0xAE794DADCE9F2616,
+            0x361F7AC59FDCB2D1, 0xE3184C8AF9373A85,
+            0xF213B30153344672, 0xA7C9826425A2D6F0,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000001B,
+            0xEB2743E8CF72451F, 0xC7B11100034C1831,
+            0xBA518EF96EE0A1F5, 0xAED3A48D9648D3BF,
+            0x5907D4895D91BD7B, 0xD493115DF412660A,
+            0x5A488219706FFBC1, 0xC69CA261BAF35FA1,
+        ]),
+    },
+    // (2^270)*G * 6
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000093,
+            0x7E4AF272D76DB0EC, 0x41DF9D82358501E2,
+            0x9F67C9A8CDC748ED, 0xBDFA6FBB4A0252CC,
+            0x3F7EFB91ACE321F9, 0x8C3DB5C23562B233,
+            0x3FEA507902DBD240, 0xE42BC055FF516AB6,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000015E,
+            0xC7C35CC2DD300FA2, 0x4E9B3520E0E7A33D,
+            0x301036BB3B46FE3A, 0xAFF4894856E94948,
+            0x6E9D832D54F22898, 0x71CF089712F0759E,
+            0xB3396EC417561753, 0xA08E328408E41964,
+        ]),
+    },
+    // (2^270)*G * 7
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000075,
+            0xF0EB2FEC4E8C8353, 0xBB91438CC9D6F0D5,
+            0x98FB302487A1DAD3, 0xB881440648D5F942,
+            0x6712B49645034A1A, 0x89C07B86FA4168B2,
+            0x9E6E3A6A2B08FA12, 0x817385BC9719C87C,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000019A,
+            0xB5DBF8618B7605BA, 0x61B3663244A20332,
+            0x1AF3FCDF2E34314E, 0x1A6D849F6C4EE93B,
+            0x0C1C4081F869ED62, 0xC544E9F3E28ABFA2,
+            0xCC4E93CD3F2F29D7, 0x66999066B392D834,
+        ]),
+    },
+    // (2^270)*G * 8
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000124,
+            0x540C29E8E6658AAC, 0xD1EB58DFEC9737E7,
+            0x4CD9BD0DE64C4745, 0xE22EC70382E3FCF3,
+            0xA704B004172167C2, 0xF18179D957ED4BF8,
+            0x601AB611845655FC, 0x28CD16CE7333EEA3,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000060,
+            0x4945B9CD7656234D, 0xFD119574309553A1,
+            0x808EB84331E62CC0, 0x66014006165AF89B,
+            0x556347EF8F07EB0D, 0x9D3D69A1052B60FD,
+            0x3EE2A66AD4BE21BC, 0x4F35D47D4B18BB58,
+        ]),
+    },
+    // (2^270)*G * 9
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000019B,
+            0x67A37C660EDCD904, 0x83B12E74F3E09FE9,
+            0x034A7F53F7B614E3, 0xDB2F069847A81761,
+            0x17A59DD5EAEE3FD7, 0x2C888AA05F646486,
+            0xF8F0BFE5F37E3DFB, 0x7A775F5E811FA55B,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000011C,
+            0xF2C0B0E2A9838746, 0x40E1AFFF1819D7FD,
+            0x16DD01F3AC969E90, 0x5ACF4A21993B4601,
+            0xA314EF6E0CCE69AA, 0x89E0D18152AAA922,
+            0x2B557671D3AB1F20, 0x1838B0D828C7A5B7,
+        ]),
+    },
+    // (2^270)*G * 10
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000175,
+            0xAFE61DDFE9DCB248, 0xB147B0F7599A8CB1,
+            0xF2C9FA5300029E70, 0x2A75D55494B95169,
+            0x8B88F9A899F207E1, 0xF7A07D68BD472E7A,
+            0x1876EE86E932EB88, 0x950F5D7C220DEE3F,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000003B,
+            0xC3664D82D4A2F99A, 0x5D36BE6040D84EAC,
+            0xC1D352FF4985EA5D, 0x627CD6D930C2E913,
+            0x13ACB37ACCDE0A5F, 0xE80B859D77B665AC,
+            0x52CC765715518D32, 0xB05F8B1D84571F39,
+        ]),
+    },
+    // (2^270)*G * 11
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000B2,
+            0x5EF25C149754A050, 0x7D0BAAE4AC54D5DF,
+            0x2A7F952740568A2E, 0x3C1A986ABD0CFD78,
+            0xBD4138D6652DC251, 0x9CBC448BB9E44C3F,
+            0x40BBC1CD2EAB012F, 0xD4D7DE7D18D8BE36,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000E1,
+            0x33BE00A38BE44AEF, 0xACA20CC1DDD68D97,
+            0xA2A9692FD6F075C4, 0x44624CB730954DA1,
+            0xD1973A01A0B71701, 0x6F8FB153B4FD5FCA,
+            0x3E407D31885D4962, 0xBC57378C324BFA00,
+        ]),
+    },
+    // (2^270)*G * 12
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000E9,
+            0x7234064EC7CB313D, 0x23F73DC24CCA8CB4,
+            0xF05D43BB71E242ED, 0xD2B9B7F67668CB0E,
+            0x4D825DC6F59C2C84, 0x102192B601745208,
+            0x0E28A4109B2E3634, 0x580EC898A5D3A5D9,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000030,
+            0x5B554917E6C4A9B3, 0x9D910EB8B6A13B4F,
+            0xB69BD932C02C9434, 0xC0AA375C2E742B43,
+            0xC8C7B6932EB17033, 0x9C4D252A3E418320,
+            0xFEC87A30D9802D9B, 0xF4B4B5603A3635BC,
+        ]),
+    },
+    // (2^270)*G * 13
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000133,
+            0x9E1C87AA8ECC7AAC, 0xD7A2CF2B891B7578,
+            0x5B4FDC3C72357A0F, 0xB76E5C9E513AB7E6,
+            0x956FDDF4DAE902D1, 0x1FB5ECC1D637215E,
+            0x00A737E2AAED3D48, 0x4BF431A945F8022D,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000188,
+            0xC13D0F26D6249A21, 0xD1FD4C4435AD10F2,
+            0xEED8A93084B1A5B1, 0xBD573562001372B7,
+            0x4C978F799B333C83, 0x4EC1A4827340C12D,
+            0xF410C8880BFB6827, 0x56D325A33450FD39,
+        ]),
+    },
+    // (2^270)*G * 14
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000007A,
+            0x6DB2F472B340F9FF, 0x3812F51658109337,
+            0xA82DA5C5A37D185B, 0x73058B4821D4AD6D,
+            0xB783AEB0C3861653, 0x0150BD06BC035F8B,
+            0xC10B028F3AC482E3, 0x1BC3661A9116ECB7,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000D4,
+            0x1EED6F47A55C986F, 0xED69392512263542,
+            0x70CC15391FB37021, 0x771B7325C6B320DA,
+            0x823C408B83F80407, 0x422E255B56666BBB,
+            0x8113ED86C6B96CC7, 0x1EECB909F2FF457E,
+        ]),
+    },
+    // (2^270)*G * 15
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000051,
+            0xB7DD6F083F622004, 0x7D3FE86B71721D4C,
+            0x73047EE1B11F3D65, 0xBC827642D2A672E2,
+            0x1ACDCD4114FBA207, 0x681AFBB905446856,
+            0x54F90E47781F2702, 0xA239E7FBF9FFF942,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000000CD,
+            0x341C315CEB11F236, 0x7BD396DDB9979D6E,
+            0x29B6B1FCA9198B19, 0x57FC68BFCC08A13A,
+            0xACC423A96AD69F59, 0x812A6FE7953062D3,
+            0xE3650B3C36F6375C, 0x70B4E0D173BF30CF,
+        ]),
+    },
+    // (2^270)*G * 16
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000D1,
+            0x38470C8F418BD616, 0x9FD56374D63F192D,
+            0xE7615AB1C43B436B, 0x4CEB9902F50356E7,
+            0x7A304FDCF52D44F0, 0x448F630B4A448FFA,
+            0x163A01129CA0D9F2, 0x05BFABADB30C227F,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000187,
+            0x9C3C9C86DD5AB14E, 0x755FDF2E1D91F20C,
+            0x57642F92B580D617, 0xF71E0FCFFC076BF8,
+            0xC00748EE637DCAAB, 0xE28B6F8994EB909C,
+            0x5556CF8B2BFE7BC9, 0x4B42C1459554D529,
+        ]),
+    },
+];
+
+// Points i*(2^405)*G for i = 1 to 16, in affine coordinates.
+static PRECOMP_G405: [PointAffine; 16] = [
+    // (2^405)*G * 1
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001B8,
+            0xC8494718771D3F2C, 0x48A246AB65840CD3,
+            0xB74A7ED3C85D002B, 0x8E90308AE76FB3F0,
+            0x3AA34DE82A5D32CA, 0xB28B5F0A5791E378,
+            0x3D9C1B48BAAB1296, 0xB4C4AC143FFE4858,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000125,
+            0x806554EE19B9CFB7, 0xDAFD1EF5C5D9DD01,
+            0x2E725571EB3C5855, 0x4FCDD3E0618486CF,
+            0x4908ADC3FC60DF70, 0xFA5F5A7ABA67F27F,
+            0xDBC8B8D91D078F91, 0xB3F57D14A28DA023,
+        ]),
+    },
+    // (2^405)*G * 2
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001FA,
+            0xBCB478E24B06F588, 0x6AB9C6709BEA9584,
+            0x00BE68013D586D9A, 0x307A9A329F9F852B,
+            0x49EF4E5504211367, 0x2FBFA499D85C69FC,
+            0xCB31387972B373E7, 0xC5F56D18FDB2DD1D,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000063,
+            0xC5694A20008B52D3, 0x89BA853BE83C9968,
+            0x8BF49E56ABE307ED, 0xCE8CD219583EF0DB,
+            0x9139A8376CFEC34B, 0xAFCF8AE678CA9E44,
+            0x2AF8B9233A783993, 0xB6D095562B1E18C4,
+        ]),
+    },
+    // (2^405)*G * 3
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000166,
+            0xF72F3DE51C633B7C, 0x7ECF6A9FC49DDCA9,
+            0xC78AA1909A0CF438, 0x995B35AC67EB2AB8,
+            0xC5C6E41559B74F10, 0x99417D38D705E07C,
+            0xD01AB035EFB1AA26, 0x0D8E9A7BA994A7B1,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000066,
+            0xE0E7009AE7B55DA7, 0x95C8C6131F316813,
+            0xDDF4A21F3A1F62D6, 0x684F52019295FA16,
+            0x3012502DCA3E9433, 0x25A385CD62AD9BF5,
+            0x53E814BCD695514A, 0x4EB78FAEB96F6D73,
+        ]),
+    },
+    // (2^405)*G * 4
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000006B,
+            0x70C745FF997713F2, 0x51152A2657474591,
+            0xBA22A605DF72938A, 0x4C2EF54B7285EB6E,
+            0x1E89C2BD5C4A2EBF, 0x2B1BA75DB1619E9E,
+            0xB6E1836C07BB3D19, 0xC3974B7675276D10,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000165,
+            0xCBAFB78836D00945, 0x6B0EE7BFA78AB4A8,
+            0xC6FF25E35A31E2DA, 0xDEDAA43BD7C0FB09,
+            0x0B56220A911F4047, 0x157E790A4B2E4EDC,
+            0x2A6D5526753370F0, 0xA7AB63790835DB88,
+        ]),
+    },
+    // (2^405)*G * 5
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000166,
+            0x1593B4702F1D61A7, 0x3BC5CAA475A6C55C,
+            0x785488093A6652B5, 0x6B3A5EB3B812F8D1,
+            0xF26B87B068E353D0, 0x0E966C67E761CB63,
+            0x5370F4000104B334, 0x7E1F2EE46CDE12CD,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000088,
+            0xA6A556F6EF14B1AE, 0x5BAD4E63D0637ACF,
+            0x913367D384CFD65F, 0x729855D8525F1453,
+            0x82F270BBB0391BA5, 0xAD09EC636CFB825A,
+            0xB8B72314FB327BB9, 0xED97935A2366B021,
+        ]),
+    },
+    // (2^405)*G * 6
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000A6,
+            0x7768C4358367E668, 0x224F35E15F0D2472,
+            0x652CC382EB9C38F2, 0xDBBFC95DA650C738,
+            0x8E7AA4907AE36AAC, 0xAA0921A6B9156758,
+            0xF204223120F5B371, 0x537B5F578E6EE46F,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000060,
+            0x6E88215523F32F05, 0x34DB8325C7996505,
+            0xDAB869207BCA6F89, 0x822B00BFF598FD98,
+            0xFD91B89A3E763D1F, 0x24B2FCA0E9360710,
+            0x4DAD6270A0C5684A, 0xE0D7B3CFDAEF3C5F,
+        ]),
+    },
+    // (2^405)*G * 7
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000009,
+            0xC70F08ACB68E071F, 0xDD3E2995B29911BB,
+            0x32715F1BB6A76ADC, 0x06758C56767320BD,
+            0x61533A625F3D1BC4, 0x05CF2191AB191DCA,
+            0x07155EDB7D733D37, 0xDBAE1C8DCCD34315,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000063,
+            0x1FDFA318D2BCDBC1, 0xA9E74440463A4E6E,
+            0x26F5AAC1DCC6E4AB, 0x2DD317293B29E08B,
+            0x1AF8F4FA8D59777F, 0xE09BF50BEDB0F71D,
+            0x649555A5C29F57BB, 0xD872A1ED9356A25A,
+        ]),
+    },
+    // (2^405)*G * 8
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000014,
+            0x509E56C3F276BF73, 0x9259425F82A3C375,
+            0x5F337E380C33624B, 0x47F244E7DA3EC391,
+            0xDB10E381587F47A6, 0xF4383AD0048BDF6F,
+            0xCD94FC24BA4F85EC, 0xBCA656EB512E743F,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000151,
+            0x7C4C0B0A00188D5E, 0xEAFD758584ADE38B,
+            0x8E6DFA7C8647613E, 0xC842CEACE628E1AF,
+            0xA1E7E0B5FF3FFFB6, 0xA36BB7BDE102ADB5,
+            0xCAC386303B28185E, 0x2D65148DD94CB899,
+        ]),
+    },
+    // (2^405)*G * 9
+    PointAffine {
+        x: GFp521::w64be([
+            0x0000000000000009,
+            0x7FE78FE880E95B62, 0x46C5038033775E01,
+            0x6D1AE1B500B18820, 0xA790CD77A8EE8255,
+            0x5DC9D1268E25B38D, 0x3BDB7713AE92AC33,
+            0x512115E0EA73FCDF, 0x935871450EAD4FF9,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000019E,
+            0x5661C083C48EA064, 0x498AC825122C8F10,
+            0xB967488E15812956, 0x73D298815A2426F3,
+            0xEF9BB869C80DCBBA, 0x1E398C88E8E6528D,
+            0xDB5539F69B282ABF, 0xD1A323FFCB8B195A,
+        ]),
+    },
+    // (2^405)*G * 10
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000011C,
+            0xC14D6EBB50676C29, 0x045D08732F402749,
+            0x83860C75B56D7265, 0xAFEA039307D4218C,
+            0x3314AF1015860444, 0x8AAB065F725233EF,
+            0xCEE69AE9A340AD2E, 0xECB8FC22D872682E,
+        ]),
+        y: GFp521::w64be([
+            0x00000000000001BF,
+            0x170868F7F2EE3B38, 0xCBA539D8B69EE483,
+            0x50D800934A5FD275, 0xF06A468A58DA370D,
+            0xFF5CE29DA5C77FD2, 0xA7D21CD59574649B,
+            0x2C88F82B189755B4, 0x7018485CECC303F1,
+        ]),
+    },
+    // (2^405)*G * 11
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000008C,
+            0x6BE4F8FBF5F9A0F4, 0x65A79BB075B933B3,
+            0x82F4CE45C3C72ED2, 0x03B979A782CAD68C,
+            0x150CCCEED025B307, 0xEF89E9B1BBB7F2EC,
+            0xAC2E426A8416A053, 0x6DD1ED07D6920A2A,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000040,
+            0x9A1F9C9C0372B423, 0x70A70B565B147E31,
+            0xB6118850C32AD0DE, 0x294DA1F03249BB24,
+            0x18CB6D717184F443, 0x108EA0AA78B33765,
+            0x7B0BFD350D670F2A, 0xF8C2A30AF1CA3CCC,
+        ]),
+    },
+    // (2^405)*G * 12
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000009C,
+            0x1B92202959664DE3, 0x68B9AFC47FE9399D,
+            0xA13F661EAC22A668, 0xC4A2EAC0F2B7A273,
+            0x3F786272C6324C08, 0x6B26BBD558BB94CF,
+            0x7BF9F35165ADEC42, 0x21F9E41CF53FCAB5,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000122,
+            0x1546D7B58D8DC62B, 0xDE8A6E1935D88156,
+            0xB90FA4FC1C25E88E, 0xCA184AC9C510D6EA,
+            0xCF17BF66E7569A01, 0xF4A4E0CE0B5F43B1,
+            0x2BA8744435CBDC79, 0x17C1B9A418A2FE9D,
+        ]),
+    },
+    // (2^405)*G * 13
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000000F4,
+            0x6BA7F4461BE46FFB, 0x25E81FFDFB79A7EE,
+            0x4CF9007B43DEEFB8, 0x8E7013DBB209042D,
+            0x288213EFEEC09039, 0x83D48260388D4747,
+            0x8028CA3CC3118C0B, 0x0B985FC5DEB5DCD3,
+        ]),
+        y: GFp521::w64be([
+            0x0000000000000174,
+            0xEABAF5F90BA07C94, 0x3E4934EE099FCD3B,
+            0x287A0A497CA6FF1B, 0x69F36D8D1B7A5F7E,
+            0xC81DBEB9D41ED165, 0x1794C1C0711D4BE9,
+            0x4AE729A44CEB0726, 0x86FF04BE53B68E6C,
+        ]),
+    },
+    // (2^405)*G * 14
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001AD,
+            0xC9EF2A5A2B8AB4D0, 0xB95AF1386AE6BCF1,
+            0x35C03B9FDEA38355, 0x7B28B6AF0B27FF7E,
+            0x5A333DD0A47323ED, 0xA70A60A281B19D43,
+            0xC1BE84A34E796905, 0xBA45C8F36C94382B,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000010B,
+            0x66529FE435216302, 0x9716AD2C04D7423C,
+            0x60E6C8E242232789, 0x9723434FDB8E80DD,
+            0x22B20731515DC1B8, 0x9FD3F19F7C76568E,
+            0xE6F37E6CE3FD98A2, 0x324116B7E42A4135,
+        ]),
+    },
+    // (2^405)*G * 15
+    PointAffine {
+        x: GFp521::w64be([
+            0x00000000000001BC,
+            0x6741AD8DA8F88656, 0x7452C77D2EFF3D3A,
+            0x06B11DFFE6F949E9, 0xB380B57C7ACFF8F0,
+            0x933B144E7B5FE8D9, 0xFB39DFC49AB7CD0C,
+            0xA2F2B8325478710A, 0x4288B8614B07B1BF,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000011A,
+            0x855D5413AED94837, 0x1B9E0FD46AD09893,
+            0xE423B3304E7E79A1, 0xC6BE2BBBEAF8CDFF,
+            0x12C8BCF282FF3B82, 0x3B5685883F1CCD9F,
+            0xD2E61601F8C9CE12, 0x574CD028C42166D8,
+        ]),
+    },
+    // (2^405)*G * 16
+    PointAffine {
+        x: GFp521::w64be([
+            0x000000000000002E,
+            0x674E973C21967233, 0x63AD9B320EC90C8F,
+            0xA03D6D8F93D92BDD, 0x5019813BC0EA0962,
+            0xE135B25B774DA98D, 0x63E8DB0D853D75EB,
+            0xA821692C272AC52D, 0xE5D4DEFB5B3E313B,
+        ]),
+        y: GFp521::w64be([
+            0x000000000000017E,
+            0x7CD3986E9FA4F44B, 0x1D5FE418A94E35B2,
+            0xF73996C8FA47FCD0, 0xF7BC31E7B3964D85,
+            0x81DFAA1B3C413325, 0xB30273DB8A4CDE4E,
+            0xB9F9C0CE4F03D4BC, 0x6CFDFEA798494014,
+        ]),
+    },
+];
+
+// ========================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Point, Scalar, PrivateKey, PublicKey};
+    use super::{signature_to_der, signature_from_der, hash_to_scalar};
+    use crate::{DecodeError, ParseError};
+    use sha2::{Sha256, Sha512, Digest};
+
+    #[test]
+    fn base_arith() {
+        // For a point P (randomly generated on the curve), points i*P
+        // for i = 1 to 6, encoded (compressed).
+        const EPC: [&str; 6] = [
+            "020112274735f8ad30e3ed1f4939e4a83a76017bd727314e439db9778378b642556cc7eae4cdcefd30ab0a905614f55e54921f89c0d862e2816018be92aebf89d54611",
+            "020008e004f2d4d057e3c4ca44ee257127724ddde98216d4cad51b3bdaa5c2125426f6e13e96c0a2245d313b097d2c6e26f90963fb26269f9c49d73ecc32f32c1d4c4b",
+            "0201fe6a657190eab5606df6661f37d1574f1fd6a42d74772487cf099c1668e5aa52b788985be4e198907457075b8bd6084b0b7f34e5580e5bee1aa5358a16484f8d27",
+            "0200378eb9d475693e8cd213a352ecba157a87ad93036322e275a9f4f4b7bb3fae6817a2579a450fc957e6d60923808143386e2893032eebbd73adec1f4caab651f401",
+            "0301e9d58d3f5f031105acd82f6d68805ca0bdaa998f214d4b33cd501d38428dffc97c80fe6aaf2bb4a28fdccae7eb8357e6ecc13ed1f87a978889cdd49fe099fd975c",
+            "0300997801ab864a7fe73a44ecd0b7d173e9b1bbe4974baa6f55de4b3a8331a53ca4afd7c8233d526c17db198f333ea137a906e1f0becc0aef762963699e56972c787b",
+        ];
+
+        // Neutral encoding and decoding.
+        let P0 = Point::decode(&[0u8]).unwrap();
+        assert!(P0.isneutral() == 0xFFFFFFFF);
+        assert!(P0.encode_compressed() == [0u8; 67]);
+        assert!(P0.encode_uncompressed() == [0u8; 133]);
+
+        let mut PP = [Point::NEUTRAL; 6];
+        for i in 0..6 {
+            let buf = hex::decode(EPC[i]).unwrap();
+            let P = Point::decode(&buf).unwrap();
+            assert!(P.isneutral() == 0);
+            assert!(P.encode_compressed()[..] == buf[..]);
+            let Q = Point::decode(&P.encode_uncompressed()).unwrap();
+            assert!(P.equals(Q) == 0xFFFFFFFF);
+            assert!(P.equals(P0) == 0);
+            PP[i] = P;
+        }
+        let P1 = PP[0];
+
+        // Additions, doublings and small multiples.
+        assert!((P1 + P0).equals(P1) == 0xFFFFFFFF);
+        assert!((P0 + P1).equals(P1) == 0xFFFFFFFF);
+        assert!((P1 - P1).isneutral() == 0xFFFFFFFF);
+        assert!(P0.double().isneutral() == 0xFFFFFFFF);
+        assert!((P1 + P1).equals(PP[1]) == 0xFFFFFFFF);
+        assert!(P1.double().equals(PP[1]) == 0xFFFFFFFF);
+        assert!((PP[1] + P1).equals(PP[2]) == 0xFFFFFFFF);
+        assert!((PP[2] + PP[1]).equals(PP[4]) == 0xFFFFFFFF);
+        assert!((PP[4] - PP[3]).equals(P1) == 0xFFFFFFFF);
+        assert!(PP[2].double().equals(PP[5]) == 0xFFFFFFFF);
+        assert!(P1.xdouble(2).equals(PP[3]) == 0xFFFFFFFF);
+        assert!(P0.xdouble(3).isneutral() == 0xFFFFFFFF);
+        for i in 0..6 {
+            assert!((P1 * ((i + 1) as u64)).equals(PP[i]) == 0xFFFFFFFF);
+        }
+        assert!((P1 * 0u64).isneutral() == 0xFFFFFFFF);
+        let mut Q = P1;
+        for _ in 0..10 {
+            Q = Q.double();
+        }
+        assert!(Q.equals(P1.xdouble(10)) == 0xFFFFFFFF);
+        assert!(Q.equals(P1 * 1024u64) == 0xFFFFFFFF);
+
+        // Negation and conditional operations.
+        let mut Q = P1;
+        Q.set_condneg(0);
+        assert!(Q.equals(P1) == 0xFFFFFFFF);
+        Q.set_condneg(0xFFFFFFFF);
+        assert!(Q.equals(-P1) == 0xFFFFFFFF);
+        assert!(Q.encode_compressed()[0] == 0x03);
+        assert!(Point::select(&P1, &PP[1], 0).equals(P1) == 0xFFFFFFFF);
+        assert!(Point::select(&P1, &PP[1], 0xFFFFFFFF).equals(PP[1])
+            == 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn mulgen() {
+        // Test vector computed with an independent implementation
+        // (OpenSSL).
+        let s = Scalar::decode(&hex::decode("2bf0dee8f11f23ea9246fa6f12fb1ef58c84c218460e715548c9cea8b0062df3c93ce25f73875d22817b4415b027c47a8e9cb155df01206f09966cac19de13785100").unwrap()).unwrap();
+        let enc = hex::decode("0300a23c4f00a1a0272ee9f7090f79cd9bd7636cf6e3f850ce1b444cb65fc1626a59c4dade9f0d7d3787b92c6ae4e567dd8955f19f20578e519ea8b7e265ba2c5264a8").unwrap();
+
+        let R = Point::decode(&enc).unwrap();
+        let P = Point::BASE * s;
+        assert!(P.equals(R) == 0xFFFFFFFF);
+        assert!(P.encode_compressed()[..] == enc[..]);
+        let Q = Point::mulgen(&s);
+        assert!(Q.equals(R) == 0xFFFFFFFF);
+        assert!(Q.encode_compressed()[..] == enc[..]);
+    }
+
+    #[test]
+    fn mul() {
+        let mut sh = Sha512::new();
+        for i in 0..20 {
+            // Build pseudorandom s1 and s2
+            sh.update(((2 * i + 0) as u64).to_le_bytes());
+            let v1 = sh.finalize_reset();
+            sh.update(((2 * i + 1) as u64).to_le_bytes());
+            let v2 = sh.finalize_reset();
+
+            let s1 = Scalar::decode_reduce(&v1);
+            let s2 = Scalar::decode_reduce(&v2);
+            let s3 = s1 * s2;
+            let P1 = Point::mulgen(&s1);
+            let Q1 = s1 * Point::BASE;
+            assert!(P1.equals(Q1) == 0xFFFFFFFF);
+            let P2 = Point::mulgen(&s3);
+            let Q2 = s2 * Q1;
+            assert!(P2.equals(Q2) == 0xFFFFFFFF);
+        }
+
+        // Edge cases: zero, and -1 (the scalar with the largest value).
+        assert!(Point::mulgen(&Scalar::ZERO).isneutral() == 0xFFFFFFFF);
+        assert!((Point::BASE * Scalar::ZERO).isneutral() == 0xFFFFFFFF);
+        assert!(Point::mulgen(&Scalar::MINUS_ONE).equals(-Point::BASE)
+            == 0xFFFFFFFF);
+        assert!((Point::BASE * Scalar::MINUS_ONE).equals(-Point::BASE)
+            == 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn mul_add_mulgen() {
+        let mut sh = Sha512::new();
+        for i in 0..20 {
+            // Build pseudorandom A, u and v
+            sh.update(((3 * i + 0) as u64).to_le_bytes());
+            let v1 = sh.finalize_reset();
+            sh.update(((3 * i + 1) as u64).to_le_bytes());
+            let v2 = sh.finalize_reset();
+            sh.update(((3 * i + 2) as u64).to_le_bytes());
+            let v3 = sh.finalize_reset();
+            let A = Point::mulgen(&Scalar::decode_reduce(&v1));
+            let u = Scalar::decode_reduce(&v2);
+            let v = Scalar::decode_reduce(&v3);
+
+            // Compute u*A + v*B in two different ways; check that they
+            // match.
+            let R1 = u * A + Point::mulgen(&v);
+            let R2 = A.mul_add_mulgen_vartime(&u, &v);
+            assert!(R1.equals(R2) == 0xFFFFFFFF);
+        }
+    }
+
+    #[test]
+    fn verify_helper() {
+        let mut sh = Sha512::new();
+        for i in 0..20 {
+            // Build pseudorandom Q, s and k.
+            // Compute R = s*G - k*Q
+            sh.update(((3 * i + 0) as u64).to_le_bytes());
+            let v1 = sh.finalize_reset();
+            sh.update(((3 * i + 1) as u64).to_le_bytes());
+            let v2 = sh.finalize_reset();
+            sh.update(((3 * i + 2) as u64).to_le_bytes());
+            let v3 = sh.finalize_reset();
+            let Q = Point::mulgen(&Scalar::decode_reduce(&v1));
+            let s = Scalar::decode_reduce(&v2);
+            let k = Scalar::decode_reduce(&v3);
+            let R = Point::mulgen(&s) - k * Q;
+
+            // verify_helper_vartime() must return true, but this
+            // must change to false if we change a scalar or a point.
+            assert!(Q.verify_helper_vartime(&R, &s, &k));
+            assert!(!Q.verify_helper_vartime(&R, &(s + Scalar::ONE), &k));
+            assert!(!Q.verify_helper_vartime(&R, &s, &(k + Scalar::ONE)));
+            assert!(!Q.verify_helper_vartime(&(R + Point::BASE), &s, &k));
+            assert!(!(Q + Point::BASE).verify_helper_vartime(&R, &s, &k));
+        }
+    }
+
+    #[test]
+    fn decode_verbose() {
+        assert!(Point::decode_verbose(&[]).err()
+            == Some(DecodeError::BadLength));
+        assert!(Point::decode_verbose(&[0x00; 49]).err()
+            == Some(DecodeError::BadLength));
+        assert!(Point::decode_verbose(&[0x01]).err()
+            == Some(DecodeError::BadHeader));
+        let mut buf = Point::BASE.encode_uncompressed();
+        assert!(Point::decode_verbose(&buf).is_ok());
+        buf[0] = 0x07 - (buf[132] & 1);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::BadHeader));
+        assert!(Point::decode_sec1(&buf).err()
+            == Some(DecodeError::NonCanonicalEncoding));
+        buf[0] = 0x06 + (buf[132] & 1);
+        assert!(Point::decode_sec1(&buf).unwrap().equals(Point::BASE)
+            == 0xFFFFFFFF);
+        buf[0] = 0x04;
+        buf[132] ^= 1;
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NotOnCurve));
+        buf[1..67].fill(0xFF);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+        let mut buf = Point::BASE.encode_compressed();
+        buf[1] = 0x02;
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+    }
+
+    #[test]
+    fn hash_truncation() {
+        // A SHA-512 output (512 bits) is shorter than the curve order,
+        // and is used as is (no truncation or shift).
+        let hv: [u8; 64] = Sha512::digest(b"sample").into();
+        let mut tmp = [0u8; 66];
+        tmp[2..].copy_from_slice(&hv);
+        let h = Scalar::decode(&super::bswap66(&tmp)).unwrap();
+        assert!(hash_to_scalar(&hv).equals(h) == 0xFFFFFFFF);
+
+        // A 65-byte (520-bit) value is also used as is.
+        let h = hash_to_scalar(&[0xFFu8; 65]);
+        let mut tmp = [0xFFu8; 66];
+        tmp[65] = 0x00;
+        assert!(h.encode() == tmp);
+
+        // Values of 66 bytes or more are truncated to their leftmost 521
+        // bits, then reduced modulo n. Expected values were computed
+        // with an independent implementation (Python).
+        let h = hash_to_scalar(&[0xFFu8; 66]);
+        let eh = hex::decode("00000000000000000000000000000000000000000000000000000000000000000005ae79787c40d069948033feb708f65a2fc44a36477663b851449048e16ec79bf6").unwrap();
+        assert!(h.encode() == super::bswap66(&eh));
+
+        // 1024-bit value: SHA-512("a") || SHA-512("b").
+        let mut hv = [0u8; 128];
+        hv[..64].copy_from_slice(&Sha512::digest(b"a"));
+        hv[64..].copy_from_slice(&Sha512::digest(b"b"));
+        let h = hash_to_scalar(&hv);
+        let eh = hex::decode("003e81f925b4482d28ea12f3dcd9eb05e5abafa51c3066bbc0b578a9a0ac1c1ea6050c18ca57e11aac04a554bce8420a8de6d3f7779d18259f8f2af64ca5fd34eaa4").unwrap();
+        assert!(h.encode() == super::bswap66(&eh));
+
+        // Only the leftmost 521 bits matter: the low 7 bits of the 66th
+        // byte are ignored, but not the bit just above them.
+        let mut hv2 = [0u8; 66];
+        hv2.copy_from_slice(&hv[..66]);
+        assert!(hash_to_scalar(&hv2).equals(h) == 0xFFFFFFFF);
+        hv2[65] ^= 0x7F;
+        assert!(hash_to_scalar(&hv2).equals(h) == 0xFFFFFFFF);
+        let mut hv3 = hv2;
+        hv3[65] ^= 0x80;
+        assert!(hash_to_scalar(&hv3).equals(h) == 0);
+
+        // Signatures use the same conversion.
+        let sk = PrivateKey::from_seed(b"hash_truncation");
+        let pk = sk.to_public_key();
+        let sig = sk.sign_hash(&hv, &[]);
+        assert!(pk.verify_hash(&sig, &hv));
+        assert!(pk.verify_hash(&sig, &hv2));
+        assert!(!pk.verify_hash(&sig, &hv3));
+        assert!(!pk.verify_hash(&sig, &hv[..65]));
+        assert!(sk.sign_hash(&hv2, &[]) == sig);
+    }
+
+    #[test]
+    fn signatures() {
+        // Test vectors from RFC 6979 (A.2.7), with SHA-512.
+        let sk = PrivateKey::decode(&hex::decode("00fad06daa62ba3b25d2fb40133da757205de67f5bb0018fee8c86e1b68c7e75caa896eb32f1f47c70855836a6d16fcc1466f6d8fbec67db89ec0c08b0e996b83538").unwrap()).unwrap();
+        let mut ep = [0x04u8; 133];
+        ep[1..67].copy_from_slice(&hex::decode("01894550d0785932e00eaa23b694f213f8c3121f86dc97a04e5a7167db4e5bcd371123d46e45db6b5d5370a7f20fb633155d38ffa16d2bd761dcac474b9a2f5023a4").unwrap());
+        ep[67..133].copy_from_slice(&hex::decode("00493101c962cd4d2fddf782285e64584139c2f91b47f87ff82354d6630f746a28a0db25741b5b34a828008b22acc23f924faafbd4d33f81ea66956dfeaa2bfdfcf5").unwrap());
+        let pk = PublicKey::decode(&ep).unwrap();
+        assert!(sk.to_public_key().encode_uncompressed() == ep);
+        assert!(sk.encode()[..] == hex::decode("00fad06daa62ba3b25d2fb40133da757205de67f5bb0018fee8c86e1b68c7e75caa896eb32f1f47c70855836a6d16fcc1466f6d8fbec67db89ec0c08b0e996b83538").unwrap()[..]);
+        const KAT: &[(&[u8], &str)] = &[
+            (b"sample", "00c328fafcbd79dd77850370c46325d987cb525569fb63c5d3bc53950e6d4c5f174e25a1ee9017b5d450606add152b534931d7d4e8455cc91f9b15bf05ec36e377fa00617cce7cf5064806c467f678d3b4080d6f1cc50af26ca209417308281b68af282623eaa63e5b5c0723d8b8c37ff0777b1a20f8ccb1dccc43997f1ee0e44da4a67a"),
+            (b"test", "013e99020abf5cee7525d16b69b229652ab6bdf2affcaef38773b4b7d08725f10cdb93482fdcc54edcee91eca4166b2a7c6265ef0ce2bd7051b7cef945babd47ee6d01fbd0013c674aa79cb39849527916ce301c66ea7ce8b80682786ad60f98f7e78a19ca69eff5c57400e3b3a0ad66ce0978214d13baf4e9ac60752f7b155e2de4dce3"),
+        ];
+        for (msg, esig) in KAT.iter() {
+            let hv: [u8; 64] = Sha512::digest(msg).into();
+            let sig = sk.sign_hash(&hv, &[]);
+            assert!(sig[..] == hex::decode(esig).unwrap()[..]);
+            assert!(pk.verify_hash(&sig, &hv));
+
+            // Altered hash or signature must be rejected.
+            let mut hv2 = hv;
+            hv2[5] ^= 0x01;
+            assert!(!pk.verify_hash(&sig, &hv2));
+            let mut sig2 = sig;
+            sig2[20] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+            let mut sig2 = sig;
+            sig2[100] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+
+            // Extra randomness changes the signature, but it is still
+            // valid.
+            let sig3 = sk.sign_hash(&hv, b"extra");
+            assert!(sig3 != sig);
+            assert!(pk.verify_hash(&sig3, &hv));
+        }
+
+        // Shorter hash values (e.g. SHA-256) are supported.
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        let sig = sk.sign_hash(&hv, &[]);
+        assert!(pk.verify_hash(&sig, &hv));
+        assert!(!pk.verify_hash(&sig, &Sha512::digest(b"sample")));
+    }
+
+    #[test]
+    fn sigver() {
+        // Test vectors computed with an independent implementation
+        // (OpenSSL, randomized ECDSA with SHA-512):
+        // (public x, public y, hash value, signature)
+        const KAT: &[[&str; 4]] = &[
+            ["00f4c4e77b98f32f54fa1e5890bd68072dc5a82ddef05200215532bda410cdb89e5d99391194d9623eb635273b7273a473578e2fdb70d8b9ba0b290b3c87b2c4bee9",
+             "00cd09ab175b58c0d88e76efe3ec14bd58a7ca191ab053af52acef726a5869b5d00afda80f8e422d532f4ae23a6ed09b28c811a253c04e63e995d7d0661c286a752c",
+             "61b830757e33e623cdacc8cc8ce18181bebdcb5dd08a490c333499ad19d1f6b39231a9b6c720f0ba5a1f775938a2c48e26130ce623198ee818fcb945dd01a0ba",
+             "0147914e66716f41ba0146a03dcd6bacda13defdae8b8b3d3389495ff2e9fc4979c7edddd8d433760a59a2192baf2febcb794708df28f3c12f08e8c9c6fc48856c4701a9c875551726dabd428b852a02f4df6a69e130014cc5f21d0c66b0888813c9eebe91384558729bb27a1ef615c8545d8776b67f727bfb4b16e817f08f3a955b03d6"],
+            ["01653e1bd1f4d4970ee31168a9a0b48e176f154a527aadffcc7f83079aa771e58572ef4d15f57c100e110e6f65da1f12530a19b64aa2db3ae74bc0e7201433989080",
+             "00a84a27934989c9c6a14b6f73ed6215ae78101292a0adaa32885f38cc9a06ed67f99139ab62c32bd41e2808bb48440fc856adbc529d3d92bae0cb40c8e43d7e34b3",
+             "ff7244559d12999295bc1bf7009f794b4a1b39acd18001186802f07c03b984d0cd8e7607afbb6d7302c464eeb66c8af00bf5a520bcfe4a167463eb14ff100e4b",
+             "01332f0b1ca28414b16b91987e9c4c2c9260f71edec19b0e14e5c762ccd2d9f3ab6a153819f22f8e004dea7a93bc6ff361269781a4e94f2eaea83e04305959e7317700d230f9416d80065414c418b2b05e034e0472a7b1b26cc2b299fd2a2a71ac3db1860f6b6f1869bdc8acbe7acfc83823ced47267f4f0005bde727239d3786572913b"],
+            ["0020b77b5abbbfbc6fcf56261726d5b34416356c041e94b969b1d4bf4d35729951fe68b222df59ca230e267b980e16cdcc1a85e3adc4c7cbf55810091286307de9d5",
+             "01af1eb8fc7fa3f17235f6b5fcad702bde0fe0ebbaca65f474201c052d8d02fef53ddf44c95001f9f3d7497d16ec18407b069dbfb3bb4aa181d84678d5adaae6064f",
+             "1f164b71de86a9d03915a0db108dc936338b3db6a03e658785cd209d84ab988e1c5629a67f643ba29562433a3fb5fc0b90de35864e6f59a5e8a45097a56dbef3",
+             "009d3b0c46cff02b9c0c11d789b0e19aca1ba986e19ef6d9ab28e5749baca47403f75e3a6e3c1ab04418fa927fd6cd1f42e3fb985c133e0a00973ea56d61d426ccde0180daf00362160ebe7626811f19f31da97952274f691c72e29196eef56bbb33a90ae787715c6ed2523fc36843987ef8b18906b7fd88d65dd24118e67fcfbaf7df63"],
+            ["01b56edcd259bea7a925e455e7c075bd279022018fc083eb0737c822bdbf443b491b806db546903fab4e8481f07787d6e804a66e936bfee9f62915f9d27f329f6e0e",
+             "001e74fdb5867e8b1d15e9425f3fd9fba2b542cfdd7447bf8c406328a8a54a43ce310efe32f90ed15fa262627fe7f01ee1d381cbfc1ac6c24657017a295a4f4ec604",
+             "ce13aa1a518f34a89b4bae2b1238d2bd7077f28c10b8c335ae18f71cbc00302beb53247bb850f764dfa806cb9cda20a864ef5a29a440b5342b1adc62504249f4",
+             "004840d3446c91217be512849fa3d26c85540238feb134b6dfdb196894c632cb01d25809a74262c9de3c768946cfb7e639e3aa27513d4fce398db6a856f79604b800007d2bf5fd97e7ced8b1dc6ab135801b0dd5f044aa01ec1142820513571e513eef596c8c06ab22b79078b5cb27fe0e06df1ffee12de7525098e4a20cd2810b5eabe8"],
+        ];
+        for kv in KAT.iter() {
+            let mut ep = [0x04u8; 133];
+            ep[1..67].copy_from_slice(&hex::decode(kv[0]).unwrap());
+            ep[67..133].copy_from_slice(&hex::decode(kv[1]).unwrap());
+            let pk = PublicKey::decode(&ep).unwrap();
+            let pk2 = PublicKey::decode(&pk.encode_compressed()).unwrap();
+            assert!(pk.point.equals(pk2.point) == 0xFFFFFFFF);
+            let hv = hex::decode(kv[2]).unwrap();
+            let sig = hex::decode(kv[3]).unwrap();
+            assert!(pk.verify_hash(&sig, &hv));
+            let mut hv2 = hv.clone();
+            hv2[63] ^= 0x80;
+            assert!(!pk.verify_hash(&sig, &hv2));
+            let mut sig2 = sig.clone();
+            sig2[1] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+            let mut sig2 = sig.clone();
+            sig2[131] ^= 0x01;
+            assert!(!pk.verify_hash(&sig2, &hv));
+
+            // r and s are swapped.
+            let mut sig2 = [0u8; 132];
+            sig2[..66].copy_from_slice(&sig[66..]);
+            sig2[66..].copy_from_slice(&sig[..66]);
+            assert!(!pk.verify_hash(&sig2, &hv));
+        }
+
+        // Out-of-range and zero values are rejected.
+        let pk = PrivateKey::from_seed(b"sigver").to_public_key();
+        assert!(!pk.verify_hash(&[0u8; 132], &[0u8; 64]));
+        assert!(!pk.verify_hash(&[0xFFu8; 132], &[0u8; 64]));
+        assert!(!pk.verify_hash(&[0x01u8; 131], &[0u8; 64]));
+    }
+
+    #[test]
+    fn ecdh() {
+        // Test vectors computed with an independent implementation
+        // (OpenSSL):
+        // (peer x, peer y, private key, public x, public y, shared secret)
+        const KAT: &[[&str; 6]] = &[
+            ["0077af2aeb5d6806b6a66f3efb8d3415790e4b50550059cfa0901dc52ec5915d622daf7702f5ca3ce2473c969332a0ac6c91e5c2df0539db25b162800ec10447d2f0",
+             "00a9fb21014aebff0ff30ecd64cb581b80633d61e1d6c084aa892c647ca61c0c723c11fc56aaa4264f10071a7a3cedad256f1faee40abd1ebdcb25d925cabbd2aab4",
+             "01b4142b52d321a5e03fac1f5de7565f03882c8bc013bd73f1a96bb89e7f095cddc920dd0bc88d36471a4f71360b459e86035260a73ea6bf38c9d2e1715219ce6a4c",
+             "01092dc90998ad612e51e51f09e349b1467a8d26bb3acb7994ef2de6f6ccf6f7dce2e15b74f95bed6bafe345e2a1bc17d62f4ebe718a4c123eabf14e206295edefbb",
+             "00b84c850005cc681f1d5f043de5836b9345b132e53c94fd16bba5723ff7de1a35922baa472555ee2056aaad0a025e824e708b2a9143fae8bbc50e51c9c42eedf3c2",
+             "01926f86ccf673d3d6ad7a1a347979caa957e3cb5cc8bfb2582435a35caed084f9c14619e135be6a88a6619beb43e1fd04900fa5bbee481f52459a440640f061a957"],
+            ["00068d33571a807f522127a7e367c4a97ffa14a24cdb4ae3c46cc36c5bfab312e6023146a43c8a6d02971da4414ef18905ae954575386503adc2f41995d1af10e742",
+             "00d47229a6420af726b1ccb49d53643376dae776146cc1649f2924c8dc9e208245448818ef3d82a27e6e85e8622a6ec424c5a67caf70efd3cb951c5b91b892cbb050",
+             "00451042d8b314e8802f88d63a7fe9353709c2a23ab0267b952bc32690acd2f4be177cc5197cef45fab3d6a34cad9e981091d28c5d7b1ec720549558405d0bd62c56",
+             "016789e5c8b5bb42cc9c0ee8a66473292cd86a286347d547f18410da246165bc39468f823189bf5e1808c6f5c77f004657ea078cef4f17fb25bef64fd5a7163b5742",
+             "00c7c5f10b292feb333919fe1cd2c526dd2f59f461e0047fe23a5516beda9d7c1cf0fbae9488a2eea7fc278d7331a1c596d6bba8d828dc50f0cc0f31c6230e3277e2",
+             "00d08c2679abf7c4f7cb2130b6b55eb6b5d79ef86645b5447a912559235a8795760aa8e93b6f68d63a083be86ca5fde684c80f9e5333415ec83c956c3535a23b245a"],
+            ["01a1b848834bb6df238389771ced84adedfc6dd5477b9960a84078d66783bde33e352af91dffbb059b548b6e83999c892d5e953cd4b27916e7c33ca6c0099f3ac978",
+             "00718ace049a635096bde20cccbe0d1268a161af52e847a37d51c1d3eae903118ba7196702d8330ef6e5e21d9f66fcfa2cad349e1e3f23dbc75425cb081e7e599b89",
+             "00a5ea99f3bb7f4a746b180420dd12fa6509e456713c5286e46eb1cb8362a0283218630002e78b73d4405b9efa9ee45a1d92ed05200fde3e69ee9073fce96cf52068",
+             "013fb318839037995e68ca878c7228db236a458fdd51f04e220ba152150f0c091d7b23ad406bbaa704882cfcf9e40ac52da9de4123241a430fc4565891b520c74039",
+             "00061a71851044d2a2e046a21eb9cc267a4c71f20b9b5430649f465d150b04a7bf9b56d5a22c7fec0795d02efc2a4d282930ad569ca9e089a98f7cf33cf8b28b3113",
+             "00bcab1fc6c059773e5625f3f5e340681381c579231d6e63f7e455e4a5212749778f03ddec08040adf755f68b45e32fd0f7adbf53b98641a45d9e571230722fcce95"],
+        ];
+        let dec_pk = |x: &str, y: &str| {
+            let mut buf = [0x04u8; 133];
+            buf[1..67].copy_from_slice(&hex::decode(x).unwrap());
+            buf[67..].copy_from_slice(&hex::decode(y).unwrap());
+            PublicKey::decode(&buf).unwrap()
+        };
+        for kv in KAT.iter() {
+            let peer = dec_pk(kv[0], kv[1]);
+            let sk = PrivateKey::decode(&hex::decode(kv[2]).unwrap()).unwrap();
+            let pk = dec_pk(kv[3], kv[4]);
+            assert!(sk.to_public_key().point.equals(pk.point) != 0);
+            let z = super::ecdh(&sk, &peer).unwrap();
+            assert!(hex::encode(z) == kv[5]);
+        }
+
+        // The point-at-infinity is rejected, both when decoding and when
+        // forced into a PublicKey instance.
+        assert!(PublicKey::decode(&[0u8]).is_none());
+        let sk = PrivateKey::decode(&hex::decode(KAT[0][2]).unwrap()).unwrap();
+        let bad = PublicKey { point: Point::NEUTRAL };
+        assert!(super::ecdh(&sk, &bad).is_none());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn ecdh_ephemeral() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x35u8; 32]);
+        let (sk1, pk1) = super::generate_ephemeral(&mut rng);
+        let (sk2, pk2) = super::generate_ephemeral(&mut rng);
+        assert!(sk1.to_public_key().point.equals(pk1.point) != 0);
+        assert!(pk1.point.equals(pk2.point) == 0);
+        let z1 = super::ecdh(&sk1, &pk2).unwrap();
+        let z2 = super::ecdh(&sk2, &pk1).unwrap();
+        assert!(z1 == z2);
+    }
+
+    #[test]
+    fn der_signatures() {
+        // Signatures generated with OpenSSL (deterministic, RFC 6979):
+        // full-size r and s, a short r, and a short s. The SEQUENCE
+        // length always uses the long form.
+        let sk = PrivateKey::decode(&hex::decode(
+            "000000000000000000000000000000000000000001234567890abcdef000000000000000000000000000000000000000000000000000000000000000000000003039")
+            .unwrap()).unwrap();
+        let pk = PublicKey::decode(&hex::decode(
+            "0300304dcc2e176aa1ebf5abe38c113e31e9055a4ca87b2b1aa7181631f118580a72213c564914cd6d5c83687d913e206a1e229ab102edaf24a09d4a8ae70beaa5d6b7")
+            .unwrap()).unwrap();
+        assert!(sk.to_public_key().encode_compressed() == pk.encode_compressed());
+        const KAT: &[(&[u8], &str)] = &[
+            (b"msg1", "308188024200f1d75339c5c84be13009b86672316d4f0fdaefefea5c7c6c0b87adbd3fbef906c1a0409a76dde740f05429886cf9da0fd421129020ca1168b6a3e5497cbd0bb6a702420147d48f5d667aa2968f13edcc65302879fee3e7f681bea527028a14a5554d82c9beb20d7201f5d293757250d9df77e840da9f9845d8c99e1dbd8771a842671e91f0"),
+            (b"msg131", "308186024100b1fc6d206dc4fe0ab8994ab481d65fd63c1d8a7813579da85e3b8ccce2cc7e6998231eb6b7b419967e020e0e24cbc8bcb3a88418a5c2e2829f511fded28ee7b4024171a1cc6ad3ad715c3e347c4ccd71445ebf7d36c2e6e06a1802a0a3d0f1c7732693fc233edb12c835f2e33aaf4311cbdb6600e304a53d8228cfced11738dbd52ecc"),
+            (b"msg1741", "308186024201b2e71d814018a257cf80643305904dbc0cb748c02fba5ae3ab96db7c656e849ace58dc189bc0183d9fbda3f1d3aea0b10ddc60e4be947d0981c1d091065ad8b4db024002000b0e71ee4dafb3ec68f43022a0a35321e43a1c85ee72d13b847de7020826fc5128c4500b24473c99fb87e1900702110d79032e679e1062d3aeacbe436a8d"),
+        ];
+        for (msg, der) in KAT.iter() {
+            let der = hex::decode(der).unwrap();
+            let hv: [u8; 64] = Sha512::digest(msg).into();
+            let sig = sk.sign_hash(&hv, &[]);
+            let mut buf = [0u8; 141];
+            let n = signature_to_der(&sig, &mut buf);
+            assert!(buf[..n] == der[..]);
+            let sig2 = signature_from_der(&der, false).unwrap();
+            assert!(sig2 == sig);
+            assert!(pk.verify_hash(&sig2, &hv));
+        }
+    }
+
+    #[test]
+    fn public_key_fmt() {
+        let pk = PrivateKey::from_seed(b"fmt").to_public_key();
+        let s = format!("{}", pk);
+        assert!(s.len() == 134);
+        assert!(format!("{:x}", pk) == s);
+        let pk2: PublicKey = s.parse().unwrap();
+        assert!(pk2.point.equals(pk.point) == 0xFFFFFFFF);
+        let s2 = hex::encode(pk.encode_uncompressed());
+        let pk3: PublicKey = s2.parse().unwrap();
+        assert!(pk3.point.equals(pk.point) == 0xFFFFFFFF);
+        assert!("00".parse::<PublicKey>().err() == Some(ParseError::BadLength));
+    }
+}
@@ -0,0 +1,247 @@
+//! PASETO version 4 public tokens (`v4.public`).
+//!
+//! A [PASETO] `v4.public` token carries a message (payload), an optional
+//! footer, and an Ed25519 signature over the pre-authentication encoding
+//! (PAE) of the header `"v4.public."`, the message, the footer and an
+//! optional implicit assertion (which is not included in the token, but
+//! must be provided again by the verifier). The token is:
+//!
+//! ```text
+//! v4.public.base64url(message || signature)[.base64url(footer)]
+//! ```
+//!
+//! with the URL-safe Base64 alphabet, without padding; the footer part
+//! is omitted when the footer is empty.
+//!
+//! Payloads and footers are treated as opaque bytes; this module does
+//! not parse or validate JSON claims (e.g. expiration times), which is
+//! the job of the application. `v4_public_verify()` returns the payload
+//! only if the signature is valid. The footer of a token can be read
+//! before verification with `v4_public_footer()` (e.g. to find a key
+//! identifier), but it must not be trusted until the token is verified.
+//!
+//! [PASETO]: https://github.com/paseto-standard/paseto-spec
+
+use crate::base64::{self, Variant};
+use crate::ed25519;
+use crate::{String, Vec};
+
+/// Error type for PASETO tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasetoError {
+    /// The token does not start with `"v4.public."`.
+    BadHeader,
+    /// The token structure or Base64 encoding is invalid.
+    BadEncoding,
+    /// The token footer does not match the expected footer.
+    FooterMismatch,
+    /// The signature is invalid.
+    InvalidSignature,
+}
+
+impl core::fmt::Display for PasetoError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PasetoError::BadHeader => f.write_str("unsupported token header"),
+            PasetoError::BadEncoding => f.write_str("invalid token encoding"),
+            PasetoError::FooterMismatch => f.write_str("footer mismatch"),
+            PasetoError::InvalidSignature => f.write_str("invalid signature"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PasetoError {}
+
+const V4_PUBLIC_HEADER: &str = "v4.public.";
+
+/// Pre-authentication encoding (PAE) of a sequence of byte strings: the
+/// number of strings, then each string preceded by its length, with all
+/// counts encoded over 64 bits (little-endian, top bit cleared).
+pub fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(
+        8 + pieces.iter().map(|p| 8 + p.len()).sum::<usize>());
+    out.extend_from_slice(&le64(pieces.len()));
+    for p in pieces {
+        out.extend_from_slice(&le64(p.len()));
+        out.extend_from_slice(p);
+    }
+    out
+}
+
+fn le64(n: usize) -> [u8; 8] {
+    ((n as u64) & 0x7FFFFFFFFFFFFFFF).to_le_bytes()
+}
+
+fn encode_b64(src: &[u8], dst: &mut String) {
+    let mut buf = vec![0u8; base64::encoded_len(src.len(), Variant::UrlSafeNoPad)];
+    base64::encode(src, &mut buf, Variant::UrlSafeNoPad).unwrap();
+    dst.push_str(core::str::from_utf8(&buf).unwrap());
+}
+
+fn decode_b64(src: &str) -> Result<Vec<u8>, PasetoError> {
+    let n = base64::decoded_len(src.as_bytes(), Variant::UrlSafeNoPad)
+        .map_err(|_| PasetoError::BadEncoding)?;
+    let mut buf = vec![0u8; n];
+    base64::decode(src.as_bytes(), &mut buf, Variant::UrlSafeNoPad)
+        .map_err(|_| PasetoError::BadEncoding)?;
+    Ok(buf)
+}
+
+// Split a token into its (still encoded) body and footer parts.
+fn split_token(token: &str) -> Result<(&str, Option<&str>), PasetoError> {
+    let rest = token.strip_prefix(V4_PUBLIC_HEADER)
+        .ok_or(PasetoError::BadHeader)?;
+    let mut parts = rest.split('.');
+    let body = parts.next().unwrap();
+    let footer = parts.next();
+    if parts.next().is_some() || footer == Some("") {
+        return Err(PasetoError::BadEncoding);
+    }
+    Ok((body, footer))
+}
+
+/// Signs a message into a `v4.public` token. The footer (included in the
+/// token) and the implicit assertion (not included) are optional; `None`
+/// is equivalent to an empty value.
+pub fn v4_public_sign(key: &ed25519::PrivateKey, message: &[u8],
+    footer: Option<&[u8]>, implicit: Option<&[u8]>) -> String
+{
+    let footer = footer.unwrap_or(&[]);
+    let implicit = implicit.unwrap_or(&[]);
+    let m2 = pae(&[V4_PUBLIC_HEADER.as_bytes(), message, footer, implicit]);
+    let sig = key.sign_raw(&m2);
+
+    let mut body = Vec::with_capacity(message.len() + 64);
+    body.extend_from_slice(message);
+    body.extend_from_slice(&sig);
+    let mut token = String::from(V4_PUBLIC_HEADER);
+    encode_b64(&body, &mut token);
+    if !footer.is_empty() {
+        token.push('.');
+        encode_b64(footer, &mut token);
+    }
+    token
+}
+
+/// Verifies a `v4.public` token, and returns its message. If `footer` is
+/// provided, then the token footer must be equal to it (the comparison
+/// is constant-time); otherwise, any footer is accepted (it is still
+/// covered by the signature). The implicit assertion must be the same
+/// as the one used for signing (`None` is equivalent to an empty
+/// value).
+pub fn v4_public_verify(key: &ed25519::PublicKey, token: &str,
+    footer: Option<&[u8]>, implicit: Option<&[u8]>)
+    -> Result<Vec<u8>, PasetoError>
+{
+    let (body, tf) = split_token(token)?;
+    let body = decode_b64(body)?;
+    if body.len() < 64 {
+        return Err(PasetoError::BadEncoding);
+    }
+    let tf = match tf {
+        Some(s) => decode_b64(s)?,
+        None => Vec::new(),
+    };
+    if let Some(ef) = footer {
+        if !crate::ct::equals(&tf, ef) {
+            return Err(PasetoError::FooterMismatch);
+        }
+    }
+    let (message, sig) = body.split_at(body.len() - 64);
+    let m2 = pae(&[V4_PUBLIC_HEADER.as_bytes(), message, &tf,
+        implicit.unwrap_or(&[])]);
+    if !key.verify_raw(sig, &m2) {
+        return Err(PasetoError::InvalidSignature);
+    }
+    Ok(message.to_vec())
+}
+
+/// Extracts the footer of a `v4.public` token, without verifying the
+/// token. An empty vector is returned if the token has no footer.
+pub fn v4_public_footer(token: &str) -> Result<Vec<u8>, PasetoError> {
+    match split_token(token)?.1 {
+        Some(s) => decode_b64(s),
+        None => Ok(Vec::new()),
+    }
+}
+
+// ========================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::{pae, v4_public_sign, v4_public_verify, v4_public_footer,
+                PasetoError};
+    use crate::ed25519::{PrivateKey, PublicKey};
+    use crate::String;
+
+    // Official PASETO v4 test vectors 4-S-1 to 4-S-3.
+    const SEED: &str = "b4cbfb43df4ce210727d953e4a713307fa19bb7d9f85041438d9e11b942a3774";
+    const PUB: &str = "1eb9dbbbbc047c03fd70604e0071f0987e16b28b757225c11f00415d0e20b1a2";
+    const PAYLOAD: &[u8] = br#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+    const FOOTER: &[u8] = br#"{"kid":"zVhMiPBP9fRf2snEcT7gFTioeA9COcNy9DfgL1W60haN"}"#;
+    const IMPLICIT: &[u8] = br#"{"test-vector":"4-S-3"}"#;
+    const KAT: [(bool, bool, &str); 3] = [
+        (false, false, "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA"),
+        (true, false, "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9v3Jt8mx_TdM2ceTGoqwrh4yDFn0XsHvvV_D0DtwQxVrJEBMl0F2caAdgnpKlt4p7xBnx1HcO-SPo8FPp214HDw.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9"),
+        (true, true, "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9NPWciuD3d0o5eXJXG5pJy-DiVEoyPYWs1YSTwWHNJq6DZD3je5gf-0M4JR9ipdUSJbIovzmBECeaWmaqcaP0DQ.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9"),
+    ];
+
+    #[test]
+    fn pae_encoding() {
+        // Examples from the PASETO specification.
+        assert!(pae(&[]) == hex::decode("0000000000000000").unwrap());
+        assert!(pae(&[b""]) == hex::decode("01000000000000000000000000000000").unwrap());
+        assert!(pae(&[b"test"]) == hex::decode("0100000000000000040000000000000074657374").unwrap());
+    }
+
+    #[test]
+    fn v4_public() {
+        let sk = PrivateKey::from_seed(&hex::decode(SEED).unwrap());
+        let pk = PublicKey::decode(&hex::decode(PUB).unwrap()).unwrap();
+        assert!(sk.public_key.encoded == pk.encoded);
+
+        for (has_footer, has_implicit, token) in KAT.iter() {
+            let f = if *has_footer { Some(FOOTER) } else { None };
+            let i = if *has_implicit { Some(IMPLICIT) } else { None };
+            assert!(v4_public_sign(&sk, PAYLOAD, f, i) == *token);
+            assert!(v4_public_verify(&pk, token, f, i).unwrap() == PAYLOAD);
+            assert!(v4_public_footer(token).unwrap() == f.unwrap_or(b""));
+
+            // Without an expected footer, any footer is accepted.
+            assert!(v4_public_verify(&pk, token, None, i).unwrap() == PAYLOAD);
+
+            // Wrong expected footer or implicit assertion.
+            assert!(v4_public_verify(&pk, token, Some(b"x"), i).err() == Some(PasetoError::FooterMismatch));
+            assert!(v4_public_verify(&pk, token, f, Some(b"x")).err() == Some(PasetoError::InvalidSignature));
+
+            // Altered body.
+            let n = "v4.public.".len() + 5;
+            let mut t2 = String::from(&token[..n]);
+            t2.push(if &token[n..(n + 1)] == "A" { 'B' } else { 'A' });
+            t2.push_str(&token[(n + 1)..]);
+            assert!(v4_public_verify(&pk, &t2, f, i).err() == Some(PasetoError::InvalidSignature));
+
+            // Other versions and purposes are rejected.
+            let t2 = token.replace("v4.public.", "v4.local.");
+            assert!(v4_public_verify(&pk, &t2, f, i).err() == Some(PasetoError::BadHeader));
+            let t2 = token.replace("v4.public.", "v3.public.");
+            assert!(v4_public_verify(&pk, &t2, f, i).err() == Some(PasetoError::BadHeader));
+
+            // Malformed tokens.
+            let t2 = format!("{}.", token);
+            assert!(v4_public_verify(&pk, &t2, f, i).err() == Some(PasetoError::BadEncoding));
+            let t2 = format!("{}=", token);
+            assert!(v4_public_verify(&pk, &t2, f, i).err() == Some(PasetoError::BadEncoding));
+        }
+
+        // Token with an explicit footer, verified without it: the
+        // signature covers the footer.
+        let token = v4_public_sign(&sk, b"", Some(b"kid"), None);
+        let body = token.split('.').nth(2).unwrap();
+        let t2 = format!("v4.public.{}", body);
+        assert!(v4_public_verify(&pk, &t2, None, None).err() == Some(PasetoError::InvalidSignature));
+        assert!(v4_public_verify(&pk, "v4.public.AAAA", None, None).err() == Some(PasetoError::BadEncoding));
+    }
+}
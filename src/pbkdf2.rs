@@ -0,0 +1,150 @@
+//! PBKDF2 (RFC 8018) with HMAC/SHA-256 and HMAC/SHA-512.
+//!
+//! `pbkdf2_hmac_sha256()` and `pbkdf2_hmac_sha512()` derive a key of
+//! arbitrary length from a password, a salt and an iteration count. The
+//! HMAC key schedule for the password (the inner and outer hash states
+//! after injection of the padded key) is computed only once, and reused
+//! for all iterations, so that each iteration costs two compression
+//! function calls.
+//!
+//! An iteration count of zero, or an empty output, is reported as an
+//! error. The output length is limited to `2^32 - 1` times the hash
+//! output length, as per the standard; this limit cannot be reached
+//! in practice on 32-bit systems.
+
+use super::hmac::{HmacSha256, HmacSha512};
+
+/// Error type for PBKDF2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pbkdf2Error {
+    /// The iteration count is zero.
+    ZeroIterations,
+    /// The requested output is empty.
+    EmptyOutput,
+    /// The requested output is longer than `2^32 - 1` times the hash
+    /// output length.
+    OutputTooLong,
+}
+
+impl core::fmt::Display for Pbkdf2Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Pbkdf2Error::ZeroIterations => f.write_str("zero iteration count"),
+            Pbkdf2Error::EmptyOutput => f.write_str("empty output"),
+            Pbkdf2Error::OutputTooLong => f.write_str("requested output too long"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Pbkdf2Error {}
+
+macro_rules! define_pbkdf2 {
+    ($fname: ident, $hmac: ident, $hlen: expr, $doc: expr) => {
+
+        #[doc = $doc]
+        pub fn $fname(password: &[u8], salt: &[u8], iterations: u32,
+            out: &mut [u8]) -> Result<(), Pbkdf2Error>
+        {
+            if iterations == 0 {
+                return Err(Pbkdf2Error::ZeroIterations);
+            }
+            if out.is_empty() {
+                return Err(Pbkdf2Error::EmptyOutput);
+            }
+            if ((out.len() - 1) / $hlen) as u64 >= 0xFFFFFFFF {
+                return Err(Pbkdf2Error::OutputTooLong);
+            }
+
+            // Keyed once; each HMAC computation starts from a copy.
+            let hm = $hmac::new(password);
+            for (i, chunk) in out.chunks_mut($hlen).enumerate() {
+                let mut h = hm;
+                h.update(salt);
+                h.update(&((i + 1) as u32).to_be_bytes());
+                let mut u = h.finalize();
+                let mut t = u;
+                for _ in 1..iterations {
+                    let mut h = hm;
+                    h.update(&u);
+                    u = h.finalize();
+                    for j in 0..$hlen {
+                        t[j] ^= u[j];
+                    }
+                }
+                chunk.copy_from_slice(&t[..chunk.len()]);
+            }
+            Ok(())
+        }
+    }
+}
+
+define_pbkdf2!(pbkdf2_hmac_sha256, HmacSha256, 32,
+    "PBKDF2 with HMAC/SHA-256: fill `out` with a key derived from the
+password and salt, with the provided iteration count.");
+define_pbkdf2!(pbkdf2_hmac_sha512, HmacSha512, 64,
+    "PBKDF2 with HMAC/SHA-512: fill `out` with a key derived from the
+password and salt, with the provided iteration count.");
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Pbkdf2Error, pbkdf2_hmac_sha256, pbkdf2_hmac_sha512};
+
+    // (password, salt, iterations, output/SHA-256, output/SHA-512)
+    // The SHA-256 values are the usual public vectors (the inputs of RFC
+    // 6070, and the PBKDF2 vectors of RFC 7914, section 11); the SHA-512
+    // values use the same inputs.
+    const KAT: [(&[u8], &[u8], u32, &str, &str); 7] = [
+        (b"password", b"salt", 1,
+         "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b",
+         "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252"),
+        (b"password", b"salt", 2,
+         "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43",
+         "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53c"),
+        (b"password", b"salt", 4096,
+         "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a",
+         "d197b1b33db0143e018b12f3d1d1479e6cdebdcc97c5c0f87f6902e072f457b5"),
+        (b"passwordPASSWORDpassword",
+         b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096,
+         "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9",
+         "8c0511f4c6e597c6ac6315d8f0362e225f3c501495ba23b868c005174dc4ee71115b59f9e60cd953"),
+        (b"pass\x00word", b"sa\x00lt", 4096,
+         "89b69d0516f829893c696226650a8687",
+         "9d9e9c4cd21fe4be24d5b8244c759665"),
+        (b"passwd", b"salt", 1,
+         "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783",
+         "c74319d99499fc3e9013acff597c23c5baf0a0bec5634c46b8352b793e324723d55caa76b2b25c43402dcfdc06cdcf66f95b7d0429420b39520006749c51a04e"),
+        (b"Password", b"NaCl", 80000,
+         "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56a1d425a1225833549adb841b51c9b3176a272bdebba1d078478f62b397f33c8d",
+         "e6337d6fbeb645c794d4a9b5b75b7b30dac9ac50376a91df1f4460f6060d5addb2c1fd1f84409abacc67de7eb4056e6bb06c2d82c3ef4ccd1bded0f675ed97c6"),
+    ];
+
+    #[test]
+    fn kat() {
+        for (pw, salt, c, r256, r512) in KAT.iter() {
+            let r = hex::decode(r256).unwrap();
+            let mut out = [0u8; 64];
+            pbkdf2_hmac_sha256(pw, salt, *c, &mut out[..r.len()]).unwrap();
+            assert!(out[..r.len()] == r[..]);
+
+            let r = hex::decode(r512).unwrap();
+            let mut out = [0u8; 64];
+            pbkdf2_hmac_sha512(pw, salt, *c, &mut out[..r.len()]).unwrap();
+            assert!(out[..r.len()] == r[..]);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut out = [0u8; 32];
+        assert!(pbkdf2_hmac_sha256(b"password", b"salt", 0, &mut out)
+            == Err(Pbkdf2Error::ZeroIterations));
+        assert!(pbkdf2_hmac_sha256(b"password", b"salt", 1, &mut out[..0])
+            == Err(Pbkdf2Error::EmptyOutput));
+        assert!(pbkdf2_hmac_sha512(b"password", b"salt", 0, &mut out)
+            == Err(Pbkdf2Error::ZeroIterations));
+        assert!(pbkdf2_hmac_sha512(b"password", b"salt", 1, &mut out[..0])
+            == Err(Pbkdf2Error::EmptyOutput));
+    }
+}
@@ -0,0 +1,243 @@
+//! Poly1305 one-time authenticator (RFC 8439).
+//!
+//! `Poly1305` is a MAC context keyed with a 32-byte one-time key (the
+//! `r` and `s` halves of RFC 8439, section 2.5); it produces 16-byte
+//! tags. The API mirrors that of the `hmac` module: `update()`,
+//! `finalize()` (which does not modify the context), `verify()`
+//! (constant-time comparison with a received tag), and the one-stop
+//! function `mac()`.
+//!
+//! A Poly1305 key MUST NOT be used for more than one message; it is
+//! normally derived from a stream cipher keystream, as in the
+//! ChaCha20-Poly1305 AEAD (see the `chacha20poly1305` module).
+//!
+//! The implementation uses three 44-bit limbs with 64x64->128
+//! multiplications, and is constant-time.
+
+use core::convert::TryFrom;
+
+const MASK44: u64 = (1u64 << 44) - 1;
+const MASK42: u64 = (1u64 << 42) - 1;
+
+/// Poly1305 context.
+#[derive(Clone, Copy, Debug)]
+pub struct Poly1305 {
+    r: [u64; 3],
+    h: [u64; 3],
+    pad: [u64; 2],
+    // Buffered partial block.
+    buf: [u8; 16],
+    buf_len: usize,
+}
+
+impl Poly1305 {
+
+    /// Tag length (in bytes).
+    pub const TAG_LEN: usize = 16;
+
+    /// Initialize a new context with the provided one-time key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        let t0 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[0..8]).unwrap());
+        let t1 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[8..16]).unwrap());
+        let p0 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[16..24]).unwrap());
+        let p1 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[24..32]).unwrap());
+
+        // r is clamped (RFC 8439, section 2.5.1).
+        Self {
+            r: [
+                t0 & 0xFFC0FFFFFFF,
+                ((t0 >> 44) | (t1 << 20)) & 0xFFFFFC0FFFF,
+                (t1 >> 24) & 0x00FFFFFFC0F,
+            ],
+            h: [0; 3],
+            pad: [p0, p1],
+            buf: [0u8; 16],
+            buf_len: 0,
+        }
+    }
+
+    // Process one 16-byte block; `hibit` is 2^40 for full blocks (the
+    // added byte of value 1, in the top limb), 0 for the padded final
+    // partial block.
+    fn block(h: &mut [u64; 3], r: &[u64; 3], m: &[u8; 16], hibit: u64) {
+        let t0 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&m[0..8]).unwrap());
+        let t1 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&m[8..16]).unwrap());
+        let h0 = h[0] + (t0 & MASK44);
+        let h1 = h[1] + (((t0 >> 44) | (t1 << 20)) & MASK44);
+        let h2 = h[2] + (((t1 >> 24) & MASK42) | hibit);
+
+        // h <- h*r mod 2^130 - 5; limbs are at offsets 0, 44 and 88, so
+        // that 2^132 = 4*5 mod p.
+        let [r0, r1, r2] = *r;
+        let s1 = r1 * 20;
+        let s2 = r2 * 20;
+        let mul = |a: u64, b: u64| (a as u128) * (b as u128);
+        let d0 = mul(h0, r0) + mul(h1, s2) + mul(h2, s1);
+        let mut d1 = mul(h0, r1) + mul(h1, r0) + mul(h2, s2);
+        let mut d2 = mul(h0, r2) + mul(h1, r1) + mul(h2, r0);
+
+        let h0 = (d0 as u64) & MASK44;
+        d1 += d0 >> 44;
+        let h1 = (d1 as u64) & MASK44;
+        d2 += d1 >> 44;
+        let h2 = (d2 as u64) & MASK42;
+        let h0 = h0 + ((d2 >> 42) as u64) * 5;
+        h[0] = h0 & MASK44;
+        h[1] = h1 + (h0 >> 44);
+        h[2] = h2;
+    }
+
+    /// Inject some more bytes into the context.
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+        if self.buf_len != 0 {
+            let n = core::cmp::min(16 - self.buf_len, data.len());
+            self.buf[self.buf_len..(self.buf_len + n)]
+                .copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+            if self.buf_len < 16 {
+                return;
+            }
+            Self::block(&mut self.h, &self.r, &self.buf, 1u64 << 40);
+            self.buf_len = 0;
+        }
+        let mut chunks = data.chunks_exact(16);
+        for m in &mut chunks {
+            Self::block(&mut self.h, &self.r,
+                <&[u8; 16]>::try_from(m).unwrap(), 1u64 << 40);
+        }
+        let rem = chunks.remainder();
+        self.buf[..rem.len()].copy_from_slice(rem);
+        self.buf_len = rem.len();
+    }
+
+    /// Finalize the current computation and get the tag. The context is
+    /// not modified.
+    pub fn finalize(&self) -> [u8; 16] {
+        let mut h = self.h;
+        if self.buf_len != 0 {
+            let mut m = [0u8; 16];
+            m[..self.buf_len].copy_from_slice(&self.buf[..self.buf_len]);
+            m[self.buf_len] = 1;
+            Self::block(&mut h, &self.r, &m, 0);
+        }
+
+        // Full carry propagation.
+        let [mut h0, mut h1, mut h2] = h;
+        for _ in 0..2 {
+            h2 += h1 >> 44;
+            h1 &= MASK44;
+            h0 += (h2 >> 42) * 5;
+            h2 &= MASK42;
+            h1 += h0 >> 44;
+            h0 &= MASK44;
+        }
+
+        // Compute g = h - p = h + 5 - 2^130, and use it if it is not
+        // negative.
+        let g0 = h0 + 5;
+        let g1 = h1 + (g0 >> 44);
+        let g2 = h2.wrapping_add(g1 >> 44).wrapping_sub(1u64 << 42);
+        let g0 = g0 & MASK44;
+        let g1 = g1 & MASK44;
+        let m = (g2 >> 63).wrapping_sub(1);
+        h0 ^= m & (h0 ^ g0);
+        h1 ^= m & (h1 ^ g1);
+        h2 ^= m & (h2 ^ g2);
+
+        // Add the pad (mod 2^128).
+        let [p0, p1] = self.pad;
+        h0 += p0 & MASK44;
+        h1 += (((p0 >> 44) | (p1 << 20)) & MASK44) + (h0 >> 44);
+        h0 &= MASK44;
+        h2 += ((p1 >> 24) & MASK42) + (h1 >> 44);
+        h1 &= MASK44;
+        let w0 = h0 | (h1 << 44);
+        let w1 = (h1 >> 20) | (h2 << 24);
+
+        let mut tag = [0u8; 16];
+        tag[..8].copy_from_slice(&w0.to_le_bytes());
+        tag[8..].copy_from_slice(&w1.to_le_bytes());
+        tag
+    }
+
+    /// Finalize the current computation and compare the output with the
+    /// provided tag. The comparison is constant-time; tags whose length
+    /// is not exactly 16 bytes are rejected. The context is not
+    /// modified.
+    pub fn verify(&self, tag: &[u8]) -> bool {
+        crate::ct::equals(&self.finalize(), tag)
+    }
+
+    /// One-stop function for computing the tag over some data.
+    pub fn mac(key: &[u8; 32], data: &[u8]) -> [u8; 16] {
+        let mut pc = Self::new(key);
+        pc.update(data);
+        pc.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Poly1305;
+
+    // Test vectors from RFC 8439 (sections 2.5.2 and A.3).
+    const KAT: [(&str, &str, &str); 6] = [
+        ("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b",
+         "43727970746f6772617068696320466f72756d2052657365617263682047726f7570",
+         "a8061dc1305136c6c22b8baf0c0127a9"),
+        ("0000000000000000000000000000000000000000000000000000000000000000",
+         "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+         "00000000000000000000000000000000"),
+        ("0200000000000000000000000000000000000000000000000000000000000000",
+         "ffffffffffffffffffffffffffffffff",
+         "03000000000000000000000000000000"),
+        ("02000000000000000000000000000000ffffffffffffffffffffffffffffffff",
+         "02000000000000000000000000000000",
+         "03000000000000000000000000000000"),
+        ("0100000000000000000000000000000000000000000000000000000000000000",
+         "fffffffffffffffffffffffffffffffff0ffffffffffffffffffffffffffffff11000000000000000000000000000000",
+         "05000000000000000000000000000000"),
+        ("0100000000000000000000000000000000000000000000000000000000000000",
+         "fffffffffffffffffffffffffffffffffbfefefefefefefefefefefefefefefe01010101010101010101010101010101",
+         "00000000000000000000000000000000"),
+    ];
+
+    #[test]
+    fn rfc8439() {
+        for (kh, mh, th) in KAT.iter() {
+            let mut key = [0u8; 32];
+            key.copy_from_slice(&hex::decode(kh).unwrap());
+            let msg = hex::decode(mh).unwrap();
+            let tag = hex::decode(th).unwrap();
+            assert!(Poly1305::mac(&key, &msg)[..] == tag[..]);
+
+            // Same computation with data injected by chunks of varying
+            // lengths.
+            for step in 1..20 {
+                let mut pc = Poly1305::new(&key);
+                for chunk in msg.chunks(step) {
+                    pc.update(chunk);
+                }
+                assert!(pc.verify(&tag));
+            }
+        }
+    }
+
+    #[test]
+    fn verify_rejects() {
+        let key = [0x5Au8; 32];
+        let mut pc = Poly1305::new(&key);
+        pc.update(b"The quick brown fox jumps over the lazy dog");
+        let tag = pc.finalize();
+        assert!(pc.verify(&tag));
+        assert!(!pc.verify(&tag[..15]));
+        for i in 0..tag.len() {
+            let mut t2 = tag;
+            t2[i] ^= 0x01;
+            assert!(!pc.verify(&t2));
+        }
+    }
+}
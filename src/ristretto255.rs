@@ -20,7 +20,30 @@
 //! Edwards curve, this is the order of a specific subgroup of the
 //! curve).
 //!
+//! `Point::encode_batch()` encodes many points at once, and
+//! `Point::double_and_encode_batch()` encodes the doubles of many points
+//! with a single field inversion for the whole batch.
+//! `PrecomputedTable` supports multi-scalar multiplications over a fixed
+//! set of elements, with precomputed tables (heap allocation support is
+//! required).
+//!
+//! `Point::derive_generators()` (and the allocation-free iterator
+//! `Point::generators()`) derive sequences of independent generators
+//! from a label, with a fixed process.
+//!
+//! The `schnorr` submodule implements Schnorr signatures over
+//! ristretto255. The `dleq` submodule implements non-interactive proofs
+//! of discrete logarithm equality.
+//!
+//! `Point::from_uniform_bytes()` maps 64 uniform bytes to a group
+//! element; `Point::hash_to_point()` hashes arbitrary data (with a domain
+//! separation tag) into the group, as specified in [RFC 9380].
+//!
+//! `Point::lizard_encode()` embeds 16 bytes of data into a group element
+//! (Lizard injection), and `Point::lizard_decode()` recovers them.
+//!
 //! [draft-irtf-cfrg-ristretto255-decaf448-07]: https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-ristretto255-decaf448
+//! [RFC 9380]: https://www.rfc-editor.org/rfc/rfc9380.html
 
 // Projective/fractional coordinates traditionally use uppercase letters,
 // using lowercase only for affine coordinates.
@@ -29,6 +52,15 @@
 use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use super::field::GF25519;
 use super::ed25519::{Point as Ed25519Point, Scalar as Ed25519Scalar};
+use super::xof::{Xof, expand_message_xof};
+use super::DecodeError;
+
+#[cfg(feature = "alloc")]
+use super::ed25519::PrecomputedTable as Ed25519PrecomputedTable;
+
+#[cfg(feature = "alloc")]
+use crate::Vec;
+use sha2::{Sha256, Sha512, Digest};
 
 /// A ristretto255 point.
 #[derive(Clone, Copy, Debug)]
@@ -37,6 +69,41 @@ pub struct Point(Ed25519Point);
 /// A ristretto255 scalar (integer modulo the group prime order `L`).
 pub type Scalar = Ed25519Scalar;
 
+/// Domain separation tag for generator derivation.
+const GENERATORS_DST: &[u8] = b"crrl-ristretto255-generators-v1";
+
+/// `expand_message_xmd` (RFC 9380, section 5.3.1) with SHA-512 and an
+/// output of 64 bytes; the message is provided as several parts (which
+/// are concatenated). The domain separation tag `dst` must have length
+/// at most 255 bytes (a panic is triggered otherwise).
+pub(crate) fn expand_message_xmd_sha512(data: &[&[u8]], dst: &[u8])
+    -> [u8; 64]
+{
+    assert!(dst.len() <= 255);
+    let mut sh = Sha512::new();
+
+    // b_0 = H(Z_pad || msg || l_i_b_str || I2OSP(0, 1) || DST_prime)
+    sh.update([0u8; 128]);
+    for d in data.iter() {
+        sh.update(d);
+    }
+    sh.update(64u16.to_be_bytes());
+    sh.update([0u8]);
+    sh.update(dst);
+    sh.update([dst.len() as u8]);
+    let b0 = sh.finalize_reset();
+
+    // b_1 = H(b_0 || I2OSP(1, 1) || DST_prime)
+    // Since SHA-512 has a 64-byte output, b_1 is the whole output.
+    sh.update(b0);
+    sh.update([1u8]);
+    sh.update(dst);
+    sh.update([dst.len() as u8]);
+    let mut b1 = [0u8; 64];
+    b1[..].copy_from_slice(&sh.finalize());
+    b1
+}
+
 impl Point {
 
     /// The neutral element (identity point) in the group.
@@ -95,53 +162,12 @@ impl Point {
         GF25519::select(&x, &-x, Self::is_negative(x))
     }
 
-    /// Square root of a ratio.
-    ///
-    /// This returns (r, x) such that:
-    ///
-    ///  - If u and v are non-zero, and u/v is a square, then
-    ///    r = 0xFFFFFFFF and x = sqrt(u/v).
-    ///
-    ///  - If u is zero, then r = 0xFFFFFFFF and x = 0 (regardless of
-    ///    the value of v).
-    ///
-    ///  - If u is non-zero but v is zero, then r = 0x00000000 and x = 0.
-    ///
-    ///  - If u and v are non-zero, and u/v is not a square, then
-    ///    r = 0x00000000 and x = sqrt(SQRT_M1*(u/v)).
-    ///
-    /// The sqrt() function returns the nonnegative square root of its
-    /// operand (as per `is_negative()`).
+    /// Square root of a ratio (see `GF25519::sqrt_ratio()`), with the
+    /// output order of the `SQRT_RATIO_M1()` function of the
+    /// specification: (was_square, x).
     fn sqrt_ratio_m1(u: GF25519, v: GF25519) -> (u32, GF25519) {
-        let v3 = v.square() * v;
-        let v7 = v3.square() * v;
-        let x = u * v7;
-
-        // Raise x to the power (p-5)/8 = 2^252 - 3.
-        let x2 = x.square() * x;
-        let x4 = x2.xsquare(2) * x2;
-        let x5 = x4.square() * x;
-        let x10 = x5.xsquare(5) * x5;
-        let x20 = x10.xsquare(10) * x10;
-        let x25 = x20.xsquare(5) * x5;
-        let x50 = x25.xsquare(25) * x25;
-        let x100 = x50.xsquare(50) * x50;
-        let x125 = x100.xsquare(25) * x25;
-        let x250 = x125.xsquare(125) * x125;
-        let x = x250.xsquare(2) * x;
-
-        let r = (u * v3) * x;
-        let c = v * r.square();
-        let correct_sign_sqrt   = c.equals(u);
-        let flipped_sign_sqrt   = c.equals(-u);
-        let flipped_sign_sqrt_i = c.equals(-u * Self::SQRT_M1);
-
-        let r_prime = r * Self::SQRT_M1;
-        let r = GF25519::select(&r, &r_prime,
-            flipped_sign_sqrt | flipped_sign_sqrt_i);
-        let r = Self::abs(r);
-
-        (correct_sign_sqrt | flipped_sign_sqrt, r)
+        let (x, r) = GF25519::sqrt_ratio(&u, &v);
+        (r, x)
     }
 
     /// Sets this element by decoding its binary representation.
@@ -202,6 +228,44 @@ impl Point {
         }
     }
 
+    /// Decodes an element from its binary representation, with detailed
+    /// error reporting.
+    ///
+    /// This accepts the same encodings as `decode()`. Failures are
+    /// reported as:
+    ///
+    ///  - `BadLength`: the input does not have length 32 bytes;
+    ///
+    ///  - `NonCanonicalField`: the encoded field element is not lower
+    ///    than p;
+    ///
+    ///  - `NonCanonicalEncoding`: the encoded field element is negative;
+    ///
+    ///  - `NotOnCurve`: the input is not the encoding of a group element
+    ///    (the square root computation failed, or the rebuilt point is
+    ///    not the canonical representative).
+    ///
+    /// This function is meant for diagnostics, e.g. to investigate
+    /// interoperability issues; the cause of a failure is computed with
+    /// variable-time code. `decode()` and `set_decode()` should be used
+    /// in production code.
+    pub fn decode_verbose(buf: &[u8]) -> Result<Self, DecodeError> {
+        if buf.len() != 32 {
+            return Err(DecodeError::BadLength);
+        }
+        if let Some(P) = Self::decode(buf) {
+            return Ok(P);
+        }
+        let (s, ok) = GF25519::decode32(buf);
+        if ok == 0 {
+            Err(DecodeError::NonCanonicalField)
+        } else if Self::is_negative(s) != 0 {
+            Err(DecodeError::NonCanonicalEncoding)
+        } else {
+            Err(DecodeError::NotOnCurve)
+        }
+    }
+
     /// Encodes this element into bytes.
     ///
     /// Encoding is always canonical.
@@ -235,6 +299,102 @@ impl Point {
         s.encode()
     }
 
+    /// Encodes many elements into bytes.
+    ///
+    /// The `points` and `out` slices MUST have the same length (a panic
+    /// is triggered otherwise); `out[i]` receives the encoding of
+    /// `points[i]` (same as `points[i].encode()`). Note that each
+    /// encoding inherently requires an inverse square root computation
+    /// (the encoded value is a square root which depends on the point),
+    /// and these cannot be shared across points. When the encodings of
+    /// the doubles of the points are needed, `double_and_encode_batch()`
+    /// is much faster, since it needs a single field inversion for the
+    /// whole batch.
+    pub fn encode_batch(points: &[Self], out: &mut [[u8; 32]]) {
+        assert!(points.len() == out.len());
+        for (P, d) in points.iter().zip(out.iter_mut()) {
+            *d = P.encode();
+        }
+    }
+
+    /// Encodes the double of this element into bytes.
+    ///
+    /// This returns the same value as `self.double().encode()`, but is
+    /// faster: for a doubled point, the encoding does not require a
+    /// square root computation, only an inversion.
+    pub fn double_and_encode(self) -> [u8; 32] {
+        let mut out = [[0u8; 32]; 1];
+        Self::double_and_encode_batch(&[self], &mut out);
+        out[0]
+    }
+
+    /// Encodes the doubles of many elements into bytes.
+    ///
+    /// The `points` and `out` slices MUST have the same length (a panic
+    /// is triggered otherwise); `out[i]` receives the encoding of
+    /// `points[i].double()`. Since encoding a doubled point requires only
+    /// an inversion (see `double_and_encode()`), the inversions for the
+    /// whole batch are mutualized with Montgomery's trick, and the cost
+    /// per point is only a few multiplications.
+    pub fn double_and_encode_batch(points: &[Self], out: &mut [[u8; 32]]) {
+        assert!(points.len() == out.len());
+
+        // Points are processed by chunks, to allow stack-based storage
+        // of the intermediate values.
+        const N: usize = 32;
+        for (pp, dd) in points.chunks(N).zip(out.chunks_mut(N)) {
+            let n = pp.len();
+
+            // The double of P = (X:Y:Z:T) is (e*f:g*h:f*g:e*h), with:
+            //   e = 2*X*Y
+            //   f = Z^2 + d*T^2
+            //   g = Y^2 + X^2
+            //   h = Z^2 - d*T^2
+            // and the encoding needs 1/(e*f*g*h).
+            let mut e = [GF25519::ZERO; N];
+            let mut f = [GF25519::ZERO; N];
+            let mut g = [GF25519::ZERO; N];
+            let mut h = [GF25519::ZERO; N];
+            let mut inv = [GF25519::ZERO; N];
+            for i in 0..n {
+                let (x0, y0, z0, t0) =
+                    (&pp[i].0.X, &pp[i].0.Y, &pp[i].0.Z, &pp[i].0.T);
+                let zz = z0.square();
+                let dtt = Self::D * t0.square();
+                e[i] = (x0 * y0).mul2();
+                f[i] = zz + dtt;
+                g[i] = y0.square() + x0.square();
+                h[i] = zz - dtt;
+                inv[i] = (e[i] * g[i]) * (f[i] * h[i]);
+            }
+
+            // Elements of value zero (for points whose double is the
+            // neutral) are left unchanged; the computations below then
+            // yield the encoding of the neutral (all-zeros).
+            GF25519::batch_invert(&mut inv[..n]);
+
+            for i in 0..n {
+                let eg = e[i] * g[i];
+                let fh = f[i] * h[i];
+                let z_inv = eg * inv[i];
+                let t_inv = fh * inv[i];
+
+                let rotate = Self::is_negative(eg * z_inv);
+                let e2 = GF25519::select(&e[i], &g[i], rotate);
+                let g2 = GF25519::select(&g[i], &-e[i], rotate);
+                let h2 = GF25519::select(&h[i], &(f[i] * Self::SQRT_M1),
+                    rotate);
+                let magic = GF25519::select(&Self::INVSQRT_A_MINUS_D,
+                    &Self::SQRT_M1, rotate);
+
+                let g2 = GF25519::select(&g2, &-g2,
+                    Self::is_negative(h2 * e2 * z_inv));
+                let s = Self::abs((h2 - g2) * (magic * g2 * t_inv));
+                dd[i] = s.encode();
+            }
+        }
+    }
+
     /// Compares two points for equality.
     ///
     /// Returned value is 0xFFFFFFFF if the two points are equal,
@@ -261,6 +421,36 @@ impl Point {
         x1.iszero() | y1.iszero()
     }
 
+    /// Multiplies this element by the cofactor of the group.
+    ///
+    /// This group has prime order (the cofactor is 1), so the element is
+    /// returned unchanged. This function is provided so that generic
+    /// code can clear cofactors uniformly across all curve modules.
+    #[inline(always)]
+    pub fn mul_by_cofactor(self) -> Self {
+        self
+    }
+
+    /// Multiplies this element by the group order.
+    ///
+    /// Since this group has prime order, the result is always the
+    /// neutral. This function is provided for uniformity with the curve
+    /// modules whose groups have a cofactor.
+    #[inline(always)]
+    pub fn mul_by_order(self) -> Self {
+        Self::NEUTRAL
+    }
+
+    /// Tests whether this element is in the subgroup of prime order.
+    ///
+    /// Since this group has prime order, 0xFFFFFFFF is always returned.
+    /// This function is provided for uniformity with the curve modules
+    /// whose groups have a cofactor.
+    #[inline(always)]
+    pub fn is_in_subgroup(self) -> u32 {
+        0xFFFFFFFF
+    }
+
     /// Conditionally copies the provided element (`P`) into `self`.
     ///
     ///  - If `ctl` = 0xFFFFFFFF, then the value of `P` is copied into `self`.
@@ -345,6 +535,245 @@ impl Point {
         Self::map(&b1) + Self::map(&b2)
     }
 
+    /// Maps 64 uniform bytes to a ristretto255 element.
+    ///
+    /// This is the same function as `one_way_map()` (named
+    /// `FROM_UNIFORM_BYTES` in RFC 9496), with the input length enforced
+    /// by the type. The computation is constant-time.
+    pub fn from_uniform_bytes(b: &[u8; 64]) -> Self {
+        Self::one_way_map(&b[..])
+    }
+
+    /// Hashes some data into a ristretto255 element, with a domain
+    /// separation tag.
+    ///
+    /// This is the `hash_to_ristretto255` process of RFC 9380 (appendix
+    /// B) with suite `ristretto255_XMD:SHA-512_R255MAP_RO_`: the data is
+    /// expanded into 64 bytes with `expand_message_xmd` (with SHA-512),
+    /// and the result is mapped with `from_uniform_bytes()`. The domain
+    /// separation tag `dst` must have length at most 255 bytes (a panic
+    /// is triggered otherwise). The computation is constant-time (with
+    /// regard to the data and tag contents).
+    pub fn hash_to_point(data: &[u8], dst: &[u8]) -> Self {
+        Self::hash_to_point_parts(&[data], dst)
+    }
+
+    /// Same as `hash_to_point()`, with the data provided as several
+    /// parts (which are concatenated).
+    fn hash_to_point_parts(data: &[&[u8]], dst: &[u8]) -> Self {
+        Self::from_uniform_bytes(&expand_message_xmd_sha512(data, dst))
+    }
+
+    /// Derives a generator from a label and an index.
+    ///
+    /// The generator is obtained with `hash_to_point()` over the
+    /// concatenation of `domain` and the index `i` (over 8 bytes,
+    /// little-endian), with the domain separation tag
+    /// `"crrl-ristretto255-generators-v1"`. Nobody knows the discrete
+    /// logarithm of the output relatively to any other generator
+    /// ("nothing up my sleeve"). This derivation process is fixed and
+    /// will not change in future versions.
+    pub fn derive_generator(domain: &[u8], i: u64) -> Self {
+        Self::hash_to_point_parts(&[domain, &i.to_le_bytes()],
+            GENERATORS_DST)
+    }
+
+    /// Gets an iterator over the generators derived from a label (see
+    /// `derive_generator()`), for indices 0, 1, 2...
+    pub fn generators(domain: &[u8]) -> Generators<'_> {
+        Generators { domain, index: 0 }
+    }
+
+    /// Derives `n` generators from a label (see `derive_generator()`),
+    /// for indices 0 to `n-1`.
+    ///
+    /// Note: this function is available only if heap allocation support
+    /// was configured.
+    #[cfg(feature = "alloc")]
+    pub fn derive_generators(domain: &[u8], n: usize) -> Vec<Self> {
+        Self::generators(domain).take(n).collect()
+    }
+
+    /// Hashes some data into a ristretto255 element, with a domain
+    /// separation tag and a caller-provided XOF.
+    ///
+    /// This is similar to `hash_to_point()`, except that the data is
+    /// expanded with `expand_message_xof` (RFC 9380, section 5.3.2)
+    /// over the provided XOF instance, which must be fresh (no data
+    /// absorbed yet).
+    pub fn hash_to_point_xof<X: Xof>(xof: X, data: &[u8], dst: &[u8])
+        -> Self
+    {
+        let mut b = [0u8; 64];
+        expand_message_xof(xof, data, dst, &mut b);
+        Self::from_uniform_bytes(&b)
+    }
+
+    // Constants for Lizard decoding.
+
+    // sqrt(i*d) (nonnegative root)
+    const SQRT_ID: GF25519 = GF25519::w64be(
+        0x05B35558F47BEB97,
+        0x7534549973EB6E15,
+        0xCDBCA92D0EEA6DAA,
+        0x75302ACB4A5C1BA8,
+    );
+    // (d + 1)/(d - 1)
+    const DP1_OVER_DM1: GF25519 = GF25519::w64be(
+        0x0E67C830A7E8D89D,
+        0xAEF8D95F999F1824,
+        0x489AE7F1CD241DC8,
+        0x277FAC5F9B81BB2C,
+    );
+    // 1/sqrt(1 + d)
+    const INVSQRT_ONE_PLUS_D: GF25519 = GF25519::w64be(
+        0x0A0D85B4032B1EA8,
+        0x1EF02C429405AC88,
+        0x4E52F71C6BDAB64E,
+        0x0B2EDB8831BBDDEC,
+    );
+    // -2*INVSQRT_A_MINUS_D
+    const MDOUBLE_INVSQRT_A_MINUS_D: GF25519 = GF25519::w64be(
+        0x0F26EDF460A006BB,
+        0xD27B08DC03FC4F7E,
+        0xC5A1D3D14B7D1A82,
+        0xCC6E04AAFF457E06,
+    );
+    // -2*i*INVSQRT_A_MINUS_D
+    const MIDOUBLE_INVSQRT_A_MINUS_D: GF25519 = GF25519::w64be(
+        0x141B0B6806563D50,
+        0x3DE05885280B5910,
+        0x9CA5EE38D7B56C9C,
+        0x165DB7106377BBD8,
+    );
+
+    /// Lizard encoding: embeds 16 bytes of data into a group element.
+    ///
+    /// This is the Lizard injection of Westerbaan (as implemented in the
+    /// `lizard` feature of curve25519-dalek, with SHA-256): the data is
+    /// placed in the middle of a field element, whose other bytes are
+    /// taken from SHA-256(data), and the field element is mapped into
+    /// the group with the ristretto255 MAP (elligator). Any 16-byte
+    /// value can be encoded, and `lizard_decode()` recovers it from the
+    /// resulting point. The computation is constant-time.
+    pub fn lizard_encode(data: &[u8; 16]) -> Self {
+        let mut fe: [u8; 32] = Sha256::digest(data).into();
+        fe[8..24].copy_from_slice(data);
+        fe[0] &= 0xFE;
+        fe[31] &= 0x3F;
+        Self::map(&fe)
+    }
+
+    /// Lizard decoding: recovers the 16 bytes of data embedded in this
+    /// element by `lizard_encode()`.
+    ///
+    /// All (up to eight) preimages of the point through the MAP are
+    /// computed, and checked for the structure enforced by
+    /// `lizard_encode()`. If exactly one preimage matches, then the
+    /// embedded data is returned. Otherwise (the point was not obtained
+    /// from `lizard_encode()`, or, with negligible probability, several
+    /// preimages match), `None` is returned. Apart from the `Option`
+    /// status, the computation is constant-time.
+    pub fn lizard_decode(self) -> Option<[u8; 16]> {
+        let mut res = [0u8; 16];
+        let mut n = 0u32;
+        for (s, t) in self.to_jacobi_quartic().iter() {
+            for (s, t) in [(*s, *t), (-s, -t)].iter() {
+                let (ok, fe) = Self::elligator_inv(*s, *t);
+                let buf = fe.encode();
+                let mut h: [u8; 32] = Sha256::digest(&buf[8..24]).into();
+                h[8..24].copy_from_slice(&buf[8..24]);
+                h[0] &= 0xFE;
+                h[31] &= 0x3F;
+                let good = ok & GF25519::decode_reduce(&h).equals(fe);
+                crate::ct::cond_copy(&mut res, &buf[8..24], good);
+                n += good & 1;
+            }
+        }
+        if n == 1 {
+            Some(res)
+        } else {
+            None
+        }
+    }
+
+    /// Computes the four points on the Jacobi quartic associated with
+    /// this element; together with their duals (-s, -t), they yield all
+    /// the MAP preimages of the element through `elligator_inv()`.
+    fn to_jacobi_quartic(self) -> [(GF25519, GF25519); 4] {
+        let (X, Y, Z) = (self.0.X, self.0.Y, self.0.Z);
+        let x2 = X.square();
+        let y2 = Y.square();
+        let y4 = y2.square();
+        let z2 = Z.square();
+        let z_min_y = Z - Y;
+        let z_pl_y = Z + Y;
+        let z2_min_y2 = z2 - y2;
+
+        // gamma = 1/sqrt(Y^4*X^2*(Z^2 - Y^2))
+        let (_, gamma) = Self::sqrt_ratio_m1(GF25519::ONE,
+            y4 * x2 * z2_min_y2);
+
+        let den = gamma * y2;
+        let s_over_x = den * z_min_y;
+        let sp_over_xp = den * z_pl_y;
+        let s0 = s_over_x * X;
+        let s1 = -(sp_over_xp * X);
+        let tmp = Self::MDOUBLE_INVSQRT_A_MINUS_D * Z;
+        let mut t0 = tmp * s_over_x;
+        let mut t1 = tmp * sp_over_xp;
+
+        // Same as above, with (X, Y, Z) replaced with (Y, X, i*Z).
+        let den = -z2_min_y2 * Self::INVSQRT_ONE_PLUS_D * gamma;
+        let iz = Self::SQRT_M1 * Z;
+        let s_over_y = den * (iz - X);
+        let sp_over_yp = den * (iz + X);
+        let mut s2 = s_over_y * Y;
+        let mut s3 = -(sp_over_yp * Y);
+        let tmp = Self::MDOUBLE_INVSQRT_A_MINUS_D * iz;
+        let mut t2 = tmp * s_over_y;
+        let mut t3 = tmp * sp_over_yp;
+
+        // Special case: if X = 0 or Y = 0, then all s_i and t_i are zero,
+        // and we use (0, 1), (1, -2*i/sqrt(a-d)) and (-1, -2*i/sqrt(a-d))
+        // instead.
+        let xy0 = X.iszero() | Y.iszero();
+        t0.set_cond(&GF25519::ONE, xy0);
+        t1.set_cond(&GF25519::ONE, xy0);
+        t2.set_cond(&Self::MIDOUBLE_INVSQRT_A_MINUS_D, xy0);
+        t3.set_cond(&Self::MIDOUBLE_INVSQRT_A_MINUS_D, xy0);
+        s2.set_cond(&GF25519::ONE, xy0);
+        s3.set_cond(&-GF25519::ONE, xy0);
+
+        [(s0, t0), (s1, t1), (s2, t2), (s3, t3)]
+    }
+
+    /// Inverse of the MAP, for a point (s, t) on the Jacobi quartic.
+    /// Returned value is (ok, r0), with ok = 0xFFFFFFFF if r0 is a
+    /// (nonnegative) preimage, 0x00000000 if there is no preimage.
+    fn elligator_inv(s: GF25519, t: GF25519) -> (u32, GF25519) {
+        // If s = 0, then t = 1 (preimage is sqrt(i*d)) or t = -1
+        // (preimage is 0).
+        let s_is_zero = s.iszero();
+        let mut out = GF25519::ZERO;
+        out.set_cond(&Self::SQRT_ID, t.equals(GF25519::ONE));
+        let mut ok = s_is_zero;
+        let mut done = s_is_zero;
+
+        let a = (t + GF25519::ONE) * Self::DP1_OVER_DM1;
+        let s2 = s.square();
+        let (sq, y) = Self::sqrt_ratio_m1(GF25519::ONE,
+            (s2.square() - a.square()) * Self::SQRT_M1);
+        ok |= sq;
+        done |= !sq;
+
+        // x = |(a + sign(s)*s^2)*y|
+        let pms2 = GF25519::select(&s2, &-s2, Self::is_negative(s));
+        let x = Self::abs((a + pms2) * y);
+        out.set_cond(&x, !done);
+        (ok, out)
+    }
+
     /// Adds `rhs` to `self`.
     #[inline(always)]
     fn set_add(&mut self, rhs: &Self) {
@@ -417,6 +846,15 @@ impl Point {
         Self(self.0.xdouble(n))
     }
 
+    /// Given scalars `u` and `v`, returns `u*self + v*B` (with `B` being
+    /// the conventional generator of the prime order subgroup).
+    ///
+    /// This operation is constant-time.
+    #[inline(always)]
+    pub fn mul_add_mulgen(self, u: &Scalar, v: &Scalar) -> Self {
+        Self(self.0.mul_add_mulgen(u, v))
+    }
+
     /// Given scalars `u` and `v`, returns `u*self + v*B` (with `B` being
     /// the conventional generator of the prime order subgroup).
     //
@@ -739,10 +1177,1180 @@ impl SubAssign<&Point> for Point {
 
 // ========================================================================
 
+/// Iterator over generators derived from a label.
+///
+/// This is returned by `Point::generators()`; it produces an unbounded
+/// sequence of elements.
+#[derive(Clone, Debug)]
+pub struct Generators<'a> {
+    domain: &'a [u8],
+    index: u64,
+}
+
+impl<'a> Iterator for Generators<'a> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        let P = Point::derive_generator(self.domain, self.index);
+        self.index += 1;
+        Some(P)
+    }
+}
+
+/// Precomputed tables for multi-scalar multiplications over a fixed set
+/// of ristretto255 elements.
+///
+/// This is a wrapper around `ed25519::PrecomputedTable`; see that type
+/// for details (including memory usage).
+///
+/// Note: this type is available only if heap allocation support was
+/// configured.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct PrecomputedTable(Ed25519PrecomputedTable);
+
+#[cfg(feature = "alloc")]
+impl PrecomputedTable {
+
+    /// Builds the tables for the provided elements, with a window of
+    /// `window_bits` bits (between 2 and 8, inclusive; a panic is
+    /// triggered otherwise).
+    pub fn new(points: &[Point], window_bits: u32) -> Self {
+        let pp: Vec<Ed25519Point> = points.iter().map(|P| P.0).collect();
+        Self(Ed25519PrecomputedTable::new(&pp, window_bits))
+    }
+
+    /// Gets the number of elements in this table.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Tests whether this table contains no element at all.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Computes `sum_i scalars[i]*P_i`, for the elements `P_i` used to
+    /// build this table. The number of scalars MUST be equal to the
+    /// number of elements (a panic is triggered otherwise).
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    pub fn mul_vartime(&self, scalars: &[Scalar]) -> Point {
+        Point(self.0.mul_vartime(scalars))
+    }
+}
+
+// ========================================================================
+
+/// Schnorr signatures over ristretto255.
+///
+/// This is a plain Schnorr signature scheme (not EdDSA) over the
+/// ristretto255 group. A signature is the pair `(R, s)`, encoded over
+/// 64 bytes (the encoding of `R`, then the encoding of `s`); it is
+/// valid for message `m` and public key `A` if `s*B = R + c*A`, where
+/// the challenge `c` is obtained by hashing (with SHA-512, and reduction
+/// of the output modulo `L`) the following values:
+///
+///  - the domain separation string `"crrl-ristretto255-schnorr-v1:c"`
+///  - the encoding of `R`
+///  - the encoding of `A`
+///  - the message `m`
+///
+/// Each value is prefixed with its length (over 8 bytes, little-endian),
+/// so that the framing is unambiguous. The per-signature secret `r`
+/// (with `R = r*B`) is derived deterministically from the private key,
+/// the message, and an optional seed; with a random seed
+/// (`sign_randomized()`), nonces are hedged: they remain safe even if
+/// the random source is flawed.
+///
+/// The hash process is fixed and pinned by test vectors; it will not
+/// change in future versions.
+///
+/// The `blind` submodule implements blind issuance of such signatures.
+pub mod schnorr {
+
+    use super::{Point, Scalar};
+    use crate::{CryptoRng, RngCore, ParseError};
+    use sha2::{Sha512, Digest};
+
+    /// Domain separation string for challenges.
+    const DST_CHALLENGE: &[u8] = b"crrl-ristretto255-schnorr-v1:c";
+
+    /// Domain separation string for nonce derivation.
+    const DST_NONCE: &[u8] = b"crrl-ristretto255-schnorr-v1:r";
+
+    /// A ristretto255 Schnorr private key.
+    ///
+    /// Such a key wraps around a secret non-zero scalar. It also contains
+    /// a copy of the public key.
+    #[derive(Clone, Copy)]
+    pub struct PrivateKey {
+        sec: Scalar,                // secret scalar
+        pub public_key: PublicKey,  // public key
+    }
+
+    /// A ristretto255 Schnorr public key.
+    ///
+    /// It wraps around a ristretto255 element, but also includes a copy of
+    /// the encoded element. The element and its encoded version can be
+    /// accessed directly; if modified, then the two values MUST match.
+    #[derive(Clone, Copy, Debug)]
+    pub struct PublicKey {
+        pub point: Point,
+        pub encoded: [u8; 32],
+    }
+
+    /// A decoded ristretto255 Schnorr signature.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Signature {
+        pub R: Point,
+        pub s: Scalar,
+    }
+
+    /// Injects a value into a hash, with its length as prefix.
+    fn frame(sh: &mut Sha512, data: &[u8]) {
+        sh.update((data.len() as u64).to_le_bytes());
+        sh.update(data);
+    }
+
+    /// Computes the challenge for a signature.
+    fn make_challenge(R_enc: &[u8; 32], A_enc: &[u8; 32], m: &[u8])
+        -> Scalar
+    {
+        let mut sh = Sha512::new();
+        frame(&mut sh, DST_CHALLENGE);
+        frame(&mut sh, R_enc);
+        frame(&mut sh, A_enc);
+        frame(&mut sh, m);
+        Scalar::decode_reduce(&sh.finalize())
+    }
+
+    impl PrivateKey {
+
+        /// Generates a new private key from a cryptographically secure RNG.
+        pub fn generate<T: CryptoRng + RngCore>(rng: &mut T) -> Self {
+            loop {
+                let mut tmp = [0u8; 64];
+                rng.fill_bytes(&mut tmp);
+                let sec = Scalar::decode_reduce(&tmp);
+                if sec.iszero() == 0 {
+                    return Self::from_scalar(&sec);
+                }
+            }
+        }
+
+        /// Instantiates a private key from a secret scalar.
+        ///
+        /// If the provided scalar is zero, then a panic is triggered.
+        pub fn from_scalar(sec: &Scalar) -> Self {
+            assert!(sec.iszero() == 0);
+            let point = Point::mulgen(sec);
+            let encoded = point.encode();
+            Self { sec: *sec, public_key: PublicKey { point, encoded } }
+        }
+
+        /// Instantiates a private key by decoding it from bytes.
+        ///
+        /// If the source bytes do not encode a correct private key (a
+        /// canonically encoded non-zero scalar, over exactly 32 bytes),
+        /// then None is returned.
+        pub fn decode(buf: &[u8]) -> Option<Self> {
+            let (sec, mut ok) = Scalar::decode32(buf);
+            ok &= !sec.iszero();
+            if ok != 0 {
+                Some(Self::from_scalar(&sec))
+            } else {
+                None
+            }
+        }
+
+        /// Encode a private key into bytes.
+        ///
+        /// This encodes the private scalar into exactly 32 bytes.
+        pub fn encode(self) -> [u8; 32] {
+            self.sec.encode()
+        }
+
+        /// Signs a message with this private key.
+        ///
+        /// This function uses a deterministic process to compute the
+        /// per-signature secret scalar. Signing the same message twice
+        /// with the same key yields the same signature.
+        pub fn sign(self, m: &[u8]) -> [u8; 64] {
+            self.sign_seeded(&[0u8; 0], m)
+        }
+
+        /// Signs a message with this private key.
+        ///
+        /// This function uses a randomized process to compute the
+        /// per-signature secret scalar. The provided `rng` is supposed to
+        /// be cryptographically secure (it implements the `CryptoRng`
+        /// trait) but signatures are still safe even if the `rng` turns
+        /// out to be flawed and entirely predictable.
+        pub fn sign_randomized<T: CryptoRng + RngCore>(self, rng: &mut T,
+            m: &[u8]) -> [u8; 64]
+        {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            self.sign_seeded(&seed, m)
+        }
+
+        /// Signs a message with this private key.
+        ///
+        /// This function uses a deterministic process to compute the
+        /// per-signature secret scalar. The provided `seed` is included
+        /// in that process. Having a varying seed (not necessarily secret
+        /// or random) improves resistance to fault attacks.
+        pub fn sign_seeded(self, seed: &[u8], m: &[u8]) -> [u8; 64] {
+            // r = SHA-512(dst || sec || A || seed || m) mod L
+            // (each value being length-prefixed)
+            let mut sh = Sha512::new();
+            frame(&mut sh, DST_NONCE);
+            frame(&mut sh, &self.sec.encode());
+            frame(&mut sh, &self.public_key.encoded);
+            frame(&mut sh, seed);
+            frame(&mut sh, m);
+            let r = Scalar::decode_reduce(&sh.finalize());
+
+            let R_enc = Point::mulgen(&r).encode();
+            let c = make_challenge(&R_enc, &self.public_key.encoded, m);
+            let s = r + c * self.sec;
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(&R_enc);
+            sig[32..].copy_from_slice(&s.encode());
+            sig
+        }
+    }
+
+    impl PublicKey {
+
+        /// Creates an instance from a ristretto255 element.
+        ///
+        /// A panic is triggered if the element is the neutral.
+        pub fn from_point(point: &Point) -> Self {
+            assert!(point.isneutral() == 0);
+            Self { point: *point, encoded: point.encode() }
+        }
+
+        /// Decodes the provided bytes as a public key.
+        ///
+        /// If the source slice does not have length exactly 32 bytes,
+        /// or the bytes do not encode a valid ristretto255 element, or
+        /// the bytes encode the neutral element, then the process fails
+        /// and this function returns `None`. Otherwise, the decoded
+        /// public key is returned.
+        pub fn decode(buf: &[u8]) -> Option<PublicKey> {
+            let point = Point::decode(buf)?;
+            if point.isneutral() != 0 {
+                None
+            } else {
+                let mut encoded = [0u8; 32];
+                encoded[..].copy_from_slice(&buf[0..32]);
+                Some(Self { point, encoded })
+            }
+        }
+
+        /// Encode this public key into exactly 32 bytes.
+        ///
+        /// This simply returns the contents of the `encoded` field.
+        pub fn encode(self) -> [u8; 32] {
+            self.encoded
+        }
+
+        /// Verifies a signature on a message against this public key.
+        ///
+        /// The signature must have length exactly 64 bytes, and both
+        /// halves must be canonical encodings (see `Signature::decode()`).
+        ///
+        /// Note: this function is not constant-time; it assumes that the
+        /// public key and signature value are public data.
+        pub fn verify(self, sig: &[u8], m: &[u8]) -> bool {
+            match Signature::decode(sig) {
+                Some(sig) => self.verify_raw(&sig.R, &sig.s, m),
+                None => false,
+            }
+        }
+
+        /// Verifies a signature, provided in decoded format, on a message
+        /// against this public key.
+        ///
+        /// Note: this function is not constant-time; it assumes that the
+        /// public key and signature value are public data.
+        pub fn verify_raw(self, R: &Point, s: &Scalar, m: &[u8]) -> bool {
+            let c = make_challenge(&R.encode(), &self.encoded, m);
+
+            // Check that s*B - c*A = R
+            (-self.point).mul_add_mulgen_vartime(&c, s).equals(*R) != 0
+        }
+    }
+
+    impl Signature {
+
+        /// Decodes a signature from bytes.
+        ///
+        /// The source must have length exactly 64 bytes; the first 32
+        /// bytes must be the canonical encoding of a ristretto255 element,
+        /// and the last 32 bytes the canonical encoding of a scalar. If
+        /// any of these conditions is not met, then `None` is returned.
+        pub fn decode(buf: &[u8]) -> Option<Self> {
+            if buf.len() != 64 {
+                return None;
+            }
+            let R = Point::decode(&buf[..32])?;
+            let (s, ok) = Scalar::decode32(&buf[32..]);
+            if ok == 0 {
+                return None;
+            }
+            Some(Self { R, s })
+        }
+
+        /// Encodes this signature into exactly 64 bytes.
+        pub fn encode(self) -> [u8; 64] {
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(&self.R.encode());
+            sig[32..].copy_from_slice(&self.s.encode());
+            sig
+        }
+    }
+
+    // The private key is not displayable, and its debug output does not
+    // include the secret scalar.
+    impl core::fmt::Debug for PrivateKey {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("PrivateKey")
+                .field("public_key", &self.public_key)
+                .finish_non_exhaustive()
+        }
+    }
+
+    // Public keys and signatures are displayed as the hexadecimal string
+    // of their encoding (32 and 64 bytes, respectively). Parsing applies
+    // the same checks as `decode()`.
+
+    impl core::fmt::Display for PublicKey {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            crate::hex::fmt_bytes(f, &self.encoded)
+        }
+    }
+
+    impl core::fmt::LowerHex for PublicKey {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            crate::hex::fmt_bytes(f, &self.encoded)
+        }
+    }
+
+    impl core::str::FromStr for PublicKey {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut buf = [0u8; 32];
+            crate::hex::parse_fixed(s, &mut buf)?;
+            let point = Point::decode_verbose(&buf)
+                .map_err(ParseError::BadPoint)?;
+            if point.isneutral() != 0 {
+                return Err(ParseError::NeutralPoint);
+            }
+            Ok(Self { point, encoded: buf })
+        }
+    }
+
+    impl core::fmt::Display for Signature {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            crate::hex::fmt_bytes(f, &self.encode())
+        }
+    }
+
+    impl core::fmt::LowerHex for Signature {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            crate::hex::fmt_bytes(f, &self.encode())
+        }
+    }
+
+    impl core::str::FromStr for Signature {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut buf = [0u8; 64];
+            crate::hex::parse_fixed(s, &mut buf)?;
+            let R = Point::decode_verbose(&buf[..32])
+                .map_err(ParseError::BadPoint)?;
+            let (s, ok) = Scalar::decode32(&buf[32..]);
+            if ok == 0 {
+                return Err(ParseError::ScalarOutOfRange);
+            }
+            Ok(Self { R, s })
+        }
+    }
+
+    /// Blind Schnorr signatures.
+    ///
+    /// This is the classic three-move blind signature protocol, which
+    /// yields ordinary Schnorr signatures (verifiable with
+    /// `PublicKey::verify()`); the signer does not learn the signed
+    /// message, and cannot link a signature to the session that produced
+    /// it. The flow is:
+    ///
+    ///  1. The signer calls `BlindSigner::start()`, obtaining a session
+    ///     and a commitment `R = k*B`, which is sent to the client.
+    ///
+    ///  2. The client calls `BlindingClient::new()` with the public key,
+    ///     `R` and the message; it picks blinding factors `(alpha, beta)`,
+    ///     computes `R' = R + alpha*B + beta*A` and the blinded challenge
+    ///     `c = H(R', A, m) + beta`, which is sent to the signer.
+    ///
+    ///  3. The signer calls `BlindSigner::respond()` with the session and
+    ///     the challenge, and sends back `s = k + c*a`.
+    ///
+    ///  4. The client calls `BlindingClient::finish()`, which checks the
+    ///     response and unblinds it into the signature `(R', s + alpha)`.
+    ///
+    /// Sessions on both sides are consumed when used, so that a nonce
+    /// cannot be used twice.
+    ///
+    /// **Security warning:** the security of blind Schnorr signatures
+    /// breaks down when many signing sessions are open concurrently: with
+    /// enough parallel sessions, the ROS attack (Benhamouda, Lepoint,
+    /// Loss, Orru and Raykova, 2021) lets a client obtain one more valid
+    /// signature than the number of completed sessions, in polynomial
+    /// time (about 256 concurrent sessions suffice for ristretto255, and
+    /// fewer sessions still enable subexponential attacks). The signer
+    /// therefore enforces a maximum number of open sessions, set when the
+    /// `BlindSigner` is created; applications should keep it as small as
+    /// possible, ideally 1 (fully sequential sessions).
+    pub mod blind {
+
+        use super::{PrivateKey, PublicKey, Point, Scalar, frame, make_challenge};
+        use crate::{CryptoRng, RngCore};
+        use sha2::{Sha512, Digest};
+
+        /// Domain separation string for signer nonce derivation.
+        const DST_BLIND_NONCE: &[u8] = b"crrl-ristretto255-schnorr-v1:blind-k";
+
+        /// Signer state for blind signatures.
+        ///
+        /// It contains the private key, and tracks the number of open
+        /// sessions.
+        #[derive(Debug)]
+        pub struct BlindSigner {
+            sk: PrivateKey,
+            max_sessions: usize,
+            open_sessions: usize,
+        }
+
+        /// An open signing session (signer side).
+        ///
+        /// It contains the secret nonce `k`; it is consumed by
+        /// `BlindSigner::respond()` or `BlindSigner::abort()`, and
+        /// cannot be cloned.
+        pub struct SignerSession {
+            k: Scalar,
+        }
+
+        impl core::fmt::Debug for SignerSession {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>)
+                -> core::fmt::Result
+            {
+                f.debug_struct("SignerSession").finish_non_exhaustive()
+            }
+        }
+
+        /// Client state for a blind signature session.
+        ///
+        /// It contains the blinding factors and the values needed to
+        /// check and unblind the signer's response. It is consumed by
+        /// `BlindingClient::finish()`, and cannot be cloned.
+        #[derive(Debug)]
+        pub struct BlindingClient {
+            alpha: Scalar,
+            c: Scalar,
+            R: Point,
+            R_enc: [u8; 32],
+            pk: PublicKey,
+        }
+
+        /// Gets a uniformly random scalar from the RNG.
+        fn random_scalar<T: CryptoRng + RngCore>(rng: &mut T) -> Scalar {
+            let mut tmp = [0u8; 64];
+            rng.fill_bytes(&mut tmp);
+            Scalar::decode_reduce(&tmp)
+        }
+
+        impl BlindSigner {
+
+            /// Creates a new signer, allowing at most `max_sessions`
+            /// sessions to be open at the same time. `max_sessions` MUST
+            /// be at least 1 (a panic is triggered otherwise); see the
+            /// module documentation for the risks of concurrent sessions.
+            pub fn new(sk: PrivateKey, max_sessions: usize) -> Self {
+                assert!(max_sessions >= 1);
+                Self { sk, max_sessions, open_sessions: 0 }
+            }
+
+            /// Gets the public key.
+            pub fn public_key(&self) -> PublicKey {
+                self.sk.public_key
+            }
+
+            /// Gets the number of currently open sessions.
+            pub fn open_sessions(&self) -> usize {
+                self.open_sessions
+            }
+
+            /// Opens a new session, returning the session and the
+            /// encoded commitment `R` (to send to the client).
+            ///
+            /// The nonce is derived from the private key and 32 bytes
+            /// obtained from `rng`, so that it remains unpredictable even
+            /// if `rng` is flawed. If the maximum number of open sessions
+            /// has been reached, then `None` is returned.
+            pub fn start<T: CryptoRng + RngCore>(&mut self, rng: &mut T)
+                -> Option<(SignerSession, [u8; 32])>
+            {
+                if self.open_sessions >= self.max_sessions {
+                    return None;
+                }
+                let mut seed = [0u8; 32];
+                rng.fill_bytes(&mut seed);
+                let mut sh = Sha512::new();
+                frame(&mut sh, DST_BLIND_NONCE);
+                frame(&mut sh, &self.sk.sec.encode());
+                frame(&mut sh, &seed);
+                let k = Scalar::decode_reduce(&sh.finalize());
+                self.open_sessions += 1;
+                Some((SignerSession { k }, Point::mulgen(&k).encode()))
+            }
+
+            /// Answers the client's blinded challenge, which closes the
+            /// session. The response `s` (32 bytes) is returned.
+            ///
+            /// If the challenge is not the canonical encoding of a
+            /// scalar, then `None` is returned (the session is closed
+            /// nonetheless).
+            pub fn respond(&mut self, session: SignerSession, c: &[u8])
+                -> Option<[u8; 32]>
+            {
+                self.open_sessions -= 1;
+                let (c, ok) = Scalar::decode32(c);
+                if ok == 0 {
+                    return None;
+                }
+                Some((session.k + c * self.sk.sec).encode())
+            }
+
+            /// Closes a session without answering it.
+            pub fn abort(&mut self, _session: SignerSession) {
+                self.open_sessions -= 1;
+            }
+        }
+
+        impl BlindingClient {
+
+            /// Starts the client side of a session, for the signer public
+            /// key `pk` and commitment `R` (encoded), and message `m`.
+            /// Blinding factors are obtained from `rng`, which MUST be
+            /// cryptographically secure (unlinkability relies on it).
+            ///
+            /// Returned values are the client state, and the encoded
+            /// blinded challenge (to send to the signer). If `R` cannot
+            /// be decoded, then `None` is returned.
+            pub fn new<T: CryptoRng + RngCore>(rng: &mut T, pk: &PublicKey,
+                R: &[u8], m: &[u8]) -> Option<(Self, [u8; 32])>
+            {
+                let R = Point::decode(R)?;
+                let alpha = random_scalar(rng);
+                let beta = random_scalar(rng);
+                let Rb = R + Point::mulgen(&alpha) + pk.point * beta;
+                let R_enc = Rb.encode();
+                let c = make_challenge(&R_enc, &pk.encoded, m) + beta;
+                Some((Self { alpha, c, R, R_enc, pk: *pk }, c.encode()))
+            }
+
+            /// Finishes the session with the signer's response `s`,
+            /// returning the (unblinded) signature.
+            ///
+            /// The response is verified; if it is invalid (e.g. the
+            /// signer did not answer the challenge that was sent), then
+            /// `None` is returned.
+            pub fn finish(self, s: &[u8]) -> Option<[u8; 64]> {
+                let (s, ok) = Scalar::decode32(s);
+                if ok == 0 {
+                    return None;
+                }
+                // Check that s*B - c*A = R
+                let R2 = (-self.pk.point).mul_add_mulgen_vartime(&self.c, &s);
+                if R2.equals(self.R) == 0 {
+                    return None;
+                }
+                let mut sig = [0u8; 64];
+                sig[..32].copy_from_slice(&self.R_enc);
+                sig[32..].copy_from_slice(&(s + self.alpha).encode());
+                Some(sig)
+            }
+        }
+
+        #[cfg(all(test, feature = "chacha20"))]
+        mod tests {
+
+            use super::{BlindSigner, BlindingClient};
+            use super::super::{PrivateKey, Signature};
+            use crate::chacha20::ChaChaRng;
+            use crate::SeedableRng;
+
+            #[test]
+            fn issue_verify() {
+                let mut rng = ChaChaRng::from_seed([0x42u8; 32]);
+                let sk = PrivateKey::generate(&mut rng);
+                let pk = sk.public_key;
+                let mut signer = BlindSigner::new(sk, 4);
+                let mut Rs = [[0u8; 32]; 8];
+                let mut sigs = [[0u8; 64]; 8];
+                for i in 0..8 {
+                    let msg = [i as u8 >> 1];
+                    let (ss, R) = signer.start(&mut rng).unwrap();
+                    let (cl, c) = BlindingClient::new(&mut rng, &pk, &R, &msg)
+                        .unwrap();
+                    let s = signer.respond(ss, &c).unwrap();
+                    let sig = cl.finish(&s).unwrap();
+                    assert!(pk.verify(&sig, &msg));
+                    assert!(!pk.verify(&sig, &[0xFF]));
+                    assert!(signer.open_sessions() == 0);
+                    Rs[i] = R;
+                    sigs[i] = sig;
+                }
+
+                // The signatures are unlinkable to the sessions: the
+                // commitments in the signatures are not the ones produced
+                // by the signer, and two signatures of the same message
+                // are distinct.
+                for i in 0..8 {
+                    let Rsig = Signature::decode(&sigs[i]).unwrap().R.encode();
+                    for j in 0..8 {
+                        assert!(Rsig != Rs[j]);
+                        if i != j {
+                            assert!(sigs[i][..32] != sigs[j][..32]);
+                        }
+                    }
+                }
+            }
+
+            #[test]
+            fn tampering() {
+                let mut rng = ChaChaRng::from_seed([0x43u8; 32]);
+                let sk = PrivateKey::generate(&mut rng);
+                let pk = sk.public_key;
+                let mut signer = BlindSigner::new(sk, 1);
+
+                // The challenge is modified before it reaches the signer:
+                // the response does not match, and is rejected.
+                let (ss, R) = signer.start(&mut rng).unwrap();
+                let (cl, mut c) = BlindingClient::new(&mut rng, &pk, &R, b"m")
+                    .unwrap();
+                c[0] ^= 0x01;
+                let s = signer.respond(ss, &c).unwrap();
+                assert!(cl.finish(&s).is_none());
+
+                // A modified response is rejected.
+                let (ss, R) = signer.start(&mut rng).unwrap();
+                let (cl, c) = BlindingClient::new(&mut rng, &pk, &R, b"m")
+                    .unwrap();
+                let mut s = signer.respond(ss, &c).unwrap();
+                s[0] ^= 0x01;
+                assert!(cl.finish(&s).is_none());
+
+                // Non-canonical challenges and invalid commitments.
+                let (ss, R) = signer.start(&mut rng).unwrap();
+                assert!(signer.respond(ss, &[0xFFu8; 32]).is_none());
+                assert!(signer.open_sessions() == 0);
+                let mut R2 = R;
+                R2[31] |= 0x80;
+                assert!(BlindingClient::new(&mut rng, &pk, &R2, b"m").is_none());
+            }
+
+            #[test]
+            fn session_limit() {
+                let mut rng = ChaChaRng::from_seed([0x44u8; 32]);
+                let sk = PrivateKey::generate(&mut rng);
+                let mut signer = BlindSigner::new(sk, 2);
+                let (ss1, _) = signer.start(&mut rng).unwrap();
+                let (ss2, R2) = signer.start(&mut rng).unwrap();
+                assert!(signer.start(&mut rng).is_none());
+                assert!(signer.open_sessions() == 2);
+                signer.abort(ss1);
+                let (ss3, _) = signer.start(&mut rng).unwrap();
+                assert!(signer.start(&mut rng).is_none());
+
+                // Closing a session with a response also frees a slot.
+                let pk = signer.public_key();
+                let (cl, c) = BlindingClient::new(&mut rng, &pk, &R2, b"m")
+                    .unwrap();
+                let s = signer.respond(ss2, &c).unwrap();
+                assert!(pk.verify(&cl.finish(&s).unwrap(), b"m"));
+                assert!(signer.open_sessions() == 1);
+                signer.abort(ss3);
+                assert!(signer.open_sessions() == 0);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::{PrivateKey, PublicKey, Signature};
+        use super::super::{Point, Scalar};
+
+        #[test]
+        fn sign_verify() {
+            // Golden vectors: (private key, message, signature). They pin
+            // the signature generation process (deterministic nonces).
+            const KAT: [(&str, &str, &str); 3] = [
+                ("90b750ab1869c8970e4f0cec68d63a5eaa73dfdd104e92f22c6f46ba86b4ba06",
+                 "",
+                 "6e9d6f400e2d38815201d479623cfc635337c21cac8b23e904aca3306f2cb55fd95d23276def7b0e767a55b7bb15439f80a7ae97178610f60bb3c4e1cfaee10d"),
+                ("8ff9f4ed6f9ee4a20dc3e382b3c531a8db61d947205786407949de126d36a401",
+                 "616263",
+                 "c056f21c8a0978b284cff485056fe67e6d413811edc958ff3a93445ea4668f1054ea778d03561a8af54925d57b2d21b92c3f5143d6c4a9c11cc5ae1309ce5803"),
+                ("fd57eccdf64ba04ba6c70357976797231307edb89fa4ec2334fc87dada3f3f0a",
+                 "54686520717569636b2062726f776e20666f78206a756d7073206f76657220746865206c617a7920646f67",
+                 "e4519052c0ee72778f5a0febf935ecdfa7efddc795aae5dfefbab0cc43218236f5cb8ddb0c2f855b897197ffaccf4f39fa5266386ba11587385a30308fcaff02"),
+            ];
+            for (skh, mh, sigh) in KAT.iter() {
+                let skey = PrivateKey::decode(&hex::decode(skh).unwrap()).unwrap();
+                let pkey = PublicKey::decode(&skey.public_key.encode()).unwrap();
+                let m = hex::decode(mh).unwrap();
+                let sig = skey.sign(&m);
+                assert!(sig[..] == hex::decode(sigh).unwrap()[..]);
+                assert!(pkey.verify(&sig, &m));
+                assert!(!pkey.verify(&sig, b"other"));
+                assert!(!pkey.verify(&sig[..63], &m));
+
+                let dsig = Signature::decode(&sig).unwrap();
+                assert!(dsig.encode() == sig);
+                assert!(pkey.verify_raw(&dsig.R, &dsig.s, &m));
+                assert!(!pkey.verify_raw(&dsig.R, &(dsig.s + Scalar::ONE), &m));
+
+                // Non-canonical s is rejected.
+                let mut sig2 = sig;
+                let L_enc = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010").unwrap();
+                let mut cc = 0u32;
+                for i in 0..32 {
+                    let z = (sig2[32 + i] as u32) + (L_enc[i] as u32) + cc;
+                    sig2[32 + i] = z as u8;
+                    cc = z >> 8;
+                }
+                assert!(Signature::decode(&sig2).is_none());
+                assert!(!pkey.verify(&sig2, &m));
+            }
+
+            // Neutral and zero keys are rejected.
+            assert!(PublicKey::decode(&Point::NEUTRAL.encode()).is_none());
+            assert!(PrivateKey::decode(&[0u8; 32]).is_none());
+        }
+
+        #[cfg(feature = "chacha20")]
+        #[test]
+        fn sign_hedged() {
+            use crate::chacha20::ChaChaRng;
+            use crate::SeedableRng;
+
+            // Hedged signatures differ, but are all valid.
+            let mut rng = ChaChaRng::from_seed([0u8; 32]);
+            for i in 0..10 {
+                let skey = PrivateKey::generate(&mut rng);
+                let pkey = skey.public_key;
+                let m = [i as u8; 5];
+                let sig = skey.sign(&m);
+                let sig3 = skey.sign_randomized(&mut rng, &m);
+                let sig4 = skey.sign_randomized(&mut rng, &m);
+                assert!(sig3 != sig4 && sig3 != sig);
+                assert!(sig3[32..] != sig4[32..]);
+                assert!(pkey.verify(&sig3, &m));
+                assert!(pkey.verify(&sig4, &m));
+            }
+        }
+
+        #[cfg(feature = "chacha20")]
+        #[test]
+        fn hex_strings() {
+            use crate::chacha20::ChaChaRng;
+            use crate::{DecodeError, ParseError, SeedableRng, Vec};
+
+            let mut rng = ChaChaRng::from_seed([7u8; 32]);
+            for i in 0..10 {
+                let skey = PrivateKey::generate(&mut rng);
+                let pkey = skey.public_key;
+                let str = format!("{}", pkey);
+                assert!(str == hex::encode(pkey.encode()));
+                assert!(format!("{:x}", pkey) == str);
+                let pk2: PublicKey = str.parse().unwrap();
+                assert!(pk2.encoded == pkey.encoded);
+
+                let m = [i as u8; 5];
+                let sig = skey.sign(&m);
+                let dsig = Signature::decode(&sig).unwrap();
+                let str = format!("{}", dsig);
+                assert!(str == hex::encode(sig));
+                assert!(format!("{:#x}", dsig) == format!("0x{}", str));
+                let sig2: Signature = str.parse().unwrap();
+                assert!(sig2.encode() == sig);
+
+                // The secret scalar does not appear in the debug output.
+                let dbg = format!("{:?}", skey);
+                assert!(dbg.starts_with("PrivateKey"));
+                assert!(!dbg.contains(&format!("{:?}", skey.sec)));
+
+                // Signature with s = s + L.
+                let L_enc = "edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010";
+                let s_plus_L = dsig.s.encode().iter().zip(hex::decode(L_enc).unwrap())
+                    .scan(0u32, |cc, (a, b)| {
+                        let z = (*a as u32) + (b as u32) + *cc;
+                        *cc = z >> 8;
+                        Some(z as u8)
+                    }).collect::<Vec<u8>>();
+                let bad = format!("{}{}", hex::encode(&sig[..32]), hex::encode(s_plus_L));
+                assert!(bad.parse::<Signature>().err()
+                    == Some(ParseError::ScalarOutOfRange));
+                assert!(str[..64].parse::<Signature>().err()
+                    == Some(ParseError::BadLength));
+            }
+
+            // Invalid and neutral public keys.
+            let neutral = hex::encode(Point::NEUTRAL.encode());
+            assert!(neutral.parse::<PublicKey>().err()
+                == Some(ParseError::NeutralPoint));
+            assert!("01".repeat(32).parse::<PublicKey>().err()
+                == Some(ParseError::BadPoint(DecodeError::NonCanonicalEncoding)));
+            assert!("zz".repeat(32).parse::<PublicKey>().err()
+                == Some(ParseError::BadHex));
+        }
+    }
+}
+
+// ========================================================================
+
+/// Proofs of discrete logarithm equality (DLEQ) over ristretto255.
+///
+/// A proof shows, in zero knowledge, that `A = x*G` and `B = x*H` for
+/// the same secret scalar `x` (i.e. `log_G(A) = log_H(B)`), without
+/// revealing `x`. This is the Chaum-Pedersen protocol, made
+/// non-interactive with the Fiat-Shamir transform: the prover computes
+/// `R1 = r*G` and `R2 = r*H` for a per-proof secret `r`, then the
+/// challenge `c` by hashing (with SHA-512, and reduction of the output
+/// modulo `L`) the following values:
+///
+///  - the domain separation string `"crrl-ristretto255-dleq-v1:c"`
+///  - the encodings of `G`, `H`, `A` and `B`
+///  - the encodings of `R1` and `R2`
+///
+/// Each value is prefixed with its length (over 8 bytes, little-endian).
+/// The proof is `(c, s)` with `s = r - c*x`, encoded over 64 bytes (`c`
+/// then `s`); the verifier recomputes `R1 = s*G + c*A` and
+/// `R2 = s*H + c*B`, then the challenge. As with Schnorr signatures (see
+/// the `schnorr` module), `r` is derived deterministically from the
+/// secret, the statement, and an optional seed (hedged with a random
+/// seed in `prove_randomized()`).
+///
+/// The hash process is fixed and pinned by test vectors; it will not
+/// change in future versions.
+pub mod dleq {
+
+    use super::{Point, Scalar};
+    use crate::{CryptoRng, RngCore};
+    use sha2::{Sha512, Digest};
+
+    #[cfg(feature = "alloc")]
+    use super::PrecomputedTable;
+
+    /// Domain separation string for challenges.
+    const DST_CHALLENGE: &[u8] = b"crrl-ristretto255-dleq-v1:c";
+
+    /// Domain separation string for nonce derivation.
+    const DST_NONCE: &[u8] = b"crrl-ristretto255-dleq-v1:r";
+
+    /// A DLEQ proof.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Proof {
+        pub c: Scalar,
+        pub s: Scalar,
+    }
+
+    /// Injects a value into a hash, with its length as prefix.
+    fn frame(sh: &mut Sha512, data: &[u8]) {
+        sh.update((data.len() as u64).to_le_bytes());
+        sh.update(data);
+    }
+
+    /// Computes the challenge for a proof.
+    fn make_challenge(G: &Point, H: &Point, A: &Point, B: &Point,
+        R1: &Point, R2: &Point) -> Scalar
+    {
+        let mut sh = Sha512::new();
+        frame(&mut sh, DST_CHALLENGE);
+        for P in [G, H, A, B, R1, R2] {
+            frame(&mut sh, &P.encode());
+        }
+        Scalar::decode_reduce(&sh.finalize())
+    }
+
+    /// Proves that `x*G` and `x*H` have the same discrete logarithm
+    /// (`x`) relatively to `G` and `H`, respectively.
+    ///
+    /// This function uses a deterministic process to compute the
+    /// per-proof secret scalar: proving the same statement twice yields
+    /// the same proof.
+    pub fn prove(x: &Scalar, G: &Point, H: &Point) -> Proof {
+        prove_seeded(x, G, H, &[0u8; 0])
+    }
+
+    /// Proves that `x*G` and `x*H` have the same discrete logarithm.
+    ///
+    /// This function uses a randomized process to compute the per-proof
+    /// secret scalar. The provided `rng` is supposed to be
+    /// cryptographically secure, but proofs remain safe even if the
+    /// `rng` turns out to be flawed and entirely predictable.
+    pub fn prove_randomized<T: CryptoRng + RngCore>(rng: &mut T,
+        x: &Scalar, G: &Point, H: &Point) -> Proof
+    {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        prove_seeded(x, G, H, &seed)
+    }
+
+    /// Proves that `x*G` and `x*H` have the same discrete logarithm.
+    ///
+    /// This function uses a deterministic process to compute the
+    /// per-proof secret scalar. The provided `seed` is included in that
+    /// process. Having a varying seed (not necessarily secret or random)
+    /// improves resistance to fault attacks.
+    pub fn prove_seeded(x: &Scalar, G: &Point, H: &Point, seed: &[u8])
+        -> Proof
+    {
+        let A = G * x;
+        let B = H * x;
+
+        // r = SHA-512(dst || x || G || H || A || B || seed) mod L
+        // (each value being length-prefixed)
+        let mut sh = Sha512::new();
+        frame(&mut sh, DST_NONCE);
+        frame(&mut sh, &x.encode());
+        for P in [G, H, &A, &B] {
+            frame(&mut sh, &P.encode());
+        }
+        frame(&mut sh, seed);
+        let r = Scalar::decode_reduce(&sh.finalize());
+
+        let c = make_challenge(G, H, &A, &B, &(G * r), &(H * r));
+        Proof { c, s: r - c * x }
+    }
+
+    /// Verifies a proof that `log_G(A) = log_H(B)`.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// proof and the points are public data.
+    pub fn verify(proof: &Proof, G: &Point, A: &Point, H: &Point, B: &Point)
+        -> bool
+    {
+        let R1 = G * proof.s + A * proof.c;
+        let R2 = H * proof.s + B * proof.c;
+        make_challenge(G, H, A, B, &R1, &R2).equals(proof.c) != 0
+    }
+
+    /// Verifies many proofs over the same `G` and `H`.
+    ///
+    /// Each entry in `items` is a proof and the corresponding points `A`
+    /// and `B`. The tables for `G` and `H` are computed only once for
+    /// the whole batch, which makes this function faster than calling
+    /// `verify()` for each proof. Returned value is `true` only if all
+    /// proofs are valid; `true` is returned for an empty batch.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// proofs and the points are public data. It is available only if
+    /// heap allocation support was configured.
+    #[cfg(feature = "alloc")]
+    pub fn verify_batch(G: &Point, H: &Point, items: &[(Proof, Point, Point)])
+        -> bool
+    {
+        if items.is_empty() {
+            return true;
+        }
+        let tab = PrecomputedTable::new(&[*G, *H], 5);
+        items.iter().all(|(proof, A, B)| {
+            let R1 = tab.mul_vartime(&[proof.s, Scalar::ZERO]) + A * proof.c;
+            let R2 = tab.mul_vartime(&[Scalar::ZERO, proof.s]) + B * proof.c;
+            make_challenge(G, H, A, B, &R1, &R2).equals(proof.c) != 0
+        })
+    }
+
+    impl Proof {
+
+        /// Decodes a proof from bytes.
+        ///
+        /// The source must have length exactly 64 bytes, and contain the
+        /// canonical encodings of two scalars. If any of these conditions
+        /// is not met, then `None` is returned.
+        pub fn decode(buf: &[u8]) -> Option<Self> {
+            if buf.len() != 64 {
+                return None;
+            }
+            let (c, ok1) = Scalar::decode32(&buf[..32]);
+            let (s, ok2) = Scalar::decode32(&buf[32..]);
+            if (ok1 & ok2) == 0 {
+                return None;
+            }
+            Some(Self { c, s })
+        }
+
+        /// Encodes this proof into exactly 64 bytes.
+        pub fn encode(self) -> [u8; 64] {
+            let mut buf = [0u8; 64];
+            buf[..32].copy_from_slice(&self.c.encode());
+            buf[32..].copy_from_slice(&self.s.encode());
+            buf
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::{Proof, prove, prove_seeded, verify};
+        use super::super::{Point, Scalar};
+        use sha2::{Sha512, Digest};
+
+        #[cfg(feature = "alloc")]
+        use super::verify_batch;
+        #[cfg(feature = "alloc")]
+        use crate::Vec;
+
+        // Test statement: x and h are SHA-512("dleq x") and
+        // SHA-512("dleq h"), reduced modulo L; G is the base point, and
+        // H = h*G.
+        fn statement() -> (Scalar, Point, Point) {
+            let x = Scalar::decode_reduce(&Sha512::digest(b"dleq x"));
+            let h = Scalar::decode_reduce(&Sha512::digest(b"dleq h"));
+            (x, Point::BASE, Point::mulgen(&h))
+        }
+
+        #[test]
+        fn kat() {
+            // Values computed with an independent implementation.
+            const H_ENC: &str = "72c3f36e32881627677ba4c3c46313b8a06cd906af89d86638588d889e0f5f06";
+            const A_ENC: &str = "d4162488112e0b60f0df6b0898a7880cebb1fb6a486a8ef9ef666631405b9443";
+            const B_ENC: &str = "b64fef9d412b2894b3ecd6e5372f4bb88e1e7041439274db07add4fbb0f7d818";
+            const PROOF: &str = "efb99226adb5e187283ce7a2bcdaab046806fe493ea6d0d8626341c1b452320a009bb0cf385fbcb726936006028f1a447c4fc18e4171e341af84317853cca401";
+            const PROOF_SEEDED: &str = "2bb6a71fc5c8f43ef9bc5d264530854fd62d17e8b3b97592ab55e816d3576e01cf3b35ba87e19dce407bb472ddc8dd0ceb14ec06f14d819c65b47e7c73ac420f";
+
+            let (x, G, H) = statement();
+            assert!(H.encode()[..] == hex::decode(H_ENC).unwrap()[..]);
+            let A = G * x;
+            let B = H * x;
+            assert!(A.encode()[..] == hex::decode(A_ENC).unwrap()[..]);
+            assert!(B.encode()[..] == hex::decode(B_ENC).unwrap()[..]);
+
+            let proof = prove(&x, &G, &H);
+            assert!(proof.encode()[..] == hex::decode(PROOF).unwrap()[..]);
+            assert!(verify(&proof, &G, &A, &H, &B));
+            let proof = prove_seeded(&x, &G, &H, b"seed");
+            assert!(proof.encode()[..]
+                == hex::decode(PROOF_SEEDED).unwrap()[..]);
+            let proof2 = Proof::decode(&proof.encode()).unwrap();
+            assert!(verify(&proof2, &G, &A, &H, &B));
+        }
+
+        #[test]
+        fn prove_verify() {
+            let (x, G, H) = statement();
+            let A = G * x;
+            let B = H * x;
+            let proof = prove(&x, &G, &H);
+            assert!(verify(&proof, &G, &A, &H, &B));
+
+            // Changing any of the four points makes verification fail.
+            let P = Point::mulgen(&Scalar::from_u32(5));
+            assert!(!verify(&proof, &P, &A, &H, &B));
+            assert!(!verify(&proof, &G, &P, &H, &B));
+            assert!(!verify(&proof, &G, &A, &P, &B));
+            assert!(!verify(&proof, &G, &A, &H, &P));
+            assert!(!verify(&proof, &H, &B, &G, &A));
+
+            // A proof for distinct logarithms is rejected.
+            let B2 = H * (x + Scalar::ONE);
+            assert!(!verify(&proof, &G, &A, &H, &B2));
+
+            // Modified proof values are rejected.
+            let mut bad = proof;
+            bad.c += Scalar::ONE;
+            assert!(!verify(&bad, &G, &A, &H, &B));
+            let mut bad = proof;
+            bad.s += Scalar::ONE;
+            assert!(!verify(&bad, &G, &A, &H, &B));
+
+            // Decoding enforces length and canonicality.
+            let enc = proof.encode();
+            assert!(Proof::decode(&enc[..63]).is_none());
+            let mut enc2 = enc;
+            enc2[31] = 0xFF;
+            assert!(Proof::decode(&enc2).is_none());
+            let mut enc2 = enc;
+            enc2[63] = 0xFF;
+            assert!(Proof::decode(&enc2).is_none());
+        }
+
+        #[cfg(feature = "chacha20")]
+        #[test]
+        fn randomized() {
+            use super::prove_randomized;
+            use crate::chacha20::ChaChaRng;
+            use crate::SeedableRng;
+
+            // Randomized proofs differ from each other and from the
+            // deterministic proof, but are all valid.
+            let (x, G, H) = statement();
+            let A = G * x;
+            let B = H * x;
+            let mut rng = ChaChaRng::from_seed([0u8; 32]);
+            let proof = prove_randomized(&mut rng, &x, &G, &H);
+            assert!(verify(&proof, &G, &A, &H, &B));
+            let proof2 = prove_randomized(&mut rng, &x, &G, &H);
+            assert!(proof.encode() != proof2.encode());
+            assert!(proof.encode() != prove(&x, &G, &H).encode());
+            assert!(verify(&proof2, &G, &A, &H, &B));
+            assert!(!verify(&proof, &G, &A, &H, &(H * (x + Scalar::ONE))));
+        }
+
+        #[cfg(feature = "alloc")]
+        #[test]
+        fn batch() {
+            let (_, G, H) = statement();
+            let mut items = Vec::new();
+            for i in 0..10u64 {
+                let x = Scalar::decode_reduce(
+                    &Sha512::digest(&i.to_le_bytes()));
+                let proof = prove(&x, &G, &H);
+                items.push((proof, G * x, H * x));
+            }
+            assert!(verify_batch(&G, &H, &items));
+            assert!(verify_batch(&G, &H, &[]));
+            assert!(!verify_batch(&H, &G, &items));
+            for i in 0..items.len() {
+                let mut items2 = items.clone();
+                items2[i].2 = items[(i + 1) % items.len()].2;
+                assert!(!verify_batch(&G, &H, &items2));
+                let mut items2 = items.clone();
+                items2[i].0.s += Scalar::ONE;
+                assert!(!verify_batch(&G, &H, &items2));
+            }
+        }
+    }
+}
+
+// ========================================================================
+
 #[cfg(test)]
 mod tests {
 
-    use super::{Point, Scalar};
+    use super::{Point, Scalar, PrecomputedTable};
+    use crate::DecodeError;
+    use crate::xof::expand_message_xof;
     use sha2::{Sha256, Digest};
 
     /*
@@ -857,6 +2465,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cofactor() {
+        // The group has prime order; the cofactor helpers are trivial,
+        // even though the internal edwards25519 representative of an
+        // element may have a low-order component.
+        let mut sh = Sha256::new();
+        for i in 0..10 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let P = Point::mulgen(&Scalar::decode_reduce(&v[..]));
+            let Q = Point::decode(&P.encode()).unwrap();
+            assert!(Q.mul_by_cofactor().equals(P) == 0xFFFFFFFF);
+            assert!(Q.mul_by_order().isneutral() == 0xFFFFFFFF);
+            assert!(Q.is_in_subgroup() == 0xFFFFFFFF);
+        }
+        assert!(Point::NEUTRAL.mul_by_cofactor().isneutral() == 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn decode_verbose() {
+        for s in VEC_MULGEN.iter() {
+            let buf = hex::decode(s).unwrap();
+            let P = Point::decode_verbose(&buf[..]).unwrap();
+            assert!(P.encode()[..] == buf[..]);
+        }
+        assert!(Point::decode_verbose(&[0u8; 31]).err()
+            == Some(DecodeError::BadLength));
+        assert!(Point::decode_verbose(&[0u8; 33]).err()
+            == Some(DecodeError::BadLength));
+
+        // VEC_INVALID lists 4 non-canonical field elements, then 8
+        // negative field elements, then other invalid encodings.
+        for (i, s) in VEC_INVALID.iter().enumerate() {
+            let buf = hex::decode(s).unwrap();
+            let err = if i < 4 {
+                DecodeError::NonCanonicalField
+            } else if i < 12 {
+                DecodeError::NonCanonicalEncoding
+            } else {
+                DecodeError::NotOnCurve
+            };
+            assert!(Point::decode_verbose(&buf[..]).err() == Some(err));
+        }
+    }
+
     // Test vectors from draft-irtf-cfrg-ristretto255-decaf448-07,
     // section A.3.
     struct Ristretto255MapTestVector<'a> {
@@ -916,7 +2569,156 @@ mod tests {
             let input = hex::decode(tv.I).unwrap();
             let output = hex::decode(tv.O).unwrap();
             assert!(Point::one_way_map(&input[..]).encode() == &output[..]);
+            let mut b = [0u8; 64];
+            b[..].copy_from_slice(&input);
+            assert!(Point::from_uniform_bytes(&b).encode() == &output[..]);
+        }
+    }
+
+    #[test]
+    fn lizard() {
+        // Encodings computed with an independent implementation.
+        const KAT: [(&str, &str); 3] = [
+            ("00000000000000000000000000000000",
+             "f0b7e34484f74cf00f15024b738539738646bbbe1e9bc7509a676815227e774f"),
+            ("000102030405060708090a0b0c0d0e0f",
+             "c830573f8a8e7778671f76cdc796dc0a235cf177f197d9fcba06e84e96247444"),
+            ("ffffffffffffffffffffffffffffffff",
+             "d805dfcda34ab5704e69727c83a7d8b002416d4fd9adba9c0ee28dce69d7c90b"),
+        ];
+        for (d, e) in KAT.iter() {
+            let mut data = [0u8; 16];
+            data[..].copy_from_slice(&hex::decode(d).unwrap());
+            let P = Point::lizard_encode(&data);
+            assert!(P.encode()[..] == hex::decode(e).unwrap()[..]);
+            assert!(P.lizard_decode() == Some(data));
+        }
+
+        let mut sh = Sha256::new();
+        for i in 0..100 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let mut data = [0u8; 16];
+            data[..].copy_from_slice(&v[..16]);
+            let P = Point::lizard_encode(&data);
+            assert!(P.lizard_decode() == Some(data));
+            // Decoding does not depend on the internal representation.
+            let Q = Point::decode(&P.encode()).unwrap();
+            assert!(Q.lizard_decode() == Some(data));
+            assert!(P.double().lizard_decode().is_none());
+
+            // Generic points are not in the image.
+            let R = Point::mulgen(&Scalar::decode_reduce(&v[16..]));
+            assert!(R.lizard_decode().is_none());
+        }
+        assert!(Point::BASE.lizard_decode().is_none());
+        assert!(Point::NEUTRAL.lizard_decode().is_none());
+    }
+
+    #[test]
+    fn encode_batch() {
+        let mut pp = [Point::NEUTRAL; 40];
+        pp[1] = Point::BASE;
+        let mut sh = Sha256::new();
+        for i in 2..pp.len() {
+            sh.update(&(i as u64).to_le_bytes());
+            pp[i] = Point::mulgen(&Scalar::decode_reduce(&sh.finalize_reset()));
+            if (i & 1) != 0 {
+                // Internal representation is not always the canonical
+                // one for the mapped points.
+                pp[i] += Point::hash_to_point(&[i as u8], b"encode_batch");
+            }
+        }
+        pp[20] = Point::NEUTRAL;
+        for n in [0, 1, 2, 33, 40] {
+            let mut out = [[0u8; 32]; 40];
+            Point::encode_batch(&pp[..n], &mut out[..n]);
+            for i in 0..n {
+                assert!(out[i] == pp[i].encode());
+            }
+            let mut out = [[0xFFu8; 32]; 40];
+            Point::double_and_encode_batch(&pp[..n], &mut out[..n]);
+            for i in 0..n {
+                assert!(out[i] == pp[i].double().encode());
+                assert!(out[i] == pp[i].double_and_encode());
+            }
+        }
+        assert!(Point::NEUTRAL.double_and_encode() == [0u8; 32]);
+    }
+
+    #[test]
+    fn precomputed_table() {
+        let mut sh = Sha256::new();
+        let mut points = [Point::NEUTRAL; 9];
+        let mut scalars = [Scalar::ZERO; 9];
+        let mut R = Point::NEUTRAL;
+        for i in 0..9 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            points[i] = Point::hash_to_point(&v, b"precomputed_table");
+            scalars[i] = Scalar::decode_reduce(&v);
+            R += points[i] * scalars[i];
         }
+        let t1 = PrecomputedTable::new(&points, 4);
+        let t2 = PrecomputedTable::new(&points, 7);
+        assert!(t1.mul_vartime(&scalars).equals(R) == 0xFFFFFFFF);
+        assert!(t2.mul_vartime(&scalars).equals(R) == 0xFFFFFFFF);
+        assert!(t1.mul_vartime(&scalars).encode() == R.encode());
+    }
+
+    #[test]
+    fn generators() {
+        // Pinned values: the derivation process must not change.
+        const KAT: [&str; 4] = [
+            "301833d014926732da8ddde3495c612e6bae957c006d97589a96c999fd80eb7c",
+            "342f1dfd01d88096d2be9979418a7c30d9c45ffc619c23842ab28041c05f543c",
+            "964ac6f10a35c7e0903f23a81debccc43d6ece3a32e39ef6671eca64323f0333",
+            "e8cb316db5df85a22f4ff25373d00f7e9827bd9b2c17e687cdfb858fc20ef71c",
+        ];
+        let domain = b"crrl test generators";
+        let gg = Point::derive_generators(domain, 4);
+        assert!(gg.len() == 4);
+        for (i, G) in gg.iter().enumerate() {
+            assert!(G.encode()[..] == hex::decode(KAT[i]).unwrap()[..]);
+            let mut d = [0u8; 28];
+            d[..20].copy_from_slice(domain);
+            d[20..].copy_from_slice(&(i as u64).to_le_bytes());
+            let G2 = Point::hash_to_point(&d,
+                b"crrl-ristretto255-generators-v1");
+            assert!(G.equals(G2) == 0xFFFFFFFF);
+        }
+        for (G, G2) in gg.iter().zip(Point::generators(domain)) {
+            assert!(G.equals(G2) == 0xFFFFFFFF);
+        }
+        assert!(Point::derive_generators(domain, 0).is_empty());
+        assert!(Point::derive_generator(b"other domain", 0).equals(gg[0])
+            == 0);
+    }
+
+    #[test]
+    fn hash_to_point() {
+        // Outputs of expand_message_xmd (SHA-512, 64 bytes) for the
+        // provided messages, computed with an independent implementation.
+        const DST: &[u8] = b"QUUX-V01-CS02-with-ristretto255_XMD:SHA-512_R255MAP_RO_";
+        const KAT: [(&[u8], &str); 3] = [
+            (b"", "0d3b043d43ae0295e4de2732fa75616ee6a1d951ab8e5b8e3686812fe28e301bc71daafdb75b1675b937b501bd905c613459f10aaca92fed5d947c19874a978d"),
+            (b"abc", "7bde08d73da381b27bdb1e5117b635f4febcde1271292b8273c3acc631f7eae461f289da85121ac0fd0e42d4405501c2488d03599be5eea74ea436efbaeac309"),
+            (b"abcdef0123456789", "44b24146e59ce019b3ed35a98b7395f5fed8b194cce1354eb9fb56ba04ad93f81afb0cd4508e46b366bb71f0e42bb222398db4f5d6025aac1f024a0c35909812"),
+        ];
+        for (msg, uh) in KAT.iter() {
+            let mut b = [0u8; 64];
+            hex::decode_to_slice(uh, &mut b[..]).unwrap();
+            let P = Point::hash_to_point(msg, DST);
+            assert!(P.equals(Point::from_uniform_bytes(&b)) == 0xFFFFFFFF);
+            assert!(Point::hash_to_point(msg, b"other").equals(P) == 0);
+        }
+
+        // XOF variant.
+        let mut b = [0u8; 64];
+        expand_message_xof(sha3::Shake256::default(), b"abc", DST, &mut b);
+        let P = Point::hash_to_point_xof(sha3::Shake256::default(),
+            b"abc", DST);
+        assert!(P.equals(Point::from_uniform_bytes(&b)) == 0xFFFFFFFF);
     }
 
     #[test]
@@ -939,6 +2741,19 @@ mod tests {
             let R1 = u * A + Point::mulgen(&v);
             let R2 = A.mul_add_mulgen_vartime(&u, &v);
             assert!(R1.equals(R2) == 0xFFFFFFFF);
+            let R3 = A.mul_add_mulgen(&u, &v);
+            assert!(R1.equals(R3) == 0xFFFFFFFF);
+
+            // Edge cases: zero scalars and neutral point.
+            for (P, u, v) in [
+                (A, Scalar::ZERO, v), (A, u, Scalar::ZERO),
+                (A, Scalar::ZERO, Scalar::ZERO), (Point::NEUTRAL, u, v),
+            ].iter()
+            {
+                let R1 = u * P + Point::mulgen(v);
+                assert!(R1.equals(P.mul_add_mulgen_vartime(u, v)) == 0xFFFFFFFF);
+                assert!(R1.equals(P.mul_add_mulgen(u, v)) == 0xFFFFFFFF);
+            }
         }
     }
 }
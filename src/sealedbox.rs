@@ -0,0 +1,349 @@
+//! Sealed boxes (anonymous public key encryption), compatible with
+//! libsodium's `crypto_box_seal()`.
+//!
+//! A sealed box encrypts a message for a recipient, identified by its
+//! X25519 public key; the sender is anonymous (a new ephemeral key pair
+//! is used for each message). The format is that of libsodium:
+//!
+//!  - the sender generates an ephemeral X25519 key pair `(esk, epk)`;
+//!
+//!  - the nonce is the 24-byte BLAKE2b hash (unkeyed) of `epk || rpk`,
+//!    where `rpk` is the recipient public key;
+//!
+//!  - the encryption key is HSalsa20 applied on the X25519 shared secret
+//!    (with an all-zero 16-byte input), as in `crypto_box_beforenm()`;
+//!
+//!  - the message is encrypted with XSalsa20-Poly1305 (`crypto_secretbox`):
+//!    the first 32 bytes of the keystream are the Poly1305 key, the
+//!    message is XORed with the rest of the keystream, and the tag is
+//!    computed over the encrypted message only.
+//!
+//! The sealed box is `epk || tag || encrypted_message`, i.e. 48 bytes
+//! longer than the plaintext. `box_seal()` encrypts a message with a
+//! random ephemeral key; `box_seal_open()` decrypts with the recipient
+//! private key. Recipient key pairs are normal X25519 key pairs (see
+//! `x25519::x25519_keygen()`). An all-zero Diffie-Hellman output (i.e. a
+//! low-order public key) is reported as an error, as libsodium does.
+//!
+//! Salsa20 is implemented here only to the extent needed by this format;
+//! it is not exposed as a standalone primitive.
+
+use core::convert::TryFrom;
+use crate::blake2b::Blake2b;
+use crate::poly1305::Poly1305;
+use crate::x25519::{x25519_base, x25519_checked, x25519_keygen};
+use crate::{CryptoRng, RngCore, Vec};
+
+/// Error type for sealed boxes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SealedBoxError {
+    /// Encryption failed (invalid recipient public key).
+    Seal,
+    /// Decryption failed (invalid or truncated sealed box, or wrong
+    /// private key).
+    Open,
+}
+
+impl core::fmt::Display for SealedBoxError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SealedBoxError::Seal => f.write_str("invalid recipient public key"),
+            SealedBoxError::Open => f.write_str("decryption failed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SealedBoxError {}
+
+/// Overhead of a sealed box: ephemeral public key (32 bytes) and tag
+/// (16 bytes).
+pub const SEAL_OVERHEAD: usize = 48;
+
+/// Encrypts a message for the recipient public key `rpk`. The ephemeral
+/// key pair is generated from the provided random source. The returned
+/// sealed box is 48 bytes longer than the message. An error is returned
+/// if the recipient public key has low order.
+pub fn box_seal<T: CryptoRng + RngCore>(rng: &mut T, rpk: &[u8; 32],
+    pt: &[u8]) -> Result<Vec<u8>, SealedBoxError>
+{
+    let (esk, _) = x25519_keygen(rng);
+    box_seal_with_ephemeral(&esk, rpk, pt)
+}
+
+// Sealing with an explicit ephemeral private key.
+fn box_seal_with_ephemeral(esk: &[u8; 32], rpk: &[u8; 32], pt: &[u8])
+    -> Result<Vec<u8>, SealedBoxError>
+{
+    let dh = x25519_checked(rpk, esk).ok_or(SealedBoxError::Seal)?;
+    let epk = x25519_base(esk);
+    let key = hsalsa20(&dh, &[0u8; 16]);
+    let nonce = seal_nonce(&epk, rpk);
+
+    let mut out = Vec::with_capacity(pt.len() + SEAL_OVERHEAD);
+    out.extend_from_slice(&epk);
+    out.extend_from_slice(&[0u8; 16]);
+    out.extend_from_slice(pt);
+    let mut xs = XSalsa20::new(&key, &nonce);
+    let otk = xs.poly1305_key();
+    xs.apply_keystream(&mut out[SEAL_OVERHEAD..]);
+    let tag = Poly1305::mac(&otk, &out[SEAL_OVERHEAD..]);
+    out[32..SEAL_OVERHEAD].copy_from_slice(&tag);
+    Ok(out)
+}
+
+/// Decrypts a sealed box with the recipient private key `rsk`. An error
+/// is returned if the sealed box is shorter than 48 bytes, uses a
+/// low-order ephemeral key, or fails authentication.
+pub fn box_seal_open(rsk: &[u8; 32], ct: &[u8])
+    -> Result<Vec<u8>, SealedBoxError>
+{
+    if ct.len() < SEAL_OVERHEAD {
+        return Err(SealedBoxError::Open);
+    }
+    let epk = <[u8; 32]>::try_from(&ct[..32]).unwrap();
+    let dh = x25519_checked(&epk, rsk).ok_or(SealedBoxError::Open)?;
+    let rpk = x25519_base(rsk);
+    let key = hsalsa20(&dh, &[0u8; 16]);
+    let nonce = seal_nonce(&epk, &rpk);
+
+    let mut xs = XSalsa20::new(&key, &nonce);
+    let otk = xs.poly1305_key();
+    let mut pc = Poly1305::new(&otk);
+    pc.update(&ct[SEAL_OVERHEAD..]);
+    if !pc.verify(&ct[32..SEAL_OVERHEAD]) {
+        return Err(SealedBoxError::Open);
+    }
+    let mut pt = ct[SEAL_OVERHEAD..].to_vec();
+    xs.apply_keystream(&mut pt);
+    Ok(pt)
+}
+
+// ========================================================================
+// Salsa20 (XSalsa20 and HSalsa20).
+
+const SIGMA: [u32; 4] = [ 0x61707865, 0x3320646E, 0x79622D32, 0x6B206574 ];
+
+fn decode_words(src: &[u8], dst: &mut [u32]) {
+    for i in 0..dst.len() {
+        dst[i] = u32::from_le_bytes(*<&[u8; 4]>::try_from(
+            &src[(4 * i)..(4 * i + 4)]).unwrap());
+    }
+}
+
+// Salsa20 initial state for a key and a 16-byte input (nonce and
+// counter, or HSalsa20 input).
+fn salsa20_state(key: &[u8; 32], inp: &[u8; 16]) -> [u32; 16] {
+    let mut st = [0u32; 16];
+    st[0] = SIGMA[0];
+    decode_words(&key[..16], &mut st[1..5]);
+    st[5] = SIGMA[1];
+    decode_words(&inp[..], &mut st[6..10]);
+    st[10] = SIGMA[2];
+    decode_words(&key[16..], &mut st[11..15]);
+    st[15] = SIGMA[3];
+    st
+}
+
+// Apply the 20 rounds (10 double-rounds) on a state.
+fn salsa20_rounds(x: &mut [u32; 16]) {
+    macro_rules! qr {
+        ($a: expr, $b: expr, $c: expr, $d: expr) => {
+            x[$b] ^= x[$a].wrapping_add(x[$d]).rotate_left(7);
+            x[$c] ^= x[$b].wrapping_add(x[$a]).rotate_left(9);
+            x[$d] ^= x[$c].wrapping_add(x[$b]).rotate_left(13);
+            x[$a] ^= x[$d].wrapping_add(x[$c]).rotate_left(18);
+        }
+    }
+    for _ in 0..10 {
+        // Column round.
+        qr!( 0,  4,  8, 12);
+        qr!( 5,  9, 13,  1);
+        qr!(10, 14,  2,  6);
+        qr!(15,  3,  7, 11);
+        // Row round.
+        qr!( 0,  1,  2,  3);
+        qr!( 5,  6,  7,  4);
+        qr!(10, 11,  8,  9);
+        qr!(15, 12, 13, 14);
+    }
+}
+
+// HSalsa20: derive a 32-byte subkey from a key and a 16-byte input.
+fn hsalsa20(key: &[u8; 32], inp: &[u8; 16]) -> [u8; 32] {
+    let mut x = salsa20_state(key, inp);
+    salsa20_rounds(&mut x);
+    let mut out = [0u8; 32];
+    for (i, &j) in [0, 5, 10, 15, 6, 7, 8, 9].iter().enumerate() {
+        out[(4 * i)..(4 * i + 4)].copy_from_slice(&x[j].to_le_bytes());
+    }
+    out
+}
+
+// XSalsa20 keystream, starting at block 0.
+struct XSalsa20 {
+    subkey: [u8; 32],
+    nonce: [u8; 8],
+    counter: u64,
+    // Buffered keystream block, and number of bytes already used.
+    buf: [u8; 64],
+    buf_ptr: usize,
+}
+
+impl XSalsa20 {
+
+    fn new(key: &[u8; 32], nonce: &[u8; 24]) -> Self {
+        let mut n = [0u8; 8];
+        n.copy_from_slice(&nonce[16..]);
+        Self {
+            subkey: hsalsa20(key, <&[u8; 16]>::try_from(&nonce[..16]).unwrap()),
+            nonce: n,
+            counter: 0,
+            buf: [0u8; 64],
+            buf_ptr: 64,
+        }
+    }
+
+    fn next_block(&mut self) {
+        let mut inp = [0u8; 16];
+        inp[..8].copy_from_slice(&self.nonce);
+        inp[8..].copy_from_slice(&self.counter.to_le_bytes());
+        let st = salsa20_state(&self.subkey, &inp);
+        let mut x = st;
+        salsa20_rounds(&mut x);
+        for i in 0..16 {
+            self.buf[(4 * i)..(4 * i + 4)].copy_from_slice(
+                &x[i].wrapping_add(st[i]).to_le_bytes());
+        }
+        self.counter += 1;
+        self.buf_ptr = 0;
+    }
+
+    // Get the Poly1305 key (first 32 bytes of the keystream). This must
+    // be called first; the next keystream bytes are used for encryption.
+    fn poly1305_key(&mut self) -> [u8; 32] {
+        self.next_block();
+        self.buf_ptr = 32;
+        <[u8; 32]>::try_from(&self.buf[..32]).unwrap()
+    }
+
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for b in data.iter_mut() {
+            if self.buf_ptr == 64 {
+                self.next_block();
+            }
+            *b ^= self.buf[self.buf_ptr];
+            self.buf_ptr += 1;
+        }
+    }
+}
+
+// Nonce for a sealed box: BLAKE2b-192(epk || rpk).
+fn seal_nonce(epk: &[u8; 32], rpk: &[u8; 32]) -> [u8; 24] {
+    let mut sh = Blake2b::new(24);
+    sh.update(epk);
+    sh.update(rpk);
+    let mut out = [0u8; 24];
+    sh.finalize_write(&mut out);
+    out
+}
+
+// ========================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::{box_seal_open, box_seal_with_ephemeral, seal_nonce};
+    use crate::x25519::x25519_base;
+    use crate::Vec;
+
+    // Recipient and ephemeral private keys for the test vectors.
+    const RSK: &str = "103621bac16538ab1c274ef67c279f15917c54687fabf4e0c15a410b366075e8";
+    const RPK: &str = "11d13ad0103e85a889432f48cf6553236552b02a82eaed1db07b79e6f6ef9460";
+    const ESK: &str = "ab4440c9d28b3a1986f54a97537f302540f21506d5713febe9ba782dc8d35355";
+
+    // Sealed boxes computed with libsodium (crypto_box_easy() with the
+    // ephemeral key above and the nonce from crypto_generichash(), which
+    // is what crypto_box_seal() does), for an empty message, "sealed",
+    // and the bytes 0 to 99.
+    const KAT: [&str; 3] = [
+        "8e7269c33268fae0b5da501d5b5323b4da971bb2de4a3d6f0c1761c932c46f25e975e83633b0a9bfce41b59b1ab6c1a6",
+        "8e7269c33268fae0b5da501d5b5323b4da971bb2de4a3d6f0c1761c932c46f258ea9c147f4ce04f832d91d642f2b2a064559c1b9a053",
+        "8e7269c33268fae0b5da501d5b5323b4da971bb2de4a3d6f0c1761c932c46f25f1447e6ac8930551fba81ca7eee49390363da2d6c132cf2043a8de1efd182a2cdb04ec073cc3f4dfc91577bc48d1b33f748f6ffd6a266326b7685dfc48f6738d73ef5b59bb54ee6a98623a62183ae927930f608c0125dcd4a2c3b7782a203463f4e467c97c669f4dd8d869ff05aebe35c1ba3af0",
+    ];
+
+    // Output of libsodium's crypto_box_seal() (random ephemeral key) for
+    // the same recipient.
+    const LIBSODIUM_MSG: &[u8] = b"message from libsodium crypto_box_seal";
+    const LIBSODIUM_SEAL: &str = "8d91a3938ce27df822a7edd56974f92f97b37715d3c06bbf87a500568ccc0142de757aa9c3b8f44187b67f1aac809f1585df9feebf9fd434c86ce318475240029967241407f2693070fcc11db55c7e94745c8fc4fa99";
+
+    fn decode32(s: &str) -> [u8; 32] {
+        let mut x = [0u8; 32];
+        hex::decode_to_slice(s, &mut x[..]).unwrap();
+        x
+    }
+
+    #[test]
+    fn libsodium_vectors() {
+        let rsk = decode32(RSK);
+        let rpk = decode32(RPK);
+        let esk = decode32(ESK);
+        assert!(x25519_base(&rsk) == rpk);
+        let msgs: [&[u8]; 3] = [b"", b"sealed", &(0..100).collect::<Vec<u8>>()];
+        for (m, kh) in msgs.iter().zip(KAT.iter()) {
+            let sb = hex::decode(kh).unwrap();
+            assert!(box_seal_with_ephemeral(&esk, &rpk, m).unwrap() == sb);
+            assert!(box_seal_open(&rsk, &sb).unwrap()[..] == m[..]);
+        }
+
+        let sb = hex::decode(LIBSODIUM_SEAL).unwrap();
+        assert!(box_seal_open(&rsk, &sb).unwrap() == LIBSODIUM_MSG);
+    }
+
+    #[test]
+    fn nonce() {
+        // BLAKE2b-192(epk || rpk), computed with Python's hashlib.
+        let epk = x25519_base(&decode32(ESK));
+        let rpk = decode32(RPK);
+        let mut n = [0u8; 24];
+        n.copy_from_slice(&seal_nonce(&epk, &rpk));
+        assert!(n[..] == hex::decode("6f742661a84d0c4d8c4f1866423dd91685c7fda8c9c949e1").unwrap()[..]);
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn tamper() {
+        use super::{SealedBoxError, box_seal, SEAL_OVERHEAD};
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x42; 32]);
+        let rsk = decode32(RSK);
+        let rpk = decode32(RPK);
+        for len in 0..70 {
+            let pt: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let sb = box_seal(&mut rng, &rpk, &pt).unwrap();
+            assert!(sb.len() == len + SEAL_OVERHEAD);
+            assert!(box_seal_open(&rsk, &sb).unwrap() == pt);
+
+            // Any modified byte (ephemeral key, tag or ciphertext) makes
+            // decryption fail.
+            for i in 0..sb.len() {
+                let mut sb2 = sb.clone();
+                sb2[i] ^= 0x01;
+                assert!(box_seal_open(&rsk, &sb2).err() == Some(SealedBoxError::Open));
+            }
+
+            // Wrong recipient key.
+            let mut rsk2 = rsk;
+            rsk2[1] ^= 0x01;
+            assert!(box_seal_open(&rsk2, &sb).err() == Some(SealedBoxError::Open));
+
+            // Truncated sealed box.
+            assert!(box_seal_open(&rsk, &sb[..(sb.len() - 1)]).err() == Some(SealedBoxError::Open));
+        }
+
+        // Low-order recipient key: sealing fails.
+        assert!(box_seal(&mut rng, &[0u8; 32], b"x").err() == Some(SealedBoxError::Seal));
+    }
+}
@@ -40,7 +40,16 @@
 //! accept a sequence of 33 or 65 zeros as a valid input. Thus, point
 //! decoding is stricly standards-conforming. All decoding operations
 //! enforce canonicality of encoding, and verify that the point is indeed
-//! on the curve.
+//! on the curve. `Point::decode_sec1()` additionally accepts the rarely
+//! used "hybrid" format, and reports decoding failures in detail.
+//!
+//! Arbitrary data can be hashed into a curve point with
+//! `Point::hash_to_curve()`, which implements the
+//! `secp256k1_XMD:SHA-256_SSWU_RO_` suite from [RFC 9380]; the
+//! non-uniform variant (`secp256k1_XMD:SHA-256_SSWU_NU_`) is provided by
+//! `Point::encode_to_curve()`. `Point::derive_generators()` (and the
+//! allocation-free iterator `Point::generators()`) derive sequences of
+//! independent generators from a label, with a fixed process.
 //!
 //! The `PrivateKey` structure represents a private key for the ECDSA
 //! signature algorithm; it is basically a wrapper around a private
@@ -58,6 +67,18 @@
 //! only wrap around the corresponding `Point` functions, except that
 //! `decode()` explicitly rejects the point-at-infinity: an ECDSA public
 //! key is never the identity point.
+//! `PublicKey::from_bytes_strict()` is a variable-time alternative to
+//! `decode()` for keys received from untrusted sources: a `ParsePolicy`
+//! selects whether only compressed keys are accepted (as in some
+//! consensus rules) or any SEC 1 encoding, and failures are reported
+//! with a `KeyError` that tells why the key was rejected.
+//!
+//! Keys can be tweaked with a scalar `t`: `PrivateKey::tweak_add()` and
+//! `PublicKey::tweak_add()` compute `x + t` and `P + t*G`, while
+//! `PrivateKey::tweak_mul()` and `PublicKey::tweak_mul()` compute `x*t`
+//! and `t*P`, so that tweaking a private key and its public key with the
+//! same `t` yields a matching key pair. These functions report a zero
+//! (or point-at-infinity) result as a failure.
 //!
 //! ECDSA signatures are generated with `PrivateKey::sign_hash()`, and
 //! verified with `PublicKey::verify_hash()`. The signature process is
@@ -74,8 +95,97 @@
 //! and `s` starts), and that the two `r` and `s` values are still in the
 //! proper range (i.e. lower than the curve order).
 //!
+//! `PrivateKey::sign_with_hash()` and `PublicKey::verify_with_hash()`
+//! hash the message themselves, with a hash function selected by a
+//! `HashId` (SHA-224, SHA-256, SHA-384 or SHA-512); the same function is
+//! used for the RFC 6979 nonce derivation, as in
+//! `PrivateKey::sign_prehashed()`. A digest
+//! longer than 256 bits is truncated to its leftmost 256 bits.
+//!
+//! When the (off by default) `insecure-test-apis` feature is enabled,
+//! `PrivateKey::sign_with_nonce()` (ECDSA) and
+//! `PrivateKey::sign_schnorr_with_nonce()` (BIP-340) sign with a
+//! caller-provided nonce. This is meant only for reproducing test
+//! vectors; misuse reveals the private key.
+//!
+//! `PrivateKey::sign_hedged()` is a variant which mixes 32 bytes of
+//! additional entropy into the nonce derivation (RFC 6979, section 3.6),
+//! for protection against fault attacks on deterministic signing.
+//! `PrivateKey::sign_hash_checked()` is another countermeasure: it
+//! verifies the new signature against the caller's trusted public key
+//! before returning it, and reports an error (`SignError`) instead of a
+//! faulty signature.
+//!
+//! Recoverable signatures, as used by Ethereum (`r || s || v`, with low
+//! `s`), are generated with `PrivateKey::sign_recoverable()`; the
+//! `RecoverableSignature::recover()` function then rebuilds the signer's
+//! public key (SEC 1, section 4.1.6), which is what Ethereum's
+//! `ecrecover` does.
+//!
+//! [BIP-340] Schnorr signatures are generated with
+//! `PrivateKey::sign_schnorr()` and verified with
+//! `XOnlyPublicKey::verify_schnorr()`; the `XOnlyPublicKey` type is the
+//! 32-byte public key format of BIP-340 (a point with an even y
+//! coordinate, encoded as its x coordinate), which is obtained from a
+//! private key with `PrivateKey::to_x_only_public_key()` (with the
+//! even-y normalization), and converted to and from a full public key
+//! with `XOnlyPublicKey::from_public_key()` and
+//! `XOnlyPublicKey::to_public_key()` (which track the parity of the y
+//! coordinate). [BIP-341] (Taproot) key tweaking is provided by
+//! `XOnlyPublicKey::tap_tweak()` (output key from the internal key and
+//! optional Merkle root), `PrivateKey::tap_tweak_seckey()` (the
+//! corresponding private key, for key-path spending), and
+//! `XOnlyPublicKey::check_tweak()` (verification of the output key and
+//! parity, for script-path spending).
+//!
+//! The `ecdh()` function performs an ECDH key exchange, with the
+//! output of libsecp256k1 (SHA-256 of the compressed shared point);
+//! `ecdh_xonly()` returns the raw x coordinate of the shared point
+//! instead.
+//!
+//! For keys used on hardware shared with potential attackers,
+//! `PrivateKey::sign_hash_blinded()` and `ecdh_blinded()` return the
+//! same values as `sign_hash()` and `ecdh()`, but with the multiplication
+//! by the secret scalar randomized with an extra RNG (see
+//! `Point::mul_blinded()` and `Point::mulgen_blinded()`), as a
+//! countermeasure against power and electromagnetic analysis.
+//!
+//! For any valid signature (r, s), the pair (r, n - s) is also valid.
+//! `normalize_s()` converts a signature to its "low-s" form (s <= n/2),
+//! and `has_low_s()` tests whether a signature is in that form;
+//! `PrivateKey::sign_hash_low_s()` always produces low-s signatures, and
+//! `PublicKey::verify_hash_with_policy()` can be used to reject high-s
+//! signatures.
+//!
+//! `verify_batch()` verifies many ECDSA signatures at once, with a
+//! single multi-scalar multiplication over random linear combinations of
+//! the verification equations; each signature must come with its
+//! recovery identifier, so that the point R can be rebuilt. If a batch
+//! fails, `verify_batch_find_invalid()` locates the invalid entries.
+//! These functions require heap allocation support.
+//!
+//! Signatures can be converted to and from the ASN.1 DER format (as used
+//! in X.509 certificates, or by OpenSSL) with `signature_to_der()` and
+//! `signature_from_der()`; see the `der` module for details.
+//!
+//! The `bip32` submodule implements [BIP-32] hierarchical deterministic
+//! key derivation (extended keys, child key derivation, derivation paths,
+//! and `xprv`/`xpub` serialization).
+//!
+//! The `musig2` submodule implements [BIP-327] MuSig2 two-round
+//! multi-signatures: key aggregation (with plain and x-only tweaking),
+//! nonce generation and aggregation, partial signing and verification,
+//! and aggregation into a BIP-340 signature that verifies with
+//! `XOnlyPublicKey::verify_schnorr()`. It requires heap allocation
+//! support.
+//!
 //! [FIPS 186-4]: https://csrc.nist.gov/publications/detail/fips/186/4/final
 //! [RFC 6979]: https://datatracker.ietf.org/doc/html/rfc6979
+//! [RFC 9380]: https://datatracker.ietf.org/doc/html/rfc9380
+//! [BIP-340]: https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+//! [BIP-341]: https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki
+//! [BIP-32]: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+//! [BIP-327]: https://github.com/bitcoin/bips/blob/master/bip-0327.mediawiki
 
 // Projective/fractional coordinates traditionally use uppercase letters,
 // using lowercase only for affine coordinates.
@@ -83,10 +193,15 @@
 
 use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use super::field::{GFsecp256k1, ModInt256};
-use sha2::{Sha512, Digest};
-use super::{CryptoRng, RngCore};
+use sha2::{Sha224, Sha256, Sha384, Sha512, Digest};
+use sha2::digest::{Output, core_api::BlockSizeUser};
+use super::{CryptoRng, RngCore, DecodeError, ParseError, HashId, SignError};
+use super::der::{self, DerError};
 use core::convert::TryFrom;
 
+#[cfg(feature = "alloc")]
+use crate::Vec;
+
 /// A point on the short Weierstraß curve secp256k1.
 #[derive(Clone, Copy, Debug)]
 pub struct Point {
@@ -180,7 +295,7 @@ impl Point {
     /// The (very rarely encountered) "hybrid" encoding (like
     /// uncompressed, but the least significant bit of y is also copied
     /// into the first byte, which has value 0x06 or 0x07) is not
-    /// supported.
+    /// supported; see `decode_sec1()`.
     ///
     /// On success, this structure is set to the decoded point, and
     /// 0xFFFFFFFF is returned. On failure, this structure is set to the
@@ -276,7 +391,7 @@ impl Point {
     /// The (very rarely encountered) "hybrid" encoding (like
     /// uncompressed, but the least significant bit of y is also copied
     /// into the first byte, which has value 0x06 or 0x07) is not
-    /// supported.
+    /// supported; see `decode_sec1()`.
     ///
     /// On success, the decoded point is returned; on failure, `None` is
     /// returned. A failure is reported if the coordinates can be decoded
@@ -295,6 +410,178 @@ impl Point {
         }
     }
 
+    /// Decodes many points in compressed format.
+    ///
+    /// Each element of `out` receives the result of `decode()` on the
+    /// corresponding 33-byte encoding; the number of successfully
+    /// decoded points is returned. `out` MUST have the same length as
+    /// `encodings`. This is meant for loading large sets of public keys
+    /// (e.g. from a UTXO set); as with `decode()`, which entries were
+    /// invalid may leak through timing-based side channels.
+    ///
+    /// Note: the `y` coordinate is obtained as a square root of
+    /// x^3 + 7, which is a single exponentiation (the validity check is
+    /// a squaring of the result); there is no division, hence no
+    /// inversion to amortize with Montgomery's trick, and the square
+    /// roots of distinct values cannot be shared. The cost is thus that
+    /// of decoding the points one by one.
+    pub fn decode_batch(encodings: &[[u8; 33]], out: &mut [Option<Point>])
+        -> usize
+    {
+        assert!(encodings.len() == out.len());
+        let mut n = 0;
+        for (buf, P) in encodings.iter().zip(out.iter_mut()) {
+            *P = Self::decode(buf);
+            n += P.is_some() as usize;
+        }
+        n
+    }
+
+    /// Tries to decode a point, with detailed error reporting.
+    ///
+    /// This accepts the same encodings as `decode()`. Failures are
+    /// reported as:
+    ///
+    ///  - `BadLength`: the input length is not 1, 33 or 65 bytes;
+    ///
+    ///  - `BadHeader`: the first byte is not 0x00 (1-byte input), 0x02
+    ///    or 0x03 (33-byte input), or 0x04 (65-byte input);
+    ///
+    ///  - `NonCanonicalField`: a coordinate is not lower than the field
+    ///    modulus;
+    ///
+    ///  - `NotOnCurve`: there is no curve point with the provided `x`
+    ///    coordinate (compressed encoding), or the coordinates do not
+    ///    match the curve equation (uncompressed encoding).
+    ///
+    /// This function is meant for diagnostics, e.g. to investigate
+    /// interoperability issues; the cause of a failure is computed with
+    /// variable-time code. `decode()` and `set_decode()` should be used
+    /// in production code.
+    pub fn decode_verbose(buf: &[u8]) -> Result<Point, DecodeError> {
+        if let Some(P) = Self::decode(buf) {
+            return Ok(P);
+        }
+        let hdr_ok = match buf.len() {
+            1 => false,
+            33 => (buf[0] & 0xFE) == 0x02,
+            65 => buf[0] == 0x04,
+            _ => { return Err(DecodeError::BadLength); }
+        };
+        if !hdr_ok {
+            return Err(DecodeError::BadHeader);
+        }
+        for j in (1..buf.len()).step_by(32) {
+            if GFsecp256k1::decode32(&bswap32(&buf[j..(j + 32)])).1 == 0 {
+                return Err(DecodeError::NonCanonicalField);
+            }
+        }
+        Err(DecodeError::NotOnCurve)
+    }
+
+    /// Decodes a point from any of the SEC 1 encodings.
+    ///
+    /// This function accepts the same encodings as `decode()`
+    /// (point-at-infinity, compressed and uncompressed), and also the
+    /// hybrid encoding: a byte of value 0x06 or 0x07, followed by
+    /// exactly 64 bytes (unsigned big-endian encodings of x and y),
+    /// where the low bit of the first byte must match the least
+    /// significant bit of y. Coordinates must be canonical and match the
+    /// curve equation, for all encodings. The single-byte encoding of
+    /// the point-at-infinity (0x00) is accepted and yields the neutral
+    /// point; since it is the only encoding of that point, callers which
+    /// must reject it (e.g. for public keys) can use `isneutral()` on the
+    /// result.
+    ///
+    /// Failures are reported as with `decode_verbose()`; a hybrid
+    /// encoding whose first byte does not match the parity of y is
+    /// reported as `NonCanonicalEncoding`. This function uses
+    /// variable-time code for classifying the encoding and reporting
+    /// errors; it does not leak the value of the decoded point.
+    pub fn decode_sec1(buf: &[u8]) -> Result<Point, DecodeError> {
+        if buf.len() == 65 && (buf[0] & 0xFE) == 0x06 {
+            let mut tmp = [0u8; 65];
+            tmp.copy_from_slice(buf);
+            tmp[0] = 0x04;
+            let P = Self::decode_verbose(&tmp)?;
+            if ((buf[0] ^ buf[64]) & 1) != 0 {
+                return Err(DecodeError::NonCanonicalEncoding);
+            }
+            return Ok(P);
+        }
+        Self::decode_verbose(buf)
+    }
+
+    /// Hashes some data into a curve point (RFC 9380).
+    ///
+    /// This implements the `secp256k1_XMD:SHA-256_SSWU_RO_` suite:
+    /// the message (`msg`) is expanded with `expand_message_xmd`
+    /// (SHA-256) into two field elements, each mapped to a point with
+    /// the simplified SWU map on a 3-isogenous curve, then through the
+    /// isogeny onto secp256k1; the result is the sum of the two points.
+    /// The output is indistinguishable from a random oracle (uniformly
+    /// random point whose discrete logarithm is unknown). The domain
+    /// separation tag (`dst`) should be specific to the application
+    /// and protocol (DST longer than 255 bytes are hashed, as specified
+    /// by RFC 9380, section 5.3.3).
+    pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        Self::hash_to_curve_parts(&[msg], dst)
+    }
+
+    /// Same as `hash_to_curve()`, with the message provided as several
+    /// parts (which are concatenated).
+    fn hash_to_curve_parts(msg: &[&[u8]], dst: &[u8]) -> Self {
+        let mut uu = [0u8; 96];
+        expand_message_xmd_sha256(msg, dst, &mut uu);
+        let Q0 = iso_map(&map_to_curve_sswu(&field_from_bytes(&uu[..48])));
+        let Q1 = iso_map(&map_to_curve_sswu(&field_from_bytes(&uu[48..])));
+        Q0 + Q1
+    }
+
+    /// Encodes some data into a curve point (RFC 9380).
+    ///
+    /// This implements the `secp256k1_XMD:SHA-256_SSWU_NU_` suite; it is
+    /// similar to `hash_to_curve()`, but with a single application of
+    /// the map, which is faster but yields a non-uniform distribution
+    /// (only about half of the curve points can be obtained). Use
+    /// `hash_to_curve()` unless the protocol explicitly allows the
+    /// nonuniform variant.
+    pub fn encode_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        let mut uu = [0u8; 48];
+        expand_message_xmd_sha256(&[msg], dst, &mut uu);
+        iso_map(&map_to_curve_sswu(&field_from_bytes(&uu)))
+    }
+
+    /// Derives a generator from a label and an index.
+    ///
+    /// The generator is obtained with `hash_to_curve()` over the
+    /// concatenation of `domain` and the index `i` (over 8 bytes,
+    /// little-endian), with the domain separation tag
+    /// `"crrl-secp256k1-generators-v1"`. Nobody knows the discrete
+    /// logarithm of the output relatively to any other generator
+    /// ("nothing up my sleeve"). This derivation process is fixed and
+    /// will not change in future versions.
+    pub fn derive_generator(domain: &[u8], i: u64) -> Self {
+        Self::hash_to_curve_parts(&[domain, &i.to_le_bytes()],
+            GENERATORS_DST)
+    }
+
+    /// Gets an iterator over the generators derived from a label (see
+    /// `derive_generator()`), for indices 0, 1, 2...
+    pub fn generators(domain: &[u8]) -> Generators<'_> {
+        Generators { domain, index: 0 }
+    }
+
+    /// Derives `n` generators from a label (see `derive_generator()`),
+    /// for indices 0 to `n-1`.
+    ///
+    /// Note: this function is available only if heap allocation support
+    /// was configured.
+    #[cfg(feature = "alloc")]
+    pub fn derive_generators(domain: &[u8], n: usize) -> Vec<Self> {
+        Self::generators(domain).take(n).collect()
+    }
+
     /// Encodes this point in compressed format (33 bytes).
     ///
     /// If the point is the neutral then `[0u8; 33]` is returned, which
@@ -643,6 +930,36 @@ impl Point {
         self.Z.iszero()
     }
 
+    /// Multiplies this point by the cofactor of the group.
+    ///
+    /// This group has prime order (the cofactor is 1), so the point is
+    /// returned unchanged. This function is provided so that generic
+    /// code can clear cofactors uniformly across all curve modules.
+    #[inline(always)]
+    pub fn mul_by_cofactor(self) -> Self {
+        self
+    }
+
+    /// Multiplies this point by the group order.
+    ///
+    /// Since this group has prime order, the result is always the
+    /// neutral. This function is provided for uniformity with the curve
+    /// modules whose groups have a cofactor.
+    #[inline(always)]
+    pub fn mul_by_order(self) -> Self {
+        Self::NEUTRAL
+    }
+
+    /// Tests whether this point is in the subgroup of prime order.
+    ///
+    /// Since this group has prime order, 0xFFFFFFFF is always returned.
+    /// This function is provided for uniformity with the curve modules
+    /// whose groups have a cofactor.
+    #[inline(always)]
+    pub fn is_in_subgroup(self) -> u32 {
+        0xFFFFFFFF
+    }
+
     // Conditionally copies the provided point (`P`) into `self`.
     //
     //  - If `ctl` is 0xFFFFFFFF, then the value of `P` is copied into `self`.
@@ -1140,12 +1457,41 @@ impl Point {
         self.set_add_affine(&P, rz);
     }
 
+    /// Computes the points i*(2^130)*G for i = 1 to 16, in affine
+    /// coordinates; this replaces the `PRECOMP_G130` table when small
+    /// tables are used.
+    #[cfg(feature = "small_tables_secp256k1")]
+    fn make_precomp_g130() -> [PointAffine; 16] {
+        let P = Self::BASE.xdouble(130);
+        let mut tp = [P; 16];
+        for i in 1..16 {
+            tp[i] = tp[i - 1] + P;
+        }
+        let mut iZ = [GFsecp256k1::ZERO; 16];
+        for i in 0..16 {
+            iZ[i] = tp[i].Z;
+        }
+        GFsecp256k1::batch_invert(&mut iZ[..]);
+        let mut win = [PointAffine { x: GFsecp256k1::ZERO, y: GFsecp256k1::ZERO }; 16];
+        for i in 0..16 {
+            win[i] = PointAffine { x: tp[i].X * iZ[i], y: tp[i].Y * iZ[i] };
+        }
+        win
+    }
+
     /// Sets this point by multiplying the conventional generator by the
     /// provided scalar.
     ///
     /// This operation is constant-time. It is faster than using the
     /// generic multiplication on `Self::BASE`.
     pub fn set_mulgen(&mut self, n: &Scalar) {
+        self.set_mulgen_inner(n, None);
+    }
+
+    // Multiplies the generator by the scalar n. If z is provided, then
+    // the coordinates of the initial point are scaled by z (which must
+    // not be zero); this does not change the result.
+    fn set_mulgen_inner(&mut self, n: &Scalar, z: Option<&GFsecp256k1>) {
         // TODO: use the endomorphism to speed up this computation
         // (see jq255.rs and gls254.rs for examples)
 
@@ -1153,18 +1499,38 @@ impl Point {
         let sd = Self::recode_scalar(n);
 
         // We process four chunks in parallel. Each chunk is 13 digits.
-        *self = Self::lookup_affine_proj(&PRECOMP_G, sd[12]);
-        self.set_lookup_affine_add(&PRECOMP_G65, sd[25]);
-        self.set_lookup_affine_add(&PRECOMP_G130, sd[38]);
-        self.set_lookup_affine_add(&PRECOMP_G195, sd[51]);
+        #[cfg(not(feature = "small_tables_secp256k1"))]
+        {
+            *self = Self::lookup_affine_proj(&PRECOMP_G, sd[12]);
+            if let Some(z) = z {
+                self.set_rerandomize(z);
+            }
+            self.set_lookup_affine_add(&PRECOMP_G65, sd[25]);
+            self.set_lookup_affine_add(&PRECOMP_G130, sd[38]);
+            self.set_lookup_affine_add(&PRECOMP_G195, sd[51]);
+
+            // Process the digits in high-to-low order.
+            for i in (0..12).rev() {
+                self.set_xdouble(5);
+                self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
+                self.set_lookup_affine_add(&PRECOMP_G65, sd[i + 13]);
+                self.set_lookup_affine_add(&PRECOMP_G130, sd[i + 26]);
+                self.set_lookup_affine_add(&PRECOMP_G195, sd[i + 39]);
+            }
+        }
 
-        // Process the digits in high-to-low order.
-        for i in (0..12).rev() {
-            self.set_xdouble(5);
-            self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
-            self.set_lookup_affine_add(&PRECOMP_G65, sd[i + 13]);
-            self.set_lookup_affine_add(&PRECOMP_G130, sd[i + 26]);
-            self.set_lookup_affine_add(&PRECOMP_G195, sd[i + 39]);
+        // With the small tables, digits are processed with Horner's
+        // rule and a single table (about 4 times as many doublings).
+        #[cfg(feature = "small_tables_secp256k1")]
+        {
+            *self = Self::lookup_affine_proj(&PRECOMP_G, sd[51]);
+            if let Some(z) = z {
+                self.set_rerandomize(z);
+            }
+            for i in (0..51).rev() {
+                self.set_xdouble(5);
+                self.set_lookup_affine_add(&PRECOMP_G, sd[i]);
+            }
         }
     }
 
@@ -1180,6 +1546,77 @@ impl Point {
         P
     }
 
+    // Scales the coordinates of this point by the non-zero field element
+    // z; the represented point is unchanged.
+    fn set_rerandomize(&mut self, z: &GFsecp256k1) {
+        self.X *= z;
+        self.Y *= z;
+        self.Z *= z;
+    }
+
+    /// Multiplies this point by a scalar (in place), with blinding.
+    ///
+    /// The result is the same as with `set_mul()`, but the computation
+    /// is randomized with the provided RNG, as a countermeasure against
+    /// power and electromagnetic analysis: the scalar n is split into
+    /// (n - r) + r for a random r, and the two partial products start
+    /// from copies of this point whose projective coordinates are
+    /// scaled by random non-zero field elements. This is about twice as
+    /// expensive as `set_mul()`.
+    pub fn set_mul_blinded<T: CryptoRng + RngCore>(&mut self, n: &Scalar,
+        rng: &mut T)
+    {
+        self.set_mul_with_blinding(n, &Blinding::new(rng));
+    }
+
+    /// Multiplies this point by a scalar, with blinding.
+    ///
+    /// See `set_mul_blinded()` for details.
+    #[inline]
+    pub fn mul_blinded<T: CryptoRng + RngCore>(self, n: &Scalar,
+        rng: &mut T) -> Self
+    {
+        let mut P = self;
+        P.set_mul_blinded(n, rng);
+        P
+    }
+
+    // Blinded multiplication, with the provided blinding values.
+    fn set_mul_with_blinding(&mut self, n: &Scalar, b: &Blinding) {
+        let mut P = *self;
+        P.set_rerandomize(&b.z1);
+        P.set_mul(&(*n - b.r));
+        self.set_rerandomize(&b.z2);
+        self.set_mul(&b.r);
+        self.set_add(&P);
+    }
+
+    /// Creates a point by multiplying the conventional generator by the
+    /// provided scalar, with blinding.
+    ///
+    /// The result is the same as with `mulgen()`, but the computation is
+    /// randomized with the provided RNG, as a countermeasure against
+    /// power and electromagnetic analysis: the scalar n is split into
+    /// (n - r) + r for a random r, and the coordinates of the initial
+    /// point of each partial product are scaled by random non-zero
+    /// field elements. This is about twice as expensive as `mulgen()`.
+    pub fn mulgen_blinded<T: CryptoRng + RngCore>(n: &Scalar, rng: &mut T)
+        -> Self
+    {
+        Self::mulgen_with_blinding(n, &Blinding::new(rng))
+    }
+
+    // Blinded multiplication of the generator, with the provided
+    // blinding values.
+    fn mulgen_with_blinding(n: &Scalar, b: &Blinding) -> Self {
+        let mut P = Self::NEUTRAL;
+        P.set_mulgen_inner(&(*n - b.r), Some(&b.z1));
+        let mut Q = Self::NEUTRAL;
+        Q.set_mulgen_inner(&b.r, Some(&b.z2));
+        P.set_add(&Q);
+        P
+    }
+
     /// 5-bit wNAF recoding of a scalar; output is a sequence of 257
     /// digits.
     ///
@@ -1239,6 +1676,19 @@ impl Point {
         sd
     }
 
+    /// Computes the window of odd multiples of a point, for 5-bit wNAF:
+    /// `win[i] = (2*i+1)*P` (for `i` = 0 to 7).
+    #[cfg(feature = "alloc")]
+    fn make_window_vartime(P: &Self) -> [Self; 8] {
+        let mut win = [Self::NEUTRAL; 8];
+        let Q = P.double();
+        win[0] = *P;
+        for i in 1..8 {
+            win[i] = win[i - 1] + Q;
+        }
+        win
+    }
+
     /// Given scalars `u` and `v`, sets this point to `u*self + v*G`
     /// (with `G` being the conventional generator point, aka
     /// `Self::BASE`).
@@ -1285,6 +1735,12 @@ impl Point {
         let sd1 = Self::recode_u128_NAF(u1);
         let sd2 = Self::recode_scalar_NAF(v);
 
+        // Window for (2^130)*G (recomputed with the small tables).
+        #[cfg(not(feature = "small_tables_secp256k1"))]
+        let win_g130 = &PRECOMP_G130;
+        #[cfg(feature = "small_tables_secp256k1")]
+        let win_g130 = &Self::make_precomp_g130();
+
         let mut zz = true;
         let mut ndbl = 0u32;
         for i in (0..130).rev() {
@@ -1334,9 +1790,9 @@ impl Point {
             }
             if e3 != 0 {
                 if e3 > 0 {
-                    self.set_add_affine(&PRECOMP_G130[e3 as usize - 1], 0);
+                    self.set_add_affine(&win_g130[e3 as usize - 1], 0);
                 } else {
-                    self.set_sub_affine(&PRECOMP_G130[(-e3) as usize - 1], 0);
+                    self.set_sub_affine(&win_g130[(-e3) as usize - 1], 0);
                 }
             }
         }
@@ -1366,6 +1822,101 @@ impl Point {
         R
     }
 
+    /// Multiplies this point by a scalar (in place), using the GLV
+    /// endomorphism.
+    ///
+    /// The scalar is split into two half-width integers `n0` and `n1`
+    /// such that `n = n0 + n1*mu` (with `zeta(P) = mu*P`); both are
+    /// recoded in 5-bit wNAF and processed with shared doublings. This
+    /// avoids the constant-time window lookups of `set_mul()`, and is
+    /// thus somewhat faster.
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    pub fn set_mul_vartime_glv(&mut self, n: &Scalar) {
+        // Split the scalar with the endomorphism.
+        let (n0, s0, n1, s1) = Self::split_theta(n);
+
+        // Compute the windows:
+        //   win0[i] = (2*i+1)*sgn(n0)*self         (i = 0 to 7)
+        //   win1[i] = (2*i+1)*sgn(n1)*zeta(self)   (i = 0 to 7)
+        let mut win0 = [Self::NEUTRAL; 8];
+        win0[0] = *self;
+        win0[0].set_condneg(s0);
+        let Q = win0[0].double();
+        for i in 1..8 {
+            win0[i] = win0[i - 1] + Q;
+        }
+        let mut win1 = [Self::NEUTRAL; 8];
+        for i in 0..8 {
+            win1[i] = win0[i].zeta();
+            win1[i].set_condneg(s0 ^ s1);
+        }
+
+        let sd0 = Self::recode_u128_NAF(n0);
+        let sd1 = Self::recode_u128_NAF(n1);
+
+        let mut zz = true;
+        let mut ndbl = 0u32;
+        for i in (0..130).rev() {
+            // We have one more doubling to perform.
+            ndbl += 1;
+
+            // Get next digits. If they are all zeros, then we can loop
+            // immediately.
+            let e0 = sd0[i];
+            let e1 = sd1[i];
+            if ((e0 as u32) | (e1 as u32)) == 0 {
+                continue;
+            }
+
+            // Apply accumulated doubles.
+            if zz {
+                *self = Self::NEUTRAL;
+                zz = false;
+            } else {
+                self.set_xdouble(ndbl);
+            }
+            ndbl = 0u32;
+
+            // Process digits.
+            if e0 != 0 {
+                if e0 > 0 {
+                    self.set_add(&win0[e0 as usize >> 1]);
+                } else {
+                    self.set_sub(&win0[(-e0) as usize >> 1]);
+                }
+            }
+            if e1 != 0 {
+                if e1 > 0 {
+                    self.set_add(&win1[e1 as usize >> 1]);
+                } else {
+                    self.set_sub(&win1[(-e1) as usize >> 1]);
+                }
+            }
+        }
+
+        if zz {
+            *self = Self::NEUTRAL;
+        } else {
+            if ndbl > 0 {
+                self.set_xdouble(ndbl);
+            }
+        }
+    }
+
+    /// Multiplies this point by a scalar, using the GLV endomorphism
+    /// (see `set_mul_vartime_glv()`).
+    ///
+    /// THIS FUNCTION IS NOT CONSTANT-TIME; it shall be used only with
+    /// public data.
+    #[inline(always)]
+    pub fn mul_vartime_glv(self, n: &Scalar) -> Self {
+        let mut R = self;
+        R.set_mul_vartime_glv(n);
+        R
+    }
+
     /// Check whether `s*G = R + k*Q`, for the provided scalars `s`
     /// and `k`, provided points `Q` (`self`) and `R`, and conventional
     /// generator `G`.
@@ -1679,8 +2230,206 @@ impl SubAssign<&Point> for Point {
 
 // ========================================================================
 
+/// Iterator over generators derived from a label.
+///
+/// This is returned by `Point::generators()`; it produces an unbounded
+/// sequence of points.
+#[derive(Clone, Debug)]
+pub struct Generators<'a> {
+    domain: &'a [u8],
+    index: u64,
+}
+
+impl<'a> Iterator for Generators<'a> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        let P = Point::derive_generator(self.domain, self.index);
+        self.index += 1;
+        Some(P)
+    }
+}
+
+/// Domain separation tag for generator derivation.
+const GENERATORS_DST: &[u8] = b"crrl-secp256k1-generators-v1";
+
+// ========================================================================
+// Hash-to-curve (RFC 9380, suites secp256k1_XMD:SHA-256_SSWU_RO_ and _NU_).
+
+// expand_message_xmd with SHA-256 (RFC 9380, section 5.3.1); the message
+// is provided as several parts (which are concatenated). The output
+// length must not exceed 8160 bytes. Oversized DST (more than 255 bytes)
+// are replaced with their hash, as per section 5.3.3.
+fn expand_message_xmd_sha256(msg: &[&[u8]], dst: &[u8], out: &mut [u8]) {
+    let len = out.len();
+    assert!(len <= 255 * 32);
+    let mut hdst = [0u8; 32];
+    let dst = if dst.len() > 255 {
+        let mut sh = Sha256::new();
+        sh.update(b"H2C-OVERSIZE-DST-");
+        sh.update(dst);
+        hdst[..].copy_from_slice(&sh.finalize());
+        &hdst[..]
+    } else {
+        dst
+    };
+    let dst_len = [dst.len() as u8];
+
+    // b_0 = H(Z_pad || msg || l_i_b_str || I2OSP(0, 1) || DST_prime)
+    let mut sh = Sha256::new();
+    sh.update([0u8; 64]);
+    for m in msg.iter() {
+        sh.update(m);
+    }
+    sh.update((len as u16).to_be_bytes());
+    sh.update([0u8]);
+    sh.update(dst);
+    sh.update(dst_len);
+    let b0: [u8; 32] = sh.finalize_reset().into();
+
+    // b_i = H(strxor(b_0, b_(i - 1)) || I2OSP(i, 1) || DST_prime)
+    // (with b_1 = H(b_0 || I2OSP(1, 1) || DST_prime))
+    let mut bi = [0u8; 32];
+    let mut i = 1;
+    let mut j = 0;
+    while j < len {
+        for k in 0..32 {
+            bi[k] ^= b0[k];
+        }
+        sh.update(bi);
+        sh.update([i as u8]);
+        sh.update(dst);
+        sh.update(dst_len);
+        bi = sh.finalize_reset().into();
+        let clen = core::cmp::min(32, len - j);
+        out[j..(j + clen)].copy_from_slice(&bi[..clen]);
+        i += 1;
+        j += clen;
+    }
+}
+
+// Converts 48 bytes (unsigned big-endian) into a field element (with
+// reduction modulo p).
+fn field_from_bytes(buf: &[u8]) -> GFsecp256k1 {
+    let mut tmp = [0u8; 48];
+    for i in 0..48 {
+        tmp[i] = buf[47 - i];
+    }
+    GFsecp256k1::decode_reduce(&tmp)
+}
+
+// Parameters of the curve E': y^2 = x^3 + A'*x + B', which is 3-isogenous
+// to secp256k1, and of the simplified SWU map on E' (RFC 9380, section
+// 8.7).
+const SSWU_A: GFsecp256k1 = GFsecp256k1::w64be(
+    0x3F8731ABDD661ADC, 0xA08A5558F0F5D272,
+    0xE953D363CB6F0E5D, 0x405447C01A444533);
+const SSWU_B: GFsecp256k1 = GFsecp256k1::w64be(0, 0, 0, 1771);
+// Z = -11
+const SSWU_Z: GFsecp256k1 = GFsecp256k1::w64be(
+    0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF,
+    0xFFFFFFFFFFFFFFFF, 0xFFFFFFFEFFFFFC24);
+// -B'/A'
+const SSWU_C1: GFsecp256k1 = GFsecp256k1::w64be(
+    0x0BC56CEE718538B2, 0xA00C4DF5D3E87B0C,
+    0x6DF4FF98E82D74FD, 0xAA01D58E8D2345C3);
+// B'/(Z*A')
+const SSWU_C2: GFsecp256k1 = GFsecp256k1::w64be(
+    0xBB407E4438DD90CA, 0x6BA4071659152275,
+    0x7E5C173C7232AD8B, 0x6C8BCD97DE490391);
+
+// Simplified SWU map (RFC 9380, section 6.6.2) onto the curve E'; the
+// affine coordinates of the obtained point are returned.
+fn map_to_curve_sswu(u: &GFsecp256k1) -> (GFsecp256k1, GFsecp256k1) {
+    // tv1 = 1/(Z^2*u^4 + Z*u^2) (or 0, if the denominator is 0)
+    let zu2 = SSWU_Z * u.square();
+    let tv1 = zu2.square() + zu2;
+    let tz = tv1.iszero();
+    let tv1 = GFsecp256k1::ONE / tv1;
+
+    // x1 = (-B/A) * (1 + tv1), or B/(Z*A) if tv1 = 0
+    let mut x1 = SSWU_C1 * (GFsecp256k1::ONE + tv1);
+    x1.set_cond(&SSWU_C2, tz);
+    let gx1 = x1 * (x1.square() + SSWU_A) + SSWU_B;
+
+    // x2 = Z*u^2*x1
+    let x2 = zu2 * x1;
+    let gx2 = x2 * (x2.square() + SSWU_A) + SSWU_B;
+
+    // If gx1 is a square, use (x1, sqrt(gx1)), else (x2, sqrt(gx2)).
+    let (y1, r1) = gx1.sqrt();
+    let (y2, _) = gx2.sqrt();
+    let x = GFsecp256k1::select(&x2, &x1, r1);
+    let mut y = GFsecp256k1::select(&y2, &y1, r1);
+
+    // Adjust the sign of y to match that of u.
+    let ws = (((u.encode()[0] ^ y.encode()[0]) & 1) as u32).wrapping_neg();
+    y.set_cond(&-y, ws);
+    (x, y)
+}
+
+// Constants for the 3-isogeny map from E' to secp256k1 (RFC 9380,
+// appendix E.1).
+const ISO_XNUM: [GFsecp256k1; 4] = [
+    GFsecp256k1::w64be(0x8E38E38E38E38E38, 0xE38E38E38E38E38E,
+                       0x38E38E38E38E38E3, 0x8E38E38DAAAAA8C7),
+    GFsecp256k1::w64be(0x07D3D4C80BC321D5, 0xB9F315CEA7FD44C5,
+                       0xD595D2FC0BF63B92, 0xDFFF1044F17C6581),
+    GFsecp256k1::w64be(0x534C328D23F234E6, 0xE2A413DECA25CAEC,
+                       0xE4506144037C4031, 0x4ECBD0B53D9DD262),
+    GFsecp256k1::w64be(0x8E38E38E38E38E38, 0xE38E38E38E38E38E,
+                       0x38E38E38E38E38E3, 0x8E38E38DAAAAA88C),
+];
+const ISO_XDEN: [GFsecp256k1; 2] = [
+    GFsecp256k1::w64be(0xD35771193D94918A, 0x9CA34CCBB7B640DD,
+                       0x86CD409542F8487D, 0x9FE6B745781EB49B),
+    GFsecp256k1::w64be(0xEDADC6F64383DC1D, 0xF7C4B2D51B542254,
+                       0x06D36B641F5E41BB, 0xC52A56612A8C6D14),
+];
+const ISO_YNUM: [GFsecp256k1; 4] = [
+    GFsecp256k1::w64be(0x4BDA12F684BDA12F, 0x684BDA12F684BDA1,
+                       0x2F684BDA12F684BD, 0xA12F684B8E38E23C),
+    GFsecp256k1::w64be(0xC75E0C32D5CB7C0F, 0xA9D0A54B12A0A6D5,
+                       0x647AB046D686DA6F, 0xDFFC90FC201D71A3),
+    GFsecp256k1::w64be(0x29A6194691F91A73, 0x715209EF6512E576,
+                       0x722830A201BE2018, 0xA765E85A9ECEE931),
+    GFsecp256k1::w64be(0x2F684BDA12F684BD, 0xA12F684BDA12F684,
+                       0xBDA12F684BDA12F6, 0x84BDA12F38E38D84),
+];
+const ISO_YDEN: [GFsecp256k1; 3] = [
+    GFsecp256k1::w64be(0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF,
+                       0xFFFFFFFFFFFFFFFF, 0xFFFFFFFEFFFFF93B),
+    GFsecp256k1::w64be(0x7A06534BB8BDB49F, 0xD5E9E6632722C298,
+                       0x9467C1BFC8E8D978, 0xDFB425D2685C2573),
+    GFsecp256k1::w64be(0x6484AA716545CA2C, 0xF3A70C3FA8FE337E,
+                       0x0A3D21162F0D6299, 0xA7BF8192BFD2A76F),
+];
+
+// 3-isogeny map from E' to secp256k1 (RFC 9380, appendix E.1). The
+// source point is provided in affine coordinates. Exceptional inputs
+// (for which a denominator is zero) map to the point-at-infinity.
+fn iso_map((x, y): &(GFsecp256k1, GFsecp256k1)) -> Point {
+    let x2 = x.square();
+    let x3 = x2 * x;
+    let xn = ISO_XNUM[3] * x3 + ISO_XNUM[2] * x2 + ISO_XNUM[1] * x
+        + ISO_XNUM[0];
+    let xd = x2 + ISO_XDEN[1] * x + ISO_XDEN[0];
+    let yn = ISO_YNUM[3] * x3 + ISO_YNUM[2] * x2 + ISO_YNUM[1] * x
+        + ISO_YNUM[0];
+    let yd = x3 + ISO_YDEN[2] * x2 + ISO_YDEN[1] * x + ISO_YDEN[0];
+
+    // Projective coordinates: x = X/Z and y = Y/Z, with:
+    //   X = xn*yd, Y = y*yn*xd, Z = xd*yd
+    let Z = xd * yd;
+    let mut P = Point { X: xn * yd, Y: y * yn * xd, Z };
+    P.set_cond(&Point::NEUTRAL, Z.iszero());
+    P
+}
+
+// ========================================================================
+
 /// A secp256k1 private key simply wraps around a scalar.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy)]
 pub struct PrivateKey {
     x: Scalar,   // secret scalar
 }
@@ -1691,6 +2440,68 @@ pub struct PublicKey {
     pub point: Point,
 }
 
+/// An ECDSA signature along with its recovery identifier.
+///
+/// The signature uses the raw format (`r || s`, unsigned big-endian,
+/// 32 bytes each). The recovery identifier is in the 0 to 3 range (in
+/// practice, only 0 and 1 are ever obtained); it is the `v` value of
+/// Ethereum signatures, which often add 27 to it.
+#[derive(Clone, Copy, Debug)]
+pub struct RecoverableSignature {
+    pub sig: [u8; 64],
+    pub recid: u8,
+}
+
+/// Accepted encodings for `PublicKey::from_bytes_strict()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParsePolicy {
+    /// Only the compressed encoding (33 bytes, first byte 0x02 or 0x03)
+    /// is accepted, as mandated by some consensus rules.
+    CompressedOnly,
+    /// Any SEC 1 encoding of a non-neutral point is accepted:
+    /// compressed, uncompressed (first byte 0x04) and hybrid (first
+    /// byte 0x06 or 0x07).
+    AnySec1,
+}
+
+/// Error type for `PublicKey::from_bytes_strict()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyError {
+    /// The input length does not match the encoding type.
+    BadLength,
+    /// The first byte (encoding type) is not allowed by the policy, or
+    /// (hybrid encoding) does not match the parity of y.
+    BadPrefix,
+    /// The x coordinate is not lower than the field modulus.
+    XOutOfRange,
+    /// The y coordinate (uncompressed or hybrid encoding) is not lower
+    /// than the field modulus.
+    YOutOfRange,
+    /// There is no curve point with the provided x coordinate
+    /// (compressed encoding), or the coordinates do not match the curve
+    /// equation.
+    NotOnCurve,
+    /// The input is the encoding of the point-at-infinity (a single
+    /// byte of value 0x00), which is never a valid public key.
+    InfinityNotAllowed,
+}
+
+impl core::fmt::Display for KeyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyError::BadLength => f.write_str("invalid public key length"),
+            KeyError::BadPrefix => f.write_str("invalid public key encoding type"),
+            KeyError::XOutOfRange => f.write_str("x coordinate out of range"),
+            KeyError::YOutOfRange => f.write_str("y coordinate out of range"),
+            KeyError::NotOnCurve => f.write_str("not a curve point"),
+            KeyError::InfinityNotAllowed => f.write_str("unexpected point-at-infinity"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyError {}
+
 impl PrivateKey {
 
     /// Generates a new private key from a cryptographically secure RNG.
@@ -1771,6 +2582,38 @@ impl PrivateKey {
         PublicKey { point: Point::mulgen(&self.x) }
     }
 
+    /// Adds a tweak to this private key.
+    ///
+    /// The returned private key has secret scalar `x + t`, where `x` is
+    /// the secret scalar of this key; its public key is the output of
+    /// `PublicKey::tweak_add()` with the same tweak. If the sum is zero,
+    /// then `None` is returned. This function is constant-time;
+    /// side-channels may leak whether the result was zero, but not the
+    /// key or the tweak.
+    pub fn tweak_add(&self, t: &Scalar) -> Option<PrivateKey> {
+        let x = self.x + t;
+        if x.iszero() != 0 {
+            return None;
+        }
+        Some(PrivateKey { x })
+    }
+
+    /// Multiplies this private key by a tweak.
+    ///
+    /// The returned private key has secret scalar `x*t`, where `x` is
+    /// the secret scalar of this key; its public key is the output of
+    /// `PublicKey::tweak_mul()` with the same tweak. If the tweak is
+    /// zero, then `None` is returned. This function is constant-time;
+    /// side-channels may leak whether the tweak was zero, but not the
+    /// key or the tweak.
+    pub fn tweak_mul(&self, t: &Scalar) -> Option<PrivateKey> {
+        let x = self.x * t;
+        if x.iszero() != 0 {
+            return None;
+        }
+        Some(PrivateKey { x })
+    }
+
     /// Signs a hash value with ECDSA.
     ///
     /// The hash value may have an arbitrary length, but in general
@@ -1790,7 +2633,29 @@ impl PrivateKey {
     /// Note: this does not follow the exact process of RFC 6979, but the
     /// same principle is applied.
     pub fn sign_hash(self, hv: &[u8], extra_rand: &[u8]) -> [u8; 64] {
+        self.sign_hash_inner(hv, extra_rand, None)
+    }
+
+    /// Signs a hash value with ECDSA, with blinding.
+    ///
+    /// This computes exactly the same signature as `sign_hash()`, but
+    /// the multiplication of the generator by the per-signature nonce
+    /// is blinded with randomness from the provided RNG (see
+    /// `Point::mulgen_blinded()`). This is meant for keys used on
+    /// hardware shared with potential attackers, where power or
+    /// electromagnetic traces may be observed. The RNG output does not
+    /// influence the signature value.
+    pub fn sign_hash_blinded<T: CryptoRng + RngCore>(self, rng: &mut T,
+        hv: &[u8], extra_rand: &[u8]) -> [u8; 64]
+    {
+        let b = Blinding::new(rng);
+        self.sign_hash_inner(hv, extra_rand, Some(&b))
+    }
 
+    // Signature generation for sign_hash() and sign_hash_blinded().
+    fn sign_hash_inner(self, hv: &[u8], extra_rand: &[u8],
+        blind: Option<&Blinding>) -> [u8; 64]
+    {
         // Convert the input hash value into an integer modulo n:
         //  - If hv.len() > 32, keep only the leftmost 32 bytes.
         //  - Interpret the value as big-endian.
@@ -1819,19 +2684,7 @@ impl PrivateKey {
         k.set_cond(&Scalar::ONE, k.iszero());
 
         loop {
-            // R = k*G; then encode x(R), and decode-reduce as a scalar
-            let R = Point::mulgen(&k);
-            let xR_le = bswap32(&R.encode_compressed()[1..33]);
-            let r = Scalar::decode_reduce(&xR_le);
-
-            // Compute s.
-            let s = (h + self.x * r) / k;
-
-            // If s and r are both non-zero, then we have our signature.
-            if (r.iszero() | s.iszero()) == 0 {
-                let mut sig = [0u8; 64];
-                sig[..32].copy_from_slice(&bswap32(&r.encode()));
-                sig[32..].copy_from_slice(&bswap32(&s.encode()));
+            if let Some((sig, _)) = self.sign_with_k(&h, &k, blind) {
                 return sig;
             }
 
@@ -1843,14 +2696,805 @@ impl PrivateKey {
             k.set_cond(&Scalar::ONE, k.iszero());
         }
     }
-}
-
-impl PublicKey {
 
-    /// Decodes a public key from bytes.
+    /// Signs a hash value with ECDSA, and verifies the signature before
+    /// returning it.
     ///
-    /// This function accepts both compressed (33 bytes) and uncompressed
-    /// (65 bytes) formats. The point is always verified to be a valid
+    /// The signature is computed as with `sign_hash()`, then verified
+    /// against the provided public key `pk`, which MUST be the trusted
+    /// public key of this private key (e.g. the one stored or published
+    /// along with it); it is not recomputed from the private key, so that
+    /// a fault on the private scalar is also detected. If the
+    /// verification fails, which may happen only if the computation was
+    /// disrupted (e.g. by a hardware glitch, or a deliberate fault
+    /// injection) or if `pk` does not match the private key, then
+    /// `SignError::VerificationFailed` is returned and the faulty
+    /// signature is discarded: with deterministic signatures, a faulty
+    /// signature and a correct one for the same message may reveal the
+    /// private key.
+    ///
+    /// The cost is about three times that of `sign_hash()`, since the
+    /// verification dominates.
+    pub fn sign_hash_checked(self, pk: &PublicKey, hv: &[u8],
+        extra_rand: &[u8]) -> Result<[u8; 64], SignError>
+    {
+        check_signature(pk, self.sign_hash(hv, extra_rand), hv)
+    }
+
+    /// Signs a hash value with hedged ECDSA.
+    ///
+    /// Contrary to `sign_hash()`, this function follows the RFC 6979
+    /// process (with HMAC/SHA-256), using the additional input of
+    /// section 3.6: the 32-byte `extra` value is fed into the HMAC_DRBG
+    /// along with the private key and the hash value. This matches the
+    /// nonce derivation of libsecp256k1 when `extra` is provided as its
+    /// extra nonce data (but, unlike libsecp256k1, the `s` value is not
+    /// normalized). The resulting signatures are standard and verifiable
+    /// with `PublicKey::verify_hash()`. If `extra` is freshly random,
+    /// then the per-signature secret nonce is unpredictable even for an
+    /// attacker who can replay the signature computation (e.g. through
+    /// fault attacks or virtual machine snapshots); if `extra` is fixed
+    /// (even all-zero), then the process is still deterministic and
+    /// safe. An all-zero `extra` is not equivalent to plain RFC 6979
+    /// (without additional input).
+    pub fn sign_hedged(self, hv: &[u8], extra: &[u8; 32]) -> [u8; 64] {
+        self.sign_rfc6979::<Sha256>(hv, extra, None).0
+    }
+
+    /// Signs a hash value with ECDSA, with a normalized `s` value.
+    ///
+    /// This function computes the signature as `sign_hash()`, then
+    /// replaces `s` with n - s if s > n/2; the returned signature thus
+    /// always has a low `s` (see `has_low_s()`), as required by
+    /// verifiers that reject high-s signatures (e.g.
+    /// `PublicKey::verify_hash_with_policy()` with `allow_high_s` set to
+    /// `false`). The signature remains valid for `verify_hash()`.
+    pub fn sign_hash_low_s(self, hv: &[u8], extra_rand: &[u8]) -> [u8; 64] {
+        normalize_s(&self.sign_hash(hv, extra_rand)).0
+    }
+
+    /// Signs a hash value with ECDSA, returning a recoverable signature.
+    ///
+    /// The signature generation process follows RFC 6979 (with
+    /// HMAC/SHA-256, and no additional input), then `s` is normalized to
+    /// its low form (i.e. if s > n/2, then it is replaced with n - s),
+    /// as is mandated by Ethereum (EIP-2) and Bitcoin (BIP-62). The
+    /// output is thus identical to that of libsecp256k1 and of the usual
+    /// Ethereum libraries. The returned structure also contains the
+    /// recovery identifier, which allows rebuilding the public key from
+    /// the signature and the hashed message.
+    pub fn sign_recoverable(self, hv: &[u8]) -> RecoverableSignature {
+        let (mut sig, mut recid) = self.sign_rfc6979::<Sha256>(hv, &[], None);
+
+        // If s > n/2, then replace it with n - s; the recovery
+        // identifier is adjusted, since the signature then matches the
+        // point -R instead of R.
+        let (mut s, _) = Scalar::decode32(&bswap32(&sig[32..]));
+        let hi = scalar_is_high(s);
+        s.set_cond(&-s, hi);
+        sig[32..].copy_from_slice(&bswap32(&s.encode()));
+        recid ^= (hi & 1) as u8;
+        RecoverableSignature { sig, recid }
+    }
+
+    /// Signs a prehashed message with ECDSA.
+    ///
+    /// `digest` is the output of the hash function `D` over the message
+    /// (e.g. a SHA-384 or SHA-512 value obtained from an external
+    /// device). It is converted into an integer with the bits2int()
+    /// rules of RFC 6979 (section 2.3.2): if it is longer than 256 bits,
+    /// only its leftmost 256 bits are used; if it is shorter, it is
+    /// interpreted as is (unsigned big-endian). Contrary to
+    /// `sign_hash()`, the per-signature nonce is derived as per RFC 6979,
+    /// with HMAC over `D`; thus, the signature is the RFC 6979 signature
+    /// for the hash function `D` (the `s` value is not normalized). The
+    /// type `D` is used only for nonce derivation; the digest itself is
+    /// not recomputed, and its length is not checked against the output
+    /// length of `D`.
+    pub fn sign_prehashed<D: Digest + BlockSizeUser>(self, digest: &[u8])
+        -> [u8; 64]
+    {
+        self.sign_rfc6979::<D>(digest, &[], None).0
+    }
+
+    /// Signs a message with ECDSA, using the specified hash function.
+    ///
+    /// The message `msg` is hashed with the function identified by
+    /// `hash_id`, and the digest is signed as with `sign_prehashed()`,
+    /// with the same hash function for the RFC 6979 nonce derivation.
+    /// Thus, this is deterministic ECDSA as specified in RFC 6979 for
+    /// that hash function. Note that `sign_hash()` does not follow the
+    /// exact RFC 6979 process, and thus yields different signatures.
+    pub fn sign_with_hash(self, msg: &[u8], hash_id: HashId) -> [u8; 64] {
+        match hash_id {
+            HashId::Sha224 =>
+                self.sign_prehashed::<Sha224>(&Sha224::digest(msg)),
+            HashId::Sha256 =>
+                self.sign_prehashed::<Sha256>(&Sha256::digest(msg)),
+            HashId::Sha384 =>
+                self.sign_prehashed::<Sha384>(&Sha384::digest(msg)),
+            HashId::Sha512 =>
+                self.sign_prehashed::<Sha512>(&Sha512::digest(msg)),
+        }
+    }
+
+    /// Signs a hash value with ECDSA, using the provided nonce.
+    ///
+    /// **This function is catastrophically insecure if misused.** It
+    /// exists only for reproducing externally produced test vectors and
+    /// for audits, and is available only when the (off by default)
+    /// `insecure-test-apis` feature is enabled. The per-signature nonce
+    /// `k` MUST be secret, uniformly random (or derived as per RFC 6979)
+    /// and never reused: two signatures with the same `k`, or a single
+    /// signature with a known or biased `k`, reveal the private key.
+    ///
+    /// The hash value `hv` is processed as in `sign_hash()`. `None` is
+    /// returned if `k` is zero, or if the signature would have `r = 0`
+    /// or `s = 0`.
+    #[cfg(feature = "insecure-test-apis")]
+    pub fn sign_with_nonce(self, hv: &[u8], k: &Scalar) -> Option<[u8; 64]> {
+        self.sign_with_k(&hash_to_scalar(hv), k, None).map(|(sig, _)| sig)
+    }
+
+    // ECDSA signature generation with the nonce k, for the hash value h
+    // (already converted to a scalar). The signature is returned along
+    // with its recovery identifier. If blinding values are provided,
+    // then they are used for computing k*G. None is returned if k is
+    // zero, or if r or s is zero.
+    fn sign_with_k(self, h: &Scalar, k: &Scalar, blind: Option<&Blinding>)
+        -> Option<([u8; 64], u8)>
+    {
+        if k.iszero() != 0 {
+            return None;
+        }
+
+        // R = k*G; then encode x(R), and decode-reduce as a scalar
+        let R = match blind {
+            Some(b) => Point::mulgen_with_blinding(k, b),
+            None => Point::mulgen(k),
+        };
+        let eR = R.encode_compressed();
+        let xR_le = bswap32(&eR[1..33]);
+        let r = Scalar::decode_reduce(&xR_le);
+
+        // Compute s.
+        let s = (*h + self.x * r) / *k;
+
+        // If s and r are both non-zero, then we have our signature.
+        if (r.iszero() | s.iszero()) != 0 {
+            return None;
+        }
+        let mut sig = [0u8; 64];
+        sig[..32].copy_from_slice(&bswap32(&r.encode()));
+        sig[32..].copy_from_slice(&bswap32(&s.encode()));
+
+        // Recovery identifier: bit 0 is the parity of y(R), bit 1 is set
+        // if x(R) >= n.
+        let (_, cx) = Scalar::decode32(&xR_le);
+        let recid = (eR[0] & 1) | ((!cx & 2) as u8);
+        Some((sig, recid))
+    }
+
+    // RFC 6979 signature generation, with HMAC over the hash function D,
+    // optional additional input (section 3.6), and optional blinding.
+    // The signature is returned along with its recovery identifier.
+    fn sign_rfc6979<D: Digest + BlockSizeUser>(self, hv: &[u8], extra: &[u8],
+        blind: Option<&Blinding>) -> ([u8; 64], u8)
+    {
+        // Convert the input hash value into an integer modulo n (h); see
+        // sign_hash(). Since n has size exactly 256 bits, truncation to
+        // the leftmost 32 bytes is bits2int().
+        let h = hash_to_scalar(hv);
+
+        // We loop in case we get a zero for r or s (so improbable that
+        // it won't happen in practice).
+        let mut ng = Rfc6979::<D>::new(&self.x, &h, extra);
+        loop {
+            if let Some(sr) = self.sign_with_k(&h, &ng.next_k(), blind) {
+                return sr;
+            }
+        }
+    }
+}
+
+// Verify-after-sign: the signature sig (over the hash value hv) is
+// returned only if it is valid for the public key pk.
+fn check_signature(pk: &PublicKey, sig: [u8; 64], hv: &[u8])
+    -> Result<[u8; 64], SignError>
+{
+    if pk.verify_hash(&sig, hv) {
+        Ok(sig)
+    } else {
+        Err(SignError::VerificationFailed)
+    }
+}
+
+// Random values for blinded scalar multiplications: the scalar n is
+// split into (n - r) + r, and the initial points of the two partial
+// products have their coordinates scaled by z1 and z2 (both non-zero).
+struct Blinding {
+    r: Scalar,
+    z1: GFsecp256k1,
+    z2: GFsecp256k1,
+}
+
+impl Blinding {
+
+    fn new<T: CryptoRng + RngCore>(rng: &mut T) -> Self {
+        let mut tmp = [0u8; 64];
+        rng.fill_bytes(&mut tmp);
+        let r = Scalar::decode_reduce(&tmp);
+        rng.fill_bytes(&mut tmp);
+        let mut z1 = GFsecp256k1::decode_reduce(&tmp);
+        z1.set_cond(&GFsecp256k1::ONE, z1.iszero());
+        rng.fill_bytes(&mut tmp);
+        let mut z2 = GFsecp256k1::decode_reduce(&tmp);
+        z2.set_cond(&GFsecp256k1::ONE, z2.iszero());
+        Self { r, z1, z2 }
+    }
+}
+
+// RFC 6979 nonce generator (section 3.2), with HMAC over the hash
+// function D, and optional additional input (section 3.6).
+struct Rfc6979<D: Digest + BlockSizeUser> {
+    K: Output<D>,
+    V: Output<D>,
+    started: bool,
+}
+
+impl<D: Digest + BlockSizeUser> Rfc6979<D> {
+
+    // Compute HMAC/D over the concatenation of the provided chunks.
+    // The key has the output length of D, which is never larger than
+    // the block length of D (for all SHA-2 and SHA-3 functions), hence
+    // it is used as is.
+    fn hmac(key: &[u8], data: &[&[u8]]) -> Output<D> {
+        let bs = D::block_size();
+        let mut tmp = [0u8; 256];
+        tmp[..key.len()].copy_from_slice(key);
+        for b in tmp[..bs].iter_mut() {
+            *b ^= 0x36;
+        }
+        let mut sh = D::new();
+        sh.update(&tmp[..bs]);
+        for d in data {
+            sh.update(d);
+        }
+        let v = sh.finalize();
+        for b in tmp[..bs].iter_mut() {
+            *b ^= 0x36 ^ 0x5C;
+        }
+        let mut sh = D::new();
+        sh.update(&tmp[..bs]);
+        sh.update(&v);
+        sh.finalize()
+    }
+
+    // Initializes the generator for the private key x and the hash
+    // value h (steps 3.2.a to 3.2.g). h is re-encoded over 32 bytes, in
+    // unsigned big-endian notation, to get hb (in RFC 6979 notations,
+    // h = bits2int(hv), and hb = bits2octets(hv)).
+    fn new(x: &Scalar, h: &Scalar, extra: &[u8]) -> Self {
+        let hb = bswap32(&h.encode());
+
+        // Get the byte representation of the private key itself.
+        let xb = bswap32(&x.encode());
+
+        // V and K have the output length of D.
+        let mut V = Output::<D>::default();
+        for b in V.iter_mut() {
+            *b = 0x01;
+        }
+        let K = Output::<D>::default();
+
+        // 3.2.d
+        let K = Self::hmac(&K, &[&V, &[0x00u8], &xb, &hb, extra]);
+
+        // 3.2.e
+        V = Self::hmac(&K, &[&V]);
+
+        // 3.2.f
+        let K = Self::hmac(&K, &[&V, &[0x01u8], &xb, &hb, extra]);
+
+        // 3.2.g
+        V = Self::hmac(&K, &[&V]);
+
+        Self { K, V, started: false }
+    }
+
+    // Gets the next candidate nonce (3.2.h). Each call after the first
+    // one first updates K and V as for a rejected candidate; values
+    // which are zero or not lower than n are skipped.
+    fn next_k(&mut self) -> Scalar {
+        loop {
+            if self.started {
+                self.K = Self::hmac(&self.K, &[&self.V, &[0x00u8]]);
+                self.V = Self::hmac(&self.K, &[&self.V]);
+            }
+            self.started = true;
+
+            // Get k: HMAC outputs are concatenated until 256 bits are
+            // obtained, and only the leftmost 256 bits are kept.
+            let mut T = [0u8; 32];
+            let mut tlen = 0;
+            while tlen < 32 {
+                self.V = Self::hmac(&self.K, &[&self.V]);
+                let clen = core::cmp::min(32 - tlen, self.V.len());
+                T[tlen..(tlen + clen)].copy_from_slice(&self.V[..clen]);
+                tlen += clen;
+            }
+            let (k, cc) = Scalar::decode32(&bswap32(&T));
+            if cc != 0 && k.iszero() == 0 {
+                return k;
+            }
+        }
+    }
+}
+
+// Returns 0xFFFFFFFF if the provided scalar is "high" (greater than
+// n/2), 0x00000000 otherwise. For 0 < s < n, 2*s mod n is odd if and only
+// if 2*s >= n, i.e. s > n/2.
+fn scalar_is_high(s: Scalar) -> u32 {
+    (((s + s).encode()[0] & 1) as u32).wrapping_neg()
+}
+
+impl RecoverableSignature {
+
+    /// Encodes this signature over 65 bytes (`r || s || v`), with `v`
+    /// being the recovery identifier (0 or 1, more rarely 2 or 3).
+    pub fn encode(self) -> [u8; 65] {
+        let mut d = [0u8; 65];
+        d[..64].copy_from_slice(&self.sig);
+        d[64] = self.recid;
+        d
+    }
+
+    /// Encodes this signature over 65 bytes (`r || s || v`), with the
+    /// Ethereum convention for `v` (27 + recovery identifier, i.e. 27
+    /// or 28).
+    pub fn encode_eth(self) -> [u8; 65] {
+        let mut d = self.encode();
+        d[64] += 27;
+        d
+    }
+
+    /// Decodes a 65-byte signature (`r || s || v`). The `v` byte may
+    /// use either the plain convention (0 to 3) or the Ethereum
+    /// convention (27 to 30). The values of `r` and `s` are not checked
+    /// here, only when recovering the public key.
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        if buf.len() != 65 {
+            return None;
+        }
+        let recid = match buf[64] {
+            v @ 0..=3 => v,
+            v @ 27..=30 => v - 27,
+            _ => return None,
+        };
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(&buf[..64]);
+        Some(Self { sig, recid })
+    }
+
+    /// Recovers the public key of the signer (Ethereum's `ecrecover`).
+    ///
+    /// If `allow_high_s` is `false`, then signatures whose `s` value is
+    /// not in the low form (s <= n/2) are rejected, as Ethereum does for
+    /// transaction signatures (EIP-2). If `allow_high_s` is `true`,
+    /// then such signatures are accepted; they yield the same public key
+    /// as their normalized counterpart (which has the opposite `y`
+    /// parity bit in its recovery identifier). `None` is returned if
+    /// the signature is invalid.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// signature value is public data.
+    pub fn recover(self, hv: &[u8], allow_high_s: bool) -> Option<PublicKey> {
+        if !allow_high_s {
+            let (s, cs) = Scalar::decode32(&bswap32(&self.sig[32..]));
+            if cs == 0 || scalar_is_high(s) != 0 {
+                return None;
+            }
+        }
+        PublicKey::recover_from_signature(hv, &self.sig, self.recid)
+    }
+}
+
+// Decodes the (r, s) pair of a signature (raw format) as scalars.
+// The two halves are truncated/padded to 32 bytes (verifying that the
+// removed bytes are all zeros), then decoded as scalars. Zeros and
+// out-of-range values are rejected.
+fn decode_rs(sig: &[u8]) -> Option<(Scalar, Scalar)> {
+    let sig_len = sig.len();
+    if (sig_len & 1) != 0 {
+        return None;
+    }
+    let rlen = sig_len >> 1;
+    let mut rb = [0u8; 32];
+    let mut sb = [0u8; 32];
+    if rlen > 32 {
+        for i in 0..(rlen - 32) {
+            if sig[i] != 0 || sig[rlen + i] != 0 {
+                return None;
+            }
+        }
+        rb[..].copy_from_slice(&sig[(rlen - 32)..rlen]);
+        sb[..].copy_from_slice(&sig[(sig_len - 32)..sig_len]);
+    } else {
+        rb[(32 - rlen)..].copy_from_slice(&sig[..rlen]);
+        sb[(32 - rlen)..].copy_from_slice(&sig[rlen..]);
+    }
+    let (r, cr) = Scalar::decode32(&bswap32(&rb));
+    if cr == 0 || r.iszero() != 0 {
+        return None;
+    }
+    let (s, cs) = Scalar::decode32(&bswap32(&sb));
+    if cs == 0 || s.iszero() != 0 {
+        return None;
+    }
+    Some((r, s))
+}
+
+// Rebuilds the point R of a signature from r and the recovery identifier
+// (bit 0 is the parity of y, bit 1 is set if x is r + n instead of r).
+// Point decoding rejects values which are not lower than p, or not the x
+// coordinate of a curve point.
+fn lift_r(r: &Scalar, recid: u8) -> Option<Point> {
+    if recid > 3 {
+        return None;
+    }
+    let mut eR = [0u8; 33];
+    eR[0] = 0x02 | (recid & 1);
+    let rb = bswap32(&r.encode());
+    if (recid & 2) == 0 {
+        eR[1..].copy_from_slice(&rb);
+    } else {
+        // Curve order n, in big-endian.
+        const N: [u8; 32] = [
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
+            0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B,
+            0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36, 0x41, 0x41,
+        ];
+        let mut cc = 0u32;
+        for i in (0..32).rev() {
+            let z = (rb[i] as u32) + (N[i] as u32) + cc;
+            eR[1 + i] = z as u8;
+            cc = z >> 8;
+        }
+        if cc != 0 {
+            return None;
+        }
+    }
+    Point::decode(&eR)
+}
+
+// Converts a hash value into an integer modulo n: if hv.len() > 32, only
+// the leftmost 32 bytes are kept; the value is interpreted with
+// big-endian convention, then reduced modulo n.
+fn hash_to_scalar(hv: &[u8]) -> Scalar {
+    let mut tmp = [0u8; 32];
+    if hv.len() >= 32 {
+        tmp[..].copy_from_slice(&hv[..32]);
+    } else {
+        tmp[32 - hv.len() .. 32].copy_from_slice(hv);
+    }
+    Scalar::decode_reduce(&bswap32(&tmp))
+}
+
+/// Encodes a signature (in the raw 64-byte format produced by
+/// `PrivateKey::sign_hash()`) into ASN.1 DER. The output buffer must
+/// have length at least 72 bytes; the number of written bytes is
+/// returned.
+pub fn signature_to_der(sig: &[u8; 64], out: &mut [u8]) -> usize {
+    der::encode_ecdsa_signature(sig, out)
+}
+
+/// Decodes an ASN.1 DER signature into the raw 64-byte format (as
+/// expected by `PublicKey::verify_hash()`). Decoding is strict, unless
+/// `lenient` is `true`, in which case non-minimal length and integer
+/// encodings are also accepted. The range of `r` and `s` is not
+/// checked here, only at verification time.
+pub fn signature_from_der(src: &[u8], lenient: bool)
+    -> Result<[u8; 64], DerError>
+{
+    let mut sig = [0u8; 64];
+    der::decode_ecdsa_signature(src, &mut sig, lenient)?;
+    Ok(sig)
+}
+
+/// Normalizes the `s` value of a signature (raw 64-byte format).
+///
+/// For any valid signature (r, s), the pair (r, n - s) is also valid
+/// for the same key and message. This function returns the signature
+/// with its low form, i.e. with `s` replaced with n - s if s > n/2,
+/// along with a flag which is `true` if `s` was modified. A signature
+/// whose `s` value is out of range (not lower than n) is returned
+/// unmodified (it will not pass verification anyway).
+pub fn normalize_s(sig: &[u8; 64]) -> ([u8; 64], bool) {
+    let (s, cs) = Scalar::decode32(&bswap32(&sig[32..]));
+    let mut nsig = *sig;
+    if (cs & scalar_is_high(s)) == 0 {
+        return (nsig, false);
+    }
+    nsig[32..].copy_from_slice(&bswap32(&(-s).encode()));
+    (nsig, true)
+}
+
+/// Tests whether the `s` value of a signature (raw 64-byte format) is
+/// in the low form (s <= n/2). Out-of-range values of `s` (not lower
+/// than n) are reported as not low.
+pub fn has_low_s(sig: &[u8; 64]) -> bool {
+    let (s, cs) = Scalar::decode32(&bswap32(&sig[32..]));
+    (cs & !scalar_is_high(s)) != 0
+}
+
+/// Verifies a batch of ECDSA signatures.
+///
+/// Each entry consists of a public key, a hashed message (32 bytes), a
+/// signature (raw 64-byte format) and the recovery identifier of the
+/// signature (as returned by `PublicKey::compute_recovery_id()`). The
+/// returned value is `true` if all signatures are valid, `false`
+/// otherwise (an empty batch is valid).
+///
+/// ECDSA verification only checks the x coordinate of the point
+/// `R_i = u1_i*G + u2_i*Q_i` (with `u1_i = h_i/s_i` and `u2_i = r_i/s_i`);
+/// the recovery identifier allows rebuilding the full point `R_i` from
+/// `r_i` (this is sometimes known as "ECDSA*" verification). The
+/// equations are then combined with random 128-bit coefficients `z_i`
+/// into a single multi-scalar multiplication:
+///
+/// ```text
+///     (sum z_i*u1_i)*G + sum (z_i*u2_i)*Q_i - sum z_i*R_i
+/// ```
+///
+/// and the result is checked to be the point-at-infinity. The
+/// coefficients are derived deterministically from `rng_seed` and the
+/// batch contents, so that no random generator is needed; however, the
+/// seed MUST be unpredictable by whoever produced the signatures (e.g.
+/// it is a fresh random value, or a local secret).
+///
+/// If the batch is accepted, then, for every entry,
+/// `PublicKey::recover_from_signature()` (with the entry's hashed
+/// message, signature and recovery identifier) returns the entry's
+/// public key, and thus `PublicKey::verify_hash()` accepts the
+/// signature, except with negligible probability (about 2^-127).
+/// Conversely, entries which all fulfill that property are always
+/// accepted as a batch. Note that a valid signature with an incorrect
+/// recovery identifier makes the batch fail. When a batch fails,
+/// `verify_batch_find_invalid()` can be used to identify the invalid
+/// entries.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_batch(entries: &[(PublicKey, &[u8; 32], [u8; 64], u8)],
+    rng_seed: &[u8; 32]) -> bool
+{
+    // Decode all signatures, rebuild the points R_i, and compute
+    // u1_i and u2_i. The batch contents are hashed along the way.
+    let n = entries.len();
+    let mut Rs = Vec::with_capacity(n);
+    let mut u1s = Vec::with_capacity(n);
+    let mut u2s = Vec::with_capacity(n);
+    let mut sh = Sha256::new();
+    sh.update(b"crrl-secp256k1-ecdsa-batch");
+    sh.update(rng_seed);
+    sh.update((n as u64).to_le_bytes());
+    for (pkey, hv, sig, recid) in entries.iter() {
+        let (r, s) = match decode_rs(sig) {
+            Some(rs) => rs,
+            None => return false,
+        };
+        let R = match lift_r(&r, *recid) {
+            Some(R) => R,
+            None => return false,
+        };
+        let w = Scalar::ONE / s;
+        Rs.push(R);
+        u1s.push(hash_to_scalar(&hv[..]) * w);
+        u2s.push(r * w);
+        sh.update(pkey.point.encode_compressed());
+        sh.update(hv);
+        sh.update(sig);
+        sh.update([*recid]);
+    }
+    let th = sh.finalize_reset();
+
+    // Terms for points R_i (128-bit coefficients) and Q_i (full
+    // scalars). For each point, we compute a window (odd multiples of
+    // the point, for 5-bit wNAF) and recode the coefficient. The
+    // coefficient of G is accumulated and processed with the
+    // precomputed table.
+    let mut ss = Scalar::ZERO;
+    let mut winR = Vec::with_capacity(n);
+    let mut winQ = Vec::with_capacity(n);
+    let mut sdR = Vec::with_capacity(n);
+    let mut sdQ = Vec::with_capacity(n);
+    let mut zz = [0u8; 32];
+    for i in 0..n {
+        if (i & 1) == 0 {
+            sh.update(th);
+            sh.update((i as u64).to_le_bytes());
+            zz[..].copy_from_slice(&sh.finalize_reset());
+        }
+        // Coefficients are forced to be odd (hence non-zero).
+        let j = (i & 1) << 4;
+        let mut zb = [0u8; 16];
+        zb[..].copy_from_slice(&zz[j..(j + 16)]);
+        zb[0] |= 1;
+        let z = u128::from_le_bytes(zb);
+        let zs = Scalar::decode_reduce(&zb);
+        ss += zs * u1s[i];
+        winR.push(Point::make_window_vartime(&-Rs[i]));
+        sdR.push(Point::recode_u128_NAF(z));
+        winQ.push(Point::make_window_vartime(&entries[i].0.point));
+        sdQ.push(Point::recode_scalar_NAF(&(zs * u2s[i])));
+    }
+    let sd0 = Point::recode_scalar_NAF(&ss);
+
+    // Straus' algorithm: all doublings are shared.
+    let mut T = Point::NEUTRAL;
+    let mut ndbl = 0u32;
+    for i in (0..257).rev() {
+        ndbl += 1;
+        let e = sd0[i];
+        let mut nz = e != 0;
+        if !nz && i < 130 {
+            nz = sdR.iter().any(|sd| sd[i] != 0);
+        }
+        if !nz {
+            nz = sdQ.iter().any(|sd| sd[i] != 0);
+        }
+        if !nz {
+            continue;
+        }
+        T.set_xdouble(ndbl);
+        ndbl = 0;
+
+        if e > 0 {
+            T.set_add_affine(&PRECOMP_G[e as usize - 1], 0);
+        } else if e < 0 {
+            T.set_sub_affine(&PRECOMP_G[(-e) as usize - 1], 0);
+        }
+        for j in 0..n {
+            let f = if i < 130 { sdR[j][i] } else { 0 };
+            if f > 0 {
+                T.set_add(&winR[j][f as usize >> 1]);
+            } else if f < 0 {
+                T.set_sub(&winR[j][(-f) as usize >> 1]);
+            }
+            let f = sdQ[j][i];
+            if f > 0 {
+                T.set_add(&winQ[j][f as usize >> 1]);
+            } else if f < 0 {
+                T.set_sub(&winQ[j][(-f) as usize >> 1]);
+            }
+        }
+    }
+
+    // Remaining doublings are skipped: since the group has prime order,
+    // they do not change whether T is the point-at-infinity.
+    T.isneutral() != 0
+}
+
+/// Finds the invalid entries in a batch of ECDSA signatures.
+///
+/// This function returns the indices (in ascending order) of the
+/// entries that are rejected by `verify_batch()`; the returned vector
+/// is empty if all entries are valid. The batch is split recursively
+/// into halves, and each half is verified with `verify_batch()` (with
+/// the provided seed), so that a large batch with few invalid entries
+/// is processed efficiently. An entry is reported as invalid if its
+/// signature is not valid for its public key and hashed message, or if
+/// its recovery identifier is incorrect.
+///
+/// Note: this function is not constant-time; it assumes that the
+/// public keys and signature values are public data.
+///
+/// Note: this function is available only if heap allocation support
+/// was configured. Only public data is allocated on the heap.
+#[cfg(feature = "alloc")]
+pub fn verify_batch_find_invalid(
+    entries: &[(PublicKey, &[u8; 32], [u8; 64], u8)],
+    rng_seed: &[u8; 32]) -> Vec<usize>
+{
+    fn find_inner(entries: &[(PublicKey, &[u8; 32], [u8; 64], u8)],
+        off: usize, rng_seed: &[u8; 32], bad: &mut Vec<usize>)
+    {
+        if verify_batch(entries, rng_seed) {
+            return;
+        }
+        // A batch of size 1 is equivalent to individual verification
+        // (the coefficient is non-zero and lower than n).
+        if entries.len() == 1 {
+            bad.push(off);
+            return;
+        }
+        let h = entries.len() >> 1;
+        find_inner(&entries[..h], off, rng_seed, bad);
+        find_inner(&entries[h..], off + h, rng_seed, bad);
+    }
+
+    let mut bad = Vec::new();
+    find_inner(entries, 0, rng_seed, &mut bad);
+    bad
+}
+
+// Computes the ECDH shared point, with optional blinding; None is
+// returned if the peer key or the shared point is the point-at-infinity.
+fn ecdh_point(sk: &PrivateKey, peer: &PublicKey, blind: Option<&Blinding>)
+    -> Option<Point>
+{
+    if peer.point.isneutral() != 0 {
+        return None;
+    }
+    let mut S = peer.point;
+    match blind {
+        Some(b) => S.set_mul_with_blinding(&sk.x, b),
+        None => S.set_mul(&sk.x),
+    }
+    if S.isneutral() != 0 {
+        return None;
+    }
+    Some(S)
+}
+
+/// Performs an ECDH key exchange, with libsecp256k1-compatible output.
+///
+/// The shared point is the product of the peer's public key (`peer`)
+/// by the private scalar; the returned shared secret is the SHA-256
+/// hash of the compressed encoding of that point (33 bytes: 0x02 or
+/// 0x03, then the x coordinate), which is the default output of
+/// libsecp256k1's `secp256k1_ecdh()`. The peer's public key is
+/// validated before use: a `PublicKey` instance is always a point on
+/// the curve (this is verified when it is decoded), and the
+/// point-at-infinity is rejected here. `None` is returned if the peer
+/// key or the shared point is the point-at-infinity; since the curve
+/// has prime order, the latter cannot happen with a valid peer key.
+///
+/// The computation is constant-time with regard to the private key.
+pub fn ecdh(sk: &PrivateKey, peer: &PublicKey) -> Option<[u8; 32]> {
+    let S = ecdh_point(sk, peer, None)?;
+    Some(Sha256::digest(S.encode_compressed()).into())
+}
+
+/// Performs an ECDH key exchange, with blinding.
+///
+/// This returns exactly the same output as `ecdh()`, but the
+/// multiplication of the peer's public key by the private scalar is
+/// blinded with randomness from the provided RNG (see
+/// `Point::mul_blinded()`), as a countermeasure against power and
+/// electromagnetic analysis.
+pub fn ecdh_blinded<T: CryptoRng + RngCore>(rng: &mut T,
+    sk: &PrivateKey, peer: &PublicKey) -> Option<[u8; 32]>
+{
+    let S = ecdh_point(sk, peer, Some(&Blinding::new(rng)))?;
+    Some(Sha256::digest(S.encode_compressed()).into())
+}
+
+/// Performs an ECDH key exchange, returning the raw x coordinate of the
+/// shared point.
+///
+/// This is the same process as `ecdh()`, except that the output is the
+/// x coordinate of the shared point (32 bytes, unsigned big-endian), as
+/// specified by SEC 1 (section 3.3.1); it is meant for protocols which
+/// apply their own key derivation function.
+pub fn ecdh_xonly(sk: &PrivateKey, peer: &PublicKey) -> Option<[u8; 32]> {
+    let S = ecdh_point(sk, peer, None)?;
+    let mut d = [0u8; 32];
+    d.copy_from_slice(&S.encode_compressed()[1..]);
+    Some(d)
+}
+
+impl PublicKey {
+
+    /// Decodes a public key from bytes.
+    ///
+    /// This function accepts both compressed (33 bytes) and uncompressed
+    /// (65 bytes) formats. The point is always verified to be a valid
     /// curve point. Note that the neutral point (the
     /// "point-at-infinity") is explicitly rejected.
     pub fn decode(buf: &[u8]) -> Option<Self> {
@@ -1858,87 +3502,2342 @@ impl PublicKey {
         if point.isneutral() != 0 {
             return None;
         }
-        Some(Self { point })
+        Some(Self { point })
+    }
+
+    /// Decodes a public key from bytes, with strict checks and detailed
+    /// error reporting.
+    ///
+    /// The accepted encodings are selected by `policy`. The first byte
+    /// is checked first: if it is not allowed by the policy, then
+    /// `KeyError::BadPrefix` is returned, except for the single-byte
+    /// encoding of the point-at-infinity (0x00), which is reported as
+    /// `KeyError::InfinityNotAllowed`. Then the length must match the
+    /// encoding type (33 bytes for compressed, 65 bytes for uncompressed
+    /// and hybrid), the coordinates must be lower than the field
+    /// modulus, and the point must be on the curve; for the hybrid
+    /// encoding, the low bit of the first byte must match the parity of
+    /// y.
+    ///
+    /// This function uses variable-time code for classifying the
+    /// encoding and reporting errors; it does not leak the value of the
+    /// decoded point. `decode()` remains the constant-time decoding
+    /// function, and accepts the same inputs as this function with
+    /// `ParsePolicy::AnySec1`, except hybrid encodings.
+    pub fn from_bytes_strict(buf: &[u8], policy: ParsePolicy)
+        -> Result<Self, KeyError>
+    {
+        if buf.is_empty() {
+            return Err(KeyError::BadLength);
+        }
+        let len = match (buf[0], policy) {
+            (0x02, _) | (0x03, _) => 33,
+            (0x04, ParsePolicy::AnySec1)
+            | (0x06, ParsePolicy::AnySec1)
+            | (0x07, ParsePolicy::AnySec1) => 65,
+            (0x00, _) if buf.len() == 1 => {
+                return Err(KeyError::InfinityNotAllowed);
+            }
+            _ => { return Err(KeyError::BadPrefix); }
+        };
+        if buf.len() != len {
+            return Err(KeyError::BadLength);
+        }
+        if GFsecp256k1::decode32(&bswap32(&buf[1..33])).1 == 0 {
+            return Err(KeyError::XOutOfRange);
+        }
+        let mut tmp = [0u8; 65];
+        tmp[..len].copy_from_slice(buf);
+        if len == 65 {
+            if GFsecp256k1::decode32(&bswap32(&buf[33..65])).1 == 0 {
+                return Err(KeyError::YOutOfRange);
+            }
+            tmp[0] = 0x04;
+        }
+        let point = match Point::decode(&tmp[..len]) {
+            Some(P) => P,
+            None => { return Err(KeyError::NotOnCurve); }
+        };
+        if (buf[0] & 0xFE) == 0x06 && ((buf[0] ^ buf[64]) & 1) != 0 {
+            return Err(KeyError::BadPrefix);
+        }
+        Ok(Self { point })
+    }
+
+    /// Encodes this public key into the compressed format (33 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x02 or 0x03.
+    pub fn encode_compressed(self) -> [u8; 33] {
+        self.point.encode_compressed()
+    }
+
+    /// Encodes this public key into the compressed format (65 bytes).
+    ///
+    /// The first byte of the encoding always has value 0x04.
+    pub fn encode_uncompressed(self) -> [u8; 65] {
+        self.point.encode_uncompressed()
+    }
+
+    /// Adds a tweak to this public key.
+    ///
+    /// The returned public key is `P + t*G`, where `P` is this public key
+    /// and `G` the conventional generator. If the result is the
+    /// point-at-infinity, then `None` is returned. This function is
+    /// constant-time (the tweak may be secret).
+    pub fn tweak_add(&self, t: &Scalar) -> Option<PublicKey> {
+        let point = self.point + Point::mulgen(t);
+        if point.isneutral() != 0 {
+            return None;
+        }
+        Some(PublicKey { point })
+    }
+
+    /// Multiplies this public key by a tweak.
+    ///
+    /// The returned public key is `t*P`, where `P` is this public key. If
+    /// the tweak is zero, then `None` is returned. This function is
+    /// constant-time (the tweak may be secret).
+    pub fn tweak_mul(&self, t: &Scalar) -> Option<PublicKey> {
+        let point = self.point * t;
+        if point.isneutral() != 0 {
+            return None;
+        }
+        Some(PublicKey { point })
+    }
+
+    /// Verifies a signature on a given hashed message.
+    ///
+    /// The signature (`sig`) MUST have an even length; the first half of
+    /// the signature is interpreted as the "r" integer, while the second
+    /// half is "s" (both use unsigned big-endian convention).
+    /// Out-of-range values are rejected. The hashed message is provided
+    /// as `hv`; it is nominally the output of a suitable hash function
+    /// (often SHA-256) computed over the actual message. This function
+    /// can tolerate arbitrary hash output lengths; however, for proper
+    /// security, the hash output must not be too short, and it must be
+    /// an actual hash function output, not raw structured data.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_hash(self, sig: &[u8], hv: &[u8]) -> bool {
+        self.verify_hash_with_policy(sig, hv, true)
+    }
+
+    /// Verifies a signature on a prehashed message.
+    ///
+    /// `digest` is the hash of the message, computed with an arbitrary
+    /// hash function. It is converted into an integer with the bits2int()
+    /// rules of RFC 6979 (section 2.3.2), as in
+    /// `PrivateKey::sign_prehashed()`: if it is longer than 256 bits,
+    /// only its leftmost 256 bits are used; if it is shorter, it is
+    /// interpreted as is (unsigned big-endian). The signature format is
+    /// the same as with `verify_hash()`, and high-s signatures are
+    /// accepted.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_prehashed(self, digest: &[u8], sig: &[u8]) -> bool {
+        self.verify_hash(sig, digest)
+    }
+
+    /// Verifies a signature on a message, using the specified hash
+    /// function.
+    ///
+    /// The message `msg` is hashed with the function identified by
+    /// `hash_id`, and the signature is verified against the digest as
+    /// with `verify_prehashed()` (high-s signatures are accepted). This
+    /// is the counterpart of `PrivateKey::sign_with_hash()`.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_with_hash(self, sig: &[u8], msg: &[u8], hash_id: HashId)
+        -> bool
+    {
+        match hash_id {
+            HashId::Sha224 => self.verify_hash(sig, &Sha224::digest(msg)),
+            HashId::Sha256 => self.verify_hash(sig, &Sha256::digest(msg)),
+            HashId::Sha384 => self.verify_hash(sig, &Sha384::digest(msg)),
+            HashId::Sha512 => self.verify_hash(sig, &Sha512::digest(msg)),
+        }
+    }
+
+    /// Verifies a signature on a given hashed message, with an explicit
+    /// policy on the `s` value.
+    ///
+    /// This function is similar to `verify_hash()`, except that if
+    /// `allow_high_s` is `false`, then signatures whose `s` value is not
+    /// in the low form (s <= n/2) are rejected. For any valid signature
+    /// (r, s), the pair (r, n - s) is also valid; requiring the low form
+    /// removes that malleability (as is done for Bitcoin transactions,
+    /// see BIP-62 and BIP-146). With `allow_high_s` set to `true`, this
+    /// function behaves exactly as `verify_hash()`.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_hash_with_policy(self, sig: &[u8], hv: &[u8],
+        allow_high_s: bool) -> bool
+    {
+        // Recover r and s as scalars.
+        let (r, s) = match decode_rs(sig) {
+            Some(rs) => rs,
+            None => return false,
+        };
+        if !allow_high_s && scalar_is_high(s) != 0 {
+            return false;
+        }
+
+        // Convert the input hash value into an integer modulo n.
+        let h = hash_to_scalar(hv);
+
+        // Verification algorithm.
+        let w = Scalar::ONE / s;
+        let R = self.point.mul_add_mulgen_vartime(&(r * w), &(h * w));
+        let xR_le = bswap32(&R.encode_compressed()[1..33]);
+        let rr = Scalar::decode_reduce(&xR_le);
+
+        // Signature is valid if the rebuilt r value (in rr) matches
+        // the one that was received.
+        return r.equals(rr) != 0;
+    }
+
+    /// Recovers the public key from a signature and the hashed message.
+    ///
+    /// This implements the public key recovery algorithm of SEC 1
+    /// (section 4.1.6). The signature (`sig`) uses the same format as
+    /// in `verify_hash()`. The recovery identifier (`recid`, in the 0 to
+    /// 3 range) designates which of the (at most four) candidate keys
+    /// is returned: bit 0 is the parity of the y coordinate of the
+    /// point R, and bit 1 is set if the x coordinate of R is r + n
+    /// instead of r (this happens only with negligible probability for
+    /// honestly generated signatures, since p - n < 2^129). The signer
+    /// can obtain the identifier with `compute_recovery_id()`. `None`
+    /// is returned if the signature or the identifier is invalid. A
+    /// returned key is such that the signature is valid for it; the
+    /// caller must still check that the key is the expected one (e.g.
+    /// against a known key fingerprint).
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// signature value is public data.
+    pub fn recover_from_signature(hv: &[u8], sig: &[u8], recid: u8)
+        -> Option<PublicKey>
+    {
+        if recid > 3 {
+            return None;
+        }
+        let (r, s) = decode_rs(sig)?;
+        let h = hash_to_scalar(hv);
+
+        let R = lift_r(&r, recid)?;
+
+        // Q = (s*R - h*G)/r
+        let w = Scalar::ONE / r;
+        let Q = R.mul_add_mulgen_vartime(&(s * w), &-(h * w));
+        if Q.isneutral() != 0 {
+            return None;
+        }
+        Some(PublicKey { point: Q })
+    }
+
+    /// Computes the recovery identifier for a signature.
+    ///
+    /// The returned value is the identifier (in the 0 to 3 range) such
+    /// that `recover_from_signature()`, with the same signature and
+    /// hashed message, returns this public key. `None` is returned if
+    /// the signature is not valid for this public key.
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn compute_recovery_id(self, sig: &[u8], hv: &[u8]) -> Option<u8> {
+        for recid in 0..4 {
+            if let Some(pk) = Self::recover_from_signature(hv, sig, recid) {
+                if pk.point.equals(self.point) != 0 {
+                    return Some(recid);
+                }
+            }
+        }
+        None
+    }
+}
+
+// ========================================================================
+// BIP-340 Schnorr signatures and BIP-341 (Taproot) key tweaking.
+
+/// Parity of the y coordinate of a curve point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+/// A BIP-340 x-only public key.
+///
+/// This is a curve point with an even y coordinate, encoded as its x
+/// coordinate only (32 bytes).
+#[derive(Clone, Copy, Debug)]
+pub struct XOnlyPublicKey {
+    point: Point,   // never the point-at-infinity; y is even
+}
+
+// Computes a BIP-340 tagged hash: SHA-256(SHA-256(tag) || SHA-256(tag) || m)
+// (with m being the concatenation of the provided parts).
+fn tagged_hash(tag: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let th = Sha256::digest(tag);
+    let mut sh = Sha256::new();
+    sh.update(th);
+    sh.update(th);
+    for p in parts.iter() {
+        sh.update(p);
+    }
+    sh.finalize().into()
+}
+
+impl XOnlyPublicKey {
+
+    // Makes an x-only key from a non-infinity point, negating it if
+    // necessary; the parity of the y coordinate of the source point
+    // is returned as a mask (0xFFFFFFFF for odd, 0x00000000 for even).
+    fn from_point(P: Point) -> (Self, u32) {
+        let odd = ((P.encode_compressed()[0] & 1) as u32).wrapping_neg();
+        let mut Q = P;
+        Q.set_condneg(odd);
+        (Self { point: Q }, odd)
+    }
+
+    /// Decodes an x-only public key from 32 bytes.
+    ///
+    /// `None` is returned if the input does not have length exactly
+    /// 32 bytes, or if it is not the x coordinate of a curve point (in
+    /// particular, values not lower than the field modulus are rejected).
+    pub fn decode(buf: &[u8]) -> Option<Self> {
+        if buf.len() != 32 {
+            return None;
+        }
+        let mut eP = [0x02u8; 33];
+        eP[1..].copy_from_slice(buf);
+        Point::decode(&eP).map(|point| Self { point })
+    }
+
+    /// Encodes this x-only public key over exactly 32 bytes (x
+    /// coordinate of the point, unsigned big-endian).
+    pub fn encode(self) -> [u8; 32] {
+        let mut d = [0u8; 32];
+        d.copy_from_slice(&self.point.encode_compressed()[1..]);
+        d
+    }
+
+    /// Decodes an x-only public key from exactly 32 bytes.
+    ///
+    /// This is equivalent to `decode()`, with a fixed-size input.
+    pub fn from_bytes(buf: &[u8; 32]) -> Option<Self> {
+        Self::decode(&buf[..])
+    }
+
+    /// Encodes this x-only public key over exactly 32 bytes.
+    ///
+    /// This is equivalent to `encode()`.
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.encode()
+    }
+
+    /// Gets the x-only version of a public key.
+    ///
+    /// The point is negated if its y coordinate is odd; the parity of
+    /// the y coordinate of the source point is returned along with the
+    /// x-only key, so that the full key can be rebuilt with
+    /// `to_public_key()`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the public key is the point-at-infinity;
+    /// this cannot happen with keys obtained from `PublicKey::decode()`
+    /// or `PrivateKey::to_public_key()`.
+    pub fn from_public_key(pk: &PublicKey) -> (Self, Parity) {
+        assert!(pk.point.isneutral() == 0, "point-at-infinity");
+        let (xP, odd) = Self::from_point(pk.point);
+        (xP, if odd != 0 { Parity::Odd } else { Parity::Even })
+    }
+
+    /// Gets the full public key with this x coordinate, and a y
+    /// coordinate of the provided parity.
+    pub fn to_public_key(self, parity: Parity) -> PublicKey {
+        let mut P = self.point;
+        P.set_condneg(((parity == Parity::Odd) as u32).wrapping_neg());
+        PublicKey { point: P }
+    }
+
+    /// Compares two x-only public keys for equality. Returned value is
+    /// 0xFFFFFFFF if they are equal, 0x00000000 otherwise. This function
+    /// is constant-time.
+    pub fn equals(self, rhs: Self) -> u32 {
+        self.point.equals(rhs.point)
+    }
+
+    /// Verifies a BIP-340 signature on a given message.
+    ///
+    /// The signature (`sig`) must have length exactly 64 bytes. The
+    /// message (`msg`) may have an arbitrary length (BIP-340 signatures
+    /// are normally computed over 32-byte messages, themselves hashes of
+    /// the actual data).
+    ///
+    /// Note: this function is not constant-time; it assumes that the
+    /// public key and signature value are public data.
+    pub fn verify_schnorr(self, sig: &[u8], msg: &[u8]) -> bool {
+        if sig.len() != 64 {
+            return false;
+        }
+        let (s, cs) = Scalar::decode32(&bswap32(&sig[32..]));
+        if cs == 0 {
+            return false;
+        }
+        let epk = self.encode();
+        let e = Scalar::decode_reduce(&bswap32(
+            &tagged_hash(b"BIP0340/challenge", &[&sig[..32], &epk, msg])));
+
+        // R = s*G - e*P; it must not be the point-at-infinity, and it
+        // must have an even y coordinate and an x coordinate equal to
+        // the first signature half (which implies that it is lower than
+        // the field modulus).
+        let R = self.point.mul_add_mulgen_vartime(&-e, &s);
+        if R.isneutral() != 0 {
+            return false;
+        }
+        let eR = R.encode_compressed();
+        eR[0] == 0x02 && eR[1..] == sig[..32]
+    }
+
+    // Computes the Taproot-tweaked key (BIP-341) along with the parity
+    // of its y coordinate; None is returned if the tweak is out of range
+    // or the output is the point-at-infinity.
+    fn tap_tweak_inner(self, merkle_root: Option<&[u8; 32]>)
+        -> Option<(XOnlyPublicKey, Parity)>
+    {
+        let t = tap_tweak_hash(&self.encode(), merkle_root)?;
+        let Q = self.point + Point::mulgen(&t);
+        if Q.isneutral() != 0 {
+            return None;
+        }
+        let (xQ, odd) = Self::from_point(Q);
+        Some((xQ, if odd != 0 { Parity::Odd } else { Parity::Even }))
+    }
+
+    /// Computes the Taproot output key for this internal key (BIP-341).
+    ///
+    /// The tweak is `t = hash_TapTweak(x(P) || merkle_root)` (the Merkle
+    /// root of the script tree is omitted if there is no script path),
+    /// and the output key is `Q = P + t*G`. The x-only version of `Q` is
+    /// returned, along with the parity of its y coordinate (which is
+    /// needed for script-path spending).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `t` is not lower than the curve order, or
+    /// if `Q` is the point-at-infinity; neither case can happen except
+    /// with negligible probability (finding such a key would require
+    /// breaking SHA-256 or the discrete logarithm).
+    pub fn tap_tweak(self, merkle_root: Option<&[u8; 32]>)
+        -> (XOnlyPublicKey, Parity)
+    {
+        self.tap_tweak_inner(merkle_root).expect("invalid Taproot tweak")
+    }
+
+    /// Checks that `output` and `parity` are the Taproot output key
+    /// (and the parity of its y coordinate) for this internal key and
+    /// the provided Merkle root, as is needed for script-path spending
+    /// validation (BIP-341).
+    pub fn check_tweak(self, output: &XOnlyPublicKey, parity: Parity,
+        merkle_root: Option<&[u8; 32]>) -> bool
+    {
+        match self.tap_tweak_inner(merkle_root) {
+            Some((xQ, p)) => p == parity && xQ.equals(*output) != 0,
+            None => false,
+        }
+    }
+}
+
+impl PartialEq for XOnlyPublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.equals(*other) != 0
+    }
+}
+
+impl Eq for XOnlyPublicKey {}
+
+// Computes the BIP-341 tweak value (as a scalar) for the provided
+// internal key; None is returned if the hash value is not lower than the
+// curve order.
+fn tap_tweak_hash(pk: &[u8; 32], merkle_root: Option<&[u8; 32]>)
+    -> Option<Scalar>
+{
+    let th = match merkle_root {
+        Some(root) => tagged_hash(b"TapTweak", &[pk, root]),
+        None => tagged_hash(b"TapTweak", &[pk]),
+    };
+    let (t, ct) = Scalar::decode32(&bswap32(&th));
+    if ct == 0 {
+        return None;
+    }
+    Some(t)
+}
+
+// BIP-340 signature generation with the private scalar d (such that
+// d*G has an even y coordinate, and encodes to epk) and the non-zero
+// nonce k.
+fn schnorr_with_k(d: &Scalar, epk: &[u8; 32], k: &Scalar, msg: &[u8])
+    -> [u8; 64]
+{
+    // R = k*G, normalized to an even y coordinate.
+    let mut k = *k;
+    let (xR, odd) = XOnlyPublicKey::from_point(Point::mulgen(&k));
+    k.set_cond(&-k, odd);
+    let eR = xR.encode();
+
+    // s = k + e*d
+    let e = Scalar::decode_reduce(&bswap32(
+        &tagged_hash(b"BIP0340/challenge", &[&eR, epk, msg])));
+    let s = k + e * *d;
+    let mut sig = [0u8; 64];
+    sig[..32].copy_from_slice(&eR);
+    sig[32..].copy_from_slice(&bswap32(&s.encode()));
+    sig
+}
+
+impl PrivateKey {
+
+    /// Gets the x-only public key (BIP-340) corresponding to this
+    /// private key.
+    pub fn to_x_only_public_key(self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(Point::mulgen(&self.x)).0
+    }
+
+    /// Signs a message with BIP-340 (Schnorr signature).
+    ///
+    /// The message (`msg`) may have an arbitrary length. The auxiliary
+    /// random data (`aux_rand`) should be freshly generated random
+    /// bytes, for protection against side-channel and fault attacks;
+    /// signatures are still safe if it is fixed (e.g. all-zero). The
+    /// signature is returned over exactly 64 bytes.
+    pub fn sign_schnorr(self, msg: &[u8], aux_rand: &[u8; 32]) -> [u8; 64] {
+        // d = x or -x, so that d*G has an even y coordinate.
+        let (xP, odd) = XOnlyPublicKey::from_point(Point::mulgen(&self.x));
+        let mut d = self.x;
+        d.set_cond(&-d, odd);
+        let epk = xP.encode();
+
+        // Nonce derivation.
+        let mut tb = bswap32(&d.encode());
+        let ah = tagged_hash(b"BIP0340/aux", &[aux_rand]);
+        for i in 0..32 {
+            tb[i] ^= ah[i];
+        }
+        let mut k = Scalar::decode_reduce(&bswap32(
+            &tagged_hash(b"BIP0340/nonce", &[&tb, &epk, msg])));
+
+        // If k = 0 (negligible probability), BIP-340 mandates failing;
+        // we use 1 instead, which is still safe.
+        k.set_cond(&Scalar::ONE, k.iszero());
+
+        schnorr_with_k(&d, &epk, &k, msg)
+    }
+
+    /// Signs a message with BIP-340 (Schnorr signature), using the
+    /// provided nonce.
+    ///
+    /// **This function is catastrophically insecure if misused.** It
+    /// exists only for reproducing externally produced test vectors and
+    /// for audits, and is available only when the (off by default)
+    /// `insecure-test-apis` feature is enabled. The nonce `k` MUST be
+    /// secret, uniformly random and never reused: two signatures with
+    /// the same `k`, or a single signature with a known or biased `k`,
+    /// reveal the private key.
+    ///
+    /// As in `sign_schnorr()`, `k` is replaced with `-k` if `k*G` has an
+    /// odd y coordinate; thus, `k` and `-k` yield the same signature.
+    /// `None` is returned if `k` is zero.
+    #[cfg(feature = "insecure-test-apis")]
+    pub fn sign_schnorr_with_nonce(self, msg: &[u8], k: &Scalar)
+        -> Option<[u8; 64]>
+    {
+        if k.iszero() != 0 {
+            return None;
+        }
+        let (xP, odd) = XOnlyPublicKey::from_point(Point::mulgen(&self.x));
+        let mut d = self.x;
+        d.set_cond(&-d, odd);
+        Some(schnorr_with_k(&d, &xP.encode(), k, msg))
+    }
+
+    /// Computes the Taproot-tweaked private key (BIP-341).
+    ///
+    /// The returned private key is such that its x-only public key is
+    /// the output key computed by `XOnlyPublicKey::tap_tweak()` on the
+    /// x-only public key of this private key, with the same Merkle root.
+    /// It can thus be used to make BIP-340 signatures for key-path
+    /// spending.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the tweak is out of range or the tweaked
+    /// key is zero; this cannot happen except with negligible
+    /// probability.
+    pub fn tap_tweak_seckey(self, merkle_root: Option<&[u8; 32]>)
+        -> PrivateKey
+    {
+        let (xP, odd) = XOnlyPublicKey::from_point(Point::mulgen(&self.x));
+        let mut d = self.x;
+        d.set_cond(&-d, odd);
+        let t = tap_tweak_hash(&xP.encode(), merkle_root)
+            .expect("invalid Taproot tweak");
+        let x = d + t;
+        assert!(x.iszero() == 0, "invalid Taproot tweak");
+        PrivateKey { x }
+    }
+}
+
+// The private key is not displayable, and its debug output does not
+// include the secret scalar.
+impl core::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PrivateKey").finish_non_exhaustive()
+    }
+}
+
+// Public keys are displayed as the hexadecimal string of their
+// compressed encoding (33 bytes). Parsing accepts both the compressed
+// (66 hexadecimal digits) and uncompressed (130 digits) formats, with
+// the same checks as `decode()`.
+
+impl core::fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::fmt::LowerHex for PublicKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::hex::fmt_bytes(f, &self.encode_compressed())
+    }
+}
+
+impl core::str::FromStr for PublicKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; 65];
+        let n = if s.len() == 130 { 65 } else { 33 };
+        crate::hex::parse_fixed(s, &mut buf[..n])?;
+        let point = Point::decode_verbose(&buf[..n])
+            .map_err(ParseError::BadPoint)?;
+        if point.isneutral() != 0 {
+            return Err(ParseError::NeutralPoint);
+        }
+        Ok(Self { point })
+    }
+}
+
+// ========================================================================
+
+/// BIP-32 hierarchical deterministic key derivation.
+///
+/// [BIP-32] derives a tree of secp256k1 key pairs from a single seed (16
+/// to 64 bytes; BIP-39 seeds have 64 bytes). Each node of the tree is an
+/// extended key: a private key (`ExtendedPrivateKey`) or public key
+/// (`ExtendedPublicKey`) along with a 32-byte chain code, and some
+/// metadata (depth in the tree, fingerprint of the parent key, and
+/// child number). The master node is obtained from the seed with
+/// `ExtendedPrivateKey::from_seed()`, and children are derived with
+/// `derive_child()`, using HMAC/SHA-512. Child indices with their top
+/// bit set (`index >= 2^31`, noted `i'` in paths) are hardened; hardened
+/// children can only be derived from an extended private key.
+///
+/// `derive_path()` parses a derivation path such as `"m/44'/0'/0'/0/0"`
+/// and performs all derivation steps from the current key (which is the
+/// `m` of the path). The `h` and `H` suffixes are accepted as synonyms
+/// for `'`.
+///
+/// In the (extremely improbable) case that the derivation for index `i`
+/// yields an invalid key, BIP-32 mandates proceeding with the next index;
+/// `derive_child()` does so, and the returned child then reports the
+/// index that was actually used (see `child_number()`). The search never
+/// crosses from non-hardened to hardened indices (or wraps around).
+///
+/// Extended keys are serialized over 78 bytes with `encode()` and
+/// `decode()`; the `Display` and `FromStr` implementations use the
+/// usual Base58Check strings (`xprv...` and `xpub...`). Only the mainnet
+/// version bytes are supported. Decoding verifies the checksum, the
+/// version, that the key is valid, and that a depth-0 key has a zero
+/// parent fingerprint and child number. Base58 conversions are not
+/// constant-time with regard to the encoded value.
+///
+/// [BIP-32]: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+#[cfg(feature = "hmac")]
+pub mod bip32 {
+
+    use super::{Scalar, PrivateKey, PublicKey, bswap32};
+    use crate::hmac::HmacSha512;
+    use sha2::{Sha256, Digest};
+    use core::fmt::Write;
+
+    /// A BIP-32 chain code.
+    pub type ChainCode = [u8; 32];
+
+    /// A key fingerprint (first 4 bytes of the key identifier).
+    pub type Fingerprint = [u8; 4];
+
+    /// Lowest hardened child index (2^31).
+    pub const HARDENED: u32 = 0x80000000;
+
+    /// Version bytes for mainnet extended private keys (`xprv`).
+    pub const VERSION_XPRV: u32 = 0x0488ADE4;
+
+    /// Version bytes for mainnet extended public keys (`xpub`).
+    pub const VERSION_XPUB: u32 = 0x0488B21E;
+
+    /// Length of a serialized extended key (in bytes).
+    pub const ENCODED_LEN: usize = 78;
+
+    /// Error type for BIP-32 derivation and decoding.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Bip32Error {
+        /// The seed length is not in the 16 to 64 bytes range, or the
+        /// seed yields an invalid master key.
+        InvalidSeed,
+        /// The derivation path is malformed.
+        InvalidPath,
+        /// A hardened child was requested from an extended public key.
+        HardenedDerivation,
+        /// The maximum depth (255) has been reached.
+        DepthOverflow,
+        /// No valid child key exists from the requested index up to the
+        /// end of its range (hardened or non-hardened).
+        NoValidChild,
+        /// The string is not valid Base58Check (invalid character, wrong
+        /// length or wrong checksum), or the serialized key does not
+        /// have length 78 bytes.
+        InvalidEncoding,
+        /// The version bytes do not match the expected key type.
+        InvalidVersion,
+        /// The key data is invalid (wrong prefix byte, private key out
+        /// of range, or public key not on the curve).
+        InvalidKey,
+        /// The depth is zero but the parent fingerprint or child number
+        /// is not zero.
+        InvalidDepth,
+    }
+
+    impl core::fmt::Display for Bip32Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Bip32Error::InvalidSeed => f.write_str("invalid seed"),
+                Bip32Error::InvalidPath => f.write_str("invalid derivation path"),
+                Bip32Error::HardenedDerivation => f.write_str("hardened derivation from public key"),
+                Bip32Error::DepthOverflow => f.write_str("maximum depth reached"),
+                Bip32Error::NoValidChild => f.write_str("no valid child key"),
+                Bip32Error::InvalidEncoding => f.write_str("invalid encoding"),
+                Bip32Error::InvalidVersion => f.write_str("invalid version"),
+                Bip32Error::InvalidKey => f.write_str("invalid key data"),
+                Bip32Error::InvalidDepth => f.write_str("invalid depth"),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for Bip32Error {}
+
+    /// An extended private key.
+    #[derive(Clone, Copy)]
+    pub struct ExtendedPrivateKey {
+        sk: PrivateKey,
+        chain_code: ChainCode,
+        depth: u8,
+        parent_fingerprint: Fingerprint,
+        child_number: u32,
+    }
+
+    /// An extended public key.
+    #[derive(Clone, Copy, Debug)]
+    pub struct ExtendedPublicKey {
+        pk: PublicKey,
+        chain_code: ChainCode,
+        depth: u8,
+        parent_fingerprint: Fingerprint,
+        child_number: u32,
+    }
+
+    impl ExtendedPrivateKey {
+
+        /// Computes the master key from a seed.
+        ///
+        /// The seed must have length 16 to 64 bytes. In the (extremely
+        /// improbable) case that the seed yields an invalid master key,
+        /// the `InvalidSeed` error is returned, and another seed must be
+        /// used.
+        pub fn from_seed(seed: &[u8]) -> Result<Self, Bip32Error> {
+            if seed.len() < 16 || seed.len() > 64 {
+                return Err(Bip32Error::InvalidSeed);
+            }
+            let i = HmacSha512::mac(b"Bitcoin seed", seed);
+            let sk = PrivateKey::decode(&i[..32]).ok_or(Bip32Error::InvalidSeed)?;
+            let mut chain_code = [0u8; 32];
+            chain_code[..].copy_from_slice(&i[32..]);
+            Ok(Self {
+                sk,
+                chain_code,
+                depth: 0,
+                parent_fingerprint: [0u8; 4],
+                child_number: 0,
+            })
+        }
+
+        /// Gets the private key.
+        pub fn private_key(&self) -> PrivateKey {
+            self.sk
+        }
+
+        /// Gets the public key.
+        pub fn public_key(&self) -> PublicKey {
+            self.sk.to_public_key()
+        }
+
+        /// Gets the chain code.
+        pub fn chain_code(&self) -> ChainCode {
+            self.chain_code
+        }
+
+        /// Gets the depth (0 for the master key).
+        pub fn depth(&self) -> u8 {
+            self.depth
+        }
+
+        /// Gets the fingerprint of the parent key (all-zeros for the
+        /// master key).
+        pub fn parent_fingerprint(&self) -> Fingerprint {
+            self.parent_fingerprint
+        }
+
+        /// Gets the child number, i.e. the index used to derive this key
+        /// from its parent (0 for the master key).
+        pub fn child_number(&self) -> u32 {
+            self.child_number
+        }
+
+        /// Gets the key identifier (HASH160 of the compressed public
+        /// key).
+        pub fn identifier(&self) -> [u8; 20] {
+            hash160(&self.public_key().encode_compressed())
+        }
+
+        /// Gets the key fingerprint (first 4 bytes of the identifier).
+        pub fn fingerprint(&self) -> Fingerprint {
+            fingerprint(&self.public_key())
+        }
+
+        /// Gets the extended public key that corresponds to this
+        /// extended private key.
+        pub fn to_extended_public_key(&self) -> ExtendedPublicKey {
+            ExtendedPublicKey {
+                pk: self.public_key(),
+                chain_code: self.chain_code,
+                depth: self.depth,
+                parent_fingerprint: self.parent_fingerprint,
+                child_number: self.child_number,
+            }
+        }
+
+        // Computes the child for index i, or returns `None` if the
+        // derived key is invalid.
+        fn ckd_priv(&self, pk: &PublicKey, i: u32)
+            -> Option<(PrivateKey, ChainCode)>
+        {
+            let mut hm = HmacSha512::new(&self.chain_code);
+            if i >= HARDENED {
+                hm.update(&[0x00]);
+                hm.update(&self.sk.encode());
+            } else {
+                hm.update(&pk.encode_compressed());
+            }
+            hm.update(&i.to_be_bytes());
+            let out = hm.finalize();
+            let (il, cc) = Scalar::decode32(&bswap32(&out[..32]));
+            if cc == 0 {
+                return None;
+            }
+            let sk = self.sk.tweak_add(&il)?;
+            let mut chain_code = [0u8; 32];
+            chain_code[..].copy_from_slice(&out[32..]);
+            Some((sk, chain_code))
+        }
+
+        /// Derives a child key.
+        ///
+        /// The index may be hardened (`index >= HARDENED`) or not. If
+        /// the derived key is invalid, the next index is used (this has
+        /// negligible probability).
+        pub fn derive_child(&self, index: u32) -> Result<Self, Bip32Error> {
+            if self.depth == 255 {
+                return Err(Bip32Error::DepthOverflow);
+            }
+            let pk = self.public_key();
+            let ((sk, chain_code), child_number) =
+                next_valid(index, |i| self.ckd_priv(&pk, i))?;
+            Ok(Self {
+                sk,
+                chain_code,
+                depth: self.depth + 1,
+                parent_fingerprint: fingerprint(&pk),
+                child_number,
+            })
+        }
+
+        /// Derives a key from this key and a derivation path.
+        ///
+        /// The path starts with `m` (which designates this key),
+        /// followed by zero or more `/i` or `/i'` elements, where `i` is
+        /// a decimal integer lower than 2^31. A malformed path yields the
+        /// `InvalidPath` error.
+        pub fn derive_path(&self, path: &str) -> Result<Self, Bip32Error> {
+            let mut elts = path.split('/');
+            if elts.next() != Some("m") {
+                return Err(Bip32Error::InvalidPath);
+            }
+            let mut k = *self;
+            for e in elts {
+                k = k.derive_child(parse_index(e)?)?;
+            }
+            Ok(k)
+        }
+
+        /// Encodes this extended key (78 bytes).
+        pub fn encode(&self) -> [u8; ENCODED_LEN] {
+            let mut key = [0u8; 33];
+            key[1..].copy_from_slice(&self.sk.encode());
+            encode_inner(VERSION_XPRV, self.depth, &self.parent_fingerprint,
+                self.child_number, &self.chain_code, &key)
+        }
+
+        /// Decodes an extended private key (78 bytes).
+        pub fn decode(buf: &[u8]) -> Result<Self, Bip32Error> {
+            let (depth, parent_fingerprint, child_number, chain_code, key) =
+                decode_inner(buf, VERSION_XPRV)?;
+            if key[0] != 0x00 {
+                return Err(Bip32Error::InvalidKey);
+            }
+            let sk = PrivateKey::decode(&key[1..])
+                .ok_or(Bip32Error::InvalidKey)?;
+            Ok(Self { sk, chain_code, depth, parent_fingerprint, child_number })
+        }
+    }
+
+    impl ExtendedPublicKey {
+
+        /// Gets the public key.
+        pub fn public_key(&self) -> PublicKey {
+            self.pk
+        }
+
+        /// Gets the chain code.
+        pub fn chain_code(&self) -> ChainCode {
+            self.chain_code
+        }
+
+        /// Gets the depth (0 for the master key).
+        pub fn depth(&self) -> u8 {
+            self.depth
+        }
+
+        /// Gets the fingerprint of the parent key (all-zeros for the
+        /// master key).
+        pub fn parent_fingerprint(&self) -> Fingerprint {
+            self.parent_fingerprint
+        }
+
+        /// Gets the child number, i.e. the index used to derive this key
+        /// from its parent (0 for the master key).
+        pub fn child_number(&self) -> u32 {
+            self.child_number
+        }
+
+        /// Gets the key identifier (HASH160 of the compressed public
+        /// key).
+        pub fn identifier(&self) -> [u8; 20] {
+            hash160(&self.pk.encode_compressed())
+        }
+
+        /// Gets the key fingerprint (first 4 bytes of the identifier).
+        pub fn fingerprint(&self) -> Fingerprint {
+            fingerprint(&self.pk)
+        }
+
+        // Computes the child for index i (non-hardened), or returns
+        // `None` if the derived key is invalid.
+        fn ckd_pub(&self, i: u32) -> Option<(PublicKey, ChainCode)> {
+            let mut hm = HmacSha512::new(&self.chain_code);
+            hm.update(&self.pk.encode_compressed());
+            hm.update(&i.to_be_bytes());
+            let out = hm.finalize();
+            let (il, cc) = Scalar::decode32(&bswap32(&out[..32]));
+            if cc == 0 {
+                return None;
+            }
+            let pk = self.pk.tweak_add(&il)?;
+            let mut chain_code = [0u8; 32];
+            chain_code[..].copy_from_slice(&out[32..]);
+            Some((pk, chain_code))
+        }
+
+        /// Derives a child key.
+        ///
+        /// The index must not be hardened (`index < HARDENED`);
+        /// otherwise, the `HardenedDerivation` error is returned. If the
+        /// derived key is invalid, the next index is used (this has
+        /// negligible probability).
+        pub fn derive_child(&self, index: u32) -> Result<Self, Bip32Error> {
+            if index >= HARDENED {
+                return Err(Bip32Error::HardenedDerivation);
+            }
+            if self.depth == 255 {
+                return Err(Bip32Error::DepthOverflow);
+            }
+            let ((pk, chain_code), child_number) =
+                next_valid(index, |i| self.ckd_pub(i))?;
+            Ok(Self {
+                pk,
+                chain_code,
+                depth: self.depth + 1,
+                parent_fingerprint: self.fingerprint(),
+                child_number,
+            })
+        }
+
+        /// Derives a key from this key and a derivation path.
+        ///
+        /// The path syntax is the same as for
+        /// `ExtendedPrivateKey::derive_path()`; hardened elements yield
+        /// the `HardenedDerivation` error.
+        pub fn derive_path(&self, path: &str) -> Result<Self, Bip32Error> {
+            let mut elts = path.split('/');
+            if elts.next() != Some("m") {
+                return Err(Bip32Error::InvalidPath);
+            }
+            let mut k = *self;
+            for e in elts {
+                k = k.derive_child(parse_index(e)?)?;
+            }
+            Ok(k)
+        }
+
+        /// Encodes this extended key (78 bytes).
+        pub fn encode(&self) -> [u8; ENCODED_LEN] {
+            encode_inner(VERSION_XPUB, self.depth, &self.parent_fingerprint,
+                self.child_number, &self.chain_code,
+                &self.pk.encode_compressed())
+        }
+
+        /// Decodes an extended public key (78 bytes).
+        pub fn decode(buf: &[u8]) -> Result<Self, Bip32Error> {
+            let (depth, parent_fingerprint, child_number, chain_code, key) =
+                decode_inner(buf, VERSION_XPUB)?;
+            if key[0] != 0x02 && key[0] != 0x03 {
+                return Err(Bip32Error::InvalidKey);
+            }
+            let pk = PublicKey::decode(&key).ok_or(Bip32Error::InvalidKey)?;
+            Ok(Self { pk, chain_code, depth, parent_fingerprint, child_number })
+        }
+    }
+
+    // The private key is not displayed by `Debug`; the Base58Check
+    // string (from `Display`) contains it.
+    impl core::fmt::Debug for ExtendedPrivateKey {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("ExtendedPrivateKey")
+                .field("depth", &self.depth)
+                .field("parent_fingerprint", &self.parent_fingerprint)
+                .field("child_number", &self.child_number)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl core::fmt::Display for ExtendedPrivateKey {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            fmt_base58check(f, &self.encode())
+        }
+    }
+
+    impl core::str::FromStr for ExtendedPrivateKey {
+        type Err = Bip32Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Self::decode(&parse_base58check(s)?)
+        }
+    }
+
+    impl core::fmt::Display for ExtendedPublicKey {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            fmt_base58check(f, &self.encode())
+        }
+    }
+
+    impl core::str::FromStr for ExtendedPublicKey {
+        type Err = Bip32Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Self::decode(&parse_base58check(s)?)
+        }
+    }
+
+    // Finds the first index, starting at `index`, for which `f` returns
+    // a value; the search stays in the range (hardened or non-hardened)
+    // of the initial index.
+    fn next_valid<T, F>(index: u32, mut f: F) -> Result<(T, u32), Bip32Error>
+        where F: FnMut(u32) -> Option<T>
+    {
+        let mut i = index;
+        loop {
+            if let Some(r) = f(i) {
+                return Ok((r, i));
+            }
+            i = i.wrapping_add(1);
+            if (i & !HARDENED) == 0 {
+                return Err(Bip32Error::NoValidChild);
+            }
+        }
+    }
+
+    // Parses one element of a derivation path (decimal index, with an
+    // optional hardened suffix).
+    fn parse_index(e: &str) -> Result<u32, Bip32Error> {
+        let (num, hardened) = match e.strip_suffix(['\'', 'h', 'H']) {
+            Some(num) => (num, true),
+            None => (e, false),
+        };
+        if num.is_empty() || num.len() > 10
+            || !num.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(Bip32Error::InvalidPath);
+        }
+        match num.parse::<u32>() {
+            Ok(i) if i < HARDENED => Ok(if hardened { i + HARDENED } else { i }),
+            _ => Err(Bip32Error::InvalidPath),
+        }
+    }
+
+    fn encode_inner(version: u32, depth: u8, parent_fingerprint: &Fingerprint,
+        child_number: u32, chain_code: &ChainCode, key: &[u8; 33])
+        -> [u8; ENCODED_LEN]
+    {
+        let mut buf = [0u8; ENCODED_LEN];
+        buf[0..4].copy_from_slice(&version.to_be_bytes());
+        buf[4] = depth;
+        buf[5..9].copy_from_slice(parent_fingerprint);
+        buf[9..13].copy_from_slice(&child_number.to_be_bytes());
+        buf[13..45].copy_from_slice(chain_code);
+        buf[45..78].copy_from_slice(key);
+        buf
+    }
+
+    // Decodes the common fields of a serialized extended key, and returns
+    // them along with the raw key data (33 bytes).
+    fn decode_inner(buf: &[u8], version: u32)
+        -> Result<(u8, Fingerprint, u32, ChainCode, [u8; 33]), Bip32Error>
+    {
+        if buf.len() != ENCODED_LEN {
+            return Err(Bip32Error::InvalidEncoding);
+        }
+        let mut tmp = [0u8; 4];
+        tmp.copy_from_slice(&buf[0..4]);
+        if u32::from_be_bytes(tmp) != version {
+            return Err(Bip32Error::InvalidVersion);
+        }
+        let depth = buf[4];
+        let mut parent_fingerprint = [0u8; 4];
+        parent_fingerprint.copy_from_slice(&buf[5..9]);
+        tmp.copy_from_slice(&buf[9..13]);
+        let child_number = u32::from_be_bytes(tmp);
+        if depth == 0 && (parent_fingerprint != [0u8; 4] || child_number != 0) {
+            return Err(Bip32Error::InvalidDepth);
+        }
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&buf[13..45]);
+        let mut key = [0u8; 33];
+        key.copy_from_slice(&buf[45..78]);
+        Ok((depth, parent_fingerprint, child_number, chain_code, key))
+    }
+
+    fn fingerprint(pk: &PublicKey) -> Fingerprint {
+        let mut fp = [0u8; 4];
+        fp.copy_from_slice(&hash160(&pk.encode_compressed())[..4]);
+        fp
+    }
+
+    // HASH160 = RIPEMD-160(SHA-256(data)).
+    fn hash160(data: &[u8]) -> [u8; 20] {
+        ripemd160(&Sha256::digest(data))
+    }
+
+    // Base58Check: 78-byte payload followed by the first 4 bytes of
+    // SHA-256(SHA-256(payload)), encoded in Base58.
+    fn checksum(payload: &[u8]) -> [u8; 4] {
+        let mut c = [0u8; 4];
+        c.copy_from_slice(&Sha256::digest(Sha256::digest(payload))[..4]);
+        c
+    }
+
+    fn fmt_base58check(f: &mut core::fmt::Formatter<'_>,
+        payload: &[u8; ENCODED_LEN]) -> core::fmt::Result
+    {
+        let mut buf = [0u8; ENCODED_LEN + 4];
+        buf[..ENCODED_LEN].copy_from_slice(payload);
+        buf[ENCODED_LEN..].copy_from_slice(&checksum(payload));
+        let mut s = [0u8; B58_MAX_LEN];
+        let n = base58_encode(&buf, &mut s);
+        for &c in s[..n].iter() {
+            f.write_char(c as char)?;
+        }
+        Ok(())
+    }
+
+    fn parse_base58check(s: &str) -> Result<[u8; ENCODED_LEN], Bip32Error> {
+        let buf = base58_decode(s.as_bytes())
+            .ok_or(Bip32Error::InvalidEncoding)?;
+        let mut payload = [0u8; ENCODED_LEN];
+        payload.copy_from_slice(&buf[..ENCODED_LEN]);
+        if checksum(&payload) != buf[ENCODED_LEN..] {
+            return Err(Bip32Error::InvalidEncoding);
+        }
+        Ok(payload)
+    }
+
+    const B58_ALPHABET: &[u8; 58] =
+        b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    // Maximum length of the Base58 encoding of 82 bytes
+    // (58^112 > 2^656).
+    const B58_MAX_LEN: usize = 112;
+
+    // Encodes 82 bytes in Base58 (each leading zero byte yields a '1'
+    // character). The string is written at the start of `dst`, and its
+    // length is returned.
+    fn base58_encode(src: &[u8; ENCODED_LEN + 4], dst: &mut [u8; B58_MAX_LEN])
+        -> usize
+    {
+        // Base-58 digits, in little-endian order.
+        let mut digits = [0u8; B58_MAX_LEN];
+        for &b in src.iter() {
+            let mut carry = b as u32;
+            for d in digits.iter_mut() {
+                carry += (*d as u32) << 8;
+                *d = (carry % 58) as u8;
+                carry /= 58;
+            }
+        }
+        let zeros = src.iter().take_while(|&&b| b == 0).count();
+        let mut n = B58_MAX_LEN;
+        while n > 0 && digits[n - 1] == 0 {
+            n -= 1;
+        }
+        dst[..zeros].fill(b'1');
+        for (c, &d) in dst[zeros..zeros + n].iter_mut()
+            .zip(digits[..n].iter().rev())
+        {
+            *c = B58_ALPHABET[d as usize];
+        }
+        zeros + n
+    }
+
+    // Decodes a Base58 string into exactly 82 bytes. The encoding must be
+    // canonical (i.e. the string must be exactly what `base58_encode()`
+    // would produce for the decoded bytes).
+    fn base58_decode(s: &[u8]) -> Option<[u8; ENCODED_LEN + 4]> {
+        if s.len() > B58_MAX_LEN {
+            return None;
+        }
+        // Big-endian output.
+        let mut out = [0u8; ENCODED_LEN + 4];
+        for &c in s.iter() {
+            let mut carry = B58_ALPHABET.iter().position(|&a| a == c)? as u32;
+            for b in out.iter_mut().rev() {
+                carry += (*b as u32) * 58;
+                *b = carry as u8;
+                carry >>= 8;
+            }
+            if carry != 0 {
+                return None;
+            }
+        }
+        let mut tmp = [0u8; B58_MAX_LEN];
+        let n = base58_encode(&out, &mut tmp);
+        if &tmp[..n] != s {
+            return None;
+        }
+        Some(out)
     }
 
-    /// Encodes this public key into the compressed format (33 bytes).
+    // RIPEMD-160 (used only for HASH160). Message word selection and
+    // rotation amounts for the left and right lines.
+    const RMD_R1: [u8; 80] = [
+         0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
+         7,  4, 13,  1, 10,  6, 15,  3, 12,  0,  9,  5,  2, 14, 11,  8,
+         3, 10, 14,  4,  9, 15,  8,  1,  2,  7,  0,  6, 13, 11,  5, 12,
+         1,  9, 11, 10,  0,  8, 12,  4, 13,  3,  7, 15, 14,  5,  6,  2,
+         4,  0,  5,  9,  7, 12,  2, 10, 14,  1,  3,  8, 11,  6, 15, 13,
+    ];
+    const RMD_R2: [u8; 80] = [
+         5, 14,  7,  0,  9,  2, 11,  4, 13,  6, 15,  8,  1, 10,  3, 12,
+         6, 11,  3,  7,  0, 13,  5, 10, 14, 15,  8, 12,  4,  9,  1,  2,
+        15,  5,  1,  3,  7, 14,  6,  9, 11,  8, 12,  2, 10,  0,  4, 13,
+         8,  6,  4,  1,  3, 11, 15,  0,  5, 12,  2, 13,  9,  7, 10, 14,
+        12, 15, 10,  4,  1,  5,  8,  7,  6,  2, 13, 14,  0,  3,  9, 11,
+    ];
+    const RMD_S1: [u8; 80] = [
+        11, 14, 15, 12,  5,  8,  7,  9, 11, 13, 14, 15,  6,  7,  9,  8,
+         7,  6,  8, 13, 11,  9,  7, 15,  7, 12, 15,  9, 11,  7, 13, 12,
+        11, 13,  6,  7, 14,  9, 13, 15, 14,  8, 13,  6,  5, 12,  7,  5,
+        11, 12, 14, 15, 14, 15,  9,  8,  9, 14,  5,  6,  8,  6,  5, 12,
+         9, 15,  5, 11,  6,  8, 13, 12,  5, 12, 13, 14, 11,  8,  5,  6,
+    ];
+    const RMD_S2: [u8; 80] = [
+         8,  9,  9, 11, 13, 15, 15,  5,  7,  7,  8, 11, 14, 14, 12,  6,
+         9, 13, 15,  7, 12,  8,  9, 11,  7,  7, 12,  7,  6, 15, 13, 11,
+         9,  7, 15, 11,  8,  6,  6, 14, 12, 13,  5, 14, 13, 13,  7,  5,
+        15,  5,  8, 11, 14, 14,  6, 14,  6,  9, 12,  9, 12,  5, 15,  8,
+         8,  5, 12,  9, 12,  5, 14,  6,  8, 13,  6,  5, 15, 13, 11, 11,
+    ];
+    const RMD_K1: [u32; 5] = [
+        0x00000000, 0x5A827999, 0x6ED9EBA1, 0x8F1BBCDC, 0xA953FD4E,
+    ];
+    const RMD_K2: [u32; 5] = [
+        0x50A28BE6, 0x5C4DD124, 0x6D703EF3, 0x7A6D76E9, 0x00000000,
+    ];
+
+    // Boolean function for round j (0 to 4).
+    fn rmd_f(j: usize, x: u32, y: u32, z: u32) -> u32 {
+        match j {
+            0 => x ^ y ^ z,
+            1 => (x & y) | (!x & z),
+            2 => (x | !y) ^ z,
+            3 => (x & z) | (y & !z),
+            _ => x ^ (y | !z),
+        }
+    }
+
+    fn rmd_block(h: &mut [u32; 5], block: &[u8]) {
+        let mut x = [0u32; 16];
+        for (w, c) in x.iter_mut().zip(block.chunks_exact(4)) {
+            *w = u32::from_le_bytes([c[0], c[1], c[2], c[3]]);
+        }
+        let (mut a1, mut b1, mut c1, mut d1, mut e1) =
+            (h[0], h[1], h[2], h[3], h[4]);
+        let (mut a2, mut b2, mut c2, mut d2, mut e2) =
+            (h[0], h[1], h[2], h[3], h[4]);
+        for j in 0..80 {
+            let r = j >> 4;
+            let t = a1.wrapping_add(rmd_f(r, b1, c1, d1))
+                .wrapping_add(x[RMD_R1[j] as usize])
+                .wrapping_add(RMD_K1[r])
+                .rotate_left(RMD_S1[j] as u32)
+                .wrapping_add(e1);
+            a1 = e1;
+            e1 = d1;
+            d1 = c1.rotate_left(10);
+            c1 = b1;
+            b1 = t;
+            let t = a2.wrapping_add(rmd_f(4 - r, b2, c2, d2))
+                .wrapping_add(x[RMD_R2[j] as usize])
+                .wrapping_add(RMD_K2[r])
+                .rotate_left(RMD_S2[j] as u32)
+                .wrapping_add(e2);
+            a2 = e2;
+            e2 = d2;
+            d2 = c2.rotate_left(10);
+            c2 = b2;
+            b2 = t;
+        }
+        let t = h[1].wrapping_add(c1).wrapping_add(d2);
+        h[1] = h[2].wrapping_add(d1).wrapping_add(e2);
+        h[2] = h[3].wrapping_add(e1).wrapping_add(a2);
+        h[3] = h[4].wrapping_add(a1).wrapping_add(b2);
+        h[4] = h[0].wrapping_add(b1).wrapping_add(c2);
+        h[0] = t;
+    }
+
+    fn ripemd160(data: &[u8]) -> [u8; 20] {
+        let mut h: [u32; 5] = [
+            0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0,
+        ];
+        let mut blocks = data.chunks_exact(64);
+        for b in &mut blocks {
+            rmd_block(&mut h, b);
+        }
+        let rem = blocks.remainder();
+        let mut buf = [0u8; 128];
+        buf[..rem.len()].copy_from_slice(rem);
+        buf[rem.len()] = 0x80;
+        let n = if rem.len() < 56 { 64 } else { 128 };
+        buf[n - 8..n].copy_from_slice(&((data.len() as u64) << 3).to_le_bytes());
+        for b in buf[..n].chunks_exact(64) {
+            rmd_block(&mut h, b);
+        }
+        let mut out = [0u8; 20];
+        for (o, w) in out.chunks_exact_mut(4).zip(h.iter()) {
+            o.copy_from_slice(&w.to_le_bytes());
+        }
+        out
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::{ripemd160, base58_encode, base58_decode, next_valid};
+        use super::{Bip32Error, HARDENED};
+
+        #[test]
+        fn ripemd160_kat() {
+            const KAT: [(&[u8], &str); 5] = [
+                (b"", "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+                (b"abc", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+                (b"message digest",
+                 "5d0689ef49d2fae572b881b123a85ffa21595f36"),
+                (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                 "12a053384a9c0c88e405a06c27dcf49ada62eb2b"),
+                (b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                 "9b752e45573d4b39f4dbd3323cab82bf63326bfb"),
+            ];
+            for (msg, exp) in KAT.iter() {
+                assert!(ripemd160(msg)[..] == hex::decode(exp).unwrap());
+            }
+        }
+
+        #[test]
+        fn base58() {
+            let mut buf = [0u8; 82];
+            for (i, b) in buf.iter_mut().enumerate() {
+                *b = (i as u8).wrapping_mul(37).wrapping_add(11);
+            }
+            for z in [0, 1, 5, 82] {
+                let mut src = buf;
+                src[..z].fill(0);
+                let mut s = [0u8; 112];
+                let n = base58_encode(&src, &mut s);
+                assert!(s[..z].iter().all(|&c| c == b'1'));
+                assert!(base58_decode(&s[..n]) == Some(src));
+
+                // Non-canonical (extra leading '1') or invalid characters.
+                let mut t = [b'1'; 113];
+                t[1..n + 1].copy_from_slice(&s[..n]);
+                assert!(base58_decode(&t[..n + 1]).is_none());
+                if n > 0 {
+                    for c in [b'0', b'O', b'I', b'l', b'+'] {
+                        let mut t = s;
+                        t[n - 1] = c;
+                        assert!(base58_decode(&t[..n]).is_none());
+                    }
+                }
+            }
+
+            // Too large for 82 bytes.
+            assert!(base58_decode(&[b'z'; 112]).is_none());
+        }
+
+        #[test]
+        fn skip_invalid() {
+            // Invalid keys are skipped, within the same range.
+            assert!(next_valid(7, |i| if i >= 10 { Some(i) } else { None })
+                == Ok((10, 10)));
+            assert!(next_valid(HARDENED - 2, |i| {
+                if i >= HARDENED { Some(i) } else { None }
+            }) == Err(Bip32Error::NoValidChild));
+            assert!(next_valid(0xFFFFFFFE, |i| {
+                if i < HARDENED { Some(i) } else { None }
+            }) == Err(Bip32Error::NoValidChild));
+            assert!(next_valid(HARDENED + 3, Some) == Ok((HARDENED + 3, HARDENED + 3)));
+        }
+    }
+}
+
+// ========================================================================
+
+/// MuSig2 multi-signatures (BIP-327).
+///
+/// [BIP-327] MuSig2 lets `n` signers, each with their own secp256k1 key
+/// pair, jointly produce a single [BIP-340] signature that verifies
+/// against an aggregate public key (n-of-n). The protocol has two
+/// rounds:
+///
+///  1. Each signer generates a nonce pair with `NonceGen`, keeps the
+///     `SecNonce` and sends the `PubNonce` to the others; all public
+///     nonces are then combined with `nonce_agg()`.
+///
+///  2. With the `KeyAggContext` (aggregation of all signers' public
+///     keys, possibly tweaked with `apply_tweak()`), the aggregate nonce
+///     and the message, each signer builds a `Session` and computes a
+///     partial signature with `Session::partial_sign()`. Partial
+///     signatures can be checked individually with
+///     `Session::partial_sig_verify()`, and are combined into the final
+///     signature with `Session::partial_sig_agg()`.
+///
+/// The final signature is verified with `XOnlyPublicKey::verify_schnorr()`
+/// against `KeyAggContext::x_only_public_key()`.
+///
+/// A secret nonce MUST NOT be used twice; a `SecNonce` cannot be copied
+/// or serialized, and it is consumed by `partial_sign()`. Nonce
+/// generation mixes fresh randomness with the optional inputs of
+/// NonceGen (secret key, aggregate key, message and extra input); the
+/// deterministic variant `NonceGen::generate_with_rand()` exists for
+/// test vectors, and is safe only if the random input is never reused.
+///
+/// Operations that involve the secret key or secret nonces are
+/// constant-time; all other operations (on public keys, public nonces
+/// and partial signatures) assume public data.
+///
+/// [BIP-327]: https://github.com/bitcoin/bips/blob/master/bip-0327.mediawiki
+/// [BIP-340]: https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+#[cfg(feature = "alloc")]
+pub mod musig2 {
+
+    use super::{Point, Scalar, PrivateKey, PublicKey, XOnlyPublicKey};
+    use super::{tagged_hash, bswap32};
+    use crate::{CryptoRng, RngCore, Vec};
+
+    /// A public nonce (two compressed points, 66 bytes).
+    pub type PubNonce = [u8; 66];
+
+    /// An aggregate nonce (two compressed points, 66 bytes; a
+    /// point-at-infinity is encoded as 33 zeros).
+    pub type AggNonce = [u8; 66];
+
+    /// A partial signature (scalar, 32 bytes, unsigned big-endian).
+    pub type PartialSignature = [u8; 32];
+
+    /// Error type for MuSig2 operations.
     ///
-    /// The first byte of the encoding always has value 0x02 or 0x03.
-    pub fn encode_compressed(self) -> [u8; 33] {
-        self.point.encode_compressed()
+    /// Errors that can be blamed on a given participant include the
+    /// index of that participant (in the list provided to the function
+    /// that reported the error).
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum MuSig2Error {
+        /// The list of public keys is empty, or a public key is the
+        /// point-at-infinity.
+        InvalidPublicKey(usize),
+        /// A public nonce is invalid.
+        InvalidPubNonce(usize),
+        /// The aggregate nonce is invalid.
+        InvalidAggNonce,
+        /// A partial signature is out of range.
+        InvalidPartialSignature(usize),
+        /// The tweak is out of range, or the tweaked key is the
+        /// point-at-infinity.
+        InvalidTweak,
+        /// The secret key does not match the secret nonce.
+        PublicKeyMismatch,
+        /// The signer's public key is not part of the aggregate key.
+        UnknownSigner,
     }
 
-    /// Encodes this public key into the compressed format (65 bytes).
+    impl core::fmt::Display for MuSig2Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                MuSig2Error::InvalidPublicKey(i) => write!(f, "invalid public key (signer {})", i),
+                MuSig2Error::InvalidPubNonce(i) => write!(f, "invalid public nonce (signer {})", i),
+                MuSig2Error::InvalidAggNonce => f.write_str("invalid aggregate nonce"),
+                MuSig2Error::InvalidPartialSignature(i) => write!(f, "invalid partial signature (signer {})", i),
+                MuSig2Error::InvalidTweak => f.write_str("invalid tweak"),
+                MuSig2Error::PublicKeyMismatch => f.write_str("secret key does not match nonce"),
+                MuSig2Error::UnknownSigner => f.write_str("signer not in key list"),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for MuSig2Error {}
+
+    /// A key aggregation context.
     ///
-    /// The first byte of the encoding always has value 0x04.
-    pub fn encode_uncompressed(self) -> [u8; 65] {
-        self.point.encode_uncompressed()
+    /// This contains the aggregate public key of a list of signers,
+    /// along with the accumulated tweaks.
+    #[derive(Clone, Debug)]
+    pub struct KeyAggContext {
+        Q: Point,                  // aggregate (tweaked) key
+        gacc: Scalar,              // accumulated sign (1 or -1)
+        tacc: Scalar,              // accumulated tweak
+        pubkeys: Vec<[u8; 33]>,    // signers' keys (compressed)
+        L: [u8; 32],               // hash of the list of keys
+        pk2: [u8; 33],             // second key (or zeros)
     }
 
-    /// Verifies a signature on a given hashed message.
+    /// A nonce generator.
     ///
-    /// The signature (`sig`) MUST have an even length; the first half of
-    /// the signature is interpreted as the "r" integer, while the second
-    /// half is "s" (both use unsigned big-endian convention).
-    /// Out-of-range values are rejected. The hashed message is provided
-    /// as `hv`; it is nominally the output of a suitable hash function
-    /// (often SHA-256) computed over the actual message. This function
-    /// can tolerate arbitrary hash output lengths; however, for proper
-    /// security, the hash output must not be too short, and it must be
-    /// an actual hash function output, not raw structured data.
+    /// The signer's public key is mandatory; the other inputs are
+    /// optional but recommended, since they provide extra protection if
+    /// the random source is flawed.
+    #[derive(Clone, Copy, Debug)]
+    pub struct NonceGen<'a> {
+        pk: [u8; 33],
+        sk: Option<&'a PrivateKey>,
+        aggpk: Option<[u8; 32]>,
+        msg: Option<&'a [u8]>,
+        extra_in: Option<&'a [u8]>,
+    }
+
+    /// A secret nonce.
     ///
-    /// Note: this function is not constant-time; it assumes that the
-    /// public key and signature value are public data.
-    pub fn verify_hash(self, sig: &[u8], hv: &[u8]) -> bool {
-        // Recover r and s as scalars. We truncate/pad them to 32 bytes
-        // (verifying that the removed bytes are all zeros), then decode
-        // them as scalars. Zeros and out-of-range values are rejected.
-        let sig_len = sig.len();
-        if (sig_len & 1) != 0 {
-            return false;
+    /// This type is intentionally neither `Clone` nor `Copy`, and has no
+    /// encoding function: each secret nonce is used for exactly one
+    /// partial signature, and is consumed by `Session::partial_sign()`.
+    pub struct SecNonce {
+        k1: Scalar,
+        k2: Scalar,
+        pk: [u8; 33],
+    }
+
+    /// A signing session.
+    ///
+    /// The session binds a key aggregation context, an aggregate nonce
+    /// and a message.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Session<'a> {
+        ctx: &'a KeyAggContext,
+        b: Scalar,
+        R: Point,
+        e: Scalar,
+    }
+
+    // Returns true if the y coordinate of the point is odd. The point
+    // must not be the point-at-infinity.
+    fn has_odd_y(P: Point) -> bool {
+        P.encode_compressed()[0] == 0x03
+    }
+
+    fn xbytes(P: Point) -> [u8; 32] {
+        let mut d = [0u8; 32];
+        d.copy_from_slice(&P.encode_compressed()[1..]);
+        d
+    }
+
+    // Decodes a compressed point; the point-at-infinity is rejected.
+    fn cpoint(buf: &[u8]) -> Option<Point> {
+        if buf[0] != 0x02 && buf[0] != 0x03 {
+            return None;
+        }
+        Point::decode(buf)
+    }
+
+    // Decodes a compressed point; 33 zeros decode to the
+    // point-at-infinity.
+    fn cpoint_ext(buf: &[u8]) -> Option<Point> {
+        if buf.iter().all(|&b| b == 0) {
+            return Some(Point::NEUTRAL);
         }
-        let rlen = sig_len >> 1;
-        let mut rb = [0u8; 32];
-        let mut sb = [0u8; 32];
-        if rlen > 32 {
-            for i in 0..(rlen - 32) {
-                if sig[i] != 0 || sig[rlen + i] != 0 {
-                    return false;
+        cpoint(buf)
+    }
+
+    // Interprets a hash output (big-endian) as a scalar, reduced.
+    fn hash_to_scalar(h: &[u8; 32]) -> Scalar {
+        Scalar::decode_reduce(&bswap32(h))
+    }
+
+    impl KeyAggContext {
+
+        /// Aggregates a list of public keys.
+        ///
+        /// The order of the keys matters (a different order yields a
+        /// different aggregate key); duplicates are allowed. An error is
+        /// returned if the list is empty, or if a key is the
+        /// point-at-infinity.
+        pub fn new(pubkeys: &[PublicKey]) -> Result<Self, MuSig2Error> {
+            let mut pks = Vec::with_capacity(pubkeys.len());
+            for (i, pk) in pubkeys.iter().enumerate() {
+                if pk.point.isneutral() != 0 {
+                    return Err(MuSig2Error::InvalidPublicKey(i));
                 }
+                pks.push(pk.encode_compressed());
             }
-            rb[..].copy_from_slice(&sig[(rlen - 32)..rlen]);
-            sb[..].copy_from_slice(&sig[(sig_len - 32)..sig_len]);
-        } else {
-            rb[(32 - rlen)..].copy_from_slice(&sig[..rlen]);
-            sb[(32 - rlen)..].copy_from_slice(&sig[rlen..]);
+            if pks.is_empty() {
+                return Err(MuSig2Error::InvalidPublicKey(0));
+            }
+            let parts: Vec<&[u8]> = pks.iter().map(|pk| &pk[..]).collect();
+            let L = tagged_hash(b"KeyAgg list", &parts);
+
+            // The second distinct key uses coefficient 1.
+            let pk2 = pks.iter().find(|&pk| *pk != pks[0])
+                .copied().unwrap_or([0u8; 33]);
+            let mut ctx = Self {
+                Q: Point::NEUTRAL,
+                gacc: Scalar::ONE,
+                tacc: Scalar::ZERO,
+                pubkeys: Vec::new(),
+                L,
+                pk2,
+            };
+            let mut Q = Point::NEUTRAL;
+            for pk in pubkeys.iter() {
+                Q += pk.point * ctx.coefficient(&pk.encode_compressed());
+            }
+            if Q.isneutral() != 0 {
+                return Err(MuSig2Error::InvalidPublicKey(0));
+            }
+            ctx.Q = Q;
+            ctx.pubkeys = pks;
+            Ok(ctx)
         }
-        let (r, cr) = Scalar::decode32(&bswap32(&rb));
-        if cr == 0 || r.iszero() != 0 {
-            return false;
+
+        // Gets the key aggregation coefficient for a given key.
+        fn coefficient(&self, pk: &[u8; 33]) -> Scalar {
+            if *pk == self.pk2 {
+                return Scalar::ONE;
+            }
+            hash_to_scalar(&tagged_hash(b"KeyAgg coefficient", &[&self.L, pk]))
         }
-        let (s, cs) = Scalar::decode32(&bswap32(&sb));
-        if cs == 0 || s.iszero() != 0 {
-            return false;
+
+        /// Applies a tweak to the aggregate key.
+        ///
+        /// With a plain tweak (`is_xonly == false`), the aggregate key
+        /// `Q` becomes `Q + t*G`; with an x-only tweak (as used by
+        /// BIP-341), `Q` is first negated if its y coordinate is odd. The
+        /// tweak (32 bytes, unsigned big-endian) must be lower than the
+        /// curve order, and the tweaked key must not be the
+        /// point-at-infinity; otherwise, the `InvalidTweak` error is
+        /// returned and the context is unmodified.
+        pub fn apply_tweak(&mut self, tweak: &[u8; 32], is_xonly: bool)
+            -> Result<(), MuSig2Error>
+        {
+            let (t, ct) = Scalar::decode32(&bswap32(tweak));
+            if ct == 0 {
+                return Err(MuSig2Error::InvalidTweak);
+            }
+            let neg = is_xonly && has_odd_y(self.Q);
+            let mut Q = if neg { -self.Q } else { self.Q };
+            Q += Point::mulgen(&t);
+            if Q.isneutral() != 0 {
+                return Err(MuSig2Error::InvalidTweak);
+            }
+            self.Q = Q;
+            if neg {
+                self.gacc = -self.gacc;
+                self.tacc = -self.tacc;
+            }
+            self.tacc += t;
+            Ok(())
         }
 
-        // Convert the input hash value into an integer modulo n.
-        let mut tmp = [0u8; 32];
-        if hv.len() >= 32 {
-            tmp[..].copy_from_slice(&hv[..32]);
-        } else {
-            tmp[32 - hv.len() .. 32].copy_from_slice(hv);
+        /// Gets the aggregate public key (including tweaks).
+        pub fn aggregate_public_key(&self) -> PublicKey {
+            PublicKey { point: self.Q }
         }
-        let h = Scalar::decode_reduce(&bswap32(&tmp));
 
-        // Verification algorithm.
-        let w = Scalar::ONE / s;
-        let R = self.point.mul_add_mulgen_vartime(&(r * w), &(h * w));
-        let xR_le = bswap32(&R.encode_compressed()[1..33]);
-        let rr = Scalar::decode_reduce(&xR_le);
+        /// Gets the x-only aggregate public key (including tweaks); this
+        /// is the key against which the final signature verifies.
+        pub fn x_only_public_key(&self) -> XOnlyPublicKey {
+            XOnlyPublicKey::from_point(self.Q).0
+        }
+    }
 
-        // Signature is valid if the rebuilt r value (in rr) matches
-        // the one that was received.
-        return r.equals(rr) != 0;
+    impl<'a> NonceGen<'a> {
+
+        /// Starts a nonce generation for the signer with public key `pk`.
+        pub fn new(pk: &PublicKey) -> Self {
+            Self {
+                pk: pk.encode_compressed(),
+                sk: None,
+                aggpk: None,
+                msg: None,
+                extra_in: None,
+            }
+        }
+
+        /// Sets the signer's secret key.
+        pub fn secret_key(self, sk: &'a PrivateKey) -> Self {
+            Self { sk: Some(sk), ..self }
+        }
+
+        /// Sets the aggregate public key.
+        pub fn aggregate_key(self, aggpk: &XOnlyPublicKey) -> Self {
+            Self { aggpk: Some(aggpk.encode()), ..self }
+        }
+
+        /// Sets the message to sign.
+        pub fn message(self, msg: &'a [u8]) -> Self {
+            Self { msg: Some(msg), ..self }
+        }
+
+        /// Sets some extra input (e.g. a session identifier or a
+        /// counter).
+        pub fn extra_input(self, extra_in: &'a [u8]) -> Self {
+            Self { extra_in: Some(extra_in), ..self }
+        }
+
+        /// Generates a nonce pair, using 32 bytes from the provided
+        /// random source.
+        pub fn generate<T: CryptoRng + RngCore>(self, rng: &mut T)
+            -> (SecNonce, PubNonce)
+        {
+            let mut rand = [0u8; 32];
+            rng.fill_bytes(&mut rand);
+            self.generate_with_rand(&rand)
+        }
+
+        /// Generates a nonce pair from the provided 32-byte random value
+        /// (`rand'` in BIP-327).
+        ///
+        /// This function is deterministic. It is meant for test vectors:
+        /// the same random value MUST NOT be used twice with the same
+        /// other inputs, since that would yield the same secret nonce.
+        /// Use `generate()` instead.
+        ///
+        /// # Panics
+        ///
+        /// This function panics if a nonce value is zero, which cannot
+        /// happen except with negligible probability.
+        pub fn generate_with_rand(self, rand: &[u8; 32]) -> (SecNonce, PubNonce) {
+            let mut r = *rand;
+            if let Some(sk) = self.sk {
+                let h = tagged_hash(b"MuSig/aux", &[rand]);
+                for (d, (a, b)) in r.iter_mut().zip(sk.encode().iter().zip(h.iter())) {
+                    *d = a ^ b;
+                }
+            }
+            let aggpk: &[u8] = match &self.aggpk {
+                Some(x) => &x[..],
+                None => &[],
+            };
+            // The message is prefixed with a presence flag and, if
+            // present, its length (64 bits).
+            let mut mbuf = [0u8; 9];
+            let (mpre, msg): (&[u8], &[u8]) = match self.msg {
+                Some(m) => {
+                    mbuf[0] = 0x01;
+                    mbuf[1..].copy_from_slice(&(m.len() as u64).to_be_bytes());
+                    (&mbuf[..], m)
+                }
+                None => (&mbuf[..1], &[]),
+            };
+            let extra_in = self.extra_in.unwrap_or(&[]);
+            let elen = (extra_in.len() as u32).to_be_bytes();
+            let kk = [0u8, 1u8].map(|i| {
+                hash_to_scalar(&tagged_hash(b"MuSig/nonce", &[
+                    &r, &[33], &self.pk, &[aggpk.len() as u8], aggpk,
+                    mpre, msg, &elen, extra_in, &[i]]))
+            });
+            assert!((kk[0].iszero() | kk[1].iszero()) == 0, "invalid nonce");
+            let sn = SecNonce { k1: kk[0], k2: kk[1], pk: self.pk };
+            let pn = sn.pub_nonce();
+            (sn, pn)
+        }
+    }
+
+    impl SecNonce {
+
+        /// Gets the public nonce that corresponds to this secret nonce.
+        pub fn pub_nonce(&self) -> PubNonce {
+            let mut pn = [0u8; 66];
+            pn[..33].copy_from_slice(&Point::mulgen(&self.k1).encode_compressed());
+            pn[33..].copy_from_slice(&Point::mulgen(&self.k2).encode_compressed());
+            pn
+        }
+    }
+
+    // The secret nonce is not displayed by `Debug`.
+    impl core::fmt::Debug for SecNonce {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("SecNonce").finish_non_exhaustive()
+        }
+    }
+
+    /// Aggregates the public nonces of all signers.
+    ///
+    /// If a public nonce is invalid, the `InvalidPubNonce` error is
+    /// returned, with the index of the first offending nonce.
+    pub fn nonce_agg(pubnonces: &[PubNonce]) -> Result<AggNonce, MuSig2Error> {
+        let mut an = [0u8; 66];
+        for j in 0..2 {
+            let mut R = Point::NEUTRAL;
+            for (i, pn) in pubnonces.iter().enumerate() {
+                R += cpoint(&pn[33 * j..33 * j + 33])
+                    .ok_or(MuSig2Error::InvalidPubNonce(i))?;
+            }
+            // The point-at-infinity is encoded as 33 zeros.
+            an[33 * j..33 * j + 33].copy_from_slice(&R.encode_compressed());
+        }
+        Ok(an)
+    }
+
+    impl<'a> Session<'a> {
+
+        /// Starts a signing session for a message.
+        ///
+        /// An error is returned if the aggregate nonce is invalid.
+        pub fn new(ctx: &'a KeyAggContext, aggnonce: &AggNonce, msg: &[u8])
+            -> Result<Self, MuSig2Error>
+        {
+            let R1 = cpoint_ext(&aggnonce[..33])
+                .ok_or(MuSig2Error::InvalidAggNonce)?;
+            let R2 = cpoint_ext(&aggnonce[33..])
+                .ok_or(MuSig2Error::InvalidAggNonce)?;
+            let xQ = xbytes(ctx.Q);
+            let b = hash_to_scalar(
+                &tagged_hash(b"MuSig/noncecoef", &[aggnonce, &xQ, msg]));
+            let mut R = R1 + R2 * b;
+            if R.isneutral() != 0 {
+                R = Point::BASE;
+            }
+            let e = hash_to_scalar(
+                &tagged_hash(b"BIP0340/challenge", &[&xbytes(R), &xQ, msg]));
+            Ok(Self { ctx, b, R, e })
+        }
+
+        // Gets g*gacc (the sign applied to the signers' keys).
+        fn key_sign(&self) -> Scalar {
+            if has_odd_y(self.ctx.Q) { -self.ctx.gacc } else { self.ctx.gacc }
+        }
+
+        // Gets the key aggregation coefficient for a signer; an error is
+        // returned if that signer is not in the list of keys.
+        fn coefficient(&self, pk: &[u8; 33]) -> Result<Scalar, MuSig2Error> {
+            if !self.ctx.pubkeys.contains(pk) {
+                return Err(MuSig2Error::UnknownSigner);
+            }
+            Ok(self.ctx.coefficient(pk))
+        }
+
+        /// Computes a partial signature.
+        ///
+        /// The secret nonce is consumed. An error is returned if the
+        /// secret key does not match the public key used to generate the
+        /// nonce, or if that key is not part of the key aggregation
+        /// context. This function is constant-time.
+        pub fn partial_sign(&self, secnonce: SecNonce, sk: &PrivateKey)
+            -> Result<PartialSignature, MuSig2Error>
+        {
+            let pk = sk.to_public_key().encode_compressed();
+            if pk != secnonce.pk {
+                return Err(MuSig2Error::PublicKeyMismatch);
+            }
+            let a = self.coefficient(&pk)?;
+            let (mut k1, mut k2) = (secnonce.k1, secnonce.k2);
+            if has_odd_y(self.R) {
+                k1 = -k1;
+                k2 = -k2;
+            }
+            let d = sk.x * self.key_sign();
+            let s = k1 + self.b * k2 + self.e * a * d;
+            Ok(bswap32(&s.encode()))
+        }
+
+        /// Verifies a partial signature.
+        ///
+        /// `pubnonce` and `pk` are the public nonce and public key of the
+        /// signer who produced the partial signature. The signature is
+        /// rejected if it is out of range, if the public nonce is
+        /// invalid, or if the public key is not part of the key
+        /// aggregation context.
+        pub fn partial_sig_verify(&self, psig: &PartialSignature,
+            pubnonce: &PubNonce, pk: &PublicKey) -> bool
+        {
+            let (s, cs) = Scalar::decode32(&bswap32(psig));
+            if cs == 0 {
+                return false;
+            }
+            let (R1, R2) = match (cpoint(&pubnonce[..33]), cpoint(&pubnonce[33..])) {
+                (Some(R1), Some(R2)) => (R1, R2),
+                _ => return false,
+            };
+            let a = match self.coefficient(&pk.encode_compressed()) {
+                Ok(a) => a,
+                Err(_) => return false,
+            };
+            let mut Re = R1 + R2 * self.b;
+            if has_odd_y(self.R) {
+                Re = -Re;
+            }
+
+            // s*G = Re + (e*a*g*gacc)*P
+            let c = self.e * a * self.key_sign();
+            pk.point.mul_add_mulgen_vartime(&-c, &s).equals(Re) != 0
+        }
+
+        /// Aggregates the partial signatures of all signers into the
+        /// final signature (64 bytes).
+        ///
+        /// An out-of-range partial signature yields the
+        /// `InvalidPartialSignature` error, with the index of the first
+        /// offending value. Partial signatures are not otherwise
+        /// verified; the resulting signature is valid only if all partial
+        /// signatures are valid (see `partial_sig_verify()`).
+        pub fn partial_sig_agg(&self, psigs: &[PartialSignature])
+            -> Result<[u8; 64], MuSig2Error>
+        {
+            let mut s = Scalar::ZERO;
+            for (i, ps) in psigs.iter().enumerate() {
+                let (si, cs) = Scalar::decode32(&bswap32(ps));
+                if cs == 0 {
+                    return Err(MuSig2Error::InvalidPartialSignature(i));
+                }
+                s += si;
+            }
+            let mut g = Scalar::ONE;
+            if has_odd_y(self.ctx.Q) {
+                g = -g;
+            }
+            s += self.e * g * self.ctx.tacc;
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(&xbytes(self.R));
+            sig[32..].copy_from_slice(&bswap32(&s.encode()));
+            Ok(sig)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::{KeyAggContext, NonceGen, SecNonce, Session, MuSig2Error};
+        use super::{nonce_agg, PubNonce, AggNonce};
+        use super::super::{Point, Scalar, PrivateKey, PublicKey, XOnlyPublicKey};
+        use super::super::bswap32;
+        use crate::Vec;
+
+        fn pk(s: &str) -> PublicKey {
+            PublicKey::decode(&hex::decode(s).unwrap()).unwrap()
+        }
+
+        fn b32(s: &str) -> [u8; 32] {
+            let mut d = [0u8; 32];
+            hex::decode_to_slice(s, &mut d).unwrap();
+            d
+        }
+
+        fn b66(s: &str) -> [u8; 66] {
+            let mut d = [0u8; 66];
+            hex::decode_to_slice(s, &mut d).unwrap();
+            d
+        }
+
+        fn scalar(s: &str) -> Scalar {
+            Scalar::decode32(&bswap32(&b32(s))).0
+        }
+
+        // Values from the BIP-327 test vectors.
+        const SK: &str = "7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671";
+        const SECNONCE: [&str; 2] = [
+            "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61",
+            "FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7",
+        ];
+        const PUBKEYS: [&str; 4] = [
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        ];
+        const PNONCES: [&str; 3] = [
+            "0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F817980279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE9303E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046",
+        ];
+        const AGGNONCE: &str = "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9";
+        const MSG: &str = "F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF";
+
+        fn secnonce() -> SecNonce {
+            SecNonce {
+                k1: scalar(SECNONCE[0]),
+                k2: scalar(SECNONCE[1]),
+                pk: pk(PUBKEYS[0]).encode_compressed(),
+            }
+        }
+
+        fn ctx(keys: &[usize]) -> KeyAggContext {
+            let pks: Vec<PublicKey> = keys.iter().map(|&i| pk(PUBKEYS[i])).collect();
+            KeyAggContext::new(&pks).unwrap()
+        }
+
+        #[test]
+        fn key_agg() {
+            // BIP-327 key aggregation vectors.
+            const X: [&str; 3] = [
+                "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+                "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+                "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+            ];
+            const KAT: [(&[usize], &str); 4] = [
+                (&[0, 1, 2], "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C"),
+                (&[2, 1, 0], "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B"),
+                (&[0, 0, 0], "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935"),
+                (&[0, 0, 1, 1], "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E"),
+            ];
+            for (idx, exp) in KAT.iter() {
+                let pks: Vec<PublicKey> = idx.iter().map(|&i| pk(X[i])).collect();
+                let c = KeyAggContext::new(&pks).unwrap();
+                assert!(c.x_only_public_key().encode() == b32(exp));
+            }
+
+            // The neutral point and the empty list are rejected.
+            let bad = PublicKey { point: Point::NEUTRAL };
+            assert!(KeyAggContext::new(&[pk(X[0]), bad]).err()
+                == Some(MuSig2Error::InvalidPublicKey(1)));
+            assert!(KeyAggContext::new(&[]).err()
+                == Some(MuSig2Error::InvalidPublicKey(0)));
+
+            // Tweak out of range, and tweak yielding the
+            // point-at-infinity.
+            let mut c = KeyAggContext::new(&[pk(X[0])]).unwrap();
+            assert!(c.apply_tweak(&b32(
+                "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141"),
+                true) == Err(MuSig2Error::InvalidTweak));
+            let mut c = KeyAggContext::new(&[pk(PUBKEYS[0])]).unwrap();
+            assert!(c.apply_tweak(&b32(
+                "252E4BD67410A76CDF933D30EAA1608214037F1B105A013ECCD3C5C184A6110B"),
+                false) == Err(MuSig2Error::InvalidTweak));
+        }
+
+        #[test]
+        fn nonce_gen() {
+            // The first two vectors are from BIP-327.
+            let sk = PrivateKey::decode(&[0x02u8; 32]).unwrap();
+            let pk = sk.to_public_key();
+            let aggpk = XOnlyPublicKey::decode(&[0x07u8; 32]).unwrap();
+            let ng = NonceGen::new(&pk).secret_key(&sk)
+                .aggregate_key(&aggpk).extra_input(&[0x08u8; 32]);
+            const KAT: [(&str, &str, &str); 4] = [
+                ("227243DCB40EF2A13A981DB188FA433717B506BDFA14B1AE47D5DC027C9C3B9E",
+                 "F2370B2AD206E724243215137C86365699361126991E6FEC816845F837BDDAC3",
+                 "020A25526B002885996358B3EE5092F2F2F197393E59C06CDFC7A92A91931E20C3024C9FECC6795D5D761F96968D871A1F3BAC605F6ECC4E52E1EBF49E1FF9208AD0"),
+                ("CD0F47FE471D6788FF3243F47345EA0A179AEF69476BE8348322EF39C2723318",
+                 "870C2065AFB52DEDF02BF4FDBF6D2F442E608692F50C2374C08FFFE57042A61C",
+                 "0283D01F92F2B6A8540867AD8C7E725E420BBE27D8A949B67F1602219A3218EDE3034EDB05E0FCC6A1AF733DA418D47F863C874ED150B0F92821BF38B9C1835958E5"),
+                ("011F8BC60EF061DEEF4D72A0A87200D9994B3F0CD9867910085C38D5366E3E6B",
+                 "9FF03BC0124E56B24069E91EC3F162378983F194E8BD0ED89BE3059649EAE262",
+                 "036C9E0851CCC4C93589C870EF67ECAD52CF883FBAFAA27C1D980199B33407D7D3023AFDDECC096613B4A8B3288FC7A2918F5014674E9F8A80A24572D68CA5506AA8"),
+                ("0EFE212677929818AA13BA9FBC19A51EDD9C37DFDE9376781A4935B4A56DCA19",
+                 "5752B45B652413B944E889AAE365C2F3610D20C9FDE48909F797BC3D42E65C66",
+                 "032D944B674A3A9333D528C1AE05367D615151C0ABEC407EA9300C51B85BC8BDC8038189CEFB7BCCBA0DCE8CF310F17D1447BABBE7EB2BC2176AD9E1FB02604F98A0"),
+            ];
+            let m38 = [0x26u8; 38];
+            let ngs = [
+                ng.message(&[0x01u8; 32]),
+                ng.message(&[]),
+                ng.message(&m38),
+                NonceGen::new(&pk),
+            ];
+            let rands = [[0x00u8; 32], [0x00u8; 32], [0x00u8; 32], [0xFFu8; 32]];
+            for (i, (k1, k2, pn)) in KAT.iter().enumerate() {
+                let (sn, pn2) = ngs[i].generate_with_rand(&rands[i]);
+                assert!(sn.k1.equals(scalar(k1)) == 0xFFFFFFFF);
+                assert!(sn.k2.equals(scalar(k2)) == 0xFFFFFFFF);
+                assert!(sn.pk == pk.encode_compressed());
+                assert!(pn2 == b66(pn));
+                assert!(sn.pub_nonce() == pn2);
+            }
+        }
+
+        #[test]
+        fn nonce_agg_kat() {
+            // BIP-327 nonce aggregation vectors.
+            const PN: [&str; 4] = [
+                "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E66603BA47FBC1834437B3212E89A84D8425E7BF12E0245D98262268EBDCB385D50641",
+                "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60248C264CDD57D3C24D79990B0F865674EB62A0F9018277A95011B41BFC193B833",
+                "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E6660279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+                "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60379BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            ];
+            let pn: Vec<PubNonce> = PN.iter().map(|s| b66(s)).collect();
+            assert!(nonce_agg(&pn[0..2]).unwrap() == b66("035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B024725377345BDE0E9C33AF3C43C0A29A9249F2F2956FA8CFEB55C8573D0262DC8"));
+            assert!(nonce_agg(&pn[2..4]).unwrap() == b66("035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B000000000000000000000000000000000000000000000000000000000000000000"));
+
+            // Invalid public nonces: wrong prefix, x not on the curve,
+            // x out of range.
+            let mut bad = pn[1];
+            bad[0] = 0x04;
+            assert!(nonce_agg(&[pn[0], bad]) == Err(MuSig2Error::InvalidPubNonce(1)));
+            let mut bad = pn[0];
+            bad[33..].copy_from_slice(&hex::decode("020000000000000000000000000000000000000000000000000000000000000007").unwrap());
+            assert!(nonce_agg(&[bad, pn[1]]) == Err(MuSig2Error::InvalidPubNonce(0)));
+            let mut bad = pn[0];
+            bad[1..33].copy_from_slice(&b32("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30"));
+            assert!(nonce_agg(&[bad, pn[1]]) == Err(MuSig2Error::InvalidPubNonce(0)));
+        }
+
+        #[test]
+        fn sign_verify() {
+            let sk = PrivateKey::decode(&b32(SK)).unwrap();
+            let pn: Vec<PubNonce> = PNONCES.iter().map(|s| b66(s)).collect();
+            let msg = b32(MSG);
+            let m38 = [0x26u8; 38];
+
+            // The first three vectors are from BIP-327.
+            const KAT: [(&[usize], &str, usize, &str); 5] = [
+                (&[0, 1, 2], AGGNONCE, 0,
+                 "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB"),
+                (&[1, 0, 2], AGGNONCE, 0,
+                 "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52"),
+                (&[1, 2, 0], AGGNONCE, 0,
+                 "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900"),
+                (&[0, 1, 2], AGGNONCE, 1,
+                 "D7D63FFD644CCDA4E62BC2BC0B1D02DD32A1DC3030E155195810231D1037D82D"),
+                (&[0, 1, 2], AGGNONCE, 2,
+                 "E184351828DA5094A97C79CABDAAA0BFB87608C32E8829A4DF5340A6F243B78C"),
+            ];
+            let msgs: [&[u8]; 3] = [&msg, &[], &m38];
+            for (keys, an, mi, exp) in KAT.iter() {
+                let c = ctx(keys);
+                let s = Session::new(&c, &b66(an), msgs[*mi]).unwrap();
+                let psig = s.partial_sign(secnonce(), &sk).unwrap();
+                assert!(psig == b32(exp));
+                let signer = keys.iter().position(|&i| i == 0).unwrap();
+                assert!(s.partial_sig_verify(&psig, &pn[keys[signer]],
+                    &pk(PUBKEYS[0])));
+
+                // Verification failures: wrong signer, negated
+                // signature, out-of-range value, invalid nonce.
+                assert!(!s.partial_sig_verify(&psig, &pn[keys[signer]],
+                    &pk(PUBKEYS[1])));
+                let neg = bswap32(&(-scalar(exp)).encode());
+                assert!(!s.partial_sig_verify(&neg, &pn[keys[signer]],
+                    &pk(PUBKEYS[0])));
+                let n = b32("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141");
+                assert!(!s.partial_sig_verify(&n, &pn[keys[signer]],
+                    &pk(PUBKEYS[0])));
+                let mut bad = pn[keys[signer]];
+                bad[0] = 0x04;
+                assert!(!s.partial_sig_verify(&psig, &bad, &pk(PUBKEYS[0])));
+            }
+
+            // Aggregate nonce with the point-at-infinity: in the second
+            // half only, and in both halves (then R = G).
+            let mut pn_neg = pn[0];
+            pn_neg[0] ^= 0x01;
+            pn_neg[33] ^= 0x01;
+            let mut pn_half = pn[1];
+            pn_half[33..].copy_from_slice(&pn_neg[33..]);
+            const KAT_INF: [(&str, &str); 2] = [
+                ("03F9784361C54151EB223A6B010D8F74FC0E4717E7F74A5F16FA630F854198B7A1000000000000000000000000000000000000000000000000000000000000000000",
+                 "9C3E2FC47F79E7E621DBFBA79F690FB61620FAD1E6A9F7B564407B30486C2AF1"),
+                ("000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                 "4D1114440EBE1574F3BB2BF36B0C5BE8647E23C9405FCFDE473D82D5B4733E6D"),
+            ];
+            let c = ctx(&[0, 1, 2]);
+            for (i, others) in [pn_half, pn_neg].iter().enumerate() {
+                let an = nonce_agg(&[pn[0], *others]).unwrap();
+                assert!(an == b66(KAT_INF[i].0));
+                let s = Session::new(&c, &an, &msg).unwrap();
+                let psig = s.partial_sign(secnonce(), &sk).unwrap();
+                assert!(psig == b32(KAT_INF[i].1));
+                assert!(s.partial_sig_verify(&psig, &pn[0], &pk(PUBKEYS[0])));
+            }
+
+            // Errors: signer not in the key list, invalid aggregate
+            // nonce, secret key not matching the nonce.
+            let c = ctx(&[1, 2]);
+            let s = Session::new(&c, &b66(AGGNONCE), &msg).unwrap();
+            assert!(s.partial_sign(secnonce(), &sk) == Err(MuSig2Error::UnknownSigner));
+            let c = ctx(&[0, 1, 2]);
+            let mut an: AggNonce = b66(AGGNONCE);
+            an[0] = 0x04;
+            assert!(Session::new(&c, &an, &msg).err() == Some(MuSig2Error::InvalidAggNonce));
+            an[0] = 0x02;
+            an[34..].copy_from_slice(&b32("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30"));
+            assert!(Session::new(&c, &an, &msg).err() == Some(MuSig2Error::InvalidAggNonce));
+            let s = Session::new(&c, &b66(AGGNONCE), &msg).unwrap();
+            let sk2 = PrivateKey::decode(&[0x02u8; 32]).unwrap();
+            assert!(s.partial_sign(secnonce(), &sk2) == Err(MuSig2Error::PublicKeyMismatch));
+        }
+
+        #[test]
+        fn tweak() {
+            // BIP-327 tweak vectors.
+            const TWEAKS: [&str; 5] = [
+                "E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB",
+                "AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455",
+                "F52ECBC565B3D8BEA2DFD5B75A4F457E54369809322E4120831626F290FA87E0",
+                "1969AD73CC177FA0B4FCED6DF1F7BF9907E665FDE9BA196A74FED0A3CF5AEF9D",
+                "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            ];
+            const KAT: [(&[usize], &[bool], &str); 5] = [
+                (&[0], &[true],
+                 "E28A5C66E61E178C2BA19DB77B6CF9F7E2F0F56C17918CD13135E60CC848FE91"),
+                (&[0], &[false],
+                 "38B0767798252F21BF5702C48028B095428320F73A4B14DB1E25DE58543D2D2D"),
+                (&[0, 1], &[false, true],
+                 "408A0A21C4A0F5DACAF9646AD6EB6FECD7F7A11F03ED1F48DFFF2185BC2C2408"),
+                (&[0, 1, 2, 3], &[false, false, true, true],
+                 "45ABD206E61E3DF2EC9E264A6FEC8292141A633C28586388235541F9ADE75435"),
+                (&[0, 1, 2, 3], &[true, false, true, false],
+                 "B255FDCAC27B40C7CE7848E2D3B7BF5EA0ED756DA81565AC804CCCA3E1D5D239"),
+            ];
+            let sk = PrivateKey::decode(&b32(SK)).unwrap();
+            let msg = b32(MSG);
+            let pn1 = b66(PNONCES[1]);
+            for (tw, xonly, exp) in KAT.iter() {
+                let mut c = ctx(&[1, 3, 0]);
+                for (&t, &x) in tw.iter().zip(xonly.iter()) {
+                    c.apply_tweak(&b32(TWEAKS[t]), x).unwrap();
+                }
+                let s = Session::new(&c, &b66(AGGNONCE), &msg).unwrap();
+                let psig = s.partial_sign(secnonce(), &sk).unwrap();
+                assert!(psig == b32(exp));
+                assert!(s.partial_sig_verify(&psig, &b66(PNONCES[0]),
+                    &pk(PUBKEYS[0])));
+                assert!(!s.partial_sig_verify(&psig, &pn1, &pk(PUBKEYS[0])));
+            }
+            let mut c = ctx(&[1, 3, 0]);
+            let before = c.aggregate_public_key().encode_compressed();
+            assert!(c.apply_tweak(&b32(TWEAKS[4]), false) == Err(MuSig2Error::InvalidTweak));
+            assert!(c.aggregate_public_key().encode_compressed() == before);
+
+            // An x-only tweak with the BIP-341 tweak value yields the
+            // Taproot output key.
+            let mut c = ctx(&[0, 1, 2]);
+            let xk = c.x_only_public_key();
+            let (out, _) = xk.tap_tweak(None);
+            let t = super::super::tagged_hash(b"TapTweak", &[&xk.encode()]);
+            c.apply_tweak(&t, true).unwrap();
+            assert!(c.x_only_public_key() == out);
+        }
+
+        #[test]
+        fn end_to_end() {
+            // Three signers, with a plain and an x-only tweak; the
+            // expected values come from the BIP-327 reference code.
+            let sks: Vec<PrivateKey> = (1..4u8)
+                .map(|i| PrivateKey::decode(&[i; 32]).unwrap()).collect();
+            let pks: Vec<PublicKey> = sks.iter()
+                .map(|sk| sk.to_public_key()).collect();
+            let mut c = KeyAggContext::new(&pks).unwrap();
+            c.apply_tweak(&b32("E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB"), false).unwrap();
+            c.apply_tweak(&b32("AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455"), true).unwrap();
+            let aggpk = c.x_only_public_key();
+            assert!(aggpk.encode() == b32("E8C109C08931C28939854314CF9C4614DD7FCC65A038753F8CD23641E4F83959"));
+            let msg = b"crrl MuSig2 test";
+
+            // Round 1.
+            let mut secnonces = Vec::new();
+            let mut pubnonces = Vec::new();
+            for (i, sk) in sks.iter().enumerate() {
+                let (sn, pn) = NonceGen::new(&pks[i]).secret_key(sk)
+                    .aggregate_key(&aggpk).message(msg)
+                    .generate_with_rand(&[0x10 + i as u8; 32]);
+                secnonces.push(sn);
+                pubnonces.push(pn);
+            }
+            let an = nonce_agg(&pubnonces).unwrap();
+
+            // Round 2.
+            let s = Session::new(&c, &an, msg).unwrap();
+            let mut psigs = Vec::new();
+            for (i, sn) in secnonces.into_iter().enumerate() {
+                let psig = s.partial_sign(sn, &sks[i]).unwrap();
+                assert!(s.partial_sig_verify(&psig, &pubnonces[i], &pks[i]));
+                psigs.push(psig);
+            }
+            assert!(psigs[0] == b32("8FFB03512333C8FCDC67063CE82FBAE2101ED6089FDEAAC919EA0C928E75F1B8"));
+            assert!(psigs[1] == b32("A0DB4A68D523D0EDE1153BF0F46DB54BA299CA7AB7F860C2B12269D0ED5EEFC1"));
+            assert!(psigs[2] == b32("37236653DB420E81422A18981F1D633642ECCE5315B96085156C8C5D249B18E8"));
+            let sig = s.partial_sig_agg(&psigs).unwrap();
+            assert!(sig[..] == hex::decode("0448059D9419D59E266C1BEBEEA3D85F077F86B264EC6CC8E5C05C2B7E9CE8F0455CD9568515DA19F30AC9E3449D52E0716FF32F5F8C970F7272724F3D8993CE").unwrap());
+            assert!(aggpk.verify_schnorr(&sig, msg));
+            assert!(!aggpk.verify_schnorr(&sig, b"crrl MuSig2 tesT"));
+
+            // A missing or altered partial signature yields an invalid
+            // signature; out-of-range values are reported.
+            let sig2 = s.partial_sig_agg(&psigs[..2]).unwrap();
+            assert!(!aggpk.verify_schnorr(&sig2, msg));
+            psigs[1] = b32("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141");
+            assert!(s.partial_sig_agg(&psigs) == Err(MuSig2Error::InvalidPartialSignature(1)));
+        }
     }
 }
 
@@ -1948,6 +5847,9 @@ impl PublicKey {
 // and (2^195)*G, with G being the conventional base point. These are
 // used to speed mulgen() operations up. The points are stored in affine
 // coordinates, i.e. their Z coordinate is implicitly equal to 1.
+// With the `small_tables_secp256k1` feature, only the multiples of B are
+// included; mulgen() then uses a single table, and the multiples of
+// (2^130)*B are recomputed when needed (in vartime functions).
 
 /// A curve point (non-infinity) in affine coordinates.
 #[derive(Clone, Copy, Debug)]
@@ -2041,6 +5943,7 @@ static PRECOMP_G: [PointAffine; 16] = [
 ];
 
 // Points i*(2^65)*G for i = 1 to 16, in affine coordinates.
+#[cfg(not(feature = "small_tables_secp256k1"))]
 static PRECOMP_G65: [PointAffine; 16] = [
     // (2^65)*G * 1
     PointAffine { x: GFsecp256k1::w64be(0x8D26200250CEBDAE, 0x120EF31B04C80CD5,
@@ -2125,6 +6028,7 @@ static PRECOMP_G65: [PointAffine; 16] = [
 ];
 
 // Points i*(2^130)*G for i = 1 to 16, in affine coordinates.
+#[cfg(not(feature = "small_tables_secp256k1"))]
 static PRECOMP_G130: [PointAffine; 16] = [
     // (2^130)*G * 1
     PointAffine { x: GFsecp256k1::w64be(0x7564539E85D56F85, 0x37D6619E1F5C5AA7,
@@ -2209,6 +6113,7 @@ static PRECOMP_G130: [PointAffine; 16] = [
 ];
 
 // Points i*(2^195)*G for i = 1 to 16, in affine coordinates.
+#[cfg(not(feature = "small_tables_secp256k1"))]
 static PRECOMP_G195: [PointAffine; 16] = [
     // (2^195)*G * 1
     PointAffine { x: GFsecp256k1::w64be(0x60144494C8F69448, 0x5B85ECB6AEE10956,
@@ -2297,8 +6202,16 @@ static PRECOMP_G195: [PointAffine; 16] = [
 #[cfg(test)]
 mod tests {
 
-    use super::{Point, Scalar, PrivateKey, PublicKey};
-    use sha2::{Sha256, Digest};
+    use super::{Point, Scalar, PrivateKey, PublicKey, RecoverableSignature};
+    use super::{XOnlyPublicKey, Parity};
+    use super::{signature_to_der, signature_from_der};
+    use super::{normalize_s, has_low_s, bswap32};
+    use super::{verify_batch, verify_batch_find_invalid};
+    use super::{Rfc6979, hash_to_scalar, check_signature};
+    use super::{ParsePolicy, KeyError};
+    use crate::der::DerError;
+    use crate::{DecodeError, ParseError, HashId};
+    use sha2::{Sha224, Sha256, Sha384, Sha512, Digest};
 
     /* unused
     fn print_gf(name: &str, x: GFsecp256k1) {
@@ -2582,6 +6495,28 @@ mod tests {
             let t = t0 + t1 * THETA;
             assert!(t.equals(k) == 0xFFFFFFFF);
         }
+
+        // Boundary values: 0, +/-1, +/-2^128, +/-THETA and their
+        // neighbours.
+        let t128 = Scalar::from_u128(u128::MAX) + Scalar::ONE;
+        let base = [Scalar::ZERO, Scalar::ONE, t128, THETA, THETA + THETA];
+        for b in base.iter() {
+            for d in [-Scalar::ONE, Scalar::ZERO, Scalar::ONE].iter() {
+                for k in [*b + *d, -(*b + *d)].iter() {
+                    let (k0, sk0, k1, sk1) = Point::split_theta(k);
+                    let mut t0 = Scalar::from_u128(k0);
+                    if sk0 != 0 {
+                        t0 = -t0;
+                    }
+                    let mut t1 = Scalar::from_u128(k1);
+                    if sk1 != 0 {
+                        t1 = -t1;
+                    }
+                    let t = t0 + t1 * THETA;
+                    assert!(t.equals(*k) == 0xFFFFFFFF);
+                }
+            }
+        }
     }
 
     #[test]
@@ -2625,7 +6560,17 @@ mod tests {
             let P2 = Point::mulgen(&s3);
             let Q2 = s2 * Q1;
             assert!(P2.equals(Q2) == 0xFFFFFFFF);
+            let R2 = Q1.mul_vartime_glv(&s2);
+            assert!(R2.equals(Q2) == 0xFFFFFFFF);
+        }
+
+        // Variable-time GLV multiplication on edge scalars.
+        let P = Point::mulgen(&Scalar::w64be(0, 0, 0, 12345));
+        for s in [Scalar::ZERO, Scalar::ONE, -Scalar::ONE].iter() {
+            assert!(P.mul_vartime_glv(s).equals(P * s) == 0xFFFFFFFF);
         }
+        assert!(Point::NEUTRAL.mul_vartime_glv(&Scalar::ONE).isneutral()
+            == 0xFFFFFFFF);
     }
 
     #[test]
@@ -2651,6 +6596,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_verbose() {
+        let G = Point::BASE;
+        let Gc = G.encode_compressed();
+        let Gu = G.encode_uncompressed();
+        assert!(Point::decode_verbose(&[0x00]).unwrap().isneutral() == 0xFFFFFFFF);
+        assert!(Point::decode_verbose(&Gc).unwrap().equals(G) == 0xFFFFFFFF);
+        assert!(Point::decode_verbose(&Gu).unwrap().equals(G) == 0xFFFFFFFF);
+
+        let zz = [0x04u8; 66];
+        for len in [0usize, 2, 32, 34, 64, 66].iter() {
+            assert!(Point::decode_verbose(&zz[..*len]).err()
+                == Some(DecodeError::BadLength));
+        }
+
+        assert!(Point::decode_verbose(&[0x01]).err() == Some(DecodeError::BadHeader));
+        let mut buf = Gc;
+        for hb in [0x00u8, 0x01, 0x04, 0x06].iter() {
+            buf[0] = *hb;
+            assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::BadHeader));
+        }
+        let mut buf = Gu;
+        for hb in [0x00u8, 0x02, 0x03, 0x06, 0x07].iter() {
+            buf[0] = *hb;
+            assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::BadHeader));
+        }
+
+        // Field modulus (big-endian), as x or y coordinate.
+        let p_enc = hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f").unwrap();
+        let mut buf = Gc;
+        buf[1..].copy_from_slice(&p_enc);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+        let mut buf = Gu;
+        buf[33..].copy_from_slice(&p_enc);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+        let mut buf = Gu;
+        buf[1..33].copy_from_slice(&p_enc);
+        assert!(Point::decode_verbose(&buf).err()
+            == Some(DecodeError::NonCanonicalField));
+
+        // x = 5 is not the x coordinate of any curve point.
+        let mut buf = [0u8; 33];
+        buf[0] = 0x03;
+        buf[32] = 5;
+        assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::NotOnCurve));
+        let mut buf = Gu;
+        buf[64] ^= 0x01;
+        assert!(Point::decode_verbose(&buf).err() == Some(DecodeError::NotOnCurve));
+    }
+
     #[test]
     fn verify_helper() {
         let mut sh = Sha256::new();
@@ -2678,6 +6675,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hex_strings() {
+        // Scalars: round trips, uppercase input, alternate format.
+        let mut sh = Sha256::new();
+        for i in 0..10 {
+            sh.update(&(i as u64).to_le_bytes());
+            let x = Scalar::decode_reduce(&sh.finalize_reset());
+            let str = format!("{}", x);
+            assert!(str == hex::encode(x.encode()));
+            assert!(format!("{:x}", x) == str);
+            assert!(format!("{:#x}", x) == format!("0x{}", str));
+            let y: Scalar = str.parse().unwrap();
+            assert!(y.equals(x) == 0xFFFFFFFF);
+            let y: Scalar = str.to_uppercase().parse().unwrap();
+            assert!(y.equals(x) == 0xFFFFFFFF);
+        }
+
+        // Scalars not lower than n are rejected.
+        let nhex = "414136d08c5ed2bf3ba048afe6dcaebafeffffffffffffffffffffffffffffff";
+        let nm1 = "404136d08c5ed2bf3ba048afe6dcaebafeffffffffffffffffffffffffffffff";
+        assert!(nhex.parse::<Scalar>().err()
+            == Some(ParseError::ScalarOutOfRange));
+        assert!(nm1.parse::<Scalar>().unwrap().equals(-Scalar::ONE) == 0xFFFFFFFF);
+        assert!("ff".repeat(32).parse::<Scalar>().err()
+            == Some(ParseError::ScalarOutOfRange));
+        assert!(nm1[2..].parse::<Scalar>().err() == Some(ParseError::BadLength));
+
+        // Public keys (compressed or uncompressed input).
+        for i in 0..10 {
+            let skey = PrivateKey::from_seed(&[i as u8; 32]);
+            let pkey = skey.to_public_key();
+            let str = format!("{}", pkey);
+            assert!(str == hex::encode(pkey.encode_compressed()));
+            assert!(format!("{:x}", pkey) == str);
+            let pk2: PublicKey = str.parse().unwrap();
+            assert!(pk2.point.equals(pkey.point) == 0xFFFFFFFF);
+            let pk3: PublicKey = hex::encode(pkey.encode_uncompressed())
+                .parse().unwrap();
+            assert!(pk3.point.equals(pkey.point) == 0xFFFFFFFF);
+
+            // The secret scalar is not printed.
+            assert!(format!("{:?}", skey) == "PrivateKey { .. }");
+        }
+        assert!("00".parse::<PublicKey>().err() == Some(ParseError::BadLength));
+        let mut buf = [0u8; 33];
+        buf[0] = 0x05;
+        assert!(hex::encode(buf).parse::<PublicKey>().err()
+            == Some(ParseError::BadPoint(DecodeError::BadHeader)));
+        buf[0] = 0x02;
+        buf[1..].copy_from_slice(&[0xFF; 32]);
+        assert!(hex::encode(buf).parse::<PublicKey>().err()
+            == Some(ParseError::BadPoint(DecodeError::NonCanonicalField)));
+    }
+
     #[test]
     fn signatures() {
         // Test vector from project Wycheproof
@@ -2730,4 +6781,1517 @@ mod tests {
             assert!(!pkey.verify_hash(&sig2, &hv2));
         }
     }
+
+    #[test]
+    fn signatures_hedged() {
+        // Vectors computed with an independent RFC 6979 implementation
+        // (with section 3.6 additional input).
+        let sk = PrivateKey::decode(&hex::decode(
+            "048d159e242af37bc00000000000000000000000000000000000000000003039")
+            .unwrap()).unwrap();
+        let pk = sk.to_public_key();
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        let mut extra = [0u8; 32];
+        for i in 0..32 {
+            extra[i] = i as u8;
+        }
+        let sig0 = sk.sign_hedged(&hv, &[0u8; 32]);
+        let sig1 = sk.sign_hedged(&hv, &extra);
+        assert!(hex::encode(sig0) == "e70a92dff2d071295f97964459ea667eacb4db8b2d09b4e4763be4c26c7b3add4d11a6a196089eb5f8fd9d05d81f3303108abf069e5f03f62bf38f0f80c1c114");
+        assert!(hex::encode(sig1) == "a3566d5e9f6ef86da5b2f68100bbf913c41090affaf311297cf25a1f802fd001fd7d6695084436d6459958cbec6c7549d7b5f45f726334e0c6ce8554790ba65e");
+        // All-zero extra input is not the same as plain RFC 6979 (no
+        // additional input; this signature was computed by OpenSSL).
+        assert!(hex::encode(sig0) != "c6e2cd4f2e3b82956cf42aba3290abdd4e085e72620b2b7c0c3e14436e5a84e5f940e6a79c647588adc7a1172450819e887dc973d08dcf674d04fd288d0b9713");
+        assert!(sig0 != sk.sign_hash(&hv, &[]));
+        assert!(pk.verify_hash(&sig0, &hv));
+        assert!(pk.verify_hash(&sig1, &hv));
+
+        // Over many messages, with both all-zero and varying extra
+        // input, k never repeats (r values are all distinct).
+        const N: usize = 200;
+        let mut rr = [[0u8; 32]; 2 * N];
+        let mut sh = Sha256::new();
+        for i in 0..N {
+            sh.update((i as u64).to_le_bytes());
+            let hv: [u8; 32] = sh.finalize_reset().into();
+            sh.update(hv);
+            let extra: [u8; 32] = sh.finalize_reset().into();
+            let sig0 = sk.sign_hedged(&hv, &[0u8; 32]);
+            let sig1 = sk.sign_hedged(&hv, &extra);
+            assert!(pk.verify_hash(&sig0, &hv));
+            assert!(pk.verify_hash(&sig1, &hv));
+            rr[2 * i].copy_from_slice(&sig0[..32]);
+            rr[2 * i + 1].copy_from_slice(&sig1[..32]);
+        }
+        rr.sort_unstable();
+        for i in 1..(2 * N) {
+            assert!(rr[i - 1] != rr[i]);
+        }
+    }
+
+    #[test]
+    fn prehashed() {
+        // RFC 6979 signatures with SHA-224, SHA-384 and SHA-512,
+        // computed with an independent implementation.
+        let sk = PrivateKey::decode(&hex::decode(
+            "5A1B7F0DFFE7C2C4A08A8A3A1D1F0C8C3B4E5D6F708192A3B4C5D6E7F8091A2B")
+            .unwrap()).unwrap();
+        let pk = sk.to_public_key();
+        fn hash(id: usize, msg: &[u8]) -> ([u8; 64], usize) {
+            let mut hv = [0u8; 64];
+            let len = id / 8;
+            match id {
+                224 => hv[..len].copy_from_slice(&Sha224::digest(msg)),
+                384 => hv[..len].copy_from_slice(&Sha384::digest(msg)),
+                _ => hv[..len].copy_from_slice(&Sha512::digest(msg)),
+            }
+            (hv, len)
+        }
+        let kat: [(usize, &[u8], &str); 6] = [
+            (224, b"sample",
+             "d43410d4c20e78ae354a66a68f309471317fadf6f22bb6ba90be7b3bc84be94f071fd87c290288e5eb497934749c1c7a0f0e81475f279b04496dda63f9fa0901"),
+            (224, b"test",
+             "3f56450a2770a8ecb4de27e0ae8be61204c52e7167ffa72b2e9804d9d03c7a49eca6b63816bca2db340a96ce7084d7bba097f3aaee867d6534356ab61f55c6b4"),
+            (384, b"sample",
+             "d42b1cc077cc8c839d6321d10100e32ef4594b913c32f34acc311f94292cd04ff304efe90e4741c1549a80d6bec20cbce50f8cc0540132540c44d4aa9df5f3b9"),
+            (384, b"test",
+             "517764de33538186619c72106c1be75cff97ff0cda087da2a58d11ee18ff18410f259d3bddd6cbebfb63bca95ad605014a816fc38a0a48f9e2845578ce2cb0fa"),
+            (512, b"sample",
+             "a7f42b9af3683d6a05fe70a7940da80a331b414731c6631ab01b2ea4bbf10a3292b743afcf14c84c3bb63d5ff704ada551a7b754e87cfae8ed6ee1c2901e34dc"),
+            (512, b"test",
+             "29e2eb4fe2979a9e4c0ce25fa8e4b1a1702b7447070027d0dc32a516c1c729bb3d90f740f217015627b7257adfea912ae538d808be0a0021a3109a39f6f4eac2"),
+        ];
+        for &(id, msg, sig_hex) in kat.iter() {
+            let (hb, len) = hash(id, msg);
+            let hv = &hb[..len];
+            let sig = match id {
+                224 => sk.sign_prehashed::<Sha224>(hv),
+                384 => sk.sign_prehashed::<Sha384>(hv),
+                _ => sk.sign_prehashed::<Sha512>(hv),
+            };
+            assert!(hex::encode(sig) == sig_hex);
+            assert!(pk.verify_prehashed(hv, &sig));
+            let (hb2, _) = hash(id, b"other");
+            assert!(!pk.verify_prehashed(&hb2[..len], &sig));
+            if hv.len() > 32 {
+                // Only the leftmost 256 bits of the digest matter.
+                assert!(pk.verify_prehashed(&hv[..32], &sig));
+                let mut hv2 = hb;
+                hv2[len - 1] ^= 0x01;
+                assert!(pk.verify_prehashed(&hv2[..len], &sig));
+            } else {
+                // A shorter digest is interpreted as is (not shifted).
+                let mut hv2 = [0u8; 32];
+                hv2[(32 - len)..].copy_from_slice(hv);
+                assert!(pk.verify_prehashed(&hv2, &sig));
+            }
+        }
+
+        // With SHA-256, this is the same as sign_hedged() without extra
+        // randomness, before normalization of s.
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        let rs = sk.sign_recoverable(&hv);
+        assert!(normalize_s(&sk.sign_prehashed::<Sha256>(&hv)).0 == rs.sig);
+
+        // Signatures produced by OpenSSL (randomized ECDSA) over the
+        // SHA-384 and SHA-512 hashes of a message.
+        let msg = b"crrl prehashed test message";
+        let ossl: [(usize, &str); 2] = [
+            (384, "304402203bcac160fde095f50f9593a8713903d6577807590ccac95243f0d8831bc54506022018a05271b11abadf44b165a0d02b08bf9bbe4a87c18415fa9562db3353985fea"),
+            (512, "3045022013a288bc2cb13d64f3cf99c017dc3f2ddd3cb0bfa44453faf111b454c981684702210090e9029dd2a807d76c439494a0fbfe5f185d35a58ae9f0509564666e993f1359"),
+        ];
+        for &(id, der_hex) in ossl.iter() {
+            let (hb, len) = hash(id, msg);
+            let hv = &hb[..len];
+            let sig = signature_from_der(
+                &hex::decode(der_hex).unwrap(), false).unwrap();
+            assert!(pk.verify_prehashed(hv, &sig));
+            let (hb2, _) = hash(id, b"other");
+            assert!(!pk.verify_prehashed(&hb2[..len], &sig));
+        }
+    }
+
+    #[test]
+    fn from_bytes_strict() {
+        use ParsePolicy::*;
+        let pk = PublicKey { point: Point::BASE };
+        let ec = pk.encode_compressed();
+        let eu = pk.encode_uncompressed();
+        let mut eh = eu;
+        eh[0] = 0x06 | (eu[64] & 1);
+
+        // Accepted encodings; the results match decode() (except for
+        // the hybrid encoding, which decode() rejects).
+        for policy in [CompressedOnly, AnySec1] {
+            let pk2 = PublicKey::from_bytes_strict(&ec, policy).unwrap();
+            assert!(pk2.point.equals(Point::BASE) == 0xFFFFFFFF);
+        }
+        let pk2 = PublicKey::from_bytes_strict(&eu, AnySec1).unwrap();
+        assert!(pk2.point.equals(Point::BASE) == 0xFFFFFFFF);
+        let pk2 = PublicKey::from_bytes_strict(&eh, AnySec1).unwrap();
+        assert!(pk2.point.equals(Point::BASE) == 0xFFFFFFFF);
+        assert!(PublicKey::decode(&eh).is_none());
+
+        // x = p - 3 is on the curve; x = p - 1 is not; x = p and
+        // x = 2^256 - 1 are out of range.
+        let xp3 = hex::decode("02FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2C").unwrap();
+        let xp1 = hex::decode("02FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2E").unwrap();
+        let xp0 = hex::decode("03FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F").unwrap();
+        let xff = [0xFFu8; 33];
+        let mut xff2 = xff;
+        xff2[0] = 0x02;
+        for policy in [CompressedOnly, AnySec1] {
+            let pk2 = PublicKey::from_bytes_strict(&xp3, policy).unwrap();
+            assert!(pk2.encode_compressed()[..] == xp3[..]);
+            let mut xp1b = xp1.clone();
+            for prefix in [0x02, 0x03] {
+                xp1b[0] = prefix;
+                assert!(PublicKey::from_bytes_strict(&xp1b, policy).err()
+                    == Some(KeyError::NotOnCurve));
+            }
+            assert!(PublicKey::from_bytes_strict(&xp0, policy).err()
+                == Some(KeyError::XOutOfRange));
+            assert!(PublicKey::from_bytes_strict(&xff2, policy).err()
+                == Some(KeyError::XOutOfRange));
+
+            // Point-at-infinity, unknown prefixes, bad lengths.
+            assert!(PublicKey::from_bytes_strict(&[0x00], policy).err()
+                == Some(KeyError::InfinityNotAllowed));
+            assert!(PublicKey::from_bytes_strict(&[0u8; 33], policy).err()
+                == Some(KeyError::BadPrefix));
+            assert!(PublicKey::from_bytes_strict(&xff, policy).err()
+                == Some(KeyError::BadPrefix));
+            let mut bad = ec;
+            bad[0] = 0x05;
+            assert!(PublicKey::from_bytes_strict(&bad, policy).err()
+                == Some(KeyError::BadPrefix));
+            assert!(PublicKey::from_bytes_strict(&[], policy).err()
+                == Some(KeyError::BadLength));
+            assert!(PublicKey::from_bytes_strict(&ec[..32], policy).err()
+                == Some(KeyError::BadLength));
+            let mut bad = [0u8; 65];
+            bad[..33].copy_from_slice(&ec);
+            assert!(PublicKey::from_bytes_strict(&bad, policy).err()
+                == Some(KeyError::BadLength));
+        }
+
+        // Uncompressed and hybrid encodings under the compressed-only
+        // policy.
+        assert!(PublicKey::from_bytes_strict(&eu, CompressedOnly).err()
+            == Some(KeyError::BadPrefix));
+        assert!(PublicKey::from_bytes_strict(&eh, CompressedOnly).err()
+            == Some(KeyError::BadPrefix));
+
+        // Invalid uncompressed and hybrid encodings: bad length, y out
+        // of range, point not on the curve, wrong parity.
+        assert!(PublicKey::from_bytes_strict(&eu[..64], AnySec1).err()
+            == Some(KeyError::BadLength));
+        assert!(PublicKey::from_bytes_strict(&eu[..33], AnySec1).err()
+            == Some(KeyError::BadLength));
+        let mut bad = eu;
+        bad[33..].copy_from_slice(&xp0[1..]);
+        assert!(PublicKey::from_bytes_strict(&bad, AnySec1).err()
+            == Some(KeyError::YOutOfRange));
+        let mut bad = eu;
+        bad[1..33].copy_from_slice(&xp0[1..]);
+        assert!(PublicKey::from_bytes_strict(&bad, AnySec1).err()
+            == Some(KeyError::XOutOfRange));
+        let mut bad = eu;
+        bad[64] ^= 0x01;
+        assert!(PublicKey::from_bytes_strict(&bad, AnySec1).err()
+            == Some(KeyError::NotOnCurve));
+        let mut bad = eh;
+        bad[0] ^= 0x01;
+        assert!(PublicKey::from_bytes_strict(&bad, AnySec1).err()
+            == Some(KeyError::BadPrefix));
+
+        // Random keys.
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let seed = sh.finalize_reset();
+            let pk = PrivateKey::from_seed(&seed).to_public_key();
+            let ec = pk.encode_compressed();
+            let eu = pk.encode_uncompressed();
+            let pk2 = PublicKey::from_bytes_strict(&ec, CompressedOnly).unwrap();
+            assert!(pk2.point.equals(pk.point) == 0xFFFFFFFF);
+            let pk2 = PublicKey::from_bytes_strict(&eu, AnySec1).unwrap();
+            assert!(pk2.point.equals(pk.point) == 0xFFFFFFFF);
+        }
+    }
+
+    #[test]
+    fn sign_checked() {
+        use crate::SignError;
+
+        let mut sh = Sha256::new();
+        for i in 0..10u64 {
+            sh.update(i.to_le_bytes());
+            let v = sh.finalize_reset();
+            let sk = PrivateKey::from_seed(&v);
+            let pk = sk.to_public_key();
+            let hv = Sha256::digest(&v);
+            let sig = sk.sign_hash(&hv, &[]);
+            assert!(sk.sign_hash_checked(&pk, &hv, &[]) == Ok(sig));
+            assert!(sk.sign_hash_checked(&pk, &hv, b"extra")
+                == Ok(sk.sign_hash(&hv, b"extra")));
+
+            // A signature corrupted before the check is not returned.
+            let mut bad = sig;
+            bad[(i as usize) * 6] ^= 0x01;
+            assert!(check_signature(&pk, bad, &hv)
+                == Err(SignError::VerificationFailed));
+
+            // Simulated fault on the private scalar: the signature is
+            // valid for another key, and the check against the trusted
+            // public key fails.
+            let faulty = PrivateKey { x: sk.x + Scalar::ONE };
+            assert!(faulty.sign_hash_checked(&pk, &hv, &[])
+                == Err(SignError::VerificationFailed));
+        }
+
+        // Simulated fault on the nonce: r and s are computed with two
+        // distinct (injected) nonces.
+        #[cfg(feature = "insecure-test-apis")]
+        {
+            let sk = PrivateKey::from_seed(b"sign_checked");
+            let hv = Sha256::digest(b"sample");
+            let sig1 = sk.sign_with_nonce(
+                &hv, &Scalar::w64be(0, 0, 0, 12345)).unwrap();
+            let sig2 = sk.sign_with_nonce(
+                &hv, &Scalar::w64be(0, 0, 0, 12346)).unwrap();
+            let pk = sk.to_public_key();
+            assert!(check_signature(&pk, sig1, &hv) == Ok(sig1));
+            let mut bad = sig1;
+            bad[32..].copy_from_slice(&sig2[32..]);
+            assert!(check_signature(&pk, bad, &hv)
+                == Err(SignError::VerificationFailed));
+        }
+    }
+
+    #[test]
+    fn rfc6979_nonce() {
+        // RFC 6979 nonces and signatures with SHA-256, as used by many
+        // Bitcoin implementations (signatures are shown before "low-s"
+        // normalization).
+        let kat: [(&str, &[u8], &str, &str); 3] = [
+            ("0000000000000000000000000000000000000000000000000000000000000001",
+             b"Satoshi Nakamoto",
+             "8F8A276C19F4149656B280621E358CCE24F5F52542772691EE69063B74F15D15",
+             "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8dbbd3162d46e9f9bef7feb87c16dc13b4f6568a87f4e83f728e2443ba586675c"),
+            ("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364140",
+             b"Satoshi Nakamoto",
+             "33A19B60E25FB6F4435AF53A3D42D493644827367E6453928554F43E49AA6F90",
+             "fd567d121db66e382991534ada77a6bd3106f0a1098c231e47993447cd6af2d094c632f14e4379fc1ea610a3df5a375152549736425ee17cebe10abbc2a2826c"),
+            ("0000000000000000000000000000000000000000000000000000000000000001",
+             b"All those moments will be lost in time, like tears in rain. Time to die...",
+             "38AA22D72376B4DBC472E06C3BA403EE0A394DA63FC58D88686C611ABA98D6B3",
+             "8600dbd41e348fe5c9465ab92d23e3db8b98b873beecd930736488696438cb6bab8019bbd8b6924cc4099fe625340ffb1eaac34bf4477daa39d0835429094520"),
+        ];
+        for &(sk_hex, msg, k_hex, sig_hex) in kat.iter() {
+            let sk = PrivateKey::decode(&hex::decode(sk_hex).unwrap()).unwrap();
+            let hv: [u8; 32] = Sha256::digest(msg).into();
+            let h = hash_to_scalar(&hv);
+            let k = Rfc6979::<Sha256>::new(&sk.x, &h, &[]).next_k();
+            let mut kb = hex::decode(k_hex).unwrap();
+            kb.reverse();
+            assert!(k.encode()[..] == kb[..]);
+            let (sig, _) = sk.sign_with_k(&h, &k, None).unwrap();
+            assert!(hex::encode(sig) == sig_hex);
+            assert!(sig == sk.sign_prehashed::<Sha256>(&hv));
+            #[cfg(feature = "insecure-test-apis")]
+            assert!(sk.sign_with_nonce(&hv, &k) == Some(sig));
+        }
+
+        // A zero nonce is rejected.
+        let sk = PrivateKey::decode(&[0x01u8; 32]).unwrap();
+        assert!(sk.sign_with_k(&Scalar::ONE, &Scalar::ZERO, None).is_none());
+        #[cfg(feature = "insecure-test-apis")]
+        assert!(sk.sign_with_nonce(&[0u8; 32], &Scalar::ZERO).is_none());
+    }
+
+    #[cfg(feature = "insecure-test-apis")]
+    #[test]
+    fn schnorr_with_nonce() {
+        // BIP-340 test vector 0, with the nonce that sign_schnorr()
+        // derives from the all-zero auxiliary data (R = k*G has an odd
+        // y coordinate, hence -k is used).
+        let mut skb = [0u8; 32];
+        skb[31] = 3;
+        let sk = PrivateKey::decode(&skb).unwrap();
+        let msg = [0u8; 32];
+        let mut kb = hex::decode(
+            "1D2DC1652FEE3AD08434469F9AD30536A5787FECCFA308E8FB396C8030DD1C69")
+            .unwrap();
+        kb.reverse();
+        let k = Scalar::decode(&kb).unwrap();
+        let sig = sk.sign_schnorr_with_nonce(&msg, &k).unwrap();
+        assert!(hex::encode(sig) == "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0");
+        assert!(sig == sk.sign_schnorr(&msg, &[0u8; 32]));
+        assert!(sk.sign_schnorr_with_nonce(&msg, &-k) == Some(sig));
+        assert!(sk.sign_schnorr_with_nonce(&msg, &Scalar::ZERO).is_none());
+    }
+
+    #[test]
+    fn with_hash() {
+        let sk = PrivateKey::decode(&hex::decode(
+            "5A1B7F0DFFE7C2C4A08A8A3A1D1F0C8C3B4E5D6F708192A3B4C5D6E7F8091A2B")
+            .unwrap()).unwrap();
+        let pk = sk.to_public_key();
+        let ids = [HashId::Sha224, HashId::Sha256, HashId::Sha384,
+            HashId::Sha512];
+        for &msg in [&b"sample"[..], &b"test"[..]].iter() {
+            for &id in ids.iter() {
+                // The message is hashed with the selected function, which
+                // is also used for the nonce derivation.
+                let sig = sk.sign_with_hash(msg, id);
+                let sig2 = match id {
+                    HashId::Sha224 =>
+                        sk.sign_prehashed::<Sha224>(&Sha224::digest(msg)),
+                    HashId::Sha256 =>
+                        sk.sign_prehashed::<Sha256>(&Sha256::digest(msg)),
+                    HashId::Sha384 =>
+                        sk.sign_prehashed::<Sha384>(&Sha384::digest(msg)),
+                    HashId::Sha512 =>
+                        sk.sign_prehashed::<Sha512>(&Sha512::digest(msg)),
+                };
+                assert!(sig == sig2);
+                for &id2 in ids.iter() {
+                    assert!(pk.verify_with_hash(&sig, msg, id2) == (id == id2));
+                }
+                assert!(!pk.verify_with_hash(&sig, b"other", id));
+            }
+        }
+
+        // The SHA-224, SHA-384 and SHA-512 signatures are those of the
+        // prehashed() test.
+        assert!(hex::encode(sk.sign_with_hash(b"sample", HashId::Sha384)) == "d42b1cc077cc8c839d6321d10100e32ef4594b913c32f34acc311f94292cd04ff304efe90e4741c1549a80d6bec20cbce50f8cc0540132540c44d4aa9df5f3b9");
+        let hv: [u8; 32] = Sha256::digest(b"sample").into();
+        let sig = sk.sign_with_hash(b"sample", HashId::Sha256);
+        assert!(sig == sk.sign_prehashed::<Sha256>(&hv));
+        assert!(pk.verify_hash(&sig, &hv));
+    }
+
+    #[test]
+    fn recoverable() {
+        // Vectors from web3.js documentation (accounts.sign() with
+        // message "Some data") and from EIP-155 (example transaction).
+        const KAT: &[(&str, &str, &str)] = &[
+            ("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+             "1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655",
+             "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"),
+            ("4646464646464646464646464646464646464646464646464646464646464646",
+             "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53",
+             "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa63627667cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d831b"),
+        ];
+        for (ssk, shv, ssig) in KAT.iter() {
+            let sk = PrivateKey::decode(&hex::decode(ssk).unwrap()).unwrap();
+            let pk = sk.to_public_key();
+            let hv = hex::decode(shv).unwrap();
+            let rs = sk.sign_recoverable(&hv);
+            assert!(hex::encode(rs.encode_eth()) == *ssig);
+            let rs2 = RecoverableSignature::decode(
+                &hex::decode(ssig).unwrap()).unwrap();
+            assert!(rs2.sig == rs.sig && rs2.recid == rs.recid);
+            let rpk = rs2.recover(&hv, false).unwrap();
+            assert!(rpk.point.equals(pk.point) != 0);
+        }
+
+        let mut sh = Sha256::new();
+        let mut seen = [false; 2];
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let seed: [u8; 32] = sh.finalize_reset().into();
+            sh.update(seed);
+            let hv: [u8; 32] = sh.finalize_reset().into();
+            let sk = PrivateKey::from_seed(&seed);
+            let pk = sk.to_public_key();
+            let rs = sk.sign_recoverable(&hv);
+            seen[rs.recid as usize] = true;
+            assert!(pk.verify_hash(&rs.sig, &hv));
+            assert!(pk.compute_recovery_id(&rs.sig, &hv) == Some(rs.recid));
+
+            // High-s values are never produced.
+            let (s, _) = Scalar::decode32(&super::bswap32(&rs.sig[32..]));
+            assert!(super::scalar_is_high(s) == 0);
+            assert!(super::scalar_is_high(-s) != 0);
+
+            // Both v conventions.
+            let d = rs.encode();
+            let d_eth = rs.encode_eth();
+            assert!(d[..64] == d_eth[..64] && d[64] + 27 == d_eth[64]);
+            for d in [d, d_eth] {
+                let rs2 = RecoverableSignature::decode(&d).unwrap();
+                let rpk = rs2.recover(&hv, false).unwrap();
+                assert!(rpk.point.equals(pk.point) != 0);
+            }
+
+            // The high-s counterpart is a valid ECDSA signature, but it
+            // is rejected in strict mode.
+            let mut hs = rs;
+            hs.sig[32..].copy_from_slice(&super::bswap32(&(-s).encode()));
+            hs.recid ^= 1;
+            assert!(pk.verify_hash(&hs.sig, &hv));
+            assert!(hs.recover(&hv, false).is_none());
+            let rpk = hs.recover(&hv, true).unwrap();
+            assert!(rpk.point.equals(pk.point) != 0);
+
+            // Invalid v values.
+            let mut d = rs.encode();
+            for v in [4u8, 26, 31, 255] {
+                d[64] = v;
+                assert!(RecoverableSignature::decode(&d).is_none());
+            }
+            assert!(RecoverableSignature::decode(&d[..64]).is_none());
+        }
+        assert!(seen[0] && seen[1]);
+    }
+
+    #[test]
+    fn schnorr_bip340() {
+        // Test vectors 0, 1 and 3 from BIP-340:
+        // (secret key, public key, aux_rand, message, signature)
+        const KAT: &[(&str, &str, &str, &str, &str)] = &[
+            ("0000000000000000000000000000000000000000000000000000000000000003",
+             "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+             "0000000000000000000000000000000000000000000000000000000000000000",
+             "0000000000000000000000000000000000000000000000000000000000000000",
+             "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0"),
+            ("b7e151628aed2a6abf7158809cf4f3c762e7160f38b4da56a784d9045190cfef",
+             "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+             "0000000000000000000000000000000000000000000000000000000000000001",
+             "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+             "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de33418906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a"),
+            ("0b432b2677937381aef05bb02a66ecd012773062cf3fa2549e44f58ed2401710",
+             "25d1dff95105f5253c4022f628a996ad3a0d95fbf21d468a1b33f8c160d8f517",
+             "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+             "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+             "7eb0509757e246f19449885651611cb965ecc1a187dd51b64fda1edc9637d5ec97582b9cb13db3933705b32ba982af5af25fd78881ebb32771fc5922efc66ea3"),
+        ];
+        for (ssk, spk, saux, smsg, ssig) in KAT.iter() {
+            let sk = PrivateKey::decode(&hex::decode(ssk).unwrap()).unwrap();
+            let epk = hex::decode(spk).unwrap();
+            let mut aux = [0u8; 32];
+            aux.copy_from_slice(&hex::decode(saux).unwrap());
+            let msg = hex::decode(smsg).unwrap();
+            let sig = hex::decode(ssig).unwrap();
+            let pk = XOnlyPublicKey::decode(&epk).unwrap();
+            assert!(sk.to_x_only_public_key().encode()[..] == epk[..]);
+            assert!(sk.sign_schnorr(&msg, &aux)[..] == sig[..]);
+            assert!(pk.verify_schnorr(&sig, &msg));
+
+            // Altered message or signature.
+            let mut msg2 = msg.clone();
+            msg2[0] ^= 1;
+            assert!(!pk.verify_schnorr(&sig, &msg2));
+            for i in [0, 31, 32, 63] {
+                let mut sig2 = sig.clone();
+                sig2[i] ^= 1;
+                assert!(!pk.verify_schnorr(&sig2, &msg));
+            }
+            assert!(!pk.verify_schnorr(&sig[..63], &msg));
+        }
+
+        // BIP-340 test vectors 5 and 14: the public key is not on the
+        // curve, or exceeds the field size.
+        assert!(XOnlyPublicKey::decode(&hex::decode(
+            "eefdea4cdb677750a420fee807eacf21eb9898ae79b9768766e4faa04a2d4a34")
+            .unwrap()).is_none());
+        assert!(XOnlyPublicKey::decode(&hex::decode(
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc30")
+            .unwrap()).is_none());
+    }
+
+    #[test]
+    fn x_only_keys() {
+        // Public keys from the BIP-340 test vectors 0 to 3, with the
+        // corresponding secret keys.
+        const KAT: &[(&str, &str)] = &[
+            ("0000000000000000000000000000000000000000000000000000000000000003",
+             "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9"),
+            ("b7e151628aed2a6abf7158809cf4f3c762e7160f38b4da56a784d9045190cfef",
+             "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659"),
+            ("c90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74020bbea63b14e5c9",
+             "dd308afec5777e13121fa72b9cc1b7cc0139715309b086c960e18fd969774eb8"),
+            ("0b432b2677937381aef05bb02a66ecd012773062cf3fa2549e44f58ed2401710",
+             "25d1dff95105f5253c4022f628a996ad3a0d95fbf21d468a1b33f8c160d8f517"),
+        ];
+        for (ssk, spk) in KAT.iter() {
+            let sk = PrivateKey::decode(&hex::decode(ssk).unwrap()).unwrap();
+            let mut epk = [0u8; 32];
+            epk.copy_from_slice(&hex::decode(spk).unwrap());
+            let xpk = XOnlyPublicKey::from_bytes(&epk).unwrap();
+            assert!(xpk.to_bytes() == epk);
+            assert!(sk.to_x_only_public_key() == xpk);
+
+            // The full public key has the same x coordinate, and the
+            // parity tracks its y coordinate.
+            let pk = sk.to_public_key();
+            let (xpk2, parity) = XOnlyPublicKey::from_public_key(&pk);
+            assert!(xpk2 == xpk);
+            assert!(xpk2.equals(xpk) == 0xFFFFFFFF);
+            let ep = pk.encode_compressed();
+            assert!(ep[1..] == epk[..]);
+            assert!(parity == if ep[0] == 0x03 {
+                Parity::Odd
+            } else {
+                Parity::Even
+            });
+            assert!(xpk.to_public_key(parity).point.equals(pk.point) != 0);
+            assert!(xpk.to_public_key(Parity::Even).encode_compressed()[0]
+                == 0x02);
+        }
+
+        // Round trips through full keys, with both parities.
+        let mut sh = Sha256::new();
+        let mut seen = [false; 2];
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let seed: [u8; 32] = sh.finalize_reset().into();
+            let pk = PrivateKey::from_seed(&seed).to_public_key();
+            let (xpk, parity) = XOnlyPublicKey::from_public_key(&pk);
+            seen[(parity == Parity::Odd) as usize] = true;
+            let pk2 = xpk.to_public_key(parity);
+            assert!(pk2.encode_compressed() == pk.encode_compressed());
+            let other = if parity == Parity::Even {
+                Parity::Odd
+            } else {
+                Parity::Even
+            };
+            let pk3 = xpk.to_public_key(other);
+            assert!(pk3.point.equals(-pk.point) != 0);
+            assert!(XOnlyPublicKey::from_public_key(&pk3) == (xpk, other));
+            let xpk4 = XOnlyPublicKey::from_bytes(&xpk.to_bytes()).unwrap();
+            assert!(xpk4 == xpk);
+            let (xpk5, _) = XOnlyPublicKey::from_public_key(
+                &PrivateKey::from_seed(&seed[..31]).to_public_key());
+            assert!(xpk5 != xpk);
+            assert!(xpk5.equals(xpk) == 0);
+        }
+        assert!(seen[0] && seen[1]);
+
+        // x values not lower than p, or which are not the x coordinate of
+        // a curve point, are rejected (BIP-340 test vectors 5 and 14).
+        let mut x = [0xFFu8; 32];
+        assert!(XOnlyPublicKey::from_bytes(&x).is_none());
+        x[27] = 0xFE;
+        x[30] = 0xFC;
+        x[31] = 0x2F;
+        assert!(XOnlyPublicKey::from_bytes(&x).is_none());
+        x.copy_from_slice(&hex::decode(
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc30")
+            .unwrap());
+        assert!(XOnlyPublicKey::from_bytes(&x).is_none());
+        x.copy_from_slice(&hex::decode(
+            "eefdea4cdb677750a420fee807eacf21eb9898ae79b9768766e4faa04a2d4a34")
+            .unwrap());
+        assert!(XOnlyPublicKey::from_bytes(&x).is_none());
+    }
+
+    #[test]
+    fn taproot() {
+        // BIP-341 wallet test vectors (scriptPubKey section):
+        // (internal key, Merkle root, tweaked key)
+        const KAT: &[(&str, &str, &str)] = &[
+            ("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+             "",
+             "53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343"),
+            ("187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+             "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21",
+             "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3"),
+        ];
+        for (sp, sroot, sq) in KAT.iter() {
+            let p = XOnlyPublicKey::decode(&hex::decode(sp).unwrap()).unwrap();
+            let mut root = [0u8; 32];
+            let root = if sroot.is_empty() {
+                None
+            } else {
+                root.copy_from_slice(&hex::decode(sroot).unwrap());
+                Some(&root)
+            };
+            let (q, parity) = p.tap_tweak(root);
+            assert!(hex::encode(q.encode()) == *sq);
+            assert!(p.check_tweak(&q, parity, root));
+            let other = if parity == Parity::Even {
+                Parity::Odd
+            } else {
+                Parity::Even
+            };
+            assert!(!p.check_tweak(&q, other, root));
+            assert!(!p.check_tweak(&p, parity, root));
+            assert!(!p.check_tweak(&q, parity, Some(&[0x42u8; 32])));
+        }
+
+        // Signing with the tweaked private key yields signatures that
+        // verify against the output key, with and without script tree.
+        let mut sh = Sha256::new();
+        for i in 0..10 {
+            sh.update((i as u64).to_le_bytes());
+            let seed: [u8; 32] = sh.finalize_reset().into();
+            let sk = PrivateKey::from_seed(&seed);
+            let p = sk.to_x_only_public_key();
+            for root in [None, Some(&seed)] {
+                let (q, parity) = p.tap_tweak(root);
+                let tsk = sk.tap_tweak_seckey(root);
+                assert!(tsk.to_x_only_public_key().encode() == q.encode());
+                let sig = tsk.sign_schnorr(b"taproot", &seed);
+                assert!(q.verify_schnorr(&sig, b"taproot"));
+                assert!(!p.verify_schnorr(&sig, b"taproot"));
+                assert!(p.check_tweak(&q, parity, root));
+            }
+        }
+    }
+
+    #[test]
+    fn ecdh() {
+        // (private key, peer public key, hashed output, x coordinate);
+        // outputs were computed with an independent implementation.
+        const KAT: &[[&str; 4]] = &[
+            ["1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef",
+             "0297855f402631f09e602e5ccadc219503f07cdd4c73b2215b5418f52a7fdbfcd9",
+             "aeaf2d25451a361e7ff694eb5ced41266c58d6ea204c8797c4b7f58fc82a3f16",
+             "2a15f55ab4d9474165e927d1b3c8e9dc0df2de76c09e4ef8072087c829af171d"],
+            ["0000000000000000000000000000000000000000000000000000000000000003",
+             "024bc2a31265153f07e70e0bab08724e6b85e217f8cd628ceb62974247bb493382",
+             "348ff471ead6e64389b90bca5718647ba7403ee16f13afc5e64b4427738e9f4c",
+             "5d1f8700144cd0a31d30a474a10931182fa5017dece7db9c7b78f30ccbadbe60"],
+            ["1111111111111111111111111111111111111111111111111111111111111111",
+             "022f8bde4d1a07209355b4a7250a5c5128e88b84bddc619ab7cba8d569b240efe4",
+             "551d39829e14fe5b59898ac79b34d471419c2ba5eda8c857a560ac73c886cc4d",
+             "9ac20335eb38768d2052be1dbbc3c8f6178407458e51e6b4ad22f1d91758895b"],
+        ];
+        for kv in KAT.iter() {
+            let sk = PrivateKey::decode(&hex::decode(kv[0]).unwrap()).unwrap();
+            let peer = PublicKey::decode(&hex::decode(kv[1]).unwrap()).unwrap();
+            assert!(hex::encode(super::ecdh(&sk, &peer).unwrap()) == kv[2]);
+            assert!(hex::encode(super::ecdh_xonly(&sk, &peer).unwrap()) == kv[3]);
+        }
+
+        // Symmetry: a*B == b*A.
+        let mut sh = Sha256::new();
+        for i in 0..10 {
+            sh.update((2 * i as u64).to_le_bytes());
+            let sa = PrivateKey::from_seed(&sh.finalize_reset());
+            sh.update((2 * i as u64 + 1).to_le_bytes());
+            let sb = PrivateKey::from_seed(&sh.finalize_reset());
+            let pa = sa.to_public_key();
+            let pb = sb.to_public_key();
+            assert!(super::ecdh(&sa, &pb) == super::ecdh(&sb, &pa));
+            assert!(super::ecdh_xonly(&sa, &pb) == super::ecdh_xonly(&sb, &pa));
+            assert!(super::ecdh(&sa, &pb) != super::ecdh(&sa, &pa));
+        }
+
+        // The point-at-infinity is rejected.
+        let sk = PrivateKey::decode(&hex::decode(KAT[0][0]).unwrap()).unwrap();
+        let bad = PublicKey { point: Point::NEUTRAL };
+        assert!(super::ecdh(&sk, &bad).is_none());
+        assert!(super::ecdh_xonly(&sk, &bad).is_none());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn blinded_mul() {
+        use crate::chacha20::ChaChaRng;
+        use crate::{RngCore, SeedableRng};
+
+        // Blinded computations return the same values as the unblinded
+        // ones, whatever the RNG output.
+        let mut rng = ChaChaRng::from_seed([0x44u8; 32]);
+        let mut buf = [0u8; 64];
+        for i in 0..20 {
+            rng.fill_bytes(&mut buf);
+            let n = match i {
+                0 => Scalar::ZERO,
+                1 => Scalar::ONE,
+                2 => -Scalar::ONE,
+                _ => Scalar::decode_reduce(&buf[..32]),
+            };
+            let Q = Point::mulgen(&n);
+            assert!(Point::mulgen_blinded(&n, &mut rng).equals(Q) != 0);
+            let P = Point::mulgen(&Scalar::decode_reduce(&buf[32..]));
+            assert!(P.mul_blinded(&n, &mut rng).equals(P * n) != 0);
+            assert!(Point::NEUTRAL.mul_blinded(&n, &mut rng).isneutral() != 0);
+
+            let sk = PrivateKey::generate(&mut rng);
+            let hv = Sha256::digest(&buf[..]);
+            assert!(sk.sign_hash_blinded(&mut rng, &hv, &[])
+                == sk.sign_hash(&hv, &[]));
+            assert!(sk.sign_hash_blinded(&mut rng, &hv, b"extra")
+                == sk.sign_hash(&hv, b"extra"));
+            let peer = PrivateKey::generate(&mut rng).to_public_key();
+            assert!(super::ecdh_blinded(&mut rng, &sk, &peer)
+                == super::ecdh(&sk, &peer));
+        }
+
+        let sk = PrivateKey::generate(&mut rng);
+        let bad = PublicKey { point: Point::NEUTRAL };
+        assert!(super::ecdh_blinded(&mut rng, &sk, &bad).is_none());
+    }
+
+    #[test]
+    fn decode_batch() {
+        use crate::Vec;
+
+        // Valid keys (with both parities of y), an off-curve x
+        // (x = p - 1), a non-canonical x (x = p), and bad prefixes on a
+        // valid x, placed at every position in the batch.
+        let mut sh = Sha256::new();
+        let mut good = Vec::new();
+        for i in 0..8 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let P = Point::mulgen(&Scalar::decode_reduce(&v[..]));
+            good.push(P.encode_compressed());
+            good.push((-P).encode_compressed());
+        }
+        let mut p_m1 = [0xFFu8; 33];
+        p_m1[0] = 0x02;
+        p_m1[28] = 0xFE;
+        p_m1[31] = 0xFC;
+        p_m1[32] = 0x2E;
+        let mut p = p_m1;
+        p[32] = 0x2F;
+        let mut bad = Vec::new();
+        bad.push(p_m1);
+        bad.push(p);
+        for &h in [0x00u8, 0x01, 0x04, 0x06, 0x07, 0x12].iter() {
+            let mut e = good[0];
+            e[0] = h;
+            bad.push(e);
+        }
+        for b in bad.iter() {
+            assert!(Point::decode(b).is_none());
+        }
+
+        for b in bad.iter() {
+            for j in 0..=good.len() {
+                let mut encs = good.clone();
+                encs.insert(j, *b);
+                let mut out = vec![None; encs.len()];
+                assert!(Point::decode_batch(&encs, &mut out) == good.len());
+                for (e, P) in encs.iter().zip(out.iter()) {
+                    match (Point::decode(e), P) {
+                        (Some(Q), Some(P)) => {
+                            assert!(P.equals(Q) == 0xFFFFFFFF);
+                            assert!(P.encode_compressed() == *e);
+                        }
+                        (None, None) => { }
+                        _ => panic!(),
+                    }
+                }
+                assert!(out[j].is_none());
+            }
+        }
+
+        // Empty batch.
+        assert!(Point::decode_batch(&[], &mut []) == 0);
+    }
+
+    #[test]
+    fn decode_sec1() {
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let v: [u8; 32] = sh.finalize_reset().into();
+            let P = Point::mulgen(&Scalar::decode_reduce(&v));
+            let ec = P.encode_compressed();
+            let eu = P.encode_uncompressed();
+            let mut eh = eu;
+            eh[0] = 0x06 | (eu[64] & 1);
+
+            // All three formats decode to the same point.
+            for e in [&ec[..], &eu[..], &eh[..]] {
+                let Q = Point::decode_sec1(e).unwrap();
+                assert!(Q.equals(P) != 0);
+            }
+
+            // Hybrid encoding with the wrong parity.
+            let mut eh2 = eh;
+            eh2[0] ^= 1;
+            assert!(Point::decode_sec1(&eh2).err()
+                == Some(DecodeError::NonCanonicalEncoding));
+
+            // Hybrid prefix with the compressed length, and other
+            // invalid prefixes.
+            let mut ec2 = ec;
+            ec2[0] = 0x06 | (ec[0] & 1);
+            assert!(Point::decode_sec1(&ec2).err()
+                == Some(DecodeError::BadHeader));
+            for hdr in [0x00u8, 0x01, 0x05, 0x08, 0xFF] {
+                let mut eu2 = eu;
+                eu2[0] = hdr;
+                assert!(Point::decode_sec1(&eu2).err()
+                    == Some(DecodeError::BadHeader));
+            }
+
+            // Coordinates which do not match the curve equation.
+            let mut eu2 = eu;
+            eu2[64] ^= 2;
+            let mut eh3 = eh;
+            eh3[64] ^= 2;
+            assert!(Point::decode_sec1(&eu2).err()
+                == Some(DecodeError::NotOnCurve));
+            assert!(Point::decode_sec1(&eh3).err()
+                == Some(DecodeError::NotOnCurve));
+
+            // Wrong lengths.
+            for len in [0, 2, 32, 34, 64, 66] {
+                let mut buf = [0u8; 66];
+                buf[..65].copy_from_slice(&eu);
+                assert!(Point::decode_sec1(&buf[..len]).err()
+                    == Some(DecodeError::BadLength));
+            }
+        }
+
+        // Point-at-infinity: accepted, and flagged as neutral.
+        let P = Point::decode_sec1(&[0x00]).unwrap();
+        assert!(P.isneutral() != 0);
+        assert!(Point::decode_sec1(&[0x01]).err()
+            == Some(DecodeError::BadHeader));
+
+        // x coordinate not lower than the field modulus.
+        let mut eh = [0xFFu8; 65];
+        eh[0] = 0x07;
+        assert!(Point::decode_sec1(&eh).err()
+            == Some(DecodeError::NonCanonicalField));
+    }
+
+    #[test]
+    fn expand_message_xmd() {
+        // RFC 9380, appendix K.1.
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        const KAT: &[(&str, &str)] = &[
+            ("", "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"),
+            ("abc", "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"),
+        ];
+        for (msg, out) in KAT.iter() {
+            let mut buf = [0u8; 32];
+            super::expand_message_xmd_sha256(&[msg.as_bytes()], dst, &mut buf);
+            assert!(buf[..] == hex::decode(out).unwrap());
+        }
+    }
+
+    #[test]
+    fn iso_map() {
+        // (x', y') on the isogenous curve, and (x, y) on secp256k1; these
+        // are the intermediate values Q0 and Q1 of the first test vector
+        // in RFC 9380, appendix J.8.1.
+        const KAT: &[[&str; 4]] = &[
+            [
+                "d4f1e0934f412a0cf1483465f96f028de15bf5bd48ceb3f7c94590ea45ccdc13",
+                "4f30b2fd1988697cabc0819818e425c38b84dedee37c8d656279b793b4a05381",
+                "74519ef88b32b425a095e4ebcc84d81b64e9e2c2675340a720bb1a1857b99f1e",
+                "c174fa322ab7c192e11748beed45b508e9fdb1ce046dee9c2cd3a2a86b410936",
+            ],
+            [
+                "f1ed49c73a5639f439b3ba4a7737b400f6c95b4bbf5013232d20a47403086410",
+                "1fa797372920e7008b13a0edc92eb8460b22d2d0d3de697d587f545d2673c54e",
+                "44548adb1b399263ded3510554d28b4bead34b8cf9a37b4bd0bd2ba4db87ae63",
+                "96eb8e2faf05e368efe5957c6167001760233e6dd2487516b46ae725c4cce0c6",
+            ],
+        ];
+        for kv in KAT.iter() {
+            let f = |s: &str| {
+                let mut buf = hex::decode(s).unwrap();
+                buf.reverse();
+                super::GFsecp256k1::decode_reduce(&buf)
+            };
+            let P = super::iso_map(&(f(kv[0]), f(kv[1])));
+            let enc = P.encode_uncompressed();
+            assert!(enc[1..33] == hex::decode(kv[2]).unwrap());
+            assert!(enc[33..] == hex::decode(kv[3]).unwrap());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn generators() {
+        // Pinned values: the derivation process must not change.
+        const KAT: [&str; 4] = [
+            "039a0978d6eb31702e9aa5c24bf1d93c8520ce407511afccb5ad0c97f616ba93c7",
+            "0231a878428acc9637d295b2747f57f1cfabecd2486140b102217314a8bbb224a3",
+            "03d4a4d515017be7227851839192009fbf3d938094d484448d2d6e62b99c23ee4c",
+            "02cd7a7080a407efa617d13e2213e5f28f6b41163fd16f4d46f1908cff4b3e6565",
+        ];
+        let domain = b"crrl test generators";
+        let gg = Point::derive_generators(domain, 4);
+        assert!(gg.len() == 4);
+        for (i, G) in gg.iter().enumerate() {
+            assert!(G.encode_compressed()[..] == hex::decode(KAT[i]).unwrap()[..]);
+            let mut d = [0u8; 28];
+            d[..20].copy_from_slice(domain);
+            d[20..].copy_from_slice(&(i as u64).to_le_bytes());
+            let G2 = Point::hash_to_curve(&d, b"crrl-secp256k1-generators-v1");
+            assert!(G.equals(G2) == 0xFFFFFFFF);
+        }
+        for (G, G2) in gg.iter().zip(Point::generators(domain)) {
+            assert!(G.equals(G2) == 0xFFFFFFFF);
+        }
+        assert!(Point::derive_generators(domain, 0).is_empty());
+
+        // Generators are distinct from each other, and are not small
+        // multiples of the conventional generator.
+        let gg = Point::derive_generators(domain, 16);
+        for i in 0..gg.len() {
+            assert!(gg[i].isneutral() == 0);
+            for j in 0..i {
+                assert!(gg[i].equals(gg[j]) == 0);
+            }
+            let mut P = Point::BASE;
+            for _ in 0..32 {
+                assert!(gg[i].equals(P) == 0);
+                assert!(gg[i].equals(-P) == 0);
+                P += Point::BASE;
+            }
+        }
+        assert!(Point::derive_generator(b"other domain", 0).equals(gg[0])
+            == 0);
+    }
+
+    #[test]
+    fn hash_to_curve() {
+        // Test vectors from RFC 9380, appendix J.8.1 and J.8.2.
+        let q128 = [b'q'; 128];
+        let a512 = [b'a'; 512];
+        let mut m128 = [0u8; 133];
+        m128[..5].copy_from_slice(b"q128_");
+        m128[5..].copy_from_slice(&q128);
+        let mut m512 = [0u8; 517];
+        m512[..5].copy_from_slice(b"a512_");
+        m512[5..].copy_from_slice(&a512);
+        let msgs: [&[u8]; 5] = [b"", b"abc", b"abcdef0123456789", &m128, &m512];
+        const KAT_RO: [&str; 10] = [
+            "c1cae290e291aee617ebaef1be6d73861479c48b841eaba9b7b5852ddfeb1346",
+            "64fa678e07ae116126f08b022a94af6de15985c996c3a91b64c406a960e51067",
+            "3377e01eab42db296b512293120c6cee72b6ecf9f9205760bd9ff11fb3cb2c4b",
+            "7f95890f33efebd1044d382a01b1bee0900fb6116f94688d487c6c7b9c8371f6",
+            "bac54083f293f1fe08e4a70137260aa90783a5cb84d3f35848b324d0674b0e3a",
+            "4436476085d4c3c4508b60fcf4389c40176adce756b398bdee27bca19758d828",
+            "e2167bc785333a37aa562f021f1e881defb853839babf52a7f72b102e41890e9",
+            "f2401dd95cc35867ffed4f367cd564763719fbc6a53e969fb8496a1e6685d873",
+            "e3c8d35aaaf0b9b647e88a0a0a7ee5d5bed5ad38238152e4e6fd8c1f8cb7c998",
+            "8446eeb6181bf12f56a9d24e262221cc2f0c4725c7e3803024b5888ee5823aa6",
+        ];
+        let dst = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+        for i in 0..msgs.len() {
+            let enc = Point::hash_to_curve(msgs[i], dst).encode_uncompressed();
+            assert!(enc[1..33] == hex::decode(KAT_RO[2 * i]).unwrap());
+            assert!(enc[33..] == hex::decode(KAT_RO[2 * i + 1]).unwrap());
+        }
+
+        const KAT_NU: [&str; 4] = [
+            "a4792346075feae77ac3b30026f99c1441b4ecf666ded19b7522cf65c4c55c5b",
+            "62c59e2a6aeed1b23be5883e833912b08ba06be7f57c0e9cdc663f31639ff3a7",
+            "3f3b5842033fff837d504bb4ce2a372bfeadbdbd84a1d2b678b6e1d7ee426b9d",
+            "902910d1fef15d8ae2006fc84f2a5a7bda0e0407dc913062c3a493c4f5d876a5",
+        ];
+        let dst = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_NU_";
+        for i in 0..2 {
+            let enc = Point::encode_to_curve(msgs[i], dst).encode_uncompressed();
+            assert!(enc[1..33] == hex::decode(KAT_NU[2 * i]).unwrap());
+            assert!(enc[33..] == hex::decode(KAT_NU[2 * i + 1]).unwrap());
+        }
+
+        // Outputs are valid curve points (they decode back), and never
+        // the point-at-infinity; a long DST is also supported.
+        let long_dst = [0x55u8; 300];
+        for i in 0..50u32 {
+            let msg = i.to_le_bytes();
+            for P in [
+                Point::hash_to_curve(&msg, b"crrl-test"),
+                Point::encode_to_curve(&msg, b"crrl-test"),
+                Point::hash_to_curve(&msg, &long_dst),
+            ] {
+                assert!(P.isneutral() == 0);
+                let Q = Point::decode(&P.encode_uncompressed()).unwrap();
+                assert!(Q.equals(P) == 0xFFFFFFFF);
+            }
+        }
+    }
+
+    #[test]
+    fn low_s() {
+        // Signatures with s = (n-1)/2 (low) and s = (n+1)/2 (high).
+        let half = -Scalar::ONE / (Scalar::ONE + Scalar::ONE);
+        let mut sig_lo = [0u8; 64];
+        sig_lo[31] = 1;
+        sig_lo[32..].copy_from_slice(&bswap32(&half.encode()));
+        let mut sig_hi = sig_lo;
+        sig_hi[32..].copy_from_slice(&bswap32(&(half + Scalar::ONE).encode()));
+        assert!(has_low_s(&sig_lo));
+        assert!(!has_low_s(&sig_hi));
+        assert!(normalize_s(&sig_lo) == (sig_lo, false));
+        assert!(normalize_s(&sig_hi) == (sig_lo, true));
+
+        // Out-of-range s is left unmodified.
+        let mut sig_bad = sig_lo;
+        sig_bad[32..].fill(0xFF);
+        assert!(!has_low_s(&sig_bad));
+        assert!(normalize_s(&sig_bad) == (sig_bad, false));
+
+        let mut sh = Sha256::new();
+        let mut nhi = 0;
+        for i in 0..20 {
+            sh.update((i as u64).to_le_bytes());
+            let seed = sh.finalize_reset();
+            let skey = PrivateKey::from_seed(&seed);
+            let pkey = skey.to_public_key();
+            sh.update(seed);
+            let hv = sh.finalize_reset();
+
+            // Normalization flips s only for high-s signatures, and is
+            // idempotent.
+            let sig = skey.sign_hash(&hv, &[]);
+            let (nsig, flipped) = normalize_s(&sig);
+            assert!(flipped == !has_low_s(&sig));
+            assert!(has_low_s(&nsig));
+            assert!(normalize_s(&nsig) == (nsig, false));
+            assert!(nsig[..32] == sig[..32]);
+            let sig2 = skey.sign_hash_low_s(&hv, &[]);
+            assert!(sig2 == nsig);
+            if flipped {
+                nhi += 1;
+            }
+
+            // Both forms are valid, but the high form is rejected if the
+            // policy requires low-s signatures.
+            let (s, _) = Scalar::decode32(&bswap32(&nsig[32..]));
+            let mut hsig = nsig;
+            hsig[32..].copy_from_slice(&bswap32(&(-s).encode()));
+            assert!(!has_low_s(&hsig));
+            assert!(pkey.verify_hash(&nsig, &hv));
+            assert!(pkey.verify_hash(&hsig, &hv));
+            assert!(pkey.verify_hash_with_policy(&nsig, &hv, true));
+            assert!(pkey.verify_hash_with_policy(&hsig, &hv, true));
+            assert!(pkey.verify_hash_with_policy(&nsig, &hv, false));
+            assert!(!pkey.verify_hash_with_policy(&hsig, &hv, false));
+        }
+        assert!(nhi > 0 && nhi < 20);
+    }
+
+    #[test]
+    fn batch() {
+        use crate::Vec;
+
+        // 24 key pairs, with signatures and recovery identifiers.
+        let mut pkeys = Vec::new();
+        let mut hvs = Vec::new();
+        let mut sigs = Vec::new();
+        let mut recids = Vec::new();
+        for i in 0..24 {
+            let skey = PrivateKey::from_seed(&Sha256::digest(&[i as u8]));
+            let pkey = skey.to_public_key();
+            let hv: [u8; 32] = Sha256::digest(&[i as u8, 0x42]).into();
+            let sig = skey.sign_hash(&hv, &[]);
+            recids.push(pkey.compute_recovery_id(&sig, &hv).unwrap());
+            pkeys.push(pkey);
+            hvs.push(hv);
+            sigs.push(sig);
+        }
+        let seed = [0x5Au8; 32];
+
+        // All-valid batches (including the empty batch).
+        for n in [0, 1, 2, 5, 24] {
+            let entries: Vec<(PublicKey, &[u8; 32], [u8; 64], u8)> = (0..n)
+                .map(|i| (pkeys[i], &hvs[i], sigs[i], recids[i])).collect();
+            assert!(verify_batch(&entries, &seed));
+            assert!(verify_batch_find_invalid(&entries, &seed).is_empty());
+        }
+
+        // Batches with exactly one invalid entry: modified s, modified
+        // hash value, wrong key, and wrong recovery identifier (the
+        // latter is still a valid signature for verify_hash()).
+        let mut bad_hv = hvs[0];
+        bad_hv[5] ^= 0x01;
+        for n in [1, 2, 7] {
+            for j in 0..n {
+                for k in 0..4 {
+                    let mut entries: Vec<(PublicKey, &[u8; 32], [u8; 64], u8)>
+                        = (0..n).map(|i| (pkeys[i], &hvs[i], sigs[i], recids[i]))
+                        .collect();
+                    match k {
+                        0 => { entries[j].2[40] ^= 0x01; }
+                        1 => { entries[j].1 = &bad_hv; }
+                        2 => { entries[j].0 = pkeys[j + 1]; }
+                        _ => { entries[j].3 ^= 1; }
+                    }
+                    let (pk, hv, sig, _) = entries[j];
+                    assert!(pk.verify_hash(&sig, hv) == (k == 3));
+                    assert!(!verify_batch(&entries, &seed));
+                    assert!(verify_batch_find_invalid(&entries, &seed) == [j]);
+                }
+            }
+        }
+
+        // Agreement with sequential verification (recovery of the
+        // public key), with several invalid entries, including
+        // out-of-range recovery identifiers and signature values.
+        let mut entries: Vec<(PublicKey, &[u8; 32], [u8; 64], u8)> = (0..24)
+            .map(|i| (pkeys[i], &hvs[i], sigs[i], recids[i])).collect();
+        entries[3].1 = &hvs[4];
+        entries[8].3 = 4;
+        entries[11].2[32..].fill(0xFF);
+        entries[12].0 = pkeys[13];
+        entries[20].2[..32].fill(0x00);
+        let mut bad = Vec::new();
+        for (i, (pk, hv, sig, recid)) in entries.iter().enumerate() {
+            match PublicKey::recover_from_signature(&hv[..], sig, *recid) {
+                Some(Q) if Q.point.equals(pk.point) != 0 => { }
+                _ => { bad.push(i); }
+            }
+        }
+        assert!(bad == [3, 8, 11, 12, 20]);
+        assert!(!verify_batch(&entries, &seed));
+        assert!(verify_batch_find_invalid(&entries, &seed) == bad);
+    }
+
+    #[test]
+    fn der_signatures() {
+        // Signatures generated with OpenSSL: high bit set in r only, in
+        // s only, and a short r. Our nonce derivation differs from
+        // OpenSSL's, so our own signatures are only round-tripped.
+        let sk = PrivateKey::decode(&hex::decode(
+            "048d159e242af37bc00000000000000000000000000000000000000000003039")
+            .unwrap()).unwrap();
+        let pk = PublicKey::decode(&hex::decode(
+            "02187e35c545cf43242bd24cabbb2f38008f90060e6a8ebb4ff716a9e99755765e")
+            .unwrap()).unwrap();
+        assert!(sk.to_public_key().encode_compressed() == pk.encode_compressed());
+        const KAT: &[(&[u8], &str)] = &[
+            (b"msg1", "3045022100e9c2cce8498d8b0b5a9cbf425688aa090b34c10bd4910f063874381c495c90ff022013e2b2ba46201b1787b4940a465422ebeebd114b5c3c1dfed6f64bf3f7a29ca7"),
+            (b"msg0", "304502207c7c43c66a0b28ddfd8173edc6b5104f195487a45e6f13fcc76cd70caa47e9ae022100de6580655b1e48d635563fcfcb089ec76f8d442b97cb99e7ef4fcafcaf3db306"),
+            (b"msg914", "3044021f4648c235215cc597c7809b5bd5906ba34c905e413d2355e42be88e2c02b30d022100a82a56806b8537e8a9a2c23ff92825968aebcc8f30ed26b58144e55e698dcac1"),
+        ];
+        for (msg, der) in KAT.iter() {
+            let der = hex::decode(der).unwrap();
+            let hv: [u8; 32] = Sha256::digest(msg).into();
+            let sig = signature_from_der(&der, false).unwrap();
+            assert!(pk.verify_hash(&sig, &hv));
+            let mut buf = [0u8; 72];
+            let n = signature_to_der(&sig, &mut buf);
+            assert!(buf[..n] == der[..]);
+            let sig2 = sk.sign_hash(&hv, &[]);
+            let n = signature_to_der(&sig2, &mut buf);
+            assert!(signature_from_der(&buf[..n], false).unwrap() == sig2);
+
+            // Trailing garbage is rejected.
+            let mut der2 = der.clone();
+            der2.push(0x00);
+            assert!(signature_from_der(&der2, false).err()
+                == Some(DerError::InvalidEncoding));
+            assert!(signature_from_der(&der2, true).err()
+                == Some(DerError::InvalidEncoding));
+
+            // Non-minimal integer encoding for s is accepted only in
+            // lenient mode.
+            let mut der3 = der.clone();
+            let k = 4 + (der[3] as usize);
+            der3[k + 1] += 1;
+            der3.insert(k + 2, 0x00);
+            der3[1] += 1;
+            assert!(signature_from_der(&der3, false).err()
+                == Some(DerError::NonCanonical));
+            assert!(signature_from_der(&der3, true).unwrap() == sig);
+        }
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn generate() {
+        use crate::chacha20::ChaChaRng;
+        use crate::{RngCore, SeedableRng};
+
+        // Keys are deterministic for a given RNG state, and distinct
+        // RNG states yield distinct keys.
+        let mut rng1 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng2 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng3 = ChaChaRng::from_seed([0x22u8; 32]);
+        let sk1 = PrivateKey::generate(&mut rng1);
+        let sk2 = PrivateKey::generate(&mut rng2);
+        let sk3 = PrivateKey::generate(&mut rng3);
+        assert!(sk1.encode() == sk2.encode());
+        assert!(sk1.encode() != sk3.encode());
+        assert!(PrivateKey::generate(&mut rng1).encode() != sk1.encode());
+
+        // The RNG output is used as seed for the deterministic
+        // derivation.
+        let mut seed = [0u8; 32];
+        ChaChaRng::from_seed([0x11u8; 32]).fill_bytes(&mut seed);
+        assert!(PrivateKey::from_seed(&seed).encode() == sk1.encode());
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn bip32() {
+        use super::bip32::{ExtendedPrivateKey, ExtendedPublicKey};
+        use super::bip32::{Bip32Error, HARDENED};
+
+        // Test vectors 1 to 4 from BIP-32: for each chain, the path,
+        // extended public key, and extended private key.
+        const KAT: [(&str, &[(&str, &str, &str)]); 4] = [
+            ("000102030405060708090a0b0c0d0e0f", &[
+                ("m",
+                 "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+                 "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi"),
+                ("m/0'",
+                 "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+                 "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7"),
+                ("m/0'/1",
+                 "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+                 "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs"),
+                ("m/0'/1/2'",
+                 "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5",
+                 "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM"),
+                ("m/0'/1/2'/2",
+                 "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+                 "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334"),
+                ("m/0'/1/2'/2/1000000000",
+                 "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+                 "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76"),
+            ]),
+            ("fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542", &[
+                ("m",
+                 "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+                 "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U"),
+                ("m/0",
+                 "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+                 "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt"),
+                ("m/0/2147483647'",
+                 "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a",
+                 "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9"),
+                ("m/0/2147483647'/1",
+                 "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon",
+                 "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef"),
+                ("m/0/2147483647'/1/2147483646'",
+                 "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL",
+                 "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc"),
+                ("m/0/2147483647'/1/2147483646'/2",
+                 "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt",
+                 "xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j"),
+            ]),
+            ("4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be", &[
+                ("m",
+                 "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13",
+                 "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6"),
+                ("m/0'",
+                 "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y",
+                 "xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L"),
+            ]),
+            ("3ddd5602285899a946114506157c7997e5444528f3003f6134712147db19b678", &[
+                ("m",
+                 "xpub661MyMwAqRbcGczjuMoRm6dXaLDEhW1u34gKenbeYqAix21mdUKJyuyu5F1rzYGVxyL6tmgBUAEPrEz92mBXjByMRiJdba9wpnN37RLLAXa",
+                 "xprv9s21ZrQH143K48vGoLGRPxgo2JNkJ3J3fqkirQC2zVdk5Dgd5w14S7fRDyHH4dWNHUgkvsvNDCkvAwcSHNAQwhwgNMgZhLtQC63zxwhQmRv"),
+                ("m/0'",
+                 "xpub69AUMk3qDBi3uW1sXgjCmVjJ2G6WQoYSnNHyzkmdCHEhSZ4tBok37xfFEqHd2AddP56Tqp4o56AePAgCjYdvpW2PU2jbUPFKsav5ut6Ch1m",
+                 "xprv9vB7xEWwNp9kh1wQRfCCQMnZUEG21LpbR9NPCNN1dwhiZkjjeGRnaALmPXCX7SgjFTiCTT6bXes17boXtjq3xLpcDjzEuGLQBM5ohqkao9G"),
+                ("m/0'/1'",
+                 "xpub6BJA1jSqiukeaesWfxe6sNK9CCGaujFFSJLomWHprUL9DePQ4JDkM5d88n49sMGJxrhpjazuXYWdMf17C9T5XnxkopaeS7jGk1GyyVziaMt",
+                 "xprv9xJocDuwtYCMNAo3Zw76WENQeAS6WGXQ55RCy7tDJ8oALr4FWkuVoHJeHVAcAqiZLE7Je3vZJHxspZdFHfnBEjHqU5hG1Jaj32dVoS6XLT1"),
+            ]),
+        ];
+
+        for (seed, chain) in KAT.iter() {
+            let master = ExtendedPrivateKey::from_seed(
+                &hex::decode(seed).unwrap()).unwrap();
+            let mut prev: Option<ExtendedPrivateKey> = None;
+            for (path, xpub, xprv) in chain.iter() {
+                let k = master.derive_path(path).unwrap();
+                assert!(format!("{}", k) == *xprv);
+                let kp = k.to_extended_public_key();
+                assert!(format!("{}", kp) == *xpub);
+
+                // Step-by-step derivation, and public derivation for
+                // non-hardened children.
+                let depth = path.split('/').count() - 1;
+                assert!(k.depth() as usize == depth);
+                if let Some(p) = prev {
+                    let k2 = p.derive_child(k.child_number()).unwrap();
+                    assert!(k2.encode() == k.encode());
+                    assert!(k.parent_fingerprint() == p.fingerprint());
+                    let pp = p.to_extended_public_key();
+                    if k.child_number() < HARDENED {
+                        let kp2 = pp.derive_child(k.child_number()).unwrap();
+                        assert!(kp2.encode() == kp.encode());
+                    } else {
+                        assert!(pp.derive_child(k.child_number()).err()
+                            == Some(Bip32Error::HardenedDerivation));
+                    }
+                } else {
+                    assert!(k.parent_fingerprint() == [0u8; 4]);
+                    assert!(k.child_number() == 0);
+                }
+
+                // Decoding.
+                let k3: ExtendedPrivateKey = xprv.parse().unwrap();
+                assert!(k3.encode() == k.encode());
+                assert!(ExtendedPrivateKey::decode(&k.encode()).unwrap()
+                    .encode() == k.encode());
+                let kp3: ExtendedPublicKey = xpub.parse().unwrap();
+                assert!(kp3.encode() == kp.encode());
+                assert!(kp3.fingerprint() == k.fingerprint());
+                assert!(xpub.parse::<ExtendedPrivateKey>().err()
+                    == Some(Bip32Error::InvalidVersion));
+                assert!(xprv.parse::<ExtendedPublicKey>().err()
+                    == Some(Bip32Error::InvalidVersion));
+                prev = Some(k);
+            }
+        }
+
+        // Identifier and fingerprint of the vector 1 master key.
+        let master = ExtendedPrivateKey::from_seed(
+            &hex::decode("000102030405060708090a0b0c0d0e0f").unwrap()).unwrap();
+        assert!(master.identifier()[..] == hex::decode(
+            "3442193e1bb70916e914552172cd4e2dbc9df811").unwrap());
+        assert!(master.fingerprint() == [0x34, 0x42, 0x19, 0x3e]);
+
+        // Public derivation along a non-hardened path.
+        let xp = master.derive_path("m/0'").unwrap();
+        let xpp = xp.to_extended_public_key();
+        assert!(xpp.derive_path("m/1/2/3").unwrap().encode()
+            == xp.derive_path("m/1/2/3").unwrap()
+                .to_extended_public_key().encode());
+        assert!(xpp.derive_path("m/1/2'").err()
+            == Some(Bip32Error::HardenedDerivation));
+
+        // Path syntax.
+        let k1 = master.derive_path("m/0'/1/2'").unwrap();
+        assert!(master.derive_path("m/0h/1/2H").unwrap().encode() == k1.encode());
+        assert!(master.derive_path("m").unwrap().encode() == master.encode());
+        for p in ["", "0", "m/", "m//1", "m/x", "M/0", "m/1'/", "m/-1",
+            "m/+1", "m/2147483648", "m/4294967296'", "m/0''", "/0"]
+        {
+            assert!(master.derive_path(p).err()
+                == Some(Bip32Error::InvalidPath));
+        }
+
+        // Seed length.
+        assert!(ExtendedPrivateKey::from_seed(&[0u8; 15]).err()
+            == Some(Bip32Error::InvalidSeed));
+        assert!(ExtendedPrivateKey::from_seed(&[0u8; 65]).err()
+            == Some(Bip32Error::InvalidSeed));
+        assert!(ExtendedPrivateKey::from_seed(&[0u8; 64]).is_ok());
+
+        // Depth overflow.
+        let mut k = master;
+        for _ in 0..255 {
+            k = k.derive_child(0).unwrap();
+        }
+        assert!(k.depth() == 255);
+        assert!(k.derive_child(0).err() == Some(Bip32Error::DepthOverflow));
+        assert!(k.to_extended_public_key().derive_child(0).err()
+            == Some(Bip32Error::DepthOverflow));
+
+        // Invalid serializations (as in test vector 5, built from the
+        // vector 1 master key): each with a valid checksum but invalid
+        // contents, except the last two.
+        const BAD: [(&str, Bip32Error); 16] = [
+            // xpub version, private key data
+            ("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gYweD1YUMnzkxQw1bm6XhhCCXF5rvDu3SQRW2A1Z5yqnVwyY4cNT",
+             Bip32Error::InvalidKey),
+            // xprv version, public key data
+            ("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChpzxM5bEu4ku6ynu4tP6GqJ5kziULDsCA7bVctSatEcmUDntDMZ",
+             Bip32Error::InvalidKey),
+            // public key with prefix 0x04
+            ("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ45ycVBsADt89FVXeDkYqbSeZmpjjnJETkyyiMwXokWPisrtUjm",
+             Bip32Error::InvalidKey),
+            // private key with prefix 0x04
+            ("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChtGudJ7tny1s3mDVifGEu33q1sqF4rpn2yU5HHVd2bvpANAPAP7",
+             Bip32Error::InvalidKey),
+            // public key with prefix 0x01
+            ("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gYxFk5nqmbwrSjnkQvUtYydeKpRyanfmc6qmeyusqpnVEF2j8DGn",
+             Bip32Error::InvalidKey),
+            // private key with prefix 0x01
+            ("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChnSg6bmoEgzBeJUNzvQF35FWGXz67kJ9g4FkYqRw3duegVvnguE",
+             Bip32Error::InvalidKey),
+            // depth 0 with non-zero parent fingerprint (private)
+            ("xprv9s21ZrQZgP7FPy2m8xcKMQTqW7DcKVHLn75ndPXRgPpEauxZvTqfLaFkUNhgwRzfUbEsRsV1nva7GztVrDt1PHHDbp2om9ggsFc5xRw7H7K",
+             Bip32Error::InvalidDepth),
+            // depth 0 with non-zero index (private)
+            ("xprv9s21ZrQH143K5xHBs26cwZK5DysagCJvyKkvGxYZfF4mZAqjPTNZDYRPyzMWuZqh2Ah4465C1KR38McHpLVffLbyzqfTkrY5tYLVhTL5ye4",
+             Bip32Error::InvalidDepth),
+            // depth 0 with non-zero parent fingerprint (public)
+            ("xpub661MyMwTWkfYcT7EEz9KiYQa4946ix1C9L1PRmw3EjMDTiHiU19utNaEKeLzTKQjQ88ovpWASDvR5X5e9Tif1KHJPWm2vgCHdNCpzmNvjTY",
+             Bip32Error::InvalidDepth),
+            // depth 0 with non-zero index (public)
+            ("xpub661MyMwAqRbcJSMey3ddJhFon1i55f2nLYgX5LxBDabkRyAsvzgomLjsqFzpRTFkwhazZ36LecmLvsoS7aLKHNc4nYPgvP3geewEjpUTwEd",
+             Bip32Error::InvalidDepth),
+            // unknown version bytes
+            ("pGoh3VSiBwoWmRoSExKdpxHJBCMF5iacGac3mc7Q7j3RD8AADSrpaVmfhA5z6V4aagkXui2W9FapryNxzQW8RvHDfJHBZWQMQj9JwRbJoC6zJxNu",
+             Bip32Error::InvalidVersion),
+            // private key 0
+            ("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChijLXZSun8bsGj49MuvWWsqL9fqS5fhiDUkRQvq8cj8L42RGwHP",
+             Bip32Error::InvalidKey),
+            // private key n
+            ("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkg5hntwdZH6QYdrGVYWUCS2Xv6FCMHoYQZYQDohv67LnGTwiNd",
+             Bip32Error::InvalidKey),
+            // public key not on the curve
+            ("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gYym6yCVZtiQKSpLUqpuy2xafsZZR8vydJmD1kZ1yXu2Lp8uNH4N",
+             Bip32Error::InvalidKey),
+            // invalid checksum
+            ("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBrw9iXq",
+             Bip32Error::InvalidEncoding),
+            // 77-byte payload
+            ("DeaWiRvhTUWHmRFa65QcRFoZqVNmvXCnyi7cod8wKuH6s3dLhoawqehRCwzNEK1fVrh3ojSNBkvrBj6GRe5UGW5qpMwtda7wfu3xHzJHBs1gum",
+             Bip32Error::InvalidEncoding),
+        ];
+        for (s, err) in BAD.iter() {
+            if s.starts_with("xpub") {
+                assert!(s.parse::<ExtendedPublicKey>().err() == Some(*err));
+            } else if s.starts_with("xprv") {
+                assert!(s.parse::<ExtendedPrivateKey>().err() == Some(*err));
+            } else {
+                assert!(s.parse::<ExtendedPublicKey>().err() == Some(*err));
+                assert!(s.parse::<ExtendedPrivateKey>().err() == Some(*err));
+            }
+        }
+        assert!("xprv0".parse::<ExtendedPrivateKey>().err()
+            == Some(Bip32Error::InvalidEncoding));
+        assert!(ExtendedPublicKey::decode(&[0u8; 77]).err()
+            == Some(Bip32Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn tweak() {
+        let mut sh = Sha256::new();
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let v = sh.finalize_reset();
+            let sk = PrivateKey::from_seed(&v);
+            let pk = sk.to_public_key();
+            sh.update(&v);
+            let t = Scalar::decode_reduce(&sh.finalize_reset());
+
+            // The tweaked private key matches the tweaked public key.
+            let sk2 = sk.tweak_add(&t).unwrap();
+            let pk2 = pk.tweak_add(&t).unwrap();
+            assert!(sk2.to_public_key().point.equals(pk2.point) == 0xFFFFFFFF);
+            assert!(pk2.point.equals(pk.point + Point::mulgen(&t))
+                == 0xFFFFFFFF);
+            let sk3 = sk.tweak_mul(&t).unwrap();
+            let pk3 = pk.tweak_mul(&t).unwrap();
+            assert!(sk3.to_public_key().point.equals(pk3.point) == 0xFFFFFFFF);
+            assert!(pk3.point.equals(pk.point * t) == 0xFFFFFFFF);
+
+            // t = 0: addition is the identity, multiplication fails.
+            assert!(sk.tweak_add(&Scalar::ZERO).unwrap().encode()
+                == sk.encode());
+            assert!(pk.tweak_add(&Scalar::ZERO).unwrap().point
+                .equals(pk.point) == 0xFFFFFFFF);
+            assert!(sk.tweak_mul(&Scalar::ZERO).is_none());
+            assert!(pk.tweak_mul(&Scalar::ZERO).is_none());
+
+            // t = n - sk: the sum is zero.
+            let x = Scalar::decode32(&bswap32(&sk.encode())).0;
+            assert!(sk.tweak_add(&-x).is_none());
+            assert!(pk.tweak_add(&-x).is_none());
+
+            // t = 1/sk: the product is one.
+            let u = Scalar::ONE / x;
+            assert!(sk.tweak_mul(&u).unwrap().encode()[31] == 1);
+            assert!(pk.tweak_mul(&u).unwrap().point.equals(Point::BASE)
+                == 0xFFFFFFFF);
+            assert!(sk.tweak_mul(&Scalar::ONE).unwrap().encode()
+                == sk.encode());
+        }
+    }
 }
@@ -0,0 +1,1193 @@
+//! SHA-2 hash functions (FIPS 180-4).
+//!
+//! This module implements SHA-224, SHA-256, SHA-384 and SHA-512, as well
+//! as the truncated variants SHA-512/224 and SHA-512/256. The latter use
+//! the SHA-512 compression function (which is faster than that of
+//! SHA-256 on 64-bit systems) with distinct initial values, so that they
+//! are not simple truncations of SHA-512 (and, since their output is
+//! shorter than the internal state, they are not subject to
+//! length-extension attacks).
+//!
+//! All types offer the same API: `new()`, `update()`, `update_vectored()`,
+//! `finalize()` (which does not modify the context), `finalize_reset()`,
+//! `reset()`, and the one-stop function `hash()`. The output is returned
+//! as a fixed-size array.
+//!
+//! On x86_64 with AVX2 support (enabled at compile time), the message
+//! schedule of the SHA-512 family is computed with AVX2 vector
+//! operations; the round function remains scalar. Independently of the
+//! hash contexts, `Sha256::hash_many()` hashes several messages at once;
+//! with AVX2, it processes eight messages in parallel (one per 32-bit
+//! vector lane).
+//!
+//! The state of a context can be exported with `export_state()`, and a
+//! context rebuilt from it with `import_state()`, e.g. to checkpoint a
+//! long computation. The exported state is a fixed-size array (105 bytes
+//! for SHA-224 and SHA-256, 209 bytes for the SHA-512 family), with the
+//! following format (all integers in big-endian):
+//!
+//!  - one byte identifying the function: 1 = SHA-224, 2 = SHA-256,
+//!    3 = SHA-384, 4 = SHA-512, 5 = SHA-512/224, 6 = SHA-512/256;
+//!
+//!  - the eight state words (32-bit words for SHA-224 and SHA-256,
+//!    64-bit words otherwise);
+//!
+//!  - the total number of bytes injected so far (over 64 bits for SHA-224
+//!    and SHA-256, 128 bits otherwise);
+//!
+//!  - the contents of the current (partial) block, over a full block
+//!    length (64 or 128 bytes); the bytes beyond the current partial
+//!    block length (which is the byte count modulo the block length) are
+//!    zero.
+//!
+//! Imported states are validated: the length and function identifier
+//! must match, the byte count must be such that the message length in
+//! bits fits in the length field of the padding, and unused buffer bytes
+//! must be zero. This format is stable across versions of this crate.
+//!
+//! When the `rustcrypto-traits` feature is enabled, the hash types also
+//! implement the traits of the RustCrypto `digest` crate (version 0.10,
+//! re-exported as `crrl::sha2::digest`), so that they can be used with
+//! generic code that expects a `digest::Digest` implementation.
+
+use core::convert::TryFrom;
+
+/// Error type for state import.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+    /// The state does not have the expected length.
+    WrongLength,
+    /// The state was exported from a different hash function.
+    WrongAlgorithm,
+    /// The byte counter is out of range.
+    InvalidCounter,
+    /// The unused part of the buffered block is not zero.
+    InvalidBuffer,
+}
+
+impl core::fmt::Display for StateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StateError::WrongLength => f.write_str("invalid state length"),
+            StateError::WrongAlgorithm => f.write_str("state is for another function"),
+            StateError::InvalidCounter => f.write_str("invalid byte counter"),
+            StateError::InvalidBuffer => f.write_str("invalid buffered data"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateError {}
+
+#[cfg(feature = "rustcrypto-traits")]
+pub use ::sha2::digest;
+
+// Context for the SHA-256 family (SHA-224 and SHA-256).
+#[derive(Clone, Copy, Debug)]
+struct Core256 {
+    h: [u32; 8],
+    buf: [u8; 64],
+    // Total number of bytes injected so far.
+    ctr: u64,
+}
+
+// Context for the SHA-512 family (SHA-384, SHA-512, SHA-512/t).
+#[derive(Clone, Copy, Debug)]
+struct Core512 {
+    h: [u64; 8],
+    buf: [u8; 128],
+    // Total number of bytes injected so far.
+    ctr: u128,
+}
+
+// Each core type implements the generic buffering on top of its block
+// function; the two differ only in word size and length encoding.
+macro_rules! define_core {
+    ($core: ident, $word: ty, $ctr: ty, $block_len: expr, $state_len: expr)
+    => {
+        impl $core {
+
+            fn new(iv: &[$word; 8]) -> Self {
+                Self { h: *iv, buf: [0u8; $block_len], ctr: 0 }
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                let mut data = data;
+                let p = (self.ctr as usize) & ($block_len - 1);
+                self.ctr += data.len() as $ctr;
+                if p != 0 {
+                    let clen = $block_len - p;
+                    if clen > data.len() {
+                        self.buf[p..(p + data.len())].copy_from_slice(data);
+                        return;
+                    }
+                    self.buf[p..].copy_from_slice(&data[..clen]);
+                    Self::process_blocks(&mut self.h, &self.buf);
+                    data = &data[clen..];
+                }
+                let n = data.len() & !($block_len - 1);
+                Self::process_blocks(&mut self.h, &data[..n]);
+                self.buf[..(data.len() - n)].copy_from_slice(&data[n..]);
+            }
+
+            // Apply the final padding, and return the resulting state
+            // words. The context itself is not modified.
+            fn finalize_state(&self) -> [$word; 8] {
+                let mut h = self.h;
+                let mut buf = self.buf;
+                let p = (self.ctr as usize) & ($block_len - 1);
+                // The message length (in bits) is encoded over as many
+                // bytes as the counter type (8 or 16).
+                let lb = (self.ctr << 3).to_be_bytes();
+                buf[p] = 0x80;
+                buf[(p + 1)..].fill(0);
+                if p >= $block_len - lb.len() {
+                    Self::process_blocks(&mut h, &buf);
+                    buf.fill(0);
+                }
+                buf[($block_len - lb.len())..].copy_from_slice(&lb);
+                Self::process_blocks(&mut h, &buf);
+                h
+            }
+
+            // Serialize the state, with the provided function identifier
+            // (the format is described in the module documentation).
+            fn export_state(&self, id: u8) -> [u8; $state_len] {
+                const WL: usize = core::mem::size_of::<$word>();
+                const CL: usize = core::mem::size_of::<$ctr>();
+                let mut r = [0u8; $state_len];
+                r[0] = id;
+                for i in 0..8 {
+                    r[(1 + WL * i)..(1 + WL * (i + 1))]
+                        .copy_from_slice(&self.h[i].to_be_bytes());
+                }
+                let off = 1 + 8 * WL;
+                r[off..(off + CL)].copy_from_slice(&self.ctr.to_be_bytes());
+                let p = (self.ctr as usize) & ($block_len - 1);
+                r[(off + CL)..(off + CL + p)].copy_from_slice(&self.buf[..p]);
+                r
+            }
+
+            // Deserialize and validate a state, which must use the
+            // provided function identifier.
+            fn import_state(id: u8, src: &[u8]) -> Result<Self, StateError> {
+                const WL: usize = core::mem::size_of::<$word>();
+                const CL: usize = core::mem::size_of::<$ctr>();
+                if src.len() != $state_len {
+                    return Err(StateError::WrongLength);
+                }
+                if src[0] != id {
+                    return Err(StateError::WrongAlgorithm);
+                }
+                let mut h = [0 as $word; 8];
+                for i in 0..8 {
+                    h[i] = <$word>::from_be_bytes(*<&[u8; WL]>::try_from(
+                        &src[(1 + WL * i)..(1 + WL * (i + 1))]).unwrap());
+                }
+                let off = 1 + 8 * WL;
+                let ctr = <$ctr>::from_be_bytes(*<&[u8; CL]>::try_from(
+                    &src[off..(off + CL)]).unwrap());
+                // The length in bits must fit in the counter type.
+                if (ctr >> (8 * CL - 3)) != 0 {
+                    return Err(StateError::InvalidCounter);
+                }
+                let mut buf = [0u8; $block_len];
+                buf.copy_from_slice(&src[(off + CL)..]);
+                let p = (ctr as usize) & ($block_len - 1);
+                if buf[p..].iter().any(|&b| b != 0) {
+                    return Err(StateError::InvalidBuffer);
+                }
+                Ok(Self { h, buf, ctr })
+            }
+        }
+    }
+}
+
+define_core!(Core256, u32, u64, 64, 105);
+define_core!(Core512, u64, u128, 128, 209);
+
+impl Core256 {
+
+    // Process some complete blocks (data length must be a multiple of 64).
+    fn process_blocks(h: &mut [u32; 8], data: &[u8]) {
+        for block in data.chunks_exact(64) {
+            let mut w = [0u32; 64];
+            for i in 0..16 {
+                w[i] = u32::from_be_bytes(
+                    *<&[u8; 4]>::try_from(&block[(4 * i)..(4 * i + 4)]).unwrap());
+            }
+            for i in 16..64 {
+                let x = w[i - 15];
+                let y = w[i - 2];
+                let s0 = x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3);
+                let s1 = y.rotate_right(17) ^ y.rotate_right(19) ^ (y >> 10);
+                w[i] = w[i - 16].wrapping_add(s0)
+                    .wrapping_add(w[i - 7]).wrapping_add(s1);
+            }
+            let mut a = h[0];
+            let mut b = h[1];
+            let mut c = h[2];
+            let mut d = h[3];
+            let mut e = h[4];
+            let mut f = h[5];
+            let mut g = h[6];
+            let mut hh = h[7];
+            for i in 0..64 {
+                let s1 = e.rotate_right(6) ^ e.rotate_right(11)
+                    ^ e.rotate_right(25);
+                let ch = (e & f) ^ (!e & g);
+                let t1 = hh.wrapping_add(s1).wrapping_add(ch)
+                    .wrapping_add(K256[i]).wrapping_add(w[i]);
+                let s0 = a.rotate_right(2) ^ a.rotate_right(13)
+                    ^ a.rotate_right(22);
+                let maj = (a & b) ^ (a & c) ^ (b & c);
+                let t2 = s0.wrapping_add(maj);
+                hh = g;
+                g = f;
+                f = e;
+                e = d.wrapping_add(t1);
+                d = c;
+                c = b;
+                b = a;
+                a = t1.wrapping_add(t2);
+            }
+            h[0] = h[0].wrapping_add(a);
+            h[1] = h[1].wrapping_add(b);
+            h[2] = h[2].wrapping_add(c);
+            h[3] = h[3].wrapping_add(d);
+            h[4] = h[4].wrapping_add(e);
+            h[5] = h[5].wrapping_add(f);
+            h[6] = h[6].wrapping_add(g);
+            h[7] = h[7].wrapping_add(hh);
+        }
+    }
+}
+
+// Multi-buffer SHA-256: on x86_64 with AVX2, eight independent messages
+// are processed in parallel, one per 32-bit lane.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+impl Core256 {
+
+    // Hash up to eight messages in parallel. Each lane has its own block
+    // count; once a lane is finished, its state is no longer updated (the
+    // lane still goes through the computations, on a stale block).
+    fn hash_8way(inputs: &[&[u8]], outputs: &mut [[u8; 32]]) {
+        let n = inputs.len();
+        assert!(n <= 8 && outputs.len() == n);
+
+        // Number of blocks for each message, including the padding.
+        let mut nb = [0usize; 8];
+        let mut max_nb = 0;
+        for i in 0..n {
+            nb[i] = (inputs[i].len() + 72) >> 6;
+            max_nb = core::cmp::max(max_nb, nb[i]);
+        }
+
+        // st[j][i] is state word j of lane i.
+        let mut st = [[0u32; 8]; 8];
+        for j in 0..8 {
+            st[j] = [IV256[j]; 8];
+        }
+        let mut blocks = [[0u8; 64]; 8];
+        for k in 0..max_nb {
+            for i in 0..n {
+                if k < nb[i] {
+                    Self::pad_block(inputs[i], k, nb[i], &mut blocks[i]);
+                }
+            }
+            let mut st2 = st;
+            Self::process_8way(&mut st2, &blocks);
+            for i in 0..n {
+                if k < nb[i] {
+                    for j in 0..8 {
+                        st[j][i] = st2[j][i];
+                    }
+                }
+            }
+        }
+
+        for i in 0..n {
+            for j in 0..8 {
+                outputs[i][(4 * j)..(4 * j + 4)]
+                    .copy_from_slice(&st[j][i].to_be_bytes());
+            }
+        }
+    }
+
+    // Get block k (out of nb) of the padded message.
+    fn pad_block(data: &[u8], k: usize, nb: usize, buf: &mut [u8; 64]) {
+        let off = k << 6;
+        let len = data.len();
+        if (off + 64) <= len {
+            buf.copy_from_slice(&data[off..(off + 64)]);
+            return;
+        }
+        buf.fill(0);
+        if off <= len {
+            buf[..(len - off)].copy_from_slice(&data[off..]);
+            buf[len - off] = 0x80;
+        }
+        if (k + 1) == nb {
+            buf[56..].copy_from_slice(&((len as u64) << 3).to_be_bytes());
+        }
+    }
+
+    // Process one block for each of the eight lanes.
+    fn process_8way(st: &mut [[u32; 8]; 8], blocks: &[[u8; 64]; 8]) {
+        unsafe {
+            use core::arch::x86_64::*;
+            use core::mem::transmute;
+
+            macro_rules! ror {
+                ($x: expr, $n: expr) => {
+                    _mm256_or_si256(_mm256_srli_epi32($x, $n),
+                        _mm256_slli_epi32($x, 32 - $n))
+                }
+            }
+
+            // Transpose the message words into lanes.
+            let mut w = [_mm256_setzero_si256(); 64];
+            for t in 0..16 {
+                let mut x = [0u32; 8];
+                for i in 0..8 {
+                    x[i] = u32::from_be_bytes(*<&[u8; 4]>::try_from(
+                        &blocks[i][(4 * t)..(4 * t + 4)]).unwrap());
+                }
+                w[t] = _mm256_loadu_si256(transmute(&x));
+            }
+            for t in 16..64 {
+                let x = w[t - 15];
+                let y = w[t - 2];
+                let s0 = _mm256_xor_si256(_mm256_xor_si256(
+                    ror!(x, 7), ror!(x, 18)), _mm256_srli_epi32(x, 3));
+                let s1 = _mm256_xor_si256(_mm256_xor_si256(
+                    ror!(y, 17), ror!(y, 19)), _mm256_srli_epi32(y, 10));
+                w[t] = _mm256_add_epi32(_mm256_add_epi32(w[t - 16], s0),
+                    _mm256_add_epi32(w[t - 7], s1));
+            }
+
+            let mut a = _mm256_loadu_si256(transmute(&st[0]));
+            let mut b = _mm256_loadu_si256(transmute(&st[1]));
+            let mut c = _mm256_loadu_si256(transmute(&st[2]));
+            let mut d = _mm256_loadu_si256(transmute(&st[3]));
+            let mut e = _mm256_loadu_si256(transmute(&st[4]));
+            let mut f = _mm256_loadu_si256(transmute(&st[5]));
+            let mut g = _mm256_loadu_si256(transmute(&st[6]));
+            let mut hh = _mm256_loadu_si256(transmute(&st[7]));
+            for i in 0..64 {
+                let s1 = _mm256_xor_si256(_mm256_xor_si256(
+                    ror!(e, 6), ror!(e, 11)), ror!(e, 25));
+                let ch = _mm256_xor_si256(
+                    _mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+                let kw = _mm256_add_epi32(
+                    _mm256_set1_epi32(K256[i] as i32), w[i]);
+                let t1 = _mm256_add_epi32(_mm256_add_epi32(hh, s1),
+                    _mm256_add_epi32(ch, kw));
+                let s0 = _mm256_xor_si256(_mm256_xor_si256(
+                    ror!(a, 2), ror!(a, 13)), ror!(a, 22));
+                let maj = _mm256_xor_si256(_mm256_and_si256(a, b),
+                    _mm256_and_si256(c, _mm256_xor_si256(a, b)));
+                let t2 = _mm256_add_epi32(s0, maj);
+                hh = g;
+                g = f;
+                f = e;
+                e = _mm256_add_epi32(d, t1);
+                d = c;
+                c = b;
+                b = a;
+                a = _mm256_add_epi32(t1, t2);
+            }
+
+            let v = [a, b, c, d, e, f, g, hh];
+            for j in 0..8 {
+                let x = _mm256_loadu_si256(transmute(&st[j]));
+                _mm256_storeu_si256(transmute(&mut st[j]),
+                    _mm256_add_epi32(x, v[j]));
+            }
+        }
+    }
+}
+
+impl Core512 {
+
+    // Compute the message schedule (80 words) for a 128-byte block.
+    fn schedule(w: &mut [u64; 80], block: &[u8]) {
+        #[cfg(not(all(target_arch = "x86_64", target_feature = "avx2")))]
+        {
+            for i in 0..16 {
+                w[i] = u64::from_be_bytes(
+                    *<&[u8; 8]>::try_from(&block[(8 * i)..(8 * i + 8)]).unwrap());
+            }
+            for i in 16..80 {
+                let x = w[i - 15];
+                let y = w[i - 2];
+                let s0 = x.rotate_right(1) ^ x.rotate_right(8) ^ (x >> 7);
+                let s1 = y.rotate_right(19) ^ y.rotate_right(61) ^ (y >> 6);
+                w[i] = w[i - 16].wrapping_add(s0)
+                    .wrapping_add(w[i - 7]).wrapping_add(s1);
+            }
+        }
+
+        #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+        unsafe {
+            // x86_64 + AVX2: words are computed four at a time. W[t] and
+            // W[t+1] depend on W[t-2] and W[t-1], so the sigma1 part is
+            // done in two 128-bit halves; everything else uses 256-bit
+            // registers.
+            use core::arch::x86_64::*;
+            use core::mem::transmute;
+
+            assert!(block.len() == 128);
+
+            // Load the block words with byte swapping.
+            let bswap = _mm256_setr_epi8(
+                7, 6, 5, 4, 3, 2, 1, 0, 15, 14, 13, 12, 11, 10, 9, 8,
+                7, 6, 5, 4, 3, 2, 1, 0, 15, 14, 13, 12, 11, 10, 9, 8);
+            for i in 0..4 {
+                let y = _mm256_loadu_si256(transmute(&block[32 * i]));
+                _mm256_storeu_si256(transmute(&mut w[4 * i]),
+                    _mm256_shuffle_epi8(y, bswap));
+            }
+
+            macro_rules! ror256 {
+                ($x: expr, $n: expr) => {
+                    _mm256_or_si256(_mm256_srli_epi64($x, $n),
+                        _mm256_slli_epi64($x, 64 - $n))
+                }
+            }
+            macro_rules! ror128 {
+                ($x: expr, $n: expr) => {
+                    _mm_or_si128(_mm_srli_epi64($x, $n),
+                        _mm_slli_epi64($x, 64 - $n))
+                }
+            }
+            macro_rules! sigma1 {
+                ($x: expr) => {
+                    _mm_xor_si128(_mm_xor_si128(
+                        ror128!($x, 19), ror128!($x, 61)),
+                        _mm_srli_epi64($x, 6))
+                }
+            }
+
+            for t in (16..80).step_by(4) {
+                // W[t-16] + sigma0(W[t-15]) + W[t-7], for four words.
+                let a = _mm256_loadu_si256(transmute(&w[t - 16]));
+                let b = _mm256_loadu_si256(transmute(&w[t - 15]));
+                let c = _mm256_loadu_si256(transmute(&w[t - 7]));
+                let s0 = _mm256_xor_si256(_mm256_xor_si256(
+                    ror256!(b, 1), ror256!(b, 8)), _mm256_srli_epi64(b, 7));
+                let p = _mm256_add_epi64(_mm256_add_epi64(a, s0), c);
+
+                // Add sigma1(W[t-2]), sigma1(W[t-1]) to get W[t], W[t+1],
+                // then sigma1(W[t]), sigma1(W[t+1]) to get W[t+2], W[t+3].
+                let x = _mm_loadu_si128(transmute(&w[t - 2]));
+                let lo = _mm_add_epi64(_mm256_castsi256_si128(p), sigma1!(x));
+                let hi = _mm_add_epi64(
+                    _mm256_extracti128_si256(p, 1), sigma1!(lo));
+                _mm_storeu_si128(transmute(&mut w[t]), lo);
+                _mm_storeu_si128(transmute(&mut w[t + 2]), hi);
+            }
+        }
+    }
+
+    // Process some complete blocks (data length must be a multiple of 128).
+    fn process_blocks(h: &mut [u64; 8], data: &[u8]) {
+        for block in data.chunks_exact(128) {
+            let mut w = [0u64; 80];
+            Self::schedule(&mut w, block);
+            let mut a = h[0];
+            let mut b = h[1];
+            let mut c = h[2];
+            let mut d = h[3];
+            let mut e = h[4];
+            let mut f = h[5];
+            let mut g = h[6];
+            let mut hh = h[7];
+            for i in 0..80 {
+                let s1 = e.rotate_right(14) ^ e.rotate_right(18)
+                    ^ e.rotate_right(41);
+                let ch = (e & f) ^ (!e & g);
+                let t1 = hh.wrapping_add(s1).wrapping_add(ch)
+                    .wrapping_add(K512[i]).wrapping_add(w[i]);
+                let s0 = a.rotate_right(28) ^ a.rotate_right(34)
+                    ^ a.rotate_right(39);
+                let maj = (a & b) ^ (a & c) ^ (b & c);
+                let t2 = s0.wrapping_add(maj);
+                hh = g;
+                g = f;
+                f = e;
+                e = d.wrapping_add(t1);
+                d = c;
+                c = b;
+                b = a;
+                a = t1.wrapping_add(t2);
+            }
+            h[0] = h[0].wrapping_add(a);
+            h[1] = h[1].wrapping_add(b);
+            h[2] = h[2].wrapping_add(c);
+            h[3] = h[3].wrapping_add(d);
+            h[4] = h[4].wrapping_add(e);
+            h[5] = h[5].wrapping_add(f);
+            h[6] = h[6].wrapping_add(g);
+            h[7] = h[7].wrapping_add(hh);
+        }
+    }
+}
+
+macro_rules! define_sha2 {
+    ($name: ident, $core: ident, $iv: ident, $id: expr, $word_len: expr,
+     $out_len: expr, $block_len: expr, $state_len: expr, $doc: expr) => {
+
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name($core);
+
+        impl $name {
+
+            /// Output length (in bytes).
+            pub const OUTPUT_LEN: usize = $out_len;
+
+            /// Internal block length (in bytes).
+            pub const BLOCK_LEN: usize = $block_len;
+
+            /// Length of an exported state (in bytes).
+            pub const STATE_LEN: usize = $state_len;
+
+            /// Initialize a new context.
+            pub fn new() -> Self {
+                Self($core::new(&$iv))
+            }
+
+            /// Reset this context, for a new hashing operation.
+            pub fn reset(&mut self) {
+                self.0 = $core::new(&$iv);
+            }
+
+            /// Inject some more bytes into the context.
+            pub fn update(&mut self, data: &[u8]) {
+                self.0.update(data);
+            }
+
+            /// Inject several chunks of data into the context; this is
+            /// equivalent to calling `update()` on each chunk in order.
+            pub fn update_vectored(&mut self, parts: &[&[u8]]) {
+                for d in parts.iter() {
+                    self.0.update(d);
+                }
+            }
+
+            /// Finalize the current computation and get the output. The
+            /// context is not modified, so more data may be injected
+            /// afterwards (the output then covers all data injected since
+            /// the last reset).
+            pub fn finalize(&self) -> [u8; $out_len] {
+                let h = self.0.finalize_state();
+                let mut tmp = [0u8; 8 * $word_len];
+                for i in 0..8 {
+                    tmp[(i * $word_len)..((i + 1) * $word_len)]
+                        .copy_from_slice(&h[i].to_be_bytes());
+                }
+                let mut r = [0u8; $out_len];
+                r.copy_from_slice(&tmp[..$out_len]);
+                r
+            }
+
+            /// Finalize the current computation and get the output. The
+            /// context is automatically reset, so that it can be used
+            /// again for a new computation.
+            pub fn finalize_reset(&mut self) -> [u8; $out_len] {
+                let r = self.finalize();
+                self.reset();
+                r
+            }
+
+            /// One-stop function for hashing some input.
+            pub fn hash(data: &[u8]) -> [u8; $out_len] {
+                let mut sh = Self::new();
+                sh.update(data);
+                sh.finalize()
+            }
+
+            /// Export the current state of this context (see the module
+            /// documentation for the format). The context is not
+            /// modified.
+            pub fn export_state(&self) -> [u8; $state_len] {
+                self.0.export_state($id)
+            }
+
+            /// Rebuild a context from an exported state. The state is
+            /// validated; an error is returned if it is malformed or was
+            /// exported from a different hash function.
+            pub fn import_state(state: &[u8]) -> Result<Self, StateError> {
+                Ok(Self($core::import_state($id, state)?))
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    }
+}
+
+define_sha2!(Sha224, Core256, IV224, 1, 4, 28, 64, 105,
+    "SHA-224 context.");
+define_sha2!(Sha256, Core256, IV256, 2, 4, 32, 64, 105,
+    "SHA-256 context.");
+define_sha2!(Sha384, Core512, IV384, 3, 8, 48, 128, 209,
+    "SHA-384 context.");
+define_sha2!(Sha512, Core512, IV512, 4, 8, 64, 128, 209,
+    "SHA-512 context.");
+define_sha2!(Sha512_224, Core512, IV512_224, 5, 8, 28, 128, 209,
+    "SHA-512/224 context (SHA-512 with a specific IV, truncated to 224 bits).");
+define_sha2!(Sha512_256, Core512, IV512_256, 6, 8, 32, 128, 209,
+    "SHA-512/256 context (SHA-512 with a specific IV, truncated to 256 bits).");
+
+impl Sha256 {
+
+    /// Hash several independent messages; `outputs[i]` receives the
+    /// SHA-256 hash of `inputs[i]`. The messages may have arbitrary (and
+    /// distinct) lengths. On x86_64 with AVX2 support (enabled at compile
+    /// time), up to eight messages are processed in parallel, which is
+    /// substantially faster than sequential hashing for many short inputs.
+    /// The two slices must have the same length.
+    pub fn hash_many(inputs: &[&[u8]], outputs: &mut [[u8; 32]]) {
+        assert!(inputs.len() == outputs.len());
+
+        #[cfg(not(all(target_arch = "x86_64", target_feature = "avx2")))]
+        for (d, out) in inputs.iter().zip(outputs.iter_mut()) {
+            *out = Self::hash(d);
+        }
+
+        #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+        for (ins, outs) in inputs.chunks(8).zip(outputs.chunks_mut(8)) {
+            Core256::hash_8way(ins, outs);
+        }
+    }
+}
+
+/// Implementations of the RustCrypto `digest` traits.
+///
+/// ```
+/// use crrl::sha2::{Sha256, Sha512};
+/// use crrl::sha2::digest::Digest;
+///
+/// let h1 = <Sha256 as Digest>::new_with_prefix(b"ab").chain_update(b"c").finalize();
+/// assert!(h1[..] == Sha256::hash(b"abc")[..]);
+/// let h2 = <Sha512 as Digest>::digest(b"abc");
+/// assert!(h2[..] == Sha512::hash(b"abc")[..]);
+/// ```
+#[cfg(feature = "rustcrypto-traits")]
+mod rustcrypto_traits {
+
+    use super::{Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256};
+    use super::digest::{Update, FixedOutput, FixedOutputReset, Reset};
+    use super::digest::{HashMarker, OutputSizeUser, Output};
+    use super::digest::core_api::BlockSizeUser;
+    use super::digest::consts::{U28, U32, U48, U64, U128};
+
+    macro_rules! impl_digest {
+        ($name: ident, $out_size: ty, $block_size: ty) => {
+
+            impl HashMarker for $name { }
+
+            impl OutputSizeUser for $name {
+                type OutputSize = $out_size;
+            }
+
+            impl BlockSizeUser for $name {
+                type BlockSize = $block_size;
+            }
+
+            impl Update for $name {
+                fn update(&mut self, data: &[u8]) {
+                    $name::update(self, data);
+                }
+            }
+
+            impl FixedOutput for $name {
+                fn finalize_into(self, out: &mut Output<Self>) {
+                    out.copy_from_slice(&$name::finalize(&self));
+                }
+            }
+
+            impl Reset for $name {
+                fn reset(&mut self) {
+                    $name::reset(self);
+                }
+            }
+
+            impl FixedOutputReset for $name {
+                fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+                    out.copy_from_slice(&$name::finalize_reset(self));
+                }
+            }
+        }
+    }
+
+    impl_digest!(Sha224, U28, U64);
+    impl_digest!(Sha256, U32, U64);
+    impl_digest!(Sha384, U48, U128);
+    impl_digest!(Sha512, U64, U128);
+    impl_digest!(Sha512_224, U28, U128);
+    impl_digest!(Sha512_256, U32, U128);
+}
+
+const IV224: [u32; 8] = [
+    0xC1059ED8, 0x367CD507, 0x3070DD17, 0xF70E5939,
+    0xFFC00B31, 0x68581511, 0x64F98FA7, 0xBEFA4FA4,
+];
+
+const IV256: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A,
+    0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+const IV384: [u64; 8] = [
+    0xCBBB9D5DC1059ED8, 0x629A292A367CD507,
+    0x9159015A3070DD17, 0x152FECD8F70E5939,
+    0x67332667FFC00B31, 0x8EB44A8768581511,
+    0xDB0C2E0D64F98FA7, 0x47B5481DBEFA4FA4,
+];
+
+const IV512: [u64; 8] = [
+    0x6A09E667F3BCC908, 0xBB67AE8584CAA73B,
+    0x3C6EF372FE94F82B, 0xA54FF53A5F1D36F1,
+    0x510E527FADE682D1, 0x9B05688C2B3E6C1F,
+    0x1F83D9ABFB41BD6B, 0x5BE0CD19137E2179,
+];
+
+const IV512_224: [u64; 8] = [
+    0x8C3D37C819544DA2, 0x73E1996689DCD4D6,
+    0x1DFAB7AE32FF9C82, 0x679DD514582F9FCF,
+    0x0F6D2B697BD44DA8, 0x77E36F7304C48942,
+    0x3F9D85A86A1D36C8, 0x1112E6AD91D692A1,
+];
+
+const IV512_256: [u64; 8] = [
+    0x22312194FC2BF72C, 0x9F555FA3C84C64C2,
+    0x2393B86B6F53B151, 0x963877195940EABD,
+    0x96283EE2A88EFFE3, 0xBE5E1E2553863992,
+    0x2B0199FC2C85B8AA, 0x0EB72DDC81C52CA2,
+];
+
+const K256: [u32; 64] = [
+    0x428A2F98, 0x71374491, 0xB5C0FBCF, 0xE9B5DBA5,
+    0x3956C25B, 0x59F111F1, 0x923F82A4, 0xAB1C5ED5,
+    0xD807AA98, 0x12835B01, 0x243185BE, 0x550C7DC3,
+    0x72BE5D74, 0x80DEB1FE, 0x9BDC06A7, 0xC19BF174,
+    0xE49B69C1, 0xEFBE4786, 0x0FC19DC6, 0x240CA1CC,
+    0x2DE92C6F, 0x4A7484AA, 0x5CB0A9DC, 0x76F988DA,
+    0x983E5152, 0xA831C66D, 0xB00327C8, 0xBF597FC7,
+    0xC6E00BF3, 0xD5A79147, 0x06CA6351, 0x14292967,
+    0x27B70A85, 0x2E1B2138, 0x4D2C6DFC, 0x53380D13,
+    0x650A7354, 0x766A0ABB, 0x81C2C92E, 0x92722C85,
+    0xA2BFE8A1, 0xA81A664B, 0xC24B8B70, 0xC76C51A3,
+    0xD192E819, 0xD6990624, 0xF40E3585, 0x106AA070,
+    0x19A4C116, 0x1E376C08, 0x2748774C, 0x34B0BCB5,
+    0x391C0CB3, 0x4ED8AA4A, 0x5B9CCA4F, 0x682E6FF3,
+    0x748F82EE, 0x78A5636F, 0x84C87814, 0x8CC70208,
+    0x90BEFFFA, 0xA4506CEB, 0xBEF9A3F7, 0xC67178F2,
+];
+
+const K512: [u64; 80] = [
+    0x428A2F98D728AE22, 0x7137449123EF65CD,
+    0xB5C0FBCFEC4D3B2F, 0xE9B5DBA58189DBBC,
+    0x3956C25BF348B538, 0x59F111F1B605D019,
+    0x923F82A4AF194F9B, 0xAB1C5ED5DA6D8118,
+    0xD807AA98A3030242, 0x12835B0145706FBE,
+    0x243185BE4EE4B28C, 0x550C7DC3D5FFB4E2,
+    0x72BE5D74F27B896F, 0x80DEB1FE3B1696B1,
+    0x9BDC06A725C71235, 0xC19BF174CF692694,
+    0xE49B69C19EF14AD2, 0xEFBE4786384F25E3,
+    0x0FC19DC68B8CD5B5, 0x240CA1CC77AC9C65,
+    0x2DE92C6F592B0275, 0x4A7484AA6EA6E483,
+    0x5CB0A9DCBD41FBD4, 0x76F988DA831153B5,
+    0x983E5152EE66DFAB, 0xA831C66D2DB43210,
+    0xB00327C898FB213F, 0xBF597FC7BEEF0EE4,
+    0xC6E00BF33DA88FC2, 0xD5A79147930AA725,
+    0x06CA6351E003826F, 0x142929670A0E6E70,
+    0x27B70A8546D22FFC, 0x2E1B21385C26C926,
+    0x4D2C6DFC5AC42AED, 0x53380D139D95B3DF,
+    0x650A73548BAF63DE, 0x766A0ABB3C77B2A8,
+    0x81C2C92E47EDAEE6, 0x92722C851482353B,
+    0xA2BFE8A14CF10364, 0xA81A664BBC423001,
+    0xC24B8B70D0F89791, 0xC76C51A30654BE30,
+    0xD192E819D6EF5218, 0xD69906245565A910,
+    0xF40E35855771202A, 0x106AA07032BBD1B8,
+    0x19A4C116B8D2D0C8, 0x1E376C085141AB53,
+    0x2748774CDF8EEB99, 0x34B0BCB5E19B48A8,
+    0x391C0CB3C5C95A63, 0x4ED8AA4AE3418ACB,
+    0x5B9CCA4F7763E373, 0x682E6FF3D6B2B8A3,
+    0x748F82EE5DEFB2FC, 0x78A5636F43172F60,
+    0x84C87814A1F0AB72, 0x8CC702081A6439EC,
+    0x90BEFFFA23631E28, 0xA4506CEBDE82BDE9,
+    0xBEF9A3F7B2C67915, 0xC67178F2E372532B,
+    0xCA273ECEEA26619C, 0xD186B8C721C0C207,
+    0xEADA7DD6CDE0EB1E, 0xF57D4F7FEE6ED178,
+    0x06F067AA72176FBA, 0x0A637DC5A2C898A6,
+    0x113F9804BEF90DAE, 0x1B710B35131C471B,
+    0x28DB77F523047D84, 0x32CAAB7B40C72493,
+    0x3C9EBE0A15C9BEBC, 0x431D67C49C100D4C,
+    0x4CC5D4BECB3E42B6, 0x597F299CFC657E2A,
+    0x5FCB6FAB3AD6FAEC, 0x6C44198C4A475817,
+];
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256};
+    use super::{Core512, IV512, IV512_224, IV512_256};
+    use super::StateError;
+
+    macro_rules! check_kat {
+        ($name: ident, $kat: expr) => {
+            for (msg, hv) in $kat.iter() {
+                let hv = hex::decode(hv).unwrap();
+                assert!($name::hash(msg)[..] == hv[..]);
+                let mut sh = $name::new();
+                for b in msg.iter() {
+                    sh.update(&[*b]);
+                }
+                assert!(sh.finalize()[..] == hv[..]);
+                assert!(sh.finalize_reset()[..] == hv[..]);
+                sh.update(msg);
+                assert!(sh.finalize()[..] == hv[..]);
+            }
+        }
+    }
+
+    const M448: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    const M896: &[u8] = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+    #[test]
+    fn sha256_family() {
+        check_kat!(Sha224, [
+            (&b""[..], "d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f"),
+            (&b"abc"[..], "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7"),
+            (M448, "75388b16512776cc5dba5da1fd890150b0c6455cb4f58b1952522525"),
+        ]);
+        check_kat!(Sha256, [
+            (&b""[..], "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            (&b"abc"[..], "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (M448, "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+        ]);
+    }
+
+    #[test]
+    fn sha512_family() {
+        check_kat!(Sha384, [
+            (&b""[..], "38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b"),
+            (&b"abc"[..], "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"),
+            (M896, "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039"),
+        ]);
+        check_kat!(Sha512, [
+            (&b""[..], "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"),
+            (&b"abc"[..], "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"),
+            (M896, "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"),
+        ]);
+        check_kat!(Sha512_224, [
+            (&b""[..], "6ed0dd02806fa89e25de060c19d3ac86cabb87d6a0ddd05c333b84f4"),
+            (&b"abc"[..], "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa"),
+            (M896, "23fec5bb94d60b23308192640b0c453335d664734fe40e7268674af9"),
+        ]);
+        check_kat!(Sha512_256, [
+            (&b""[..], "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a"),
+            (&b"abc"[..], "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23"),
+            (M896, "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a"),
+        ]);
+    }
+
+    #[test]
+    fn sha512t_iv() {
+        // FIPS 180-4, section 5.3.6: the IV for SHA-512/t is the SHA-512
+        // hash of the string "SHA-512/t", computed with the SHA-512 IV
+        // XORed with 0xa5a5a5a5a5a5a5a5.
+        let mut iv2 = IV512;
+        for w in iv2.iter_mut() {
+            *w ^= 0xa5a5a5a5a5a5a5a5;
+        }
+        for (name, iv) in [(&b"SHA-512/224"[..], IV512_224),
+            (&b"SHA-512/256"[..], IV512_256)]
+        {
+            let mut cc = Core512::new(&iv2);
+            cc.update(name);
+            assert!(cc.finalize_state() == iv);
+            // These are not truncations of the SHA-512 IV, and not
+            // equal to each other.
+            assert!(iv != IV512);
+        }
+        assert!(IV512_224 != IV512_256);
+
+        // Published constants (FIPS 180-4, sections 5.3.6.1 and 5.3.6.2).
+        assert!(IV512_224[0] == 0x8C3D37C819544DA2);
+        assert!(IV512_224[7] == 0x1112E6AD91D692A1);
+        assert!(IV512_256[0] == 0x22312194FC2BF72C);
+        assert!(IV512_256[7] == 0x0EB72DDC81C52CA2);
+    }
+
+    #[test]
+    fn streaming() {
+        // Compare with the sha2 crate, over many lengths (around block
+        // boundaries) and with various chunkings.
+        use ::sha2::Digest;
+        let mut data = [0u8; 600];
+        for i in 0..data.len() {
+            data[i] = (i * 7 + 1) as u8;
+        }
+        for n in 0..data.len() {
+            let d = &data[..n];
+            let r256 = ::sha2::Sha256::digest(d);
+            let r512 = ::sha2::Sha512::digest(d);
+            let r512_256 = ::sha2::Sha512_256::digest(d);
+            assert!(Sha256::hash(d)[..] == r256[..]);
+            assert!(Sha512::hash(d)[..] == r512[..]);
+            assert!(Sha512_256::hash(d)[..] == r512_256[..]);
+            for k in [1, 55, 64, 111, 128, 200] {
+                let mut s1 = Sha256::new();
+                let mut s2 = Sha512::new();
+                let parts: crate::Vec<&[u8]> = d.chunks(k).collect();
+                for p in parts.iter() {
+                    s1.update(p);
+                }
+                s2.update_vectored(&parts);
+                assert!(s1.finalize()[..] == r256[..]);
+                assert!(s2.finalize()[..] == r512[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_large() {
+        // Multi-megabyte input (pseudorandom, so that all schedule words
+        // vary), injected in chunks of irregular sizes; this exercises the
+        // message schedule over many consecutive blocks.
+        use ::sha2::Digest;
+        let mut data = crate::Vec::with_capacity(3 << 20);
+        let mut x = 0x0123456789ABCDEFu64;
+        while data.len() < (3 << 20) + 77 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            data.extend_from_slice(&x.to_le_bytes());
+        }
+        let r512 = ::sha2::Sha512::digest(&data);
+        let r384 = ::sha2::Sha384::digest(&data);
+        let mut s1 = Sha512::new();
+        let mut s2 = Sha384::new();
+        let mut j = 0;
+        let mut k = 1;
+        while j < data.len() {
+            let clen = core::cmp::min(k, data.len() - j);
+            s1.update(&data[j..(j + clen)]);
+            s2.update(&data[j..(j + clen)]);
+            j += clen;
+            k = (k * 7 + 13) % 5000;
+        }
+        assert!(s1.finalize()[..] == r512[..]);
+        assert!(s2.finalize()[..] == r384[..]);
+        assert!(Sha512::hash(&data)[..] == r512[..]);
+    }
+
+    #[test]
+    fn state_roundtrip() {
+        // Split the input at offsets around block boundaries, export the
+        // state, import it into a new context and finish the hashing.
+        let mut data = [0u8; 400];
+        for i in 0..data.len() {
+            data[i] = (i * 11 + 5) as u8;
+        }
+        let r256 = Sha256::hash(&data);
+        let r224 = Sha224::hash(&data);
+        let r512 = Sha512::hash(&data);
+        let r384 = Sha384::hash(&data);
+        for k in [0, 1, 55, 56, 63, 64, 65, 127, 128, 129, 255, 256, 257, 400] {
+            let mut sh = Sha256::new();
+            sh.update(&data[..k]);
+            let st = sh.export_state();
+            assert!(st.len() == Sha256::STATE_LEN);
+            let mut sh2 = Sha256::import_state(&st).unwrap();
+            assert!(sh2.export_state() == st);
+            sh2.update(&data[k..]);
+            assert!(sh2.finalize() == r256);
+
+            let mut sh = Sha224::new();
+            sh.update(&data[..k]);
+            let mut sh2 = Sha224::import_state(&sh.export_state()).unwrap();
+            sh2.update(&data[k..]);
+            assert!(sh2.finalize() == r224);
+
+            let mut sh = Sha512::new();
+            sh.update(&data[..k]);
+            let st = sh.export_state();
+            assert!(st.len() == Sha512::STATE_LEN);
+            let mut sh2 = Sha512::import_state(&st).unwrap();
+            assert!(sh2.export_state() == st);
+            sh2.update(&data[k..]);
+            assert!(sh2.finalize() == r512);
+
+            let mut sh = Sha384::new();
+            sh.update(&data[..k]);
+            let mut sh2 = Sha384::import_state(&sh.export_state()).unwrap();
+            sh2.update(&data[k..]);
+            assert!(sh2.finalize() == r384);
+        }
+
+        // Buffered bytes from a previous block do not leak into the
+        // exported state (unused bytes are zero).
+        let mut sh = Sha256::new();
+        sh.update(&data[..70]);
+        let st = sh.export_state();
+        assert!(st[(1 + 32 + 8 + 6)..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn state_counter_carry() {
+        // Build states with byte counters just below 2^32 (and 2^64 for
+        // the 128-bit counter of SHA-512), inject data across the carry,
+        // and check the serialized counter and the round trip.
+        let mut st = Sha256::new().export_state();
+        let c0 = (1u64 << 32) - 3;
+        st[33..41].copy_from_slice(&c0.to_be_bytes());
+        let mut sh = Sha256::import_state(&st).unwrap();
+        sh.update(&[0xA5u8; 10]);
+        let st2 = sh.export_state();
+        assert!(st2[33..41] == ((1u64 << 32) + 7).to_be_bytes());
+        let sh2 = Sha256::import_state(&st2).unwrap();
+        assert!(sh2.finalize() == sh.finalize());
+
+        for c0 in [(1u128 << 32) - 3, (1u128 << 64) - 3] {
+            let mut st = Sha512::new().export_state();
+            st[65..81].copy_from_slice(&c0.to_be_bytes());
+            let mut sh = Sha512::import_state(&st).unwrap();
+            sh.update(&[0xA5u8; 200]);
+            let st2 = sh.export_state();
+            assert!(st2[65..81] == (c0 + 200).to_be_bytes());
+            let mut sh2 = Sha512::import_state(&st2).unwrap();
+            assert!(sh2.finalize() == sh.finalize());
+            sh.update(b"more");
+            sh2.update(b"more");
+            assert!(sh2.finalize() == sh.finalize());
+        }
+    }
+
+    #[test]
+    fn state_invalid() {
+        let mut sh = Sha256::new();
+        sh.update(b"abc");
+        let st = sh.export_state();
+        assert!(Sha256::import_state(&st[..104]).err() == Some(StateError::WrongLength));
+        let mut tmp = [0u8; 106];
+        tmp[..105].copy_from_slice(&st);
+        assert!(Sha256::import_state(&tmp).err() == Some(StateError::WrongLength));
+        assert!(Sha224::import_state(&st).err() == Some(StateError::WrongAlgorithm));
+        assert!(Sha512::import_state(&st).err() == Some(StateError::WrongLength));
+
+        // Counter such that the bit length does not fit in 64 bits.
+        let mut bad = st;
+        bad[33..41].copy_from_slice(&(1u64 << 61).to_be_bytes());
+        assert!(Sha256::import_state(&bad).err() == Some(StateError::InvalidCounter));
+        bad[33..41].copy_from_slice(&((1u64 << 61) - 64 + 3).to_be_bytes());
+        assert!(Sha256::import_state(&bad).is_ok());
+
+        // Non-zero byte beyond the buffered data.
+        let mut bad = st;
+        bad[1 + 32 + 8 + 3] = 1;
+        assert!(Sha256::import_state(&bad).err() == Some(StateError::InvalidBuffer));
+
+        let st = Sha512_256::new().export_state();
+        assert!(Sha512_256::import_state(&st).is_ok());
+        assert!(Sha512::import_state(&st).err() == Some(StateError::WrongAlgorithm));
+        let mut bad = st;
+        bad[65] = 0x20;
+        assert!(Sha512_256::import_state(&bad).err() == Some(StateError::InvalidCounter));
+    }
+
+    #[cfg(feature = "rustcrypto-traits")]
+    #[test]
+    fn digest_traits() {
+        use super::digest::{Digest, FixedOutputReset};
+        use super::digest::core_api::BlockSizeUser;
+
+        fn generic_hash<D: Digest>(data: &[u8]) -> crate::Vec<u8> {
+            let mut d = D::new();
+            Digest::update(&mut d, &data[..1]);
+            Digest::update(&mut d, &data[1..]);
+            d.finalize().to_vec()
+        }
+
+        let data = b"The quick brown fox jumps over the lazy dog";
+        assert!(generic_hash::<Sha224>(data)[..] == Sha224::hash(data)[..]);
+        assert!(generic_hash::<Sha256>(data)[..] == Sha256::hash(data)[..]);
+        assert!(generic_hash::<Sha384>(data)[..] == Sha384::hash(data)[..]);
+        assert!(generic_hash::<Sha512>(data)[..] == Sha512::hash(data)[..]);
+        assert!(<Sha256 as Digest>::output_size() == 32);
+        assert!(<Sha384 as Digest>::output_size() == 48);
+        assert!(<Sha256 as BlockSizeUser>::block_size() == 64);
+        assert!(<Sha224 as BlockSizeUser>::block_size() == 64);
+        assert!(<Sha384 as BlockSizeUser>::block_size() == 128);
+        assert!(<Sha512 as BlockSizeUser>::block_size() == 128);
+
+        let mut sh = Sha512::new();
+        Digest::update(&mut sh, data);
+        let r1 = FixedOutputReset::finalize_fixed_reset(&mut sh);
+        assert!(r1[..] == Sha512::hash(data)[..]);
+        let r2 = Digest::finalize(sh);
+        assert!(r2[..] == Sha512::hash(b"")[..]);
+    }
+
+    #[test]
+    fn hash_many() {
+        // Mixed lengths around the padding boundaries (55/56 and 63/64
+        // bytes), in an order such that lanes finish at different times,
+        // and with a message count which is not a multiple of 8.
+        use crate::Vec;
+        let mut data = [0u8; 300];
+        for i in 0..data.len() {
+            data[i] = (i as u8).wrapping_mul(37).wrapping_add(11);
+        }
+        let mut lens = Vec::new();
+        let mut x = 5usize;
+        for _ in 0..301 {
+            lens.push(x);
+            x = (x * 113 + 29) % 301;
+        }
+        for len in 0..301 {
+            lens.push(len);
+        }
+        for num in [0, 1, 7, 8, 9, 15, 16, 17, 301, lens.len()] {
+            let inputs: Vec<&[u8]> = lens[..num].iter()
+                .map(|&len| &data[..len]).collect();
+            let mut outputs = Vec::new();
+            outputs.resize(num, [0u8; 32]);
+            Sha256::hash_many(&inputs, &mut outputs);
+            for i in 0..num {
+                assert!(outputs[i] == Sha256::hash(inputs[i]));
+            }
+        }
+    }
+
+    #[test]
+    fn hash_many_55() {
+        // 10000 messages of 55 bytes each: the longest length for which
+        // the padding fits in a single block.
+        use crate::Vec;
+        let mut msgs = Vec::with_capacity(10000);
+        for i in 0..10000u32 {
+            let mut m = [0u8; 55];
+            for j in 0..55 {
+                m[j] = (i.wrapping_mul(0x9E3779B1) >> (j & 24)) as u8
+                    ^ (j as u8);
+            }
+            msgs.push(m);
+        }
+        let inputs: Vec<&[u8]> = msgs.iter().map(|m| &m[..]).collect();
+        let mut outputs = Vec::new();
+        outputs.resize(inputs.len(), [0u8; 32]);
+        Sha256::hash_many(&inputs, &mut outputs);
+        for i in 0..inputs.len() {
+            assert!(outputs[i] == Sha256::hash(inputs[i]));
+        }
+    }
+}
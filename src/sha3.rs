@@ -0,0 +1,933 @@
+//! SHA-3 hash functions (FIPS 202) and the Keccak permutation.
+//!
+//! This module implements the Keccak-f\[1600\] permutation (with a
+//! portable implementation over 64-bit lanes), and the SHA-3 fixed-output
+//! hash functions SHA3-224, SHA3-256, SHA3-384 and SHA3-512. The original
+//! Keccak-256 function (as used by Ethereum, with the pre-standard padding
+//! byte 0x01 instead of 0x06) is also provided as `Keccak256`.
+//!
+//! All hash types offer the same API as those of the `sha2` module:
+//! `new()`, `update()`, `update_vectored()`, `finalize()` (which does not
+//! modify the context), `finalize_reset()`, `reset()`, and the one-stop
+//! function `hash()`.
+//!
+//! The extendable-output functions SHAKE128 and SHAKE256 are provided by
+//! `Shake128` and `Shake256`; after input has been injected, a reader
+//! (`ShakeReader`) is obtained with `finalize_xof()`, and can produce an
+//! arbitrary amount of output, over as many calls as needed. These types
+//! implement the `Xof` trait of the `xof` module.
+//!
+//! The derived functions of NIST SP 800-185 are also implemented:
+//! cSHAKE128 and cSHAKE256 (`CShake128`, `CShake256`, with a function
+//! name and a customization string), KMAC128 and KMAC256 (`Kmac128`,
+//! `Kmac256`, with fixed-length or XOF output), and TupleHash128 and
+//! TupleHash256 (`TupleHash128`, `TupleHash256`, over a sequence of byte
+//! strings). The integer encoding functions `left_encode()` and
+//! `right_encode()` are exposed for protocols that need to frame their
+//! own inputs in the same way.
+
+use core::convert::TryFrom;
+
+// Round constants.
+const RC: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082,
+    0x800000000000808A, 0x8000000080008000,
+    0x000000000000808B, 0x0000000080000001,
+    0x8000000080008081, 0x8000000000008009,
+    0x000000000000008A, 0x0000000000000088,
+    0x0000000080008009, 0x000000008000000A,
+    0x000000008000808B, 0x800000000000008B,
+    0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080,
+    0x000000000000800A, 0x800000008000000A,
+    0x8000000080008081, 0x8000000000008080,
+    0x0000000080000001, 0x8000000080008008,
+];
+
+// Rotation counts and lane indices for the combined rho and pi steps,
+// following the lane cycle of pi starting at lane 1.
+const RHO: [u32; 24] = [
+     1,  3,  6, 10, 15, 21, 28, 36, 45, 55,  2, 14,
+    27, 41, 56,  8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+const PI: [usize; 24] = [
+    10,  7, 11, 17, 18,  3,  5, 16,  8, 21, 24,  4,
+    15, 23, 19, 13, 12,  2, 20, 14, 22,  9,  6,  1,
+];
+
+/// Apply the Keccak-p\[1600, rounds\] permutation on a state. The last
+/// `rounds` rounds of Keccak-f\[1600\] are applied (e.g. KangarooTwelve
+/// uses 12 rounds). Lane `a[x + 5*y]` is the lane at coordinates (x, y).
+/// `rounds` MUST be between 0 and 24 (inclusive).
+pub fn keccak_p1600(a: &mut [u64; 25], rounds: usize) {
+    assert!(rounds <= 24);
+    for &rc in &RC[(24 - rounds)..] {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut t = a[1];
+        for i in 0..24 {
+            let j = PI[i];
+            let u = a[j];
+            a[j] = t.rotate_left(RHO[i]);
+            t = u;
+        }
+
+        // chi
+        for y in 0..5 {
+            let mut r = [0u64; 5];
+            r.copy_from_slice(&a[(5 * y)..(5 * y + 5)]);
+            for x in 0..5 {
+                a[x + 5 * y] = r[x] ^ (!r[(x + 1) % 5] & r[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        a[0] ^= rc;
+    }
+}
+
+/// Apply the Keccak-f\[1600\] permutation (24 rounds) on a state.
+#[inline]
+pub fn keccak_f1600(a: &mut [u64; 25]) {
+    keccak_p1600(a, 24);
+}
+
+// Generic Keccak sponge, with a configurable rate (in bytes, multiple of
+// 8) and number of rounds. Data is XORed directly into the state lanes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct KeccakSponge {
+    a: [u64; 25],
+    rate: usize,
+    rounds: usize,
+    // Current position (in bytes) within the rate.
+    pos: usize,
+}
+
+impl KeccakSponge {
+
+    pub(crate) fn new(rate: usize, rounds: usize) -> Self {
+        Self { a: [0u64; 25], rate, rounds, pos: 0 }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.a = [0u64; 25];
+        self.pos = 0;
+    }
+
+    #[inline(always)]
+    fn xor_byte(&mut self, b: u8) {
+        self.a[self.pos >> 3] ^= (b as u64) << ((self.pos & 7) << 3);
+        self.pos += 1;
+    }
+
+    // Absorb some data.
+    pub(crate) fn absorb(&mut self, data: &[u8]) {
+        let mut j = 0;
+        while j < data.len() {
+            if self.pos == self.rate {
+                keccak_p1600(&mut self.a, self.rounds);
+                self.pos = 0;
+            }
+            if (self.pos & 7) == 0 && (data.len() - j) >= 8 {
+                let n = core::cmp::min(self.rate - self.pos,
+                    (data.len() - j) & !7usize);
+                for k in (0..n).step_by(8) {
+                    self.a[(self.pos + k) >> 3] ^= u64::from_le_bytes(
+                        *<&[u8; 8]>::try_from(&data[(j + k)..(j + k + 8)])
+                        .unwrap());
+                }
+                self.pos += n;
+                j += n;
+            } else {
+                self.xor_byte(data[j]);
+                j += 1;
+            }
+        }
+    }
+
+    // Terminate the input with the provided domain separation byte
+    // (which includes the first bit of the pad10*1 padding, e.g. 0x06
+    // for SHA-3, 0x1F for SHAKE, 0x01 for the original Keccak), and
+    // switch to output mode.
+    pub(crate) fn pad(&mut self, ds: u8) {
+        if self.pos == self.rate {
+            keccak_p1600(&mut self.a, self.rounds);
+            self.pos = 0;
+        }
+        self.xor_byte(ds);
+        self.pos = self.rate - 1;
+        self.xor_byte(0x80);
+        keccak_p1600(&mut self.a, self.rounds);
+        self.pos = 0;
+    }
+
+    // Complete the current block with zeros (XORing zeros is a no-op, so
+    // only the position is updated). If no data has been injected since
+    // the last block boundary, then this does nothing.
+    pub(crate) fn fill_block(&mut self) {
+        if self.pos != 0 {
+            self.pos = self.rate;
+        }
+    }
+
+    // Get the next output bytes (after padding).
+    pub(crate) fn squeeze(&mut self, out: &mut [u8]) {
+        for b in out.iter_mut() {
+            if self.pos == self.rate {
+                keccak_p1600(&mut self.a, self.rounds);
+                self.pos = 0;
+            }
+            *b = (self.a[self.pos >> 3] >> ((self.pos & 7) << 3)) as u8;
+            self.pos += 1;
+        }
+    }
+}
+
+macro_rules! define_sha3 {
+    ($name: ident, $out_len: expr, $ds: expr, $doc: expr) => {
+
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name(KeccakSponge);
+
+        impl $name {
+
+            /// Output length (in bytes).
+            pub const OUTPUT_LEN: usize = $out_len;
+
+            /// Internal block length (rate, in bytes).
+            pub const BLOCK_LEN: usize = 200 - 2 * $out_len;
+
+            /// Initialize a new context.
+            pub fn new() -> Self {
+                Self(KeccakSponge::new(Self::BLOCK_LEN, 24))
+            }
+
+            /// Reset this context, for a new hashing operation.
+            pub fn reset(&mut self) {
+                self.0.reset();
+            }
+
+            /// Inject some more bytes into the context.
+            pub fn update(&mut self, data: &[u8]) {
+                self.0.absorb(data);
+            }
+
+            /// Inject several chunks of data into the context; this is
+            /// equivalent to calling `update()` on each chunk in order.
+            pub fn update_vectored(&mut self, parts: &[&[u8]]) {
+                for d in parts.iter() {
+                    self.0.absorb(d);
+                }
+            }
+
+            /// Finalize the current computation and get the output. The
+            /// context is not modified, so more data may be injected
+            /// afterwards (the output then covers all data injected since
+            /// the last reset).
+            pub fn finalize(&self) -> [u8; $out_len] {
+                let mut sp = self.0;
+                sp.pad($ds);
+                let mut r = [0u8; $out_len];
+                sp.squeeze(&mut r);
+                r
+            }
+
+            /// Finalize the current computation and get the output. The
+            /// context is automatically reset, so that it can be used
+            /// again for a new computation.
+            pub fn finalize_reset(&mut self) -> [u8; $out_len] {
+                let r = self.finalize();
+                self.reset();
+                r
+            }
+
+            /// One-stop function for hashing some input.
+            pub fn hash(data: &[u8]) -> [u8; $out_len] {
+                let mut sh = Self::new();
+                sh.update(data);
+                sh.finalize()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    }
+}
+
+define_sha3!(Sha3_224, 28, 0x06, "SHA3-224 context.");
+define_sha3!(Sha3_256, 32, 0x06, "SHA3-256 context.");
+define_sha3!(Sha3_384, 48, 0x06, "SHA3-384 context.");
+define_sha3!(Sha3_512, 64, 0x06, "SHA3-512 context.");
+define_sha3!(Keccak256, 32, 0x01,
+    "Keccak-256 context (original Keccak padding, as used by Ethereum).");
+
+/// Output reader for SHAKE128 and SHAKE256.
+#[derive(Clone, Copy, Debug)]
+pub struct ShakeReader(KeccakSponge);
+
+impl ShakeReader {
+
+    /// Get the next bytes of output; the provided slice is filled.
+    /// Successive calls return consecutive chunks of the output stream,
+    /// regardless of how the output is split over calls.
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        self.0.squeeze(out);
+    }
+}
+
+impl crate::xof::XofReader for ShakeReader {
+    fn squeeze(&mut self, out: &mut [u8]) {
+        self.0.squeeze(out);
+    }
+}
+
+macro_rules! define_shake {
+    ($name: ident, $sec: expr, $doc: expr) => {
+
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name(KeccakSponge);
+
+        impl $name {
+
+            /// Internal block length (rate, in bytes).
+            pub const BLOCK_LEN: usize = 200 - ($sec >> 2);
+
+            /// Initialize a new context.
+            pub fn new() -> Self {
+                Self(KeccakSponge::new(Self::BLOCK_LEN, 24))
+            }
+
+            /// Reset this context, for a new hashing operation.
+            pub fn reset(&mut self) {
+                self.0.reset();
+            }
+
+            /// Inject some more bytes into the context.
+            pub fn update(&mut self, data: &[u8]) {
+                self.0.absorb(data);
+            }
+
+            /// Inject several chunks of data into the context; this is
+            /// equivalent to calling `update()` on each chunk in order.
+            pub fn update_vectored(&mut self, parts: &[&[u8]]) {
+                for d in parts.iter() {
+                    self.0.absorb(d);
+                }
+            }
+
+            /// Finalize the current computation and get a reader for the
+            /// output. The context is not modified, so more data may be
+            /// injected afterwards (a new reader then covers all data
+            /// injected since the last reset).
+            pub fn finalize_xof(&self) -> ShakeReader {
+                let mut sp = self.0;
+                sp.pad(0x1F);
+                ShakeReader(sp)
+            }
+
+            /// One-stop function for hashing some input into an output
+            /// of the provided length (`out` is filled).
+            pub fn hash_into(data: &[u8], out: &mut [u8]) {
+                let mut sh = Self::new();
+                sh.update(data);
+                sh.finalize_xof().squeeze(out);
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl crate::xof::Xof for $name {
+            type Reader = ShakeReader;
+
+            fn absorb(&mut self, data: &[u8]) {
+                self.0.absorb(data);
+            }
+
+            fn finalize_xof(self) -> ShakeReader {
+                $name::finalize_xof(&self)
+            }
+        }
+    }
+}
+
+define_shake!(Shake128, 128, "SHAKE128 context.");
+define_shake!(Shake256, 256, "SHAKE256 context.");
+
+/// Encode an integer with the `left_encode()` function of NIST SP 800-185:
+/// the minimal big-endian encoding of `x` (at least one byte), preceded
+/// by its length in bytes. The encoding is written at the start of `out`,
+/// and its length (between 2 and 9 bytes) is returned.
+pub fn left_encode(x: u64, out: &mut [u8; 9]) -> usize {
+    let n = core::cmp::max(1, 8 - (x.leading_zeros() as usize >> 3));
+    out[0] = n as u8;
+    out[1..(n + 1)].copy_from_slice(&x.to_be_bytes()[(8 - n)..]);
+    n + 1
+}
+
+/// Encode an integer with the `right_encode()` function of NIST SP
+/// 800-185: the minimal big-endian encoding of `x` (at least one byte),
+/// followed by its length in bytes. The encoding is written at the start
+/// of `out`, and its length (between 2 and 9 bytes) is returned.
+pub fn right_encode(x: u64, out: &mut [u8; 9]) -> usize {
+    let n = core::cmp::max(1, 8 - (x.leading_zeros() as usize >> 3));
+    out[..n].copy_from_slice(&x.to_be_bytes()[(8 - n)..]);
+    out[n] = n as u8;
+    n + 1
+}
+
+// Absorb encode_string(s) (SP 800-185): left_encode() of the bit length
+// of s, followed by s itself.
+fn absorb_encode_string(sp: &mut KeccakSponge, s: &[u8]) {
+    let mut tmp = [0u8; 9];
+    let n = left_encode((s.len() as u64) << 3, &mut tmp);
+    sp.absorb(&tmp[..n]);
+    sp.absorb(s);
+}
+
+// Absorb bytepad(encode_string(s[0]) || encode_string(s[1]) || ..., rate)
+// (SP 800-185). The sponge must be at a block boundary.
+fn absorb_bytepad(sp: &mut KeccakSponge, strs: &[&[u8]]) {
+    let mut tmp = [0u8; 9];
+    let n = left_encode(sp.rate as u64, &mut tmp);
+    sp.absorb(&tmp[..n]);
+    for s in strs.iter() {
+        absorb_encode_string(sp, s);
+    }
+    sp.fill_block();
+}
+
+// Absorb right_encode(x).
+fn absorb_right_encode(sp: &mut KeccakSponge, x: u64) {
+    let mut tmp = [0u8; 9];
+    let n = right_encode(x, &mut tmp);
+    sp.absorb(&tmp[..n]);
+}
+
+macro_rules! define_cshake {
+    ($name: ident, $sec: expr, $doc: expr) => {
+
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name {
+            sp: KeccakSponge,
+            ds: u8,
+        }
+
+        impl $name {
+
+            /// Internal block length (rate, in bytes).
+            pub const BLOCK_LEN: usize = 200 - ($sec >> 2);
+
+            /// Initialize a new context, with the provided function name
+            /// and customization string. If both are empty, then the
+            /// function is identical to SHAKE (as per SP 800-185).
+            pub fn new(function_name: &[u8], customization: &[u8]) -> Self {
+                let mut sp = KeccakSponge::new(Self::BLOCK_LEN, 24);
+                if function_name.is_empty() && customization.is_empty() {
+                    return Self { sp, ds: 0x1F };
+                }
+                absorb_bytepad(&mut sp, &[function_name, customization]);
+                Self { sp, ds: 0x04 }
+            }
+
+            /// Inject some more bytes into the context.
+            pub fn update(&mut self, data: &[u8]) {
+                self.sp.absorb(data);
+            }
+
+            /// Inject several chunks of data into the context; this is
+            /// equivalent to calling `update()` on each chunk in order.
+            pub fn update_vectored(&mut self, parts: &[&[u8]]) {
+                for d in parts.iter() {
+                    self.sp.absorb(d);
+                }
+            }
+
+            /// Finalize the current computation and get a reader for the
+            /// output. The context is not modified, so more data may be
+            /// injected afterwards.
+            pub fn finalize_xof(&self) -> ShakeReader {
+                let mut sp = self.sp;
+                sp.pad(self.ds);
+                ShakeReader(sp)
+            }
+
+            /// One-stop function for hashing some input into an output
+            /// of the provided length (`out` is filled).
+            pub fn hash_into(function_name: &[u8], customization: &[u8],
+                data: &[u8], out: &mut [u8])
+            {
+                let mut sh = Self::new(function_name, customization);
+                sh.update(data);
+                sh.finalize_xof().squeeze(out);
+            }
+        }
+
+        impl crate::xof::Xof for $name {
+            type Reader = ShakeReader;
+
+            fn absorb(&mut self, data: &[u8]) {
+                self.sp.absorb(data);
+            }
+
+            fn finalize_xof(self) -> ShakeReader {
+                $name::finalize_xof(&self)
+            }
+        }
+    }
+}
+
+define_cshake!(CShake128, 128, "cSHAKE128 context (NIST SP 800-185).");
+define_cshake!(CShake256, 256, "cSHAKE256 context (NIST SP 800-185).");
+
+macro_rules! define_kmac {
+    ($name: ident, $cshake: ident, $doc: expr) => {
+
+        #[doc = $doc]
+        ///
+        /// The output length is chosen at finalization time, and is part of
+        /// the MAC input: `finalize_into()` computes KMAC with the length of
+        /// the provided buffer, while `finalize_xof()` computes KMACXOF,
+        /// whose output stream does not depend on the number of bytes
+        /// which are read.
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name($cshake);
+
+        impl $name {
+
+            /// Initialize a new context, with the provided key and
+            /// customization string.
+            pub fn new(key: &[u8], customization: &[u8]) -> Self {
+                let mut cs = $cshake::new(b"KMAC", customization);
+                absorb_bytepad(&mut cs.sp, &[key]);
+                Self(cs)
+            }
+
+            /// Inject some more bytes into the context.
+            pub fn update(&mut self, data: &[u8]) {
+                self.0.update(data);
+            }
+
+            /// Finalize the current computation and write the MAC output
+            /// into `out` (the output length is that of `out`). The
+            /// context is not modified, so more data may be injected
+            /// afterwards.
+            pub fn finalize_into(&self, out: &mut [u8]) {
+                let mut cs = self.0;
+                absorb_right_encode(&mut cs.sp, (out.len() as u64) << 3);
+                cs.finalize_xof().squeeze(out);
+            }
+
+            /// Finalize the current computation as KMACXOF (arbitrary
+            /// output length) and get a reader for the output. The context
+            /// is not modified.
+            pub fn finalize_xof(&self) -> ShakeReader {
+                let mut cs = self.0;
+                absorb_right_encode(&mut cs.sp, 0);
+                cs.finalize_xof()
+            }
+        }
+    }
+}
+
+define_kmac!(Kmac128, CShake128, "KMAC128 context (NIST SP 800-185).");
+define_kmac!(Kmac256, CShake256, "KMAC256 context (NIST SP 800-185).");
+
+macro_rules! define_tuplehash {
+    ($name: ident, $cshake: ident, $doc: expr) => {
+
+        #[doc = $doc]
+        ///
+        /// The input is a sequence of byte strings, injected one by one
+        /// with `push()`; the boundaries between strings are unambiguously
+        /// encoded. As with KMAC, the output length is part of the input
+        /// for `finalize_into()`, while `finalize_xof()` computes the XOF
+        /// variant (TupleHashXOF).
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name($cshake);
+
+        impl $name {
+
+            /// Initialize a new context, with the provided customization
+            /// string.
+            pub fn new(customization: &[u8]) -> Self {
+                Self($cshake::new(b"TupleHash", customization))
+            }
+
+            /// Inject the next string of the tuple.
+            pub fn push(&mut self, item: &[u8]) {
+                absorb_encode_string(&mut self.0.sp, item);
+            }
+
+            /// Finalize the current computation and write the output into
+            /// `out` (the output length is that of `out`). The context is
+            /// not modified, so more strings may be pushed afterwards.
+            pub fn finalize_into(&self, out: &mut [u8]) {
+                let mut cs = self.0;
+                absorb_right_encode(&mut cs.sp, (out.len() as u64) << 3);
+                cs.finalize_xof().squeeze(out);
+            }
+
+            /// Finalize the current computation as TupleHashXOF (arbitrary
+            /// output length) and get a reader for the output. The context
+            /// is not modified.
+            pub fn finalize_xof(&self) -> ShakeReader {
+                let mut cs = self.0;
+                absorb_right_encode(&mut cs.sp, 0);
+                cs.finalize_xof()
+            }
+
+            /// One-stop function for hashing a tuple of strings into an
+            /// output of the provided length (`out` is filled).
+            pub fn hash_into(items: &[&[u8]], customization: &[u8],
+                out: &mut [u8])
+            {
+                let mut th = Self::new(customization);
+                for it in items.iter() {
+                    th.push(it);
+                }
+                th.finalize_into(out);
+            }
+        }
+    }
+}
+
+define_tuplehash!(TupleHash128, CShake128,
+    "TupleHash128 context (NIST SP 800-185).");
+define_tuplehash!(TupleHash256, CShake256,
+    "TupleHash256 context (NIST SP 800-185).");
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Sha3_224, Sha3_256, Sha3_384, Sha3_512, Keccak256};
+    use super::{Shake128, Shake256};
+    use super::keccak_f1600;
+    use super::{left_encode, right_encode};
+    use super::{CShake128, CShake256, Kmac128, Kmac256};
+    use super::{TupleHash128, TupleHash256};
+
+    macro_rules! check_kat {
+        ($name: ident, $kat: expr) => {
+            let m448 = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+            let m896 = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+            let m1600 = [0xA3u8; 200];
+            let msgs: [&[u8]; 5] = [b"", b"abc", m448, m896, &m1600];
+            for (msg, hv) in msgs.iter().zip($kat.iter()) {
+                let hv = hex::decode(hv).unwrap();
+                assert!($name::hash(msg)[..] == hv[..]);
+                let mut sh = $name::new();
+                for b in msg.iter() {
+                    sh.update(&[*b]);
+                }
+                assert!(sh.finalize()[..] == hv[..]);
+                assert!(sh.finalize_reset()[..] == hv[..]);
+                sh.update(msg);
+                assert!(sh.finalize()[..] == hv[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn keccak_f() {
+        // Keccak-f[1600] applied twice on the all-zero state (reference
+        // values from the Keccak team's intermediate-value files).
+        let mut a = [0u64; 25];
+        keccak_f1600(&mut a);
+        assert!(a[0] == 0xF1258F7940E1DDE7);
+        assert!(a[24] == 0xEAF1FF7B5CECA249);
+        keccak_f1600(&mut a);
+        assert!(a[0] == 0x2D5C954DF96ECB3C);
+        assert!(a[24] == 0x20D06CD26A8FBF5C);
+    }
+
+    #[test]
+    fn sha3_kat() {
+        // Messages: empty, "abc", the 448-bit and 896-bit NIST example
+        // messages, and the 1600-bit message made of bytes 0xA3.
+        check_kat!(Sha3_224, [
+            "6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7",
+            "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf",
+            "8a24108b154ada21c9fd5574494479ba5c7e7ab76ef264ead0fcce33",
+            "543e6868e1666c1a643630df77367ae5a62a85070a51c14cbf665cbc",
+            "9376816aba503f72f96ce7eb65ac095deee3be4bf9bbc2a1cb7e11e0",
+        ]);
+        check_kat!(Sha3_256, [
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            "41c0dba2a9d6240849100376a8235e2c82e1b9998a999e21db32dd97496d3376",
+            "916f6061fe879741ca6469b43971dfdb28b1a32dc36cb3254e812be27aad1d18",
+            "79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787",
+        ]);
+        check_kat!(Sha3_384, [
+            "0c63a75b845e4f7d01107d852e4c2485c51a50aaaa94fc61995e71bbee983a2ac3713831264adb47fb6bd1e058d5f004",
+            "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25",
+            "991c665755eb3a4b6bbdfb75c78a492e8c56a22c5c4d7e429bfdbc32b9d4ad5aa04a1f076e62fea19eef51acd0657c22",
+            "79407d3b5916b59c3e30b09822974791c313fb9ecc849e406f23592d04f625dc8c709b98b43b3852b337216179aa7fc7",
+            "1881de2ca7e41ef95dc4732b8f5f002b189cc1e42b74168ed1732649ce1dbcdd76197a31fd55ee989f2d7050dd473e8f",
+        ]);
+        check_kat!(Sha3_512, [
+            "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a615b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26",
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+            "04a371e84ecfb5b8b77cb48610fca8182dd457ce6f326a0fd3d7ec2f1e91636dee691fbe0c985302ba1b0d8dc78c086346b533b49c030d99a27daf1139d6e75e",
+            "afebb2ef542e6579c50cad06d2e578f9f8dd6881d7dc824d26360feebf18a4fa73e3261122948efcfd492e74e82e2189ed0fb440d187f382270cb455f21dd185",
+            "e76dfad22084a8b1467fcf2ffa58361bec7628edf5f3fdc0e4805dc48caeeca81b7c13c30adf52a3659584739a2df46be589c51ca1a4a8416df6545a1ce8ba00",
+        ]);
+    }
+
+    #[test]
+    fn keccak256() {
+        assert!(Keccak256::hash(b"")[..] == hex::decode("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").unwrap()[..]);
+        assert!(Keccak256::hash(b"abc")[..] == hex::decode("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45").unwrap()[..]);
+    }
+
+    #[test]
+    fn shake_kat() {
+        // Messages: empty, "abc", and the 1600-bit message made of bytes
+        // 0xA3; 400-byte outputs (more than two blocks for SHAKE256).
+        let m1600 = [0xA3u8; 200];
+        let msgs: [&[u8]; 3] = [b"", b"abc", &m1600];
+        const KAT128: [&str; 3] = [
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef263cb1eea988004b93103cfb0aeefd2a686e01fa4a58e8a3639ca8a1e3f9ae57e235b8cc873c23dc62b8d260169afa2f75ab916a58d974918835d25e6a435085b2badfd6dfaac359a5efbb7bcc4b59d538df9a04302e10c8bc1cbf1a0b3a5120ea17cda7cfad765f5623474d368ccca8af0007cd9f5e4c849f167a580b14aabdefaee7eef47cb0fca9767be1fda69419dfb927e9df07348b196691abaeb580b32def58538b8d23f87732ea63b02b4fa0f4873360e2841928cd60dd4cee8cc0d4c922a96188d032675c8ac850933c7aff1533b94c834adbb69c6115bad4692d8619f90b0cdf8a7b9c264029ac185b70b83f2801f2f4b3f70c593ea3aeeb613a7f1b1de33fd75081f592305f2e4526edc09631b10958f464d889f31ba010250fda7f1368ec2967fc84ef2ae9aff268e0b1700affc6820b523a3d917135f2dff2ee06bfe72b3124721d4a26c04e53a75e30e73a7a9c4a95d91c55d495e9f51dd0b5e9d83c6d5e8ce803aa62b8d654db53d09b",
+            "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc844c50af32acd3f2cdd066568706f509bc1bdde58295dae3f891a9a0fca5783789a41f8611214ce612394df286a62d1a2252aa94db9c538956c717dc2bed4f232a0294c857c730aa16067ac1062f1201fb0d377cfb9cde4c63599b27f3462bba4a0ed296c801f9ff7f57302bb3076ee145f97a32ae68e76ab66c48d51675bd49acc29082f5647584e6aa01b3f5af057805f973ff8ecb8b226ac32ada6f01c1fcd4818cb006aa5b4cdb3611eb1e533c8964cacfdf31012cd3fb744d02225b988b475375faad996eb1b9176ecb0f8b2871723d6dbb804e23357e50732f5cfc904b1319795000d7361d9e5e1b77b4b8f5774aa1482cfa58f83096bdb2e06a3eed543a38919b57ecbec737f4086be007f8ef80094ceea8807193d46e9be540b6e99b4c1c71507095028a024e8d39aa8f4c5854cedd50d30a223e7d54e9a24f0a2526b31002afbd1b4ebea69c8400c3deb4c1c35d6dbb75651b284076f5fde47b4a0586ee173e30bd4d08f2bc59c6114bdd745",
+            "131ab8d2b594946b9c81333f9bb6e0ce75c3b93104fa3469d3917457385da037cf232ef7164a6d1eb448c8908186ad852d3f85a5cf28da1ab6fe3438171978467f1c05d58c7ef38c284c41f6c2221a76f12ab1c04082660250802294fb87180213fdef5b0ecb7df50ca1f8555be14d32e10f6edcde892c09424b29f597afc270c904556bfcb47a7d40778d390923642b3cbd0579e60908d5a000c1d08b98ef933f806445bf87f8b009ba9e94f7266122ed7ac24e5e266c42a82fa1bbefb7b8db0066e16a85e0493f07df4809aec084a593748ac3dde5a6d7aae1e8b6e5352b2d71efbb47d4caeed5e6d633805d2d323e6fd81b4684b93a2677d45e7421c2c6aea259b855a698fd7d13477a1fe53e5a4a6197dbec5ce95f505b520bcd9570c4a8265a7e01f89c0c002c59bfec6cd4a5c109258953ee5ee70cd577ee217af21fa70178f0946c9bf6ca8751793479f6b537737e40b6ed28511d8a2d7e73eb75f8daac912ff906e0ab955b083bac45a8e5e9b744c8506f37e9b4e749a184b30f43eb188d855f1b70d71ff3e50c537ac1b0f8",
+        ];
+        const KAT256: [&str; 3] = [
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be141e96616fb13957692cc7edd0b45ae3dc07223c8e92937bef84bc0eab862853349ec75546f58fb7c2775c38462c5010d846c185c15111e595522a6bcd16cf86f3d122109e3b1fdd943b6aec468a2d621a7c06c6a957c62b54dafc3be87567d677231395f6147293b68ceab7a9e0c58d864e8efde4e1b9a46cbe854713672f5caaae314ed9083dab4b099f8e300f01b8650f1f4b1d8fcf3f3cb53fb8e9eb2ea203bdc970f50ae55428a91f7f53ac266b28419c3778a15fd248d339ede785fb7f5a1aaa96d313eacc890936c173cdcd0fab882c45755feb3aed96d477ff96390bf9a66d1368b208e21f7c10d04a3dbd4e360633e5db4b602601c14cea737db3dcf722632cc77851cbdde2aaf0a33a07b373445df490cc8fc1e4160ff118378f11f0477de055a81a9eda57a4a2cfb0c83929d310912f729ec6cfa36c6ac6a75837143045d791cc85eff5b21932f23861bc",
+            "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e41385141204f329979fd3047a13c5657724ada64d2470157b3cdc288620944d78dbcddbd912993f0913f164fb2ce95131a2d09a3e6d51cbfc622720d7a75c6334e8a2d7ec71a7cc29cf0ea610eeff1a588290a53000faa79932becec0bd3cd0b33a7e5d397fed1ada9442b99903f4dcfd8559ed3950faf40fe6f3b5d710ed3b677513771af6bfe11934817e8762d9896ba579d88d84ba7aa3cdc7055f6796f195bd9ae788f2f5bb96100d6bbaff7fbc6eea24d4449a2477d172a5507dcc931412fc346b1bb39b878330e026b12ddf384af3334560ea1d363966caa7d8ddcbec7da52b42215c11d5f8ee57f341e399343ce63a752fc5edec99124a0eb314403e5f358b8b83d05be2d2970099284b00dcc33d7c753d1f752ab743325bc53d91aa671e50f9c3f93abf6e9662f90145c61954f2abbd26edad1553ea3a626f359e8f79ade16384e151755c47e822fc74c5d710",
+            "cd8a920ed141aa0407a22d59288652e9d9f1a7ee0c1e7c1ca699424da84a904d2d700caae7396ece96604440577da4f3aa22aeb8857f961c4cd8e06f0ae6610b1048a7f64e1074cd629e85ad7566048efc4fb500b486a3309a8f26724c0ed628001a1099422468de726f1061d99eb9e93604d5aa7467d4b1bd6484582a384317d7f47d750b8f5499512bb85a226c4243556e696f6bd072c5aa2d9b69730244b56853d16970ad817e213e470618178001c9fb56c54fefa5fee67d2da524bb3b0b61ef0e9114a92cdbb6cccb98615cfe76e3510dd88d1cc28ff99287512f24bfafa1a76877b6f37198e3a641c68a7c42d45fa7acc10dae5f3cefb7b735f12d4e589f7a456e78c0f5e4c4471fffa5e4fa0514ae974d8c2648513b5db494cea847156d277ad0e141c24c7839064cd08851bc2e7ca109fd4e251c35bb0a04fb05b364ff8c4d8b59bc303e25328c09a882e952518e1a8ae0ff265d61c465896973d7490499dc639fb8502b39456791b1b6ec5bcc5d9ac36a6df622a070d43fed781f5f149f7b62675e7d1a4d6dec48c1c71645",
+        ];
+        for i in 0..3 {
+            let r128 = hex::decode(KAT128[i]).unwrap();
+            let r256 = hex::decode(KAT256[i]).unwrap();
+            let mut out = [0u8; 400];
+            Shake128::hash_into(msgs[i], &mut out);
+            assert!(out[..] == r128[..]);
+            Shake256::hash_into(msgs[i], &mut out);
+            assert!(out[..] == r256[..]);
+
+            // Short outputs are prefixes of longer outputs.
+            let mut out = [0u8; 32];
+            Shake128::hash_into(msgs[i], &mut out);
+            assert!(out[..] == r128[..32]);
+
+            // Output does not depend on how squeezing is split.
+            let mut sh = Shake256::new();
+            sh.update(msgs[i]);
+            let mut rd = sh.finalize_xof();
+            let mut out = [0u8; 400];
+            let mut j = 0;
+            let mut k = 0;
+            while j < out.len() {
+                let clen = core::cmp::min(k, out.len() - j);
+                rd.squeeze(&mut out[j..(j + clen)]);
+                j += clen;
+                k = (k * 7 + 3) % 150;
+            }
+            assert!(out[..] == r256[..]);
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        // Compare with the sha3 crate, over many lengths (around the rate
+        // boundaries) and with various chunkings.
+        use ::sha3::Digest;
+        let mut data = [0u8; 600];
+        for i in 0..data.len() {
+            data[i] = (i * 5 + 3) as u8;
+        }
+        for n in 0..data.len() {
+            let d = &data[..n];
+            let r1 = ::sha3::Sha3_256::digest(d);
+            let r2 = ::sha3::Sha3_512::digest(d);
+            let r3 = ::sha3::Keccak256::digest(d);
+            assert!(Sha3_256::hash(d)[..] == r1[..]);
+            assert!(Sha3_512::hash(d)[..] == r2[..]);
+            assert!(Keccak256::hash(d)[..] == r3[..]);
+            for k in [1, 7, 8, 71, 136, 200] {
+                let parts: crate::Vec<&[u8]> = d.chunks(k).collect();
+                let mut s1 = Sha3_256::new();
+                for p in parts.iter() {
+                    s1.update(p);
+                }
+                let mut s2 = Sha3_512::new();
+                s2.update_vectored(&parts[..]);
+                assert!(s1.finalize()[..] == r1[..]);
+                assert!(s2.finalize()[..] == r2[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn encodings() {
+        const KAT: [(u64, &str, &str); 7] = [
+            (0, "0100", "0001"),
+            (1, "0101", "0101"),
+            (168, "01a8", "a801"),
+            (255, "01ff", "ff01"),
+            (256, "020100", "010002"),
+            (0x123456, "03123456", "12345603"),
+            (u64::MAX, "08ffffffffffffffff", "ffffffffffffffff08"),
+        ];
+        for (x, le, re) in KAT.iter() {
+            let mut buf = [0u8; 9];
+            let n = left_encode(*x, &mut buf);
+            assert!(buf[..n] == hex::decode(le).unwrap()[..]);
+            let mut buf = [0u8; 9];
+            let n = right_encode(*x, &mut buf);
+            assert!(buf[..n] == hex::decode(re).unwrap()[..]);
+        }
+    }
+
+    // Test vectors from the NIST SP 800-185 examples. Data is either
+    // 00010203 or the 200 bytes 00..C7; KMAC keys are the 32 bytes 40..5F.
+    fn sp800_data(n: usize) -> [u8; 200] {
+        let mut d = [0u8; 200];
+        for i in 0..n {
+            d[i] = i as u8;
+        }
+        d
+    }
+
+    #[test]
+    fn cshake_kat() {
+        let d4 = sp800_data(4);
+        let d200 = sp800_data(200);
+        let msgs: [&[u8]; 2] = [&d4[..4], &d200[..]];
+        const KAT128: [&str; 2] = [
+            "c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5",
+            "c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b",
+        ];
+        const KAT256: [&str; 2] = [
+            "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd164020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c",
+            "07dc27b11e51fbac75bc7b3c1d983e8b4b85fb1defaf218912ac86430273091727f42b17ed1df63e8ec118f04b23633c1dfb1574c8fb55cb45da8e25afb092bb",
+        ];
+        for i in 0..2 {
+            let mut out = [0u8; 32];
+            CShake128::hash_into(b"", b"Email Signature", msgs[i], &mut out);
+            assert!(out[..] == hex::decode(KAT128[i]).unwrap()[..]);
+            let mut out = [0u8; 64];
+            CShake256::hash_into(b"", b"Email Signature", msgs[i], &mut out);
+            assert!(out[..] == hex::decode(KAT256[i]).unwrap()[..]);
+        }
+
+        // With empty name and customization, cSHAKE is SHAKE.
+        let mut out1 = [0u8; 300];
+        let mut out2 = [0u8; 300];
+        CShake128::hash_into(b"", b"", b"abc", &mut out1);
+        Shake128::hash_into(b"abc", &mut out2);
+        assert!(out1 == out2);
+        CShake256::hash_into(b"", b"", b"abc", &mut out1);
+        Shake256::hash_into(b"abc", &mut out2);
+        assert!(out1 == out2);
+    }
+
+    #[test]
+    fn kmac_kat() {
+        let d4 = sp800_data(4);
+        let d200 = sp800_data(200);
+        let mut key = [0u8; 32];
+        for i in 0..32 {
+            key[i] = 0x40 + i as u8;
+        }
+        let s = b"My Tagged Application";
+        // (data, customization, KMAC output, KMACXOF output)
+        let kat128: [(&[u8], &[u8], &str, &str); 3] = [
+            (&d4[..4], b"",
+             "e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e",
+             "cd83740bbd92ccc8cf032b1481a0f4460e7ca9dd12b08a0c4031178bacd6ec35"),
+            (&d4[..4], s,
+             "3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5",
+             "31a44527b4ed9f5c6101d11de6d26f0620aa5c341def41299657fe9df1a3b16c"),
+            (&d200[..], s,
+             "1f5b4e6cca02209e0dcb5ca635b89a15e271ecc760071dfd805faa38f9729230",
+             "47026c7cd793084aa0283c253ef658490c0db61438b8326fe9bddf281b83ae0f"),
+        ];
+        let kat256: [(&[u8], &[u8], &str, &str); 3] = [
+            (&d4[..4], s,
+             "20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd",
+             "1755133f1534752aad0748f2c706fb5c784512cab835cd15676b16c0c6647fa96faa7af634a0bf8ff6df39374fa00fad9a39e322a7c92065a64eb1fb0801eb2b"),
+            (&d200[..], b"",
+             "75358cf39e41494e949707927cee0af20a3ff553904c86b08f21cc414bcfd691589d27cf5e15369cbbff8b9a4c2eb17800855d0235ff635da82533ec6b759b69",
+             "ff7b171f1e8a2b24683eed37830ee797538ba8dc563f6da1e667391a75edc02ca633079f81ce12a25f45615ec89972031d18337331d24ceb8f8ca8e6a19fd98b"),
+            (&d200[..], s,
+             "b58618f71f92e1d56c1b8c55ddd7cd188b97b4ca4d99831eb2699a837da2e4d970fbacfde50033aea585f1a2708510c32d07880801bd182898fe476876fc8965",
+             "d5be731c954ed7732846bb59dbe3a8e30f83e77a4bff4459f2f1c2b4ecebb8ce67ba01c62e8ab8578d2d499bd1bb276768781190020a306a97de281dcc30305d"),
+        ];
+        for (data, cust, r1, r2) in kat128.iter() {
+            let mut mac = Kmac128::new(&key, cust);
+            mac.update(data);
+            let mut out = [0u8; 32];
+            mac.finalize_into(&mut out);
+            assert!(out[..] == hex::decode(r1).unwrap()[..]);
+            mac.finalize_xof().squeeze(&mut out);
+            assert!(out[..] == hex::decode(r2).unwrap()[..]);
+        }
+        for (data, cust, r1, r2) in kat256.iter() {
+            let mut mac = Kmac256::new(&key, cust);
+            mac.update(data);
+            let mut out = [0u8; 64];
+            mac.finalize_into(&mut out);
+            assert!(out[..] == hex::decode(r1).unwrap()[..]);
+            mac.finalize_xof().squeeze(&mut out);
+            assert!(out[..] == hex::decode(r2).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn tuplehash_kat() {
+        let t1: &[u8] = &[0x00, 0x01, 0x02];
+        let t2: &[u8] = &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15];
+        let t3: &[u8] = &[0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28];
+        let s = b"My Tuple App";
+        // (number of items, customization, TupleHash128, TupleHash256)
+        let kat: [(usize, &[u8], &str, &str); 3] = [
+            (2, b"",
+             "c5d8786c1afb9b82111ab34b65b2c0048fa64e6d48e263264ce1707d3ffc8ed1",
+             "cfb7058caca5e668f81a12a20a2195ce97a925f1dba3e7449a56f82201ec607311ac2696b1ab5ea2352df1423bde7bd4bb78c9aed1a853c78672f9eb23bbe194"),
+            (2, s,
+             "75cdb20ff4db1154e841d758e24160c54bae86eb8c13e7f5f40eb35588e96dfb",
+             "147c2191d5ed7efd98dbd96d7ab5a11692576f5fe2a5065f3e33de6bba9f3aa1c4e9a068a289c61c95aab30aee1e410b0b607de3620e24a4e3bf9852a1d4367e"),
+            (3, s,
+             "e60f202c89a2631eda8d4c588ca5fd07f39e5151998deccf973adb3804bb6e84",
+             "45000be63f9b6bfd89f54717670f69a9bc763591a4f05c50d68891a744bcc6e7d6d5b5e82c018da999ed35b0bb49c9678e526abd8e85c13ed254021db9e790ce"),
+        ];
+        let items = [t1, t2, t3];
+        for (n, cust, r1, r2) in kat.iter() {
+            let mut out = [0u8; 32];
+            TupleHash128::hash_into(&items[..*n], cust, &mut out);
+            assert!(out[..] == hex::decode(r1).unwrap()[..]);
+            let mut out = [0u8; 64];
+            TupleHash256::hash_into(&items[..*n], cust, &mut out);
+            assert!(out[..] == hex::decode(r2).unwrap()[..]);
+        }
+
+        // Item boundaries matter.
+        let mut out1 = [0u8; 32];
+        let mut out2 = [0u8; 32];
+        TupleHash128::hash_into(&[b"ab", b"c"], b"", &mut out1);
+        TupleHash128::hash_into(&[b"a", b"bc"], b"", &mut out2);
+        assert!(out1 != out2);
+    }
+}
@@ -0,0 +1,570 @@
+//! SipHash implementation.
+//!
+//! This implements SipHash-2-4 and SipHash-1-3, with either a 64-bit
+//! output (`SipHash24`, `SipHash13`) or a 128-bit output
+//! (`SipHash128_24`, `SipHash128_13`), as defined by the reference
+//! implementation (<https://github.com/veorq/SipHash>). SipHash is a
+//! keyed pseudorandom function optimized for short inputs; it is meant
+//! for hash tables and similar uses (e.g. network cookies), where the
+//! key is secret but collision resistance against parties who know the
+//! key is not needed. It is NOT a general-purpose MAC for long-term
+//! security.
+//!
+//! The 128-bit outputs are returned as a `u128` value; the 16-byte output
+//! of the reference implementation is the little-endian encoding of that
+//! value.
+//!
+//! The 64-bit output types implement `core::hash::Hasher`, so that they
+//! may be used directly with collections that support custom hashers.
+
+use core::convert::TryFrom;
+
+// Internal SipHash state (for all variants).
+#[derive(Clone, Copy, Debug)]
+struct SipState {
+    v: [u64; 4],
+    // Pending bytes (up to 7) and total input length.
+    tail: [u8; 8],
+    ntail: usize,
+    len: u64,
+}
+
+impl SipState {
+
+    fn new(k0: u64, k1: u64, out128: bool) -> Self {
+        let mut v = [
+            k0 ^ 0x736F6D6570736575,
+            k1 ^ 0x646F72616E646F6D,
+            k0 ^ 0x6C7967656E657261,
+            k1 ^ 0x7465646279746573,
+        ];
+        if out128 {
+            v[1] ^= 0xEE;
+        }
+        Self { v, tail: [0u8; 8], ntail: 0, len: 0 }
+    }
+
+    #[inline(always)]
+    fn rounds(&mut self, n: usize) {
+        let [mut v0, mut v1, mut v2, mut v3] = self.v;
+        for _ in 0..n {
+            v0 = v0.wrapping_add(v1);
+            v1 = v1.rotate_left(13);
+            v1 ^= v0;
+            v0 = v0.rotate_left(32);
+            v2 = v2.wrapping_add(v3);
+            v3 = v3.rotate_left(16);
+            v3 ^= v2;
+            v0 = v0.wrapping_add(v3);
+            v3 = v3.rotate_left(21);
+            v3 ^= v0;
+            v2 = v2.wrapping_add(v1);
+            v1 = v1.rotate_left(17);
+            v1 ^= v2;
+            v2 = v2.rotate_left(32);
+        }
+        self.v = [v0, v1, v2, v3];
+    }
+
+    #[inline(always)]
+    fn process_word(&mut self, m: u64, c: usize) {
+        self.v[3] ^= m;
+        self.rounds(c);
+        self.v[0] ^= m;
+    }
+
+    fn update(&mut self, data: &[u8], c: usize) {
+        self.len = self.len.wrapping_add(data.len() as u64);
+        let mut j = 0;
+
+        // Complete the pending word, if any.
+        if self.ntail > 0 {
+            let clen = core::cmp::min(8 - self.ntail, data.len());
+            self.tail[self.ntail..(self.ntail + clen)]
+                .copy_from_slice(&data[..clen]);
+            self.ntail += clen;
+            j = clen;
+            if self.ntail < 8 {
+                return;
+            }
+            self.process_word(u64::from_le_bytes(self.tail), c);
+            self.ntail = 0;
+        }
+
+        // Process full words.
+        while (data.len() - j) >= 8 {
+            let m = u64::from_le_bytes(*<&[u8; 8]>::try_from(
+                &data[j..(j + 8)]).unwrap());
+            self.process_word(m, c);
+            j += 8;
+        }
+
+        // Keep the remaining bytes.
+        let clen = data.len() - j;
+        self.tail[..clen].copy_from_slice(&data[j..]);
+        self.ntail = clen;
+    }
+
+    // Process the final word (with the length byte); the state is then
+    // ready for the output rounds.
+    fn finish_input(&mut self, c: usize) {
+        let mut last = [0u8; 8];
+        last[..self.ntail].copy_from_slice(&self.tail[..self.ntail]);
+        last[7] = self.len as u8;
+        self.process_word(u64::from_le_bytes(last), c);
+    }
+
+    fn finalize64(&self, c: usize, d: usize) -> u64 {
+        let mut st = *self;
+        st.finish_input(c);
+        st.v[2] ^= 0xFF;
+        st.rounds(d);
+        st.v[0] ^ st.v[1] ^ st.v[2] ^ st.v[3]
+    }
+
+    fn finalize128(&self, c: usize, d: usize) -> u128 {
+        let mut st = *self;
+        st.finish_input(c);
+        st.v[2] ^= 0xEE;
+        st.rounds(d);
+        let lo = st.v[0] ^ st.v[1] ^ st.v[2] ^ st.v[3];
+        st.v[1] ^= 0xDD;
+        st.rounds(d);
+        let hi = st.v[0] ^ st.v[1] ^ st.v[2] ^ st.v[3];
+        (lo as u128) | ((hi as u128) << 64)
+    }
+}
+
+fn split_key(key: &[u8; 16]) -> (u64, u64) {
+    let k0 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[..8]).unwrap());
+    let k1 = u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[8..]).unwrap());
+    (k0, k1)
+}
+
+macro_rules! define_siphash { ($typename:ident, $c:expr, $d:expr,
+    $out128:expr, $outtype:ty, $finalize:ident, $name:expr) =>
+{
+    #[doc = concat!($name, " context.")]
+    #[derive(Clone, Copy, Debug)]
+    pub struct $typename(SipState);
+
+    impl $typename {
+
+        /// Create a new context with the provided key, given as two
+        /// 64-bit words (`k0` is the little-endian decoding of the
+        /// first 8 bytes of the 16-byte key, `k1` of the last 8 bytes).
+        #[inline]
+        pub fn new(k0: u64, k1: u64) -> Self {
+            Self(SipState::new(k0, k1, $out128))
+        }
+
+        /// Create a new context with the provided 16-byte key.
+        #[inline]
+        pub fn new_with_key(key: &[u8; 16]) -> Self {
+            let (k0, k1) = split_key(key);
+            Self::new(k0, k1)
+        }
+
+        /// Inject some more bytes into the context.
+        #[inline]
+        pub fn update(&mut self, data: &[u8]) {
+            self.0.update(data, $c);
+        }
+
+        /// Get the output for all data injected so far. The context is
+        /// not modified, and more data may be injected afterwards.
+        #[inline]
+        pub fn finalize(&self) -> $outtype {
+            self.0.$finalize($c, $d)
+        }
+
+        /// One-stop function for computing the output over some data,
+        /// with the provided 16-byte key.
+        pub fn hash(key: &[u8; 16], data: &[u8]) -> $outtype {
+            let mut sh = Self::new_with_key(key);
+            sh.update(data);
+            sh.finalize()
+        }
+    }
+} }
+
+define_siphash!(SipHash24, 2, 4, false, u64, finalize64, "SipHash-2-4");
+define_siphash!(SipHash13, 1, 3, false, u64, finalize64, "SipHash-1-3");
+define_siphash!(SipHash128_24, 2, 4, true, u128, finalize128,
+    "SipHash-2-4 (128-bit output)");
+define_siphash!(SipHash128_13, 1, 3, true, u128, finalize128,
+    "SipHash-1-3 (128-bit output)");
+
+impl core::hash::Hasher for SipHash24 {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.finalize()
+    }
+}
+
+impl core::hash::Hasher for SipHash13 {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{SipHash24, SipHash13, SipHash128_24, SipHash128_13};
+    use core::convert::TryFrom;
+    use core::hash::Hasher;
+
+    // Test vectors from the reference implementation (vectors.h): the
+    // key is 00 01 02 ... 0F, and the i-th message consists of the
+    // i bytes 00 01 02 ... (i-1). Outputs are the bytes produced by the
+    // reference code.
+    static KAT_SIP24: [&str; 64] = [
+        "310e0edd47db6f72",
+        "fd67dc93c539f874",
+        "5a4fa9d909806c0d",
+        "2d7efbd796666785",
+        "b7877127e09427cf",
+        "8da699cd64557618",
+        "cee3fe586e46c9cb",
+        "37d1018bf50002ab",
+        "6224939a79f5f593",
+        "b0e4a90bdf82009e",
+        "f3b9dd94c5bb5d7a",
+        "a7ad6b22462fb3f4",
+        "fbe50e86bc8f1e75",
+        "903d84c02756ea14",
+        "eef27a8e90ca23f7",
+        "e545be4961ca29a1",
+        "db9bc2577fcc2a3f",
+        "9447be2cf5e99a69",
+        "9cd38d96f0b3c14b",
+        "bd6179a71dc96dbb",
+        "98eea21af25cd6be",
+        "c7673b2eb0cbf2d0",
+        "883ea3e395675393",
+        "c8ce5ccd8c030ca8",
+        "94af49f6c650adb8",
+        "eab8858ade92e1bc",
+        "f315bb5bb835d817",
+        "adcf6b0763612e2f",
+        "a5c91da7acaa4dde",
+        "716595876650a2a6",
+        "28ef495c53a387ad",
+        "42c341d8fa92d832",
+        "ce7cf2722f512771",
+        "e37859f94623f3a7",
+        "381205bb1ab0e012",
+        "ae97a10fd434e015",
+        "b4a31508beff4d31",
+        "81396229f0907902",
+        "4d0cf49ee5d4dcca",
+        "5c73336a76d8bf9a",
+        "d0a704536ba93e0e",
+        "925958fcd6420cad",
+        "a915c29bc8067318",
+        "952b79f3bc0aa6d4",
+        "f21df2e41d4535f9",
+        "87577519048f53a9",
+        "10a56cf5dfcd9adb",
+        "eb75095ccd986cd0",
+        "51a9cb9ecba312e6",
+        "96afadfc2ce666c7",
+        "72fe52975a4364ee",
+        "5a1645b276d592a1",
+        "b274cb8ebf87870a",
+        "6f9bb4203de7b381",
+        "eaecb2a30b22a87f",
+        "9924a43cc1315724",
+        "bd838d3aafbf8db7",
+        "0b1a2a3265d51aea",
+        "135079a3231ce660",
+        "932b2846e4d70666",
+        "e1915f5cb1eca46c",
+        "f325965ca16d629f",
+        "575ff28e60381be5",
+        "724506eb4c328a95",
+    ];
+
+    static KAT_SIP128_24: [&str; 64] = [
+        "a3817f04ba25a8e66df67214c7550293",
+        "da87c1d86b99af44347659119b22fc45",
+        "8177228da4a45dc7fca38bdef60affe4",
+        "9c70b60c5267a94e5f33b6b02985ed51",
+        "f88164c12d9c8faf7d0f6e7c7bcd5579",
+        "1368875980776f8854527a07690e9627",
+        "14eeca338b208613485ea0308fd7a15e",
+        "a1f1ebbed8dbc153c0b84aa61ff08239",
+        "3b62a9ba6258f5610f83e264f31497b4",
+        "264499060ad9baabc47f8b02bb6d71ed",
+        "00110dc378146956c95447d3f3d0fbba",
+        "0151c568386b6677a2b4dc6f81e5dc18",
+        "d626b266905ef35882634df68532c125",
+        "9869e247e9c08b10d029934fc4b952f7",
+        "31fcefac66d7de9c7ec7485fe4494902",
+        "5493e99933b0a8117e08ec0f97cfc3d9",
+        "6ee2a4ca67b054bbfd3315bf85230577",
+        "473d06e8738db89854c066c47ae47740",
+        "a426e5e423bf4885294da481feaef723",
+        "78017731cf65fab074d5208952512eb1",
+        "9e25fc833f2290733e9344a5e83839eb",
+        "568e495abe525a218a2214cd3e071d12",
+        "4a29b54552d16b9a469c10528eff0aae",
+        "c9d184ddd5a9f5e0cf8ce29a9abf691c",
+        "2db479ae78bd50d8882a8a178a6132ad",
+        "8ece5f042d5e447b5051b9eacb8d8f6f",
+        "9c0b53b4b3c307e87eaee08678141f66",
+        "abf248af69a6eae4bfd3eb2f129eeb94",
+        "0664da1668574b88b935f3027358aef4",
+        "aa4b9dc4bf337de90cd4fd3c467c6ab7",
+        "ea5c7f471faf6bde2b1ad7d4686d2287",
+        "2939b0183223fafc1723de4f52c43d35",
+        "7c3956ca5eeafc3e363e9d556546eb68",
+        "77c6077146f01c32b6b69d5f4ea9ffcf",
+        "37a6986cb8847edf0925f0f1309b54de",
+        "a705f0e69da9a8f907241a2e923c8cc8",
+        "3dc47d1f29c448461e9e76ed904f6711",
+        "0d62bf01e6fc0e1a0d3c4751c5d3692b",
+        "8c03468bca7c669ee4fd5e084bbee7b5",
+        "528a5bb93baf2c9c4473cce5d0d22bd9",
+        "df6a301e95c95dad97ae0cc8c6913bd8",
+        "801189902c857f39e73591285e70b6db",
+        "e617346ac9c231bb3650ae34ccca0c5b",
+        "27d93437efb721aa401821dcec5adf89",
+        "89237d9ded9c5e78d8b1c9b166cc7342",
+        "4a6d8091bf5e7d651189fa94a250b14c",
+        "0e33f96055e7ae893ffc0e3dcf492902",
+        "e61c432b720b19d18ec8d84bdc63151b",
+        "f7e5aef549f782cf379055a608269b16",
+        "438d030fd0b7a54fa837f2ad201a6403",
+        "a590d3ee4fbf04e3247e0d27f286423f",
+        "5fe2c1a172fe93c4b15cd37caef9f538",
+        "2c97325cbd06b36eb2133dd08b3a017c",
+        "92c814227a6bca949ff0659f002ad39e",
+        "dce850110bd8328cfbd50841d6911d87",
+        "67f14984c7da791248e32bb5922583da",
+        "1938f2cf72d54ee97e94166fa91d2a36",
+        "74481e9646ed49fe0f6224301604698e",
+        "57fca5de98a9d6d8006438d0583d8a1d",
+        "9fecde1cefdc1cbed4763674d9575359",
+        "e3040c00eb28f15366ca73cbd872e740",
+        "7697009a6a831dfecca91c5993670f7a",
+        "5853542321f567a005d547a4f04759bd",
+        "5150d1772f50834a503e069a973fbd7c",
+    ];
+
+    static KAT_SIP13: [&str; 64] = [
+        "dcc40f055801acab",
+        "93ca577df39bf4c9",
+        "4dd4c74d029bcb82",
+        "fbf7dde7b80af88b",
+        "2883d388605775cf",
+        "673b53492fd5f9de",
+        "a7229fc5502b0dc5",
+        "4011b19b987d92d3",
+        "8e9a298d11959036",
+        "e43d066cb38ea425",
+        "7f09ff92ee85de79",
+        "52c34df9c118c170",
+        "a2d9b457b184a378",
+        "a7ff29120c766f30",
+        "345df9c011a15a60",
+        "5699512a6dd820d3",
+        "668b907d1add4fcc",
+        "0cd8db639068f29c",
+        "3ee673b49c38fc8f",
+        "1c7d298de59d1ff2",
+        "40e0cca6462fdcc0",
+        "44f8452bfeab92b9",
+        "2e8720a39b7bfe7f",
+        "23c1e6da7f0e5a52",
+        "8c9c3467b2ae64f4",
+        "79095b702859cd45",
+        "a51399cae3353e3a",
+        "353bde4a4ec71da9",
+        "0dd06cef02ed0bfb",
+        "f4e1b14ab43cd988",
+        "63e6c543d6110f54",
+        "bcd1218c1fdd7023",
+        "0db6a7166c7b1581",
+        "bff98f7ae5b9544d",
+        "3e752a1f78129f75",
+        "916b18bfbea3a1ce",
+        "0662a2add308f52c",
+        "5730c3a32d1c10b6",
+        "a1363aae9674f4b3",
+        "9283107b54576b62",
+        "3115e4993236d2c1",
+        "44d91a3f92c17c66",
+        "258813c8fe4f7065",
+        "a64989c2d180f224",
+        "6b87f8faed1ccac2",
+        "9621049ffc4b16c2",
+        "23d6b168939c6ea1",
+        "fd14518b9c16fb49",
+        "464c07dff843319f",
+        "b386cc1224affdc6",
+        "8f09520ad149af7e",
+        "9a2f299d5513f31c",
+        "121ff4a2dd304ac4",
+        "d01ea74389e9fa36",
+        "e6bcf0734cb38f31",
+        "80e9a77036bf7aa2",
+        "756d3c24dbc0bcb4",
+        "1315b7fd52d8f823",
+        "088a7da64d5f038f",
+        "48f1e8b7e5d09cd8",
+        "ee44a6f7bce6f4f6",
+        "f237180fd89ac5ae",
+        "e094664b15f6b2c3",
+        "a8b3bbb76290199d",
+    ];
+
+    static KAT_SIP128_13: [&str; 64] = [
+        "e77ebcb22788a5befd62db6add303001",
+        "fc6f370460d3eda85e0573cc2b2ff063",
+        "75787f090569839b855bc9548c6aea95",
+        "6bc5ccfa1edcf79f4823187712ebd743",
+        "0c784e71ac2b285a9f8e92e78fbf2c25",
+        "f328db89345b620c795229a42695843e",
+        "dcd03d29f743e7100951b0e83985a6f8",
+        "1084b923f2aae0c3a62f2ec80848ab77",
+        "aa12fee1d5e3dab4724f16ab35f9c799",
+        "81ddb8042cf33994f4720e0094137c42",
+        "4faa541d5d498e89ba0ea4c387b22fb4",
+        "723b9af3554491dbb1d6633dfc6e0c4e",
+        "e53f92859e4819a8dc0695739fea8c65",
+        "b2f858c7c9ea801d53d603596d657844",
+        "87e76268dbc9227226b0ca665f64e378",
+        "c17e5505b2bd526c2921cdec1e7e0109",
+        "d0a8d95715518eebb513b0f83d9e1793",
+        "234126f93fbb668d975112e8febdf7ec",
+        "ef42f03db78f704d023c449f16b7092b",
+        "abf76238c20af161b2314b4d5526bce9",
+        "3c2c2f11bb90cf0be335ca9b2e91e9b7",
+        "2a7a680f22a02a92f45149d20fece0ef",
+        "c9a8d130231dd43e42e6456957f83779",
+        "1d127b84405ceab99fd8775a9be6c559",
+        "9e4bf837bcfd92cace09d2061a84d04a",
+        "39031a965d73b4af5a274d18f973b1d2",
+        "7f4d0a1209d67e4ed06f7538e1cfad64",
+        "e61ee240fbdcce38969f4cd24927dd93",
+        "4c3ba2b37b0fdd8cfa5e95c189b29414",
+        "e06fd4ca066fecdd54068a5ad8896f86",
+        "5ca84c34139c6580a88af24990720706",
+        "42ea961c5b3c858b17c3e550dfa79010",
+        "406c44dee67857b2943160f30c7417d3",
+        "c5f57bae1320fcf4b4e868e71d56c66b",
+        "04bf737a5b676be7c3de05017df4bff9",
+        "5163c9c03f1907ea1044ed5c30727b4f",
+        "37a110f002718edad24b3f9ee453f140",
+        "b9877e381aedd3da08c33e75ff23ac10",
+        "7c5004005ec5da4c5ac9440e5c723193",
+        "81b8243783dbc646ca9d0cd82abdb46c",
+        "505720543eb9b413d50b3cfad9eef938",
+        "945f594de72411e4d335be874456d8f3",
+        "37923b3e371777b21170bf9d7e62f602",
+        "3ad4e7c85764964611eb0a6c4d62de56",
+        "cd91396c44af4f5185578d9dd9803f0a",
+        "fe28158e727b868f3903c9acda64a258",
+        "40cc10b8288ce5f0bc3ac0b68a0eebc8",
+        "6f1490f540699a3cd4974420ecc92737",
+        "d505f1b75e1a84a603c43583b2ed0308",
+        "491573cfd72bb4682b7ca5880e1c8d6f",
+        "3ed69cfe45ab403f2fd2ad959ba27666",
+        "8be839ef1b20b57c83ba7eb6a8c22b6a",
+        "1409186ab42231fedee18162cf1cb4ca",
+        "2bf3ccc24ab672cf151fb8d2f3f3069b",
+        "b9b93a2882d6025cdb8c56fa13f7537b",
+        "d97cca3694fb206db8bd1f3650c33322",
+        "94ec2e19a40be41af3940d6b30c49384",
+        "4b41603f209a045be140a341a3dffe10",
+        "23fbcb309f1cf094890755ab1b426569",
+        "e7d9b65690918a2b232f2f5c12c8300e",
+        "ade83cf7e7f3847b36fa4b54b00dce61",
+        "0610c5f2ee571c8ac80cbfe538bdf1c7",
+        "271d5d00fbdb5d155d9dcea97cb40218",
+        "4c5800e34efe426f079f6b0aa75260ad",
+    ];
+
+    fn key_and_msg() -> ([u8; 16], [u8; 64]) {
+        let mut key = [0u8; 16];
+        for i in 0..16 {
+            key[i] = i as u8;
+        }
+        let mut msg = [0u8; 64];
+        for i in 0..64 {
+            msg[i] = i as u8;
+        }
+        (key, msg)
+    }
+
+    macro_rules! check_kat { ($typename:ident, $kat:ident) => {
+        let (key, msg) = key_and_msg();
+        for i in 0..64 {
+            let refout = hex::decode($kat[i]).unwrap();
+            let out = $typename::hash(&key, &msg[..i]).to_le_bytes();
+            assert!(out[..] == refout[..]);
+
+            // Byte-by-byte processing.
+            let mut sh = $typename::new_with_key(&key);
+            for j in 0..i {
+                sh.update(&msg[j..(j + 1)]);
+            }
+            assert!(sh.finalize().to_le_bytes()[..] == refout[..]);
+
+            // Uneven split, with finalization in the middle.
+            let mut sh = $typename::new(
+                u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[..8]).unwrap()),
+                u64::from_le_bytes(*<&[u8; 8]>::try_from(&key[8..]).unwrap()));
+            let k = i / 3;
+            sh.update(&msg[..k]);
+            let _ = sh.finalize();
+            sh.update(&msg[k..i]);
+            assert!(sh.finalize().to_le_bytes()[..] == refout[..]);
+        }
+    } }
+
+    #[test]
+    fn siphash24() {
+        check_kat!(SipHash24, KAT_SIP24);
+    }
+
+    #[test]
+    fn siphash13() {
+        check_kat!(SipHash13, KAT_SIP13);
+    }
+
+    #[test]
+    fn siphash128_24() {
+        check_kat!(SipHash128_24, KAT_SIP128_24);
+    }
+
+    #[test]
+    fn siphash128_13() {
+        check_kat!(SipHash128_13, KAT_SIP128_13);
+    }
+
+    #[test]
+    fn hasher() {
+        let (key, msg) = key_and_msg();
+        let mut sh = SipHash24::new_with_key(&key);
+        sh.write(&msg[..10]);
+        sh.write_u8(msg[10]);
+        assert!(sh.finish() == SipHash24::hash(&key, &msg[..11]));
+        let mut sh = SipHash13::new_with_key(&key);
+        sh.write(&msg[..10]);
+        assert!(sh.finish() == SipHash13::hash(&key, &msg[..10]));
+    }
+}
@@ -0,0 +1,413 @@
+//! SPAKE2 password-authenticated key exchange over edwards25519.
+//!
+//! This module implements the SPAKE2 balanced PAKE with the exact
+//! parameters and message formats of the [python-spake2] reference
+//! implementation (`ParamsEd25519`), so that it interoperates with it and
+//! with its ports (e.g. the `spake2` Rust crate):
+//!
+//!  - Side A calls `Spake2::start_a()` with the password and both
+//!    identities, and sends the returned message to side B.
+//!
+//!  - Side B calls `Spake2::start_b()` with the same parameters, and sends
+//!    its own message to side A.
+//!
+//!  - Each side calls `finish()` on its state with the peer message, and
+//!    obtains the 32-byte shared key.
+//!
+//! In symmetric mode, both peers call `Spake2::start_symmetric()` with
+//! the password and a single common identity; messages can then be sent
+//! in any order.
+//!
+//! Messages are 33 bytes: a side byte (`A`, `B` or `S`) followed by the
+//! encoding of an edwards25519 point. The password is converted into a
+//! scalar `w` with HKDF-SHA256; side A sends `x*G + w*M`, side B sends
+//! `y*G + w*N`, and both compute `K = x*y*G`. The shared key is
+//! SHA-256 over the transcript:
+//!
+//! ```text
+//!     SHA-256(SHA-256(pw) || SHA-256(idA) || SHA-256(idB) || X || Y || K)
+//! ```
+//!
+//! The `M`, `N` and `S` (symmetric mode) elements are the ones used by
+//! python-spake2: each is obtained by hashing a seed (`"M"`, `"N"`,
+//! `"symmetric"`) into a curve point with unknown discrete logarithm;
+//! they are pinned here as encoded points.
+//!
+//! Like the reference implementation, there is no key confirmation step:
+//! a password mismatch results in distinct keys on both sides, not in an
+//! error. Applications should confirm the key (e.g. by exchanging MACs
+//! computed with keys derived from it) before relying on it. The password
+//! is used as is; low-entropy passwords shared by many devices should
+//! first go through a memory-hard function.
+//!
+//! [python-spake2]: https://github.com/warner/python-spake2
+
+// Points traditionally use uppercase letters.
+#![allow(non_snake_case)]
+
+use crate::ed25519::{Point, Scalar};
+use crate::hkdf::{hkdf_sha256_extract, hkdf_sha256_expand};
+use crate::{CryptoRng, RngCore};
+use sha2::{Sha256, Digest};
+
+/// Error type for SPAKE2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spake2Error {
+    /// The peer message does not have the expected length.
+    WrongLength,
+    /// The peer message side byte does not match the expected peer role.
+    BadSide,
+    /// The peer message does not encode a valid group element.
+    CorruptMessage,
+}
+
+impl core::fmt::Display for Spake2Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Spake2Error::WrongLength => f.write_str("invalid message length"),
+            Spake2Error::BadSide => f.write_str("unexpected message side"),
+            Spake2Error::CorruptMessage => f.write_str("invalid group element"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Spake2Error {}
+
+/// Length of a SPAKE2 message (in bytes).
+pub const MESSAGE_LEN: usize = 33;
+
+/// Encoded M element (blinding for side A), python-spake2 seed "M".
+const M_BYTES: [u8; 32] = [
+    0x15, 0xCF, 0xD1, 0x8E, 0x38, 0x59, 0x52, 0x98,
+    0x2B, 0x6A, 0x8F, 0x8C, 0x78, 0x54, 0x96, 0x3B,
+    0x58, 0xE3, 0x43, 0x88, 0xC8, 0xE6, 0xDA, 0xE8,
+    0x91, 0xDB, 0x75, 0x64, 0x81, 0xA0, 0x23, 0x12,
+];
+
+/// Encoded N element (blinding for side B), python-spake2 seed "N".
+const N_BYTES: [u8; 32] = [
+    0xF0, 0x4F, 0x2E, 0x7E, 0xB7, 0x34, 0xB2, 0xA8,
+    0xF8, 0xB4, 0x72, 0xEA, 0xF9, 0xC3, 0xC6, 0x32,
+    0x57, 0x6A, 0xC6, 0x4A, 0xEA, 0x65, 0x0B, 0x49,
+    0x6A, 0x8A, 0x20, 0xFF, 0x00, 0xE5, 0x83, 0xC3,
+];
+
+/// Encoded S element (symmetric mode), python-spake2 seed "symmetric".
+const S_BYTES: [u8; 32] = [
+    0x6F, 0x00, 0xDA, 0xE8, 0x7C, 0x1B, 0xE1, 0xA7,
+    0x3B, 0x59, 0x22, 0xEF, 0x43, 0x1C, 0xD8, 0xF5,
+    0x78, 0x79, 0x56, 0x9C, 0x22, 0x2D, 0x22, 0xB1,
+    0xCD, 0x71, 0xE8, 0x54, 0x6A, 0xB8, 0xE6, 0xF1,
+];
+
+/// Protocol role.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    A,
+    B,
+    Symmetric,
+}
+
+impl Side {
+    fn byte(self) -> u8 {
+        match self {
+            Side::A => b'A',
+            Side::B => b'B',
+            Side::Symmetric => b'S',
+        }
+    }
+
+    fn peer(self) -> Side {
+        match self {
+            Side::A => Side::B,
+            Side::B => Side::A,
+            Side::Symmetric => Side::Symmetric,
+        }
+    }
+
+    // Blinding element for messages sent by this side.
+    fn blinding(self) -> Point {
+        let buf = match self {
+            Side::A => &M_BYTES,
+            Side::B => &N_BYTES,
+            Side::Symmetric => &S_BYTES,
+        };
+        Point::decode(buf).unwrap()
+    }
+}
+
+/// Converts a password into the scalar `w`: 48 bytes of HKDF-SHA256
+/// output (empty salt, info "SPAKE2 pw"), interpreted in big-endian
+/// convention and reduced modulo the group order.
+fn password_to_scalar(password: &[u8]) -> Scalar {
+    let prk = hkdf_sha256_extract(&[], password);
+    let mut buf = [0u8; 48];
+    hkdf_sha256_expand(&prk, b"SPAKE2 pw", &mut buf).unwrap();
+    buf.reverse();
+    Scalar::decode_reduce(&buf)
+}
+
+/// State of a SPAKE2 exchange in progress.
+///
+/// A state is obtained from one of the `start_*()` functions, and
+/// consumed by `finish()`.
+pub struct Spake2 {
+    side: Side,
+    x: Scalar,
+    w: Scalar,
+    msg: [u8; MESSAGE_LEN],
+    // Transcript hash, with the password and identity hashes already
+    // absorbed.
+    th: Sha256,
+}
+
+impl Spake2 {
+
+    /// Starts an exchange as side A.
+    ///
+    /// Returned values are the state, and the message to send to side B.
+    pub fn start_a<T: CryptoRng + RngCore>(rng: &mut T,
+        password: &[u8], id_a: &[u8], id_b: &[u8])
+        -> (Self, [u8; MESSAGE_LEN])
+    {
+        Self::start_inner(Side::A, Self::random_scalar(rng),
+            password, &[id_a, id_b])
+    }
+
+    /// Starts an exchange as side B.
+    ///
+    /// Returned values are the state, and the message to send to side A.
+    /// The parameters must be the same as the ones used by side A (in
+    /// particular, `id_a` is the identity of side A, not of the peer).
+    pub fn start_b<T: CryptoRng + RngCore>(rng: &mut T,
+        password: &[u8], id_a: &[u8], id_b: &[u8])
+        -> (Self, [u8; MESSAGE_LEN])
+    {
+        Self::start_inner(Side::B, Self::random_scalar(rng),
+            password, &[id_a, id_b])
+    }
+
+    /// Starts an exchange in symmetric mode.
+    ///
+    /// Both peers use the same password and identity `id_s`. Returned
+    /// values are the state, and the message to send to the peer.
+    pub fn start_symmetric<T: CryptoRng + RngCore>(rng: &mut T,
+        password: &[u8], id_s: &[u8])
+        -> (Self, [u8; MESSAGE_LEN])
+    {
+        Self::start_inner(Side::Symmetric, Self::random_scalar(rng),
+            password, &[id_s])
+    }
+
+    fn random_scalar<T: CryptoRng + RngCore>(rng: &mut T) -> Scalar {
+        let mut buf = [0u8; 64];
+        rng.fill_bytes(&mut buf);
+        Scalar::decode_reduce(&buf)
+    }
+
+    fn start_inner(side: Side, x: Scalar, password: &[u8], ids: &[&[u8]])
+        -> (Self, [u8; MESSAGE_LEN])
+    {
+        let w = password_to_scalar(password);
+        let X = Point::mulgen(&x) + side.blinding() * w;
+        let mut msg = [0u8; MESSAGE_LEN];
+        msg[0] = side.byte();
+        msg[1..].copy_from_slice(&X.encode());
+        let mut th = Sha256::new();
+        th.update(Sha256::digest(password));
+        for id in ids.iter() {
+            th.update(Sha256::digest(id));
+        }
+        (Self { side, x, w, msg, th }, msg)
+    }
+
+    /// Finishes the exchange with the message received from the peer.
+    ///
+    /// On success, the 32-byte shared key is returned. An error is
+    /// reported if the message is malformed, comes from the wrong side,
+    /// or does not encode a point in the prime-order subgroup. A
+    /// password mismatch is _not_ detected: it yields distinct keys.
+    pub fn finish(self, peer_msg: &[u8]) -> Result<[u8; 32], Spake2Error> {
+        if peer_msg.len() != MESSAGE_LEN {
+            return Err(Spake2Error::WrongLength);
+        }
+        let peer = self.side.peer();
+        if peer_msg[0] != peer.byte() {
+            return Err(Spake2Error::BadSide);
+        }
+        let Y = match Point::decode(&peer_msg[1..]) {
+            Some(Y) if Y.is_in_subgroup() != 0 => Y,
+            _ => return Err(Spake2Error::CorruptMessage),
+        };
+        let K = (Y - peer.blinding() * self.w) * self.x;
+
+        // Messages are in A-then-B order; in symmetric mode, they are
+        // sorted lexicographically.
+        let (first, second) = match self.side {
+            Side::A => (&self.msg[1..], &peer_msg[1..]),
+            Side::B => (&peer_msg[1..], &self.msg[1..]),
+            Side::Symmetric => {
+                if self.msg[1..] <= peer_msg[1..] {
+                    (&self.msg[1..], &peer_msg[1..])
+                } else {
+                    (&peer_msg[1..], &self.msg[1..])
+                }
+            }
+        };
+        let mut th = self.th;
+        th.update(first);
+        th.update(second);
+        th.update(K.encode());
+        let mut key = [0u8; 32];
+        key[..].copy_from_slice(&th.finalize());
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Spake2, Side, M_BYTES, N_BYTES, S_BYTES, password_to_scalar};
+    use crate::ed25519::{Point, Scalar};
+    use crate::field::GF25519;
+    use crate::hkdf::{hkdf_sha256_extract, hkdf_sha256_expand};
+
+    fn scalar_from_decimal(s: &str) -> Scalar {
+        let ten = Scalar::from_u32(10);
+        let mut x = Scalar::ZERO;
+        for c in s.bytes() {
+            x = x * ten + Scalar::from_u32((c - b'0') as u32);
+        }
+        x
+    }
+
+    // Recomputes a python-spake2 "arbitrary element" from its seed.
+    fn arbitrary_element(seed: &[u8]) -> Point {
+        let prk = hkdf_sha256_extract(&[], seed);
+        let mut buf = [0u8; 48];
+        hkdf_sha256_expand(&prk, b"SPAKE2 arbitrary element", &mut buf)
+            .unwrap();
+        buf.reverse();
+        let mut y = GF25519::decode_reduce(&buf);
+        loop {
+            // Decoding with a cleared sign bit selects the even x.
+            if let Some(P) = Point::decode(&y.encode()) {
+                let P8 = P.xdouble(3);
+                if P8.isneutral() == 0 {
+                    return P8;
+                }
+            }
+            y += GF25519::ONE;
+        }
+    }
+
+    #[test]
+    fn elements() {
+        assert!(arbitrary_element(b"M").encode() == M_BYTES);
+        assert!(arbitrary_element(b"N").encode() == N_BYTES);
+        assert!(arbitrary_element(b"symmetric").encode() == S_BYTES);
+    }
+
+    #[test]
+    fn password_scalar() {
+        // Test vector from python-spake2.
+        assert!(password_to_scalar(b"password").encode()
+            == scalar_from_decimal("3515301705789368674385125653994241092664323519848410154015274772661223168839").encode());
+    }
+
+    #[test]
+    fn asymmetric() {
+        // Test vector from python-spake2 (and the spake2 Rust crate).
+        let x = scalar_from_decimal("2611694063369306139794446498317402240796898290761098242657700742213257926693");
+        let y = scalar_from_decimal("7002393159576182977806091886122272758628412261510164356026361256515836884383");
+        let (sa, ma) = Spake2::start_inner(Side::A, x,
+            b"password", &[b"idA", b"idB"]);
+        let (sb, mb) = Spake2::start_inner(Side::B, y,
+            b"password", &[b"idA", b"idB"]);
+        assert!(ma[..] == hex::decode("416fc960df73c9cf8ed7198b0c9534e2e96a5984bfc5edc023fd24dacf371f2af9").unwrap());
+        assert!(mb[..] == hex::decode("42354e97b88406922b1df4bea1d7870f17aed3dba7c720b313edae315b00959309").unwrap());
+        let ka = sa.finish(&mb).unwrap();
+        let kb = sb.finish(&ma).unwrap();
+        assert!(ka == kb);
+        assert!(ka[..] == hex::decode("712295de7219c675ddd31942184aa26e0a957cf216bc230d165b215047b520c1").unwrap());
+    }
+
+    #[test]
+    fn symmetric() {
+        // Computed with an independent implementation of python-spake2.
+        let x = scalar_from_decimal("2611694063369306139794446498317402240796898290761098242657700742213257926693");
+        let y = scalar_from_decimal("7002393159576182977806091886122272758628412261510164356026361256515836884383");
+        let (s1, m1) = Spake2::start_inner(Side::Symmetric, x,
+            b"password", &[b"idS"]);
+        let (s2, m2) = Spake2::start_inner(Side::Symmetric, y,
+            b"password", &[b"idS"]);
+        assert!(m1[..] == hex::decode("53aea2233c62601567666f8b955e5ab12158ba6d2a4cca2faab4edb40fc7d1c73b").unwrap());
+        assert!(m2[..] == hex::decode("53ae433c484a85fba9caceece86b62612ce337b2a7d258f8e05e4d613c3b780407").unwrap());
+        let k1 = s1.finish(&m2).unwrap();
+        let k2 = s2.finish(&m1).unwrap();
+        assert!(k1 == k2);
+        assert!(k1[..] == hex::decode("bee407e254c657a77713dc666be6f9b5c1cf02e2d5276c6bb9152ce55cbbde3c").unwrap());
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn exchange() {
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x42; 32]);
+        for i in 0..10 {
+            let pw = [i as u8; 10];
+            let (sa, ma) = Spake2::start_a(&mut rng, &pw, b"alice", b"bob");
+            let (sb, mb) = Spake2::start_b(&mut rng, &pw, b"alice", b"bob");
+            assert!(sa.finish(&mb).unwrap() == sb.finish(&ma).unwrap());
+
+            let (s1, m1) = Spake2::start_symmetric(&mut rng, &pw, b"pair");
+            let (s2, m2) = Spake2::start_symmetric(&mut rng, &pw, b"pair");
+            assert!(s1.finish(&m2).unwrap() == s2.finish(&m1).unwrap());
+        }
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn mismatch() {
+        use super::Spake2Error;
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng = ChaChaRng::from_seed([0x17; 32]);
+
+        // Distinct passwords or identities yield distinct keys.
+        let (sa, ma) = Spake2::start_a(&mut rng, b"password", b"A", b"B");
+        let (sb, mb) = Spake2::start_b(&mut rng, b"passwore", b"A", b"B");
+        assert!(sa.finish(&mb).unwrap() != sb.finish(&ma).unwrap());
+        let (sa, ma) = Spake2::start_a(&mut rng, b"password", b"A", b"B");
+        let (sb, mb) = Spake2::start_b(&mut rng, b"password", b"A", b"C");
+        assert!(sa.finish(&mb).unwrap() != sb.finish(&ma).unwrap());
+        let (s1, m1) = Spake2::start_symmetric(&mut rng, b"password", b"S");
+        let (s2, m2) = Spake2::start_symmetric(&mut rng, b"Password", b"S");
+        assert!(s1.finish(&m2).unwrap() != s2.finish(&m1).unwrap());
+
+        // Malformed messages are rejected.
+        let (_, ma) = Spake2::start_a(&mut rng, b"password", b"A", b"B");
+        let (_, mb) = Spake2::start_b(&mut rng, b"password", b"A", b"B");
+        let (sa, _) = Spake2::start_a(&mut rng, b"password", b"A", b"B");
+        assert!(sa.finish(&ma) == Err(Spake2Error::BadSide));
+        let (sa, _) = Spake2::start_a(&mut rng, b"password", b"A", b"B");
+        assert!(sa.finish(&mb[..32]) == Err(Spake2Error::WrongLength));
+        let (s1, _) = Spake2::start_symmetric(&mut rng, b"password", b"S");
+        assert!(s1.finish(&mb) == Err(Spake2Error::BadSide));
+
+        // Points outside of the prime-order subgroup, and invalid
+        // encodings, are rejected.
+        let mut bad = mb;
+        bad[1..].copy_from_slice(&(Point::decode(&mb[1..]).unwrap()
+            + Point::decode(&[0u8; 32]).unwrap()).encode());
+        let (sa, _) = Spake2::start_a(&mut rng, b"password", b"A", b"B");
+        assert!(sa.finish(&bad) == Err(Spake2Error::CorruptMessage));
+        bad[1..].copy_from_slice(&[0xFF; 32]);
+        let (sa, _) = Spake2::start_a(&mut rng, b"password", b"A", b"B");
+        assert!(sa.finish(&bad) == Err(Spake2Error::CorruptMessage));
+    }
+}
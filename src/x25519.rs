@@ -23,6 +23,18 @@
 //! 2^255; the three least significant bits of the first byte, and two
 //! most significant bits of the last byte, are ignored.
 //!
+//! The `x25519_checked()` function is a variant of `x25519()` which
+//! returns `None` if the output is all-zeros (which happens when the
+//! source point has low order), as recommended by RFC 7748 for protocols
+//! which need contributory behaviour.
+//!
+//! `x25519_keygen()` generates a new key pair (private scalar and
+//! public point) from a cryptographically secure RNG.
+//!
+//! `scalar_mul_unclamped()` multiplies a point by a scalar used as is,
+//! without clamping; it is meant for protocols which specify the exact
+//! scalar (see its documentation for when clamping is required).
+//!
 //! [RFC 7748]: https://datatracker.ietf.org/doc/html/rfc7748
 
 // Projective/fractional coordinates traditionally use uppercase letters,
@@ -31,6 +43,7 @@
 
 use super::field::GF25519;
 use super::ed25519::{Point, Scalar};
+use crate::{CryptoRng, RngCore};
 
 /// X25519 function (from RFC 7748), general case.
 ///
@@ -51,6 +64,40 @@ pub fn x25519(point: &[u8; 32], scalar: &[u8; 32]) -> [u8; 32] {
     s[31] &= 127;
     s[31] |= 64;
 
+    // Bit 255 of the clamped scalar is zero.
+    ladder(point, &s, 255)
+}
+
+/// Multiplies a Curve25519 point by a scalar, without clamping.
+///
+/// This is the same operation as `x25519()`, except that the scalar `k`
+/// is used as is: it is interpreted as an integer in the 0 to 2^256-1
+/// range (unsigned little-endian convention), and all its bits are
+/// processed by the Montgomery ladder. The point `u` is decoded as in
+/// `x25519()` (the top bit is ignored, non-canonical values are
+/// reduced). If the result is the point-at-infinity (e.g. `k` is a
+/// multiple of the order of the point), then the output is all-zeros.
+/// The function is constant-time.
+///
+/// Clamping is a security requirement whenever the scalar is a private
+/// key used against points chosen by a possibly malicious peer (e.g.
+/// plain Diffie-Hellman): clearing the low three bits makes the scalar
+/// a multiple of the cofactor, so that the low-order component of an
+/// invalid or twisted point cannot leak the private key bits modulo 8,
+/// and setting bit 254 keeps the ladder length independent of the key.
+/// This function should be used only when the protocol defines the
+/// scalar exactly (e.g. blinding factors, or multiplications by values
+/// already reduced modulo the group order), and when the input point is
+/// known to be in the prime-order subgroup or the cofactor is otherwise
+/// handled by the protocol.
+pub fn scalar_mul_unclamped(k: &[u8; 32], u: &[u8; 32]) -> [u8; 32] {
+    ladder(u, k, 256)
+}
+
+// Montgomery ladder (RFC 7748, section 5) over the low `bits` bits of
+// scalar `s`, applied to the point with u coordinate `point` (top bit
+// ignored, non-canonical values accepted).
+fn ladder(point: &[u8; 32], s: &[u8; 32], bits: usize) -> [u8; 32] {
     // Decode the source point. As per RFC 7748 rules, the top bit is
     // ignored, and non-canonical values are acceptable.
     let mut u = *point;
@@ -64,7 +111,7 @@ pub fn x25519(point: &[u8; 32], scalar: &[u8; 32]) -> [u8; 32] {
     let mut z3 = GF25519::ONE;
     let mut swap = 0u32;
 
-    for t in (0..255).rev() {
+    for t in (0..bits).rev() {
         let kt = (((s[t >> 3] >> (t & 7)) & 1) as u32).wrapping_neg();
         swap ^= kt;
         GF25519::cswap(&mut x2, &mut x3, swap);
@@ -91,6 +138,26 @@ pub fn x25519(point: &[u8; 32], scalar: &[u8; 32]) -> [u8; 32] {
     (x2 / z2).encode()
 }
 
+/// X25519 function (from RFC 7748), with a check on the output.
+///
+/// This is the same function as `x25519()`, except that if the output
+/// is all-zeros, then `None` is returned. An all-zero output is obtained
+/// when the source point has low order (regardless of the scalar);
+/// protocols that need contributory behaviour (i.e. that neither party
+/// can force the shared secret to a known value) should use this
+/// function, as recommended by RFC 7748, section 6.1. The check is
+/// constant-time.
+pub fn x25519_checked(point: &[u8; 32], scalar: &[u8; 32])
+    -> Option<[u8; 32]>
+{
+    let r = x25519(point, scalar);
+    if crate::ct::equals(&r, &[0u8; 32]) {
+        None
+    } else {
+        Some(r)
+    }
+}
+
 /// Specialized version of X25519, when applied to the conventional
 /// generator point (u = 9).
 ///
@@ -113,12 +180,29 @@ pub fn x25519_base(scalar: &[u8; 32]) -> [u8; 32] {
     u.encode()
 }
 
+/// Generates a new X25519 key pair from a cryptographically secure RNG.
+///
+/// The private key is a sequence of 32 random bytes (clamping is
+/// applied by `x25519()` when the key is used); the public key is
+/// obtained from the private key with `x25519_base()`. Returned value
+/// is `(private_key, public_key)`.
+pub fn x25519_keygen<T: CryptoRng + RngCore>(rng: &mut T)
+    -> ([u8; 32], [u8; 32])
+{
+    let mut sk = [0u8; 32];
+    rng.fill_bytes(&mut sk);
+    let pk = x25519_base(&sk);
+    (sk, pk)
+}
+
 // ========================================================================
 
 #[cfg(test)]
 mod tests {
 
-    use super::{x25519, x25519_base};
+    use super::{x25519, x25519_base, x25519_checked};
+    use super::scalar_mul_unclamped;
+    use crate::ed25519::{Point, Scalar};
     use sha2::{Sha256, Digest};
 
     #[test]
@@ -143,15 +227,157 @@ mod tests {
 
     #[test]
     fn x25519_basepoint() {
+        // RFC 7748, section 6.1: Alice and Bob's key pairs.
+        const KAT: [(&str, &str); 2] = [
+            ("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+             "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"),
+            ("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+             "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"),
+        ];
+        for (sk, pk) in KAT.iter() {
+            let mut k = [0u8; 32];
+            hex::decode_to_slice(sk, &mut k[..]).unwrap();
+            assert!(x25519_base(&k)[..] == hex::decode(pk).unwrap()[..]);
+        }
+
+        // Agreement with the generic function, including on scalars
+        // with all the clamped bits set or cleared.
         let mut sh = Sha256::new();
         let mut b = [0u8; 32];
         b[0] = 9;
-        for i in 0..20 {
+        for i in 0..2000 {
             sh.update(&(i as u64).to_le_bytes());
             let v = sh.finalize_reset();
             let mut k = [0u8; 32];
             k[..].copy_from_slice(&v);
+            match i % 3 {
+                1 => {
+                    k[0] |= 0x07;
+                    k[31] |= 0xC0;
+                }
+                2 => {
+                    k[0] &= 0xF8;
+                    k[31] &= 0x3F;
+                }
+                _ => { }
+            }
             assert!(x25519(&b, &k) == x25519_base(&k));
         }
     }
+
+    #[test]
+    fn x25519_low_order() {
+        // Encodings of low-order points: u = 0, 1, the two points of
+        // order 8, and the non-canonical encodings p-1, p and p+1; each
+        // is also used with the (ignored) top bit set.
+        const LOW_U: [&str; 7] = [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800",
+            "5f9c95bca3508c24b1d0b1559c83ef5b04445cc4581c8e86d8224eddd09f1157",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        ];
+        let mut sh = Sha256::new();
+        for (i, uh) in LOW_U.iter().enumerate() {
+            let mut u = [0u8; 32];
+            hex::decode_to_slice(uh, &mut u[..]).unwrap();
+            for j in 0..2 {
+                if j == 1 {
+                    u[31] |= 0x80;
+                }
+                sh.update(&((2 * i + j) as u64).to_le_bytes());
+                let s: [u8; 32] = sh.finalize_reset().into();
+                assert!(x25519(&u, &s) == [0u8; 32]);
+                assert!(x25519_checked(&u, &s).is_none());
+            }
+        }
+
+        // Normal points are accepted, with the same output as x25519().
+        let mut u = [0u8; 32];
+        u[0] = 9;
+        for i in 0..20 {
+            sh.update(&(i as u64).to_le_bytes());
+            let s: [u8; 32] = sh.finalize_reset().into();
+            let r = x25519(&u, &s);
+            assert!(x25519_checked(&u, &s) == Some(r));
+            u = r;
+        }
+    }
+
+    #[test]
+    fn unclamped() {
+        let mut sh = Sha256::new();
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        // 8*L, the order of the whole curve.
+        let mut order8 = [0u8; 32];
+        hex::decode_to_slice("689faee7d21893c0b2e6bc17f5cef7a600000000000000000000000000000080", &mut order8[..]).unwrap();
+        // L, the order of the prime-order subgroup.
+        let mut lb = [0u8; 32];
+        hex::decode_to_slice("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010", &mut lb[..]).unwrap();
+        // A point of order 8.
+        let T8 = Point::decode(&hex::decode("c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa").unwrap()).unwrap();
+        for i in 0..20 {
+            sh.update(&(2 * i as u64).to_le_bytes());
+            let k: [u8; 32] = sh.finalize_reset().into();
+            sh.update(&(2 * i as u64 + 1).to_le_bytes());
+            let v = sh.finalize_reset();
+
+            // Point in the prime-order subgroup, and the same point with
+            // a low-order component.
+            let P = Point::mulgen(&Scalar::decode_reduce(&v[..]));
+            let u = P.to_montgomery_u().encode();
+            let Q = P + T8 * (i as u64);
+            let uq = Q.to_montgomery_u().encode();
+
+            // With a clamped scalar, the result matches x25519().
+            let mut kc = k;
+            kc[0] &= 248;
+            kc[31] &= 127;
+            kc[31] |= 64;
+            assert!(scalar_mul_unclamped(&kc, &u) == x25519(&u, &k));
+            assert!(scalar_mul_unclamped(&kc, &uq) == x25519(&uq, &k));
+
+            // Multiplication by 1 and by the curve order.
+            assert!(scalar_mul_unclamped(&one, &u) == u);
+            assert!(scalar_mul_unclamped(&one, &uq) == uq);
+            assert!(scalar_mul_unclamped(&order8, &uq) == [0u8; 32]);
+            assert!(scalar_mul_unclamped(&lb, &u) == [0u8; 32]);
+
+            // Arbitrary (unclamped) scalars match the Edwards-side
+            // computation (for a point in the prime-order subgroup, the
+            // scalar can be reduced modulo L).
+            let R = P * Scalar::decode_reduce(&k);
+            assert!(scalar_mul_unclamped(&k, &u) == R.to_montgomery_u().encode());
+            let mut k2 = k;
+            k2[31] |= 0x80;
+            let R = P * Scalar::decode_reduce(&k2);
+            assert!(scalar_mul_unclamped(&k2, &u) == R.to_montgomery_u().encode());
+        }
+    }
+
+    #[cfg(feature = "chacha20")]
+    #[test]
+    fn keygen() {
+        use super::x25519_keygen;
+        use crate::chacha20::ChaChaRng;
+        use crate::SeedableRng;
+
+        let mut rng1 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng2 = ChaChaRng::from_seed([0x11u8; 32]);
+        let mut rng3 = ChaChaRng::from_seed([0x22u8; 32]);
+        let (sk1, pk1) = x25519_keygen(&mut rng1);
+        let (sk2, pk2) = x25519_keygen(&mut rng2);
+        let (sk3, pk3) = x25519_keygen(&mut rng3);
+        assert!(sk1 == sk2 && pk1 == pk2);
+        assert!(sk1 != sk3 && pk1 != pk3);
+        assert!(pk1 == x25519_base(&sk1));
+        let (sk4, pk4) = x25519_keygen(&mut rng1);
+        assert!(sk1 != sk4 && pk1 != pk4);
+
+        // Key exchange works with the generated keys.
+        assert!(x25519(&pk3, &sk1) == x25519(&pk1, &sk3));
+    }
 }
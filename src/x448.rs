@@ -116,6 +116,41 @@ mod tests {
     use super::{x448, x448_base};
     use sha2::{Sha512, Digest};
 
+    #[test]
+    fn x448_kat() {
+        // Test vectors from RFC 7748, section 5.2.
+        const KAT: [(&str, &str, &str); 2] = [
+            ("3d262fddf9ec8e88495266fea19a34d28882acef045104d0d1aae121700a779c984c24f8cdd78fbff44943eba368f54b29259a4f1c600ad3",
+             "06fce640fa3487bfda5f6cf2d5263f8aad88334cbd07437f020f08f9814dc031ddbdc38c19c6da2583fa5429db94ada18aa7a7fb4ef8a086",
+             "ce3e4ff95a60dc6697da1db1d85e6afbdf79b50a2412d7546d5f239fe14fbaadeb445fc66a01b0779d98223961111e21766282f73dd96b6f"),
+            ("203d494428b8399352665ddca42f9de8fef600908e0d461cb021f8c538345dd77c3e4806e25f46d3315c44e0a5b4371282dd2c8d5be3095f",
+             "0fbcc2f993cd56d3305b0b7d9e55d4c1a8fb5dbb52f8e9a1e9b6201b165d015894e56c4d3570bee52fe205e28a78b91cdfbde71ce8d157db",
+             "884a02576239ff7a2f2f63b2db6a9ff37047ac13568e1e30fe63c4a7ad1b3ee3a5700df34321d62077e63633c575c1c954514e99da7c179d"),
+        ];
+        for (sh, uh, rh) in KAT.iter() {
+            let mut s = [0u8; 56];
+            let mut u = [0u8; 56];
+            let mut r = [0u8; 56];
+            hex::decode_to_slice(sh, &mut s[..]).unwrap();
+            hex::decode_to_slice(uh, &mut u[..]).unwrap();
+            hex::decode_to_slice(rh, &mut r[..]).unwrap();
+            assert!(x448(&u, &s) == r);
+        }
+
+        // Diffie-Hellman example from RFC 7748, section 6.2.
+        let mut a = [0u8; 56];
+        let mut b = [0u8; 56];
+        hex::decode_to_slice("9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf574a9419744897391006382a6f127ab1d9ac2d8c0a598726b", &mut a[..]).unwrap();
+        hex::decode_to_slice("1c306a7ac2a0e2e0990b294470cba339e6453772b075811d8fad0d1d6927c120bb5ee8972b0d3e21374c9c921b09d1b0366f10b65173992d", &mut b[..]).unwrap();
+        let pa = x448_base(&a);
+        let pb = x448_base(&b);
+        assert!(pa[..] == hex::decode("9b08f7cc31b7e3e67d22d5aea121074a273bd2b83de09c63faa73d2c22c5d9bbc836647241d953d40c5b12da88120d53177f80e532c41fa0").unwrap()[..]);
+        assert!(pb[..] == hex::decode("3eb7a829b0cd20f5bcfc0b599b6feccf6da4627107bdb0d4f345b43027d8b972fc3e34fb4232a13ca706dcb57aec3dae07bdc1c67bf33609").unwrap()[..]);
+        let k = x448(&pb, &a);
+        assert!(k == x448(&pa, &b));
+        assert!(k[..] == hex::decode("07fff4181ac6cc95ec1c16a94a0f74d12da232ce40a77552281d282bb60c0b56fd2464c335543936521c24403085d59a449a5037514a879d").unwrap()[..]);
+    }
+
     #[test]
     fn x448_mc() {
         let mut k = [0u8; 56];
@@ -0,0 +1,187 @@
+//! Common interface for extendable-output functions.
+//!
+//! An extendable-output function (XOF) absorbs an arbitrary amount of
+//! input data, and then produces an output of arbitrary length. The
+//! `Xof` trait defined here allows protocol code (e.g. hash-to-field
+//! processes, key derivation) to be written generically over the
+//! underlying primitive, so that the caller may use whichever function
+//! is mandated by the relevant specification. The output is obtained
+//! from a separate "reader" object (`XofReader`), which may be invoked
+//! repeatedly to obtain successive output bytes.
+//!
+//! The trait is implemented for SHAKE128 and SHAKE256 (both the types
+//! from the external `sha3` crate, and, when the corresponding feature is
+//! enabled, those of this crate's `sha3` module), for KangarooTwelve, and
+//! for BLAKE3.
+//!
+//! The `expand_message_xof()` function implements the process of the
+//! same name from RFC 9380 (section 5.3.2), which is used to produce
+//! uniform bytes (to be reduced into field elements) when hashing to
+//! curves with an XOF.
+
+use sha3::digest::{Update, ExtendableOutput};
+
+/// An extendable-output function, in its input (absorbing) state.
+pub trait Xof {
+
+    /// Type of the object that produces the output.
+    type Reader: XofReader;
+
+    /// Inject some data into the function.
+    fn absorb(&mut self, data: &[u8]);
+
+    /// Terminate the input, and obtain the output reader.
+    fn finalize_xof(self) -> Self::Reader;
+}
+
+/// The output side of an extendable-output function.
+pub trait XofReader {
+
+    /// Fill `out` with the next output bytes. Output may be obtained
+    /// in several calls of arbitrary sizes; the concatenation of all
+    /// outputs does not depend on how the output was split over
+    /// calls.
+    fn squeeze(&mut self, out: &mut [u8]);
+}
+
+impl Xof for sha3::Shake128 {
+    type Reader = sha3::Shake128Reader;
+
+    fn absorb(&mut self, data: &[u8]) {
+        self.update(data);
+    }
+
+    fn finalize_xof(self) -> Self::Reader {
+        ExtendableOutput::finalize_xof(self)
+    }
+}
+
+impl XofReader for sha3::Shake128Reader {
+    fn squeeze(&mut self, out: &mut [u8]) {
+        sha3::digest::XofReader::read(self, out);
+    }
+}
+
+impl Xof for sha3::Shake256 {
+    type Reader = sha3::Shake256Reader;
+
+    fn absorb(&mut self, data: &[u8]) {
+        self.update(data);
+    }
+
+    fn finalize_xof(self) -> Self::Reader {
+        ExtendableOutput::finalize_xof(self)
+    }
+}
+
+impl XofReader for sha3::Shake256Reader {
+    fn squeeze(&mut self, out: &mut [u8]) {
+        sha3::digest::XofReader::read(self, out);
+    }
+}
+
+/// Expand a message into uniform bytes with an XOF (RFC 9380, section
+/// 5.3.2, `expand_message_xof`).
+///
+/// The provided `xof` instance must be fresh (no data absorbed yet).
+/// The domain separation tag `dst` must have length at most 255 bytes,
+/// and the output length must be at most 65535 bytes; this function
+/// panics otherwise. (RFC 9380 specifies a pre-hashing of oversized
+/// tags, whose output length depends on the target security level; the
+/// caller should apply it if needed.)
+pub fn expand_message_xof<X: Xof>(mut xof: X, msg: &[u8], dst: &[u8],
+    out: &mut [u8])
+{
+    assert!(dst.len() <= 255);
+    assert!(out.len() <= 65535);
+    xof.absorb(msg);
+    xof.absorb(&(out.len() as u16).to_be_bytes());
+    xof.absorb(dst);
+    xof.absorb(&[dst.len() as u8]);
+    xof.finalize_xof().squeeze(out);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Xof, XofReader, expand_message_xof};
+    use sha3::{Shake128, Shake256};
+
+    // Check that squeezing the output in 1-byte pieces (and in pieces of
+    // varying sizes) yields the same bytes as a single squeeze call.
+    fn check_chunked<X: Xof, F: Fn() -> X>(mk: F) {
+        let mut data = [0u8; 300];
+        for i in 0..data.len() {
+            data[i] = (i * 7 + 11) as u8;
+        }
+        for dlen in [0, 1, 135, 136, 137, 168, 300] {
+            let mut x = mk();
+            x.absorb(&data[..dlen]);
+            let mut ref_out = [0u8; 700];
+            x.finalize_xof().squeeze(&mut ref_out);
+
+            let mut x = mk();
+            x.absorb(&data[..dlen]);
+            let mut r = x.finalize_xof();
+            let mut out = [0u8; 700];
+            for i in 0..out.len() {
+                r.squeeze(&mut out[i..(i + 1)]);
+            }
+            assert!(out == ref_out);
+
+            let mut x = mk();
+            x.absorb(&data[..dlen]);
+            let mut r = x.finalize_xof();
+            let mut out = [0u8; 700];
+            let mut j = 0;
+            let mut k = 0;
+            while j < out.len() {
+                let clen = core::cmp::min(k, out.len() - j);
+                r.squeeze(&mut out[j..(j + clen)]);
+                j += clen;
+                k = (k * 3 + 5) % 200;
+            }
+            assert!(out == ref_out);
+        }
+    }
+
+    #[test]
+    fn chunked() {
+        check_chunked(Shake128::default);
+        check_chunked(Shake256::default);
+        #[cfg(feature = "blake3")]
+        check_chunked(crate::blake3::Blake3::new);
+        #[cfg(feature = "sha3")]
+        check_chunked(crate::sha3::Shake128::new);
+        #[cfg(feature = "sha3")]
+        check_chunked(crate::sha3::Shake256::new);
+        #[cfg(feature = "k12")]
+        check_chunked(|| crate::k12::KangarooTwelve::new(b""));
+    }
+
+    #[test]
+    fn expand_xof() {
+        // RFC 9380, appendix K.6 (expand_message_xof with SHAKE128).
+        const DST: &[u8] = b"QUUX-V01-CS02-with-expander-SHAKE128";
+        const KAT: [(&[u8], &str); 4] = [
+            (b"", "86518c9cd86581486e9485aa74ab35ba150d1c75c88e26b7043e44e2acd735a2"),
+            (b"abc", "8696af52a4d862417c0763556073f47bc9b9ba43c99b505305cb1ec04a9ab468"),
+            (b"abcdef0123456789", "912c58deac4821c3509dbefa094df54b34b8f5d01a191d1d3108a2c89077acca"),
+            (b"abc", "c952f0c8e529ca8824acc6a4cab0e782fc3648c563ddb00da7399f2ae35654f4860ec671db2356ba7baa55a34a9d7f79197b60ddae6e64768a37d699a78323496db3878c8d64d909d0f8a7de4927dcab0d3dbbc26cb20a49eceb0530b431cdf47bc8c0fa3e0d88f53b318b6739fbed7d7634974f1b5c386d6230c76260d5337a"),
+        ];
+        for (msg, ref_hex) in KAT.iter() {
+            let ref_out = hex::decode(ref_hex).unwrap();
+            let mut out = [0u8; 128];
+            let out = &mut out[..ref_out.len()];
+            expand_message_xof(Shake128::default(), msg, DST, out);
+            assert!(out[..] == ref_out[..]);
+            #[cfg(feature = "sha3")]
+            {
+                out.fill(0);
+                expand_message_xof(crate::sha3::Shake128::new(),
+                    msg, DST, out);
+                assert!(out[..] == ref_out[..]);
+            }
+        }
+    }
+}